@@ -0,0 +1,413 @@
+// Статический анализ исходного кода без выполнения (datacode --check / --fmt, REPL)
+//
+// Блоки DataCode ограничены скобками: { } для тел if/while/for/fn/try/match, ( ) и [ ] для
+// выражений. Сканер разбирает строки на фрагменты с учетом строковых литералов и комментариев,
+// поверх него работают проверка парности скобок, определение незавершенного ввода
+// и форматирование с отступом в 4 пробела на уровень вложенности.
+
+use crate::common::error::LangError;
+use crate::compiler::Compiler;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::semantic::resolver::Resolver;
+use std::fmt;
+
+/// Ширина одного уровня отступа при форматировании
+pub const INDENT: &str = "    ";
+
+/// Найденная ошибка: строка (с 1) и сообщение
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl From<&LangError> for Diagnostic {
+    fn from(error: &LangError) -> Self {
+        Diagnostic { line: error.line(), message: error.message().to_string() }
+    }
+}
+
+/// Фрагмент строки исходника
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Word(String),    // Идентификатор, ключевое слово или число
+    Str(String),     // Строковый литерал как в исходнике, с кавычками (или его часть до конца строки)
+    Op(String),      // Оператор: + - * / // % ** = == != < > <= >= += -= *= /= //= %= **= ->
+    Open(char),
+    Close(char),
+    Comma,
+    Semicolon,
+    Colon,
+    Dot,
+    Question,
+    Bang,
+    At,
+    Comment(String), // Комментарий до конца строки
+    Other(char),     // Символ, который не разбирает лексер - оставляется как есть
+}
+
+impl Piece {
+    fn text(&self) -> String {
+        match self {
+            Piece::Word(text) | Piece::Str(text) | Piece::Op(text) | Piece::Comment(text) => text.clone(),
+            Piece::Open(c) | Piece::Close(c) | Piece::Other(c) => c.to_string(),
+            Piece::Comma => ",".to_string(),
+            Piece::Semicolon => ";".to_string(),
+            Piece::Colon => ":".to_string(),
+            Piece::Dot => ".".to_string(),
+            Piece::Question => "?".to_string(),
+            Piece::Bang => "!".to_string(),
+            Piece::At => "@".to_string(),
+        }
+    }
+}
+
+/// Фрагмент и число пробельных символов перед ним в исходнике
+type Scanned = (Piece, usize);
+
+/// Разобранная строка: номер (с 1), признак продолжения многострочного литерала, фрагменты
+type ScannedLine = (usize, bool, Vec<Scanned>);
+
+/// Ключевые слова: после них "(" и "[" отделяются пробелом, а "-" и "+" унарные
+const KEYWORDS: &[&str] = &[
+    "let", "global", "fn", "if", "else", "while", "for", "return", "break", "continue", "in", "or",
+    "and", "not", "try", "catch", "throw", "match", "case", "fallthrough", "cache",
+];
+
+/// Многосимвольные операторы, от длинных к коротким (как в лексере)
+const OPERATORS: &[&str] = &[
+    "**=", "//=", "**", "//", "==", "!=", "<=", ">=", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "->", "+", "-", "*", "/", "%", "=", "<", ">", "&", "|", "^",
+];
+
+/// Открытый строковый литерал, продолжающийся на следующих строках
+#[derive(Debug, Clone, Copy)]
+struct OpenString {
+    delimiter: char,
+    line: usize,
+}
+
+/// Разбирает строку исходника на фрагменты. open_string - литерал, начатый на предыдущих строках;
+/// после разбора содержит литерал, не закрытый до конца этой строки
+fn scan_line(line: &str, line_number: usize, open_string: &mut Option<OpenString>) -> Vec<Scanned> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    let mut gap = 0;
+
+    // Продолжение многострочного литерала
+    if let Some(open) = *open_string {
+        let (end, closed) = string_end(&chars, 0, open.delimiter);
+        pieces.push((Piece::Str(chars[..end].iter().collect()), 0));
+        if !closed {
+            return pieces;
+        }
+        *open_string = None;
+        i = end;
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == ' ' || c == '\t' || c == '\r' {
+            gap += 1;
+            i += 1;
+            continue;
+        }
+        let rest: String = chars[i..].iter().collect();
+        let (piece, len) = match c {
+            '#' => (Piece::Comment(rest.trim_end().to_string()), chars.len() - i),
+            // Как в лексере: "//" - комментарий, только если за ним "/" или конец строки
+            '/' if rest.starts_with("//") && matches!(chars.get(i + 2), None | Some('/')) => {
+                (Piece::Comment(rest.trim_end().to_string()), chars.len() - i)
+            }
+            '"' | '\'' => {
+                let (end, closed) = string_end(&chars, i + 1, c);
+                if !closed {
+                    *open_string = Some(OpenString { delimiter: c, line: line_number });
+                }
+                (Piece::Str(chars[i..end].iter().collect()), end - i)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = i;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                if end + 1 < chars.len() && chars[end] == '.' && chars[end + 1].is_ascii_digit() {
+                    end += 1;
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                }
+                (Piece::Word(chars[i..end].iter().collect()), end - i)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                (Piece::Word(chars[i..end].iter().collect()), end - i)
+            }
+            '(' | '[' | '{' => (Piece::Open(c), 1),
+            ')' | ']' | '}' => (Piece::Close(c), 1),
+            ',' => (Piece::Comma, 1),
+            ';' => (Piece::Semicolon, 1),
+            ':' => (Piece::Colon, 1),
+            '.' => (Piece::Dot, 1),
+            '?' => (Piece::Question, 1),
+            '@' => (Piece::At, 1),
+            '!' if !rest.starts_with("!=") => (Piece::Bang, 1),
+            _ => match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => (Piece::Op(op.to_string()), op.len()),
+                None => (Piece::Other(c), 1),
+            },
+        };
+        pieces.push((piece, gap));
+        gap = 0;
+        i += len;
+    }
+    pieces
+}
+
+/// Конец строкового литерала, начинающегося с позиции start (после открывающей кавычки):
+/// позиция после закрывающей кавычки и признак, что литерал закрыт в этой строке
+fn string_end(chars: &[char], start: usize, delimiter: char) -> (usize, bool) {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == delimiter {
+            return (i + 1, true);
+        }
+        i += 1;
+    }
+    (chars.len(), false)
+}
+
+fn closing_for(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Разбирает весь исходник: строки и литерал, не закрытый к концу файла
+fn scan_source(source: &str) -> (Vec<ScannedLine>, Option<OpenString>) {
+    let mut open_string = None;
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let continued = open_string.is_some();
+            let pieces = scan_line(line, index + 1, &mut open_string);
+            (index + 1, continued, pieces)
+        })
+        .collect();
+    (lines, open_string)
+}
+
+/// Проверяет парность скобок { } ( ) [ ] и незакрытые строки. Возвращает все найденные ошибки
+/// с номерами строк; пустой результат - структура блоков корректна
+pub fn check_block_structure(source: &str) -> Vec<Diagnostic> {
+    let (lines, open_string) = scan_source(source);
+    let mut diagnostics = Vec::new();
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    for (line, _, pieces) in &lines {
+        for (piece, _) in pieces {
+            match piece {
+                Piece::Open(c) => stack.push((*c, *line)),
+                Piece::Close(c) => match stack.iter().rposition(|(open, _)| closing_for(*open) == *c) {
+                    Some(position) => {
+                        // Скобки, открытые после парной, не закрыты до этой
+                        for (open, open_line) in stack.drain(position..).skip(1) {
+                            diagnostics.push(Diagnostic {
+                                line: open_line,
+                                message: format!("'{}' is not closed before '{}' on line {}", open, c, line),
+                            });
+                        }
+                    }
+                    None => diagnostics.push(Diagnostic {
+                        line: *line,
+                        message: format!("Unexpected '{}' without a matching opening bracket", c),
+                    }),
+                },
+                _ => {}
+            }
+        }
+    }
+    for (open, line) in stack {
+        diagnostics.push(Diagnostic { line, message: format!("'{}' is never closed", open) });
+    }
+    if let Some(open) = open_string {
+        diagnostics.push(Diagnostic { line: open.line, message: "Unterminated string".to_string() });
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
+/// Ввод не завершен: есть незакрытые скобки или строка (REPL продолжает чтение следующей строки)
+pub fn is_incomplete(source: &str) -> bool {
+    let (lines, open_string) = scan_source(source);
+    if open_string.is_some() {
+        return true;
+    }
+    let mut depth: usize = 0;
+    for (_, _, pieces) in &lines {
+        for (piece, _) in pieces {
+            match piece {
+                Piece::Open(_) => depth += 1,
+                // Лишняя закрывающая скобка - ошибка, а не незавершенный ввод
+                Piece::Close(_) if depth == 0 => return false,
+                Piece::Close(_) => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    depth > 0
+}
+
+/// Проверяет код без выполнения: структуру блоков, затем синтаксис, разрешение имен и компиляцию.
+/// Ошибки структуры возвращаются все сразу; синтаксическая ошибка - первая найденная парсером
+pub fn check_source(source: &str) -> Vec<Diagnostic> {
+    let structure = check_block_structure(source);
+    if !structure.is_empty() {
+        return structure;
+    }
+    match compile_only(source) {
+        Ok(()) => Vec::new(),
+        Err(error) => vec![Diagnostic::from(&error)],
+    }
+}
+
+fn compile_only(source: &str) -> Result<(), LangError> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let ast = Parser::new(tokens).parse()?;
+    Resolver::new().resolve(&ast)?;
+    Compiler::new().compile(&ast)?;
+    Ok(())
+}
+
+/// Канонически форматирует код: отступ 4 пробела на каждую открытую скобку строк выше,
+/// одиночные пробелы вокруг операторов и после запятых. Комментарии, пустые строки
+/// и содержимое строковых литералов сохраняются. Повторное форматирование ничего не меняет
+pub fn format_source(source: &str) -> String {
+    let (lines, _) = scan_source(source);
+    let raw_lines: Vec<&str> = source.lines().collect();
+    let mut output = String::new();
+    // Открытые скобки и отступ строки, в которой каждая открыта
+    let mut stack: Vec<(char, usize)> = Vec::new();
+
+    for (line, continued, pieces) in &lines {
+        let indent = match pieces.first() {
+            Some((Piece::Close(_), _)) => stack.last().map_or(0, |(_, indent)| *indent),
+            _ => stack.last().map_or(0, |(_, indent)| indent + 1),
+        };
+        for (piece, _) in pieces {
+            match piece {
+                Piece::Open(c) => stack.push((*c, indent)),
+                Piece::Close(c) => {
+                    if let Some(position) = stack.iter().rposition(|(open, _)| closing_for(*open) == *c) {
+                        stack.truncate(position);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if *continued {
+            // Строка внутри многострочного литерала не меняется
+            output.push_str(raw_lines[line - 1]);
+        } else if !pieces.is_empty() {
+            output.push_str(&INDENT.repeat(indent));
+            output.push_str(&render_line(pieces));
+        }
+        output.push('\n');
+    }
+
+    // Пустые строки в конце файла не сохраняются
+    let trimmed_len = output.trim_end_matches('\n').len();
+    output.truncate(trimmed_len);
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Собирает строку из фрагментов с нормализованными пробелами
+fn render_line(pieces: &[Scanned]) -> String {
+    let mut text = String::new();
+    let mut pending_questions = 0;
+    for (index, (piece, gap)) in pieces.iter().enumerate() {
+        if index > 0 {
+            let ternary_colon = matches!(piece, Piece::Colon) && pending_questions > 0;
+            let spaces = match piece {
+                // Отступ перед комментарием в конце строки сохраняется (выравнивание комментариев)
+                Piece::Comment(_) => (*gap).max(1),
+                _ => usize::from(needs_space(pieces, index, ternary_colon)),
+            };
+            text.push_str(&" ".repeat(spaces));
+        }
+        match piece {
+            Piece::Question => pending_questions += 1,
+            Piece::Colon if pending_questions > 0 => pending_questions -= 1,
+            _ => {}
+        }
+        text.push_str(&piece.text());
+    }
+    text
+}
+
+/// Заканчивается ли на фрагменте операнд (после него "-" бинарный, "(" - вызов, "[" - индекс)
+fn ends_operand(piece: &Piece) -> bool {
+    match piece {
+        Piece::Word(word) => !KEYWORDS.contains(&word.as_str()),
+        Piece::Str(_) | Piece::Close(_) => true,
+        _ => false,
+    }
+}
+
+/// Унарный ли оператор на позиции index
+fn is_unary(pieces: &[Scanned], index: usize) -> bool {
+    matches!(&pieces[index].0, Piece::Op(op) if op == "-" || op == "+")
+        && (index == 0 || !ends_operand(&pieces[index - 1].0))
+}
+
+/// Нужен ли пробел между фрагментами index - 1 и index
+fn needs_space(pieces: &[Scanned], index: usize, ternary_colon: bool) -> bool {
+    let previous = &pieces[index - 1].0;
+    let (current, gap) = (&pieces[index].0, pieces[index].1);
+    match (previous, current) {
+        (_, Piece::Comma) | (_, Piece::Semicolon) => false,
+        (Piece::Comma, Piece::Close(_)) => false,
+        (Piece::Comma, _) | (Piece::Semicolon, _) => true,
+        (_, Piece::Close(')')) | (_, Piece::Close(']')) => false,
+        // Для { } сохраняется, был ли пробел: {"a": 1} и { return 1 }
+        (_, Piece::Close(_)) => gap > 0,
+        (Piece::Open('('), _) | (Piece::Open('['), _) => false,
+        (Piece::Open(_), _) => gap > 0,
+        (Piece::Dot, _) | (_, Piece::Dot) => false,
+        (Piece::At, _) | (Piece::Bang, _) => false,
+        (Piece::Op(_), _) if is_unary(pieces, index - 1) => false,
+        (_, Piece::Colon) => ternary_colon,
+        (Piece::Colon, _) => true,
+        (_, Piece::Question) | (Piece::Question, _) => true,
+        (_, Piece::Op(_)) | (Piece::Op(_), _) => true,
+        // Вызов и индекс - без пробела, после ключевого слова - с пробелом
+        (_, Piece::Open('(')) | (_, Piece::Open('[')) => match previous {
+            Piece::Word(_) | Piece::Str(_) | Piece::Close(_) => !ends_operand(previous),
+            _ => gap > 0,
+        },
+        (_, Piece::Open(_)) => true,
+        (_, Piece::Other(_)) | (Piece::Other(_), _) => gap > 0,
+        _ => true,
+    }
+}
@@ -22,6 +22,7 @@ pub struct Chunk {
     pub error_type_table: Vec<String>, // Таблица типов ошибок для текущей функции
     pub global_names: std::collections::HashMap<usize, String>, // Маппинг индексов глобальных переменных на их имена
     pub explicit_global_names: std::collections::HashMap<usize, String>, // Маппинг индексов переменных, явно объявленных с ключевым словом 'global'
+    pub global_indices: std::collections::HashMap<String, usize>, // Полная таблица глобальных имен (нужна для компиляции кода eval()/exec())
 }
 
 impl Chunk {
@@ -34,6 +35,7 @@ impl Chunk {
             error_type_table: Vec::new(),
             global_names: std::collections::HashMap::new(),
             explicit_global_names: std::collections::HashMap::new(),
+            global_indices: std::collections::HashMap::new(),
         }
     }
 
@@ -130,6 +132,26 @@ impl Chunk {
                 output.push_str("INT_DIV\n");
                 offset + 1
             }
+            OpCode::BitAnd => {
+                output.push_str("BIT_AND\n");
+                offset + 1
+            }
+            OpCode::BitOr => {
+                output.push_str("BIT_OR\n");
+                offset + 1
+            }
+            OpCode::BitXor => {
+                output.push_str("BIT_XOR\n");
+                offset + 1
+            }
+            OpCode::ShiftLeft => {
+                output.push_str("SHIFT_LEFT\n");
+                offset + 1
+            }
+            OpCode::ShiftRight => {
+                output.push_str("SHIFT_RIGHT\n");
+                offset + 1
+            }
             OpCode::Mod => {
                 output.push_str("MOD\n");
                 offset + 1
@@ -146,14 +168,6 @@ impl Chunk {
                 output.push_str("NOT\n");
                 offset + 1
             }
-            OpCode::Or => {
-                output.push_str("OR\n");
-                offset + 1
-            }
-            OpCode::And => {
-                output.push_str("AND\n");
-                offset + 1
-            }
             OpCode::Equal => {
                 output.push_str("EQUAL\n");
                 offset + 1
@@ -218,6 +232,18 @@ impl Chunk {
                 output.push_str(&format!("CALL {}\n", arity));
                 offset + 1
             }
+            OpCode::SpreadArg => {
+                output.push_str("SPREAD_ARG\n");
+                offset + 1
+            }
+            OpCode::CallSpread => {
+                output.push_str("CALL_SPREAD\n");
+                offset + 1
+            }
+            OpCode::MakeClosure(function_index) => {
+                output.push_str(&format!("MAKE_CLOSURE {}\n", function_index));
+                offset + 1
+            }
             OpCode::Return => {
                 output.push_str("RETURN\n");
                 offset + 1
@@ -226,6 +252,18 @@ impl Chunk {
                 output.push_str(&format!("MAKE_ARRAY {}\n", count));
                 offset + 1
             }
+            OpCode::MakeObject(count) => {
+                output.push_str(&format!("MAKE_OBJECT {}\n", count));
+                offset + 1
+            }
+            OpCode::Unpack(count) => {
+                output.push_str(&format!("UNPACK {}\n", count));
+                offset + 1
+            }
+            OpCode::Extend => {
+                output.push_str("EXTEND\n");
+                offset + 1
+            }
             OpCode::GetArrayLength => {
                 output.push_str("GET_ARRAY_LENGTH\n");
                 offset + 1
@@ -234,6 +272,14 @@ impl Chunk {
                 output.push_str("GET_ARRAY_ELEMENT\n");
                 offset + 1
             }
+            OpCode::LoopProgress(array_slot, index_slot) => {
+                output.push_str(&format!("LOOP_PROGRESS array={} index={}\n", array_slot, index_slot));
+                offset + 1
+            }
+            OpCode::IterSnapshot => {
+                output.push_str("ITER_SNAPSHOT\n");
+                offset + 1
+            }
             OpCode::Clone => {
                 output.push_str("CLONE\n");
                 offset + 1
@@ -242,6 +288,10 @@ impl Chunk {
                 output.push_str("POP\n");
                 offset + 1
             }
+            OpCode::Dup => {
+                output.push_str("DUP\n");
+                offset + 1
+            }
             OpCode::BeginTry(handler_index) => {
                 output.push_str(&format!("BEGIN_TRY handler={}\n", handler_index));
                 offset + 1
@@ -268,6 +318,10 @@ impl Chunk {
                 }
                 offset + 1
             }
+            OpCode::Rethrow => {
+                output.push_str("RETHROW\n");
+                offset + 1
+            }
             OpCode::PopExceptionHandler => {
                 output.push_str("POP_EXCEPTION_HANDLER\n");
                 offset + 1
@@ -1,18 +1,18 @@
 // Function object для VM
 
 use super::chunk::Chunk;
+use crate::common::table::Table;
 use crate::common::value::Value;
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone)]
 pub struct CapturedVar {
     pub name: String,           // Variable name
-    pub parent_slot_index: usize, // Slot index in ancestor function's frame
+    pub parent_slot_index: usize, // Slot index in the enclosing function's frame
     pub local_slot_index: usize,  // Slot index in this function's frame
-    pub ancestor_depth: usize,    // Depth of ancestor (0 = immediate parent, 1 = grandparent, etc.)
 }
 
 /// Ключ для кэша функции - оптимизирован для разных случаев
@@ -21,19 +21,169 @@ pub enum CacheKey {
     /// Оптимизация для двух чисел (частый случай для Ackermann)
     TwoNumbers(i64, i64),
     /// Универсальный ключ для произвольных аргументов
-    Args(Vec<Value>),
+    Args(Vec<KeyArg>),
 }
 
+/// Аргумент в ключе кэша. Ключ проверяется точным сравнением, а не только хешем: скаляры
+/// хранятся по значению, массивы, объекты и небольшие таблицы - деревом своих элементов.
+/// Большая таблица хранится слабой ссылкой и счетчиком изменений (Table::generation), поэтому
+/// кэш не удерживает ее копию, а адрес освобожденной таблицы не может совпасть с новой
+#[derive(Debug, Clone)]
+pub enum KeyArg {
+    Value(Value),
+    Array(Vec<KeyArg>),
+    /// Пары ключ-значение в порядке объекта: порядок ключей виден в результате (str, вывод)
+    Object(Vec<(String, KeyArg)>),
+    Table {
+        headers: Vec<String>,
+        column: Option<String>,
+        cells: Vec<KeyArg>,
+    },
+    LargeTable {
+        table: Weak<RefCell<Table>>,
+        generation: u64,
+        column: Option<String>,
+    },
+}
+
+/// Таблицы до стольких ячеек входят в ключ целиком, большие - по ссылке и счетчику изменений
+const TABLE_FULL_KEY_CELLS: usize = 1024;
+/// Глубина вложенности контейнеров, после которой вызов не кэшируется
+const MAX_KEY_DEPTH: usize = 32;
+
+impl KeyArg {
+    /// Строит аргумент ключа. Возвращает None для значений, которые нельзя кэшировать
+    fn new(value: &Value, depth: usize) -> Option<Self> {
+        if depth > MAX_KEY_DEPTH {
+            return None;
+        }
+        let key = match value {
+            Value::Array(array) => KeyArg::Array(
+                array.borrow().iter().map(|item| KeyArg::new(item, depth + 1)).collect::<Option<_>>()?,
+            ),
+            Value::Object(map) => KeyArg::Object(
+                map.iter()
+                    .map(|(key, item)| Some((key.clone(), KeyArg::new(item, depth + 1)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Value::Table(table) | Value::ColumnReference { table, .. } => {
+                let column = match value {
+                    Value::ColumnReference { column_name, .. } => Some(column_name.clone()),
+                    _ => None,
+                };
+                let table_ref = table.borrow();
+                let cells = table_ref.rows.len() * table_ref.headers.len();
+                if cells > TABLE_FULL_KEY_CELLS {
+                    return Some(KeyArg::LargeTable {
+                        table: Rc::downgrade(table),
+                        generation: table_ref.generation,
+                        column,
+                    });
+                }
+                let mut key_cells = Vec::with_capacity(cells + table_ref.rows.len());
+                for row in &table_ref.rows {
+                    // Длина строки входит в ключ: строки разной длины не сливаются
+                    key_cells.push(KeyArg::Value(Value::Integer(row.len() as i64)));
+                    for cell in row {
+                        key_cells.push(KeyArg::new(cell, depth + 1)?);
+                    }
+                }
+                KeyArg::Table { headers: table_ref.headers.clone(), column, cells: key_cells }
+            }
+            // План читает исходную таблицу при collect(): ее изменения не видны по плану
+            Value::LazyTable(_) => return None,
+            _ => KeyArg::Value(value.clone()),
+        };
+        Some(key)
+    }
+
+    /// Приблизительный размер аргумента ключа в байтах
+    fn size_of_estimate(&self) -> usize {
+        let base = std::mem::size_of::<KeyArg>();
+        match self {
+            KeyArg::Value(value) => value.size_of_estimate(),
+            KeyArg::Array(items) => base + items.iter().map(KeyArg::size_of_estimate).sum::<usize>(),
+            KeyArg::Object(entries) => {
+                base + entries.iter().map(|(key, item)| key.len() + item.size_of_estimate()).sum::<usize>()
+            }
+            KeyArg::Table { headers, column, cells } => {
+                base + headers.iter().map(String::len).sum::<usize>()
+                    + column.as_ref().map_or(0, String::len)
+                    + cells.iter().map(KeyArg::size_of_estimate).sum::<usize>()
+            }
+            KeyArg::LargeTable { column, .. } => base + column.as_ref().map_or(0, String::len),
+        }
+    }
+}
+
+impl Hash for KeyArg {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            KeyArg::Value(value) => {
+                state.write_u8(0);
+                match value {
+                    Value::Number(_) | Value::Integer(_) | Value::Bool(_) | Value::String(_) | Value::Null => value.hash(state),
+                    Value::Currency { amount, symbol } => {
+                        state.write_u64((amount + 0.0).to_bits());
+                        symbol.hash(state);
+                    }
+                    Value::Function(index) | Value::NativeFunction(index) => index.hash(state),
+                    Value::Closure(closure) => closure.function.hash(state),
+                    // Пути сравниваются с нормализацией (paths_equal): хешируется только вид значения
+                    _ => {}
+                }
+            }
+            KeyArg::Array(items) => {
+                state.write_u8(1);
+                items.hash(state);
+            }
+            KeyArg::Object(entries) => {
+                state.write_u8(2);
+                entries.hash(state);
+            }
+            KeyArg::Table { headers, column, cells } => {
+                state.write_u8(3);
+                headers.hash(state);
+                column.hash(state);
+                cells.hash(state);
+            }
+            KeyArg::LargeTable { table, generation, column } => {
+                state.write_u8(4);
+                (table.as_ptr() as usize).hash(state);
+                generation.hash(state);
+                column.hash(state);
+            }
+        }
+    }
+}
+
+impl PartialEq for KeyArg {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (KeyArg::Value(a), KeyArg::Value(b)) => a == b,
+            (KeyArg::Array(a), KeyArg::Array(b)) => a == b,
+            (KeyArg::Object(a), KeyArg::Object(b)) => a == b,
+            (
+                KeyArg::Table { headers: h1, column: c1, cells: v1 },
+                KeyArg::Table { headers: h2, column: c2, cells: v2 },
+            ) => h1 == h2 && c1 == c2 && v1 == v2,
+            (
+                KeyArg::LargeTable { table: t1, generation: g1, column: c1 },
+                KeyArg::LargeTable { table: t2, generation: g2, column: c2 },
+            ) => Weak::ptr_eq(t1, t2) && g1 == g2 && c1 == c2,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for KeyArg {}
+
 impl CacheKey {
-    /// Создает CacheKey из аргументов, если все аргументы hashable
-    /// Возвращает None, если хотя бы один аргумент не hashable
+    /// Создает CacheKey из аргументов. Контейнеры входят в ключ по содержимому, большая таблица -
+    /// по ссылке и счетчику изменений, так что изменение на месте дает другой ключ.
+    /// Возвращает None, если аргумент нельзя кэшировать (ленивый план, слишком глубокая вложенность)
     /// Оптимизирует для частых случаев (1-2 числа)
     pub fn new(args: &[Value]) -> Option<Self> {
-        // Проверяем, что все аргументы hashable
-        if !args.iter().all(|v| v.is_hashable()) {
-            return None;
-        }
-        
         // Оптимизация для двух чисел (частый случай для Ackermann)
         if args.len() == 2 {
             if let (Value::Number(m), Value::Number(n)) = (&args[0], &args[1]) {
@@ -48,7 +198,17 @@ impl CacheKey {
         }
         
         // Универсальный случай
-        Some(CacheKey::Args(args.to_vec()))
+        let key = args.iter().map(|arg| KeyArg::new(arg, 0)).collect::<Option<Vec<_>>>()?;
+        Some(CacheKey::Args(key))
+    }
+
+    /// Приблизительный размер ключа в байтах (для ограничения памяти кэша)
+    fn size_of_estimate(&self) -> usize {
+        let args = match self {
+            CacheKey::TwoNumbers(..) => 0,
+            CacheKey::Args(args) => args.iter().map(KeyArg::size_of_estimate).sum(),
+        };
+        std::mem::size_of::<CacheKey>() + args
     }
 }
 
@@ -90,10 +250,45 @@ impl PartialEq for CacheKey {
 
 impl Eq for CacheKey {}
 
-/// Кэш для функции - хранит результаты вызовов
+/// Ограничения кэша одной функции (set_option("cache_max_entries" | "cache_max_bytes" |
+/// "cache_max_result_bytes", ...)); None - без ограничения
+#[derive(Debug, Clone, Copy)]
+pub struct CacheLimits {
+    pub max_entries: Option<usize>,
+    pub max_bytes: Option<usize>,
+    /// Результат больше этого размера не сохраняется
+    pub max_result_bytes: Option<usize>,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(100_000),
+            max_bytes: Some(64 * 1024 * 1024),
+            max_result_bytes: Some(1024 * 1024),
+        }
+    }
+}
+
+/// Сохраненный результат и время последнего обращения (для вытеснения LRU)
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub value: Value,
+    pub bytes: usize,
+    last_used: u64,
+}
+
+/// Кэш для функции - хранит результаты вызовов и вытесняет давно не использованные (LRU)
 #[derive(Debug)]
 pub struct FnCache {
-    pub map: HashMap<CacheKey, Value>,
+    pub map: HashMap<CacheKey, CacheEntry>,
+    /// Ключи в порядке последнего обращения: первым вытесняется самый старый
+    order: BTreeMap<u64, CacheKey>,
+    clock: u64,
+    pub bytes: usize,    // Приблизительный объем ключей и результатов
+    pub hits: u64,       // Вызовы, результат которых взят из кэша (для cache_stats())
+    pub misses: u64,     // Вызовы, выполнившие функцию
+    pub evictions: u64,  // Результаты, вытесненные из-за ограничений
 }
 
 impl FnCache {
@@ -103,46 +298,112 @@ impl FnCache {
         map.reserve(1500);
         Self {
             map,
+            order: BTreeMap::new(),
+            clock: 0,
+            bytes: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Результат для ключа; попадание обновляет время обращения. Считает hits/misses
+    pub fn lookup(&mut self, key: &CacheKey) -> Option<Value> {
+        self.clock += 1;
+        match self.map.get_mut(key) {
+            Some(entry) => {
+                if let Some(key) = self.order.remove(&entry.last_used) {
+                    self.order.insert(self.clock, key);
+                }
+                entry.last_used = self.clock;
+                self.hits += 1;
+                Some(entry.value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
         }
     }
+
+    /// Сохраняет результат и вытесняет самые старые записи сверх ограничений.
+    /// Результат больше limits.max_result_bytes не сохраняется
+    pub fn insert(&mut self, key: CacheKey, value: Value, limits: &CacheLimits) {
+        let bytes = key.size_of_estimate() + value.size_of_estimate();
+        if limits.max_result_bytes.is_some_and(|max| bytes > max) {
+            return;
+        }
+        self.clock += 1;
+        self.order.insert(self.clock, key.clone());
+        let entry = CacheEntry { value, bytes, last_used: self.clock };
+        self.bytes += bytes;
+        if let Some(previous) = self.map.insert(key, entry) {
+            self.order.remove(&previous.last_used);
+            self.bytes -= previous.bytes;
+        }
+        while limits.max_entries.is_some_and(|max| self.map.len() > max)
+            || limits.max_bytes.is_some_and(|max| self.bytes > max)
+        {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            if let Some(entry) = self.map.remove(&oldest) {
+                self.bytes -= entry.bytes;
+                self.evictions += 1;
+            }
+        }
+    }
+
+    /// Удаляет все результаты; возвращает их число
+    pub fn clear(&mut self) -> usize {
+        let removed = self.map.len();
+        self.map.clear();
+        self.order.clear();
+        self.bytes = 0;
+        removed
+    }
 }
 
 #[derive(Debug)]
 pub struct Function {
     pub name: String,  // Имя функции для трассировки
-    pub chunk: Chunk,
+    pub chunk: Rc<Chunk>, // Общий байткод: вызов копирует только счетчик ссылок, а не код
     pub arity: usize, // Количество параметров
     pub param_names: Vec<String>, // Имена параметров для разрешения именованных аргументов
     pub default_values: Vec<Option<Value>>, // Значения по умолчанию для каждого параметра (None если нет default)
     pub captured_vars: Vec<CapturedVar>, // Information about captured variables
     pub is_cached: bool, // Флаг, указывающий, что функция должна кэшироваться
     pub cache: Option<Rc<RefCell<FnCache>>>, // Кэш для мемоизации (если is_cached = true)
+    pub is_local: bool, // Вложенная функция без global: доступна только по имени в объявившей функции
+    pub parent: Option<usize>, // Объемлющая функция, из фрейма которой берутся захваченные переменные
 }
 
 impl Function {
     pub fn new(name: String, arity: usize) -> Self {
         Self {
             name,
-            chunk: Chunk::new(),
+            chunk: Rc::new(Chunk::new()),
             arity,
             param_names: Vec::new(),
             default_values: Vec::new(),
             captured_vars: Vec::new(),
             is_cached: false,
             cache: None,
+            is_local: false,
+            parent: None,
         }
     }
 
     pub fn with_cache(name: String, arity: usize) -> Self {
         Self {
             name,
-            chunk: Chunk::new(),
+            chunk: Rc::new(Chunk::new()),
             arity,
             param_names: Vec::new(),
             default_values: Vec::new(),
             captured_vars: Vec::new(),
             is_cached: true,
             cache: Some(Rc::new(RefCell::new(FnCache::new()))),
+            is_local: false,
+            parent: None,
         }
     }
 }
@@ -158,6 +419,8 @@ impl Clone for Function {
             captured_vars: self.captured_vars.clone(),
             is_cached: self.is_cached,
             cache: self.cache.clone(),
+            is_local: self.is_local,
+            parent: self.parent,
         }
     }
 }
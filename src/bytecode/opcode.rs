@@ -22,11 +22,17 @@ pub enum OpCode {
     Mod, // Модуло (%)
     Pow, // Возведение в степень (**)
     Negate, // Унарный минус
+
+    // Побитовые операции над целыми числами
+    BitAnd, // &
+    BitOr, // |
+    BitXor, // ^
+    ShiftLeft, // <<
+    ShiftRight, // >>
     
     // Логические операции
+    // and/or компилируются в условные переходы (короткое вычисление)
     Not, // Унарный Bang (!)
-    Or,  // Логическое ИЛИ (or)
-    And, // Логическое И (and)
 
     // Операции сравнения
     Equal,
@@ -52,12 +58,20 @@ pub enum OpCode {
 
     // Функции
     Call(usize), // Вызов функции с количеством аргументов
+    SpreadArg,   // Добавить значение к аргументам вызова со spread: массив - к позиционным, объект - к именованным
+    CallSpread,  // Вызов функции со стека с аргументами из массива позиционных и объекта именованных
+    MakeClosure(usize), // Создать замыкание функции: значения захваченных переменных копируются из текущего фрейма
     Return,      // Возврат из функции
 
     // Массивы
     MakeArray(usize), // Создать массив из N элементов со стека
+    MakeObject(usize), // Создать объект из N пар ключ-значение со стека (ключ, затем значение)
+    Unpack(usize),    // Распаковать массив ровно из N элементов: элементы на стек, первый - на вершину
+    Extend,           // Дополнить массив элементами массива или объект ключами объекта (spread в литералах)
     GetArrayLength,   // Получить длину массива
     GetArrayElement,  // Получить элемент массива по индексу (индекс и массив на стеке)
+    LoopProgress(usize, usize), // Прогресс цикла for: (слот массива, слот индекса); работает при auto_progress
+    IterSnapshot,     // Снимок итерируемого значения for: таблица заменяется массивом строк-объектов
     Clone,            // Глубокое клонирование значения на стеке (для массивов и таблиц)
 
     // Обработка исключений
@@ -66,9 +80,11 @@ pub enum OpCode {
     Catch(Option<usize>),   // Начало catch блока, Option<usize> - тип ошибки (индекс в таблице типов), None для catch всех
     EndCatch,               // Конец catch блока
     Throw(Option<usize>),    // Выбрасывание исключения (для будущего использования), Option<usize> - тип ошибки
+    Rethrow,                 // Повторное выбрасывание ошибки, обрабатываемой текущим catch блоком
     PopExceptionHandler,     // Удаление обработчика исключений со стека
 
     // Стек
     Pop, // Удалить значение со стека
+    Dup, // Продублировать значение на вершине стека
 }
 
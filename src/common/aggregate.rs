@@ -0,0 +1,182 @@
+// Потоковые агрегаты: сумма, среднее, количество, минимум и максимум накапливаются по одному
+// значению. sum/average/count и table_group_by передают сюда строки по одной, поэтому ленивая
+// таблица над CSV файлом агрегируется за один проход без сбора строк в память: хранится
+// только по набору аккумуляторов на группу.
+
+use crate::common::table::{compare_values, Table};
+use crate::common::value::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Агрегатная функция
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggFunc {
+    Sum,
+    Average,
+    Count,
+    Min,
+    Max,
+}
+
+impl AggFunc {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sum" => Some(AggFunc::Sum),
+            "average" | "avg" | "mean" => Some(AggFunc::Average),
+            "count" => Some(AggFunc::Count),
+            "min" => Some(AggFunc::Min),
+            "max" => Some(AggFunc::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggFunc::Sum => "sum",
+            AggFunc::Average => "average",
+            AggFunc::Count => "count",
+            AggFunc::Min => "min",
+            AggFunc::Max => "max",
+        }
+    }
+}
+
+/// Накопленное значение одного агрегата. Правила те же, что у sum()/average() для массивов:
+/// учитываются только числа, сумма одних Integer считается точно
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    func: AggFunc,
+    sum: f64,
+    /// Точная сумма Integer (None - было переполнение)
+    integer_sum: Option<i64>,
+    has_numbers: bool,
+    has_integers: bool,
+    /// Число учтенных значений: чисел для sum/average, не-null значений для count
+    count: usize,
+    extreme: Option<Value>,
+}
+
+impl Accumulator {
+    pub fn new(func: AggFunc) -> Self {
+        Self {
+            func,
+            sum: 0.0,
+            integer_sum: Some(0),
+            has_numbers: false,
+            has_integers: false,
+            count: 0,
+            extreme: None,
+        }
+    }
+
+    pub fn push(&mut self, value: &Value) {
+        match self.func {
+            AggFunc::Sum | AggFunc::Average => match value {
+                Value::Number(n) => {
+                    self.sum += n;
+                    self.has_numbers = true;
+                    self.count += 1;
+                }
+                Value::Integer(i) => {
+                    self.sum += *i as f64;
+                    self.integer_sum = self.integer_sum.and_then(|acc| acc.checked_add(*i));
+                    self.has_integers = true;
+                    self.count += 1;
+                }
+                _ => {}
+            },
+            AggFunc::Count => {
+                if !matches!(value, Value::Null) {
+                    self.count += 1;
+                }
+            }
+            AggFunc::Min | AggFunc::Max => {
+                if matches!(value, Value::Null) {
+                    return;
+                }
+                let wanted = if self.func == AggFunc::Min { Ordering::Less } else { Ordering::Greater };
+                let replace = self.extreme.as_ref().is_none_or(|current| compare_values(value, current) == wanted);
+                if replace {
+                    self.extreme = Some(value.clone());
+                }
+            }
+        }
+    }
+
+    pub fn finish(&self) -> Value {
+        match self.func {
+            AggFunc::Sum => match self.integer_sum {
+                Some(total) if self.has_integers && !self.has_numbers => Value::Integer(total),
+                _ => Value::Number(self.sum),
+            },
+            AggFunc::Average if self.count > 0 => Value::Number(self.sum / self.count as f64),
+            AggFunc::Average => Value::Number(0.0),
+            AggFunc::Count => Value::Number(self.count as f64),
+            AggFunc::Min | AggFunc::Max => self.extreme.clone().unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Агрегат группировки: функция и позиция значения в переданной строке (None - подсчет строк)
+#[derive(Debug, Clone)]
+pub struct AggSpec {
+    pub func: AggFunc,
+    pub column: Option<usize>,
+}
+
+/// Группировка с агрегатами: хэш-таблица ключ группы -> аккумуляторы.
+/// Группы идут в порядке первого появления ключа
+#[derive(Debug)]
+pub struct GroupAggregator {
+    keys: Vec<usize>,
+    specs: Vec<AggSpec>,
+    index: HashMap<Vec<Value>, usize>,
+    groups: Vec<(Vec<Value>, Vec<Accumulator>)>,
+}
+
+impl GroupAggregator {
+    /// keys - позиции ключевых значений в строках, которые будут переданы в push
+    pub fn new(keys: Vec<usize>, specs: Vec<AggSpec>) -> Self {
+        Self { keys, specs, index: HashMap::new(), groups: Vec::new() }
+    }
+
+    /// Учитывает строку. Ключ - значения колонок keys; ошибка, если среди них
+    /// есть значение, которое нельзя использовать как ключ (массив, объект, таблица)
+    pub fn push(&mut self, row: &[Value]) -> Result<(), Value> {
+        let key: Vec<Value> = self.keys.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect();
+        if let Some(bad) = key.iter().find(|v| !v.is_hashable()) {
+            return Err(bad.clone());
+        }
+        let group = match self.index.get(&key) {
+            Some(&group) => group,
+            None => {
+                let accumulators = self.specs.iter().map(|spec| Accumulator::new(spec.func)).collect();
+                self.groups.push((key.clone(), accumulators));
+                self.index.insert(key, self.groups.len() - 1);
+                self.groups.len() - 1
+            }
+        };
+        let accumulators = &mut self.groups[group].1;
+        for (spec, accumulator) in self.specs.iter().zip(accumulators.iter_mut()) {
+            match spec.column {
+                Some(column) => accumulator.push(row.get(column).unwrap_or(&Value::Null)),
+                // Подсчет строк: любое не-null значение
+                None => accumulator.push(&Value::Bool(true)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Таблица результата: ключевые колонки и по колонке на агрегат
+    pub fn into_table(self, headers: Vec<String>) -> Table {
+        let rows: Vec<Vec<Value>> = self
+            .groups
+            .into_iter()
+            .map(|(mut key, accumulators)| {
+                key.extend(accumulators.iter().map(Accumulator::finish));
+                key
+            })
+            .collect();
+        Table::from_data(rows, Some(headers))
+    }
+}
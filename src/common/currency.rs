@@ -0,0 +1,210 @@
+// Денежные значения (Value::Currency): разбор строк, форматирование и арифметика
+//
+// Сумма хранится как f64 вместе с символом валюты ("$", "€", "EUR", ...).
+// Складывать и сравнивать можно только суммы с одинаковым символом,
+// умножать и делить - на обычные числа.
+
+use crate::common::error::ErrorType;
+use crate::common::value::Value;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// Символы, которые пишутся перед суммой; трехбуквенные коды пишутся после нее
+const PREFIX_SYMBOLS: [&str; 5] = ["$", "€", "£", "¥", "₽"];
+
+/// Разбирает строку вида "$1,234.56", "-€10", "1 234,50 EUR" в (сумма, символ)
+pub fn parse(text: &str) -> Option<(f64, String)> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
+    };
+
+    let (symbol, number) = if let Some(symbol) = PREFIX_SYMBOLS.iter().find(|s| text.starts_with(**s)) {
+        (symbol.to_string(), &text[symbol.len()..])
+    } else if let Some(symbol) = PREFIX_SYMBOLS.iter().find(|s| text.ends_with(**s)) {
+        (symbol.to_string(), &text[..text.len() - symbol.len()])
+    } else {
+        // Код валюты из трех заглавных букв в начале или в конце: "USD 10", "10 EUR"
+        let is_code = |s: &str| s.len() == 3 && s.chars().all(|c| c.is_ascii_uppercase());
+        match (text.get(..3), text.get(text.len().saturating_sub(3)..)) {
+            (Some(code), _) if is_code(code) => (code.to_string(), &text[3..]),
+            (_, Some(code)) if is_code(code) => (code.to_string(), &text[..text.len() - 3]),
+            _ => return None,
+        }
+    };
+
+    let amount = parse_amount(number.trim())?;
+    Some((if negative { -amount } else { amount }, symbol))
+}
+
+/// Число с разделителями разрядов. Если есть и ',' и '.', десятичным считается последний;
+/// одиночная ',' - десятичная, если после нее не ровно три цифры ("10,5" но "1,234")
+fn parse_amount(text: &str) -> Option<f64> {
+    let text: String = text.chars().filter(|c| !matches!(c, ' ' | '\u{a0}' | '_' | '\'')).collect();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.to_string()),
+        None => (false, text),
+    };
+    let decimal_comma = match (text.rfind(','), text.rfind('.')) {
+        (Some(comma), Some(dot)) => comma > dot,
+        (Some(comma), None) => text.matches(',').count() == 1 && text.len() - comma - 1 != 3,
+        _ => false,
+    };
+    let normalized: String = if decimal_comma {
+        text.replace('.', "").replace(',', ".")
+    } else {
+        text.replace(',', "")
+    };
+    if normalized.is_empty() || !normalized.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let amount = normalized.parse::<f64>().ok().filter(|n| n.is_finite())?;
+    Some(if negative { -amount } else { amount })
+}
+
+/// Форматирует сумму: разделители разрядов ',' и decimals знаков после '.';
+/// "$", "€" и т.п. ставятся перед суммой, коды валют - после: "$1,234.56", "10.00 EUR"
+pub fn format(amount: f64, symbol: &str, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, amount.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+
+    // -0.00 после округления выводится без минуса
+    let sign = if amount < 0.0 && grouped.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+    if symbol.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else if PREFIX_SYMBOLS.contains(&symbol) {
+        format!("{}{}{}", sign, symbol, grouped)
+    } else {
+        format!("{}{} {}", sign, grouped, symbol)
+    }
+}
+
+/// Арифметические операции, определенные для денежных значений
+#[derive(Debug, Clone, Copy)]
+pub enum CurrencyOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+fn currency(amount: f64, symbol: &Rc<str>) -> Value {
+    Value::Currency { amount, symbol: Rc::clone(symbol) }
+}
+
+/// Ошибка денежной операции: сообщение и тип исключения
+pub type CurrencyError = (String, ErrorType);
+
+fn mixed_symbols(action: &str, a: &str, b: &str) -> CurrencyError {
+    (format!("Cannot {} amounts in different currencies: {} and {}", action, a, b), ErrorType::TypeError)
+}
+
+fn division_by_zero() -> CurrencyError {
+    ("Division by zero".to_string(), ErrorType::RuntimeError)
+}
+
+/// Результат операции, если хотя бы один операнд - Currency (None - операция не денежная)
+pub fn binary_op(op: CurrencyOp, a: &Value, b: &Value) -> Option<Result<Value, CurrencyError>> {
+    use CurrencyOp::*;
+
+    let result = match (op, a, b) {
+        (Add, Value::Currency { amount: x, symbol: s1 }, Value::Currency { amount: y, symbol: s2 })
+        | (Sub, Value::Currency { amount: x, symbol: s1 }, Value::Currency { amount: y, symbol: s2 }) => {
+            if s1 != s2 {
+                let action = if matches!(op, Add) { "add" } else { "subtract" };
+                return Some(Err(mixed_symbols(action, s1, s2)));
+            }
+            Ok(currency(if matches!(op, Add) { x + y } else { x - y }, s1))
+        }
+        // Сумма и обычное число: число считается суммой в той же валюте
+        (Add, Value::Currency { amount, symbol }, other) | (Add, other, Value::Currency { amount, symbol })
+            if other.is_numeric() =>
+        {
+            Ok(currency(amount + other.as_f64().unwrap_or(0.0), symbol))
+        }
+        (Sub, Value::Currency { amount, symbol }, other) if other.is_numeric() => {
+            Ok(currency(amount - other.as_f64().unwrap_or(0.0), symbol))
+        }
+        (Sub, other, Value::Currency { amount, symbol }) if other.is_numeric() => {
+            Ok(currency(other.as_f64().unwrap_or(0.0) - amount, symbol))
+        }
+        // Конкатенация со строкой, как для чисел
+        (Add, Value::String(s), Value::Currency { .. }) => Ok(Value::String(format!("{}{}", s, b.to_string()).into())),
+        (Add, Value::Currency { .. }, Value::String(s)) => Ok(Value::String(format!("{}{}", a.to_string(), s).into())),
+        (Mul, Value::Currency { amount, symbol }, other) | (Mul, other, Value::Currency { amount, symbol })
+            if other.is_numeric() =>
+        {
+            Ok(currency(amount * other.as_f64().unwrap_or(0.0), symbol))
+        }
+        (Div, Value::Currency { amount, symbol }, other) if other.is_numeric() => {
+            let divisor = other.as_f64().unwrap_or(0.0);
+            if divisor == 0.0 {
+                Err(division_by_zero())
+            } else {
+                Ok(currency(amount / divisor, symbol))
+            }
+        }
+        // Отношение двух сумм в одной валюте - обычное число
+        (Div, Value::Currency { amount: x, symbol: s1 }, Value::Currency { amount: y, symbol: s2 }) => {
+            if s1 != s2 {
+                Err(mixed_symbols("divide", s1, s2))
+            } else if *y == 0.0 {
+                Err(division_by_zero())
+            } else {
+                Ok(Value::Number(x / y))
+            }
+        }
+        (_, Value::Currency { .. }, _) | (_, _, Value::Currency { .. }) => Err((
+            format!("Unsupported operand types for money arithmetic: {} and {}", type_label(a), type_label(b)),
+            ErrorType::TypeError,
+        )),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn type_label(value: &Value) -> String {
+    crate::vm::natives::native_typeof(std::slice::from_ref(value)).to_string()
+}
+
+/// Сравнение для <, >, <=, >= (None - операнды не денежные).
+/// Суммы сравниваются только в одной валюте; с обычным числом сравнивается сумма
+pub fn compare(a: &Value, b: &Value) -> Option<Result<Ordering, CurrencyError>> {
+    let ordering = |x: f64, y: f64| x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+    match (a, b) {
+        (Value::Currency { amount: x, symbol: s1 }, Value::Currency { amount: y, symbol: s2 }) => {
+            if s1 != s2 {
+                Some(Err(mixed_symbols("compare", s1, s2)))
+            } else {
+                Some(Ok(ordering(*x, *y)))
+            }
+        }
+        (Value::Currency { amount, .. }, other) if other.is_numeric() => {
+            Some(Ok(ordering(*amount, other.as_f64().unwrap_or(0.0))))
+        }
+        (other, Value::Currency { amount, .. }) if other.is_numeric() => {
+            Some(Ok(ordering(other.as_f64().unwrap_or(0.0), *amount)))
+        }
+        (Value::Currency { .. }, _) | (_, Value::Currency { .. }) => Some(Err((
+            format!("Cannot compare {} and {}", type_label(a), type_label(b)),
+            ErrorType::TypeError,
+        ))),
+        _ => None,
+    }
+}
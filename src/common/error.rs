@@ -1,5 +1,8 @@
 // Единый формат ошибок компиляции и рантайма
 
+use crate::common::value::Value;
+use serde::Serialize;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorType {
     // RuntimeError и его подтипы
@@ -10,12 +13,14 @@ pub enum ErrorType {
     KeyError,
     StateError,
     OverflowError,
+    AssertionError,
     // IOError и его подтипы
     IOError,
     FileNotFoundError,
     PermissionError,
     DirectoryError,
     ReadOnlyError,
+    ConnectionError,
     // ParseError и его подтипы
     ParseError,
     SyntaxError,
@@ -25,6 +30,8 @@ pub enum ErrorType {
     SchemaError,
     ColumnNotFoundError,
     DataFormatError,
+    // Прерывание пользователем (Ctrl-C); не является RuntimeError
+    Interrupted,
 }
 
 impl ErrorType {
@@ -37,11 +44,13 @@ impl ErrorType {
             ErrorType::KeyError => "KeyError",
             ErrorType::StateError => "StateError",
             ErrorType::OverflowError => "OverflowError",
+            ErrorType::AssertionError => "AssertionError",
             ErrorType::IOError => "IOError",
             ErrorType::FileNotFoundError => "FileNotFoundError",
             ErrorType::PermissionError => "PermissionError",
             ErrorType::DirectoryError => "DirectoryError",
             ErrorType::ReadOnlyError => "ReadOnlyError",
+            ErrorType::ConnectionError => "ConnectionError",
             ErrorType::ParseError => "ParseError",
             ErrorType::SyntaxError => "SyntaxError",
             ErrorType::TokenError => "TokenError",
@@ -49,6 +58,7 @@ impl ErrorType {
             ErrorType::SchemaError => "SchemaError",
             ErrorType::ColumnNotFoundError => "ColumnNotFoundError",
             ErrorType::DataFormatError => "DataFormatError",
+            ErrorType::Interrupted => "Interrupted",
         }
     }
 
@@ -72,6 +82,8 @@ impl ErrorType {
             (ErrorType::StateError, ErrorType::RuntimeError) => true,
             // OverflowError является RuntimeError
             (ErrorType::OverflowError, ErrorType::RuntimeError) => true,
+            // AssertionError является RuntimeError
+            (ErrorType::AssertionError, ErrorType::RuntimeError) => true,
             
             // FileNotFoundError является IOError
             (ErrorType::FileNotFoundError, ErrorType::IOError) => true,
@@ -81,6 +93,8 @@ impl ErrorType {
             (ErrorType::DirectoryError, ErrorType::IOError) => true,
             // ReadOnlyError является IOError
             (ErrorType::ReadOnlyError, ErrorType::IOError) => true,
+            // ConnectionError является IOError
+            (ErrorType::ConnectionError, ErrorType::IOError) => true,
             
             // SyntaxError является ParseError
             (ErrorType::SyntaxError, ErrorType::ParseError) => true,
@@ -108,11 +122,13 @@ impl ErrorType {
             "KeyError" => Some(ErrorType::KeyError),
             "StateError" => Some(ErrorType::StateError),
             "OverflowError" => Some(ErrorType::OverflowError),
+            "AssertionError" => Some(ErrorType::AssertionError),
             "IOError" => Some(ErrorType::IOError),
             "FileNotFoundError" => Some(ErrorType::FileNotFoundError),
             "PermissionError" => Some(ErrorType::PermissionError),
             "DirectoryError" => Some(ErrorType::DirectoryError),
             "ReadOnlyError" => Some(ErrorType::ReadOnlyError),
+            "ConnectionError" => Some(ErrorType::ConnectionError),
             "ParseError" => Some(ErrorType::ParseError),
             "SyntaxError" => Some(ErrorType::SyntaxError),
             "TokenError" => Some(ErrorType::TokenError),
@@ -120,6 +136,7 @@ impl ErrorType {
             "SchemaError" => Some(ErrorType::SchemaError),
             "ColumnNotFoundError" => Some(ErrorType::ColumnNotFoundError),
             "DataFormatError" => Some(ErrorType::DataFormatError),
+            "Interrupted" => Some(ErrorType::Interrupted),
             _ => None,
         }
     }
@@ -141,6 +158,8 @@ pub enum LangError {
         line: usize,
         stack_trace: Vec<StackTraceEntry>,
         error_type: Option<ErrorType>,
+        /// Значение из `throw` (не строка): попадает в переменную catch как есть
+        thrown: Option<Box<Value>>,
     },
 }
 
@@ -151,6 +170,7 @@ impl LangError {
             line,
             stack_trace: Vec::new(),
             error_type: None,
+            thrown: None,
         }
     }
 
@@ -160,6 +180,7 @@ impl LangError {
             line,
             stack_trace,
             error_type: None,
+            thrown: None,
         }
     }
 
@@ -169,6 +190,7 @@ impl LangError {
             line,
             stack_trace: Vec::new(),
             error_type: Some(error_type),
+            thrown: None,
         }
     }
 
@@ -183,6 +205,63 @@ impl LangError {
             line,
             stack_trace,
             error_type: Some(error_type),
+            thrown: None,
+        }
+    }
+
+    /// Ошибка из значения `throw`. Строка становится сообщением, как и раньше.
+    /// Для объекта сообщение берется из ключа "message", тип - из ключа "kind"
+    /// (встроенный тип ошибки или собственное имя, например "ValidationError");
+    /// для остальных значений сообщение - их строковое представление
+    pub fn thrown(value: Value, line: usize, stack_trace: Vec<StackTraceEntry>) -> Self {
+        let (message, error_type) = match &value {
+            Value::String(message) => {
+                return LangError::runtime_error_with_trace(message.to_string(), line, stack_trace);
+            }
+            Value::Object(map) => {
+                let message = match map.get("message") {
+                    Some(message) => message.to_string(),
+                    None => value.to_string(),
+                };
+                let error_type = match map.get("kind") {
+                    Some(Value::String(kind)) => ErrorType::from_name(kind),
+                    _ => None,
+                };
+                (message, error_type)
+            }
+            other => (other.to_string(), None),
+        };
+        LangError::RuntimeError {
+            message,
+            line,
+            stack_trace,
+            error_type,
+            thrown: Some(Box::new(value)),
+        }
+    }
+
+    /// Значение, переданное в `throw` (кроме строк)
+    pub fn thrown_value(&self) -> Option<&Value> {
+        match self {
+            LangError::RuntimeError { thrown, .. } => thrown.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Имя вида ошибки: ключ "kind" брошенного объекта или имя типа ошибки
+    pub fn kind_name(&self) -> String {
+        if let Some(Value::Object(map)) = self.thrown_value() {
+            if let Some(Value::String(kind)) = map.get("kind") {
+                return kind.to_string();
+            }
+        }
+        match self {
+            LangError::LexError { .. } => "LexError".to_string(),
+            LangError::ParseError { .. } => "ParseError".to_string(),
+            LangError::SemanticError { .. } => "SemanticError".to_string(),
+            LangError::RuntimeError { error_type, .. } => {
+                error_type.as_ref().map_or("RuntimeError", |et| et.name()).to_string()
+            }
         }
     }
 
@@ -194,6 +273,57 @@ impl LangError {
         }
     }
 
+    /// Текст ошибки без префикса вида и номера строки
+    pub fn message(&self) -> &str {
+        match self {
+            LangError::LexError { message, .. }
+            | LangError::ParseError { message, .. }
+            | LangError::SemanticError { message, .. }
+            | LangError::RuntimeError { message, .. } => message,
+        }
+    }
+
+    /// Номер строки (0 - строка неизвестна)
+    pub fn line(&self) -> usize {
+        match self {
+            LangError::LexError { line, .. }
+            | LangError::ParseError { line, .. }
+            | LangError::SemanticError { line, .. }
+            | LangError::RuntimeError { line, .. } => *line,
+        }
+    }
+
+    /// Колонка ошибки. Позиции внутри строки пока не сохраняются ни лексером, ни VM
+    pub fn column(&self) -> Option<usize> {
+        None
+    }
+
+    /// Функция, в которой произошла ошибка выполнения (самый глубокий кадр стека)
+    pub fn function_name(&self) -> Option<&str> {
+        match self {
+            LangError::RuntimeError { stack_trace, .. } => stack_trace.first().map(|entry| entry.function_name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Машиночитаемое описание ошибки; source нужен для строки-фрагмента (snippet)
+    pub fn to_structured(&self, source: Option<&str>) -> StructuredError {
+        let line = self.line();
+        StructuredError {
+            kind: self.kind_name(),
+            message: self.message().to_string(),
+            line: (line > 0).then_some(line),
+            column: self.column(),
+            function: self.function_name().map(str::to_string),
+            snippet: source.and_then(|source| source_line(source, line)),
+        }
+    }
+
+    /// JSON объект {kind, message, line, column, function, snippet} (snippet - null, исходник неизвестен)
+    pub fn to_json(&self) -> serde_json::Value {
+        self.to_structured(None).to_json()
+    }
+
     /// Проверяет, является ли ошибка указанного типа или его подтипом
     pub fn is_instance_of(&self, error_type: &ErrorType) -> bool {
         match self {
@@ -207,6 +337,51 @@ impl LangError {
     }
 }
 
+/// Ошибка или предупреждение в виде, удобном для CI и встраивания: сериализуется serde
+/// в JSON объект с полями kind, message, line, column, function и snippet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructuredError {
+    /// Вид: имя типа ошибки ("TypeError", "ParseError", ...) или "Warning"
+    pub kind: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Имя функции для ошибок выполнения ("<main>" - код верхнего уровня)
+    pub function: Option<String>,
+    /// Строка исходного кода, на которую указывает line (без отступов)
+    pub snippet: Option<String>,
+}
+
+impl StructuredError {
+    /// Предупреждение, не привязанное к строке кода
+    pub fn warning(message: String) -> Self {
+        StructuredError {
+            kind: "Warning".to_string(),
+            message,
+            line: None,
+            column: None,
+            function: None,
+            snippet: None,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+impl std::fmt::Display for StructuredError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+/// Строка исходника с номером line (нумерация с 1)
+fn source_line(source: &str, line: usize) -> Option<String> {
+    let text = source.lines().nth(line.checked_sub(1)?)?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
 impl std::fmt::Display for LangError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -219,12 +394,27 @@ impl std::fmt::Display for LangError {
             LangError::SemanticError { message, line } => {
                 write!(f, "[Semantic Error] Line {}: {}", line, message)
             }
-            LangError::RuntimeError { message, line, stack_trace, error_type } => {
-                if let Some(et) = error_type {
+            LangError::RuntimeError { message, line, stack_trace, error_type, thrown } => {
+                let custom_kind = match thrown.as_deref() {
+                    Some(Value::Object(map)) => match map.get("kind") {
+                        Some(Value::String(kind)) if ErrorType::from_name(kind).is_none() => Some(kind.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(kind) = custom_kind {
+                    write!(f, "[{}] Line {}: {}", kind, line, message)?;
+                } else if let Some(et) = error_type {
                     write!(f, "[{}] Line {}: {}", et.name(), line, message)?;
                 } else {
                     write!(f, "[Runtime Error] Line {}: {}", line, message)?;
                 }
+                // Остальные поля брошенного объекта
+                if let Some(Value::Object(map)) = thrown.as_deref() {
+                    for (key, value) in map.iter().filter(|(key, _)| *key != "kind" && *key != "message") {
+                        write!(f, "\n  {}: {}", key, value.to_string())?;
+                    }
+                }
                 if !stack_trace.is_empty() {
                     write!(f, "\nStack trace:")?;
                     for (i, entry) in stack_trace.iter().enumerate() {
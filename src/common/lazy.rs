@@ -0,0 +1,479 @@
+// Ленивые таблицы: цепочка операций над исходной таблицей, выполняемая одним проходом
+//
+// table_lazy(t) создает LazyTable, а table_where/table_select/table_sort/table_head
+// для нее только добавляют операцию в план. collect() выполняет план: фильтры и
+// выборка колонок работают по индексам строк исходной таблицы без промежуточных копий,
+// head останавливает просмотр, как только набрано нужное число строк,
+// сортировка выполняется отдельной стадией над уже отфильтрованными строками.
+//
+// Источником может быть и CSV файл (read_file(path, lazy=true)): тогда фильтры и head до
+// первой сортировки выполняются прямо при чтении файла, а в памяти остаются только
+// прошедшие строки и колонки, которые нужны плану.
+
+use crate::common::table::{compare_values, Table};
+use crate::common::value::Value;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// Оператор сравнения в условии table_where
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Greater,
+    Less,
+    GreaterEqual,
+    LessEqual,
+    Equal,
+    NotEqual,
+}
+
+impl CompareOp {
+    pub fn parse(operator: &str) -> Option<Self> {
+        match operator {
+            ">" => Some(CompareOp::Greater),
+            "<" => Some(CompareOp::Less),
+            ">=" => Some(CompareOp::GreaterEqual),
+            "<=" => Some(CompareOp::LessEqual),
+            "==" | "=" => Some(CompareOp::Equal),
+            "!=" | "<>" => Some(CompareOp::NotEqual),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Greater => ">",
+            CompareOp::Less => "<",
+            CompareOp::GreaterEqual => ">=",
+            CompareOp::LessEqual => "<=",
+            CompareOp::Equal => "==",
+            CompareOp::NotEqual => "!=",
+        }
+    }
+
+    pub fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Greater => ordering == Ordering::Greater,
+            CompareOp::Less => ordering == Ordering::Less,
+            CompareOp::GreaterEqual => ordering != Ordering::Less,
+            CompareOp::LessEqual => ordering != Ordering::Greater,
+            CompareOp::Equal => ordering == Ordering::Equal,
+            CompareOp::NotEqual => ordering != Ordering::Equal,
+        }
+    }
+}
+
+/// Разобранное условие фильтра: колонка, оператор и значение для сравнения
+#[derive(Debug, Clone)]
+pub struct Predicate {
+    pub column: String,
+    pub op: CompareOp,
+    pub value: Value,
+}
+
+impl Predicate {
+    pub fn test(&self, cell: &Value) -> bool {
+        self.op.matches(compare_values(cell, &self.value))
+    }
+}
+
+/// Операция ленивого плана
+#[derive(Debug, Clone)]
+pub enum LazyOp {
+    Filter(Predicate),
+    Select(Vec<String>),
+    Sort { column: String, ascending: bool },
+    Head(usize),
+}
+
+impl LazyOp {
+    pub fn describe(&self) -> String {
+        match self {
+            LazyOp::Filter(p) => format!("where {} {} {}", p.column, p.op.symbol(), p.value.to_string()),
+            LazyOp::Select(columns) => format!("select {}", columns.join(", ")),
+            LazyOp::Sort { column, ascending } => {
+                format!("sort {} {}", column, if *ascending { "asc" } else { "desc" })
+            }
+            LazyOp::Head(n) => format!("head {}", n),
+        }
+    }
+}
+
+/// Шаг потоковой стадии: фильтр по индексу колонки или ограничение числа строк
+enum Step<'a> {
+    Filter(usize, &'a Predicate),
+    Head(usize),
+}
+
+/// Решение потоковой стадии для очередной строки
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+    Keep,
+    Skip,
+    /// Все head набрали свои строки - дальше читать не нужно
+    Stop,
+}
+
+/// Потоковая стадия: фильтры и head, которые применяются к каждой строке по порядку
+struct Segment<'a> {
+    steps: Vec<Step<'a>>,
+    taken: Vec<usize>,
+}
+
+impl<'a> Segment<'a> {
+    fn new(ops: &'a [LazyOp], column_index: impl Fn(&str) -> Option<usize>) -> Self {
+        let steps: Vec<Step> = ops
+            .iter()
+            .filter_map(|op| match op {
+                LazyOp::Filter(p) => column_index(&p.column).map(|column| Step::Filter(column, p)),
+                LazyOp::Head(n) => Some(Step::Head(*n)),
+                _ => None,
+            })
+            .collect();
+        let taken = vec![0; steps.len()];
+        Self { steps, taken }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Проверяет строку; cell возвращает значение колонки по индексу в источнике
+    fn check<'v>(&mut self, cell: impl Fn(usize) -> &'v Value) -> Verdict {
+        // head, который уже набрал свои строки, не пропустит ни одной следующей
+        let exhausted = self
+            .steps
+            .iter()
+            .zip(&self.taken)
+            .any(|(step, &count)| matches!(step, Step::Head(n) if count >= *n));
+        if exhausted {
+            return Verdict::Stop;
+        }
+        for (step, count) in self.steps.iter().zip(self.taken.iter_mut()) {
+            match step {
+                Step::Filter(column, p) => {
+                    if !p.test(cell(*column)) {
+                        return Verdict::Skip;
+                    }
+                }
+                Step::Head(n) => {
+                    if *count >= *n {
+                        return Verdict::Skip;
+                    }
+                    *count += 1;
+                }
+            }
+        }
+        Verdict::Keep
+    }
+}
+
+/// Источник строк, который читается потоком при каждом collect() (CSV файл из read_file(lazy=true))
+pub trait RowSource: std::fmt::Debug {
+    /// Колонки источника
+    fn headers(&self) -> &[String];
+
+    /// Имя итоговой таблицы
+    fn name(&self) -> Option<String>;
+
+    /// Описание источника для стадий плана, например "csv sales.csv"
+    fn describe(&self) -> String;
+
+    /// Читает строки по порядку. Значения разбираются только для колонок с needed[i] == true,
+    /// остальные - Null. visit возвращает false, чтобы прекратить чтение
+    fn scan(&self, needed: &[bool], visit: &mut dyn FnMut(&[Value]) -> bool) -> Result<(), String>;
+}
+
+/// Источник данных ленивой таблицы
+#[derive(Debug, Clone)]
+pub enum LazySource {
+    Table(Rc<RefCell<Table>>),
+    Rows(Rc<dyn RowSource>),
+}
+
+/// Статистика выполнения плана
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LazyStats {
+    pub rows_scanned: usize,
+    pub rows_produced: usize,
+    pub stages: Vec<String>,
+}
+
+/// Ленивая таблица: исходная таблица и план операций над ней
+#[derive(Debug, Clone)]
+pub struct LazyTable {
+    pub source: LazySource,
+    pub ops: Vec<LazyOp>,
+    /// Колонки результата после всех операций плана
+    pub headers: Vec<String>,
+}
+
+impl LazyTable {
+    pub fn new(source: Rc<RefCell<Table>>) -> Self {
+        let headers = source.borrow().headers.clone();
+        Self { source: LazySource::Table(source), ops: Vec::new(), headers }
+    }
+
+    /// Ленивая таблица над потоковым источником строк
+    pub fn from_rows(source: Rc<dyn RowSource>) -> Self {
+        let headers = source.headers().to_vec();
+        Self { source: LazySource::Rows(source), ops: Vec::new(), headers }
+    }
+
+    /// Новый план с добавленной операцией. Имена колонок проверяются сразу
+    pub fn with_op(&self, op: LazyOp) -> Result<LazyTable, String> {
+        let mut headers = self.headers.clone();
+        match &op {
+            LazyOp::Filter(Predicate { column, .. }) | LazyOp::Sort { column, .. } => {
+                if !headers.contains(column) {
+                    return Err(format!("Column '{}' not found", column));
+                }
+            }
+            LazyOp::Select(columns) => {
+                if let Some(missing) = columns.iter().find(|c| !headers.contains(c)) {
+                    return Err(format!("Column '{}' not found", missing));
+                }
+                headers = columns.clone();
+            }
+            LazyOp::Head(_) => {}
+        }
+        let mut ops = self.ops.clone();
+        ops.push(op);
+        Ok(LazyTable { source: self.source.clone(), ops, headers })
+    }
+
+    /// Стадии выполнения: последовательные фильтры/выборки/head объединяются в один проход,
+    /// каждая сортировка - отдельная стадия. Первый проход по потоковому источнику
+    /// показывает файл и число читаемых колонок
+    pub fn stages(&self) -> Vec<String> {
+        self.stages_for(&self.headers)
+    }
+
+    /// Стадии выполнения, когда из результата нужны только колонки columns
+    fn stages_for(&self, columns: &[String]) -> Vec<String> {
+        let first_scan = match &self.source {
+            LazySource::Table(_) => "scan".to_string(),
+            LazySource::Rows(rows) => {
+                let needed = self.needed_columns(rows.headers(), columns).iter().filter(|&&n| n).count();
+                format!("scan {} ({} of {} columns)", rows.describe(), needed, rows.headers().len())
+            }
+        };
+        let mut stages: Vec<String> = Vec::new();
+        let mut streaming: Vec<String> = Vec::new();
+        let scan_label = |stages: &Vec<String>| if stages.is_empty() { first_scan.clone() } else { "scan".to_string() };
+        for op in &self.ops {
+            match op {
+                LazyOp::Sort { .. } => {
+                    if !streaming.is_empty() || (stages.is_empty() && matches!(self.source, LazySource::Rows(_))) {
+                        stages.push(format!("{} [{}]", scan_label(&stages), streaming.join(" -> ")));
+                        streaming.clear();
+                    }
+                    stages.push(op.describe());
+                }
+                _ => streaming.push(op.describe()),
+            }
+        }
+        if !streaming.is_empty() || stages.is_empty() {
+            stages.push(format!("{} [{}]", scan_label(&stages), streaming.join(" -> ")));
+        }
+        stages
+    }
+
+    /// Колонки источника, которые нужны плану: колонки результата columns, условия фильтров
+    /// и ключи сортировок
+    fn needed_columns(&self, headers: &[String], columns: &[String]) -> Vec<bool> {
+        let mut names: Vec<&str> = columns.iter().map(String::as_str).collect();
+        for op in &self.ops {
+            match op {
+                LazyOp::Filter(Predicate { column, .. }) | LazyOp::Sort { column, .. } => names.push(column),
+                LazyOp::Select(_) | LazyOp::Head(_) => {}
+            }
+        }
+        headers.iter().map(|h| names.contains(&h.as_str())).collect()
+    }
+
+    /// Выполняет план и возвращает итоговую таблицу со статистикой. Ошибка чтения
+    /// потокового источника дает пустую таблицу; сообщение об ошибке возвращает try_collect()
+    pub fn collect(&self) -> (Table, LazyStats) {
+        self.try_collect().unwrap_or_else(|_| {
+            let stats = LazyStats { stages: self.stages(), ..LazyStats::default() };
+            (Table::from_data(Vec::new(), Some(self.headers.clone())), stats)
+        })
+    }
+
+    /// Выполняет план; для CSV источника - ошибка чтения или разбора файла
+    pub fn try_collect(&self) -> Result<(Table, LazyStats), String> {
+        match &self.source {
+            LazySource::Table(table) => {
+                let source = table.borrow();
+                let (mut table, mut stats) = self.run(&source, &self.ops);
+                table.name = source.name.clone();
+                stats.stages = self.stages();
+                Ok((table, stats))
+            }
+            LazySource::Rows(rows) => self.collect_rows(rows.as_ref()),
+        }
+    }
+
+    /// Потоковый источник: фильтры и head до первой сортировки применяются при чтении,
+    /// в памяти собираются только прошедшие строки и нужные плану колонки.
+    /// Остаток плана выполняется над этой уменьшенной таблицей
+    fn collect_rows(&self, rows: &dyn RowSource) -> Result<(Table, LazyStats), String> {
+        let headers = rows.headers();
+        let needed = self.needed_columns(headers, &self.headers);
+        let kept: Vec<usize> = (0..headers.len()).filter(|&i| needed[i]).collect();
+        let end = self
+            .ops
+            .iter()
+            .position(|op| matches!(op, LazyOp::Sort { .. }))
+            .unwrap_or(self.ops.len());
+        let mut segment = Segment::new(&self.ops[..end], |name| headers.iter().position(|h| h == name));
+
+        let mut data: Vec<Vec<Value>> = Vec::new();
+        let mut scanned = 0;
+        rows.scan(&needed, &mut |row: &[Value]| {
+            let verdict = segment.check(|column| row.get(column).unwrap_or(&Value::Null));
+            if verdict == Verdict::Stop {
+                return false;
+            }
+            scanned += 1;
+            if verdict == Verdict::Keep {
+                data.push(kept.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect());
+            }
+            true
+        })?;
+
+        let reduced = Table::from_data(data, Some(kept.iter().map(|&i| headers[i].clone()).collect()));
+        let (mut table, mut stats) = self.run(&reduced, &self.ops[end..]);
+        table.name = rows.name();
+        stats.rows_scanned = scanned;
+        stats.stages = self.stages();
+        Ok((table, stats))
+    }
+
+    /// Передает в visit строки результата плана - значения колонок columns в этом порядке, -
+    /// не собирая итоговую таблицу. Для потокового источника без сортировок строки идут прямо
+    /// из чтения файла и в памяти хранится только текущая строка; иначе план сначала
+    /// выполняется через try_collect()
+    pub fn stream(&self, columns: &[String], visit: &mut dyn FnMut(&[Value])) -> Result<LazyStats, String> {
+        if let Some(missing) = columns.iter().find(|c| !self.headers.contains(c)) {
+            return Err(format!("Column '{}' not found", missing));
+        }
+        let mut row_buffer = Vec::with_capacity(columns.len());
+        let rows = match &self.source {
+            LazySource::Rows(rows) if !self.ops.iter().any(|op| matches!(op, LazyOp::Sort { .. })) => rows,
+            _ => {
+                let (table, stats) = self.try_collect()?;
+                let indices: Vec<Option<usize>> =
+                    columns.iter().map(|c| table.headers.iter().position(|h| h == c)).collect();
+                for row in &table.rows {
+                    row_buffer.clear();
+                    row_buffer.extend(indices.iter().map(|i| i.and_then(|i| row.get(i)).cloned().unwrap_or(Value::Null)));
+                    visit(&row_buffer);
+                }
+                return Ok(stats);
+            }
+        };
+
+        let headers = rows.headers();
+        let needed = self.needed_columns(headers, columns);
+        let indices: Vec<Option<usize>> = columns.iter().map(|c| headers.iter().position(|h| h == c)).collect();
+        let mut segment = Segment::new(&self.ops, |name| headers.iter().position(|h| h == name));
+        let mut stats = LazyStats { stages: self.stages_for(columns), ..LazyStats::default() };
+        rows.scan(&needed, &mut |row: &[Value]| {
+            let verdict = segment.check(|column| row.get(column).unwrap_or(&Value::Null));
+            if verdict == Verdict::Stop {
+                return false;
+            }
+            stats.rows_scanned += 1;
+            if verdict == Verdict::Keep {
+                row_buffer.clear();
+                row_buffer.extend(indices.iter().map(|i| i.and_then(|i| row.get(i)).cloned().unwrap_or(Value::Null)));
+                stats.rows_produced += 1;
+                visit(&row_buffer);
+            }
+            true
+        })?;
+        Ok(stats)
+    }
+
+    /// Выполняет операции над таблицей в памяти; колонки результата - self.headers
+    fn run(&self, source: &Table, ops: &[LazyOp]) -> (Table, LazyStats) {
+        let column_index = |name: &str| source.headers.iter().position(|h| h == name);
+        let cell = |row: usize, column: usize| -> &Value {
+            source.rows.get(row).and_then(|r| r.get(column)).unwrap_or(&Value::Null)
+        };
+
+        let mut stats = LazyStats::default();
+        // Строки представлены индексами в исходной таблице; None - все строки исходной таблицы по порядку
+        let mut rows: Option<Vec<usize>> = None;
+        let mut position = 0;
+
+        loop {
+            // Потоковая стадия: операции до следующей сортировки применяются к каждой строке по порядку
+            let end = ops[position..]
+                .iter()
+                .position(|op| matches!(op, LazyOp::Sort { .. }))
+                .map_or(ops.len(), |offset| position + offset);
+            let mut segment = Segment::new(&ops[position..end], column_index);
+
+            if !segment.is_empty() {
+                let from_source = rows.is_none();
+                let input: Box<dyn Iterator<Item = usize>> = match rows.take() {
+                    Some(indices) => Box::new(indices.into_iter()),
+                    None => Box::new(0..source.rows.len()),
+                };
+                let mut output = Vec::new();
+                for index in input {
+                    let verdict = segment.check(|column| cell(index, column));
+                    if verdict == Verdict::Stop {
+                        break;
+                    }
+                    if from_source {
+                        stats.rows_scanned += 1;
+                    }
+                    if verdict == Verdict::Keep {
+                        output.push(index);
+                    }
+                }
+                rows = Some(output);
+            }
+
+            match ops.get(end) {
+                Some(LazyOp::Sort { column, ascending }) => {
+                    let mut indices = rows.take().unwrap_or_else(|| {
+                        stats.rows_scanned = source.rows.len();
+                        (0..source.rows.len()).collect()
+                    });
+                    if let Some(column) = column_index(column) {
+                        // Устойчивая сортировка - тот же порядок равных строк, что и у table_sort
+                        indices.sort_by(|&a, &b| {
+                            let ordering = compare_values(cell(a, column), cell(b, column));
+                            if *ascending { ordering } else { ordering.reverse() }
+                        });
+                    }
+                    rows = Some(indices);
+                    position = end + 1;
+                }
+                _ => break,
+            }
+        }
+
+        let indices = rows.unwrap_or_else(|| {
+            stats.rows_scanned = source.rows.len();
+            (0..source.rows.len()).collect()
+        });
+        let columns: Vec<Option<usize>> = self.headers.iter().map(|h| column_index(h)).collect();
+        let data: Vec<Vec<Value>> = indices
+            .iter()
+            .map(|&row| {
+                columns
+                    .iter()
+                    .map(|column| column.map_or(Value::Null, |c| cell(row, c).clone()))
+                    .collect()
+            })
+            .collect();
+        stats.rows_produced = data.len();
+        (Table::from_data(data, Some(self.headers.clone())), stats)
+    }
+}
@@ -2,4 +2,13 @@ pub mod span;
 pub mod error;
 pub mod value;
 pub mod table;
+pub mod random;
+pub mod lazy;
+pub mod aggregate;
+pub mod render;
+pub mod path;
+pub mod currency;
+pub mod operators;
+pub mod output_capture;
 
+pub mod suggest;
@@ -0,0 +1,386 @@
+// Семантика операторов языка: арифметика, сравнения, побитовые и унарные операторы.
+// Общее ядро для VM (выполнение байткода) и компилятора (свертка константных выражений):
+// константное выражение и то же выражение над переменными дают одинаковый результат
+
+use crate::common::currency::{self, CurrencyOp};
+use crate::common::error::ErrorType;
+use crate::common::value::Value;
+use std::cmp::Ordering;
+
+/// Бинарный оператор над двумя значениями (and, or и in вычисляются отдельно)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    IntDiv,
+    Mod,
+    Pow,
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+    GreaterEqual,
+    LessEqual,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::IntDiv => "//",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "**",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Greater => ">",
+            BinaryOp::Less => "<",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+        }
+    }
+}
+
+/// Ошибка оператора. error_type None - ошибка выполнения без типа
+/// (деление на ноль, неподходящие операнды арифметики и сравнений)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorError {
+    pub message: String,
+    pub error_type: Option<ErrorType>,
+}
+
+impl OperatorError {
+    fn untyped(message: impl Into<String>) -> Self {
+        Self { message: message.into(), error_type: None }
+    }
+
+    fn typed(message: String, error_type: ErrorType) -> Self {
+        Self { message, error_type: Some(error_type) }
+    }
+}
+
+impl From<(String, ErrorType)> for OperatorError {
+    fn from((message, error_type): (String, ErrorType)) -> Self {
+        Self::typed(message, error_type)
+    }
+}
+
+pub type OperatorResult = Result<Value, OperatorError>;
+
+/// a op b
+pub fn binary(op: BinaryOp, a: &Value, b: &Value) -> OperatorResult {
+    match op {
+        BinaryOp::Add => add(a, b),
+        BinaryOp::Sub => sub(a, b),
+        BinaryOp::Mul => mul(a, b),
+        BinaryOp::Div => div(a, b),
+        BinaryOp::IntDiv => int_div(a, b),
+        BinaryOp::Mod => modulo(a, b),
+        BinaryOp::Pow => pow(a, b),
+        BinaryOp::Equal => Ok(Value::Bool(a == b)),
+        BinaryOp::NotEqual => Ok(Value::Bool(a != b)),
+        BinaryOp::Greater => compare(a, b, Ordering::is_gt),
+        BinaryOp::Less => compare(a, b, Ordering::is_lt),
+        BinaryOp::GreaterEqual => compare(a, b, Ordering::is_ge),
+        BinaryOp::LessEqual => compare(a, b, Ordering::is_le),
+        BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+            bitwise(op, a, b)
+        }
+    }
+}
+
+/// Унарный минус
+pub fn negate(value: &Value) -> OperatorResult {
+    match value {
+        Value::Number(n) => Ok(Value::Number(-n)),
+        Value::Currency { amount, symbol } => Ok(Value::Currency { amount: -amount, symbol: symbol.clone() }),
+        Value::Integer(i) => Ok(match i.checked_neg() {
+            Some(neg) => Value::Integer(neg),
+            None => Value::Number(-(*i as f64)),
+        }),
+        _ => Err(OperatorError::untyped("Operand must be a number")),
+    }
+}
+
+/// not / !
+pub fn not(value: &Value) -> Value {
+    Value::Bool(!value.is_truthy())
+}
+
+/// Денежная арифметика (Value::Currency); None - ни один операнд не является денежной суммой
+fn currency_binary(op: CurrencyOp, a: &Value, b: &Value) -> Option<OperatorResult> {
+    Some(currency::binary_op(op, a, b)?.map_err(OperatorError::from))
+}
+
+fn add(a: &Value, b: &Value) -> OperatorResult {
+    if let Some(result) = currency_binary(CurrencyOp::Add, a, b) {
+        return result;
+    }
+    // Integer в паре с Number приводится к Number
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return add(&a, &b);
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => Ok(match i1.checked_add(*i2) {
+            Some(sum) => Value::Integer(sum),
+            None => Value::Number(*i1 as f64 + *i2 as f64),
+        }),
+        (Value::String(s), Value::Integer(i)) => Ok(Value::String(format!("{}{}", s, i).into())),
+        (Value::Integer(i), Value::String(s)) => Ok(Value::String(format!("{}{}", i, s).into())),
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 + n2)),
+        (Value::String(s1), Value::String(s2)) => Ok(Value::String(format!("{}{}", s1, s2).into())),
+        (Value::String(s), Value::Number(n)) => Ok(Value::String(format!("{}{}", s, n).into())),
+        (Value::Number(n), Value::String(s)) => Ok(Value::String(format!("{}{}", n, s).into())),
+        _ => Err(OperatorError::untyped("Operands must be numbers or strings")),
+    }
+}
+
+fn sub(a: &Value, b: &Value) -> OperatorResult {
+    if let Some(result) = currency_binary(CurrencyOp::Sub, a, b) {
+        return result;
+    }
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return sub(&a, &b);
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => Ok(match i1.checked_sub(*i2) {
+            Some(diff) => Value::Integer(diff),
+            None => Value::Number(*i1 as f64 - *i2 as f64),
+        }),
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 - n2)),
+        _ => Err(OperatorError::untyped("Operands must be numbers")),
+    }
+}
+
+fn mul(a: &Value, b: &Value) -> OperatorResult {
+    if let Some(result) = currency_binary(CurrencyOp::Mul, a, b) {
+        return result;
+    }
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return mul(&a, &b);
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => Ok(match i1.checked_mul(*i2) {
+            Some(product) => Value::Integer(product),
+            None => Value::Number(*i1 as f64 * *i2 as f64),
+        }),
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 * n2)),
+        _ => Err(OperatorError::untyped("Operands must be numbers")),
+    }
+}
+
+fn div(a: &Value, b: &Value) -> OperatorResult {
+    if let Some(result) = currency_binary(CurrencyOp::Div, a, b) {
+        return result;
+    }
+    // Обычное деление всегда дает Number, даже для двух Integer
+    if matches!(a, Value::Integer(_)) || matches!(b, Value::Integer(_)) {
+        if let (Some(x), Some(y)) = (a.as_f64(), b.as_f64()) {
+            return div(&Value::Number(x), &Value::Number(y));
+        }
+    }
+    match (a, b) {
+        (Value::Number(_), Value::Number(n2)) if *n2 == 0.0 => Err(OperatorError::untyped("Division by zero")),
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 / n2)),
+        // Конкатенация путей: Path / String -> Path
+        (Value::Path(p), Value::String(s)) => {
+            let mut new_path = crate::common::path::normalize_separators(p);
+            new_path.push(crate::common::path::from_user_str(s));
+            Ok(Value::Path(new_path))
+        }
+        // Конкатенация путей: String / String -> Path (если контекст предполагает путь)
+        (Value::String(s1), Value::String(s2)) => {
+            use crate::common::path::from_user_str;
+            let mut path = from_user_str(s1);
+            path.push(from_user_str(s2));
+            Ok(Value::Path(path))
+        }
+        _ => Err(OperatorError::untyped("Operands must be numbers or paths")),
+    }
+}
+
+fn int_div(a: &Value, b: &Value) -> OperatorResult {
+    // Integer в паре с Number (и целый ноль в делителе) приводится к Number
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return int_div(&a, &b);
+    }
+    if let (Value::Integer(i1), Value::Integer(0)) = (a, b) {
+        return int_div(&Value::Number(*i1 as f64), &Value::Number(0.0));
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => {
+            // Деление с округлением вниз, как и для Number
+            Ok(match i1.checked_div(*i2) {
+                Some(q) if q * i2 != *i1 && ((*i1 < 0) != (*i2 < 0)) => Value::Integer(q - 1),
+                Some(q) => Value::Integer(q),
+                None => Value::Number((*i1 as f64 / *i2 as f64).floor()),
+            })
+        }
+        (Value::Number(_), Value::Number(n2)) if *n2 == 0.0 => Err(OperatorError::untyped("Division by zero")),
+        // Целочисленное деление: отбрасываем дробную часть
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number((n1 / n2).floor())),
+        _ => Err(OperatorError::untyped("Operands must be numbers")),
+    }
+}
+
+fn modulo(a: &Value, b: &Value) -> OperatorResult {
+    // Integer в паре с Number (и целый ноль в делителе) приводится к Number
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return modulo(&a, &b);
+    }
+    if let (Value::Integer(i1), Value::Integer(0)) = (a, b) {
+        return modulo(&Value::Number(*i1 as f64), &Value::Number(0.0));
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => Ok(i1.checked_rem(*i2).map_or(Value::Integer(0), Value::Integer)),
+        (Value::Number(_), Value::Number(n2)) if *n2 == 0.0 => Err(OperatorError::untyped("Modulo by zero")),
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 % n2)),
+        _ => Err(OperatorError::untyped("Operands must be numbers")),
+    }
+}
+
+fn pow(a: &Value, b: &Value) -> OperatorResult {
+    // Integer в паре с Number (и отрицательная целая степень) приводится к Number
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return pow(&a, &b);
+    }
+    if let (Value::Integer(i1), Value::Integer(i2)) = (a, b) {
+        if *i2 < 0 {
+            return pow(&Value::Number(*i1 as f64), &Value::Number(*i2 as f64));
+        }
+    }
+    match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => {
+            let result = u32::try_from(*i2).ok().and_then(|exp| i1.checked_pow(exp));
+            Ok(match result {
+                Some(power) => Value::Integer(power),
+                None => Value::Number((*i1 as f64).powf(*i2 as f64)),
+            })
+        }
+        // 0 ** отрицательная степень = деление на ноль
+        (Value::Number(n1), Value::Number(n2)) if *n1 == 0.0 && *n2 < 0.0 => {
+            Err(OperatorError::untyped("Zero cannot be raised to a negative power"))
+        }
+        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1.powf(*n2))),
+        _ => Err(OperatorError::untyped("Operands must be numbers")),
+    }
+}
+
+/// <, >, <=, >=: test получает порядок a относительно b
+fn compare(a: &Value, b: &Value, test: fn(Ordering) -> bool) -> OperatorResult {
+    if let Some(result) = currency::compare(a, b) {
+        return result.map(|ordering| Value::Bool(test(ordering))).map_err(OperatorError::from);
+    }
+    // Integer в паре с Number приводится к Number
+    if let Some((a, b)) = promote_mixed_numbers(a, b) {
+        return compare(&a, &b, test);
+    }
+    let ordering = match (a, b) {
+        (Value::Integer(i1), Value::Integer(i2)) => i1.partial_cmp(i2),
+        (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2),
+        (Value::String(s1), Value::String(s2)) => s1.partial_cmp(s2),
+        _ => return Err(OperatorError::untyped(comparison_operands_error(a, b))),
+    };
+    // NaN не больше, не меньше и не равно ни одному числу
+    Ok(Value::Bool(ordering.is_some_and(test)))
+}
+
+/// &, |, ^, <<, >> над целыми числами. Number допускается только с целым значением;
+/// результат - Integer, если оба операнда Integer, иначе Number
+fn bitwise(op: BinaryOp, a: &Value, b: &Value) -> OperatorResult {
+    let symbol = op.symbol();
+    let integer = |value: &Value| match value {
+        Value::Integer(i) => Ok(*i),
+        Value::Number(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => Ok(*n as i64),
+        Value::Number(n) if n.is_finite() && n.fract() == 0.0 => Err(OperatorError::typed(
+            format!("Operator '{}' operand {} is out of the integer range", symbol, n),
+            ErrorType::OverflowError,
+        )),
+        Value::Number(n) => Err(OperatorError::typed(
+            format!("Operator '{}' requires integers, got {}", symbol, n),
+            ErrorType::ValueError,
+        )),
+        other => Err(OperatorError::typed(
+            format!(
+                "Operator '{}' requires integers, got {}",
+                symbol,
+                crate::vm::natives::native_typeof(std::slice::from_ref(other)).to_string()
+            ),
+            ErrorType::TypeError,
+        )),
+    };
+    let (x, y) = (integer(a)?, integer(b)?);
+    let result = match op {
+        BinaryOp::BitAnd => x & y,
+        BinaryOp::BitOr => x | y,
+        BinaryOp::BitXor => x ^ y,
+        _ => {
+            if !(0..64).contains(&y) {
+                return Err(OperatorError::typed(
+                    format!("Shift amount must be between 0 and 63, got {}", y),
+                    ErrorType::ValueError,
+                ));
+            }
+            if op == BinaryOp::ShiftRight {
+                // Арифметический сдвиг: -8 >> 1 = -4, как x // 2 ** y
+                x >> y
+            } else {
+                match x.checked_mul(1i64 << y) {
+                    Some(shifted) => shifted,
+                    None => {
+                        return Err(OperatorError::typed(
+                            format!("Integer overflow in {} << {}", x, y),
+                            ErrorType::OverflowError,
+                        ));
+                    }
+                }
+            }
+        }
+    };
+    Ok(if matches!((a, b), (Value::Integer(_), Value::Integer(_))) {
+        Value::Integer(result)
+    } else {
+        Value::Number(result as f64)
+    })
+}
+
+/// Приводит пару Integer/Number к Number: дробный операнд делает дробным и результат
+fn promote_mixed_numbers(a: &Value, b: &Value) -> Option<(Value, Value)> {
+    match (a, b) {
+        (Value::Integer(i), Value::Number(n)) => Some((Value::Number(*i as f64), Value::Number(*n))),
+        (Value::Number(n), Value::Integer(i)) => Some((Value::Number(*n), Value::Number(*i as f64))),
+        _ => None,
+    }
+}
+
+/// Сообщение об ошибке <, >, <=, >= для несравнимых операндов. Строка рядом с числом
+/// обычно означает, что + связал раньше сравнения: 'total: ' + n > 10 = ('total: ' + n) > 10
+fn comparison_operands_error(a: &Value, b: &Value) -> String {
+    let is_number = |v: &Value| matches!(v, Value::Number(_) | Value::Integer(_));
+    let string_and_number = (matches!(a, Value::String(_)) && is_number(b))
+        || (is_number(a) && matches!(b, Value::String(_)));
+    if string_and_number {
+        format!(
+            "Operands must be numbers or strings, cannot compare a string with a number. \
+             Note that + binds tighter than comparisons: 'total: ' + n > 10 means ('total: ' + n) > 10. \
+             Operator precedence from highest: {}",
+            crate::parser::OPERATOR_PRECEDENCE
+        )
+    } else {
+        "Operands must be numbers or strings".to_string()
+    }
+}
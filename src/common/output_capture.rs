@@ -1,3 +1,5 @@
+// Перехват вывода print() и progress() в буфер потока: ответы WebSocket сервера и Session::exec
+
 use std::cell::RefCell;
 use std::io::Write;
 
@@ -53,5 +55,12 @@ impl OutputCapture {
             buf.borrow().is_some()
         })
     }
+
+    /// Идет ли вывод в терминал: при перехвате или перенаправленном stdout - нет.
+    /// По этому признаку progress() выбирает обновляемую полосу или редкие обычные строки
+    pub fn is_terminal() -> bool {
+        use std::io::IsTerminal;
+        !Self::is_capturing() && std::io::stdout().is_terminal()
+    }
 }
 
@@ -0,0 +1,120 @@
+// Платформенно-независимая работа со значениями Path
+//
+// На Windows пользователь может писать пути и через '/', и через '\', а std::path
+// при этом оставляет смешанные разделители в строке. Все строки, превращаемые в Path,
+// проходят через from_user_str, а сравнение и вывод - через normalize_separators,
+// поэтому "C:/data/a.csv" и "C:\data\a.csv" - один и тот же путь.
+// SMB пути (lib://share/... и smb://share/...) не меняются: у них всегда '/'.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Префикс путей на SMB шарах
+pub const SMB_PREFIX: &str = "lib://";
+/// Другая запись того же пути: smb://share/path = lib://share/path
+pub const SMB_URL_PREFIX: &str = "smb://";
+
+fn is_smb(path: &str) -> bool {
+    path.starts_with(SMB_PREFIX) || path.starts_with(SMB_URL_PREFIX)
+}
+
+/// Имя шары и путь на ней для lib://share/dir/file и smb://share/dir/file; None для обычных путей
+pub fn split_smb_path(path: &str) -> Option<(&str, &str)> {
+    let rest = path.strip_prefix(SMB_PREFIX).or_else(|| path.strip_prefix(SMB_URL_PREFIX))?;
+    Some(rest.split_once('/').unwrap_or((rest, "")))
+}
+
+/// smb://share/path как lib://share/path; остальные пути не меняются
+pub fn smb_url_to_lib(path: &Path) -> PathBuf {
+    match path.to_string_lossy().strip_prefix(SMB_URL_PREFIX) {
+        Some(rest) => PathBuf::from(format!("{}{}", SMB_PREFIX, rest)),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Приводит разделители к платформенному виду (на Windows '/' -> '\')
+pub fn normalize_str(path: &str) -> String {
+    if cfg!(windows) && !is_smb(path) {
+        path.replace('/', "\\")
+    } else {
+        path.to_string()
+    }
+}
+
+/// Путь из строки пользователя: на Windows принимаются оба разделителя
+pub fn from_user_str(path: &str) -> PathBuf {
+    PathBuf::from(normalize_str(path))
+}
+
+/// Тот же путь с платформенными разделителями
+pub fn normalize_separators(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        from_user_str(&path.to_string_lossy())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Строковое представление пути с платформенными разделителями
+pub fn display(path: &Path) -> String {
+    normalize_str(&path.to_string_lossy())
+}
+
+/// Сравнение путей без учета вида разделителей
+pub fn paths_equal(a: &Path, b: &Path) -> bool {
+    a == b || normalize_separators(a) == normalize_separators(b)
+}
+
+/// Убирает '.' и схлопывает '..' без обращения к файловой системе.
+/// '..' в начале относительного пути сохраняется, выше корня подняться нельзя
+pub fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in normalize_separators(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                match result.components().next_back() {
+                    Some(Component::Normal(_)) => {
+                        result.pop();
+                    }
+                    Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                    _ => result.push(".."),
+                }
+            }
+            other => result.push(other.as_os_str()),
+        }
+    }
+    result
+}
+
+/// Абсолютный канонический путь, даже если файла еще нет: канонизируется ближайший
+/// существующий предок, остаток пути присоединяется после нормализации '..' и '.'
+pub fn canonicalize_lenient(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        normalize_separators(path)
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(normalize_separators(path)),
+            Err(_) => normalize_separators(path),
+        }
+    };
+    let absolute = lexical_normalize(&absolute);
+
+    let mut existing = absolute.as_path();
+    let mut rest: Vec<&std::ffi::OsStr> = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut result = canonical;
+            for part in rest.iter().rev() {
+                result.push(part);
+            }
+            return result;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return absolute,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// Генератор случайных чисел для встроенных функций
+//
+// Все функции со случайностью получают генератор отсюда, чтобы одинаковый seed
+// давал одинаковый результат во всех частях языка.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Генератор с фиксированным seed (воспроизводимый) или инициализированный из энтропии ОС
+pub fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
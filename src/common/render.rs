@@ -0,0 +1,242 @@
+// Текстовый вывод таблиц: рамка Unicode, markdown для вставки в issues и CSV.
+// Общий для show_table(), вывода таблиц в REPL, write_file() и предпросмотра WebSocket сервера
+
+use crate::common::table::Table;
+use crate::common::value::Value;
+
+/// Максимальная длина массива или объекта в ячейке рамки и markdown; длиннее - обрезается с "..."
+pub const NESTED_PREVIEW_CHARS: usize = 40;
+
+/// Строки по умолчанию: больше - выводятся начало и конец таблицы
+pub const DEFAULT_MAX_ROWS: usize = 20;
+
+/// Формат вывода таблицы
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderFormat {
+    Grid,
+    Markdown,
+    Csv,
+}
+
+impl RenderFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "grid" => Some(RenderFormat::Grid),
+            "markdown" => Some(RenderFormat::Markdown),
+            "csv" => Some(RenderFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub format: RenderFormat,
+    /// Позиции выводимых колонок в нужном порядке (None - все колонки)
+    pub columns: Option<Vec<usize>>,
+    /// Максимальная ширина ячейки в символах: длиннее - обрезается с "…" (рамка и markdown, кроме чисел)
+    pub max_col_width: Option<usize>,
+    /// Максимум строк (None - все). В рамке и markdown выводятся начало и конец таблицы со строкой
+    /// "…" между ними, в CSV - первые max_rows строк, чтобы вывод оставался корректным CSV
+    pub max_rows: Option<usize>,
+    /// Колонка "#" с номерами строк (с 0, как в t.rows[i])
+    pub index: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            format: RenderFormat::Grid,
+            columns: None,
+            max_col_width: None,
+            max_rows: Some(DEFAULT_MAX_ROWS),
+            index: false,
+        }
+    }
+}
+
+/// Таблица в виде текста; каждая строка вывода завершается '\n'
+pub fn render_table(table: &Table, options: &RenderOptions) -> String {
+    let columns: Vec<usize> = match &options.columns {
+        Some(columns) => columns.clone(),
+        None => (0..table.headers.len()).collect(),
+    };
+    // Выводимые строки: номер строки или None для строки-разделителя "…"
+    let total = table.rows.len();
+    let shown: Vec<Option<usize>> = match options.max_rows {
+        Some(limit) if total > limit && options.format == RenderFormat::Csv => (0..limit).map(Some).collect(),
+        Some(limit) if total > limit => {
+            let (head, tail) = (limit.div_ceil(2), limit / 2);
+            (0..head).map(Some).chain(std::iter::once(None)).chain((total - tail..total).map(Some)).collect()
+        }
+        _ => (0..total).map(Some).collect(),
+    };
+    match options.format {
+        RenderFormat::Csv => render_csv(table, &columns, &shown, options.index),
+        RenderFormat::Grid | RenderFormat::Markdown => render_text(table, &columns, &shown, options),
+    }
+}
+
+/// Вся таблица в CSV (write_file)
+pub fn table_to_csv(table: &Table) -> String {
+    render_table(table, &RenderOptions { format: RenderFormat::Csv, max_rows: None, ..RenderOptions::default() })
+}
+
+/// Число без ".0" у целых значений, в том числе больших: 1e20 выводится всеми цифрами
+fn number_text(n: f64) -> String {
+    if n == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// Числа выравниваются по правому краю
+fn is_numeric_cell(value: &Value) -> bool {
+    matches!(value, Value::Number(_) | Value::Integer(_) | Value::Currency { .. })
+}
+
+/// Строка заголовков и строки CSV через запятую, кавычки по RFC 4180.
+/// null - пустое поле, массивы и объекты (например, из table_collect) - JSON
+fn render_csv(table: &Table, columns: &[usize], shown: &[Option<usize>], index: bool) -> String {
+    let field = |value: &Value| match value {
+        Value::Null => String::new(),
+        Value::Number(n) => number_text(*n),
+        Value::Array(_) | Value::Object(_) => value.to_json().to_string(),
+        other => other.to_string(),
+    };
+    let quote = |text: String| {
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text
+        }
+    };
+    let mut csv = String::new();
+    let mut header: Vec<String> = columns.iter().map(|&c| quote(table.headers[c].clone())).collect();
+    if index {
+        header.insert(0, "#".to_string());
+    }
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+    for &row_index in shown.iter().flatten() {
+        let row = &table.rows[row_index];
+        let mut fields: Vec<String> = columns.iter().map(|&c| quote(row.get(c).map(field).unwrap_or_default())).collect();
+        if index {
+            fields.insert(0, row_index.to_string());
+        }
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Текст ячейки рамки и markdown: одна строка, обрезанная до max_col_width символов.
+/// Числа не обрезаются - обрезанное число читалось бы как другое значение
+fn display_cell(value: &Value, options: &RenderOptions) -> String {
+    let text = match value {
+        Value::Number(n) => return number_text(*n),
+        Value::Integer(_) | Value::Currency { .. } => return value.to_string(),
+        Value::Array(_) | Value::Object(_) => value.preview(NESTED_PREVIEW_CHARS),
+        other => other.to_string(),
+    };
+    let text = truncate(text.replace("\r\n", "\n"), options.max_col_width);
+    if options.format == RenderFormat::Markdown {
+        text.replace('|', "\\|").replace('\n', "<br>")
+    } else {
+        text.replace('\n', " ")
+    }
+}
+
+fn truncate(text: String, max_width: Option<usize>) -> String {
+    match max_width {
+        Some(width) if text.chars().count() > width => {
+            let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+            cut.push('…');
+            cut
+        }
+        _ => text,
+    }
+}
+
+/// Колонка вывода: заголовок, ячейки выводимых строк и выравнивание
+struct TextColumn {
+    header: String,
+    cells: Vec<String>,
+    right: bool,
+    width: usize,
+}
+
+impl TextColumn {
+    fn new(header: String, cells: Vec<String>, right: bool) -> Self {
+        let width = cells.iter().chain(std::iter::once(&header)).map(|s| s.chars().count()).max().unwrap_or(0).max(3);
+        Self { header, cells, right, width }
+    }
+
+    fn pad(&self, text: &str) -> String {
+        let fill = " ".repeat(self.width - text.chars().count());
+        if self.right {
+            format!("{}{}", fill, text)
+        } else {
+            format!("{}{}", text, fill)
+        }
+    }
+}
+
+fn render_text(table: &Table, columns: &[usize], shown: &[Option<usize>], options: &RenderOptions) -> String {
+    let total = table.rows.len();
+    if options.format == RenderFormat::Grid && total == 0 {
+        return "Empty table\n".to_string();
+    }
+    let mut text_columns = Vec::with_capacity(columns.len() + 1);
+    if options.index {
+        let cells = shown.iter().map(|row| row.map_or("…".to_string(), |i| i.to_string())).collect();
+        text_columns.push(TextColumn::new("#".to_string(), cells, true));
+    }
+    for &column in columns {
+        let values: Vec<Option<&Value>> = shown.iter().map(|row| row.map(|i| table.rows[i].get(column).unwrap_or(&Value::Null))).collect();
+        // Колонка числовая, если все выводимые значения, кроме null, - числа
+        let present: Vec<&Value> = values.iter().flatten().copied().filter(|v| !matches!(v, Value::Null)).collect();
+        let right = !present.is_empty() && present.iter().all(|v| is_numeric_cell(v));
+        let cells = values.iter().map(|value| value.map_or("…".to_string(), |v| display_cell(v, options))).collect();
+        let header = truncate(table.headers[column].clone(), options.max_col_width);
+        let header = if options.format == RenderFormat::Markdown { header.replace('|', "\\|") } else { header };
+        text_columns.push(TextColumn::new(header, cells, right));
+    }
+
+    let mut out = String::new();
+    let line = |out: &mut String, parts: Vec<String>, (left, sep, right): (&str, &str, &str)| {
+        out.push_str(left);
+        out.push_str(&parts.join(sep));
+        out.push_str(right);
+        out.push('\n');
+    };
+    let row_parts = |cells: &dyn Fn(&TextColumn) -> String| -> Vec<String> {
+        text_columns.iter().map(|column| format!(" {} ", column.pad(&cells(column)))).collect()
+    };
+    if options.format == RenderFormat::Grid {
+        let rule: Vec<String> = text_columns.iter().map(|c| "─".repeat(c.width + 2)).collect();
+        line(&mut out, rule.clone(), ("┌", "┬", "┐"));
+        line(&mut out, row_parts(&|c| c.header.clone()), ("│", "│", "│"));
+        line(&mut out, rule.clone(), ("├", "┼", "┤"));
+        for i in 0..shown.len() {
+            line(&mut out, row_parts(&|c| c.cells[i].clone()), ("│", "│", "│"));
+        }
+        line(&mut out, rule, ("└", "┴", "┘"));
+        let hidden = total - shown.iter().flatten().count();
+        if hidden > 0 {
+            out.push_str(&format!("{} rows ({} hidden)\n", total, hidden));
+        }
+    } else {
+        line(&mut out, row_parts(&|c| c.header.clone()), ("|", "|", "|"));
+        let rule: Vec<String> = text_columns
+            .iter()
+            .map(|c| if c.right { format!(" {}: ", "-".repeat(c.width - 1)) } else { format!(" {} ", "-".repeat(c.width)) })
+            .collect();
+        line(&mut out, rule, ("|", "|", "|"));
+        for i in 0..shown.len() {
+            line(&mut out, row_parts(&|c| c.cells[i].clone()), ("|", "|", "|"));
+        }
+    }
+    out
+}
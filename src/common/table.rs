@@ -9,6 +9,10 @@ pub struct Table {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<Value>>,
     pub name: Option<String>,
+    /// Счетчик изменений на месте: растет при каждом изменяемом доступе из встроенных функций
+    /// и в rename_columns, drop_columns, set_column. Ключ кэша @cache для большой таблицы
+    /// включает его, поэтому измененная таблица не найдет старый результат
+    pub generation: u64,
 }
 
 impl Table {
@@ -18,6 +22,7 @@ impl Table {
             headers: Vec::new(),
             rows: Vec::new(),
             name: None,
+            generation: 0,
         }
     }
 
@@ -87,36 +92,126 @@ impl Table {
     pub fn get_row(&self, index: usize) -> Option<&Vec<Value>> {
         self.rows.get(index)
     }
-}
 
-impl PartialEq for Table {
-    fn eq(&self, other: &Self) -> bool {
-        // Сравниваем заголовки
-        if self.headers != other.headers {
-            return false;
+    /// Переименовывает колонки на месте. Все старые имена должны существовать,
+    /// а итоговые имена колонок - оставаться уникальными
+    pub fn rename_columns(&mut self, renames: &[(String, String)]) -> Result<(), String> {
+        let unknown: Vec<&str> = renames
+            .iter()
+            .filter(|(old, _)| !self.headers.contains(old))
+            .map(|(old, _)| old.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!("Unknown column(s): {}", unknown.join(", ")));
         }
 
-        // Сравниваем количество строк
-        if self.rows.len() != other.rows.len() {
-            return false;
+        let new_headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|header| {
+                renames
+                    .iter()
+                    .rev()
+                    .find(|(old, _)| old == header)
+                    .map(|(_, new)| new.clone())
+                    .unwrap_or_else(|| header.clone())
+            })
+            .collect();
+
+        let mut seen = std::collections::HashSet::new();
+        let mut duplicates = Vec::new();
+        for header in &new_headers {
+            if !seen.insert(header.as_str()) && !duplicates.contains(&header.as_str()) {
+                duplicates.push(header.as_str());
+            }
+        }
+        if !duplicates.is_empty() {
+            return Err(format!("Duplicate column name(s) after rename: {}", duplicates.join(", ")));
         }
 
-        // Сравниваем данные по колонкам
-        for header in &self.headers {
-            let self_col = self.columns.get(header);
-            let other_col = other.columns.get(header);
-            
-            match (self_col, other_col) {
-                (Some(a), Some(b)) => {
-                    if a != b {
-                        return false;
-                    }
-                }
-                _ => return false,
+        // Колонки переносятся под новыми именами, порядок строк не меняется
+        let mut new_columns = HashMap::with_capacity(new_headers.len());
+        for (old, new) in self.headers.iter().zip(&new_headers) {
+            let column = self.columns.remove(old).unwrap_or_default();
+            new_columns.insert(new.clone(), column);
+        }
+        self.columns = new_columns;
+        self.headers = new_headers;
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Удаляет колонки на месте. Отсутствующие имена игнорируются,
+    /// возвращаются имена реально удаленных колонок
+    pub fn drop_columns(&mut self, names: &[String]) -> Vec<String> {
+        let keep: Vec<bool> = self.headers.iter().map(|h| !names.contains(h)).collect();
+        let dropped: Vec<String> = self
+            .headers
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| !**keep)
+            .map(|(h, _)| h.clone())
+            .collect();
+        if dropped.is_empty() {
+            return dropped;
+        }
+
+        for name in &dropped {
+            self.columns.remove(name);
+        }
+        self.headers.retain(|h| !dropped.contains(h));
+        for row in &mut self.rows {
+            let mut index = 0;
+            row.retain(|_| {
+                let kept = keep.get(index).copied().unwrap_or(true);
+                index += 1;
+                kept
+            });
+        }
+        self.generation += 1;
+        dropped
+    }
+
+    /// Заменяет значения колонки на месте (в строках и в кэше колонок).
+    /// values должен содержать по значению на каждую строку
+    pub fn set_column(&mut self, name: &str, values: Vec<Value>) {
+        let Some(index) = self.headers.iter().position(|h| h == name) else {
+            return;
+        };
+        for (row, value) in self.rows.iter_mut().zip(&values) {
+            if row.len() <= index {
+                row.resize(index + 1, Value::Null);
             }
+            row[index] = value.clone();
         }
+        self.columns.insert(name.to_string(), values);
+        self.generation += 1;
+    }
+}
 
-        true
+impl PartialEq for Table {
+    fn eq(&self, other: &Self) -> bool {
+        // Заголовки с учетом порядка, количество строк и ячейки колонок
+        crate::common::value::tables_equal(self, other, Default::default(), 0) == Some(true)
     }
 }
 
+/// Порядок значений ячеек для сортировки и фильтров: числа по величине, Null меньше остальных
+pub fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
+    match (a, b) {
+        (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2).unwrap_or(std::cmp::Ordering::Equal),
+        (Value::Integer(i1), Value::Integer(i2)) => i1.cmp(i2),
+        (a, b) if a.is_numeric() && b.is_numeric() => {
+            a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(std::cmp::Ordering::Equal)
+        }
+        (Value::Currency { amount: a1, .. }, Value::Currency { amount: a2, .. }) => {
+            a1.partial_cmp(a2).unwrap_or(std::cmp::Ordering::Equal)
+        }
+        (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
+        (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
+        (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
+        (Value::Null, _) => std::cmp::Ordering::Less,
+        (_, Value::Null) => std::cmp::Ordering::Greater,
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
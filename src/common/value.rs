@@ -2,22 +2,40 @@
 
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::collections::HashSet;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::hash::{Hash, Hasher};
 use crate::common::table::Table;
+use crate::common::lazy::LazyTable;
+
+/// Ключи объекта в порядке добавления: вывод, итерация и JSON не переставляют поля
+pub type ObjectMap = IndexMap<String, Value>;
+
+/// Вложенная функция, захватившая переменные объемлющей функции. Значения копируются при
+/// создании замыкания, поэтому вызов не зависит от того, какой фрейм его выполняет
+#[derive(Debug)]
+pub struct Closure {
+    pub function: usize,      // Индекс функции в массиве функций
+    pub captured: Vec<Value>, // Значения в порядке Function::captured_vars
+    pub frame: u64,           // Идентификатор фрейма объемлющей функции, в котором создано замыкание
+}
 
 #[derive(Debug)]
 pub enum Value {
     Number(f64),
+    Integer(i64), // Целое число без потери точности (из CSV/JSON и целочисленной арифметики)
     Bool(bool),
-    String(String),
+    String(Rc<str>), // Разделяемая строка: клонирование значения - увеличение счетчика ссылок
     Array(Rc<RefCell<Vec<Value>>>),
     Function(usize), // Индекс функции в массиве функций
+    Closure(Rc<Closure>), // Вложенная функция со значениями захваченных переменных
     NativeFunction(usize), // Индекс нативной функции
     Path(PathBuf), // Путь к файлу или директории
     Table(Rc<RefCell<Table>>),
-    Object(HashMap<String, Value>), // Словарь/объект: ключ-значение
+    LazyTable(Rc<LazyTable>), // Отложенная цепочка операций над таблицей (table_lazy)
+    Object(ObjectMap), // Словарь/объект: ключ-значение в порядке добавления
+    Currency { amount: f64, symbol: Rc<str> }, // Денежная сумма с символом валюты (parse_currency)
     ColumnReference {
         table: Rc<RefCell<Table>>,
         column_name: String,
@@ -29,14 +47,19 @@ impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Integer(a), Value::Number(b)) | (Value::Number(b), Value::Integer(a)) => integer_equals_number(*a, *b),
             (Value::Bool(a), Value::Bool(b)) => a == b,
-            (Value::String(a), Value::String(b)) => a == b,
-            (Value::Array(a), Value::Array(b)) => *a.borrow() == *b.borrow(),
+            (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::Array(_), Value::Array(_)) | (Value::Table(_), Value::Table(_)) | (Value::Object(_), Value::Object(_)) => {
+                self.deep_equal(other, EqualityOptions::default()) == Some(true)
+            }
             (Value::Function(a), Value::Function(b)) => a == b,
+            (Value::Closure(a), Value::Closure(b)) => Rc::ptr_eq(a, b),
             (Value::NativeFunction(a), Value::NativeFunction(b)) => a == b,
-            (Value::Path(a), Value::Path(b)) => a == b,
-            (Value::Table(a), Value::Table(b)) => *a.borrow() == *b.borrow(),
-            (Value::Object(a), Value::Object(b)) => a == b,
+            (Value::Path(a), Value::Path(b)) => crate::common::path::paths_equal(a, b),
+            (Value::LazyTable(a), Value::LazyTable(b)) => Rc::ptr_eq(a, b),
+            (Value::Currency { amount: a, symbol: sa }, Value::Currency { amount: b, symbol: sb }) => a == b && sa == sb,
             (Value::ColumnReference { table: a, column_name: col_a }, Value::ColumnReference { table: b, column_name: col_b }) => {
                 Rc::ptr_eq(a, b) && col_a == col_b
             },
@@ -46,23 +69,156 @@ impl PartialEq for Value {
     }
 }
 
+/// Точное сравнение: выше 2^53 `i as f64` округляется, и разные числа оказались бы равны
+fn integer_equals_number(i: i64, n: f64) -> bool {
+    // -2^63 представимо точно, 2^63 уже вне диапазона i64
+    n.fract() == 0.0 && n >= i64::MIN as f64 && n < -(i64::MIN as f64) && n as i64 == i
+}
+
+fn numbers_equal(a: f64, b: f64, options: EqualityOptions) -> bool {
+    a == b || (options.nan_equal && a.is_nan() && b.is_nan())
+}
+
+fn deep_equal_at(a: &Value, b: &Value, options: EqualityOptions, depth: usize) -> Option<bool> {
+    if depth > MAX_EQUALITY_DEPTH {
+        return None;
+    }
+    let result = match (a, b) {
+        (Value::Number(x), Value::Number(y)) => numbers_equal(*x, *y, options),
+        (Value::Integer(x), Value::Number(y)) | (Value::Number(y), Value::Integer(x)) => integer_equals_number(*x, *y),
+        (Value::Currency { amount: x, symbol: sx }, Value::Currency { amount: y, symbol: sy }) => {
+            sx == sy && numbers_equal(*x, *y, options)
+        }
+        (Value::Array(x), Value::Array(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return Some(true);
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            if x.len() != y.len() {
+                return Some(false);
+            }
+            for (x, y) in x.iter().zip(y.iter()) {
+                if !deep_equal_at(x, y, options, depth + 1)? {
+                    return Some(false);
+                }
+            }
+            true
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            if x.len() != y.len() {
+                return Some(false);
+            }
+            for (key, x) in x {
+                match y.get(key) {
+                    Some(y) if deep_equal_at(x, y, options, depth + 1)? => {}
+                    _ => return Some(false),
+                }
+            }
+            true
+        }
+        (Value::Table(x), Value::Table(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return Some(true);
+            }
+            return tables_equal(&x.borrow(), &y.borrow(), options, depth);
+        }
+        _ => a == b,
+    };
+    Some(result)
+}
+
+/// Таблицы равны, если совпадают колонки (с учетом порядка, если не ignore_column_order),
+/// число строк и значения ячеек. Имя таблицы не сравнивается
+pub(crate) fn tables_equal(a: &Table, b: &Table, options: EqualityOptions, depth: usize) -> Option<bool> {
+    let same_columns = if options.ignore_column_order {
+        a.headers.len() == b.headers.len() && a.headers.iter().all(|h| b.headers.contains(h))
+    } else {
+        a.headers == b.headers
+    };
+    if !same_columns || a.rows.len() != b.rows.len() {
+        return Some(false);
+    }
+    for header in &a.headers {
+        match (a.columns.get(header), b.columns.get(header)) {
+            (Some(x), Some(y)) if x.len() == y.len() => {
+                for (x, y) in x.iter().zip(y.iter()) {
+                    if !deep_equal_at(x, y, options, depth + 1)? {
+                        return Some(false);
+                    }
+                }
+            }
+            _ => return Some(false),
+        }
+    }
+    Some(true)
+}
+
+/// Максимальная глубина вложенности массивов, объектов и таблиц при структурном сравнении
+pub const MAX_EQUALITY_DEPTH: usize = 256;
+
+/// Настройки структурного сравнения (Value::deep_equal)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EqualityOptions {
+    /// NaN равен NaN (для ==, как в IEEE 754, не равен)
+    pub nan_equal: bool,
+    /// Таблицы с одинаковым набором колонок равны при любом порядке колонок
+    pub ignore_column_order: bool,
+}
+
 impl Value {
+    /// Структурное сравнение: массивы поэлементно, объекты по набору ключей и значениям,
+    /// таблицы по колонкам и ячейкам. Один и тот же массив/таблица равен себе без обхода.
+    /// None - вложенность глубже MAX_EQUALITY_DEPTH (например, массив, содержащий себя)
+    pub fn deep_equal(&self, other: &Value, options: EqualityOptions) -> Option<bool> {
+        deep_equal_at(self, other, options, 0)
+    }
+
     /// Проверяет, можно ли использовать это значение как ключ кэша
-    /// (только простые типы: Number, Bool, String, Null)
+    /// (только простые типы: Number, Integer, Bool, String, Null)
     pub fn is_hashable(&self) -> bool {
-        matches!(self, Value::Number(_) | Value::Bool(_) | Value::String(_) | Value::Null)
+        matches!(self, Value::Number(_) | Value::Integer(_) | Value::Bool(_) | Value::String(_) | Value::Null)
     }
 
+    /// Числовое значение как f64 (для Number и Integer)
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Является ли значение числом (Number или Integer)
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Value::Number(_) | Value::Integer(_))
+    }
+
+    /// Индекс пользовательской функции (Function или Closure)
+    pub fn function_index(&self) -> Option<usize> {
+        match self {
+            Value::Function(index) => Some(*index),
+            Value::Closure(closure) => Some(closure.function),
+            _ => None,
+        }
+    }
+
+    /// Истинность значения - единственное определение для if/while/for-условий, `?:`, `not`,
+    /// `and`/`or`, bool(), to_bool(), предикатов table_drop_rows/table_filter_expr, assert()
+    /// и свертки констант в компиляторе.
+    /// Ложны null, false, 0, пустые строка/массив/объект/таблица/колонка, пустой путь и
+    /// денежная сумма 0. Путь не проверяется на существование файла
     pub fn is_truthy(&self) -> bool {
         match self {
             Value::Null => false,
             Value::Bool(false) => false,
             Value::Number(n) => *n != 0.0,
+            Value::Integer(i) => *i != 0,
             Value::String(s) => !s.is_empty(),  // Пустая строка = false
             Value::Array(arr) => !arr.borrow().is_empty(),
             Value::Path(p) => !p.as_os_str().is_empty(),  // Путь не пустой = true
             Value::Table(table) => table.borrow().len() > 0,  // Таблица не пустая = true
             Value::Object(map) => !map.is_empty(),  // Объект не пустой = true
+            Value::Currency { amount, .. } => *amount != 0.0,
             Value::ColumnReference { table, column_name } => {
                 let table_ref = table.borrow();
                 if let Some(column) = table_ref.get_column(column_name) {
@@ -84,14 +240,15 @@ impl Value {
                     format!("{}", n)
                 }
             }
+            Value::Integer(i) => format!("{}", i),
             Value::Bool(b) => format!("{}", b),
-            Value::String(s) => s.clone(),
+            Value::String(s) => s.to_string(),
             Value::Array(arr) => {
                 let arr_ref = arr.borrow();
                 let elements: Vec<String> = arr_ref.iter().map(|v| v.to_string()).collect();
                 format!("[{}]", elements.join(", "))
             }
-            Value::Function(_) => "<function>".to_string(),
+            Value::Function(_) | Value::Closure(_) => "<function>".to_string(),
             Value::NativeFunction(_) => "<native function>".to_string(),
             Value::Path(p) => {
                 // В режиме --use-ve показываем относительные пути
@@ -119,21 +276,24 @@ impl Value {
                             }
                         } else {
                             // Путь вне сессии - возвращаем как есть (не канонизированный для сохранения оригинального формата)
-                            p.to_string_lossy().to_string()
+                            crate::common::path::display(p)
                         }
                     } else {
                         // Нет пути сессии - возвращаем как есть
-                        p.to_string_lossy().to_string()
+                        crate::common::path::display(p)
                     }
                 } else {
-                    // Не режим --use-ve - возвращаем полный путь
-                    p.to_string_lossy().to_string()
+                    // Не режим --use-ve - возвращаем полный путь с платформенными разделителями
+                    crate::common::path::display(p)
                 }
             },
             Value::Table(table) => {
                 let t = table.borrow();
                 format!("<table: {} rows, {} columns>", t.len(), t.column_count())
             }
+            Value::LazyTable(lazy) => {
+                format!("<lazy table: {} operations, {} columns>", lazy.ops.len(), lazy.headers.len())
+            }
             Value::ColumnReference { table, column_name } => {
                 let t = table.borrow();
                 if let Some(column) = t.get_column(column_name) {
@@ -153,9 +313,124 @@ impl Value {
                     .collect();
                 format!("{{{}}}", pairs.join(", "))
             }
+            Value::Currency { amount, symbol } => crate::common::currency::format(*amount, symbol, 2),
             Value::Null => "null".to_string(),
         }
     }
+
+    /// Сериализация значения в JSON с сохранением типов (числа - числами, null - null)
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Number(n) => {
+                // Целые значения выводим без дробной части, как и в to_string()
+                if n.fract() == 0.0 && n.abs() < 9007199254740992.0 {
+                    serde_json::Value::from(*n as i64)
+                } else {
+                    serde_json::Number::from_f64(*n)
+                        .map(serde_json::Value::Number)
+                        .unwrap_or(serde_json::Value::Null) // NaN и бесконечности в JSON не представимы
+                }
+            }
+            Value::Integer(i) => serde_json::Value::from(*i),
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::String(s) => serde_json::Value::String(s.to_string()),
+            Value::Array(arr) => {
+                serde_json::Value::Array(arr.borrow().iter().map(|v| v.to_json()).collect())
+            }
+            Value::Object(map) => {
+                let object = map.iter().map(|(key, value)| (key.clone(), value.to_json())).collect();
+                serde_json::Value::Object(object)
+            }
+            Value::Table(table) => {
+                let t = table.borrow();
+                serde_json::Value::Array(table_rows_to_json(&t, 0, t.len()))
+            }
+            Value::LazyTable(lazy) => {
+                let (t, _) = lazy.collect();
+                serde_json::Value::Array(table_rows_to_json(&t, 0, t.len()))
+            }
+            Value::ColumnReference { table, column_name } => {
+                let t = table.borrow();
+                match t.get_column(column_name) {
+                    Some(column) => serde_json::Value::Array(column.iter().map(|v| v.to_json()).collect()),
+                    None => serde_json::Value::Null,
+                }
+            }
+            Value::Null => serde_json::Value::Null,
+            // Функции, пути и денежные суммы сериализуются строковым представлением
+            Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_) | Value::Path(_) | Value::Currency { .. } => {
+                serde_json::Value::String(self.to_string())
+            }
+        }
+    }
+
+    /// Короткое строковое представление значения (не длиннее max_chars символов)
+    pub fn preview(&self, max_chars: usize) -> String {
+        let full = self.to_string();
+        if full.chars().count() <= max_chars {
+            full
+        } else {
+            let truncated: String = full.chars().take(max_chars).collect();
+            format!("{}...", truncated)
+        }
+    }
+
+    /// Приблизительный объем памяти значения в байтах (учет памяти VM и memory_usage())
+    pub fn size_of_estimate(&self) -> usize {
+        self.size_of_estimate_with(&mut HashSet::new())
+    }
+
+    /// Оценка с общим множеством уже посчитанных массивов и таблиц (по адресу Rc):
+    /// разделяемые значения считаются один раз, циклические массивы не зацикливают обход
+    pub fn size_of_estimate_with(&self, seen: &mut HashSet<usize>) -> usize {
+        let base = std::mem::size_of::<Value>();
+        match self {
+            Value::String(s) => base + s.len(),
+            Value::Path(p) => base + p.as_os_str().len(),
+            Value::Currency { symbol, .. } => base + symbol.len(),
+            Value::Object(map) => {
+                base + map.iter()
+                    .map(|(key, value)| key.len() + value.size_of_estimate_with(seen))
+                    .sum::<usize>()
+            }
+            Value::Array(arr) => {
+                if !seen.insert(Rc::as_ptr(arr) as *const () as usize) {
+                    return base;
+                }
+                base + arr.borrow().iter().map(|item| item.size_of_estimate_with(seen)).sum::<usize>()
+            }
+            Value::Table(table) | Value::ColumnReference { table, .. } => {
+                if !seen.insert(Rc::as_ptr(table) as *const () as usize) {
+                    return base;
+                }
+                let table = table.borrow();
+                // Таблица хранит данные дважды: по строкам и по колонкам
+                let cells = table.rows.iter().flatten()
+                    .chain(table.columns.values().flatten())
+                    .map(|cell| cell.size_of_estimate_with(seen))
+                    .sum::<usize>();
+                let names = table.headers.iter().chain(table.columns.keys()).map(String::len).sum::<usize>();
+                base + cells + names
+            }
+            _ => base,
+        }
+    }
+}
+
+/// Сериализует строки таблицы [offset, offset + limit) в массив JSON объектов (колонка → значение)
+pub fn table_rows_to_json(table: &Table, offset: usize, limit: usize) -> Vec<serde_json::Value> {
+    table.rows.iter()
+        .skip(offset)
+        .take(limit)
+        .map(|row| {
+            let mut object = serde_json::Map::new();
+            for (i, header) in table.headers.iter().enumerate() {
+                let value = row.get(i).map(|v| v.to_json()).unwrap_or(serde_json::Value::Null);
+                object.insert(header.clone(), value);
+            }
+            serde_json::Value::Object(object)
+        })
+        .collect()
 }
 
 // Реализуем Hash только для простых типов
@@ -166,7 +441,13 @@ impl Hash for Value {
             Value::Number(n) => {
                 // Хешируем число как байты для точности
                 state.write_u8(0); // Тег для Number
-                state.write_u64(n.to_bits());
+                // -0.0 == 0.0, поэтому хешируем их одинаково
+                state.write_u64((n + 0.0).to_bits());
+            }
+            Value::Integer(i) => {
+                // Integer равен Number с тем же значением, поэтому хеш должен совпадать
+                state.write_u8(0);
+                state.write_u64((*i as f64).to_bits());
             }
             Value::Bool(b) => {
                 state.write_u8(1); // Тег для Bool
@@ -193,6 +474,7 @@ impl Clone for Value {
     fn clone(&self) -> Self {
         match self {
             Value::Number(n) => Value::Number(*n),
+            Value::Integer(i) => Value::Integer(*i),
             Value::Bool(b) => Value::Bool(*b),
             Value::String(s) => Value::String(s.clone()),
             Value::Array(arr) => {
@@ -202,12 +484,15 @@ impl Clone for Value {
                 Value::Array(Rc::new(RefCell::new(cloned_vec)))
             },
             Value::Function(idx) => Value::Function(*idx),
+            Value::Closure(closure) => Value::Closure(Rc::clone(closure)),
             Value::NativeFunction(idx) => Value::NativeFunction(*idx),
             Value::Path(p) => Value::Path(p.clone()),
             Value::Table(table) => {
                 // Создаем новый Rc с глубокой копией таблицы
                 Value::Table(Rc::new(RefCell::new(table.borrow().clone())))
             },
+            // План неизменяем - копия разделяет его
+            Value::LazyTable(lazy) => Value::LazyTable(Rc::clone(lazy)),
             Value::ColumnReference { table, column_name } => {
                 // Для ColumnReference клонируем ссылку на таблицу и имя колонки
                 Value::ColumnReference {
@@ -217,12 +502,13 @@ impl Clone for Value {
             },
             Value::Object(map) => {
                 // Создаем глубокую копию объекта (клонируем каждое значение)
-                let mut cloned_map = HashMap::new();
+                let mut cloned_map = ObjectMap::with_capacity(map.len());
                 for (k, v) in map {
                     cloned_map.insert(k.clone(), v.clone());
                 }
                 Value::Object(cloned_map)
             },
+            Value::Currency { amount, symbol } => Value::Currency { amount: *amount, symbol: symbol.clone() },
             Value::Null => Value::Null,
         }
     }
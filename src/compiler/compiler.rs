@@ -1,11 +1,31 @@
 // Компилятор AST → Bytecode
 
-use crate::parser::ast::{Expr, Stmt, Arg};
+use crate::parser::ast::{Expr, Stmt, Arg, Param, BindingKind};
 use crate::bytecode::{Chunk, OpCode, Function, CapturedVar};
 use crate::common::error::LangError;
+use crate::common::operators::{self, BinaryOp};
+use crate::common::suggest::closest_match;
 use crate::common::value::Value;
 use crate::lexer::TokenKind;
 
+/// Если до конца стека осталось меньше COMPILER_RED_ZONE байт, compile_expr, evaluate_constant_expr
+/// и find_used_variables_in_expr продолжают рекурсию на новом сегменте размером COMPILER_STACK_SIZE
+const COMPILER_RED_ZONE: usize = 256 * 1024;
+const COMPILER_STACK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Встроенные функции с побочными эффектами (вывод, файлы, SMB, настройки) или с результатом,
+/// зависящим не только от аргументов (время, случайность, файловая система). Функция @cache,
+/// которая их использует, не кэшируется: повторный вызов должен выполнить их снова
+const IMPURE_BUILTINS: &[&str] = &[
+    "print", "now", "getcwd", "sleep", "shuffle", "progress", "progress_done", "memory_usage",
+    "set_option", "eval", "exec", "retry", "read_file", "read_lines", "list_files", "list_sheets",
+    "glob", "path_exists", "path_is_file", "path_is_dir", "path_absolute", "write_file",
+    "append_file", "write_excel", "last_read_report", "last_collect_report", "smb_connect",
+    "smb_list", "smb_read", "relate", "primary_key", "declare_export", "register_foreign_key",
+    "cache_stats", "cache_clear", "set_memoization", "analyze_csv", "set_parallel_threshold",
+    "cached_eval",
+];
+
 // Структура для отслеживания обработчиков исключений
 #[derive(Clone)]
 struct ExceptionHandler {
@@ -20,24 +40,30 @@ struct ExceptionHandler {
 struct LoopContext {
     continue_label: usize,   // Метка для continue (начало следующей итерации или инкремент)
     break_label: usize,      // Метка для break (конец цикла)
+    try_depth: usize,        // Число активных обработчиков исключений при входе в цикл
 }
 
 pub struct Compiler {
     chunk: Chunk,
     functions: Vec<Function>,
-    function_names: Vec<String>, // Имена функций для поиска
     current_function: Option<usize>, // Индекс текущей компилируемой функции
     globals: std::collections::HashMap<String, usize>, // Глобальные переменные и функции
     locals: Vec<std::collections::HashMap<String, usize>>, // Локальные переменные для каждой функции (стек областей видимости)
+    local_functions: Vec<std::collections::HashMap<String, usize>>, // Вложенные функции каждой области locals (имя → индекс функции)
+    declared_local_functions: std::collections::HashMap<(String, usize), usize>, // (имя, строка) вложенной функции → индекс
     local_count: usize, // Счетчик локальных переменных в текущей функции
+    function_scope_start: usize, // Индекс первой области видимости текущей функции в locals
     current_line: usize, // Текущий номер строки (для отладки и ошибок)
     exception_handlers: Vec<ExceptionHandler>, // Стек обработчиков исключений
     error_type_table: Vec<String>, // Таблица типов ошибок для текущей функции
     loop_contexts: Vec<LoopContext>, // Стек контекстов циклов для break/continue
+    catch_depth: usize, // Вложенность catch блоков текущей функции (для throw без значения)
+    try_depth: usize, // Число обработчиков исключений, активных в текущей точке функции (try и catch блоки)
     // Система меток для эталонного алгоритма апгрейда jump-инструкций
     label_counter: usize, // Счетчик для генерации уникальных ID меток
     labels: std::collections::HashMap<usize, usize>, // Маппинг label_id -> индекс инструкции
     pending_jumps: Vec<(usize, usize, bool)>, // (индекс_инструкции, label_id, is_conditional)
+    builtin_count: usize, // Число глобальных слотов встроенных функций (они регистрируются первыми)
 }
 
 impl Compiler {
@@ -45,20 +71,26 @@ impl Compiler {
         let mut compiler = Self {
             chunk: Chunk::new(),
             functions: Vec::new(),
-            function_names: Vec::new(),
             current_function: None,
             globals: std::collections::HashMap::new(),
             locals: Vec::new(),
+            local_functions: Vec::new(),
+            declared_local_functions: std::collections::HashMap::new(),
             local_count: 0,
+            function_scope_start: 0,
             current_line: 0,
             exception_handlers: Vec::new(),
             error_type_table: Vec::new(),
             loop_contexts: Vec::new(),
+            catch_depth: 0,
+            try_depth: 0,
             label_counter: 0,
             labels: std::collections::HashMap::new(),
             pending_jumps: Vec::new(),
+            builtin_count: 0,
         };
         compiler.register_natives();
+        compiler.builtin_count = compiler.globals.len();
         compiler
     }
 
@@ -292,6 +324,273 @@ impl Compiler {
         let primary_key_index = self.globals.len();
         self.globals.insert("primary_key".to_string(), primary_key_index);
 
+        let eval_index = self.globals.len();
+        self.globals.insert("eval".to_string(), eval_index);
+
+        let exec_index = self.globals.len();
+        self.globals.insert("exec".to_string(), exec_index);
+
+        let table_interpolate_index = self.globals.len();
+        self.globals.insert("table_interpolate".to_string(), table_interpolate_index);
+
+        let last_read_report_index = self.globals.len();
+        self.globals.insert("last_read_report".to_string(), last_read_report_index);
+
+        let table_rename_index = self.globals.len();
+        self.globals.insert("table_rename".to_string(), table_rename_index);
+
+        let table_drop_index = self.globals.len();
+        self.globals.insert("table_drop".to_string(), table_drop_index);
+
+        let table_rename_map_index = self.globals.len();
+        self.globals.insert("table_rename_map".to_string(), table_rename_map_index);
+
+        let to_number_index = self.globals.len();
+        self.globals.insert("to_number".to_string(), to_number_index);
+
+        let to_string_index = self.globals.len();
+        self.globals.insert("to_string".to_string(), to_string_index);
+
+        let to_bool_index = self.globals.len();
+        self.globals.insert("to_bool".to_string(), to_bool_index);
+
+        let to_array_index = self.globals.len();
+        self.globals.insert("to_array".to_string(), to_array_index);
+
+        let list_sheets_index = self.globals.len();
+        self.globals.insert("list_sheets".to_string(), list_sheets_index);
+
+        let write_excel_index = self.globals.len();
+        self.globals.insert("write_excel".to_string(), write_excel_index);
+
+        let zip_index = self.globals.len();
+        self.globals.insert("zip".to_string(), zip_index);
+
+        let unzip_index = self.globals.len();
+        self.globals.insert("unzip".to_string(), unzip_index);
+
+        let assert_index = self.globals.len();
+        self.globals.insert("assert".to_string(), assert_index);
+
+        let assert_eq_index = self.globals.len();
+        self.globals.insert("assert_eq".to_string(), assert_eq_index);
+
+        let assert_throws_index = self.globals.len();
+        self.globals.insert("assert_throws".to_string(), assert_throws_index);
+
+        let flatten_index = self.globals.len();
+        self.globals.insert("flatten".to_string(), flatten_index);
+
+        let chunk_index = self.globals.len();
+        self.globals.insert("chunk".to_string(), chunk_index);
+
+        let shuffle_index = self.globals.len();
+        self.globals.insert("shuffle".to_string(), shuffle_index);
+
+        let table_lazy_index = self.globals.len();
+        self.globals.insert("table_lazy".to_string(), table_lazy_index);
+
+        let collect_index = self.globals.len();
+        self.globals.insert("collect".to_string(), collect_index);
+
+        let last_collect_report_index = self.globals.len();
+        self.globals.insert("last_collect_report".to_string(), last_collect_report_index);
+
+        let reduce_index = self.globals.len();
+        self.globals.insert("reduce".to_string(), reduce_index);
+
+        let path_join_index = self.globals.len();
+        self.globals.insert("path_join".to_string(), path_join_index);
+
+        let path_filename_index = self.globals.len();
+        self.globals.insert("path_filename".to_string(), path_filename_index);
+
+        let path_ext_index = self.globals.len();
+        self.globals.insert("path_ext".to_string(), path_ext_index);
+
+        let path_absolute_index = self.globals.len();
+        self.globals.insert("path_absolute".to_string(), path_absolute_index);
+
+        let is_absolute_index = self.globals.len();
+        self.globals.insert("is_absolute".to_string(), is_absolute_index);
+
+        let map_index = self.globals.len();
+        self.globals.insert("map".to_string(), map_index);
+
+        let merge_index = self.globals.len();
+        self.globals.insert("merge".to_string(), merge_index);
+
+        let deep_merge_index = self.globals.len();
+        self.globals.insert("deep_merge".to_string(), deep_merge_index);
+
+        let table_slice_index = self.globals.len();
+        self.globals.insert("table_slice".to_string(), table_slice_index);
+
+        let pick_index = self.globals.len();
+        self.globals.insert("pick".to_string(), pick_index);
+
+        let omit_index = self.globals.len();
+        self.globals.insert("omit".to_string(), omit_index);
+
+        let has_key_index = self.globals.len();
+        self.globals.insert("has_key".to_string(), has_key_index);
+
+        let get_index = self.globals.len();
+        self.globals.insert("get".to_string(), get_index);
+
+        let sort_by_index = self.globals.len();
+        self.globals.insert("sort_by".to_string(), sort_by_index);
+
+        let index_of_index = self.globals.len();
+        self.globals.insert("index_of".to_string(), index_of_index);
+
+        let parse_currency_index = self.globals.len();
+        self.globals.insert("parse_currency".to_string(), parse_currency_index);
+
+        let format_currency_index = self.globals.len();
+        self.globals.insert("format_currency".to_string(), format_currency_index);
+
+        let table_filter_expr_index = self.globals.len();
+        self.globals.insert("table_filter_expr".to_string(), table_filter_expr_index);
+
+        let set_option_index = self.globals.len();
+        self.globals.insert("set_option".to_string(), set_option_index);
+
+        let glob_index = self.globals.len();
+        self.globals.insert("glob".to_string(), glob_index);
+
+        let read_lines_index = self.globals.len();
+        self.globals.insert("read_lines".to_string(), read_lines_index);
+
+        let progress_index = self.globals.len();
+        self.globals.insert("progress".to_string(), progress_index);
+
+        let progress_done_index = self.globals.len();
+        self.globals.insert("progress_done".to_string(), progress_done_index);
+
+        let write_file_index = self.globals.len();
+        self.globals.insert("write_file".to_string(), write_file_index);
+
+        let append_file_index = self.globals.len();
+        self.globals.insert("append_file".to_string(), append_file_index);
+
+        let memory_usage_index = self.globals.len();
+        self.globals.insert("memory_usage".to_string(), memory_usage_index);
+
+        let table_string_agg_index = self.globals.len();
+        self.globals.insert("table_string_agg".to_string(), table_string_agg_index);
+
+        let table_collect_index = self.globals.len();
+        self.globals.insert("table_collect".to_string(), table_collect_index);
+
+        let table_merge_columns_index = self.globals.len();
+        self.globals.insert("table_merge_columns".to_string(), table_merge_columns_index);
+
+        let deep_equal_index = self.globals.len();
+        self.globals.insert("deep_equal".to_string(), deep_equal_index);
+
+        let same_reference_index = self.globals.len();
+        self.globals.insert("same_reference".to_string(), same_reference_index);
+
+        let declare_export_index = self.globals.len();
+        self.globals.insert("declare_export".to_string(), declare_export_index);
+
+        let register_foreign_key_index = self.globals.len();
+        self.globals.insert("register_foreign_key".to_string(), register_foreign_key_index);
+
+        let sleep_index = self.globals.len();
+        self.globals.insert("sleep".to_string(), sleep_index);
+
+        let retry_index = self.globals.len();
+        self.globals.insert("retry".to_string(), retry_index);
+
+        let table_reorder_index = self.globals.len();
+        self.globals.insert("table_reorder".to_string(), table_reorder_index);
+
+        let table_add_column_index = self.globals.len();
+        self.globals.insert("table_add_column".to_string(), table_add_column_index);
+
+        let table_drop_column_index = self.globals.len();
+        self.globals.insert("table_drop_column".to_string(), table_drop_column_index);
+
+        let table_drop_rows_index = self.globals.len();
+        self.globals.insert("table_drop_rows".to_string(), table_drop_rows_index);
+
+        let table_drop_duplicates_index = self.globals.len();
+        self.globals.insert("table_drop_duplicates".to_string(), table_drop_duplicates_index);
+
+        let table_cast_index = self.globals.len();
+        self.globals.insert("table_cast".to_string(), table_cast_index);
+
+        let table_autocast_index = self.globals.len();
+        self.globals.insert("table_autocast".to_string(), table_autocast_index);
+
+        let table_fillna_index = self.globals.len();
+        self.globals.insert("table_fillna".to_string(), table_fillna_index);
+
+        let table_dropna_index = self.globals.len();
+        self.globals.insert("table_dropna".to_string(), table_dropna_index);
+
+        let describe_index = self.globals.len();
+        self.globals.insert("describe".to_string(), describe_index);
+
+        let value_counts_index = self.globals.len();
+        self.globals.insert("value_counts".to_string(), value_counts_index);
+
+        let table_window_index = self.globals.len();
+        self.globals.insert("table_window".to_string(), table_window_index);
+
+        let table_where_regex_index = self.globals.len();
+        self.globals.insert("table_where_regex".to_string(), table_where_regex_index);
+
+        let smb_connect_index = self.globals.len();
+        self.globals.insert("smb_connect".to_string(), smb_connect_index);
+
+        let smb_list_index = self.globals.len();
+        self.globals.insert("smb_list".to_string(), smb_list_index);
+
+        let smb_read_index = self.globals.len();
+        self.globals.insert("smb_read".to_string(), smb_read_index);
+
+        let table_apply_index = self.globals.len();
+        self.globals.insert("table_apply".to_string(), table_apply_index);
+
+        let table_apply_column_index = self.globals.len();
+        self.globals.insert("table_apply_column".to_string(), table_apply_column_index);
+
+        let cache_stats_index = self.globals.len();
+        self.globals.insert("cache_stats".to_string(), cache_stats_index);
+
+        let cache_clear_index = self.globals.len();
+        self.globals.insert("cache_clear".to_string(), cache_clear_index);
+
+        let set_memoization_index = self.globals.len();
+        self.globals.insert("set_memoization".to_string(), set_memoization_index);
+
+        let analyze_csv_index = self.globals.len();
+        self.globals.insert("analyze_csv".to_string(), analyze_csv_index);
+
+        let table_group_by_index = self.globals.len();
+        self.globals.insert("table_group_by".to_string(), table_group_by_index);
+
+        let filter_index = self.globals.len();
+        self.globals.insert("filter".to_string(), filter_index);
+
+        let set_parallel_threshold_index = self.globals.len();
+        self.globals.insert("set_parallel_threshold".to_string(), set_parallel_threshold_index);
+
+        let divmod_index = self.globals.len();
+        self.globals.insert("divmod".to_string(), divmod_index);
+
+        let cached_eval_index = self.globals.len();
+        self.globals.insert("cached_eval".to_string(), cached_eval_index);
+
+        let table_fill_nulls_index = self.globals.len();
+        self.globals.insert("table_fill_nulls".to_string(), table_fill_nulls_index);
+
+        let table_fill_direction_index = self.globals.len();
+        self.globals.insert("table_fill_direction".to_string(), table_fill_direction_index);
+
         let concat_table_index = self.globals.len();
         self.globals.insert("concat".to_string(), concat_table_index);
     }
@@ -301,7 +600,7 @@ impl Compiler {
         self.begin_scope();
         
         // Первый проход: объявляем все функции (forward declaration), включая вложенные
-        self.collect_all_functions(statements)?;
+        self.collect_all_functions(statements, false)?;
         
         // Второй проход: компилируем все statements
         for (i, stmt) in statements.iter().enumerate() {
@@ -320,14 +619,97 @@ impl Compiler {
         // Очищаем метки после финализации главного скрипта
         self.clear_labels();
         
+        // Сохраняем таблицу глобальных имен для eval()/exec()
+        self.chunk.global_indices = self.globals.clone();
+        
+        Ok(self.chunk.clone())
+    }
+
+    /// Создает компилятор для кода eval()/exec(), который продолжает нумерацию
+    /// глобальных переменных и функций уже выполняющейся программы
+    pub fn with_context(
+        global_indices: std::collections::HashMap<String, usize>,
+        functions: Vec<Function>,
+    ) -> Self {
+        let mut compiler = Self::new();
+        compiler.globals.extend(global_indices);
+        compiler.functions = functions;
+        compiler
+    }
+
+    /// Компилирует код eval()/exec() в контексте вызывающего фрейма.
+    /// `locals` - видимые локальные переменные вызывающего (имя → слот): код работает прямо с его слотами.
+    /// `caller_function` - индекс функции, из которой сделан вызов (None для верхнего уровня).
+    /// Для eval() результат выражения возвращается, exec() всегда возвращает null.
+    pub fn compile_dynamic(
+        &mut self,
+        statements: &[Stmt],
+        locals: &std::collections::HashMap<String, usize>,
+        caller_function: Option<usize>,
+        is_exec: bool,
+    ) -> Result<Chunk, LangError> {
+        // Область видимости вызывающего: новые локальные получают слоты после существующих
+        self.begin_scope();
+        if let Some(scope) = self.locals.last_mut() {
+            scope.extend(locals.iter().map(|(name, &slot)| (name.clone(), slot)));
+        }
+        self.local_count = locals.values().max().map_or(0, |&max| max + 1);
+        self.current_function = caller_function;
+        
+        // На верхнем уровне новые переменные из exec() должны пережить вызов,
+        // поэтому присваивания неизвестным именам регистрируем как глобальные
+        if caller_function.is_none() {
+            for stmt in statements {
+                let names: &[String] = match stmt {
+                    Stmt::Expr { expr: Expr::Assign { name, .. }, .. } => std::slice::from_ref(name),
+                    Stmt::Destructure { names, kind: BindingKind::Assign, .. } => names,
+                    _ => &[],
+                };
+                for name in names {
+                    if !locals.contains_key(name) && !self.globals.contains_key(name) {
+                        let global_index = self.globals.len();
+                        self.globals.insert(name.clone(), global_index);
+                    }
+                }
+            }
+        }
+        
+        self.collect_all_functions(statements, caller_function.is_some())?;
+        
+        if is_exec {
+            for stmt in statements {
+                self.compile_stmt_with_pop(stmt, true)?;
+            }
+            let null_index = self.chunk.add_constant(Value::Null);
+            self.chunk.write_with_line(OpCode::Constant(null_index), self.current_line);
+        } else {
+            match statements {
+                [stmt @ Stmt::Expr { .. }] => self.compile_stmt_with_pop(stmt, false)?,
+                _ => {
+                    return Err(LangError::ParseError {
+                        message: "eval() expects a single expression, use exec() for statements".to_string(),
+                        line: statements.first().map_or(1, |s| s.line()),
+                    });
+                }
+            }
+        }
+        self.chunk.write_with_line(OpCode::Return, self.current_line);
+        
+        self.stabilize_layout()?;
+        self.finalize_jumps()?;
+        self.clear_labels();
+        
+        self.chunk.global_indices = self.globals.clone();
+        
         Ok(self.chunk.clone())
     }
 
-    /// Рекурсивно собирает все объявления функций на всех уровнях вложенности
-    fn collect_all_functions(&mut self, statements: &[Stmt]) -> Result<(), LangError> {
+    /// Рекурсивно собирает все объявления функций на всех уровнях вложенности.
+    /// `in_function` - объявления находятся в теле функции: без global они локальные
+    fn collect_all_functions(&mut self, statements: &[Stmt], in_function: bool) -> Result<(), LangError> {
         for stmt in statements {
             match stmt {
-                Stmt::Function { name, params, body, is_cached, .. } => {
+                Stmt::Function { name, params, body, is_cached, is_global, line } => {
                     // Объявляем функцию с правильной сигнатурой сразу
                     let arity = params.len();
                     let param_names: Vec<String> = params.iter().map(|p| p.name.clone()).collect();
@@ -342,41 +724,58 @@ impl Compiler {
                     function.param_names = param_names;
                     // Инициализируем default_values как None для всех параметров (обработаем позже)
                     function.default_values = vec![None; params.len()];
+                    function.is_local = in_function && !is_global;
                     
+                    if function.is_local {
+                        // Вложенная функция получает слот в объявившей функции при компиляции,
+                        // одноименные вложенные функции различаются по строке объявления
+                        self.declared_local_functions.insert((name.clone(), *line), self.functions.len());
+                    } else {
+                        // Регистрируем функцию в глобальной таблице. Функция с именем встроенной
+                        // занимает ее слот: новый индекс по globals.len() совпал бы со следующей
+                        // глобальной переменной, ведь замена имени не увеличивает размер таблицы
+                        let global_index = self.globals.len();
+                        self.globals.entry(name.clone()).or_insert(global_index);
+                    }
                     self.functions.push(function);
-                    self.function_names.push(name.clone());
-                    // Регистрируем функцию в глобальной таблице
-                    let global_index = self.globals.len();
-                    self.globals.insert(name.clone(), global_index);
                     
                     // Рекурсивно собираем функции из тела этой функции
-                    self.collect_all_functions(body)?;
+                    self.collect_all_functions(body, true)?;
                 }
                 Stmt::If { then_branch, else_branch, .. } => {
                     // Рекурсивно собираем функции из веток if
-                    self.collect_all_functions(then_branch)?;
+                    self.collect_all_functions(then_branch, in_function)?;
                     if let Some(else_branch) = else_branch {
-                        self.collect_all_functions(else_branch)?;
+                        self.collect_all_functions(else_branch, in_function)?;
                     }
                 }
                 Stmt::While { body, .. } => {
                     // Рекурсивно собираем функции из тела while
-                    self.collect_all_functions(body)?;
+                    self.collect_all_functions(body, in_function)?;
                 }
                 Stmt::For { body, .. } => {
                     // Рекурсивно собираем функции из тела for
-                    self.collect_all_functions(body)?;
+                    self.collect_all_functions(body, in_function)?;
                 }
                 Stmt::Try { try_block, catch_blocks, else_block, .. } => {
                     // Рекурсивно собираем функции из try блока
-                    self.collect_all_functions(try_block)?;
+                    self.collect_all_functions(try_block, in_function)?;
                     // Рекурсивно собираем функции из catch блоков
                     for catch_block in catch_blocks {
-                        self.collect_all_functions(&catch_block.body)?;
+                        self.collect_all_functions(&catch_block.body, in_function)?;
                     }
                     // Рекурсивно собираем функции из else блока (если есть)
                     if let Some(else_block) = else_block {
-                        self.collect_all_functions(else_block)?;
+                        self.collect_all_functions(else_block, in_function)?;
+                    }
+                }
+                Stmt::Match { cases, default, .. } => {
+                    // Рекурсивно собираем функции из веток match
+                    for case in cases {
+                        self.collect_all_functions(&case.body, in_function)?;
+                    }
+                    if let Some(default) = default {
+                        self.collect_all_functions(default, in_function)?;
                     }
                 }
                 _ => {
@@ -392,8 +791,58 @@ impl Compiler {
     }
 
     /// Возвращает имена параметров для нативной функции, если она поддерживает именованные аргументы
+    pub(crate) fn get_native_function_params(function_name: &str) -> Option<Vec<String>> {
+        Self::get_native_function_signature(function_name)
+            .map(|params| params.into_iter().map(|p| p.trim_end_matches('?').to_string()).collect())
+    }
+
+    /// Ошибка вызова неизвестной функции с подсказкой ближайшего встроенного или пользовательского имени
+    fn function_not_found(&self, name: &str, line: usize) -> LangError {
+        let builtins = self.globals.iter()
+            .filter(|(_, &index)| index < self.builtin_count)
+            .map(|(builtin, _)| builtin.as_str());
+        let functions = self.functions.iter().filter(|f| !f.is_local).map(|f| f.name.as_str());
+        let message = match closest_match(name, builtins.chain(functions)) {
+            Some(candidate) => format!("Function '{}' not found, did you mean '{}'?", name, candidate),
+            None => format!("Function '{}' not found", name),
+        };
+        LangError::ParseError { message, line }
+    }
+
+    /// Проверяет, что встроенной функции передано не меньше обязательных аргументов.
+    /// Ошибка содержит сигнатуру: "range(start, end?, step?) expects 1 to 3 arguments, got 0"
+    fn check_native_arg_count(&self, function_name: &str, given: usize, line: usize) -> Result<(), LangError> {
+        let params = match Self::get_native_function_signature(function_name) {
+            Some(params) => params,
+            None => return Ok(()),
+        };
+        let required = params.iter().filter(|p| !p.ends_with('?') && !p.starts_with('*')).count();
+        if given >= required {
+            return Ok(());
+        }
+        let expected = if params.iter().any(|p| p.starts_with('*')) {
+            format!("at least {}", required)
+        } else if required == params.len() {
+            required.to_string()
+        } else {
+            format!("{} to {}", required, params.len())
+        };
+        Err(LangError::ParseError {
+            message: format!(
+                "{}({}) expects {} argument{}, got {}",
+                function_name,
+                params.join(", "),
+                expected,
+                if required == 1 && !expected.contains(" to ") { "" } else { "s" },
+                given
+            ),
+            line,
+        })
+    }
+
+    /// Сигнатура нативной функции: имена параметров, необязательные помечены '?', вариативный - '*'.
     /// None возвращается для функций с переменным числом аргументов (print, min, max, array)
-    fn get_native_function_params(&self, function_name: &str) -> Option<Vec<String>> {
+    fn get_native_function_signature(function_name: &str) -> Option<Vec<String>> {
         match function_name {
             // Функции с переменным числом аргументов - именованные аргументы не поддерживаются
             "print" | "min" | "max" | "array" => None,
@@ -425,42 +874,129 @@ impl Compiler {
             "unique" => Some(vec!["array".to_string()]),
             "reverse" => Some(vec!["array".to_string()]),
             "sort" => Some(vec!["array".to_string()]),
-            "sum" => Some(vec!["array".to_string()]),
-            "average" => Some(vec!["array".to_string()]),
-            "count" => Some(vec!["array".to_string()]),
+            "sum" => Some(vec!["array".to_string(), "column?".to_string()]),
+            "average" => Some(vec!["array".to_string(), "column?".to_string()]),
+            "count" => Some(vec!["array".to_string(), "column?".to_string()]),
             "table_info" => Some(vec!["table".to_string()]),
-            "show_table" => Some(vec!["table".to_string()]),
+            "show_table" => Some(vec![
+                "table".to_string(), "limit?".to_string(), "columns?".to_string(), "max_col_width?".to_string(),
+                "max_rows?".to_string(), "format?".to_string(), "index?".to_string(),
+            ]),
             "now" => Some(vec![]),
             "getcwd" => Some(vec![]),
             
             // Функции с двумя параметрами
-            "range" => Some(vec!["start".to_string(), "end".to_string(), "step".to_string()]),
+            "range" => Some(vec!["start".to_string(), "end?".to_string(), "step?".to_string()]),
             "pow" => Some(vec!["base".to_string(), "exp".to_string()]),
             "split" => Some(vec!["str".to_string(), "delim".to_string()]),
-            "join" => Some(vec!["array".to_string(), "delim".to_string()]),
+            "join" => Some(vec!["array".to_string(), "delim?".to_string()]),
             "contains" => Some(vec!["str".to_string(), "substr".to_string()]),
             "push" => Some(vec!["array".to_string(), "item".to_string()]),
             "isinstance" => Some(vec!["value".to_string(), "type".to_string()]),
-            "money" => Some(vec!["amount".to_string(), "format".to_string()]),
-            "list_files" => Some(vec!["path".to_string()]),
+            "money" => Some(vec!["amount".to_string(), "format?".to_string()]),
+            "list_files" => Some(vec!["path?".to_string()]),
             
             // Функции с опциональными параметрами
-            "table" => Some(vec!["data".to_string(), "headers".to_string()]),
-            "read_file" => Some(vec!["path".to_string(), "header_row".to_string(), "sheet_name".to_string()]),
-            "table_head" => Some(vec!["table".to_string(), "n".to_string()]),
-            "table_tail" => Some(vec!["table".to_string(), "n".to_string()]),
+            "table" => Some(vec!["data".to_string(), "headers?".to_string()]),
+            "read_file" => Some(vec![
+                "path".to_string(), "header_row?".to_string(), "sheet_name?".to_string(),
+                "delimiter?".to_string(), "encoding?".to_string(), "on_bad_lines?".to_string(),
+                "quote_char?".to_string(), "skip_rows?".to_string(), "options?".to_string(), "lazy?".to_string(),
+            ]),
+            "table_head" => Some(vec!["table".to_string(), "n?".to_string()]),
+            "table_tail" => Some(vec!["table".to_string(), "n?".to_string()]),
+            "table_slice" => Some(vec!["table".to_string(), "offset?".to_string(), "limit?".to_string()]),
+            "pick" => Some(vec!["object".to_string(), "keys".to_string(), "strict?".to_string()]),
+            "omit" => Some(vec!["object".to_string(), "keys".to_string()]),
+            "has_key" => Some(vec!["object".to_string(), "key".to_string()]),
+            "get" => Some(vec!["object".to_string(), "key".to_string(), "default?".to_string()]),
+            "sort_by" => Some(vec!["array".to_string(), "fn".to_string(), "descending?".to_string()]),
+            "index_of" => Some(vec!["array".to_string(), "value".to_string()]),
+            "parse_currency" => Some(vec!["text".to_string()]),
+            "format_currency" => Some(vec!["amount".to_string(), "symbol?".to_string(), "decimals?".to_string()]),
+            "table_filter_expr" => Some(vec!["table".to_string(), "expr".to_string(), "missing?".to_string()]),
+            "glob" => Some(vec!["pattern".to_string()]),
+            "read_lines" => Some(vec!["path".to_string()]),
+            "progress" => Some(vec!["label".to_string(), "current".to_string(), "total".to_string()]),
+            "progress_done" => Some(vec!["label".to_string()]),
+            "write_file" | "append_file" => Some(vec!["path".to_string(), "content".to_string()]),
+            "memory_usage" => Some(vec!["value?".to_string()]),
+            "table_string_agg" => Some(vec!["table".to_string(), "keys".to_string(), "column".to_string(), "separator?".to_string()]),
+            "table_collect" => Some(vec!["table".to_string(), "keys".to_string(), "column".to_string()]),
+            "table_group_by" => Some(vec!["table".to_string(), "keys".to_string(), "aggregations".to_string()]),
+            "filter" => Some(vec!["fn".to_string(), "array".to_string()]),
+            "set_parallel_threshold" => Some(vec!["n".to_string()]),
+            "divmod" => Some(vec!["a".to_string(), "b".to_string()]),
+            "cached_eval" => Some(vec!["key".to_string(), "fn".to_string()]),
+            "table_fill_nulls" => Some(vec!["table".to_string(), "fills".to_string()]),
+            "table_fill_direction" => Some(vec!["table".to_string(), "columns".to_string(), "direction?".to_string(), "partition_by?".to_string()]),
+            "table_merge_columns" => Some(vec!["table".to_string(), "columns".to_string(), "name".to_string(), "separator?".to_string()]),
+            "deep_equal" => Some(vec!["a".to_string(), "b".to_string(), "ignore_column_order?".to_string()]),
+            "same_reference" => Some(vec!["a".to_string(), "b".to_string()]),
+            "declare_export" => Some(vec!["table".to_string(), "name?".to_string(), "primary_key?".to_string()]),
+            "register_foreign_key" => Some(vec!["child_table".to_string(), "child_col".to_string(), "parent_table".to_string(), "parent_col".to_string()]),
+            "sleep" => Some(vec!["ms".to_string()]),
+            "retry" => Some(vec!["fn".to_string(), "attempts?".to_string(), "backoff_ms?".to_string(), "factor?".to_string(), "retry_on?".to_string()]),
             "table_select" => Some(vec!["table".to_string(), "cols".to_string()]),
-            "table_sort" => Some(vec!["table".to_string(), "col".to_string(), "asc".to_string()]),
-            "table_where" => Some(vec!["table".to_string(), "col".to_string(), "op".to_string(), "value".to_string()]),
-            "merge_tables" => Some(vec!["tables".to_string(), "mode".to_string()]),
+            "table_sort" => Some(vec!["table".to_string(), "col".to_string(), "asc?".to_string()]),
+            "table_where" => Some(vec!["table".to_string(), "col".to_string(), "op?".to_string(), "value?".to_string()]),
+            "merge_tables" => Some(vec!["tables".to_string(), "mode?".to_string()]),
             "cross_join" => Some(vec!["left".to_string(), "right".to_string()]),
-            "table_suffixes" => Some(vec!["left".to_string(), "right".to_string(), "left_suffix".to_string(), "right_suffix".to_string()]),
+            "table_suffixes" => Some(vec!["left".to_string(), "right".to_string(), "left_suffix?".to_string(), "right_suffix?".to_string()]),
             "relate" => Some(vec!["col1".to_string(), "col2".to_string()]),
             "primary_key" => Some(vec!["col".to_string()]),
+            "eval" => Some(vec!["code".to_string()]),
+            "exec" => Some(vec!["code".to_string()]),
+            "table_interpolate" => Some(vec!["table".to_string(), "column".to_string(), "method?".to_string()]),
+            "last_read_report" => Some(vec![]),
+            "table_rename" => Some(vec!["table".to_string(), "mapping".to_string()]),
+            "table_reorder" => Some(vec!["table".to_string(), "columns".to_string(), "all?".to_string()]),
+            "table_add_column" => Some(vec!["table".to_string(), "name".to_string(), "fn".to_string()]),
+            "table_drop" => Some(vec!["table".to_string(), "columns".to_string()]),
+            "table_drop_column" => Some(vec!["table".to_string(), "columns".to_string()]),
+            "table_drop_rows" => Some(vec!["table".to_string(), "predicate".to_string()]),
+            "table_drop_duplicates" => Some(vec!["table".to_string(), "columns?".to_string()]),
+            "table_cast" => Some(vec!["table".to_string(), "types".to_string(), "on_error?".to_string(), "locale?".to_string()]),
+            "table_autocast" => Some(vec!["table".to_string(), "locale?".to_string()]),
+            "table_fillna" => Some(vec!["table".to_string(), "value".to_string()]),
+            "table_dropna" => Some(vec!["table".to_string(), "how?".to_string(), "subset?".to_string()]),
+            "describe" => Some(vec!["table".to_string()]),
+            "value_counts" => Some(vec!["table".to_string(), "column".to_string(), "normalize?".to_string(), "dropna?".to_string()]),
+            "table_window" => Some(vec!["table".to_string(), "partition_by".to_string(), "order_by".to_string(), "specs".to_string()]),
+            "table_where_regex" => Some(vec!["table".to_string(), "column".to_string(), "pattern".to_string(), "skip_non_strings?".to_string()]),
+            "smb_connect" => Some(vec!["ip".to_string(), "login".to_string(), "password".to_string(), "domain".to_string(), "share".to_string()]),
+            "smb_list" => Some(vec!["share".to_string(), "path?".to_string()]),
+            "smb_read" => Some(vec!["share".to_string(), "path".to_string()]),
+            "table_apply" => Some(vec!["table".to_string(), "fn".to_string()]),
+            "table_apply_column" => Some(vec!["table".to_string(), "column".to_string(), "fn".to_string()]),
+            "cache_stats" => Some(vec!["fn?".to_string()]),
+            "cache_clear" => Some(vec!["fn?".to_string()]),
+            "set_memoization" => Some(vec!["fn".to_string(), "enabled".to_string()]),
+            "analyze_csv" => Some(vec!["path".to_string(), "sample_rows?".to_string(), "print?".to_string()]),
+            "table_rename_map" => Some(vec!["table".to_string(), "fn".to_string()]),
+            "to_number" => Some(vec!["value".to_string(), "strict?".to_string()]),
+            "list_sheets" => Some(vec!["path".to_string()]),
+            "write_excel" => Some(vec!["path".to_string(), "data".to_string()]),
+            "zip" => Some(vec!["*arrays".to_string(), "strict?".to_string()]),
+            "unzip" => Some(vec!["tuples".to_string()]),
+            "assert" => Some(vec!["condition".to_string(), "message?".to_string()]),
+            "assert_eq" => Some(vec!["left".to_string(), "right".to_string(), "message?".to_string()]),
+            "assert_throws" => Some(vec!["fn".to_string(), "args?".to_string()]),
+            "flatten" => Some(vec!["array".to_string(), "depth?".to_string()]),
+            "chunk" => Some(vec!["array".to_string(), "size".to_string()]),
+            "shuffle" => Some(vec!["array".to_string(), "seed?".to_string()]),
+            "table_lazy" => Some(vec!["table".to_string()]),
+            "collect" => Some(vec!["table".to_string()]),
+            "reduce" => Some(vec!["array".to_string(), "fn".to_string(), "initial?".to_string()]),
+            "path_join" => Some(vec!["*parts".to_string()]),
+            "path_filename" => Some(vec!["path".to_string()]),
+            "path_ext" => Some(vec!["path".to_string()]),
+            "path_absolute" => Some(vec!["path".to_string()]),
+            "is_absolute" => Some(vec!["path".to_string()]),
             
             // JOIN функции - они все имеют одинаковую структуру (left, right, on, type?, suffixes?)
             "inner_join" | "left_join" | "right_join" | "full_join" | "semi_join" | "anti_join" | "zip_join" | "asof_join" | "join_on" | "apply_join" => {
-                Some(vec!["left".to_string(), "right".to_string(), "on".to_string(), "type".to_string(), "suffixes".to_string()])
+                Some(vec!["left".to_string(), "right".to_string(), "on?".to_string(), "type?".to_string(), "suffixes?".to_string()])
             },
             
             // Функция не найдена или не поддерживает именованные аргументы
@@ -481,50 +1017,23 @@ impl Compiler {
                 self.compile_expr(value)?;
                 // Не клонируем автоматически - переменные должны разделять ссылки на массивы/таблицы/объекты
                 // Клонирование происходит только при явном вызове .clone()
-                if *is_global {
-                    // Явное объявление глобальной переменной
-                    // Всегда изменяет глобальную переменную, даже внутри функций
-                    let global_index = if let Some(&idx) = self.globals.get(name) {
-                        idx
-                    } else {
-                        let idx = self.globals.len();
-                        self.globals.insert(name.clone(), idx);
-                        idx
-                    };
-                    // Сохраняем имя глобальной переменной для использования в JOIN
-                    self.chunk.global_names.insert(global_index, name.clone());
-                    // Сохраняем имя явно объявленной глобальной переменной для экспорта в SQLite
-                    self.chunk.explicit_global_names.insert(global_index, name.clone());
-                    self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
-                } else {
-                    // Локальная переменная или глобальная на верхнем уровне
-                    // Проверяем, есть ли переменная в локальной области видимости
-                    if let Some(local_index) = self.resolve_local(name) {
-                        // Локальная переменная уже объявлена - обновляем
-                        self.chunk.write_with_line(OpCode::StoreLocal(local_index), *line);
-                    } else if self.current_function.is_some() {
-                        // Мы находимся внутри функции - объявляем новую локальную переменную
-                        let index = self.declare_local(name);
-                        self.chunk.write_with_line(OpCode::StoreLocal(index), *line);
-                    } else {
-                        // Переменная не найдена локально - проверяем, является ли она глобальной
-                        // На верхнем уровне главной функции переменные без 'global' все равно глобальные
-                        if let Some(&global_index) = self.globals.get(name) {
-                            // Глобальная переменная уже существует - обновляем
-                            // Сохраняем имя глобальной переменной для использования в JOIN
-                            self.chunk.global_names.insert(global_index, name.clone());
-                            self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
-                        } else {
-                            // Новая глобальная переменная на верхнем уровне
-                            let global_index = self.globals.len();
-                            self.globals.insert(name.clone(), global_index);
-                            // Сохраняем имя глобальной переменной для использования в JOIN
-                            self.chunk.global_names.insert(global_index, name.clone());
-                            self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
+                self.compile_let_store(name, *is_global, *line);
+                // StoreGlobal/StoreLocal уже удаляют значение со стека, поэтому дополнительный Pop не нужен
+            }
+            Stmt::Destructure { names, value, kind, line } => {
+                self.current_line = *line;
+                self.compile_expr(value)?;
+                // Unpack оставляет элементы на стеке так, что первый оказывается на вершине
+                self.chunk.write_with_line(OpCode::Unpack(names.len()), *line);
+                for name in names {
+                    match kind {
+                        BindingKind::Let => self.compile_let_store(name, false, *line),
+                        BindingKind::Global => self.compile_let_store(name, true, *line),
+                        BindingKind::Assign => {
+                            self.compile_assign_store(name, *line);
                         }
                     }
                 }
-                // StoreGlobal/StoreLocal уже удаляют значение со стека, поэтому дополнительный Pop не нужен
             }
             Stmt::Expr { expr, line } => {
                 self.current_line = *line;
@@ -590,6 +1099,7 @@ impl Compiler {
                 let loop_context = LoopContext {
                     continue_label: loop_start_label, // continue возвращается к началу цикла
                     break_label: loop_end_label,      // break переходит к концу цикла
+                    try_depth: self.try_depth,
                 };
                 self.loop_contexts.push(loop_context);
                 
@@ -617,8 +1127,10 @@ impl Compiler {
                 // Начинаем новую область видимости для переменных цикла
                 self.begin_scope();
                 
-                // Компилируем итерируемое выражение (оно должно быть массивом)
+                // Компилируем итерируемое выражение (массив, колонка или таблица).
+                // Строки таблицы берутся снимком: изменение таблицы в теле цикла не меняет итерацию
                 self.compile_expr(iterable)?;
+                self.chunk.write_with_line(OpCode::IterSnapshot, *line);
                 
                 // Сохраняем массив во временную переменную (локальную)
                 // Создаем скрытую переменную для массива
@@ -663,11 +1175,13 @@ impl Compiler {
                 
                 // Сохраняем элемент в переменную-итератор
                 self.chunk.write_with_line(OpCode::StoreLocal(var_local), *line);
+                self.chunk.write_with_line(OpCode::LoopProgress(array_local, index_local), *line);
                 
                 // Создаем контекст цикла
                 let loop_context = LoopContext {
                     continue_label: continue_label, // continue переходит к инкременту
                     break_label: loop_end_label,    // break переходит к концу цикла
+                    try_depth: self.try_depth,
                 };
                 self.loop_contexts.push(loop_context);
                 
@@ -678,6 +1192,8 @@ impl Compiler {
                 
                 // Помечаем метку continue (начало инкремента индекса)
                 self.mark_label(continue_label);
+                // Инкремент и переход к началу относятся к строке for, а не к последней строке тела
+                self.current_line = *line;
                 
                 // Инкрементируем индекс
                 self.chunk.write_with_line(OpCode::LoadLocal(index_local), *line);
@@ -700,11 +1216,17 @@ impl Compiler {
                 self.end_scope();
                 self.loop_contexts.pop();
             }
-            Stmt::Function { name, params, body, is_cached, line } => {
+            Stmt::Function { name, params, body, is_cached, is_global, line } => {
                 self.current_line = *line;
+                // Внутри функции объявление без global привязывает имя к локальному слоту
+                let is_local = self.current_function.is_some() && !is_global;
                 // Находим индекс функции (она уже объявлена в первом проходе)
-                let function_index = self.function_names.iter()
-                    .position(|n| n == name)
+                let declared_index = if is_local {
+                    self.declared_local_functions.get(&(name.clone(), *line)).copied()
+                } else {
+                    self.global_function_index(name)
+                };
+                let function_index = declared_index
                     .ok_or_else(|| LangError::ParseError {
                         message: format!("Function '{}' not found in forward declarations", name),
                         line: *line,
@@ -713,7 +1235,9 @@ impl Compiler {
                 // Получаем функцию и обновляем количество параметров и флаг кэширования
                 let mut function = self.functions[function_index].clone();
                 function.arity = params.len();
-                function.is_cached = *is_cached;
+                // @cache не действует, если тело использует встроенные функции с побочными эффектами;
+                // кэш все равно создается, чтобы set_memoization() могла включить его явно
+                function.is_cached = *is_cached && self.find_impure_builtin(params, body).is_none();
                 
                 // Сохраняем имена параметров и вычисляем значения по умолчанию
                 let mut param_names = Vec::new();
@@ -750,6 +1274,7 @@ impl Compiler {
                 
                 function.param_names = param_names;
                 function.default_values = default_values;
+                function.parent = self.current_function;
                 // Если кэш включен, но еще не инициализирован, инициализируем его
                 if *is_cached && function.cache.is_none() {
                     use std::rc::Rc;
@@ -762,6 +1287,24 @@ impl Compiler {
                 // могли видеть правильное количество параметров и их имена
                 self.functions[function_index] = function.clone();
                 
+                // Слот вложенной функции объявляется до компиляции тела: рекурсивные вызовы
+                // и вызовы из соседних вложенных функций разрешаются в эту же функцию
+                let local_slot = if is_local {
+                    let hoisted = self.local_functions.last().and_then(|scope| scope.get(name)) == Some(&function_index);
+                    match self.locals.last().and_then(|scope| scope.get(name)) {
+                        Some(&slot) if hoisted => Some(slot),
+                        _ => {
+                            let slot = self.declare_local(name);
+                            if let Some(scope) = self.local_functions.last_mut() {
+                                scope.insert(name.clone(), function_index);
+                            }
+                            Some(slot)
+                        }
+                    }
+                } else {
+                    None
+                };
+                
                 // Сохраняем текущие локальные области видимости для доступа к переменным родительских функций
                 // Это нужно для поддержки замыканий - переменные из родительских функций должны быть доступны
                 let parent_locals_snapshot: Vec<std::collections::HashMap<String, usize>> = self.locals.iter()
@@ -769,13 +1312,19 @@ impl Compiler {
                     .collect();
                 
                 // Компилируем тело функции в chunk функции
-                let saved_chunk = std::mem::replace(&mut self.chunk, function.chunk.clone());
+                let saved_chunk = std::mem::replace(&mut self.chunk, (*function.chunk).clone());
                 let saved_exception_handlers = self.exception_handlers.clone();
                 let saved_error_type_table = self.error_type_table.clone();
                 let saved_function = self.current_function;
                 let saved_local_count = self.local_count;
+                let saved_function_scope_start = self.function_scope_start;
+                let saved_catch_depth = std::mem::take(&mut self.catch_depth);
+                // break/continue в теле функции не относятся к циклам, внутри которых она объявлена
+                let saved_loop_contexts = std::mem::take(&mut self.loop_contexts);
+                let saved_try_depth = std::mem::take(&mut self.try_depth);
                 self.current_function = Some(function_index);
                 self.local_count = 0;
+                self.function_scope_start = self.locals.len();
                 // Очищаем обработчики и таблицу типов ошибок для новой функции
                 self.exception_handlers.clear();
                 self.error_type_table.clear();
@@ -803,29 +1352,21 @@ impl Compiler {
                 for var_name in &captured_vars {
                     let local_slot_index = self.declare_local(var_name);
                     
-                    // Находим slot index в родительской функции и глубину предка
-                    // Ищем переменную в parent_locals_snapshot (от последней области к первой)
-                    // Последняя область - это самая внутренняя, которая соответствует текущему контексту
-                    // Это должна быть область видимости непосредственного родителя (родительской функции)
+                    // Находим slot index в родительской функции: ищем с самой внутренней области.
+                    // Захват транзитивен (тело включает вложенные функции), поэтому переменная
+                    // предка уже захвачена непосредственным родителем и лежит в его фрейме
                     let mut parent_slot_index = None;
-                    let mut ancestor_depth = 0;
-                    // Ищем в обратном порядке, чтобы найти в самой внутренней (последней) области сначала
-                    // parent_locals_snapshot[0] - самый внешний предок (например, outer)
-                    // parent_locals_snapshot[n-1] - ближайший родитель (например, middle)
                     
                     #[cfg(debug_assertions)]
                     eprintln!("[DEBUG COMPILER] Looking for captured var '{}' in {} parent scopes", 
                         var_name, parent_locals_snapshot.len());
                     
-                    for (depth, scope) in parent_locals_snapshot.iter().rev().enumerate() {
+                    for scope in parent_locals_snapshot.iter().rev() {
                         if let Some(&slot_idx) = scope.get(var_name) {
                             parent_slot_index = Some(slot_idx);
-                            // depth = 0 означает ближайший родитель, depth = 1 означает дедушку и т.д.
-                            ancestor_depth = depth;
                             
                             #[cfg(debug_assertions)]
-                            eprintln!("[DEBUG COMPILER] Found '{}' at depth {} (ancestor_depth={}), slot={}", 
-                                var_name, depth, ancestor_depth, slot_idx);
+                            eprintln!("[DEBUG COMPILER] Found '{}', slot={}", var_name, slot_idx);
                             
                             break;
                         }
@@ -844,14 +1385,13 @@ impl Compiler {
                     let parent_slot = parent_slot_index.unwrap();
                     
                     #[cfg(debug_assertions)]
-                    eprintln!("[DEBUG COMPILER] Captured var '{}': parent_slot={}, local_slot={}, ancestor_depth={}", 
-                        var_name, parent_slot, local_slot_index, ancestor_depth);
+                    eprintln!("[DEBUG COMPILER] Captured var '{}': parent_slot={}, local_slot={}", 
+                        var_name, parent_slot, local_slot_index);
                     
                     captured_vars_info.push(CapturedVar {
                         name: var_name.clone(),
                         parent_slot_index: parent_slot,
                         local_slot_index,
-                        ancestor_depth,
                     });
                 }
                 
@@ -860,6 +1400,9 @@ impl Compiler {
                     self.declare_local(&param.name);
                 }
                 
+                // Вложенные функции тела видны во всем теле: соседние функции могут вызывать друг друга
+                self.declare_local_functions(body);
+                
                 // Компилируем тело функции
                 // При компиляции переменных, resolve_local будет искать в текущих областях видимости,
                 // которые включают параметры функции. Если переменная не найдена, она будет искаться
@@ -885,7 +1428,7 @@ impl Compiler {
                 
                 // Сохраняем скомпилированную функцию (обработчики уже сохранены в chunk при компиляции try/catch)
                 let function_chunk = std::mem::replace(&mut self.chunk, saved_chunk);
-                function.chunk = function_chunk;
+                function.chunk = std::rc::Rc::new(function_chunk);
                 function.captured_vars = captured_vars_info;
                 self.functions[function_index] = function;
                 
@@ -894,19 +1437,33 @@ impl Compiler {
                 self.error_type_table = saved_error_type_table;
                 self.current_function = saved_function;
                 self.local_count = saved_local_count;
+                self.function_scope_start = saved_function_scope_start;
+                self.catch_depth = saved_catch_depth;
+                self.loop_contexts = saved_loop_contexts;
+                self.try_depth = saved_try_depth;
+                
+                if self.functions[function_index].captured_vars.is_empty() {
+                    // Сохраняем функцию как константу
+                    let constant_index = self.chunk.add_constant(Value::Function(function_index));
+                    self.chunk.write_with_line(OpCode::Constant(constant_index), *line);
+                } else {
+                    // Замыкание копирует значения захваченных переменных в момент объявления
+                    self.chunk.write_with_line(OpCode::MakeClosure(function_index), *line);
+                }
                 
-                // Сохраняем функцию в глобальную таблицу (уже сделано в первом проходе)
-                let global_index = *self.globals.get(name).unwrap();
-                
-                // Сохраняем имя глобальной переменной для использования в JOIN
-                self.chunk.global_names.insert(global_index, name.clone());
-                
-                // Сохраняем функцию как константу
-                let constant_index = self.chunk.add_constant(Value::Function(function_index));
-                
-                // Сохраняем функцию в глобальную переменную
-                self.chunk.write_with_line(OpCode::Constant(constant_index), *line);
-                self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
+                if let Some(slot) = local_slot {
+                    // Вложенная функция - значение локальной переменной, оно исчезает вместе с фреймом
+                    self.chunk.write_with_line(OpCode::StoreLocal(slot), *line);
+                } else {
+                    // Сохраняем функцию в глобальную таблицу (уже сделано в первом проходе)
+                    let global_index = *self.globals.get(name).unwrap();
+                    
+                    // Сохраняем имя глобальной переменной для использования в JOIN
+                    self.chunk.global_names.insert(global_index, name.clone());
+                    
+                    // Сохраняем функцию в глобальную переменную
+                    self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
+                }
             }
             Stmt::Return { value, line } => {
                 self.current_line = *line;
@@ -928,6 +1485,7 @@ impl Compiler {
                 }
                 // Jump к метке конца цикла
                 let break_label = self.loop_contexts.last().unwrap().break_label;
+                self.emit_leave_try_blocks(*line);
                 self.emit_jump(false, break_label)?;
             }
             Stmt::Continue { line } => {
@@ -940,19 +1498,34 @@ impl Compiler {
                 }
                 // Jump к метке continue
                 let continue_label = self.loop_contexts.last().unwrap().continue_label;
+                self.emit_leave_try_blocks(*line);
                 self.emit_jump(false, continue_label)?;
             }
-            Stmt::Throw { value, line } => {
+            Stmt::Throw { value: Some(value), line } => {
                 self.current_line = *line;
                 // Компилируем выражение (оно оставит значение на стеке)
                 self.compile_expr(value)?;
                 // Генерируем Throw опкод (None означает RuntimeError без конкретного типа)
                 self.chunk.write_with_line(OpCode::Throw(None), *line);
             }
+            Stmt::Throw { value: None, line } => {
+                self.current_line = *line;
+                if self.catch_depth == 0 {
+                    return Err(LangError::ParseError {
+                        message: "'throw' without a value is only allowed inside a catch block".to_string(),
+                        line: *line,
+                    });
+                }
+                self.chunk.write_with_line(OpCode::Rethrow, *line);
+            }
             Stmt::Try { try_block, catch_blocks, else_block, line } => {
                 self.current_line = *line;
                 self.compile_try(try_block, catch_blocks, else_block.as_deref(), *line)?;
             }
+            Stmt::Match { value, cases, default, line } => {
+                self.current_line = *line;
+                self.compile_match(value, cases, default.as_deref(), *line)?;
+            }
         }
         Ok(())
     }
@@ -963,17 +1536,45 @@ impl Compiler {
         &self,
         function_name: &str,
         args: &[Arg],
-        function_info: Option<(usize, &Function)>,
+        function_index: Option<usize>,
         line: usize,
     ) -> Result<Vec<Arg>, LangError> {
         // Если это встроенная функция, проверяем, поддерживает ли она именованные аргументы
-        if function_info.is_none() {
+        if function_index.is_none() {
             // Проверяем, есть ли именованные аргументы
             let has_named = args.iter().any(|a| matches!(a, Arg::Named { .. }));
             
             if has_named {
                 // Проверяем, поддерживает ли эта нативная функция именованные аргументы
-                if let Some(param_names) = self.get_native_function_params(function_name) {
+                if let Some(param_names) = Self::get_native_function_params(function_name) {
+                    // Вариативная функция (первый параметр "*имя"): позиционные аргументы передаются как есть,
+                    // именованные добавляются после них в порядке объявления параметров
+                    if param_names.first().is_some_and(|p| p.starts_with('*')) {
+                        let mut final_args: Vec<Arg> = args.iter()
+                            .filter(|a| matches!(a, Arg::Positional(_)))
+                            .cloned()
+                            .collect();
+                        for arg in args {
+                            if let Arg::Named { name, .. } = arg {
+                                if !param_names[1..].contains(name) {
+                                    return Err(LangError::ParseError {
+                                        message: format!(
+                                            "Function '{}' got an unexpected keyword argument '{}'",
+                                            function_name, name
+                                        ),
+                                        line,
+                                    });
+                                }
+                            }
+                        }
+                        for param in &param_names[1..] {
+                            if let Some(Arg::Named { value, .. }) = args.iter().find(|a| matches!(a, Arg::Named { name, .. } if name == param)) {
+                                final_args.push(Arg::Positional(value.clone()));
+                            }
+                        }
+                        return Ok(final_args);
+                    }
+                    
                     // Нативная функция поддерживает именованные аргументы
                     // Разрешаем их аналогично пользовательским функциям
                     let mut resolved = vec![None; param_names.len()];
@@ -1025,14 +1626,15 @@ impl Compiler {
                     }
                     
                     // Собираем итоговый список аргументов в правильном порядке
-                    // Включаем только предоставленные параметры (нативные функции сами обрабатывают опциональные)
+                    // Пропущенные параметры перед последним переданным заполняются null, чтобы
+                    // именованные аргументы попадали на свои позиции (нативные функции сами обрабатывают опциональные)
+                    let provided_count = resolved.iter().rposition(|a| a.is_some()).map_or(0, |last| last + 1);
                     let mut final_args = Vec::new();
-                    for i in 0..param_names.len() {
-                        if let Some(arg) = resolved[i].take() {
-                            match arg {
-                                Arg::Positional(expr) => final_args.push(Arg::Positional(expr)),
-                                Arg::Named { value, .. } => final_args.push(Arg::Positional(value)),
-                            }
+                    for arg in resolved.into_iter().take(provided_count) {
+                        match arg {
+                            Some(Arg::Positional(expr)) => final_args.push(Arg::Positional(expr)),
+                            Some(Arg::Named { value, .. }) => final_args.push(Arg::Positional(value)),
+                            None => final_args.push(Arg::Positional(Expr::Literal { value: Value::Null, line })),
                         }
                     }
                     
@@ -1056,7 +1658,7 @@ impl Compiler {
             }
         }
         
-        let (_, function) = function_info.unwrap();
+        let function = &self.functions[function_index.unwrap()];
         let param_names = &function.param_names;
         let default_values = &function.default_values;
         
@@ -1146,7 +1748,83 @@ impl Compiler {
         Ok(final_args)
     }
 
+    /// Загружает вызываемую функцию: сначала проверяем переменные (локальные или глобальные),
+    /// затем ищем функцию по имени
+    fn compile_callee(&mut self, name: &str) -> Result<(), LangError> {
+        if let Some((function_index, true)) = self.resolve_local_function(name) {
+            // Вложенная функция объемлющей функции известна при компиляции
+            let constant_index = self.chunk.add_constant(Value::Function(function_index));
+            self.chunk.write_with_line(OpCode::Constant(constant_index), self.current_line);
+        } else if let Some(local_index) = self.resolve_local(name) {
+            // Локальная переменная содержит функцию
+            self.chunk.write_with_line(OpCode::LoadLocal(local_index), self.current_line);
+        } else if let Some(&global_index) = self.globals.get(name) {
+            // Глобальная переменная содержит функцию
+            self.chunk.write_with_line(OpCode::LoadGlobal(global_index), self.current_line);
+        } else {
+            // Ищем функцию по имени в списке функций
+            if let Some(function_index) = self.global_function_index(name) {
+                let constant_index = self.chunk.add_constant(Value::Function(function_index));
+                self.chunk.write_with_line(OpCode::Constant(constant_index), self.current_line);
+            } else {
+                return Err(self.function_not_found(name, self.current_line));
+            }
+        }
+        Ok(())
+    }
+
+    /// Вызов со spread: f(a, ...args, key = v, ...options). Значения ...spread известны только
+    /// во время выполнения, поэтому аргументы собираются на стеке в массив позиционных и объект
+    /// именованных, а CallSpread привязывает их к параметрам функции по именам
+    fn compile_spread_call(&mut self, name: &str, args: &[Arg], line: usize) -> Result<(), LangError> {
+        self.chunk.write_with_line(OpCode::MakeArray(0), line);
+        self.chunk.write_with_line(OpCode::MakeObject(0), line);
+        // Подряд идущие позиционные (или именованные) аргументы добавляются одним SpreadArg,
+        // порядок вычисления аргументов сохраняется
+        let mut index = 0;
+        while index < args.len() {
+            match &args[index] {
+                Arg::Positional(Expr::Spread { value, .. }) => {
+                    self.compile_expr(value)?;
+                    index += 1;
+                }
+                Arg::Positional(_) => {
+                    let start = index;
+                    while let Some(Arg::Positional(expr)) = args.get(index) {
+                        if matches!(expr, Expr::Spread { .. }) {
+                            break;
+                        }
+                        self.compile_expr(expr)?;
+                        index += 1;
+                    }
+                    self.chunk.write_with_line(OpCode::MakeArray(index - start), line);
+                }
+                Arg::Named { .. } => {
+                    let start = index;
+                    while let Some(Arg::Named { name, value }) = args.get(index) {
+                        let key_index = self.chunk.add_constant(Value::String(name.as_str().into()));
+                        self.chunk.write_with_line(OpCode::Constant(key_index), line);
+                        self.compile_expr(value)?;
+                        index += 1;
+                    }
+                    self.chunk.write_with_line(OpCode::MakeObject(index - start), line);
+                }
+            }
+            self.chunk.write_with_line(OpCode::SpreadArg, line);
+        }
+        self.current_line = line;
+        self.compile_callee(name)?;
+        self.chunk.write_with_line(OpCode::CallSpread, line);
+        Ok(())
+    }
+
     fn compile_expr(&mut self, expr: &Expr) -> Result<(), LangError> {
+        // Дерево длинной цепочки операторов (a + b + ... + z) глубже, чем вложенность скобок,
+        // а кадр compile_expr велик: при нехватке стека компиляция продолжается на новом сегменте
+        stacker::maybe_grow(COMPILER_RED_ZONE, COMPILER_STACK_SIZE, || self.compile_expr_inner(expr))
+    }
+
+    fn compile_expr_inner(&mut self, expr: &Expr) -> Result<(), LangError> {
         let expr_line = expr.line();
         self.current_line = expr_line;
         
@@ -1167,28 +1845,9 @@ impl Compiler {
                 self.compile_expr(value)?;
                 // Не клонируем автоматически - переменные должны разделять ссылки на массивы/таблицы/объекты
                 // Клонирование происходит только при явном вызове .clone()
-                // Проверяем, является ли переменная локальной или глобальной
-                if let Some(local_index) = self.resolve_local(name) {
-                    // Локальная переменная найдена - обновляем
-                    self.chunk.write_with_line(OpCode::StoreLocal(local_index), *line);
-                    self.chunk.write_with_line(OpCode::LoadLocal(local_index), *line);
-                } else if self.current_function.is_some() {
-                    // Мы находимся внутри функции - создаем локальную переменную, которая скрывает глобальную
-                    let index = self.declare_local(name);
-                    self.chunk.write_with_line(OpCode::StoreLocal(index), *line);
-                    self.chunk.write_with_line(OpCode::LoadLocal(index), *line);
-                } else if let Some(&global_index) = self.globals.get(name) {
-                    // Мы в главной функции, глобальная переменная найдена - обновляем
-                    // Сохраняем имя глобальной переменной для использования в JOIN
-                    self.chunk.global_names.insert(global_index, name.clone());
-                    self.chunk.write_with_line(OpCode::StoreGlobal(global_index), *line);
-                    self.chunk.write_with_line(OpCode::LoadGlobal(global_index), *line);
-                } else {
-                    // Переменная не найдена ни локально, ни глобально - создаем новую локальную переменную
-                    let index = self.declare_local(name);
-                    self.chunk.write_with_line(OpCode::StoreLocal(index), *line);
-                    self.chunk.write_with_line(OpCode::LoadLocal(index), *line);
-                }
+                // Присваивание - выражение: загружаем значение обратно
+                let reload = self.compile_assign_store(name, *line);
+                self.chunk.write_with_line(reload, *line);
             }
             Expr::AssignOp { name, op, value, line } => {
                 // Оператор присваивания: a += b эквивалентно a = a + b
@@ -1235,53 +1894,8 @@ impl Compiler {
                     self.chunk.write_with_line(OpCode::StoreLocal(local_index), *line);
                     // Загружаем значение обратно, чтобы присваивание возвращало значение
                     self.chunk.write_with_line(OpCode::LoadLocal(local_index), *line);
-                } else if self.current_function.is_some() {
-                    // Мы находимся внутри функции - создаем локальную переменную, которая скрывает глобальную
-                    let index = self.declare_local(name);
-                    // Загружаем 0 как начальное значение
-                    let zero_index = self.chunk.add_constant(Value::Number(0.0));
-                    self.chunk.write_with_line(OpCode::Constant(zero_index), *line);
-                    
-                    // Компилируем правую часть
-                    self.compile_expr(value)?;
-                    
-                    // Выполняем операцию
-                    match op {
-                        TokenKind::PlusEqual => {
-                            self.chunk.write_with_line(OpCode::Add, *line);
-                        }
-                        TokenKind::MinusEqual => {
-                            self.chunk.write_with_line(OpCode::Sub, *line);
-                        }
-                        TokenKind::StarEqual => {
-                            self.chunk.write_with_line(OpCode::Mul, *line);
-                        }
-                        TokenKind::StarStarEqual => {
-                            self.chunk.write_with_line(OpCode::Pow, *line);
-                        }
-                        TokenKind::SlashEqual => {
-                            self.chunk.write_with_line(OpCode::Div, *line);
-                        }
-                        TokenKind::SlashSlashEqual => {
-                            self.chunk.write_with_line(OpCode::IntDiv, *line);
-                        }
-                        TokenKind::PercentEqual => {
-                            self.chunk.write_with_line(OpCode::Mod, *line);
-                        }
-                        _ => {
-                            return Err(LangError::ParseError {
-                                message: format!("Unknown assignment operator: {:?}", op),
-                                line: *line,
-                            });
-                        }
-                    }
-                    
-                    // Сохраняем результат обратно в локальную переменную
-                    self.chunk.write_with_line(OpCode::StoreLocal(index), *line);
-                    // Загружаем значение обратно, чтобы присваивание возвращало значение
-                    self.chunk.write_with_line(OpCode::LoadLocal(index), *line);
-                } else if let Some(&global_index) = self.globals.get(name) {
-                    // Мы в главной функции, глобальная переменная найдена - загружаем её значение
+                } else if let Some(global_index) = self.assignable_global(name) {
+                    // Глобальная переменная найдена (в главной функции или внутри функции) - загружаем её значение
                     self.chunk.write_with_line(OpCode::LoadGlobal(global_index), *line);
                     
                     // Компилируем правую часть
@@ -1373,7 +1987,11 @@ impl Compiler {
             }
             Expr::Variable { name, line } => {
                 // Определяем, глобальная или локальная переменная
-                if let Some(local_index) = self.resolve_local(name) {
+                if let Some((function_index, true)) = self.resolve_local_function(name) {
+                    // Вложенная функция объемлющей функции как значение
+                    let constant_index = self.chunk.add_constant(Value::Function(function_index));
+                    self.chunk.write_with_line(OpCode::Constant(constant_index), *line);
+                } else if let Some(local_index) = self.resolve_local(name) {
                     // Локальная переменная
                     self.chunk.write_with_line(OpCode::LoadLocal(local_index), *line);
                 } else if let Some(&global_index) = self.globals.get(name) {
@@ -1395,8 +2013,8 @@ impl Compiler {
                         self.compile_expr(right)?;
                         self.chunk.write_with_line(OpCode::Negate, *line);
                     }
-                    TokenKind::Bang => {
-                        // Унарный Bang: !value (логическое отрицание)
+                    TokenKind::Bang | TokenKind::Not => {
+                        // Унарный Bang: !value / not value (логическое отрицание)
                         self.compile_expr(right)?;
                         self.chunk.write_with_line(OpCode::Not, *line);
                     }
@@ -1408,6 +2026,51 @@ impl Compiler {
                     }
                 }
             }
+            Expr::ChainedComparison { operands, ops, line } => {
+                self.current_line = *line;
+                // a < b < c: средние операнды вычисляются один раз и хранятся в скрытой переменной,
+                // первое ложное сравнение завершает цепочку (как and)
+                self.begin_scope();
+                let operand_local = self.declare_local("__chain_operand");
+                let end_label = self.create_label();
+                self.compile_expr(&operands[0])?;
+                for (i, op) in ops.iter().enumerate() {
+                    let is_last = i + 1 == ops.len();
+                    self.compile_expr(&operands[i + 1])?;
+                    if !is_last {
+                        self.chunk.write_with_line(OpCode::Dup, *line);
+                        self.chunk.write_with_line(OpCode::StoreLocal(operand_local), *line);
+                    }
+                    let opcode = match op {
+                        TokenKind::Greater => OpCode::Greater,
+                        TokenKind::GreaterEqual => OpCode::GreaterEqual,
+                        TokenKind::Less => OpCode::Less,
+                        _ => OpCode::LessEqual,
+                    };
+                    self.chunk.write_with_line(opcode, *line);
+                    if !is_last {
+                        self.chunk.write_with_line(OpCode::Dup, *line);
+                        self.emit_jump(true, end_label)?;
+                        self.chunk.write_with_line(OpCode::Pop, *line);
+                        self.chunk.write_with_line(OpCode::LoadLocal(operand_local), *line);
+                    }
+                }
+                self.mark_label(end_label);
+                self.end_scope();
+            }
+            Expr::Ternary { condition, then_branch, else_branch, line } => {
+                self.current_line = *line;
+                // Вычисляется только выбранная ветка
+                let else_label = self.create_label();
+                let end_label = self.create_label();
+                self.compile_expr(condition)?;
+                self.emit_jump(true, else_label)?;
+                self.compile_expr(then_branch)?;
+                self.emit_jump(false, end_label)?;
+                self.mark_label(else_label);
+                self.compile_expr(else_branch)?;
+                self.mark_label(end_label);
+            }
             Expr::Binary { left, op, right, line } => {
                 self.current_line = *line;
                 // Специальная обработка логических операторов
@@ -1419,6 +2082,25 @@ impl Compiler {
                     self.compile_expr(left)?;
                     self.compile_expr(right)?;
                     self.chunk.write_with_line(OpCode::NotEqual, *line);
+                } else if *op == TokenKind::And || *op == TokenKind::Or {
+                    // Короткое вычисление: правая часть вычисляется, только если левой недостаточно.
+                    // Результат - значение левого или правого операнда, как в Python
+                    let end_label = self.create_label();
+                    self.compile_expr(left)?;
+                    self.chunk.write_with_line(OpCode::Dup, *line);
+                    if *op == TokenKind::And {
+                        // Левый операнд ложен - он и есть результат
+                        self.emit_jump(true, end_label)?;
+                    } else {
+                        // Левый операнд истинен - он и есть результат
+                        let right_label = self.create_label();
+                        self.emit_jump(true, right_label)?;
+                        self.emit_jump(false, end_label)?;
+                        self.mark_label(right_label);
+                    }
+                    self.chunk.write_with_line(OpCode::Pop, *line);
+                    self.compile_expr(right)?;
+                    self.mark_label(end_label);
                 } else {
                     self.compile_expr(left)?;
                     self.compile_expr(right)?;
@@ -1430,13 +2112,16 @@ impl Compiler {
                         TokenKind::Slash => self.chunk.write_with_line(OpCode::Div, *line),
                         TokenKind::SlashSlash => self.chunk.write_with_line(OpCode::IntDiv, *line),
                         TokenKind::Percent => self.chunk.write_with_line(OpCode::Mod, *line),
+                        TokenKind::Ampersand => self.chunk.write_with_line(OpCode::BitAnd, *line),
+                        TokenKind::Pipe => self.chunk.write_with_line(OpCode::BitOr, *line),
+                        TokenKind::Caret => self.chunk.write_with_line(OpCode::BitXor, *line),
+                        TokenKind::LessLess => self.chunk.write_with_line(OpCode::ShiftLeft, *line),
+                        TokenKind::GreaterGreater => self.chunk.write_with_line(OpCode::ShiftRight, *line),
                         TokenKind::Greater => self.chunk.write_with_line(OpCode::Greater, *line),
                         TokenKind::Less => self.chunk.write_with_line(OpCode::Less, *line),
                         TokenKind::GreaterEqual => self.chunk.write_with_line(OpCode::GreaterEqual, *line),
                         TokenKind::LessEqual => self.chunk.write_with_line(OpCode::LessEqual, *line),
                         TokenKind::In => self.chunk.write_with_line(OpCode::In, *line),
-                        TokenKind::Or => self.chunk.write_with_line(OpCode::Or, *line),
-                        TokenKind::And => self.chunk.write_with_line(OpCode::And, *line),
                         _ => {
                             return Err(LangError::ParseError {
                                 message: format!("Unknown binary operator: {:?}", op),
@@ -1446,18 +2131,26 @@ impl Compiler {
                     }
                 }
             }
+            Expr::Call { name, args, line } if args.iter().any(|a| matches!(a, Arg::Positional(Expr::Spread { .. }))) => {
+                self.current_line = *line;
+                self.compile_spread_call(name, args, *line)?;
+            }
             Expr::Call { name, args, line } => {
                 self.current_line = *line;
                 
                 // Находим функцию для получения информации о параметрах
-                let function_info = if let Some(function_index) = self.function_names.iter().position(|n| n == name) {
-                    Some((function_index, &self.functions[function_index]))
-                } else {
-                    None
-                };
+                let function_index = self.resolve_local_function(name)
+                    .map(|(function_index, _)| function_index)
+                    .or_else(|| self.global_function_index(name));
                 
                 // Разрешаем аргументы: именованные -> позиционные, применяем значения по умолчанию
-                let resolved_args = self.resolve_function_args(name, args, function_info, *line)?;
+                let resolved_args = self.resolve_function_args(name, args, function_index, *line)?;
+                let is_builtin = function_index.is_none()
+                    && self.resolve_local(name).is_none()
+                    && self.globals.get(name).is_some_and(|&index| index < self.builtin_count);
+                if is_builtin {
+                    self.check_native_arg_count(name, resolved_args.len(), *line)?;
+                }
                 
                 // Специальная обработка для isinstance: преобразуем идентификаторы типов в строки
                 let processed_args = if name == "isinstance" && resolved_args.len() >= 2 {
@@ -1466,7 +2159,7 @@ impl Compiler {
                         let type_names = vec!["int", "str", "bool", "array", "null", "num", "float"];
                         if type_names.contains(&type_name.as_str()) {
                             new_args[1] = Arg::Positional(Expr::Literal {
-                                value: Value::String(type_name.clone()),
+                                value: Value::String(type_name.as_str().into()),
                                 line: match &resolved_args[1] {
                                     Arg::Positional(e) => e.line(),
                                     Arg::Named { value, .. } => value.line(),
@@ -1479,8 +2172,24 @@ impl Compiler {
                     resolved_args
                 };
                 
+                // eval()/exec() получают скрытым последним аргументом карту видимых локальных
+                // переменных (имя → слот), чтобы код из строки мог читать и изменять их
+                let processed_args = if (name == "eval" || name == "exec")
+                    && function_index.is_none()
+                    && self.resolve_local(name).is_none()
+                {
+                    let mut new_args = processed_args;
+                    new_args.push(Arg::Positional(Expr::Literal {
+                        value: self.visible_locals(),
+                        line: *line,
+                    }));
+                    new_args
+                } else {
+                    processed_args
+                };
+                
                 // Специальная обработка для функций, которые модифицируют первый аргумент in-place
-                let in_place_functions = vec!["push", "reverse", "sort"];
+                let in_place_functions = vec!["push", "sort"];
                 let should_assign_back = in_place_functions.contains(&name.as_str()) 
                     && !processed_args.is_empty()
                     && matches!(&processed_args[0], Arg::Positional(Expr::Variable { .. }));
@@ -1509,26 +2218,7 @@ impl Compiler {
                     }
                 }
                 
-                // Загружаем функцию: сначала проверяем переменные (локальные или глобальные),
-                // затем ищем функцию по имени
-                if let Some(local_index) = self.resolve_local(name) {
-                    // Локальная переменная содержит функцию
-                    self.chunk.write_with_line(OpCode::LoadLocal(local_index), self.current_line);
-                } else if let Some(&global_index) = self.globals.get(name) {
-                    // Глобальная переменная содержит функцию
-                    self.chunk.write_with_line(OpCode::LoadGlobal(global_index), self.current_line);
-                } else {
-                    // Ищем функцию по имени в списке функций
-                    if let Some(function_index) = self.function_names.iter().position(|n| n == name) {
-                        let constant_index = self.chunk.add_constant(Value::Function(function_index));
-                        self.chunk.write_with_line(OpCode::Constant(constant_index), self.current_line);
-                    } else {
-                        return Err(LangError::ParseError {
-                            message: format!("Function '{}' not found", name),
-                            line: self.current_line,
-                        });
-                    }
-                }
+                self.compile_callee(name)?;
                 
                 // Вызываем функцию с количеством аргументов
                 self.chunk.write_with_line(OpCode::Call(processed_args.len()), *line);
@@ -1561,6 +2251,28 @@ impl Compiler {
                     }
                 }
             }
+            Expr::ArrayLiteral { elements, line } if elements.iter().any(|e| matches!(e, Expr::Spread { .. })) => {
+                // [...a, x, ...b]: подряд идущие элементы собираются в массив, к накопленному
+                // массиву он и раскрываемые значения добавляются через Extend
+                self.chunk.write_with_line(OpCode::MakeArray(0), *line);
+                for group in elements.split_inclusive(|e| matches!(e, Expr::Spread { .. })) {
+                    let (plain, spread) = match group.split_last() {
+                        Some((Expr::Spread { value, .. }, plain)) => (plain, Some(value)),
+                        _ => (group, None),
+                    };
+                    if !plain.is_empty() {
+                        for element in plain {
+                            self.compile_expr(element)?;
+                        }
+                        self.chunk.write_with_line(OpCode::MakeArray(plain.len()), *line);
+                        self.chunk.write_with_line(OpCode::Extend, *line);
+                    }
+                    if let Some(value) = spread {
+                        self.compile_expr(value)?;
+                        self.chunk.write_with_line(OpCode::Extend, *line);
+                    }
+                }
+            }
             Expr::ArrayLiteral { elements, line } => {
                 // Компилируем каждый элемент массива
                 for element in elements {
@@ -1570,6 +2282,44 @@ impl Compiler {
                 let arity = elements.len();
                 self.chunk.write_with_line(OpCode::MakeArray(arity), *line);
             }
+            Expr::ObjectLiteral { entries, line } if entries.iter().any(|(_, e)| matches!(e, Expr::Spread { .. })) => {
+                // {...defaults, "x": 1}: ключи объектов добавляются по порядку, последний побеждает
+                self.chunk.write_with_line(OpCode::MakeObject(0), *line);
+                for group in entries.split_inclusive(|(_, e)| matches!(e, Expr::Spread { .. })) {
+                    let (plain, spread) = match group.split_last() {
+                        Some(((_, Expr::Spread { value, .. }), plain)) => (plain, Some(value)),
+                        _ => (group, None),
+                    };
+                    if !plain.is_empty() {
+                        for (key, value) in plain {
+                            let key_index = self.chunk.add_constant(Value::String(key.as_str().into()));
+                            self.chunk.write_with_line(OpCode::Constant(key_index), *line);
+                            self.compile_expr(value)?;
+                        }
+                        self.chunk.write_with_line(OpCode::MakeObject(plain.len()), *line);
+                        self.chunk.write_with_line(OpCode::Extend, *line);
+                    }
+                    if let Some(value) = spread {
+                        self.compile_expr(value)?;
+                        self.chunk.write_with_line(OpCode::Extend, *line);
+                    }
+                }
+            }
+            Expr::Spread { line, .. } => {
+                return Err(LangError::ParseError {
+                    message: "Spread '...' is only allowed in function call arguments and array or object literals".to_string(),
+                    line: *line,
+                });
+            }
+            Expr::ObjectLiteral { entries, line } => {
+                // Для каждой пары на стек кладутся ключ и значение
+                for (key, value) in entries {
+                    let key_index = self.chunk.add_constant(Value::String(key.as_str().into()));
+                    self.chunk.write_with_line(OpCode::Constant(key_index), *line);
+                    self.compile_expr(value)?;
+                }
+                self.chunk.write_with_line(OpCode::MakeObject(entries.len()), *line);
+            }
             Expr::ArrayIndex { array, index, line } => {
                 // Компилируем выражение массива (оно должно быть на стеке первым)
                 self.compile_expr(array)?;
@@ -1585,7 +2335,7 @@ impl Compiler {
                 // Затем при индексации [i] это будет обработано как table[i]
                 if name != "idx" {
                     // Для других свойств создаем строку и используем индексацию
-                    let name_index = self.chunk.add_constant(Value::String(name.clone()));
+                    let name_index = self.chunk.add_constant(Value::String(name.as_str().into()));
                     self.chunk.write_with_line(OpCode::Constant(name_index), *line);
                     self.chunk.write_with_line(OpCode::GetArrayElement, *line);
                 }
@@ -1737,10 +2487,7 @@ impl Compiler {
                         // Вызываем функцию с количеством аргументов (object + args)
                         self.chunk.write_with_line(OpCode::Call(args.len() + 1), *line);
                     } else {
-                        return Err(LangError::ParseError {
-                            message: format!("Function '{}' not found", function_name),
-                            line: *line,
-                        });
+                        return Err(self.function_not_found(function_name, *line));
                     }
                 } else {
                     // Для других методов пока не поддерживаем
@@ -1768,7 +2515,11 @@ impl Compiler {
             OpCode::LoadLocal(_) | OpCode::StoreLocal(_) => 2, // 1 байт opcode + 1 байт индекс
             OpCode::LoadGlobal(_) | OpCode::StoreGlobal(_) => 2, // 1 байт opcode + 1 байт индекс
             OpCode::Call(_) => 2, // 1 байт opcode + 1 байт количество аргументов
+            OpCode::MakeClosure(_) => 2, // 1 байт opcode + 1 байт индекс функции
             OpCode::MakeArray(_) => 2, // 1 байт opcode + 1 байт количество элементов
+            OpCode::MakeObject(_) => 2, // 1 байт opcode + 1 байт количество пар
+            OpCode::Unpack(_) => 2, // 1 байт opcode + 1 байт количество элементов
+            OpCode::LoopProgress(_, _) => 3, // 1 байт opcode + 2 байта слотов
             OpCode::BeginTry(_) => 2, // 1 байт opcode + 1 байт индекс обработчика
             OpCode::Catch(Some(_)) => 2, // 1 байт opcode + 1 байт тип ошибки
             OpCode::Catch(None) => 1, // 1 байт opcode
@@ -2061,24 +2812,155 @@ impl Compiler {
 
     fn begin_scope(&mut self) {
         self.locals.push(std::collections::HashMap::new());
+        self.local_functions.push(std::collections::HashMap::new());
     }
 
     fn end_scope(&mut self) {
+        self.local_functions.pop();
         if let Some(scope) = self.locals.pop() {
             // Уменьшаем счетчик локальных переменных на количество переменных в этой области
             self.local_count -= scope.len();
         }
     }
 
+    /// Сохраняет значение с вершины стека в переменную let/global объявления
+    fn compile_let_store(&mut self, name: &str, is_global: bool, line: usize) {
+        if is_global {
+            // Явное объявление глобальной переменной
+            // Всегда изменяет глобальную переменную, даже внутри функций
+            let global_index = if let Some(&idx) = self.globals.get(name) {
+                idx
+            } else {
+                let idx = self.globals.len();
+                self.globals.insert(name.to_string(), idx);
+                idx
+            };
+            // Сохраняем имя глобальной переменной для использования в JOIN
+            self.chunk.global_names.insert(global_index, name.to_string());
+            // Сохраняем имя явно объявленной глобальной переменной для экспорта в SQLite
+            self.chunk.explicit_global_names.insert(global_index, name.to_string());
+            self.chunk.write_with_line(OpCode::StoreGlobal(global_index), line);
+        } else {
+            // Локальная переменная или глобальная на верхнем уровне
+            if self.current_function.is_some() {
+                // Внутри функции let привязывает имя к текущему блоку: повторный let в том же
+                // блоке обновляет переменную, во вложенном блоке - создает новую, скрывая внешнюю
+                let current_scope_index = self.locals.last().and_then(|scope| scope.get(name)).copied();
+                let index = match current_scope_index {
+                    Some(local_index) => local_index,
+                    None => self.declare_local(name),
+                };
+                self.chunk.write_with_line(OpCode::StoreLocal(index), line);
+            } else if let Some(local_index) = self.resolve_local(name) {
+                // Локальная переменная уже объявлена - обновляем
+                self.chunk.write_with_line(OpCode::StoreLocal(local_index), line);
+            } else {
+                // Переменная не найдена локально - проверяем, является ли она глобальной
+                // На верхнем уровне главной функции переменные без 'global' все равно глобальные
+                if let Some(&global_index) = self.globals.get(name) {
+                    // Глобальная переменная уже существует - обновляем
+                    // Сохраняем имя глобальной переменной для использования в JOIN
+                    self.chunk.global_names.insert(global_index, name.to_string());
+                    self.chunk.write_with_line(OpCode::StoreGlobal(global_index), line);
+                } else {
+                    // Новая глобальная переменная на верхнем уровне
+                    let global_index = self.globals.len();
+                    self.globals.insert(name.to_string(), global_index);
+                    // Сохраняем имя глобальной переменной для использования в JOIN
+                    self.chunk.global_names.insert(global_index, name.to_string());
+                    self.chunk.write_with_line(OpCode::StoreGlobal(global_index), line);
+                }
+            }
+        }
+    }
+
+    /// Сохраняет значение с вершины стека в переменную присваивания `name = ...`.
+    /// Возвращает инструкцию, загружающую присвоенное значение обратно
+    fn compile_assign_store(&mut self, name: &str, line: usize) -> OpCode {
+        // Проверяем, является ли переменная локальной или глобальной
+        if let Some(local_index) = self.resolve_local(name) {
+            // Локальная переменная найдена - обновляем
+            self.chunk.write_with_line(OpCode::StoreLocal(local_index), line);
+            OpCode::LoadLocal(local_index)
+        } else if let Some(global_index) = self.assignable_global(name) {
+            // Глобальная переменная найдена - обновляем ее (в том числе из функции:
+            // локальная переменная с тем же именем объявляется только через let)
+            // Сохраняем имя глобальной переменной для использования в JOIN
+            self.chunk.global_names.insert(global_index, name.to_string());
+            self.chunk.write_with_line(OpCode::StoreGlobal(global_index), line);
+            OpCode::LoadGlobal(global_index)
+        } else {
+            // Переменная не найдена ни локально, ни глобально - создаем новую локальную переменную
+            let index = self.declare_local(name);
+            self.chunk.write_with_line(OpCode::StoreLocal(index), line);
+            OpCode::LoadLocal(index)
+        }
+    }
+
     fn declare_local(&mut self, name: &str) -> usize {
         let index = self.local_count;
         if let Some(scope) = self.locals.last_mut() {
             scope.insert(name.to_string(), index);
         }
+        // Переменная с тем же именем скрывает вложенную функцию этой области
+        if let Some(scope) = self.local_functions.last_mut() {
+            scope.remove(name);
+        }
         self.local_count += 1;
         index
     }
 
+    /// Собирает локальные переменные текущей функции (имя → слот) для eval()/exec()
+    fn visible_locals(&self) -> Value {
+        let mut visible = crate::common::value::ObjectMap::new();
+        let start = self.function_scope_start.min(self.locals.len());
+        // Внутренние области видимости перекрывают внешние
+        for scope in &self.locals[start..] {
+            for (name, &slot) in scope {
+                visible.insert(name.clone(), Value::Number(slot as f64));
+            }
+        }
+        Value::Object(visible)
+    }
+
+    /// Вложенная функция, на которую указывает ближайшее локальное имя: (индекс функции,
+    /// объявлена ли она в объемлющей функции, а не в текущей)
+    fn resolve_local_function(&self, name: &str) -> Option<(usize, bool)> {
+        let index = self.locals.iter().rposition(|scope| scope.contains_key(name))?;
+        let function_index = *self.local_functions.get(index)?.get(name)?;
+        Some((function_index, index < self.function_scope_start))
+    }
+
+    /// Заранее объявляет слоты вложенных функций, объявленных непосредственно в теле функции
+    fn declare_local_functions(&mut self, body: &[Stmt]) {
+        for stmt in body {
+            if let Stmt::Function { name, is_global: false, line, .. } = stmt {
+                if let Some(&function_index) = self.declared_local_functions.get(&(name.clone(), *line)) {
+                    self.declare_local(name);
+                    if let Some(scope) = self.local_functions.last_mut() {
+                        scope.insert(name.clone(), function_index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Функция верхнего уровня или объявленная через global fn
+    /// Глобальный слот, в который присваивание `name = ...` может записать значение.
+    /// Внутри функции встроенные и глобальные функции не перезаписываются:
+    /// `sum = 0` в теле функции объявляет локальную переменную, а не затирает sum()
+    fn assignable_global(&self, name: &str) -> Option<usize> {
+        let &index = self.globals.get(name)?;
+        if self.current_function.is_some() && (index < self.builtin_count || self.global_function_index(name).is_some()) {
+            return None;
+        }
+        Some(index)
+    }
+
+    fn global_function_index(&self, name: &str) -> Option<usize> {
+        self.functions.iter().position(|f| f.name == name && !f.is_local)
+    }
+
     fn resolve_local(&mut self, name: &str) -> Option<usize> {
         // Ищем переменную в текущих областях видимости (от последней к первой)
         for scope in self.locals.iter().rev() {
@@ -2089,8 +2971,23 @@ impl Compiler {
         None
     }
     
+    /// Первая встроенная функция из IMPURE_BUILTINS, которую вызывает (или передает как значение)
+    /// тело функции, включая вложенные функции. Параметры с тем же именем ее перекрывают
+    fn find_impure_builtin(&self, params: &[Param], body: &[Stmt]) -> Option<&'static str> {
+        let used: std::collections::HashSet<String> =
+            body.iter().flat_map(|stmt| self.find_used_variables_in_stmt(stmt)).collect();
+        IMPURE_BUILTINS
+            .iter()
+            .copied()
+            .find(|name| used.contains(*name) && !params.iter().any(|param| param.name == *name))
+    }
+
     /// Находит все переменные, используемые в выражениях и statements
     fn find_used_variables_in_expr(&self, expr: &Expr) -> std::collections::HashSet<String> {
+        stacker::maybe_grow(COMPILER_RED_ZONE, COMPILER_STACK_SIZE, || self.find_used_variables_in_expr_inner(expr))
+    }
+
+    fn find_used_variables_in_expr_inner(&self, expr: &Expr) -> std::collections::HashSet<String> {
         let mut vars = std::collections::HashSet::new();
         match expr {
             Expr::Variable { name, .. } => {
@@ -2107,7 +3004,19 @@ impl Compiler {
             Expr::Unary { right, .. } => {
                 vars.extend(self.find_used_variables_in_expr(right));
             }
-            Expr::Call { args, .. } => {
+            Expr::ChainedComparison { operands, .. } => {
+                for operand in operands {
+                    vars.extend(self.find_used_variables_in_expr(operand));
+                }
+            }
+            Expr::Ternary { condition, then_branch, else_branch, .. } => {
+                vars.extend(self.find_used_variables_in_expr(condition));
+                vars.extend(self.find_used_variables_in_expr(then_branch));
+                vars.extend(self.find_used_variables_in_expr(else_branch));
+            }
+            Expr::Call { name, args, .. } => {
+                // Имя вызова тоже может быть переменной родительской функции
+                vars.insert(name.clone());
                 for arg in args {
                     match arg {
                         Arg::Positional(expr) => {
@@ -2124,6 +3033,14 @@ impl Compiler {
                     vars.extend(self.find_used_variables_in_expr(elem));
                 }
             }
+            Expr::ObjectLiteral { entries, .. } => {
+                for (_, value) in entries {
+                    vars.extend(self.find_used_variables_in_expr(value));
+                }
+            }
+            Expr::Spread { value, .. } => {
+                vars.extend(self.find_used_variables_in_expr(value));
+            }
             Expr::ArrayIndex { array, index, .. } => {
                 vars.extend(self.find_used_variables_in_expr(array));
                 vars.extend(self.find_used_variables_in_expr(index));
@@ -2156,6 +3073,13 @@ impl Compiler {
             Stmt::Let { value, .. } => {
                 vars.extend(self.find_used_variables_in_expr(value));
             }
+            Stmt::Destructure { names, value, kind, .. } => {
+                // Как у Expr::Assign: присваивание может менять захваченную переменную
+                if *kind == BindingKind::Assign {
+                    vars.extend(names.iter().cloned());
+                }
+                vars.extend(self.find_used_variables_in_expr(value));
+            }
             Stmt::Expr { expr, .. } => {
                 vars.extend(self.find_used_variables_in_expr(expr));
             }
@@ -2218,7 +3142,25 @@ impl Compiler {
             }
             Stmt::Throw { value, .. } => {
                 // Находим переменные в выражении throw
+                if let Some(value) = value {
+                    vars.extend(self.find_used_variables_in_expr(value));
+                }
+            }
+            Stmt::Match { value, cases, default, .. } => {
                 vars.extend(self.find_used_variables_in_expr(value));
+                for case in cases {
+                    for case_value in &case.values {
+                        vars.extend(self.find_used_variables_in_expr(case_value));
+                    }
+                    for stmt in &case.body {
+                        vars.extend(self.find_used_variables_in_stmt(stmt));
+                    }
+                }
+                if let Some(default) = default {
+                    for stmt in default {
+                        vars.extend(self.find_used_variables_in_stmt(stmt));
+                    }
+                }
             }
         }
         vars
@@ -2237,6 +3179,9 @@ impl Compiler {
                         declared_vars.insert(name.clone());
                     }
                 }
+                Stmt::Destructure { names, kind: BindingKind::Let, .. } => {
+                    declared_vars.extend(names.iter().cloned());
+                }
                 Stmt::For { variable, body, .. } => {
                     // Переменная цикла for объявляется локально
                     declared_vars.insert(variable.clone());
@@ -2254,7 +3199,11 @@ impl Compiler {
                     // Рекурсивно проверяем тело while
                     declared_vars.extend(self.find_locally_declared_variables(body));
                 }
-                Stmt::Function { body, .. } => {
+                Stmt::Function { name, body, is_global, .. } => {
+                    // Вложенная функция без global - локальная переменная
+                    if !is_global {
+                        declared_vars.insert(name.clone());
+                    }
                     // Рекурсивно проверяем тело вложенной функции
                     declared_vars.extend(self.find_locally_declared_variables(body));
                 }
@@ -2270,6 +3219,15 @@ impl Compiler {
                         declared_vars.extend(self.find_locally_declared_variables(else_block));
                     }
                 }
+                Stmt::Match { cases, default, .. } => {
+                    // Рекурсивно проверяем ветки match
+                    for case in cases {
+                        declared_vars.extend(self.find_locally_declared_variables(&case.body));
+                    }
+                    if let Some(default) = default {
+                        declared_vars.extend(self.find_locally_declared_variables(default));
+                    }
+                }
                 _ => {
                     // Expr, Return, Break, Continue не объявляют переменные
                 }
@@ -2303,6 +3261,9 @@ impl Compiler {
         // Они локальные, не требуют захвата из родительских областей
         used_vars.retain(|v| !locally_declared.contains(v));
         
+        // Вложенные функции объемлющих функций подставляются как константы и не захватываются
+        used_vars.retain(|v| !matches!(self.resolve_local_function(v), Some((_, true))));
+        
         // Ищем переменные, которые используются, но не найдены в текущих областях видимости
         // но найдены в родительских областях видимости
         let mut captured = Vec::new();
@@ -2341,125 +3302,145 @@ impl Compiler {
         captured
     }
 
+    /// Оператор, который можно свернуть; and, or и in вычисляются только VM
+    fn binary_operator(op: &TokenKind) -> Option<BinaryOp> {
+        Some(match op {
+            TokenKind::Plus => BinaryOp::Add,
+            TokenKind::Minus => BinaryOp::Sub,
+            TokenKind::Star => BinaryOp::Mul,
+            TokenKind::Slash => BinaryOp::Div,
+            TokenKind::SlashSlash => BinaryOp::IntDiv,
+            TokenKind::Percent => BinaryOp::Mod,
+            TokenKind::StarStar => BinaryOp::Pow,
+            TokenKind::EqualEqual => BinaryOp::Equal,
+            TokenKind::BangEqual => BinaryOp::NotEqual,
+            TokenKind::Greater => BinaryOp::Greater,
+            TokenKind::Less => BinaryOp::Less,
+            TokenKind::GreaterEqual => BinaryOp::GreaterEqual,
+            TokenKind::LessEqual => BinaryOp::LessEqual,
+            TokenKind::Ampersand => BinaryOp::BitAnd,
+            TokenKind::Pipe => BinaryOp::BitOr,
+            TokenKind::Caret => BinaryOp::BitXor,
+            TokenKind::LessLess => BinaryOp::ShiftLeft,
+            TokenKind::GreaterGreater => BinaryOp::ShiftRight,
+            _ => return None,
+        })
+    }
+
     /// Оптимизация: вычисляет константные выражения во время компиляции
     fn evaluate_constant_expr(&self, expr: &Expr) -> Result<Option<Value>, LangError> {
+        stacker::maybe_grow(COMPILER_RED_ZONE, COMPILER_STACK_SIZE, || self.evaluate_constant_expr_inner(expr))
+    }
+
+    fn evaluate_constant_expr_inner(&self, expr: &Expr) -> Result<Option<Value>, LangError> {
         match expr {
             Expr::Literal { value, .. } => Ok(Some(value.clone())),
-            Expr::ArrayLiteral { .. } => Ok(None), // Не можем вычислить во время компиляции
+            Expr::ArrayLiteral { .. } | Expr::ObjectLiteral { .. } | Expr::Spread { .. } => Ok(None), // Не можем вычислить во время компиляции
             Expr::Property { .. } => Ok(None), // Не можем вычислить во время компиляции
             Expr::MethodCall { .. } => Ok(None), // Не можем вычислить во время компиляции
+            Expr::ChainedComparison { .. } => Ok(None),
+            Expr::Ternary { condition, then_branch, else_branch, .. } => {
+                match self.evaluate_constant_expr(condition)? {
+                    Some(c) if c.is_truthy() => self.evaluate_constant_expr(then_branch),
+                    Some(_) => self.evaluate_constant_expr(else_branch),
+                    None => Ok(None),
+                }
+            }
             Expr::Binary { left, op, right, .. } => {
-                // Пытаемся вычислить бинарное выражение, если оба операнда константы
-                let left_val = self.evaluate_constant_expr(left)?;
-                let right_val = self.evaluate_constant_expr(right)?;
-                
-                if let (Some(l), Some(r)) = (left_val, right_val) {
-                    match op {
-                        TokenKind::Plus => {
-                            match (l, r) {
-                                (Value::Number(n1), Value::Number(n2)) => Ok(Some(Value::Number(n1 + n2))),
-                                (Value::String(s1), Value::String(s2)) => Ok(Some(Value::String(format!("{}{}", s1, s2)))),
-                                (Value::String(s), Value::Number(n)) => Ok(Some(Value::String(format!("{}{}", s, n)))),
-                                (Value::Number(n), Value::String(s)) => Ok(Some(Value::String(format!("{}{}", n, s)))),
-                                _ => Ok(None),
-                            }
-                        }
-                        TokenKind::Minus => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Number(n1 - n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::Star => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Number(n1 * n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::Slash => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                if n2 == 0.0 {
-                                    // Don't constant-fold division by zero - let it be a runtime error
-                                    // so we can provide proper stack traces
-                                    return Ok(None);
-                                }
-                                Ok(Some(Value::Number(n1 / n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::SlashSlash => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                if n2 == 0.0 {
-                                    return Ok(None);
-                                }
-                                Ok(Some(Value::Number((n1 / n2).floor())))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::EqualEqual => Ok(Some(Value::Bool(l == r))),
-                        TokenKind::BangEqual => Ok(Some(Value::Bool(l != r))),
-                        TokenKind::Greater => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Bool(n1 > n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::Less => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Bool(n1 < n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::GreaterEqual => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Bool(n1 >= n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::LessEqual => {
-                            if let (Value::Number(n1), Value::Number(n2)) = (l, r) {
-                                Ok(Some(Value::Bool(n1 <= n2)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        _ => Ok(None),
-                    }
-                } else {
-                    Ok(None)
+                // Пытаемся вычислить бинарное выражение, если оба операнда константы.
+                // Ошибка (деление на ноль и т.п.) не сворачивается: ее сообщит VM со стеком вызовов
+                let Some(op) = Self::binary_operator(op) else {
+                    return Ok(None);
+                };
+                match (self.evaluate_constant_expr(left)?, self.evaluate_constant_expr(right)?) {
+                    (Some(l), Some(r)) => Ok(operators::binary(op, &l, &r).ok()),
+                    _ => Ok(None),
                 }
             }
             Expr::Unary { op, right, .. } => {
-                let right_val = self.evaluate_constant_expr(right)?;
-                if let Some(r) = right_val {
-                    match op {
-                        TokenKind::Minus => {
-                            if let Value::Number(n) = r {
-                                Ok(Some(Value::Number(-n)))
-                            } else {
-                                Ok(None)
-                            }
-                        }
-                        TokenKind::Bang => {
-                            Ok(Some(Value::Bool(!r.is_truthy())))
-                        }
-                        _ => Ok(None),
-                    }
-                } else {
-                    Ok(None)
+                let Some(r) = self.evaluate_constant_expr(right)? else {
+                    return Ok(None);
+                };
+                match op {
+                    TokenKind::Minus => Ok(operators::negate(&r).ok()),
+                    TokenKind::Bang | TokenKind::Not => Ok(Some(operators::not(&r))),
+                    _ => Ok(None),
                 }
             }
             _ => Ok(None), // Переменные, вызовы функций и присваивания не могут быть вычислены во время компиляции
         }
     }
 
+    fn compile_match(
+        &mut self,
+        value: &Expr,
+        cases: &[crate::parser::ast::MatchCase],
+        default: Option<&[Stmt]>,
+        line: usize,
+    ) -> Result<(), LangError> {
+        self.begin_scope();
+
+        // Значение вычисляется один раз и хранится в скрытой локальной переменной
+        self.compile_expr(value)?;
+        let value_local = self.declare_local("__match_value");
+        self.chunk.write_with_line(OpCode::StoreLocal(value_local), line);
+
+        let body_labels: Vec<usize> = cases.iter().map(|_| self.create_label()).collect();
+        let default_label = self.create_label();
+        let end_label = self.create_label();
+
+        // Проверки: значение сравнивается с каждым вариантом по структурному равенству (==)
+        for (case, &body_label) in cases.iter().zip(&body_labels) {
+            for case_value in &case.values {
+                self.current_line = case.line;
+                let next_label = self.create_label();
+                self.chunk.write_with_line(OpCode::LoadLocal(value_local), case.line);
+                self.compile_expr(case_value)?;
+                self.chunk.write_with_line(OpCode::Equal, case.line);
+                self.emit_jump(true, next_label)?;
+                self.emit_jump(false, body_label)?;
+                self.mark_label(next_label);
+            }
+        }
+        // Ни одна ветка не подошла: default или выход
+        self.emit_jump(false, if default.is_some() { default_label } else { end_label })?;
+
+        // Тела веток идут подряд, поэтому fallthrough - это просто отсутствие перехода к концу
+        for (case, &body_label) in cases.iter().zip(&body_labels) {
+            self.mark_label(body_label);
+            self.begin_scope();
+            for stmt in &case.body {
+                self.compile_stmt(stmt)?;
+            }
+            self.end_scope();
+            if !case.fallthrough {
+                self.emit_jump(false, end_label)?;
+            }
+        }
+
+        self.mark_label(default_label);
+        if let Some(default) = default {
+            self.begin_scope();
+            for stmt in default {
+                self.compile_stmt(stmt)?;
+            }
+            self.end_scope();
+        }
+
+        self.mark_label(end_label);
+        self.end_scope();
+        Ok(())
+    }
+
+    /// break/continue из try или catch блока: снимает обработчики исключений,
+    /// установленные внутри цикла, иначе они перехватывали бы ошибки после выхода из блока
+    fn emit_leave_try_blocks(&mut self, line: usize) {
+        let loop_try_depth = self.loop_contexts.last().map_or(0, |context| context.try_depth);
+        for _ in loop_try_depth..self.try_depth {
+            self.chunk.write_with_line(OpCode::PopExceptionHandler, line);
+        }
+    }
+
     fn compile_try(
         &mut self,
         try_block: &[Stmt],
@@ -2487,9 +3468,11 @@ impl Compiler {
         self.chunk.write_with_line(OpCode::BeginTry(0), line); // Временное значение
         
         // Компилируем try блок
+        self.try_depth += 1;
         for stmt in try_block {
             self.compile_stmt(stmt)?;
         }
+        self.try_depth -= 1;
         
         // Генерируем EndTry
         self.chunk.write_with_line(OpCode::EndTry, line);
@@ -2538,10 +3521,14 @@ impl Compiler {
                 catch_block.line,
             );
             
-            // Компилируем тело catch блока
+            // Компилируем тело catch блока (обработчик остается на стеке до PopExceptionHandler)
+            self.catch_depth += 1;
+            self.try_depth += 1;
             for stmt in &catch_block.body {
                 self.compile_stmt(stmt)?;
             }
+            self.try_depth -= 1;
+            self.catch_depth -= 1;
             
             // Генерируем EndCatch
             self.chunk.write_with_line(OpCode::EndCatch, catch_block.line);
@@ -2592,10 +3579,8 @@ impl Compiler {
         };
         self.chunk.exception_handlers.push(handler_info);
         
-        // Копируем таблицу типов ошибок в chunk (если еще не скопирована)
-        if self.chunk.error_type_table.is_empty() {
-            self.chunk.error_type_table = self.error_type_table.clone();
-        }
+        // Копируем таблицу типов ошибок в chunk (таблица пополняется каждым следующим try)
+        self.chunk.error_type_table = self.error_type_table.clone();
         
         // Патчим BeginTry с правильным индексом обработчика
         if let Some(OpCode::BeginTry(_)) = self.chunk.code.get_mut(begin_try_ip) {
@@ -75,6 +75,13 @@ impl Lexer {
                 return Ok(token);
             }
             '.' => {
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    // Оператор ... (spread)
+                    self.advance();
+                    self.advance();
+                    let token = self.make_token(TokenKind::DotDotDot);
+                    return Ok(token);
+                }
                 let token = self.make_token(TokenKind::Dot);
                 return Ok(token);
             }
@@ -82,6 +89,14 @@ impl Lexer {
                 let token = self.make_token(TokenKind::At);
                 return Ok(token);
             }
+            '?' => {
+                let token = self.make_token(TokenKind::Question);
+                return Ok(token);
+            }
+            ':' => {
+                let token = self.make_token(TokenKind::Colon);
+                return Ok(token);
+            }
             '+' => {
                 if self.match_char('=') {
                     // Оператор +=
@@ -97,6 +112,10 @@ impl Lexer {
                     // Оператор -=
                     let token = self.make_token(TokenKind::MinusEqual);
                     return Ok(token);
+                } else if self.match_char('>') {
+                    // Стрелка -> в ветках match
+                    let token = self.make_token(TokenKind::Arrow);
+                    return Ok(token);
                 } else {
                     let token = self.make_token(TokenKind::Minus);
                     return Ok(token);
@@ -161,6 +180,18 @@ impl Lexer {
                     return Ok(token);
                 }
             }
+            '&' => {
+                let token = self.make_token(TokenKind::Ampersand);
+                return Ok(token);
+            }
+            '|' => {
+                let token = self.make_token(TokenKind::Pipe);
+                return Ok(token);
+            }
+            '^' => {
+                let token = self.make_token(TokenKind::Caret);
+                return Ok(token);
+            }
             '#' => {
                 // Комментарий до конца строки (стиль #)
                 while self.peek() != '\n' && !self.is_at_end() {
@@ -190,6 +221,8 @@ impl Lexer {
             '<' => {
                 let kind = if self.match_char('=') {
                     TokenKind::LessEqual
+                } else if self.match_char('<') {
+                    TokenKind::LessLess
                 } else {
                     TokenKind::Less
                 };
@@ -199,6 +232,8 @@ impl Lexer {
             '>' => {
                 let kind = if self.match_char('=') {
                     TokenKind::GreaterEqual
+                } else if self.match_char('>') {
+                    TokenKind::GreaterGreater
                 } else {
                     TokenKind::Greater
                 };
@@ -327,9 +362,13 @@ impl Lexer {
             "in" => TokenKind::In,
             "or" => TokenKind::Or,
             "and" => TokenKind::And,
+            "not" => TokenKind::Not,
             "try" => TokenKind::Try,
             "catch" => TokenKind::Catch,
             "throw" => TokenKind::Throw,
+            "match" => TokenKind::Match,
+            "case" => TokenKind::Case,
+            "fallthrough" => TokenKind::Fallthrough,
             "cache" => TokenKind::Cache,
             _ => TokenKind::Identifier,
         }
@@ -392,6 +431,8 @@ impl Lexer {
             TokenKind::BangEqual => "!=".to_string(),
             TokenKind::LessEqual => "<=".to_string(),
             TokenKind::GreaterEqual => ">=".to_string(),
+            TokenKind::LessLess => "<<".to_string(),
+            TokenKind::GreaterGreater => ">>".to_string(),
             TokenKind::PlusEqual => "+=".to_string(),
             TokenKind::MinusEqual => "-=".to_string(),
             TokenKind::StarEqual => "*=".to_string(),
@@ -45,8 +45,14 @@ pub enum TokenKind {
     Greater, // >
     LessEqual,  // <=
     GreaterEqual, // >=
+    Ampersand,  // & (побитовое И)
+    Pipe,       // | (побитовое ИЛИ)
+    Caret,      // ^ (побитовое исключающее ИЛИ)
+    LessLess,   // << (сдвиг влево)
+    GreaterGreater, // >> (сдвиг вправо)
     Or,         // or
     And,        // and
+    Not,        // not
 
     // Разделители
     LParen,   // (
@@ -58,6 +64,9 @@ pub enum TokenKind {
     Comma,    // ,
     Semicolon, // ;
     Dot,      // .
+    DotDotDot, // ... (spread)
+    Question, // ?
+    Colon,    // :
 
     // Ключевые слова для циклов
     In,       // in
@@ -67,6 +76,12 @@ pub enum TokenKind {
     Catch,    // catch
     Throw,    // throw 
 
+    // Ключевые слова для оператора match
+    Match,       // match
+    Case,        // case
+    Fallthrough, // fallthrough
+    Arrow,       // ->
+
     // Аннотации
     At,       // @
     Cache,    // cache
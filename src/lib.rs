@@ -9,76 +9,56 @@ pub mod compiler;
 pub mod vm;
 pub mod websocket;
 pub mod sqlite_export;
+pub mod testing;
+pub mod progress;
+pub mod repl;
+pub mod analysis;
+pub mod session;
+pub mod watch;
 
 // Публичный API для запуска интерпретатора
-pub use common::{error::LangError, value::Value};
+pub use common::{error::{LangError, StructuredError}, value::Value};
 pub use bytecode::Chunk;
 pub use vm::Vm;
+pub use session::{ExecOutcome, Session};
 
 pub fn run(source: &str) -> Result<Value, LangError> {
-    use lexer::Lexer;
-    use parser::Parser;
-    use semantic::resolver::Resolver;
-    use compiler::Compiler;
-    use vm::Vm;
-
-    // 1. Лексический анализ
-    let mut lexer = Lexer::new(source);
-    let tokens = lexer.tokenize()?;
-
-    // 2. Парсинг
-    let mut parser = Parser::new(tokens);
-    let ast = parser.parse()?;
-
-    // 3. Семантический анализ
-    let mut resolver = Resolver::new();
-    resolver.resolve(&ast)?;
-
-    // 4. Компиляция в байт-код
-    let mut compiler = Compiler::new();
-    let chunk = compiler.compile(&ast)?;
-    let functions = compiler.get_functions();
+    session::Session::new().run(source)
+}
 
-    // 5. Выполнение на VM
+/// Выполняет код как run(); выставленный из другого потока флаг cancel прерывает выполнение
+/// ошибкой "execution cancelled" (см. Vm::request_cancel)
+pub fn run_with_cancel(source: &str, cancel: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Result<Value, LangError> {
+    let (chunk, functions) = compile(source)?;
     let mut vm = Vm::new();
+    vm.set_cancel_handle(cancel);
     vm.set_functions(functions);
     vm.register_native_globals();
-    let result = vm.run(&chunk)?;
+    vm.run(&chunk)
+}
 
-    Ok(result)
+/// Выполняет код как run(), но ошибку возвращает в машиночитаемом виде
+/// (вид, сообщение, строка, функция и фрагмент исходника) - без разбора текста ошибки.
+/// StructuredError велик, поэтому возвращается в Box
+pub fn run_collect(source: &str) -> Result<Value, Box<StructuredError>> {
+    run(source).map_err(|e| Box::new(e.to_structured(Some(source))))
 }
 
 /// Выполняет код и возвращает VM для доступа к глобальным переменным
 pub fn run_with_vm(source: &str) -> Result<(Value, Vm), LangError> {
-    use lexer::Lexer;
-    use parser::Parser;
-    use semantic::resolver::Resolver;
-    use compiler::Compiler;
-    use vm::Vm;
-
-    // 1. Лексический анализ
-    let mut lexer = Lexer::new(source);
-    let tokens = lexer.tokenize()?;
-
-    // 2. Парсинг
-    let mut parser = Parser::new(tokens);
-    let ast = parser.parse()?;
-
-    // 3. Семантический анализ
-    let mut resolver = Resolver::new();
-    resolver.resolve(&ast)?;
-
-    // 4. Компиляция в байт-код
-    let mut compiler = Compiler::new();
-    let chunk = compiler.compile(&ast)?;
-    let functions = compiler.get_functions();
+    let mut session = session::Session::new();
+    let result = session.run(source)?;
+    Ok((result, session.into_vm()))
+}
 
-    // 5. Выполнение на VM
+/// Выполняет код как run_with_vm() с флагом отмены, как у run_with_cancel()
+pub fn run_with_vm_cancel(source: &str, cancel: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Result<(Value, Vm), LangError> {
+    let (chunk, functions) = compile(source)?;
     let mut vm = Vm::new();
+    vm.set_cancel_handle(cancel);
     vm.set_functions(functions);
     vm.register_native_globals();
     let result = vm.run(&chunk)?;
-
     Ok((result, vm))
 }
 
@@ -1,10 +1,19 @@
 // Main entry point для DataCode интерпретатора
 
-use data_code::{run, run_with_vm};
+use data_code::{LangError, Session, StructuredError, Vm};
+use data_code::analysis;
+use data_code::repl::{print_result, run_repl_line, ReplSession};
 use data_code::sqlite_export;
+use data_code::parser::ParserLimits;
+use data_code::vm::profiler::profile_path_from_env;
+use data_code::watch;
+use data_code::vm::policy::{get_execution_policy, is_cancelled_error, is_interrupted_error, set_execution_policy, ExecutionPolicy};
+use data_code::websocket::{AuthConfig, PermissionProfile};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -17,7 +26,14 @@ fn print_help() {
     println!("  datacode main.dc           # Execute DataCode file");
     println!("  datacode main.dc --build_model  # Execute and export tables to SQLite");
     println!("  datacode main.dc --build_model output.db  # Export to specific file");
+    println!("  datacode main.dc --json-errors  # Report errors as JSON on stderr");
+    println!("  datacode main.dc --max-memory-mb 512  # Stop the script when its values exceed 512 MB");
+    println!("  datacode main.dc --profile 20  # Print the 20 slowest source lines (default 10)");
     println!("  datacode --websocket       # Start WebSocket server for remote code execution");
+    println!("  datacode --test tests/     # Run test_* functions from .dc files");
+    println!("  datacode --check main.dc   # Report syntax and block errors without running");
+    println!("  datacode --fmt main.dc     # Reformat the file in place (4-space indentation)");
+    println!("  datacode --watch main.dc   # Re-run the file every time it changes");
     println!("  datacode --help            # Show this help");
     println!();
     println!("File Execution:");
@@ -27,29 +43,80 @@ fn print_help() {
 
     println!();
     println!("SQLite Export (--build_model):");
-    println!("  • Exports all tables from global variables to SQLite database");
-    println!("  • Automatically detects foreign key relationships");
+    println!("  • Exports all tables (and arrays of objects) from global variables to SQLite database");
+    println!("  • Scalar globals go to the key-value table _datacode_scalars");
+    println!("  • Automatically detects foreign key relationships (disable with --no-auto-fk)");
+    println!("  • Explicit keys: register_foreign_key(orders, \"customer_id\", customers, \"id\")");
     println!("  • Creates metadata table _datacode_variables with all variable info");
     println!("  • Default output: <script_name>.db");
     println!("  • Custom output: --build_model output.db");
     println!("  • Environment variable: DATACODE_SQLITE_OUTPUT=path.db");
+    println!("  • Only some tables: --tables sales:fact_sales,customers:dim_customers");
+    println!("  • Or by pattern: --tables 'final_*' (or DATACODE_EXPORT_TABLES=final_*)");
+    println!("  • Or in the script: declare_export(sales, \"fact_sales\", \"id\")");
+    println!();
+    println!("Test Runner (--test):");
+    println!("  • Accepts a .dc file or a directory (searched recursively)");
+    println!("  • Runs every function without parameters whose name starts with test_");
+    println!("  • Each test runs on a fresh VM after the file's top-level code");
+    println!("  • Use assert(), assert_eq() and assert_throws() inside tests");
+    println!("  • Exit code is 1 if any test fails");
+    println!();
+    println!("Static checks (--check, --fmt):");
+    println!("  • --check reports every unbalanced {{ }}, ( ), [ ] and unterminated string with its line");
+    println!("  • If brackets balance, the first syntax or compile error is reported");
+    println!("  • Exit code is 1 if any error is found; the file is never executed");
+    println!("  • --fmt indents each block by 4 spaces and normalizes spacing around operators and commas");
+    println!("  • Comments, blank lines and string contents are preserved; --fmt --check to only verify");
+    println!();
+    println!("Watch mode (--watch):");
+    println!("  • Runs the file, then re-runs it whenever it is saved; a run in progress is cancelled");
+    println!("  • Errors are printed and the watcher keeps waiting for the next change");
+    println!("  • cached_eval(\"key\", fn) keeps fn()'s result between runs until a file read inside fn changes");
+    println!();
+    println!("Profiling (--profile, DATACODE_PROFILE):");
+    println!("  • --profile [N] prints the N slowest source lines after the run: hits, time and share");
+    println!("  • Builtin calls count toward the calling line, user functions toward their own lines");
+    println!("  • DATACODE_PROFILE=profile.folded datacode main.dc records time per call path");
+    println!("  • The file uses the collapsed stack format: \"<main>;load;parse 1500\" (microseconds)");
+    println!("  • View it with flamegraph.pl, inferno-flamegraph or speedscope");
+    println!();
+    println!("Machine-readable errors (--json-errors):");
+    println!("  • On failure prints one JSON object to stderr:");
+    println!("    {{\"kind\", \"message\", \"line\", \"column\", \"function\", \"snippet\"}}");
+    println!("  • Warnings are printed the same way with kind \"Warning\"");
     println!();
     println!("WebSocket Server:");
     println!("  • Start server: datacode --websocket");
     println!("  • Default address: ws://127.0.0.1:8080");
     println!("  • Custom host/port: datacode --websocket --host 0.0.0.0 --port 8899");
     println!("  • Or use env var: DATACODE_WS_ADDRESS=0.0.0.0:3000 datacode --websocket");
+    println!("  • Require an auth token: datacode --websocket --auth-token secret");
+    println!("    (or DATACODE_WS_TOKEN=secret datacode --websocket)");
+    println!("    - Clients must first send {{\"type\": \"auth\", \"token\": \"secret\"}} within 10 seconds");
+    println!("      or connect to ws://host:port/?token=secret");
+    println!("    - The connection is closed after 3 failed attempts");
+    println!("  • Per-token permissions: --auth-file tokens.json");
+    println!("    {{\"tokens\": {{\"secret\": {{\"allow_file_write\": false, \"allow_smb\": false}}}}}}");
+    println!("    - Fields: allow_file_read, allow_file_write, allow_smb, allow_dynamic_eval,");
+    println!("      max_session_disk_mb (limit of the --use-ve session folder)");
+    println!("  • Execution timeout per request: DATACODE_WS_TIMEOUT=seconds (default 30, 0 disables)");
     println!("  • Virtual environment mode: datacode --websocket --use-ve");
     println!("    - Creates isolated session folders in src/temp_sessions");
     println!("    - getcwd() returns empty string");
     println!("    - Supports file uploads via upload_file request");
     println!("    - Session folder is deleted on disconnect");
+    println!("  • Parser limits for client code (stricter than the CLI):");
+    println!("    --max-depth N (default 128), --max-statement-tokens N (default 100000),");
+    println!("    --max-collection-len N (default 10000, elements in one array/object literal)");
+    println!("  • Memory limit per session: --max-memory-mb N");
     println!("  • Send JSON: {{\"code\": \"print('Hello World')\"}}");
     println!("  • Receive JSON: {{\"success\": true, \"output\": \"Hello World\\n\", \"error\": null}}");
     println!("  • Upload file: {{\"type\": \"upload_file\", \"filename\": \"test.txt\", \"content\": \"...\"}}");
     println!();
     println!("Features:");
     println!("  • Interactive REPL with multiline support");
+    println!("  • Ctrl+C raises a catchable Interrupted error in running code and skips --build_model export (twice to force exit)");
     println!("  • User-defined functions with local scope");
     println!("  • Arithmetic and logical operations");
     println!("  • File system operations");
@@ -73,11 +140,53 @@ fn print_help() {
 }
 
 
+/// Печатает ошибку выполнения в stderr: текстом или JSON объектом (--json-errors)
+fn report_error(error: &LangError, source: &str, json_errors: bool) {
+    if is_stopped_by_user(error) && !json_errors {
+        eprintln!("Выполнение прервано (Ctrl+C): {}", error);
+    } else if json_errors {
+        eprintln!("{}", error.to_structured(Some(source)));
+    } else {
+        eprintln!("Ошибка выполнения: {}", error);
+    }
+}
+
+/// Строк в отчете --profile по умолчанию
+const DEFAULT_PROFILE_LINES: usize = 10;
+
+/// Печатает в stderr самые долгие строки source (--profile N)
+fn print_line_profile(vm: Option<&Vm>, source: &str, top: Option<usize>) {
+    if let (Some(top), Some(profiler)) = (top, vm.and_then(Vm::profiler)) {
+        eprint!("{}", profiler.line_report(source, top));
+    }
+}
+
+/// Записывает профиль выполнения в файл из DATACODE_PROFILE (если профилирование включено)
+fn write_profile(vm: Option<&Vm>) {
+    let (Some(path), Some(profiler)) = (profile_path_from_env(), vm.and_then(Vm::profiler)) else {
+        return;
+    };
+    match fs::write(&path, profiler.export_folded()) {
+        Ok(()) => eprintln!("Профиль записан: {}", path.display()),
+        Err(e) => eprintln!("Ошибка записи профиля '{}': {}", path.display(), e),
+    }
+}
+
+/// Код завершения при ошибке выполнения: 130 после Ctrl-C, как у прерванных сигналом программ
+fn exit_code(error: &LangError) -> i32 {
+    if is_stopped_by_user(error) { 130 } else { 1 }
+}
+
+/// Выполнение остановлено по Ctrl-C: неперехваченная ошибка Interrupted или отмена
+fn is_stopped_by_user(error: &LangError) -> bool {
+    is_interrupted_error(error) || is_cancelled_error(error)
+}
+
 fn print_version() {
     println!("DataCode v{}", VERSION);
 }
 
-fn start_websocket_server(host: String, port: u16, use_ve: bool) {
+fn start_websocket_server(host: String, port: u16, use_ve: bool, limits: ParserLimits, auth: AuthConfig) {
     let address = format!("{}:{}", host, port);
     
     println!("🚀 Запуск WebSocket сервера DataCode...");
@@ -92,12 +201,99 @@ fn start_websocket_server(host: String, port: u16, use_ve: bool) {
     // Создаем tokio runtime для асинхронного выполнения
     let rt = tokio::runtime::Runtime::new().unwrap();
     
-    if let Err(e) = rt.block_on(data_code::websocket::start_server(&address, use_ve)) {
+    if let Err(e) = rt.block_on(data_code::websocket::start_server_with_auth(&address, use_ve, limits, auth)) {
         eprintln!("❌ Ошибка запуска WebSocket сервера: {}", e);
         std::process::exit(1);
     }
 }
 
+/// Ctrl-C: первое нажатие во время выполнения выставляет флаг прерывания VM, и программа
+/// получает ошибку Interrupted ("execution interrupted by user"), которую может перехватить.
+/// Повторное нажатие в том же запуске или нажатие вне выполнения (приглашение REPL)
+/// завершает процесс сразу. running сбрасывается первым нажатием
+fn install_interrupt_handler(interrupt: Arc<AtomicBool>, running: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(_) => return,
+        };
+        runtime.block_on(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if running.swap(false, Ordering::SeqCst) {
+                    interrupt.store(true, Ordering::SeqCst);
+                    eprintln!("\nПрерывание... (повторный Ctrl+C - немедленный выход)");
+                } else {
+                    println!();
+                    std::process::exit(130);
+                }
+            }
+        });
+    });
+}
+
+/// --max-memory-mb N: лимит памяти значений программы (ExecutionPolicy::max_memory_bytes)
+fn set_memory_limit_mb(value: Option<&String>) {
+    let mb = match value.map(|v| v.parse::<usize>()) {
+        Some(Ok(mb)) if mb > 0 => mb,
+        _ => {
+            eprintln!("Ошибка: --max-memory-mb требует положительное число");
+            std::process::exit(1);
+        }
+    };
+    set_execution_policy(ExecutionPolicy {
+        max_memory_bytes: Some(mb.saturating_mul(1024 * 1024)),
+        ..get_execution_policy()
+    });
+}
+
+/// Путь к файлу после опции (--check file.dc) или выход с ошибкой
+fn require_file_arg(args: &[String], option: &str) -> String {
+    match args.get(2) {
+        Some(filename) => filename.clone(),
+        None => {
+            eprintln!("Ошибка: {} требует путь к файлу .dc", option);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read_source_or_exit(filename: &str) -> String {
+    fs::read_to_string(filename).unwrap_or_else(|e| {
+        eprintln!("Ошибка чтения файла '{}': {}", filename, e);
+        std::process::exit(1);
+    })
+}
+
+/// --watch: выполняет файл при запуске и после каждого его изменения. Изменение во время
+/// выполнения отменяет запуск через флаг отмены VM; ошибки выводятся, а наблюдение продолжается.
+/// Результаты cached_eval() сохраняются между запусками (src/watch.rs)
+fn watch_file(filename: &str, json_errors: bool) -> ! {
+    let path = PathBuf::from(filename);
+    loop {
+        let changed = Arc::new(AtomicBool::new(false));
+        let poller = watch::spawn_poller(vec![path.clone()], Arc::clone(&changed));
+        eprintln!("▶ {}", filename);
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                let started = std::time::Instant::now();
+                let mut session = Session::new().cancel_handle(Arc::clone(&changed));
+                match session.run(&source) {
+                    Ok(_) => eprintln!("✅ Выполнено за {:.2} с", started.elapsed().as_secs_f64()),
+                    Err(e) if is_cancelled_error(&e) && changed.load(Ordering::SeqCst) => {
+                        eprintln!("⟳ Файл изменен во время выполнения, перезапуск");
+                    }
+                    Err(e) => report_error(&e, &source, json_errors),
+                }
+            }
+            Err(e) => eprintln!("Ошибка чтения файла '{}': {}", filename, e),
+        }
+        if !changed.load(Ordering::SeqCst) {
+            eprintln!("👀 Ожидание изменений {} (Ctrl+C - выход)", filename);
+        }
+        let _ = poller.join();
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
@@ -115,11 +311,76 @@ fn main() {
                 print_version();
                 return;
             }
+            "--test" => {
+                let target = args.get(2).map(String::as_str).unwrap_or(".");
+                match data_code::testing::run_tests(Path::new(target)) {
+                    Ok(summary) => {
+                        data_code::testing::print_report(&summary);
+                        if !summary.is_success() {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            "--check" => {
+                let filename = require_file_arg(&args, "--check");
+                let source = read_source_or_exit(&filename);
+                let diagnostics = analysis::check_source(&source);
+                if diagnostics.is_empty() {
+                    println!("✅ {}: ошибок не найдено", filename);
+                    return;
+                }
+                for diagnostic in &diagnostics {
+                    eprintln!("{}:{}: {}", filename, diagnostic.line, diagnostic.message);
+                }
+                std::process::exit(1);
+            }
+            "--watch" => {
+                let filename = require_file_arg(&args, "--watch");
+                let json_errors = args[3..].iter().any(|a| a == "--json-errors");
+                watch_file(&filename, json_errors);
+            }
+            "--fmt" => {
+                // --fmt --check file.dc: только проверить, что файл уже отформатирован
+                let check_only = args.get(2).map(String::as_str) == Some("--check");
+                let filename = require_file_arg(&args[usize::from(check_only)..], "--fmt");
+                let source = read_source_or_exit(&filename);
+                let structure = analysis::check_block_structure(&source);
+                if !structure.is_empty() {
+                    for diagnostic in &structure {
+                        eprintln!("{}:{}: {}", filename, diagnostic.line, diagnostic.message);
+                    }
+                    eprintln!("Файл не отформатирован: сначала исправьте структуру блоков");
+                    std::process::exit(1);
+                }
+                let formatted = analysis::format_source(&source);
+                if check_only {
+                    if formatted != source {
+                        eprintln!("{}: файл требует форматирования", filename);
+                        std::process::exit(1);
+                    }
+                } else if formatted != source {
+                    if let Err(e) = fs::write(&filename, formatted) {
+                        eprintln!("Ошибка записи файла '{}': {}", filename, e);
+                        std::process::exit(1);
+                    }
+                    println!("Отформатирован: {}", filename);
+                }
+                return;
+            }
             "--websocket" => {
                 // Парсим аргументы для WebSocket сервера
                 let mut host = "127.0.0.1".to_string();
                 let mut port = 8080u16;
                 let mut use_ve = false;
+                let mut limits = ParserLimits::server();
+                let mut auth_token: Option<String> = None;
+                let mut auth_file: Option<String> = None;
                 
                 // Проверяем переменную окружения
                 if let Ok(ws_address) = env::var("DATACODE_WS_ADDRESS") {
@@ -164,6 +425,40 @@ fn main() {
                             use_ve = true;
                             i += 1;
                         }
+                        "--auth-token" | "--auth-file" => {
+                            let value = match args.get(i + 1) {
+                                Some(value) if !value.is_empty() => value.clone(),
+                                _ => {
+                                    eprintln!("Ошибка: {} требует значение", args[i]);
+                                    std::process::exit(1);
+                                }
+                            };
+                            if args[i] == "--auth-token" {
+                                auth_token = Some(value);
+                            } else {
+                                auth_file = Some(value);
+                            }
+                            i += 2;
+                        }
+                        "--max-memory-mb" => {
+                            set_memory_limit_mb(args.get(i + 1));
+                            i += 2;
+                        }
+                        "--max-depth" | "--max-statement-tokens" | "--max-collection-len" => {
+                            let value = match args.get(i + 1).map(|v| v.parse::<usize>()) {
+                                Some(Ok(value)) if value > 0 => value,
+                                _ => {
+                                    eprintln!("Ошибка: {} требует положительное число", args[i]);
+                                    std::process::exit(1);
+                                }
+                            };
+                            match args[i].as_str() {
+                                "--max-depth" => limits.max_depth = value,
+                                "--max-statement-tokens" => limits.max_statement_tokens = value,
+                                _ => limits.max_collection_len = value,
+                            }
+                            i += 2;
+                        }
                         _ => {
                             eprintln!("Неизвестный аргумент: {}", args[i]);
                             std::process::exit(1);
@@ -171,7 +466,24 @@ fn main() {
                     }
                 }
                 
-                start_websocket_server(host, port, use_ve);
+                // Токены: профили из --auth-file и токен с полными правами из --auth-token
+                // (без флагов - из DATACODE_WS_TOKEN)
+                let mut auth = match &auth_file {
+                    Some(path) => match AuthConfig::from_file(Path::new(path)) {
+                        Ok(auth) => auth,
+                        Err(e) => {
+                            eprintln!("Ошибка: --auth-file: {}", e);
+                            std::process::exit(1);
+                        }
+                    },
+                    None if auth_token.is_none() => AuthConfig::from_env(),
+                    None => AuthConfig::default(),
+                };
+                if let Some(token) = &auth_token {
+                    auth.add_token(token, PermissionProfile::default());
+                }
+
+                start_websocket_server(host, port, use_ve, limits, auth);
                 return;
             }
             _ => {
@@ -193,17 +505,58 @@ fn main() {
             std::process::exit(1);
         }
         
+        let json_errors = args[2..].iter().any(|a| a == "--json-errors");
+        
         // Проверка расширения файла (опционально, но полезно)
         if !filename.ends_with(".dc") {
-            eprintln!("Предупреждение: файл '{}' не имеет расширения .dc", filename);
+            if json_errors {
+                let warning = StructuredError::warning(format!("file '{}' does not have the .dc extension", filename));
+                eprintln!("{}", warning);
+            } else {
+                eprintln!("Предупреждение: файл '{}' не имеет расширения .dc", filename);
+            }
         }
         
         // Проверяем наличие флага --build_model
         let mut build_model = false;
         let mut output_db: Option<String> = None;
+        let mut tables_spec: Option<String> = None;
+        let mut auto_foreign_keys = true;
+        let mut line_profile: Option<usize> = None;
         let mut i = 2;
         while i < args.len() {
             match args[i].as_str() {
+                "--max-memory-mb" => {
+                    set_memory_limit_mb(args.get(i + 1));
+                    i += 2;
+                }
+                "--tables" => {
+                    match args.get(i + 1) {
+                        Some(spec) => tables_spec = Some(spec.clone()),
+                        None => {
+                            eprintln!("Ошибка: --tables требует список переменная:таблица или шаблонов через запятую");
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                }
+                "--profile" => {
+                    // Необязательное число строк отчета
+                    match args.get(i + 1).map(|n| n.parse::<usize>()) {
+                        Some(Ok(top)) => {
+                            line_profile = Some(top);
+                            i += 2;
+                        }
+                        _ => {
+                            line_profile = Some(DEFAULT_PROFILE_LINES);
+                            i += 1;
+                        }
+                    }
+                }
+                "--no-auto-fk" => {
+                    auto_foreign_keys = false;
+                    i += 1;
+                }
                 "--build_model" | "--build-model" => {
                     build_model = true;
                     // Проверяем следующий аргумент - может быть имя файла
@@ -220,6 +573,24 @@ fn main() {
             }
         }
         
+        // Выбор таблиц для экспорта: --tables или DATACODE_EXPORT_TABLES
+        let table_map = match tables_spec.or_else(|| env::var("DATACODE_EXPORT_TABLES").ok()).filter(|_| build_model) {
+            Some(spec) => match sqlite_export::parse_table_map(&spec) {
+                Ok(map) => Some(map),
+                Err(e) => {
+                    eprintln!("Ошибка: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => None,
+        };
+        let export_options = sqlite_export::ExportOptions { tables: table_map, auto_foreign_keys };
+        
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let running = Arc::new(AtomicBool::new(false));
+        install_interrupt_handler(Arc::clone(&interrupt), Arc::clone(&running));
+        let mut session = Session::new().interrupt_handle(Arc::clone(&interrupt)).profile(line_profile.is_some());
+        
         // Определяем имя выходного файла для SQLite
         if build_model {
             let db_filename = if let Some(db) = output_db {
@@ -235,13 +606,20 @@ fn main() {
                 format!("{}.db", stem)
             };
             
-            // Чтение и выполнение файла с экспортом
+            // Чтение и выполнение файла с экспортом. Прерванная Ctrl-C программа не экспортируется
+            // (если не перехватила Interrupted), а Ctrl-C во время экспорта откладывается до его
+            // завершения (повторный - выход)
             match fs::read_to_string(filename) {
                 Ok(source) => {
-                    match run_with_vm(&source) {
-                        Ok((_, vm)) => {
+                    running.store(true, Ordering::SeqCst);
+                    let result = session.run(&source);
+                    write_profile(session.vm());
+                    print_line_profile(session.vm(), &source, line_profile);
+                    match result {
+                        Ok(_) => {
+                            let vm = session.into_vm();
                             // Экспортируем таблицы в SQLite
-                            match sqlite_export::export_to_sqlite(&vm, &db_filename) {
+                            match sqlite_export::export_to_sqlite_with(&vm, &db_filename, &export_options) {
                                 Ok(_) => {
                                     println!("✅ База данных создана: {}", db_filename);
                                 }
@@ -252,8 +630,11 @@ fn main() {
                             }
                         }
                         Err(e) => {
-                            eprintln!("Ошибка выполнения: {}", e);
-                            std::process::exit(1);
+                            report_error(&e, &source, json_errors);
+                            if is_stopped_by_user(&e) {
+                                eprintln!("SQLite экспорт пропущен");
+                            }
+                            std::process::exit(exit_code(&e));
                         }
                     }
                 }
@@ -266,11 +647,15 @@ fn main() {
             // Обычное выполнение без экспорта
             match fs::read_to_string(filename) {
                 Ok(source) => {
-                    match run(&source) {
+                    running.store(true, Ordering::SeqCst);
+                    let result = session.run(&source);
+                    write_profile(session.vm());
+                    print_line_profile(session.vm(), &source, line_profile);
+                    match result {
                         Ok(_) => {}
                         Err(e) => {
-                            eprintln!("Ошибка выполнения: {}", e);
-                            std::process::exit(1);
+                            report_error(&e, &source, json_errors);
+                            std::process::exit(exit_code(&e));
                         }
                     }
                 }
@@ -283,15 +668,20 @@ fn main() {
     } else {
         // REPL режим (интерактивный)
         println!("ДатаКод v{} - Bytecode VM", VERSION);
-        println!("Введите код (Ctrl+D или 'exit' для выхода):");
+        println!("Введите код (Ctrl+D или 'exit' для выхода, Ctrl+C прерывает выполнение):");
         println!();
         
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let running = Arc::new(AtomicBool::new(false));
+        install_interrupt_handler(Arc::clone(&interrupt), Arc::clone(&running));
+        
+        let mut session = ReplSession::with_interrupt(Arc::clone(&interrupt));
         let mut input = String::new();
         loop {
             use std::io::{self, Write};
             
-            // Показываем приглашение
-            print!("datacode> ");
+            // Показываем приглашение; "...> " - продолжение незавершенного блока
+            print!("{}", if input.is_empty() { "datacode> " } else { "     ...> " });
             io::stdout().flush().unwrap();
             
             match io::stdin().read_line(&mut input) {
@@ -313,14 +703,23 @@ fn main() {
                         input.clear();
                         continue;
                     }
+
+                    // Незакрытые скобки или строка: читаем следующую строку блока
+                    if analysis::is_incomplete(&input) {
+                        continue;
+                    }
                     
-                    // Выполнение кода
-                    match run(trimmed) {
-                        Ok(value) => {
-                            // Если есть результат, показываем его
-                            if !matches!(value, data_code::Value::Null) {
-                                println!("=> {:?}", value);
-                            }
+                    // Выполнение кода; Ctrl-C во время выполнения возвращает в приглашение,
+                    // переменные сессии сохраняются
+                    interrupt.store(false, Ordering::SeqCst);
+                    running.store(true, Ordering::SeqCst);
+                    let result = run_repl_line(&mut session, trimmed);
+                    running.store(false, Ordering::SeqCst);
+                    match result {
+                        Ok(Some(value)) => print_result(&value),
+                        Ok(None) => {}
+                        Err(e) if is_stopped_by_user(&e) => {
+                            eprintln!("Выполнение прервано: {}", e);
                         }
                         Err(e) => {
                             eprintln!("Ошибка: {}", e);
@@ -11,6 +11,15 @@ pub struct CatchBlock {
     pub line: usize,
 }
 
+/// Ветка оператора match: `case 1, 2 -> { ... }`
+#[derive(Debug, Clone)]
+pub struct MatchCase {
+    pub values: Vec<Expr>,  // Значения, с которыми сравнивается выражение
+    pub body: Vec<Stmt>,
+    pub fallthrough: bool,  // Тело заканчивается явным fallthrough - переход в следующую ветку
+    pub line: usize,
+}
+
 /// Параметр функции с опциональным значением по умолчанию
 #[derive(Debug, Clone)]
 pub struct Param {
@@ -57,6 +66,19 @@ pub enum Expr {
         right: Box<Expr>,
         line: usize,
     },
+    /// Цепочка сравнений `a < b <= c` - эквивалент `a < b and b <= c`, где b вычисляется один раз
+    ChainedComparison {
+        operands: Vec<Expr>,
+        ops: Vec<TokenKind>, // ops.len() == operands.len() - 1
+        line: usize,
+    },
+    /// Условное выражение `condition ? then_branch : else_branch`
+    Ternary {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+        line: usize,
+    },
     Call {
         name: String,
         args: Vec<Arg>,
@@ -66,6 +88,16 @@ pub enum Expr {
         elements: Vec<Expr>,
         line: usize,
     },
+    ObjectLiteral {
+        entries: Vec<(String, Expr)>, // Пары ключ-значение в порядке записи; `...obj` - пустой ключ и Expr::Spread
+        line: usize,
+    },
+    /// `...value` - раскрытие массива или объекта в аргументах вызова, массиве или объекте.
+    /// В вызове записывается как позиционный аргумент: массив дает позиционные аргументы, объект - именованные
+    Spread {
+        value: Box<Expr>,
+        line: usize,
+    },
     ArrayIndex {
         array: Box<Expr>,
         index: Box<Expr>,
@@ -93,13 +125,53 @@ impl Expr {
             Expr::AssignOp { line, .. } => *line,
             Expr::Binary { line, .. } => *line,
             Expr::Unary { line, .. } => *line,
+            Expr::Ternary { line, .. } => *line,
+            Expr::ChainedComparison { line, .. } => *line,
             Expr::Call { line, .. } => *line,
             Expr::ArrayLiteral { line, .. } => *line,
+            Expr::ObjectLiteral { line, .. } => *line,
+            Expr::Spread { line, .. } => *line,
             Expr::ArrayIndex { line, .. } => *line,
             Expr::Property { line, .. } => *line,
             Expr::MethodCall { line, .. } => *line,
         }
     }
+
+    /// Забирает выражение, оставляя на его месте null
+    pub(crate) fn take(&mut self) -> Expr {
+        std::mem::replace(self, Expr::Literal { value: Value::Null, line: self.line() })
+    }
+}
+
+impl Drop for Expr {
+    /// Цепочка a + b + c + ... - это дерево глубиной в длину цепочки, и рекурсивное
+    /// освобождение переполнило бы стек. Вложенные бинарные операнды снимаются в цикле
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        take_binary_operands(self, &mut pending);
+        while let Some(mut expr) = pending.pop() {
+            // После этого у expr не остается бинарных операндов, и его освобождение не рекурсивно
+            take_binary_operands(&mut expr, &mut pending);
+        }
+    }
+}
+
+fn take_binary_operands(expr: &mut Expr, pending: &mut Vec<Expr>) {
+    if let Expr::Binary { left, right, .. } = expr {
+        for operand in [left, right] {
+            if matches!(**operand, Expr::Binary { .. }) {
+                pending.push(operand.take());
+            }
+        }
+    }
+}
+
+/// Как распаковка привязывает имена: как let, как global или как обычное присваивание
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindingKind {
+    Let,
+    Global,
+    Assign,
 }
 
 #[derive(Debug, Clone)]
@@ -110,6 +182,13 @@ pub enum Stmt {
         is_global: bool,
         line: usize,
     },
+    /// Распаковка массива в несколько имен: `let a, b = f()`, `global a, b = f()`, `a, b = f()`
+    Destructure {
+        names: Vec<String>,
+        value: Expr,
+        kind: BindingKind,
+        line: usize,
+    },
     Expr {
         expr: Expr,
         line: usize,
@@ -136,6 +215,7 @@ pub enum Stmt {
         params: Vec<Param>,
         body: Vec<Stmt>,
         is_cached: bool,
+        is_global: bool, // global fn: вложенная функция видна глобально, а не только в объявившей функции
         line: usize,
     },
     Return {
@@ -155,7 +235,13 @@ pub enum Stmt {
         line: usize,
     },
     Throw {
+        value: Option<Expr>, // None - повторное выбрасывание текущей ошибки внутри catch
+        line: usize,
+    },
+    Match {
         value: Expr,
+        cases: Vec<MatchCase>,
+        default: Option<Vec<Stmt>>,
         line: usize,
     },
 }
@@ -164,6 +250,7 @@ impl Stmt {
     pub fn line(&self) -> usize {
         match self {
             Stmt::Let { line, .. } => *line,
+            Stmt::Destructure { line, .. } => *line,
             Stmt::Expr { line, .. } => *line,
             Stmt::If { line, .. } => *line,
             Stmt::While { line, .. } => *line,
@@ -174,7 +261,7 @@ impl Stmt {
             Stmt::Continue { line, .. } => *line,
             Stmt::Try { line, .. } => *line,
             Stmt::Throw { line, .. } => *line,
+            Stmt::Match { line, .. } => *line,
         }
     }
 }
-
@@ -2,5 +2,5 @@ pub mod ast;
 pub mod parser;
 
 pub use ast::{Expr, Stmt};
-pub use parser::Parser;
+pub use parser::{parser_limits, set_parser_limits, Parser, ParserLimits, OPERATOR_PRECEDENCE};
 
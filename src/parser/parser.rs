@@ -1,20 +1,122 @@
 // Recursive Descent Parser
 
 use crate::lexer::{Token, TokenKind};
-use crate::parser::ast::{Expr, Stmt, Param, Arg};
+use crate::parser::ast::{Expr, Stmt, Param, Arg, BindingKind};
 use crate::common::error::LangError;
 use crate::common::value::Value;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+/// Ограничения парсера, защищающие от патологических входных данных
+/// (глубокая вложенность переполняет стек рекурсивного спуска, огромные литералы
+/// разбираются минутами)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParserLimits {
+    /// Максимальная глубина вложенности: скобки, блоки, вызовы и унарные операторы
+    pub max_depth: usize,
+    /// Максимальное число токенов в одной инструкции
+    pub max_statement_tokens: usize,
+    /// Максимальное число элементов в литерале массива или объекта
+    pub max_collection_len: usize,
+}
+
+impl Default for ParserLimits {
+    /// Ограничения для CLI
+    fn default() -> Self {
+        Self {
+            max_depth: 256,
+            max_statement_tokens: 1_000_000,
+            max_collection_len: 1_000_000,
+        }
+    }
+}
+
+impl ParserLimits {
+    /// Более строгие ограничения для WebSocket сервера
+    pub fn server() -> Self {
+        Self {
+            max_depth: 128,
+            max_statement_tokens: 100_000,
+            max_collection_len: 10_000,
+        }
+    }
+}
+
+/// Если до конца стека осталось меньше PARSER_RED_ZONE байт, рекурсивный спуск
+/// продолжается на новом сегменте размером PARSER_STACK_SIZE
+const PARSER_RED_ZONE: usize = 256 * 1024;
+const PARSER_STACK_SIZE: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    static PARSER_LIMITS: Cell<ParserLimits> = Cell::new(ParserLimits::default());
+}
+
+/// Устанавливает ограничения для всех парсеров текущего потока (включая eval/exec)
+pub fn set_parser_limits(limits: ParserLimits) {
+    PARSER_LIMITS.with(|l| l.set(limits));
+}
+
+pub fn parser_limits() -> ParserLimits {
+    PARSER_LIMITS.with(|l| l.get())
+}
+
+/// Приоритет операторов от самого сильного к самому слабому. Дерево строит только парсер
+/// (методы ниже идут в обратном порядке: ternary -> or_expression -> ... -> call),
+/// run(), eval/exec и table_filter_expr компилируют одно и то же Expr
+pub const OPERATOR_PRECEDENCE: &str =
+    "call/index, **, unary - !, * / // %, + -, << >>, &, ^, |, in not in < <= > >=, == !=, not, and, or, ? :";
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    limits: ParserLimits,
+    depth: usize,          // Текущая глубина вложенности
+    statement_start: usize, // Индекс первого токена текущей инструкции
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self::with_limits(tokens, parser_limits())
+    }
+
+    pub fn with_limits(tokens: Vec<Token>, limits: ParserLimits) -> Self {
+        Self { tokens, current: 0, limits, depth: 0, statement_start: 0 }
+    }
+
+    /// Увеличивает глубину вложенности, проверяя ограничение
+    fn enter(&mut self) -> Result<(), LangError> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(LangError::ParseError {
+                message: format!("expression too deeply nested (limit {})", self.limits.max_depth),
+                line: self.peek().line,
+            });
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self, levels: usize) {
+        self.depth -= levels;
+    }
+
+    fn check_statement_length(&self) -> Result<(), LangError> {
+        if self.current - self.statement_start > self.limits.max_statement_tokens {
+            return Err(LangError::ParseError {
+                message: format!("statement too long (limit {} tokens)", self.limits.max_statement_tokens),
+                line: self.peek().line,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_collection_length(&self, len: usize) -> Result<(), LangError> {
+        if len > self.limits.max_collection_len {
+            return Err(LangError::ParseError {
+                message: format!("collection literal too long (limit {} elements)", self.limits.max_collection_len),
+                line: self.peek().line,
+            });
+        }
+        Ok(())
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, LangError> {
@@ -26,17 +128,34 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Result<Stmt, LangError> {
+        // Вложенные блоки тоже увеличивают глубину
+        self.enter()?;
+        self.statement_start = self.current;
+        let statement = stacker::maybe_grow(PARSER_RED_ZONE, PARSER_STACK_SIZE, || self.declaration_inner());
+        self.leave(1);
+        statement
+    }
+
+    fn declaration_inner(&mut self) -> Result<Stmt, LangError> {
         if self.match_token(TokenKind::Global) {
+            // global fn f() { ... } внутри функции объявляет функцию глобально
+            if self.check(TokenKind::At) || self.check(TokenKind::Fn) {
+                return self.function_declaration(true);
+            }
             // global a = 5
             let global_line = self.previous().line;
             let name = self.consume(TokenKind::Identifier, "Expect variable name after 'global'")?.lexeme.clone();
+            // global t, report = f()
+            if self.check(TokenKind::Comma) {
+                return self.destructure(name, BindingKind::Global, global_line);
+            }
             self.consume(TokenKind::Equal, "Expect '=' after variable name")?;
             let value = self.expression()?;
             Ok(Stmt::Let { name, value, is_global: true, line: global_line })
         } else if self.match_token(TokenKind::Let) {
             self.variable_declaration()
         } else if self.check(TokenKind::At) || self.check(TokenKind::Fn) {
-            self.function_declaration()
+            self.function_declaration(false)
         } else {
             self.statement()
         }
@@ -45,12 +164,33 @@ impl Parser {
     fn variable_declaration(&mut self) -> Result<Stmt, LangError> {
         let let_line = self.previous().line;
         let name = self.consume(TokenKind::Identifier, "Expect variable name")?.lexeme.clone();
+        if self.check(TokenKind::Comma) {
+            return self.destructure(name, BindingKind::Let, let_line);
+        }
         self.consume(TokenKind::Equal, "Expect '=' after variable name")?;
         let value = self.expression()?;
         Ok(Stmt::Let { name, value, is_global: false, line: let_line })
     }
 
-    fn function_declaration(&mut self) -> Result<Stmt, LangError> {
+    /// Список имен через запятую после первого имени, '=' и значение: `a, b, c = f()`
+    fn destructure(&mut self, first: String, kind: BindingKind, line: usize) -> Result<Stmt, LangError> {
+        let mut names = vec![first];
+        while self.match_token(TokenKind::Comma) {
+            let name = self.consume(TokenKind::Identifier, "Expect variable name after ','")?.lexeme.clone();
+            if names.contains(&name) {
+                return Err(LangError::ParseError {
+                    message: format!("Variable '{}' is listed twice in destructuring assignment", name),
+                    line: self.previous().line,
+                });
+            }
+            names.push(name);
+        }
+        self.consume(TokenKind::Equal, "Expect '=' after variable names")?;
+        let value = self.expression()?;
+        Ok(Stmt::Destructure { names, value, kind, line })
+    }
+
+    fn function_declaration(&mut self, is_global: bool) -> Result<Stmt, LangError> {
         // Проверяем наличие аннотации @cache
         let is_cached = if self.match_token(TokenKind::At) {
             self.consume(TokenKind::Cache, "Expect 'cache' after '@'")?;
@@ -109,7 +249,7 @@ impl Parser {
 
         let body = self.block()?;
 
-        Ok(Stmt::Function { name, params, body, is_cached, line: fn_line })
+        Ok(Stmt::Function { name, params, body, is_cached, is_global, line: fn_line })
     }
 
     fn statement(&mut self) -> Result<Stmt, LangError> {
@@ -129,6 +269,13 @@ impl Parser {
             self.throw_statement()
         } else if self.match_token(TokenKind::Try) {
             self.try_statement()
+        } else if self.match_token(TokenKind::Match) {
+            self.match_statement()
+        } else if self.check(TokenKind::Identifier) && self.check_next(TokenKind::Comma) {
+            // a, b = f() - присваивание уже существующим или новым переменным
+            let token = self.advance();
+            let (name, line) = (token.lexeme.clone(), token.line);
+            self.destructure(name, BindingKind::Assign, line)
         } else {
             self.expression_statement()
         }
@@ -205,7 +352,17 @@ impl Parser {
     fn return_statement(&mut self) -> Result<Stmt, LangError> {
         let return_line = self.previous().line;
         let value = if !self.check(TokenKind::Semicolon) && !self.check(TokenKind::RBrace) {
-            Some(self.expression()?)
+            let first = self.expression()?;
+            if self.check(TokenKind::Comma) {
+                // return a, b возвращает массив [a, b] для распаковки: let x, y = f()
+                let mut elements = vec![first];
+                while self.match_token(TokenKind::Comma) {
+                    elements.push(self.expression()?);
+                }
+                Some(Expr::ArrayLiteral { elements, line: return_line })
+            } else {
+                Some(first)
+            }
         } else {
             None
         };
@@ -230,8 +387,13 @@ impl Parser {
 
     fn throw_statement(&mut self) -> Result<Stmt, LangError> {
         let throw_line = self.previous().line;
-        // Парсим выражение (значение ошибки)
-        let value = self.expression()?;
+        // throw без значения (перед '}' или ';') - повторное выбрасывание в catch
+        let value = if self.check(TokenKind::RBrace) || self.check(TokenKind::Semicolon) || self.is_at_end() {
+            None
+        } else {
+            // Парсим выражение (значение ошибки)
+            Some(self.expression()?)
+        };
         // Семиколон опционален для throw
         self.match_token(TokenKind::Semicolon);
         Ok(Stmt::Throw { value, line: throw_line })
@@ -252,11 +414,14 @@ impl Parser {
         while self.match_token(TokenKind::Catch) {
             let catch_line = self.previous().line;
             
-            // Парсим тип ошибки (опционально)
+            // Парсим тип ошибки (опционально): встроенный тип или собственный вид ошибки
+            // из throw {"kind": ...}, за которым следует имя переменной
             let error_type = if self.check(TokenKind::Identifier) {
                 let error_type_name = self.peek().lexeme.clone();
                 // Проверяем, является ли это типом ошибки
-                if crate::common::error::ErrorType::from_name(&error_type_name).is_some() {
+                if crate::common::error::ErrorType::from_name(&error_type_name).is_some()
+                    || self.check_next(TokenKind::Identifier)
+                {
                     self.advance();
                     Some(error_type_name)
                 } else {
@@ -309,6 +474,101 @@ impl Parser {
         })
     }
 
+    fn match_statement(&mut self) -> Result<Stmt, LangError> {
+        use crate::parser::ast::MatchCase;
+
+        let match_line = self.previous().line;
+        // Парсим: match value { case 1, 2 -> { ... } default -> { ... } }
+        let value = self.expression()?;
+        self.consume(TokenKind::LBrace, "Expect '{' after match value")?;
+
+        let mut cases: Vec<MatchCase> = Vec::new();
+        let mut default = None;
+        while !self.check(TokenKind::RBrace) && !self.is_at_end() {
+            if self.match_token(TokenKind::Case) {
+                let case_line = self.previous().line;
+                if default.is_some() {
+                    return Err(LangError::ParseError {
+                        message: "'default' must be the last arm of match".to_string(),
+                        line: case_line,
+                    });
+                }
+                let mut values = vec![self.expression()?];
+                while self.match_token(TokenKind::Comma) {
+                    values.push(self.expression()?);
+                }
+                self.consume(TokenKind::Arrow, "Expect '->' after case values")?;
+                let (body, fallthrough) = self.match_arm_body()?;
+                cases.push(MatchCase { values, body, fallthrough, line: case_line });
+            } else if self.check(TokenKind::Identifier) && self.peek().lexeme == "default" {
+                // default - контекстное ключевое слово, вне match это обычный идентификатор
+                let default_line = self.advance().line;
+                if default.is_some() {
+                    return Err(LangError::ParseError {
+                        message: "match can have only one 'default' arm".to_string(),
+                        line: default_line,
+                    });
+                }
+                self.consume(TokenKind::Arrow, "Expect '->' after 'default'")?;
+                let (body, fallthrough) = self.match_arm_body()?;
+                if fallthrough {
+                    return Err(LangError::ParseError {
+                        message: "Cannot use 'fallthrough' in the last arm of match".to_string(),
+                        line: default_line,
+                    });
+                }
+                default = Some(body);
+            } else {
+                return Err(LangError::ParseError {
+                    message: "Expect 'case' or 'default' in match".to_string(),
+                    line: self.peek().line,
+                });
+            }
+        }
+        self.consume(TokenKind::RBrace, "Expect '}' after match arms")?;
+
+        // fallthrough из последней ветки некуда передавать управление
+        if default.is_none() {
+            if let Some(last) = cases.last() {
+                if last.fallthrough {
+                    return Err(LangError::ParseError {
+                        message: "Cannot use 'fallthrough' in the last arm of match".to_string(),
+                        line: last.line,
+                    });
+                }
+            }
+        }
+
+        Ok(Stmt::Match { value, cases, default, line: match_line })
+    }
+
+    /// Тело ветки match: блок в фигурных скобках или одна инструкция.
+    /// Возвращает инструкции и признак явного fallthrough в конце блока.
+    fn match_arm_body(&mut self) -> Result<(Vec<Stmt>, bool), LangError> {
+        if !self.match_token(TokenKind::LBrace) {
+            return Ok((vec![self.declaration()?], false));
+        }
+        let mut statements = Vec::new();
+        let mut fallthrough = false;
+        while !self.check(TokenKind::RBrace) && !self.is_at_end() {
+            if self.match_token(TokenKind::Fallthrough) {
+                let fallthrough_line = self.previous().line;
+                self.match_token(TokenKind::Semicolon);
+                if !self.check(TokenKind::RBrace) {
+                    return Err(LangError::ParseError {
+                        message: "'fallthrough' must be the last statement of a case".to_string(),
+                        line: fallthrough_line,
+                    });
+                }
+                fallthrough = true;
+            } else {
+                statements.push(self.declaration()?);
+            }
+        }
+        self.consume(TokenKind::RBrace, "Expect '}' after case body")?;
+        Ok((statements, fallthrough))
+    }
+
     fn expression_statement(&mut self) -> Result<Stmt, LangError> {
         let expr = self.expression()?;
         let line = expr.line();
@@ -327,11 +587,18 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, LangError> {
-        self.assignment()
+        self.check_statement_length()?;
+        self.enter()?;
+        // Один уровень вложенности - это цепочка из ~17 рекурсивных вызовов (по одному на
+        // уровень приоритета), поэтому max_depth сам по себе не гарантирует, что стек потока
+        // не кончится: при нехватке стека разбор продолжается на новом сегменте
+        let expr = stacker::maybe_grow(PARSER_RED_ZONE, PARSER_STACK_SIZE, || self.assignment());
+        self.leave(1);
+        expr
     }
 
     fn assignment(&mut self) -> Result<Expr, LangError> {
-        let expr = self.or_expression()?;
+        let expr = self.ternary()?;
         
         // Проверяем операторы присваивания (+=, -=, *=, /=, //=, %=, **=)
         if self.match_token(TokenKind::PlusEqual)
@@ -344,8 +611,11 @@ impl Parser {
         {
             let op_line = self.previous().line;
             let op_kind = self.previous().kind.clone();
-            if let Expr::Variable { name, .. } = expr {
+            if let Expr::Variable { name, .. } = &expr {
+                let name = name.clone();
+                self.enter()?;
                 let value = self.assignment()?;
+                self.leave(1);
                 return Ok(Expr::AssignOp {
                     name,
                     op: op_kind,
@@ -362,8 +632,11 @@ impl Parser {
         // Обычное присваивание (=)
         if self.match_token(TokenKind::Equal) {
             let equal_line = self.previous().line;
-            if let Expr::Variable { name, .. } = expr {
+            if let Expr::Variable { name, .. } = &expr {
+                let name = name.clone();
+                self.enter()?;
                 let value = self.assignment()?;
+                self.leave(1);
                 return Ok(Expr::Assign {
                     name,
                     value: Box::new(value),
@@ -378,65 +651,139 @@ impl Parser {
         Ok(expr)
     }
 
-    fn or_expression(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.and_expression()?;
-        while self.match_token(TokenKind::Or) {
+    fn ternary(&mut self) -> Result<Expr, LangError> {
+        let condition = self.or_expression()?;
+        if self.match_token(TokenKind::Question) {
             let op_line = self.previous().line;
-            let op_kind = self.previous().kind.clone();
-            let right = self.and_expression()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                op: op_kind,
-                right: Box::new(right),
+            // Ветки правоассоциативны: a ? b : c ? d : e = a ? b : (c ? d : e)
+            self.enter()?;
+            let then_branch = self.ternary()?;
+            self.consume(TokenKind::Colon, "Expected ':' in conditional expression")?;
+            let else_branch = self.ternary()?;
+            self.leave(1);
+            return Ok(Expr::Ternary {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
                 line: op_line,
-            };
+            });
         }
-        Ok(expr)
+        Ok(condition)
+    }
+
+    fn or_expression(&mut self) -> Result<Expr, LangError> {
+        self.binary_level(&[TokenKind::Or], Self::and_expression)
     }
 
     fn and_expression(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.equality()?;
-        while self.match_token(TokenKind::And) {
+        self.binary_level(&[TokenKind::And], Self::not_expression)
+    }
+
+    fn not_expression(&mut self) -> Result<Expr, LangError> {
+        // Словесный not связывает слабее сравнений: not a == b = not (a == b)
+        if self.match_token(TokenKind::Not) {
             let op_line = self.previous().line;
-            let op_kind = self.previous().kind.clone();
-            let right = self.equality()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                op: op_kind,
+            self.enter()?;
+            let right = self.not_expression()?;
+            self.leave(1);
+            return Ok(Expr::Unary {
+                op: TokenKind::Not,
                 right: Box::new(right),
                 line: op_line,
-            };
+            });
         }
-        Ok(expr)
+        self.equality()
     }
 
     fn equality(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.comparison()?;
-        while self.match_token(TokenKind::BangEqual) || self.match_token(TokenKind::EqualEqual) {
-            let op_line = self.previous().line;
-            let op_kind = self.previous().kind.clone();
-            let right = self.comparison()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                op: op_kind,
-                right: Box::new(right),
-                line: op_line,
-            };
+        self.binary_level(&[TokenKind::BangEqual, TokenKind::EqualEqual], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, LangError> {
+        let mut expr = self.bit_or()?;
+        loop {
+            // Двухсловный оператор not in: a not in b = not (a in b)
+            let negated = self.check(TokenKind::Not) && self.check_next(TokenKind::In);
+            if negated {
+                self.advance();
+            }
+            if self.match_token(TokenKind::In) {
+                let op_line = self.previous().line;
+                let right = self.bit_or()?;
+                expr = Expr::Binary {
+                    left: Box::new(expr),
+                    op: TokenKind::In,
+                    right: Box::new(right),
+                    line: op_line,
+                };
+                if negated {
+                    expr = Expr::Unary {
+                        op: TokenKind::Not,
+                        right: Box::new(expr),
+                        line: op_line,
+                    };
+                }
+            } else if self.match_relational_operator() {
+                // Подряд идущие <, <=, >, >= образуют цепочку: 0 < x < 10 = 0 < x and x < 10
+                let op_line = self.previous().line;
+                let mut ops = vec![self.previous().kind.clone()];
+                let mut operands = vec![expr, self.bit_or()?];
+                while self.match_relational_operator() {
+                    ops.push(self.previous().kind.clone());
+                    operands.push(self.bit_or()?);
+                }
+                expr = if ops.len() == 1 {
+                    let right = operands.pop().unwrap();
+                    Expr::Binary {
+                        left: Box::new(operands.pop().unwrap()),
+                        op: ops.pop().unwrap(),
+                        right: Box::new(right),
+                        line: op_line,
+                    }
+                } else {
+                    Expr::ChainedComparison { operands, ops, line: op_line }
+                };
+            } else {
+                break;
+            }
         }
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.term()?;
-        while self.match_token(TokenKind::Greater)
+    fn match_relational_operator(&mut self) -> bool {
+        self.match_token(TokenKind::Greater)
             || self.match_token(TokenKind::GreaterEqual)
             || self.match_token(TokenKind::Less)
             || self.match_token(TokenKind::LessEqual)
-            || self.match_token(TokenKind::In)
-        {
+    }
+
+    /// Побитовые операторы связывают сильнее сравнений и слабее арифметики:
+    /// a | b ^ c & d << 1 = a | (b ^ (c & (d << 1)))
+    fn bit_or(&mut self) -> Result<Expr, LangError> {
+        self.binary_level(&[TokenKind::Pipe], Self::bit_xor)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expr, LangError> {
+        self.binary_level(&[TokenKind::Caret], Self::bit_and)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, LangError> {
+        self.binary_level(&[TokenKind::Ampersand], Self::shift)
+    }
+
+    fn shift(&mut self) -> Result<Expr, LangError> {
+        self.binary_level(&[TokenKind::LessLess, TokenKind::GreaterGreater], Self::term)
+    }
+
+    /// Левоассоциативная цепочка операторов ops с операндами operand. Плоская цепочка
+    /// a + b + c не увеличивает глубину вложенности: рекурсия здесь не растет с длиной цепочки
+    fn binary_level(&mut self, ops: &[TokenKind], operand: fn(&mut Self) -> Result<Expr, LangError>) -> Result<Expr, LangError> {
+        let mut expr = operand(self)?;
+        while ops.iter().any(|op| self.check(op.clone())) {
+            self.advance();
             let op_line = self.previous().line;
             let op_kind = self.previous().kind.clone();
-            let right = self.term()?;
+            let right = operand(self)?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 op: op_kind,
@@ -448,71 +795,55 @@ impl Parser {
     }
 
     fn term(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.factor()?;
-        while self.match_token(TokenKind::Minus) || self.match_token(TokenKind::Plus) {
-            let op_line = self.previous().line;
-            let op_kind = self.previous().kind.clone();
-            let right = self.factor()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                op: op_kind,
-                right: Box::new(right),
-                line: op_line,
-            };
-        }
-        Ok(expr)
+        self.binary_level(&[TokenKind::Minus, TokenKind::Plus], Self::factor)
     }
 
     fn factor(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.exponent()?;
-        while self.match_token(TokenKind::Slash) || self.match_token(TokenKind::SlashSlash) || self.match_token(TokenKind::Star) || self.match_token(TokenKind::Percent) {
+        self.binary_level(&[TokenKind::Slash, TokenKind::SlashSlash, TokenKind::Star, TokenKind::Percent], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, LangError> {
+        // Унарные операторы связывают слабее **: -x ** 2 = -(x ** 2)
+        if self.match_token(TokenKind::Bang) || self.match_token(TokenKind::Minus) {
             let op_line = self.previous().line;
             let op_kind = self.previous().kind.clone();
-            let right = self.exponent()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
+            self.enter()?;
+            let right = self.unary()?;
+            self.leave(1);
+            return Ok(Expr::Unary {
                 op: op_kind,
                 right: Box::new(right),
                 line: op_line,
-            };
+            });
         }
-        Ok(expr)
+        self.exponent()
     }
 
     fn exponent(&mut self) -> Result<Expr, LangError> {
-        let mut expr = self.unary()?;
+        let expr = self.call()?;
         // Exponentiation is right-associative: 2 ** 3 ** 2 = 2 ** (3 ** 2)
         // Don't match ** if the next token is **= (to avoid consuming **=)
-        while self.check(TokenKind::StarStar) && !self.check_next(TokenKind::StarStarEqual) {
+        if self.check(TokenKind::StarStar) && !self.check_next(TokenKind::StarStarEqual) {
             self.advance(); // Consume StarStar
             let op_line = self.previous().line;
-            let right = self.exponent()?; // Recursive call for right-associativity
-            expr = Expr::Binary {
+            // Правый операнд может быть унарным: 2 ** -1
+            self.enter()?;
+            let right = self.unary()?;
+            self.leave(1);
+            return Ok(Expr::Binary {
                 left: Box::new(expr),
                 op: TokenKind::StarStar,
                 right: Box::new(right),
                 line: op_line,
-            };
-        }
-        Ok(expr)
-    }
-
-    fn unary(&mut self) -> Result<Expr, LangError> {
-        if self.match_token(TokenKind::Bang) || self.match_token(TokenKind::Minus) {
-            let op_line = self.previous().line;
-            let op_kind = self.previous().kind.clone();
-            let right = self.unary()?;
-            return Ok(Expr::Unary {
-                op: op_kind,
-                right: Box::new(right),
-                line: op_line,
             });
         }
-        self.call()
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expr, LangError> {
         let mut expr = self.primary()?;
+        // Вызовы, индексы и свойства вкладывают выражение слева
+        let mut nested = 0;
         loop {
             // Обрабатываем вызовы функций (круглые скобки)
             // Может быть вызовом переменной или метода (Property)
@@ -522,6 +853,8 @@ impl Parser {
                 // (переменная или Property), а не просто группировка
                 match &expr {
                     Expr::Variable { .. } | Expr::Property { .. } => {
+                        self.enter()?;
+                        nested += 1;
                         self.advance(); // Съедаем LParen
                         expr = self.finish_call(expr)?;
                         continue;
@@ -535,14 +868,20 @@ impl Parser {
             }
             
             // Обрабатываем индексацию массивов (квадратные скобки)
-            // Массивом может быть любое выражение, не только переменная
-            if self.match_token(TokenKind::LBracket) {
+            // Массивом может быть любое выражение, не только переменная.
+            // '[' на новой строке начинает литерал массива следующей инструкции, а не индекс
+            if self.check(TokenKind::LBracket) && self.peek().line == self.previous().line {
+                self.advance(); // Съедаем LBracket
+                self.enter()?;
+                nested += 1;
                 expr = self.finish_array_index(expr)?;
                 continue;
             }
             
             // Обрабатываем доступ к свойствам (точка)
             if self.match_token(TokenKind::Dot) {
+                self.enter()?;
+                nested += 1;
                 let name = self.consume(TokenKind::Identifier, "Expect property name after '.'")?.lexeme.clone();
                 let line = self.previous().line;
                 expr = Expr::Property {
@@ -556,10 +895,11 @@ impl Parser {
             // Если ни вызов функции, ни индексация, ни свойство - выходим из цикла
             break;
         }
+        self.leave(nested);
         Ok(expr)
     }
 
-    fn finish_call(&mut self, callee: Expr) -> Result<Expr, LangError> {
+    fn finish_call(&mut self, mut callee: Expr) -> Result<Expr, LangError> {
         let call_line = self.previous().line; // Номер строки открывающей скобки (LParen)
         let mut args = Vec::new();
         let mut has_named = false;
@@ -574,8 +914,13 @@ impl Parser {
                 
                 // Проверяем, является ли это именованным аргументом (identifier = expression)
                 // Сохраняем текущую позицию для проверки
-                let arg = if self.check(TokenKind::Identifier) {
-                    // Проверяем, является ли следующий токен '='
+                let arg = if self.check(TokenKind::DotDotDot) {
+                    // ...value: массив раскрывается в позиционные аргументы, объект - в именованные,
+                    // поэтому spread допустим и после именованных аргументов
+                    Arg::Positional(self.spread()?)
+                } else if self.check(TokenKind::Identifier) || self.check(TokenKind::Fn) {
+                    // Проверяем, является ли следующий токен '='.
+                    // Ключевое слово fn тоже допустимо как имя: table_apply(t, fn = double)
                     // Сохраняем текущую позицию
                     let saved_current = self.current;
                     // Временно продвигаемся вперед для проверки
@@ -619,7 +964,8 @@ impl Parser {
                 };
                 
                 args.push(arg);
-                if !self.match_token(TokenKind::Comma) {
+                // Допускается завершающая запятая: f(a, b,)
+                if !self.match_token(TokenKind::Comma) || self.check(TokenKind::RParen) {
                     break;
                 }
             }
@@ -628,15 +974,15 @@ impl Parser {
         
         // Извлекаем имя функции из callee
         // Может быть переменной или методом (Property)
-        match callee {
+        match &mut callee {
             Expr::Variable { name, .. } => {
-                Ok(Expr::Call { name, args, line: call_line })
+                Ok(Expr::Call { name: std::mem::take(name), args, line: call_line })
             }
             Expr::Property { object, name, .. } => {
                 // Это вызов метода - создаем MethodCall
                 Ok(Expr::MethodCall {
-                    object,
-                    method: name,
+                    object: Box::new(object.take()),
+                    method: std::mem::take(name),
                     args,
                     line: call_line,
                 })
@@ -679,6 +1025,13 @@ impl Parser {
         if self.match_token(TokenKind::Number) {
             let line = self.previous().line;
             let lexeme = self.previous().lexeme.clone();
+            // Литерал без точки - Integer: f64 теряет точность выше 2^53 (9007199254740993).
+            // Целое за пределами i64 остается Number
+            if !lexeme.contains('.') {
+                if let Ok(value) = lexeme.parse::<i64>() {
+                    return Ok(Expr::Literal { value: Value::Integer(value), line });
+                }
+            }
             let value = lexeme.parse::<f64>()
                 .map_err(|_| LangError::ParseError {
                     message: "Invalid number".to_string(),
@@ -690,7 +1043,7 @@ impl Parser {
             let line = self.previous().line;
             let lexeme = self.previous().lexeme.clone();
             let value = lexeme[1..lexeme.len() - 1].to_string(); // Убираем кавычки
-            return Ok(Expr::Literal { value: Value::String(value), line });
+            return Ok(Expr::Literal { value: Value::String(value.into()), line });
         }
         if self.match_token(TokenKind::Identifier) {
             let line = self.previous().line;
@@ -705,6 +1058,9 @@ impl Parser {
         if self.match_token(TokenKind::LBracket) {
             return self.array_literal();
         }
+        if self.match_token(TokenKind::LBrace) {
+            return self.object_literal();
+        }
 
         let token = self.peek();
         Err(LangError::ParseError {
@@ -763,8 +1119,10 @@ impl Parser {
 
         if !self.check(TokenKind::RBracket) {
             loop {
-                elements.push(self.expression()?);
-                if !self.match_token(TokenKind::Comma) {
+                elements.push(if self.check(TokenKind::DotDotDot) { self.spread()? } else { self.expression()? });
+                self.check_collection_length(elements.len())?;
+                // Допускается завершающая запятая: [1, 2,]
+                if !self.match_token(TokenKind::Comma) || self.check(TokenKind::RBracket) {
                     break;
                 }
             }
@@ -773,23 +1131,18 @@ impl Parser {
         self.consume(TokenKind::RBracket, "Expect ']' after array elements")?;
 
         // Если все элементы - литералы, создаем Value::Array напрямую
-        // Иначе создаем ArrayLiteral для компиляции во время выполнения
-        let mut all_literals = true;
-        let mut values = Vec::new();
-        
-        for expr in &elements {
-            match expr {
-                Expr::Literal { value, .. } => {
-                    values.push(value.clone());
-                }
-                _ => {
-                    all_literals = false;
-                    break;
-                }
-            }
-        }
+        // Иначе создаем ArrayLiteral для компиляции во время выполнения.
+        // Значения переносятся без clone(): clone() массива глубокий и рекурсивный
+        let all_literals = elements.iter().all(|expr| matches!(expr, Expr::Literal { .. }));
 
         if all_literals {
+            let values = elements
+                .into_iter()
+                .filter_map(|mut expr| match &mut expr {
+                    Expr::Literal { value, .. } => Some(std::mem::replace(value, Value::Null)),
+                    _ => None,
+                })
+                .collect();
             Ok(Expr::Literal {
                 value: Value::Array(Rc::new(RefCell::new(values))),
                 line,
@@ -802,6 +1155,53 @@ impl Parser {
         }
     }
 
+    /// `...выражение` в аргументах вызова, массиве или объекте
+    fn spread(&mut self) -> Result<Expr, LangError> {
+        let line = self.advance().line;
+        Ok(Expr::Spread { value: Box::new(self.expression()?), line })
+    }
+
+    /// Объектный литерал {ключ: значение, ...}; ключ - строка или идентификатор
+    fn object_literal(&mut self) -> Result<Expr, LangError> {
+        let line = self.previous().line;
+        let mut entries = Vec::new();
+
+        if !self.check(TokenKind::RBrace) {
+            loop {
+                if self.check(TokenKind::DotDotDot) {
+                    // {...defaults, "x": 1}: ключи раскрываемого объекта, последующие записи их заменяют
+                    entries.push((String::new(), self.spread()?));
+                    self.check_collection_length(entries.len())?;
+                    if !self.match_token(TokenKind::Comma) || self.check(TokenKind::RBrace) {
+                        break;
+                    }
+                    continue;
+                }
+                let key = if self.match_token(TokenKind::String) {
+                    let lexeme = &self.previous().lexeme;
+                    lexeme[1..lexeme.len() - 1].to_string() // Убираем кавычки
+                } else if self.match_token(TokenKind::Identifier) {
+                    self.previous().lexeme.clone()
+                } else {
+                    return Err(LangError::ParseError {
+                        message: "Expect string or identifier as object key".to_string(),
+                        line: self.peek().line,
+                    });
+                };
+                self.consume(TokenKind::Colon, "Expect ':' after object key")?;
+                entries.push((key, self.expression()?));
+                self.check_collection_length(entries.len())?;
+                // Допускается завершающая запятая: {"a": 1,}
+                if !self.match_token(TokenKind::Comma) || self.check(TokenKind::RBrace) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenKind::RBrace, "Expect '}' after object entries")?;
+        Ok(Expr::ObjectLiteral { entries, line })
+    }
+
     fn consume(&mut self, kind: TokenKind, message: &str) -> Result<&Token, LangError> {
         if self.check(kind) {
             Ok(self.advance())
@@ -0,0 +1,167 @@
+// Отчет о прогрессе долгих циклов: progress(), progress_done() и set_option("auto_progress", true)
+//
+// В терминале прогресс рисуется одной обновляемой строкой с полосой, процентом и ETA.
+// Если вывод перехвачен (OutputCapture) или stdout не терминал, печатаются обычные строки,
+// не чаще одной в PLAIN_INTERVAL
+
+use crate::common::output_capture::OutputCapture;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Минимальный интервал между строками прогресса без терминала
+pub const PLAIN_INTERVAL: Duration = Duration::from_secs(2);
+/// Минимальный интервал между перерисовками полосы в терминале
+pub const TTY_INTERVAL: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 30;
+
+/// Пропускает событие, если с прошлого пропущенного прошло не меньше interval.
+/// Первое событие пропускается всегда
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last: None }
+    }
+
+    pub fn allow(&mut self, now: Instant) -> bool {
+        match self.last {
+            Some(last) if now.saturating_duration_since(last) < self.interval => false,
+            _ => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// Состояние одной задачи прогресса (по метке)
+struct Task {
+    started: Instant,
+    limiter: RateLimiter,
+    terminal: bool,
+    current: u64,
+    total: u64,
+}
+
+thread_local! {
+    static TASKS: RefCell<HashMap<String, Task>> = RefCell::new(HashMap::new());
+}
+
+/// Длительность в виде "42s", "3m 05s" или "1h 02m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+fn percent(current: u64, total: u64) -> u64 {
+    // Пустая задача считается выполненной
+    (current.min(total) * 100).checked_div(total).unwrap_or(100)
+}
+
+/// Оставшееся время по средней скорости; "?" пока не обработано ни одного элемента
+fn eta(current: u64, total: u64, elapsed: Duration) -> String {
+    if current == 0 {
+        return "?".to_string();
+    }
+    let remaining = total.saturating_sub(current) as f64 * elapsed.as_secs_f64() / current as f64;
+    format_duration(Duration::from_secs_f64(remaining))
+}
+
+/// Строка прогресса для терминала: "loading [######--------] 40% 4/10 ETA 6s"
+pub fn render_bar(label: &str, current: u64, total: u64, elapsed: Duration) -> String {
+    let filled = (percent(current, total) as usize * BAR_WIDTH) / 100;
+    format!(
+        "{} [{}{}] {}% {}/{} ETA {}",
+        label,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        percent(current, total),
+        current,
+        total,
+        eta(current, total, elapsed)
+    )
+}
+
+/// Строка прогресса без терминала: "loading: 4/10 (40%), ETA 6s"
+pub fn render_line(label: &str, current: u64, total: u64, elapsed: Duration) -> String {
+    format!(
+        "{}: {}/{} ({}%), ETA {}",
+        label,
+        current,
+        total,
+        percent(current, total),
+        eta(current, total, elapsed)
+    )
+}
+
+/// Итоговая строка без терминала: "loading: done, 10 items in 12s"
+pub fn render_done(label: &str, total: u64, elapsed: Duration) -> String {
+    format!("{}: done, {} items in {}", label, total, format_duration(elapsed))
+}
+
+fn redraw(line: &str) {
+    let mut stdout = std::io::stdout();
+    // \x1b[K стирает остаток предыдущей, более длинной строки
+    let _ = write!(stdout, "\r{}\x1b[K", line);
+    let _ = stdout.flush();
+}
+
+/// Сообщает о прогрессе задачи label. Новая задача (или начатая заново с меньшего current)
+/// отсчитывает время от этого вызова
+pub fn report(label: &str, current: u64, total: u64) {
+    let now = Instant::now();
+    TASKS.with(|tasks| {
+        let mut tasks = tasks.borrow_mut();
+        let restart = tasks.get(label).is_none_or(|task| current < task.current);
+        if restart {
+            let terminal = OutputCapture::is_terminal();
+            let interval = if terminal { TTY_INTERVAL } else { PLAIN_INTERVAL };
+            tasks.insert(label.to_string(), Task {
+                started: now,
+                limiter: RateLimiter::new(interval),
+                terminal,
+                current,
+                total,
+            });
+        }
+        let task = tasks.get_mut(label).expect("task is registered above");
+        task.current = current;
+        task.total = total;
+        if !task.limiter.allow(now) {
+            return;
+        }
+        let elapsed = now.saturating_duration_since(task.started);
+        if task.terminal {
+            redraw(&render_bar(label, current, total, elapsed));
+        } else {
+            OutputCapture::write_output(&render_line(label, current, total, elapsed));
+        }
+    });
+}
+
+/// Завершает задачу label: в терминале дорисовывает полосу и переводит строку,
+/// иначе печатает итоговую строку. Для неизвестной метки ничего не делает
+pub fn finish(label: &str) {
+    let task = TASKS.with(|tasks| tasks.borrow_mut().remove(label));
+    if let Some(task) = task {
+        let elapsed = task.started.elapsed();
+        if task.terminal {
+            redraw(&render_bar(label, task.total, task.total, elapsed));
+            println!();
+        } else {
+            OutputCapture::write_output(&render_done(label, task.total, elapsed));
+        }
+    }
+}
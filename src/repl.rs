@@ -0,0 +1,101 @@
+// Интерактивный режим: выполнение одной строки REPL и правила вывода ее результата.
+// Строки выполняются на одной VM (Session): переменные и функции сохраняются между строками,
+// в том числе после ошибки или прерывания по Ctrl-C. При запуске файла значения выражений
+// отбрасываются молча; в REPL выводится значение выражения верхнего уровня, кроме присваиваний
+// и null. Последнее выведенное значение доступно следующим строкам как переменная `_`.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use crate::common::{error::LangError, value::Value};
+use crate::parser::ast::{Expr, Stmt};
+use crate::session::Session;
+
+/// Имя переменной с последним выведенным значением
+pub const LAST_VALUE_NAME: &str = "_";
+
+/// Состояние REPL между строками: сессия интерпретатора и последнее выведенное значение
+pub struct ReplSession {
+    last_value: Option<Value>,
+    session: Session,
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        Self { last_value: None, session: Session::new() }
+    }
+
+    /// Сессия с общим флагом отмены: выставленный флаг прерывает текущую строку
+    pub fn with_cancel(cancel: Arc<AtomicBool>) -> Self {
+        Self { last_value: None, session: Session::new().cancel_handle(cancel) }
+    }
+
+    /// Сессия с общим флагом прерывания (Ctrl-C): текущая строка получает ошибку Interrupted
+    pub fn with_interrupt(interrupt: Arc<AtomicBool>) -> Self {
+        Self { last_value: None, session: Session::new().interrupt_handle(interrupt) }
+    }
+
+    /// Последнее выведенное значение (значение `_`)
+    pub fn last_value(&self) -> Option<&Value> {
+        self.last_value.as_ref()
+    }
+
+    /// Значение глобальной переменной, заданной предыдущими строками
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.session.get_global(name)
+    }
+}
+
+impl Default for ReplSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Выполняет строку REPL. Возвращает значение, которое нужно вывести: результат выражения
+/// верхнего уровня, если последняя инструкция строки - не присваивание и значение не null.
+/// Выведенное значение становится значением `_` для следующих строк.
+pub fn run_repl_line(session: &mut ReplSession, line: &str) -> Result<Option<Value>, LangError> {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    // Разбор нужен только для правила вывода; строку компилирует и выполняет сессия
+    let tokens = Lexer::new(line).tokenize()?;
+    let ast = Parser::new(tokens).parse()?;
+    let echo = ast.last().is_some_and(is_echoed_statement);
+
+    // `_` определен только после первого выведенного значения
+    if let Some(last) = &session.last_value {
+        session.session.set_global(LAST_VALUE_NAME, last.clone());
+    }
+    let value = session.session.run(line)?;
+
+    if !echo || matches!(value, Value::Null) {
+        return Ok(None);
+    }
+    session.last_value = Some(value.clone());
+    Ok(Some(value))
+}
+
+/// Выводится ли результат инструкции: только выражение, не являющееся присваиванием
+fn is_echoed_statement(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Expr { expr, .. } => !matches!(expr, Expr::Assign { .. } | Expr::AssignOp { .. }),
+        _ => false,
+    }
+}
+
+/// Выводит результат строки REPL: таблицы - как show_table(), остальные значения - после "=> "
+pub fn print_result(value: &Value) {
+    use crate::common::render::{render_table, RenderOptions};
+
+    match value {
+        Value::Table(table) => print!("{}", render_table(&table.borrow(), &RenderOptions::default())),
+        // Ленивая таблица вычисляется перед выводом
+        Value::LazyTable(lazy) => match lazy.try_collect() {
+            Ok((table, _)) => print!("{}", render_table(&table, &RenderOptions::default())),
+            Err(message) => eprintln!("Ошибка выполнения: {}", message),
+        },
+        _ => println!("=> {}", value.to_string()),
+    }
+}
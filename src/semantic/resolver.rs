@@ -1,9 +1,14 @@
 // Разрешение переменных и подготовка к компиляции
 
-use crate::parser::ast::{Expr, Stmt, Param, Arg};
+use crate::parser::ast::{Expr, Stmt, Param, Arg, BindingKind};
 use crate::common::error::LangError;
 use crate::semantic::scope::Scope;
 
+/// Если до конца стека осталось меньше RESOLVER_RED_ZONE байт, resolve_expr продолжает
+/// рекурсию на новом сегменте размером RESOLVER_STACK_SIZE
+const RESOLVER_RED_ZONE: usize = 128 * 1024;
+const RESOLVER_STACK_SIZE: usize = 4 * 1024 * 1024;
+
 pub struct Resolver {
     scopes: Vec<Scope>,
     current_function: FunctionType,
@@ -40,6 +45,15 @@ impl Resolver {
                     self.define(name);
                 }
             }
+            Stmt::Destructure { names, value, kind, .. } => {
+                self.resolve_expr(value)?;
+                if *kind == BindingKind::Let {
+                    for name in names {
+                        self.declare(name);
+                        self.define(name);
+                    }
+                }
+            }
             Stmt::Expr { expr, .. } => {
                 self.resolve_expr(expr)?;
             }
@@ -121,7 +135,22 @@ impl Resolver {
             }
             Stmt::Throw { value, .. } => {
                 // Разрешаем выражение в throw
+                if let Some(value) = value {
+                    self.resolve_expr(value)?;
+                }
+            }
+            Stmt::Match { value, cases, default, .. } => {
                 self.resolve_expr(value)?;
+                // Каждая ветка - отдельная область видимости
+                for case in cases {
+                    for case_value in &case.values {
+                        self.resolve_expr(case_value)?;
+                    }
+                    self.resolve_stmt_block(&case.body)?;
+                }
+                if let Some(default) = default {
+                    self.resolve_stmt_block(default)?;
+                }
             }
         }
         Ok(())
@@ -137,6 +166,11 @@ impl Resolver {
     }
 
     fn resolve_expr(&mut self, expr: &Expr) -> Result<(), LangError> {
+        // Дерево длинной цепочки операторов глубже любого ограничения вложенности парсера
+        stacker::maybe_grow(RESOLVER_RED_ZONE, RESOLVER_STACK_SIZE, || self.resolve_expr_inner(expr))
+    }
+
+    fn resolve_expr_inner(&mut self, expr: &Expr) -> Result<(), LangError> {
         match expr {
             Expr::Variable { name, line } => {
                 if !self.scopes.is_empty() {
@@ -180,11 +214,29 @@ impl Resolver {
             Expr::Unary { right, .. } => {
                 self.resolve_expr(right)?;
             }
+            Expr::ChainedComparison { operands, .. } => {
+                for operand in operands {
+                    self.resolve_expr(operand)?;
+                }
+            }
+            Expr::Ternary { condition, then_branch, else_branch, .. } => {
+                self.resolve_expr(condition)?;
+                self.resolve_expr(then_branch)?;
+                self.resolve_expr(else_branch)?;
+            }
             Expr::ArrayLiteral { elements, .. } => {
                 for element in elements {
                     self.resolve_expr(element)?;
                 }
             }
+            Expr::ObjectLiteral { entries, .. } => {
+                for (_, value) in entries {
+                    self.resolve_expr(value)?;
+                }
+            }
+            Expr::Spread { value, .. } => {
+                self.resolve_expr(value)?;
+            }
             Expr::ArrayIndex { array, index, .. } => {
                 self.resolve_expr(array)?;
                 self.resolve_expr(index)?;
@@ -0,0 +1,172 @@
+// Встраивание DataCode в программы на Rust: сессия с общим состоянием интерпретатора.
+// Каждый вызов exec() выполняет фрагмент кода на той же VM, как строки REPL: глобальные
+// переменные и функции сохраняются между вызовами. Вывод print() может перехватываться,
+// ошибки возвращаются в структурированном виде.
+
+use crate::common::error::{LangError, StructuredError};
+use crate::common::output_capture::OutputCapture;
+use crate::common::value::Value;
+use crate::vm::policy::{get_execution_policy, set_execution_policy, ExecutionPolicy};
+use crate::vm::Vm;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Результат Session::exec
+#[derive(Debug, Clone)]
+pub struct ExecOutcome {
+    /// Значение последней инструкции (null при ошибке)
+    pub value: Value,
+    /// Перехваченный вывод print(); пустой, если перехват выключен
+    pub output: String,
+    pub error: Option<StructuredError>,
+}
+
+impl ExecOutcome {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Сессия интерпретатора для встраивания: Session::new().capture_output(true).policy(p),
+/// затем set_global() для входных данных, exec() для кода и get_global() для результатов
+pub struct Session {
+    vm: Option<Vm>,
+    capture_output: bool,
+    policy: Option<ExecutionPolicy>,
+    cancel: Option<Arc<AtomicBool>>,
+    interrupt: Option<Arc<AtomicBool>>,
+    profile: bool,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self { vm: None, capture_output: false, policy: None, cancel: None, interrupt: None, profile: false }
+    }
+
+    /// Перехватывать вывод print() в ExecOutcome::output вместо stdout
+    pub fn capture_output(mut self, enabled: bool) -> Self {
+        self.capture_output = enabled;
+        self
+    }
+
+    /// Политика выполнения (разрешения, лимиты памяти и времени) для кода сессии.
+    /// Действует на время каждого вызова; политика потока после вызова восстанавливается
+    pub fn policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Общий флаг отмены (Vm::set_cancel_handle): выставленный флаг прерывает выполнение
+    /// неперехватываемой ошибкой "execution cancelled"
+    pub fn cancel_handle(mut self, cancel: Arc<AtomicBool>) -> Self {
+        if let Some(vm) = &mut self.vm {
+            vm.set_cancel_handle(Arc::clone(&cancel));
+        }
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Общий флаг прерывания пользователем (Vm::set_interrupt_handle), например от обработчика
+    /// Ctrl-C: выполнение получает ошибку Interrupted, которую программа может перехватить
+    pub fn interrupt_handle(mut self, interrupt: Arc<AtomicBool>) -> Self {
+        if let Some(vm) = &mut self.vm {
+            vm.set_interrupt_handle(Arc::clone(&interrupt));
+        }
+        self.interrupt = Some(interrupt);
+        self
+    }
+
+    /// Профилировать выполнение по стекам вызовов (Vm::profiler), как при DATACODE_PROFILE
+    pub fn profile(mut self, enabled: bool) -> Self {
+        if let (Some(vm), true) = (&mut self.vm, enabled) {
+            vm.enable_profiling();
+        }
+        self.profile = enabled;
+        self
+    }
+
+    /// Выполняет фрагмент кода в состоянии сессии. Ошибка не завершает сессию:
+    /// переменные, заданные до ошибки, сохраняются
+    pub fn exec(&mut self, code: &str) -> ExecOutcome {
+        let capture = self.capture_output.then(|| {
+            let capture = OutputCapture::new();
+            capture.set_capture(true);
+            capture
+        });
+        let result = self.run(code);
+        let output = match capture {
+            Some(capture) => {
+                let output = capture.get_output();
+                capture.set_capture(false);
+                output
+            }
+            None => String::new(),
+        };
+        match result {
+            Ok(value) => ExecOutcome { value, output, error: None },
+            Err(error) => ExecOutcome { value: Value::Null, output, error: Some(error.to_structured(Some(code))) },
+        }
+    }
+
+    /// Выполняет фрагмент кода как exec(), но без перехвата вывода и с исходной ошибкой
+    pub fn run(&mut self, code: &str) -> Result<Value, LangError> {
+        self.with_vm(|vm| vm.run_continued(code))
+    }
+
+    /// Значение глобальной переменной (в том числе объявленной без `global` на верхнем уровне)
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.vm.as_ref()?.get_global_by_name(name).cloned()
+    }
+
+    /// Задает глобальную переменную, видимую следующим вызовам exec()
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.with_vm(|vm| vm.set_global_by_name(name, value));
+    }
+
+    /// VM сессии (для экспорта в SQLite и просмотра переменных); None до первого использования
+    pub fn vm(&self) -> Option<&Vm> {
+        self.vm.as_ref()
+    }
+
+    /// Завершает сессию и возвращает ее VM
+    pub fn into_vm(mut self) -> Vm {
+        self.with_vm(|_| ());
+        self.vm.take().unwrap_or_else(Vm::new)
+    }
+
+    /// Вызывает f с VM сессии под политикой сессии. VM создается при первом вызове,
+    /// чтобы лимит памяти политики учитывался с самого начала
+    fn with_vm<T>(&mut self, f: impl FnOnce(&mut Vm) -> T) -> T {
+        let saved_policy = self.policy.as_ref().map(|policy| {
+            let saved = get_execution_policy();
+            set_execution_policy(policy.clone());
+            saved
+        });
+        let (cancel, interrupt, profile) = (&self.cancel, &self.interrupt, self.profile);
+        let vm = self.vm.get_or_insert_with(|| {
+            let mut vm = Vm::new();
+            if profile {
+                vm.enable_profiling();
+            }
+            if let Some(cancel) = cancel {
+                vm.set_cancel_handle(Arc::clone(cancel));
+            }
+            if let Some(interrupt) = interrupt {
+                vm.set_interrupt_handle(Arc::clone(interrupt));
+            }
+            vm.register_native_globals();
+            vm
+        });
+        let result = f(vm);
+        if let Some(saved) = saved_policy {
+            set_execution_policy(saved);
+        }
+        result
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
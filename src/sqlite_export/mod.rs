@@ -10,10 +10,12 @@ use chrono::Utc;
 
 /// Структура для хранения информации о таблице для экспорта
 struct TableInfo {
-    #[allow(dead_code)]
+    /// Имя переменной DataCode (для таблиц declare_export() вне переменных - имя таблицы SQLite)
     name: String,
     table: Rc<RefCell<Table>>,
     sqlite_name: String,
+    /// Первичный ключ из declare_export()
+    primary_key: Option<String>,
 }
 
 /// Структура для информации о первичном ключе
@@ -31,16 +33,74 @@ struct ForeignKeyInfo {
     referenced_table: String,
     #[allow(dead_code)]
     referenced_column: String,
+    /// Объявлен через relate()/register_foreign_key(), а не определен автоматически
+    explicit: bool,
+}
+
+/// Параметры экспорта в SQLite
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Пары (переменная или glob шаблон, имя таблицы SQLite) из --tables
+    pub tables: Option<Vec<(String, String)>>,
+    /// Автоматически определять внешние ключи по именам колонок (--no-auto-fk отключает)
+    pub auto_foreign_keys: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions { tables: None, auto_foreign_keys: true }
+    }
 }
 
 /// Главная функция экспорта в SQLite
 pub fn export_to_sqlite(vm: &Vm, output_path: &str) -> Result<(), String> {
-    // Получаем все таблицы из глобальных переменных
-    let tables = get_global_tables(vm)?;
+    export_to_sqlite_with(vm, output_path, &ExportOptions::default())
+}
+
+/// Является ли элемент --tables шаблоном имен переменных (glob: *, ?, [...])
+fn is_table_pattern(entry: &str) -> bool {
+    entry.contains(['*', '?', '['])
+}
+
+/// Разбор --tables: "sales:fact_sales,customers,final_*" → [(sales, fact_sales), (customers, customers),
+/// (final_*, final_*)]. Шаблон выбирает все подходящие переменные под их собственными именами
+pub fn parse_table_map(spec: &str) -> Result<Vec<(String, String)>, String> {
+    let mut map = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (variable, table) = match entry.split_once(':') {
+            Some((variable, table)) => (variable.trim(), table.trim()),
+            None => (entry, entry),
+        };
+        if variable.is_empty() || table.is_empty() {
+            return Err(format!("некорректный элемент --tables: '{}' (ожидается переменная:таблица)", entry));
+        }
+        if is_table_pattern(variable) {
+            if variable != table {
+                return Err(format!("--tables: шаблон '{}' нельзя переименовать", variable));
+            }
+            glob::Pattern::new(variable).map_err(|e| format!("--tables: некорректный шаблон '{}': {}", variable, e))?;
+        }
+        map.push((variable.to_string(), table.to_string()));
+    }
+    if map.is_empty() {
+        return Err("--tables: не указано ни одной таблицы".to_string());
+    }
+    Ok(map)
+}
+
+/// Экспорт в SQLite с параметрами. options.tables (--tables) - пары (переменная или glob шаблон,
+/// имя таблицы SQLite): экспортируются только они, об остальных выводится предупреждение. Без них экспортируются таблицы declare_export(), а если
+/// объявлений нет - все таблицы глобальных переменных
+pub fn export_to_sqlite_with(vm: &Vm, output_path: &str, options: &ExportOptions) -> Result<(), String> {
+    let tables = select_export_tables(vm, options.tables.as_deref())?;
     
     if tables.is_empty() {
         return Err("Нет таблиц для экспорта".to_string());
     }
+    let mut sqlite_names = HashSet::new();
+    if let Some(duplicate) = tables.iter().map(|t| &t.sqlite_name).find(|name| !sqlite_names.insert(name.to_lowercase())) {
+        return Err(format!("Несколько таблиц экспортируются под именем {}", duplicate));
+    }
 
     // Создаем базу данных
     let mut conn = Connection::open(output_path)
@@ -51,17 +111,11 @@ pub fn export_to_sqlite(vm: &Vm, output_path: &str) -> Result<(), String> {
         .map_err(|e| format!("Ошибка включения FOREIGN KEY: {}", e))?;
 
     // Экспортируем каждую таблицу (сначала без FOREIGN KEY)
-    let mut table_infos = Vec::new();
-    for (var_name, table) in &tables {
-        let sqlite_name = sanitize_table_name(var_name);
-        export_table(&conn, var_name, &sqlite_name, table)
-            .map_err(|e| format!("Ошибка экспорта таблицы {}: {}", var_name, e))?;
-        table_infos.push(TableInfo {
-            name: var_name.clone(),
-            table: table.clone(),
-            sqlite_name,
-        });
+    for table_info in &tables {
+        export_table(&conn, &table_info.name, &table_info.sqlite_name, &table_info.table)
+            .map_err(|e| format!("Ошибка экспорта таблицы {}: {}", table_info.name, e))?;
     }
+    let table_infos = tables;
 
     // Определяем первичные ключи (явные имеют приоритет)
     let primary_keys = detect_primary_keys(&table_infos, vm)?;
@@ -69,11 +123,17 @@ pub fn export_to_sqlite(vm: &Vm, output_path: &str) -> Result<(), String> {
     // Получаем явные связи из VM
     let explicit_foreign_keys = get_explicit_foreign_keys(&table_infos, vm);
 
-    // Определяем внешние ключи (явные связи имеют приоритет, затем автоматическое определение)
-    let foreign_keys = detect_foreign_keys(&table_infos, &primary_keys, &explicit_foreign_keys)?;
+    // Определяем внешние ключи (явные связи имеют приоритет, затем автоматическое определение,
+    // если оно не отключено)
+    let foreign_keys = if options.auto_foreign_keys {
+        detect_foreign_keys(&table_infos, &primary_keys, &explicit_foreign_keys)?
+    } else {
+        explicit_foreign_keys
+    };
 
-    // Пересоздаем таблицы с FOREIGN KEY constraints
-    if !foreign_keys.is_empty() {
+    // Пересоздаем таблицы с FOREIGN KEY и PRIMARY KEY constraints (первичные ключи
+    // declare_export() всегда становятся ограничениями)
+    if !foreign_keys.is_empty() || table_infos.iter().any(|t| t.primary_key.is_some()) {
         recreate_tables_with_foreign_keys(&mut conn, &table_infos, &primary_keys, &foreign_keys)
             .map_err(|e| format!("Ошибка пересоздания таблиц с FOREIGN KEY: {}", e))?;
     } else {
@@ -83,13 +143,103 @@ pub fn export_to_sqlite(vm: &Vm, output_path: &str) -> Result<(), String> {
     }
 
     // Создаем таблицу метаданных
-    create_metadata_table(&conn, vm, &tables)
+    create_metadata_table(&conn, vm, &table_infos, &foreign_keys)
         .map_err(|e| format!("Ошибка создания таблицы метаданных: {}", e))?;
 
+    // Скалярные глобальные переменные - в таблицу ключ-значение
+    create_scalars_table(&conn, vm)
+        .map_err(|e| format!("Ошибка создания таблицы _datacode_scalars: {}", e))?;
+
     println!("✅ Экспорт завершен: {}", output_path);
     Ok(())
 }
 
+/// Таблицы для экспорта: из --tables, из declare_export() или все глобальные
+fn select_export_tables(vm: &Vm, table_map: Option<&[(String, String)]>) -> Result<Vec<TableInfo>, String> {
+    let declarations = vm.get_export_declarations();
+    let declared_table = |declaration: &crate::vm::vm::ExportDeclaration| match &declaration.table {
+        Value::Table(table) => Some(table.clone()),
+        Value::LazyTable(lazy) => Some(Rc::new(RefCell::new(lazy.collect().0))),
+        _ => None,
+    };
+
+    if let Some(table_map) = table_map {
+        let globals = get_global_tables(vm)?;
+        let mut candidates: Vec<&str> = globals
+            .keys()
+            .map(String::as_str)
+            .chain(declarations.iter().filter_map(|d| d.variable_name.as_deref()))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // Имена переменных и таблиц SQLite: шаблоны раскрываются в подходящие переменные
+        let mut names: Vec<(&str, &str)> = Vec::new();
+        for (variable, sqlite_name) in table_map {
+            if is_table_pattern(variable) {
+                let pattern = glob::Pattern::new(variable).map_err(|e| format!("--tables: некорректный шаблон '{}': {}", variable, e))?;
+                for &candidate in candidates.iter().filter(|c| pattern.matches(c)) {
+                    if !names.iter().any(|(name, _)| *name == candidate) {
+                        names.push((candidate, candidate));
+                    }
+                }
+            } else if let Some(entry) = names.iter_mut().find(|entry| entry.0 == variable.as_str()) {
+                entry.1 = sqlite_name.as_str();
+            } else if candidates.contains(&variable.as_str()) {
+                names.push((variable.as_str(), sqlite_name.as_str()));
+            } else {
+                return Err(format!("--tables: таблица '{}' не найдена среди глобальных переменных", variable));
+            }
+        }
+
+        let skipped: Vec<&str> = candidates.iter().copied().filter(|c| !names.iter().any(|(name, _)| name == c)).collect();
+        if !skipped.is_empty() {
+            eprintln!("⚠️ Не экспортируются (не выбраны в --tables): {}", skipped.join(", "));
+        }
+
+        let mut selected = Vec::new();
+        for (variable, sqlite_name) in names {
+            // Объявленная через declare_export() таблица сохраняет свой первичный ключ
+            let declaration = declarations.iter().find(|d| d.variable_name.as_deref() == Some(variable));
+            if let Some(table) = declaration.and_then(declared_table).or_else(|| globals.get(variable).cloned()) {
+                selected.push(TableInfo {
+                    name: variable.to_string(),
+                    table,
+                    sqlite_name: sanitize_table_name(sqlite_name),
+                    primary_key: declaration.and_then(|d| d.primary_key.clone()),
+                });
+            }
+        }
+        return Ok(selected);
+    }
+
+    if !declarations.is_empty() {
+        return Ok(declarations
+            .iter()
+            .filter_map(|declaration| {
+                Some(TableInfo {
+                    name: declaration.variable_name.clone().unwrap_or_else(|| declaration.table_name.clone()),
+                    table: declared_table(declaration)?,
+                    sqlite_name: sanitize_table_name(&declaration.table_name),
+                    primary_key: declaration.primary_key.clone(),
+                })
+            })
+            .collect());
+    }
+
+    let mut tables: Vec<TableInfo> = get_global_tables(vm)?
+        .into_iter()
+        .map(|(name, table)| TableInfo {
+            sqlite_name: sanitize_table_name(&name),
+            name,
+            table,
+            primary_key: None,
+        })
+        .collect();
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tables)
+}
+
 /// Получить все таблицы из глобальных переменных VM
 fn get_global_tables(vm: &Vm) -> Result<HashMap<String, Rc<RefCell<Table>>>, String> {
     let mut tables = HashMap::new();
@@ -100,14 +250,27 @@ fn get_global_tables(vm: &Vm) -> Result<HashMap<String, Rc<RefCell<Table>>>, Str
     for (index, value) in globals.iter().enumerate() {
         if let Some(_var_name) = explicit_global_names.get(&index) {
             // Пропускаем нативные функции
-            if matches!(value, Value::NativeFunction(_) | Value::Function(_)) {
+            if matches!(value, Value::NativeFunction(_) | Value::Function(_) | Value::Closure(_)) {
                 continue;
             }
 
-            // Проверяем, является ли значение таблицей
-            if let Value::Table(table) = value {
-                if let Some(var_name) = explicit_global_names.get(&index) {
-                    tables.insert(var_name.clone(), table.clone());
+            // Проверяем, является ли значение таблицей (ленивая таблица вычисляется перед экспортом)
+            if let Some(var_name) = explicit_global_names.get(&index) {
+                match value {
+                    Value::Table(table) => {
+                        tables.insert(var_name.clone(), table.clone());
+                    }
+                    Value::LazyTable(lazy) => {
+                        let (table, _) = lazy.try_collect()?;
+                        tables.insert(var_name.clone(), Rc::new(RefCell::new(table)));
+                    }
+                    // Массив объектов экспортируется как таблица
+                    Value::Array(items) => {
+                        if let Some(table) = objects_to_table(&items.borrow()) {
+                            tables.insert(var_name.clone(), Rc::new(RefCell::new(table)));
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
@@ -116,6 +279,36 @@ fn get_global_tables(vm: &Vm) -> Result<HashMap<String, Rc<RefCell<Table>>>, Str
     Ok(tables)
 }
 
+/// Таблица из непустого массива объектов: колонки - объединение ключей в порядке первого
+/// появления, отсутствующие у объекта ключи заполняются null. None, если элемент не объект
+fn objects_to_table(items: &[Value]) -> Option<Table> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut headers: Vec<String> = Vec::new();
+    let mut known: HashSet<&str> = HashSet::new();
+    for item in items {
+        match item {
+            Value::Object(map) => {
+                for key in map.keys() {
+                    if known.insert(key.as_str()) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+            _ => return None,
+        }
+    }
+    let rows = items
+        .iter()
+        .map(|item| match item {
+            Value::Object(map) => headers.iter().map(|h| map.get(h).cloned().unwrap_or(Value::Null)).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    Some(Table::from_data(rows, Some(headers)))
+}
+
 /// Санитизация имени таблицы для SQLite
 fn sanitize_table_name(name: &str) -> String {
     // Заменяем недопустимые символы на подчеркивания
@@ -180,31 +373,7 @@ fn export_table(
         
         for row in &table_ref.rows {
             // Преобразуем значения в параметры SQLite
-            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-            for value in row {
-                params_vec.push(match value {
-                    Value::Number(n) => {
-                        if n.fract() == 0.0 {
-                            Box::new(*n as i64) as Box<dyn rusqlite::ToSql>
-                        } else {
-                            Box::new(*n) as Box<dyn rusqlite::ToSql>
-                        }
-                    }
-                    Value::Bool(b) => {
-                        Box::new(if *b { 1i64 } else { 0i64 }) as Box<dyn rusqlite::ToSql>
-                    }
-                    Value::String(s) => {
-                        Box::new(s.clone()) as Box<dyn rusqlite::ToSql>
-                    }
-                    Value::Null => {
-                        Box::new(Option::<String>::None) as Box<dyn rusqlite::ToSql>
-                    }
-                    _ => {
-                        let s = value.to_string();
-                        Box::new(s) as Box<dyn rusqlite::ToSql>
-                    }
-                });
-            }
+            let params_vec: Vec<Box<dyn rusqlite::ToSql>> = row.iter().map(sql_param).collect();
             
             // Преобразуем в срез параметров
             let params: Vec<&dyn rusqlite::ToSql> = params_vec.iter()
@@ -218,6 +387,26 @@ fn export_table(
     Ok(())
 }
 
+/// Значение ячейки как параметр SQLite
+fn sql_param(value: &Value) -> Box<dyn rusqlite::ToSql> {
+    match value {
+        Value::Number(n) => {
+            if n.fract() == 0.0 {
+                Box::new(*n as i64)
+            } else {
+                Box::new(*n)
+            }
+        }
+        Value::Integer(i) => Box::new(*i),
+        Value::Bool(b) => Box::new(if *b { 1i64 } else { 0i64 }),
+        Value::String(s) => Box::new(s.to_string()),
+        Value::Null => Box::new(Option::<String>::None),
+        // Массивы и объекты (например, из table_collect) сохраняются как TEXT с JSON
+        Value::Array(_) | Value::Object(_) => Box::new(value.to_json().to_string()),
+        _ => Box::new(value.to_string()),
+    }
+}
+
 /// Определение типа колонки на основе данных
 fn infer_column_type(column: &[Value]) -> String {
     if column.is_empty() {
@@ -238,6 +427,7 @@ fn infer_column_type(column: &[Value]) -> String {
                     has_float = true;
                 }
             }
+            Value::Integer(_) => has_integer = true,
             Value::String(_) => has_string = true,
             Value::Bool(_) => has_bool = true,
             Value::Null => {}, // NULL значения не влияют на тип
@@ -298,7 +488,16 @@ fn get_explicit_primary_keys(
     vm: &Vm,
 ) -> Vec<PrimaryKeyInfo> {
     let explicit_primary_keys = vm.get_explicit_primary_keys();
-    let mut primary_keys = Vec::new();
+    // Первичные ключи declare_export() важнее primary_key()
+    let mut primary_keys: Vec<PrimaryKeyInfo> = table_infos
+        .iter()
+        .filter_map(|table_info| {
+            Some(PrimaryKeyInfo {
+                table_name: table_info.sqlite_name.clone(),
+                column_name: sanitize_column_name(table_info.primary_key.as_ref()?),
+            })
+        })
+        .collect();
 
     // Создаем индекс для быстрого поиска таблиц по имени переменной
     let mut table_name_map: HashMap<&str, &TableInfo> = HashMap::new();
@@ -309,6 +508,9 @@ fn get_explicit_primary_keys(
     for explicit_pk in explicit_primary_keys {
         // Ищем таблицу по имени переменной
         if let Some(table_info) = table_name_map.get(explicit_pk.table_name.as_str()) {
+            if table_info.primary_key.is_some() {
+                continue;
+            }
             primary_keys.push(PrimaryKeyInfo {
                 table_name: table_info.sqlite_name.clone(),
                 column_name: sanitize_column_name(&explicit_pk.column_name),
@@ -379,7 +581,7 @@ fn detect_primary_keys(table_infos: &[TableInfo], vm: &Vm) -> Result<Vec<Primary
             }
 
             // Правило 4: Колонки, где все значения уникальны
-            if is_unique_column(column) && is_integer_column(column) {
+            if is_integer_column(column) && is_unique_column(column) {
                 primary_keys.push(PrimaryKeyInfo {
                     table_name: table_info.sqlite_name.clone(),
                     column_name: sanitize_column_name(header),
@@ -417,6 +619,7 @@ fn get_explicit_foreign_keys(
                 column_name: sanitize_column_name(&relation.source_column_name),
                 referenced_table: target_table.sqlite_name.clone(),
                 referenced_column: sanitize_column_name(&relation.target_column_name),
+                explicit: true,
             });
         }
     }
@@ -481,6 +684,7 @@ fn detect_foreign_keys(
                                 column_name: sanitize_column_name(header),
                                 referenced_table: other_table_info.sqlite_name.clone(),
                                 referenced_column: "id".to_string(),
+                                explicit: false,
                             });
                             break;
                         }
@@ -522,6 +726,7 @@ fn is_integer_column(column: &[Value]) -> bool {
                     return false;
                 }
             }
+            Value::Integer(_) => continue,
             Value::Null => continue, // NULL значения пропускаем
             _ => return false,
         }
@@ -537,7 +742,8 @@ fn is_unique_column(column: &[Value]) -> bool {
     
     for value in column {
         if !matches!(value, Value::Null) {
-            if !seen.insert(value) {
+            // Массивы и объекты в ячейках (table_collect) не хешируются и не подходят для ключа
+            if !value.is_hashable() || !seen.insert(value) {
                 return false;
             }
         }
@@ -738,31 +944,7 @@ fn recreate_tables_with_foreign_keys(
                 let mut stmt = tx.prepare(&insert_sql)?;
                 
                 for row in rows {
-                    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-                    for value in row {
-                        params_vec.push(match value {
-                            Value::Number(n) => {
-                                if n.fract() == 0.0 {
-                                    Box::new(*n as i64) as Box<dyn rusqlite::ToSql>
-                                } else {
-                                    Box::new(*n) as Box<dyn rusqlite::ToSql>
-                                }
-                            }
-                            Value::Bool(b) => {
-                                Box::new(if *b { 1i64 } else { 0i64 }) as Box<dyn rusqlite::ToSql>
-                            }
-                            Value::String(s) => {
-                                Box::new(s.clone()) as Box<dyn rusqlite::ToSql>
-                            }
-                            Value::Null => {
-                                Box::new(Option::<String>::None) as Box<dyn rusqlite::ToSql>
-                            }
-                            _ => {
-                                let s = value.to_string();
-                                Box::new(s) as Box<dyn rusqlite::ToSql>
-                            }
-                        });
-                    }
+                    let params_vec: Vec<Box<dyn rusqlite::ToSql>> = row.iter().map(sql_param).collect();
                     
                     let params: Vec<&dyn rusqlite::ToSql> = params_vec.iter()
                         .map(|v| v.as_ref())
@@ -845,7 +1027,8 @@ fn create_indexes(
 fn create_metadata_table(
     conn: &Connection,
     vm: &Vm,
-    _tables: &HashMap<String, Rc<RefCell<Table>>>,
+    table_infos: &[TableInfo],
+    foreign_keys: &[ForeignKeyInfo],
 ) -> SqliteResult<()> {
     // Создаем таблицу метаданных
     conn.execute(
@@ -857,10 +1040,20 @@ fn create_metadata_table(
             column_count INTEGER,
             created_at TEXT,
             description TEXT,
-            value TEXT
+            value TEXT,
+            foreign_keys TEXT
         )",
         [],
     )?;
+    // База, созданная прежней версией, не содержит колонки foreign_keys
+    let has_foreign_keys_column: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('_datacode_variables') WHERE name = 'foreign_keys'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_foreign_keys_column {
+        conn.execute("ALTER TABLE _datacode_variables ADD COLUMN foreign_keys TEXT", [])?;
+    }
 
     let globals = vm.get_globals();
     let explicit_global_names = vm.get_explicit_global_names();
@@ -868,25 +1061,49 @@ fn create_metadata_table(
 
     let mut stmt = conn.prepare(
         "INSERT OR REPLACE INTO _datacode_variables 
-         (variable_name, variable_type, table_name, row_count, column_count, created_at, description, value)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+         (variable_name, variable_type, table_name, row_count, column_count, created_at, description, value, foreign_keys)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
     )?;
 
+    // Внешние ключи таблицы в JSON: [{"column", "references_table", "references_column", "explicit"}]
+    let foreign_keys_json = |sqlite_name: &str| {
+        let keys: Vec<serde_json::Value> = foreign_keys
+            .iter()
+            .filter(|fk| fk.table_name == sqlite_name)
+            .map(|fk| serde_json::json!({
+                "column": fk.column_name,
+                "references_table": fk.referenced_table,
+                "references_column": fk.referenced_column,
+                "explicit": fk.explicit,
+            }))
+            .collect();
+        serde_json::Value::Array(keys).to_string()
+    };
+
+    let tables: HashMap<&str, &TableInfo> = table_infos.iter().map(|t| (t.name.as_str(), t)).collect();
+    let mut recorded = HashSet::new();
+
     // Добавляем информацию только о переменных, явно объявленных с ключевым словом 'global'
     for (index, value) in globals.iter().enumerate() {
         if let Some(var_name) = explicit_global_names.get(&index) {
             // Пропускаем нативные функции
-            if matches!(value, Value::NativeFunction(_) | Value::Function(_)) {
+            if matches!(value, Value::NativeFunction(_) | Value::Function(_) | Value::Closure(_)) {
                 continue;
             }
 
             let var_type = get_value_type_name(value);
-            let (table_name, row_count, column_count) = if let Value::Table(table) = value {
-                let table_ref = table.borrow();
-                let sqlite_name = sanitize_table_name(var_name);
-                (Some(sqlite_name), Some(table_ref.len() as i64), Some(table_ref.column_count() as i64))
+            // table_name - имя таблицы SQLite (null, если переменная не экспортирована)
+            let (table_name, row_count, column_count, table_foreign_keys) = if let Some(table_info) = tables.get(var_name.as_str()) {
+                recorded.insert(var_name.as_str());
+                let table_ref = table_info.table.borrow();
+                (
+                    Some(table_info.sqlite_name.clone()),
+                    Some(table_ref.len() as i64),
+                    Some(table_ref.column_count() as i64),
+                    Some(foreign_keys_json(&table_info.sqlite_name)),
+                )
             } else {
-                (None, None, None)
+                (None, None, None, None)
             };
 
             let value_str = value.to_string();
@@ -900,11 +1117,65 @@ fn create_metadata_table(
                 column_count,
                 created_at,
                 description,
-                value_str
+                value_str,
+                table_foreign_keys
             ])?;
         }
     }
 
+    // Таблицы declare_export() из локальных переменных и без переменной
+    for table_info in table_infos.iter().filter(|t| !recorded.contains(t.name.as_str())) {
+        let table_ref = table_info.table.borrow();
+        stmt.execute(params![
+            table_info.name,
+            "Table",
+            table_info.sqlite_name,
+            table_ref.len() as i64,
+            table_ref.column_count() as i64,
+            created_at,
+            None::<String>,
+            Value::Table(table_info.table.clone()).to_string(),
+            foreign_keys_json(&table_info.sqlite_name)
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// Таблица _datacode_scalars (name, value_type, value) со значениями скалярных переменных,
+/// объявленных с ключевым словом 'global'. Создается, только если такие переменные есть
+fn create_scalars_table(conn: &Connection, vm: &Vm) -> SqliteResult<()> {
+    let globals = vm.get_globals();
+    let mut scalars: Vec<(&String, &Value)> = vm
+        .get_explicit_global_names()
+        .iter()
+        .filter_map(|(index, name)| Some((name, globals.get(*index)?)))
+        .filter(|(_, value)| {
+            matches!(
+                value,
+                Value::Number(_) | Value::Integer(_) | Value::Bool(_) | Value::String(_)
+                    | Value::Currency { .. } | Value::Path(_) | Value::Null
+            )
+        })
+        .collect();
+    if scalars.is_empty() {
+        return Ok(());
+    }
+    scalars.sort_by(|a, b| a.0.cmp(b.0));
+
+    // value без объявленного типа: числа остаются числами, строки - текстом
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS _datacode_scalars (
+            name TEXT PRIMARY KEY,
+            value_type TEXT NOT NULL,
+            value
+        )",
+        [],
+    )?;
+    let mut stmt = conn.prepare("INSERT OR REPLACE INTO _datacode_scalars (name, value_type, value) VALUES (?1, ?2, ?3)")?;
+    for (name, value) in scalars {
+        stmt.execute(params![name, get_value_type_name(value), sql_param(value)])?;
+    }
     Ok(())
 }
 
@@ -912,14 +1183,17 @@ fn create_metadata_table(
 fn get_value_type_name(value: &Value) -> &str {
     match value {
         Value::Number(_) => "Number",
+        Value::Integer(_) => "Integer",
         Value::Bool(_) => "Bool",
         Value::String(_) => "String",
         Value::Array(_) => "Array",
         Value::Table(_) => "Table",
+        Value::LazyTable(_) => "LazyTable",
         Value::Object(_) => "Object",
+        Value::Currency { .. } => "Currency",
         Value::Path(_) => "Path",
         Value::ColumnReference { .. } => "ColumnReference",
-        Value::Function(_) => "Function",
+        Value::Function(_) | Value::Closure(_) => "Function",
         Value::NativeFunction(_) => "NativeFunction",
         Value::Null => "Null",
     }
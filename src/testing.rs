@@ -0,0 +1,196 @@
+// Тестовый раннер для наборов тестов на DataCode (datacode --test)
+//
+// В каждом .dc файле ищутся функции без параметров с именами test_*.
+// Каждый тест выполняется на новой VM: код верхнего уровня файла выполняется заново
+// (объявляет вспомогательные функции и глобальные переменные), затем вызывается сам тест.
+// Так состояние, измененное одним тестом, не видно другим.
+
+use crate::bytecode::{Chunk, Function};
+use crate::common::error::LangError;
+use crate::compiler::Compiler;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::semantic::resolver::Resolver;
+use crate::vm::Vm;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Префикс имени функции, по которому находятся тесты
+pub const TEST_PREFIX: &str = "test_";
+
+/// Результат одного теста
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub file: PathBuf,
+    pub name: String,
+    pub duration: Duration,
+    pub error: Option<String>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Итоги запуска: результаты тестов и ошибки загрузки файлов
+#[derive(Debug, Default)]
+pub struct TestSummary {
+    pub outcomes: Vec<TestOutcome>,
+    pub file_errors: Vec<(PathBuf, String)>,
+    pub duration: Duration,
+}
+
+impl TestSummary {
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.passed()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0 && self.file_errors.is_empty()
+    }
+}
+
+/// Собирает .dc файлы: сам файл или все .dc файлы каталога (рекурсивно, в порядке имен)
+pub fn discover_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(format!("Test path not found: {}", path.display()));
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read directory '{}': {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+            } else if entry_path.extension().and_then(|e| e.to_str()) == Some("dc") {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn compile_source(source: &str) -> Result<(Chunk, Vec<Function>), LangError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse()?;
+    let mut resolver = Resolver::new();
+    resolver.resolve(&ast)?;
+    let mut compiler = Compiler::new();
+    let chunk = compiler.compile(&ast)?;
+    let functions = compiler.get_functions();
+    Ok((chunk, functions))
+}
+
+/// Имена тестов файла в порядке объявления
+pub fn test_names(functions: &[Function]) -> Vec<String> {
+    functions
+        .iter()
+        .filter(|f| f.name.starts_with(TEST_PREFIX) && f.arity == 0 && !f.is_local)
+        .map(|f| f.name.clone())
+        .collect()
+}
+
+fn run_single_test(chunk: &Chunk, functions: &[Function], name: &str) -> Result<(), LangError> {
+    let mut vm = Vm::new();
+    vm.set_functions(functions.to_vec());
+    vm.register_native_globals();
+    vm.run(chunk)?;
+    vm.call_function_by_name(name, &[])?;
+    Ok(())
+}
+
+/// Выполняет все тесты одного файла
+pub fn run_file(path: &Path, summary: &mut TestSummary) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            summary.file_errors.push((path.to_path_buf(), format!("Failed to read file: {}", e)));
+            return;
+        }
+    };
+    let (chunk, functions) = match compile_source(&source) {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            summary.file_errors.push((path.to_path_buf(), e.to_string()));
+            return;
+        }
+    };
+
+    for name in test_names(&functions) {
+        let start = Instant::now();
+        let result = run_single_test(&chunk, &functions, &name);
+        summary.outcomes.push(TestOutcome {
+            file: path.to_path_buf(),
+            name,
+            duration: start.elapsed(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+}
+
+/// Находит и выполняет тесты по пути к файлу или каталогу
+pub fn run_tests(path: &Path) -> Result<TestSummary, String> {
+    let files = discover_files(path)?;
+    let start = Instant::now();
+    let mut summary = TestSummary::default();
+    for file in &files {
+        run_file(file, &mut summary);
+    }
+    summary.duration = start.elapsed();
+    Ok(summary)
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Печатает результаты тестов и итоговую строку
+pub fn print_report(summary: &TestSummary) {
+    let mut current_file: Option<&Path> = None;
+    for outcome in &summary.outcomes {
+        if current_file != Some(outcome.file.as_path()) {
+            println!("{}", outcome.file.display());
+            current_file = Some(outcome.file.as_path());
+        }
+        match &outcome.error {
+            None => println!("  ✅ {} ({})", outcome.name, format_duration(outcome.duration)),
+            Some(error) => {
+                println!("  ❌ {} ({})", outcome.name, format_duration(outcome.duration));
+                for line in error.lines() {
+                    println!("     {}", line);
+                }
+            }
+        }
+    }
+    for (file, error) in &summary.file_errors {
+        println!("{}", file.display());
+        println!("  ❌ {}", error);
+    }
+
+    println!();
+    println!(
+        "Tests: {} passed, {} failed, {} total ({})",
+        summary.passed(),
+        summary.failed(),
+        summary.outcomes.len(),
+        format_duration(summary.duration)
+    );
+    if !summary.file_errors.is_empty() {
+        println!("Files with errors: {}", summary.file_errors.len());
+    }
+}
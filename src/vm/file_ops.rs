@@ -1,14 +1,18 @@
 // Модуль для работы с файловыми операциями и SMB
 
-use std::sync::{Arc, Mutex};
-use crate::websocket::smb::SmbManager;
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::websocket::smb::{SmbManager, SmbShares};
 
 thread_local! {
-    static SMB_MANAGER: std::cell::RefCell<Option<Arc<Mutex<SmbManager>>>> = std::cell::RefCell::new(None);
+    static SMB_MANAGER: std::cell::RefCell<Option<Arc<Mutex<dyn SmbShares>>>> = std::cell::RefCell::new(None);
 }
 
+/// SMB подключения вне WebSocket сервера (запуск из командной строки): менеджер создается
+/// при первом обращении и общий для всего процесса
+static LOCAL_SMB_MANAGER: OnceLock<Arc<Mutex<dyn SmbShares>>> = OnceLock::new();
+
 /// Установить SmbManager для текущего потока
-pub fn set_smb_manager(manager: Arc<Mutex<SmbManager>>) {
+pub fn set_smb_manager(manager: Arc<Mutex<dyn SmbShares>>) {
     SMB_MANAGER.with(|m| {
         *m.borrow_mut() = Some(manager);
     });
@@ -21,10 +25,12 @@ pub fn clear_smb_manager() {
     });
 }
 
-/// Получить SmbManager для текущего потока
-pub fn get_smb_manager() -> Option<Arc<Mutex<SmbManager>>> {
-    SMB_MANAGER.with(|m| {
-        m.borrow().clone()
-    })
+/// Получить SmbManager для текущего потока: подключения WebSocket клиента, если они
+/// установлены через set_smb_manager, иначе общий менеджер процесса
+pub fn get_smb_manager() -> Arc<Mutex<dyn SmbShares>> {
+    SMB_MANAGER
+        .with(|m| m.borrow().clone())
+        .unwrap_or_else(|| {
+            Arc::clone(LOCAL_SMB_MANAGER.get_or_init(|| Arc::new(Mutex::new(SmbManager::new()))))
+        })
 }
-
@@ -1,33 +1,45 @@
 // CallFrame для виртуальной машины
 
 use crate::bytecode::Function;
-use crate::common::value::Value;
+use crate::bytecode::function::CacheKey;
+use crate::common::value::{Closure, Value};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Счетчик идентификаторов фреймов: замыкание ссылается на фрейм, в котором создано
+static NEXT_FRAME_ID: AtomicU64 = AtomicU64::new(0);
 
 pub struct CallFrame {
     pub function: Function,
     pub ip: usize,           // Instruction pointer
     pub slots: Vec<Value>,   // Локальные переменные и стек для этой функции
     pub stack_start: usize,  // Начало стека для этой функции в общем стеке VM
-    pub cached_args: Option<Vec<Value>>, // Аргументы для кэширования (только для кэшируемых функций)
+    pub cache_key: Option<CacheKey>, // Ключ кэша вызова (только для кэшируемых функций)
+    pub id: u64,             // Уникальный идентификатор фрейма
+    pub closure: Option<Rc<Closure>>, // Замыкание вызванной вложенной функции: захваченные значения и фрейм объемлющей функции
 }
 
 impl CallFrame {
     pub fn new(function: Function, stack_start: usize) -> Self {
-        // Оптимизация: резервируем только необходимое количество слотов
-        let initial_slots = function.arity.max(8); // Минимум 8 слотов для локальных переменных
+        Self::with_slots(function, stack_start, Vec::new())
+    }
+
+    /// Фрейм, слоты которого размещаются в переданном (пустом) векторе:
+    /// VM повторно использует векторы слотов завершившихся вызовов
+    pub fn with_slots(function: Function, stack_start: usize, mut slots: Vec<Value>) -> Self {
+        // Оптимизация: резервируем только слоты захваченных переменных и параметров,
+        // остальные локальные переменные расширяют вектор при первой записи
+        let initial_slots = (function.captured_vars.len() + function.arity).max(8); // Минимум 8 слотов для локальных переменных
+        slots.reserve(initial_slots);
         Self {
-            slots: Vec::with_capacity(initial_slots + 64), // Динамическое расширение при необходимости
+            slots,
             ip: 0,
             function,
             stack_start,
-            cached_args: None,
+            cache_key: None,
+            id: NEXT_FRAME_ID.fetch_add(1, Ordering::Relaxed),
+            closure: None,
         }
     }
-    
-    pub fn new_with_cache(function: Function, stack_start: usize, args: Vec<Value>) -> Self {
-        let mut frame = Self::new(function, stack_start);
-        frame.cached_args = Some(args);
-        frame
-    }
 }
 
@@ -0,0 +1,74 @@
+// Приблизительный учет памяти значений программы и лимит ExecutionPolicy::max_memory_bytes
+//
+// Оценка меняется приращениями: присваивание глобальной переменной заменяет размер старого
+// значения размером нового, push() добавляет размер элемента. Память локальных переменных
+// освобождается без учета, поэтому оценка со временем завышается; перед ошибкой лимита
+// она пересчитывается по всем живым значениям VM
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone)]
+pub struct MemoryTracker {
+    used: usize,
+    limit: Option<usize>,
+    /// Прирост оценки после последнего пересчета
+    grown_since_recount: usize,
+}
+
+impl MemoryTracker {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            used: 0,
+            limit,
+            grown_since_recount: 0,
+        }
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Значение размером old заменено значением размером new
+    pub fn replace(&mut self, old: usize, new: usize) {
+        if new >= old {
+            self.grow(new - old);
+        } else {
+            self.used = self.used.saturating_sub(old - new);
+        }
+    }
+
+    pub fn grow(&mut self, bytes: usize) {
+        self.used = self.used.saturating_add(bytes);
+        self.grown_since_recount = self.grown_since_recount.saturating_add(bytes);
+    }
+
+    /// Оценка превышает лимит
+    pub fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used > limit)
+    }
+
+    /// Стоит ли пересчитывать перед ошибкой: пересчет обходит все значения, поэтому
+    /// повторяется, только если с прошлого раза оценка выросла хотя бы на восьмую часть лимита
+    pub fn should_recount(&self) -> bool {
+        self.limit.is_some_and(|limit| self.grown_since_recount.saturating_mul(8) >= limit)
+    }
+
+    /// Точный (в пределах оценки) объем после обхода всех живых значений
+    pub fn recounted(&mut self, used: usize) {
+        self.used = used;
+        self.grown_since_recount = 0;
+    }
+}
+
+/// "memory limit exceeded (used 1.02 of 1.00 MB)"
+pub fn limit_exceeded_message(used: usize, limit: usize) -> String {
+    format!(
+        "memory limit exceeded (used {:.2} of {:.2} MB)",
+        used as f64 / BYTES_PER_MB,
+        limit as f64 / BYTES_PER_MB
+    )
+}
@@ -2,6 +2,10 @@ pub mod vm;
 pub mod frame;
 pub mod natives;
 pub mod file_ops;
+pub mod policy;
+pub mod memory;
+pub mod parallel;
+pub mod profiler;
 
 pub use vm::Vm;
 
@@ -1,10 +1,15 @@
 // Встроенные функции (native functions)
 
-use crate::common::value::Value;
-use crate::common::table::Table;
-use std::path::PathBuf;
+use crate::common::value::{ObjectMap, Value};
+use crate::common::table::{compare_values, Table};
+use crate::common::aggregate::{Accumulator, AggFunc, AggSpec, GroupAggregator};
+use crate::common::render;
+use crate::common::lazy::{CompareOp, LazyOp, LazyStats, LazyTable, Predicate, RowSource};
+use crate::common::path::{from_user_str, smb_url_to_lib, split_smb_path};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fs;
 use std::io;
 use std::env;
@@ -21,6 +26,14 @@ thread_local! {
     static PRIMARY_KEYS: RefCell<Vec<(*const RefCell<Table>, String)>> = RefCell::new(Vec::new());
 }
 
+/// Объявление экспорта из declare_export(): таблица, имя таблицы SQLite и колонка первичного ключа
+pub type ExportDeclaration = (Value, Option<String>, Option<String>);
+
+// Thread-local storage для объявлений экспорта, созданных через declare_export()
+thread_local! {
+    static EXPORT_DECLARATIONS: RefCell<Vec<ExportDeclaration>> = const { RefCell::new(Vec::new()) };
+}
+
 /// Получить все временные связи (для использования в VM)
 pub fn take_relations() -> Vec<(*const RefCell<Table>, String, *const RefCell<Table>, String)> {
     RELATIONS.with(|r| {
@@ -80,7 +93,7 @@ pub fn native_relate(args: &[Value]) -> Value {
         
         // Проверяем совместимость типов (Number <-> Number, String <-> String)
         match (type1, type2) {
-            (Value::Number(_), Value::Number(_)) => {},
+            (a, b) if a.is_numeric() && b.is_numeric() => {},
             (Value::String(_), Value::String(_)) => {},
             _ => {
                 // Типы не совместимы, но все равно сохраняем связь
@@ -132,8 +145,90 @@ pub fn native_primary_key(args: &[Value]) -> Value {
     Value::Null
 }
 
+/// register_foreign_key(child_table, child_col, parent_table, parent_col) - явный внешний ключ для
+/// экспорта в SQLite: child_table.child_col ссылается на parent_table.parent_col. Сохраняется как связь
+/// relate(parent_table[parent_col], child_table[child_col]); имена таблиц находит VM
+pub fn native_register_foreign_key(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (child, child_col, parent, parent_col) = match (args.first(), args.get(1), args.get(2), args.get(3)) {
+        (Some(Value::Table(child)), Some(Value::String(child_col)), Some(Value::Table(parent)), Some(Value::String(parent_col))) => {
+            (child, child_col, parent, parent_col)
+        }
+        _ => {
+            set_native_error_with_type(
+                "register_foreign_key() expects (child_table, child_column, parent_table, parent_column)".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    for (table, column) in [(child, child_col), (parent, parent_col)] {
+        if !table.borrow().headers.iter().any(|h| **h == **column) {
+            set_native_error_with_type(format!("register_foreign_key(): unknown column: {}", column), ErrorType::ValueError);
+            return Value::Null;
+        }
+    }
+
+    RELATIONS.with(|r| {
+        r.borrow_mut().push((Rc::as_ptr(parent), parent_col.to_string(), Rc::as_ptr(child), child_col.to_string()))
+    });
+    Value::Null
+}
+
+/// Получить объявления экспорта, сделанные declare_export() (для использования в VM)
+pub fn take_export_declarations() -> Vec<ExportDeclaration> {
+    EXPORT_DECLARATIONS.with(|d| std::mem::take(&mut *d.borrow_mut()))
+}
+
+/// declare_export(table, name = null, primary_key = null) - экспортировать таблицу в --build_model
+/// под именем name с первичным ключом primary_key. Имя по умолчанию - имя переменной (находит VM)
+pub fn native_declare_export(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let headers = match args.first() {
+        Some(Value::Table(table)) => table.borrow().headers.clone(),
+        Some(Value::LazyTable(lazy)) => lazy.headers.clone(),
+        _ => {
+            set_native_error_with_type("declare_export() expects a table".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let name = match args.get(1) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(name)) if !name.trim().is_empty() => Some(name.trim().to_string()),
+        Some(_) => {
+            set_native_error_with_type("declare_export() name must be a non-empty string".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let primary_key = match args.get(2) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(column)) if headers.iter().any(|h| **h == **column) => Some(column.to_string()),
+        Some(Value::String(column)) => {
+            set_native_error_with_type(format!("declare_export(): unknown primary key column: {}", column), ErrorType::ValueError);
+            return Value::Null;
+        }
+        Some(_) => {
+            set_native_error_with_type("declare_export() primary_key must be a column name".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+
+    // Объявление ссылается на ту же таблицу (clone() таблицы глубокий): по ней VM находит
+    // переменную, а экспорт видит изменения после declare_export()
+    let table = match &args[0] {
+        Value::Table(table) => Value::Table(Rc::clone(table)),
+        other => other.clone(),
+    };
+    EXPORT_DECLARATIONS.with(|d| d.borrow_mut().push((table, name, primary_key)));
+    Value::Null
+}
+
 pub fn native_print(args: &[Value]) -> Value {
-    use crate::websocket::output_capture::OutputCapture;
+    use crate::common::output_capture::OutputCapture;
     
     if args.is_empty() {
         if OutputCapture::is_capturing() {
@@ -187,6 +282,7 @@ pub fn native_range(args: &[Value]) -> Value {
             // range(10) → range(0, 10, 1)
             let end = match &args[0] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             (0, end, 1)
@@ -195,10 +291,12 @@ pub fn native_range(args: &[Value]) -> Value {
             // range(1, 10) → range(1, 10, 1)
             let start = match &args[0] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             let end = match &args[1] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             (start, end, 1)
@@ -207,14 +305,17 @@ pub fn native_range(args: &[Value]) -> Value {
             // range(1, 10, 2) → range(1, 10, 2)
             let start = match &args[0] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             let end = match &args[1] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             let step = match &args[2] {
                 Value::Number(n) => *n as i64,
+                Value::Integer(i) => *i,
                 _ => return Value::Null,
             };
             if step == 0 {
@@ -256,6 +357,7 @@ pub fn native_int(args: &[Value]) -> Value {
     }
     match &args[0] {
         Value::Number(n) => Value::Number(n.trunc()), // Округление вниз до целого
+        Value::Integer(i) => Value::Integer(*i), // Уже целое
         Value::String(s) => {
             // Парсинг строки в число
             match s.parse::<f64>() {
@@ -275,6 +377,7 @@ pub fn native_float(args: &[Value]) -> Value {
     }
     match &args[0] {
         Value::Number(n) => Value::Number(*n), // Уже число
+        Value::Integer(i) => Value::Number(*i as f64),
         Value::String(s) => {
             // Парсинг строки в число
             match s.parse::<f64>() {
@@ -297,9 +400,13 @@ pub fn native_bool(args: &[Value]) -> Value {
 
 pub fn native_str(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
-    Value::String(args[0].to_string())
+    if let Value::String(s) = &args[0] {
+        // Строка уже готова - разделяем ее без копирования
+        return Value::String(s.clone());
+    }
+    Value::String(args[0].to_string().into())
 }
 
 pub fn native_array(args: &[Value]) -> Value {
@@ -309,7 +416,7 @@ pub fn native_array(args: &[Value]) -> Value {
 
 pub fn native_date(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
     match &args[0] {
@@ -320,34 +427,36 @@ pub fn native_date(args: &[Value]) -> Value {
             
             // Если уже в формате ISO (YYYY-MM-DD или YYYY-MM-DDTHH:MM:SSZ), возвращаем как есть
             if date_str.len() >= 10 && date_str.chars().nth(4) == Some('-') && date_str.chars().nth(7) == Some('-') {
-                Value::String(date_str.to_string())
+                Value::String(date_str.into())
             } else {
                 // Для других форматов пока возвращаем как есть
                 // В будущем можно добавить парсинг других форматов
-                Value::String(date_str.to_string())
+                Value::String(date_str.into())
             }
         }
         Value::Number(n) => {
             // Если передано число (timestamp), конвертируем в ISO формат
             // Для простоты пока возвращаем как строку числа
-            Value::String(format!("{}", n))
+            Value::String(format!("{}", n).into())
         }
-        _ => Value::String(String::new()),
+        Value::Integer(i) => Value::String(format!("{}", i).into()),
+        _ => Value::String("".into()),
     }
 }
 
 pub fn native_money(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String("0".to_string());
+        return Value::String("0".into());
     }
     
     if args.len() < 2 {
         // Если формат не указан, просто возвращаем число как строку
-        return Value::String(args[0].to_string());
+        return Value::String(args[0].to_string().into());
     }
     
     let amount = match &args[0] {
         Value::Number(n) => *n,
+        Value::Integer(i) => *i as f64,
         Value::String(s) => {
             // Пытаемся распарсить строку как число
             s.parse::<f64>().unwrap_or(0.0)
@@ -356,7 +465,7 @@ pub fn native_money(args: &[Value]) -> Value {
     };
     
     let format_str = match &args[1] {
-        Value::String(s) => s.clone(),
+        Value::String(s) => s.to_string(),
         _ => String::new(),
     };
     
@@ -404,14 +513,14 @@ pub fn native_money(args: &[Value]) -> Value {
         format!("{:.2}", amount)
     };
     
-    Value::String(formatted)
+    Value::String(formatted.into())
 }
 
 // Функции работы с типами
 
 pub fn native_typeof(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String("null".to_string());
+        return Value::String("null".into());
     }
     let type_name = match &args[0] {
         Value::Number(n) => {
@@ -422,6 +531,7 @@ pub fn native_typeof(args: &[Value]) -> Value {
                 "float"
             }
         }
+        Value::Integer(_) => "int",
         Value::Bool(_) => "bool",
         Value::String(s) => {
             // Проверяем, является ли строка датой или деньгами
@@ -439,13 +549,15 @@ pub fn native_typeof(args: &[Value]) -> Value {
         Value::Array(_) => "array",
         Value::Path(_) => "path",
         Value::Table(_) => "table",
+        Value::LazyTable(_) => "lazy_table",
         Value::Object(_) => "object",
+        Value::Currency { .. } => "money",
         Value::ColumnReference { .. } => "column",
         Value::Null => "null",
-        Value::Function(_) => "function",
+        Value::Function(_) | Value::Closure(_) => "function",
         Value::NativeFunction(_) => "function",
     };
-    Value::String(type_name.to_string())
+    Value::String(type_name.into())
 }
 
 pub fn native_isinstance(args: &[Value]) -> Value {
@@ -453,11 +565,21 @@ pub fn native_isinstance(args: &[Value]) -> Value {
         return Value::Bool(false);
     }
     
-    let value = &args[0];
+    // Вторым аргументом можно передать массив допустимых типов: isinstance(v, ["int", "string"])
+    match &args[1] {
+        Value::Array(types) => {
+            let types = types.borrow();
+            Value::Bool(types.iter().any(|type_arg| isinstance_matches(&args[0], type_arg)))
+        }
+        type_arg => Value::Bool(isinstance_matches(&args[0], type_arg)),
+    }
+}
+
+fn isinstance_matches(value: &Value, type_arg: &Value) -> bool {
     // Извлекаем имя типа из второго аргумента
     // Поддерживаем как строки, так и другие типы (для констант типов, которые являются строками)
-    let type_name_str = match &args[1] {
-        Value::String(s) => s.clone(),
+    let type_name_str = match type_arg {
+        Value::String(s) => s.to_string(),
         Value::NativeFunction(index) => {
             // Если передан NativeFunction, извлекаем имя типа по индексу
             // Индексы: 0=print, 1=len, 2=range, 3=int, 4=float, 5=bool, 6=str, 7=array, 8=typeof, 9=isinstance, 10=date, 11=money, 12=path
@@ -481,20 +603,23 @@ pub fn native_isinstance(args: &[Value]) -> Value {
     // Нормализуем имя типа (приводим к нижнему регистру)
     let type_name_lower = type_name_str.to_lowercase();
     
-    let matches = match value {
+    match value {
         Value::Number(n) => {
             // Для чисел проверяем int, float и money
             if type_name_lower == "int" || type_name_lower == "integer" || type_name_lower == "num" || type_name_lower == "number" {
                 true
             } else if type_name_lower == "float" {
                 n.fract() != 0.0
-            } else if type_name_lower == "money" {
+            } else if type_name_lower == "money" || type_name_lower == "currency" {
                 // Числа могут быть деньгами
                 true
             } else {
                 false
             }
         }
+        Value::Integer(_) => {
+            matches!(type_name_lower.as_str(), "int" | "integer" | "num" | "number" | "money" | "currency")
+        }
         Value::Bool(_) => type_name_lower == "bool" || type_name_lower == "boolean",
         Value::String(s) => {
             let s_trimmed = s.trim();
@@ -503,7 +628,7 @@ pub fn native_isinstance(args: &[Value]) -> Value {
             } else if type_name_lower == "date" {
                 // Проверка на формат даты: YYYY-MM-DD или ISO формат
                 s_trimmed.len() >= 10 && s_trimmed.chars().nth(4) == Some('-') && s_trimmed.chars().nth(7) == Some('-')
-            } else if type_name_lower == "money" {
+            } else if type_name_lower == "money" || type_name_lower == "currency" {
                 // Проверка на деньги: содержит валютные символы
                 s_trimmed.starts_with('$') || s_trimmed.contains("EUR") || s_trimmed.contains("€")
             } else {
@@ -513,13 +638,108 @@ pub fn native_isinstance(args: &[Value]) -> Value {
         Value::Path(_) => type_name_lower == "path",
         Value::Array(_) => type_name_lower == "array" || type_name_lower == "list",
         Value::Table(_) => type_name_lower == "table",
+        Value::LazyTable(_) => type_name_lower == "lazy_table",
         Value::Object(_) => type_name_lower == "object" || type_name_lower == "dict" || type_name_lower == "dictionary",
+        Value::Currency { .. } => type_name_lower == "money" || type_name_lower == "currency",
         Value::ColumnReference { .. } => type_name_lower == "column",
         Value::Null => type_name_lower == "null" || type_name_lower == "none",
-        Value::Function(_) | Value::NativeFunction(_) => type_name_lower == "function",
+        Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_) => type_name_lower == "function",
+    }
+}
+
+/// Разбирает число из строки: допускает разделители разрядов (1,234 567_890) и символы валют
+fn parse_number_lenient(text: &str) -> Option<f64> {
+    const CURRENCY_MARKERS: [&str; 9] = ["$", "€", "£", "¥", "₽", "EUR", "USD", "RUB", "GBP"];
+    let mut cleaned = text.trim().to_string();
+    for marker in CURRENCY_MARKERS {
+        cleaned = cleaned.replace(marker, "");
+    }
+    let cleaned: String = cleaned
+        .chars()
+        .filter(|c| !matches!(c, ',' | '_' | ' ' | '\u{a0}'))
+        .collect();
+    if cleaned.is_empty() {
+        return None;
+    }
+    cleaned.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// to_number(value, strict=true) - преобразует значение в число
+/// При strict=false вместо ошибки возвращает null
+pub fn native_to_number(args: &[Value]) -> Value {
+    let value = args.first().unwrap_or(&Value::Null);
+    let strict = !matches!(args.get(1), Some(Value::Bool(false)));
+    let converted = match value {
+        Value::Number(n) => Some(Value::Number(*n)),
+        Value::Integer(i) => Some(Value::Integer(*i)),
+        Value::Bool(b) => Some(Value::Number(if *b { 1.0 } else { 0.0 })),
+        Value::String(s) => parse_number_lenient(s).map(Value::Number),
+        Value::Currency { amount, .. } => Some(Value::Number(*amount)),
+        _ => None,
     };
-    
-    Value::Bool(matches)
+    match converted {
+        Some(number) => number,
+        None if strict => {
+            use crate::common::error::ErrorType;
+            use crate::websocket::set_native_error_with_type;
+            // Нечисловая строка - неверное значение, остальные типы числа не содержат
+            let error_type = match value {
+                Value::String(_) => ErrorType::ValueError,
+                _ => ErrorType::TypeError,
+            };
+            set_native_error_with_type(
+                format!("to_number(): cannot convert {} to a number", value.preview(40)),
+                error_type,
+            );
+            Value::Null
+        }
+        None => Value::Null,
+    }
+}
+
+/// to_bool(value) - распознает строки "true"/"false", "yes"/"no", "on"/"off", "1"/"0";
+/// остальные значения преобразуются по правилам истинности
+pub fn native_to_bool(args: &[Value]) -> Value {
+    let value = args.first().unwrap_or(&Value::Null);
+    if let Value::String(s) = value {
+        match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "y" | "on" | "1" => return Value::Bool(true),
+            "false" | "no" | "n" | "off" | "0" | "" => return Value::Bool(false),
+            _ => {}
+        }
+    }
+    Value::Bool(value.is_truthy())
+}
+
+/// to_array(value) - строка в массив символов, объект в массив пар [ключ, значение],
+/// таблица в массив объектов-строк, null в пустой массив, остальное в массив из одного элемента
+pub fn native_to_array(args: &[Value]) -> Value {
+    let value = args.first().unwrap_or(&Value::Null);
+    let items = match value {
+        Value::Array(arr) => arr.borrow().clone(),
+        Value::String(s) => s.chars().map(|c| Value::String(c.to_string().into())).collect(),
+        Value::Object(map) => map.iter()
+            .map(|(key, value)| {
+                let pair = vec![Value::String(key.as_str().into()), value.clone()];
+                Value::Array(Rc::new(RefCell::new(pair)))
+            })
+            .collect(),
+        Value::Table(table) => {
+            let table = table.borrow();
+            table.rows.iter()
+                .map(|row| {
+                    let object = table.headers.iter()
+                        .zip(row.iter())
+                        .map(|(header, cell)| (header.clone(), cell.clone()))
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect()
+        }
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    Value::Array(Rc::new(RefCell::new(items)))
 }
 
 // Функции для работы с путями
@@ -531,8 +751,8 @@ pub fn native_path(args: &[Value]) -> Value {
     
     match &args[0] {
         Value::String(s) => {
-            // Создаем путь из строки
-            Value::Path(PathBuf::from(s))
+            // Создаем путь из строки (на Windows принимаются оба разделителя)
+            Value::Path(from_user_str(s))
         }
         Value::Path(p) => {
             // Если уже путь, возвращаем копию
@@ -540,25 +760,25 @@ pub fn native_path(args: &[Value]) -> Value {
         }
         _ => {
             // Для других типов преобразуем в строку и создаем путь
-            Value::Path(PathBuf::from(args[0].to_string()))
+            Value::Path(from_user_str(&args[0].to_string()))
         }
     }
 }
 
 pub fn native_path_name(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
-    match &args[0] {
-        Value::Path(p) => {
+    match path_arg(&args[0]) {
+        Some(p) => {
             if let Some(name) = p.file_name() {
-                Value::String(name.to_string_lossy().to_string())
+                Value::String(name.to_string_lossy().into())
             } else {
-                Value::String(String::new())
+                Value::String("".into())
             }
         }
-        _ => Value::String(String::new()),
+        None => Value::String("".into()),
     }
 }
 
@@ -567,26 +787,24 @@ pub fn native_path_parent(args: &[Value]) -> Value {
         return Value::Null;
     }
     
-    match &args[0] {
-        Value::Path(p) => {
+    match path_arg(&args[0]) {
+        Some(p) => {
             // Используем безопасную функцию для получения parent
-            match safe_path_parent(p) {
+            match safe_path_parent(&p) {
                 Some(parent) => Value::Path(parent),
                 None => Value::Null,
             }
         }
-        _ => Value::Null,
+        None => Value::Null,
     }
 }
 
+/// path_exists(path) - существует ли файл или директория. В режиме --use-ve относительные пути
+/// разрешаются от папки сессии, пути вне нее считаются несуществующими
 pub fn native_path_exists(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Bool(false);
-    }
-    
-    match &args[0] {
-        Value::Path(p) => Value::Bool(p.exists()),
-        _ => Value::Bool(false),
+    match args.first().and_then(path_arg) {
+        Some(p) => Value::Bool(resolve_path_in_session(&p).is_ok_and(|p| p.exists())),
+        None => Value::Bool(false),
     }
 }
 
@@ -614,35 +832,35 @@ pub fn native_path_is_dir(args: &[Value]) -> Value {
 
 pub fn native_path_extension(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
     match &args[0] {
         Value::Path(p) => {
             if let Some(ext) = p.extension() {
-                Value::String(ext.to_string_lossy().to_string())
+                Value::String(ext.to_string_lossy().into())
             } else {
-                Value::String(String::new())
+                Value::String("".into())
             }
         }
-        _ => Value::String(String::new()),
+        _ => Value::String("".into()),
     }
 }
 
 pub fn native_path_stem(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
-    match &args[0] {
-        Value::Path(p) => {
+    match path_arg(&args[0]) {
+        Some(p) => {
             if let Some(stem) = p.file_stem() {
-                Value::String(stem.to_string_lossy().to_string())
+                Value::String(stem.to_string_lossy().into())
             } else {
-                Value::String(String::new())
+                Value::String("".into())
             }
         }
-        _ => Value::String(String::new()),
+        None => Value::String("".into()),
     }
 }
 
@@ -660,6 +878,102 @@ pub fn native_path_len(args: &[Value]) -> Value {
     }
 }
 
+/// Путь из аргумента path-функций: Path или строка (на Windows с любыми разделителями)
+fn path_arg(value: &Value) -> Option<PathBuf> {
+    match value {
+        Value::Path(p) => Some(p.clone()),
+        Value::String(s) => Some(from_user_str(s)),
+        _ => None,
+    }
+}
+
+/// path_join(...parts) - объединяет части пути по правилам PathBuf::push:
+/// абсолютная часть заменяет все предыдущие.
+/// В режиме --use-ve результат, выходящий за папку сессии (через '..' или абсолютную часть), - PermissionError
+pub fn native_path_join(args: &[Value]) -> Value {
+    use crate::websocket::{confine_to_session, get_use_ve, set_native_error_with_type};
+    use crate::common::error::ErrorType;
+
+    let mut joined = PathBuf::new();
+    for part in args {
+        match path_arg(part) {
+            Some(p) => joined.push(p),
+            None => {
+                set_native_error_with_type(
+                    format!("path_join() expects strings or paths, got {}", part.to_string()),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            }
+        }
+    }
+    if get_use_ve() {
+        if let Err(err_msg) = confine_to_session(&crate::common::path::normalize_separators(&joined)) {
+            set_native_error_with_type(format!("path_join(): {}", err_msg), ErrorType::PermissionError);
+            return Value::Null;
+        }
+    }
+    Value::Path(joined)
+}
+
+/// path_filename(path) - имя файла с расширением
+pub fn native_path_filename(args: &[Value]) -> Value {
+    match args.first().and_then(path_arg) {
+        Some(p) => native_path_name(&[Value::Path(p)]),
+        None => Value::String("".into()),
+    }
+}
+
+/// path_ext(path) - расширение без точки
+pub fn native_path_ext(args: &[Value]) -> Value {
+    match args.first().and_then(path_arg) {
+        Some(p) => native_path_extension(&[Value::Path(p)]),
+        None => Value::String("".into()),
+    }
+}
+
+/// path_absolute(path) - абсолютный путь с убранными '.' и '..' (файл может не существовать).
+/// Относительные пути разрешаются от текущей директории, в режиме --use-ve - от папки сессии
+pub fn native_path_absolute(args: &[Value]) -> Value {
+    use crate::common::path::lexical_normalize;
+    use crate::websocket::{get_use_ve, set_native_error};
+
+    let path = match args.first().and_then(path_arg) {
+        Some(p) => p,
+        None => return Value::Null,
+    };
+    if get_use_ve() {
+        return match resolve_path_in_session(&path) {
+            Ok(p) => Value::Path(p),
+            Err(err_msg) => {
+                set_native_error(err_msg);
+                Value::Null
+            }
+        };
+    }
+    if path.is_absolute() {
+        return Value::Path(lexical_normalize(&path));
+    }
+    match env::current_dir() {
+        Ok(cwd) => Value::Path(lexical_normalize(&cwd.join(path))),
+        Err(e) => {
+            set_native_error(format!("path_absolute(): cannot get current directory: {}", e));
+            Value::Null
+        }
+    }
+}
+
+/// is_absolute(path) - true для абсолютных путей (на Windows - с буквой диска или UNC)
+/// и SMB путей lib://
+pub fn native_is_absolute(args: &[Value]) -> Value {
+    use crate::common::path::SMB_PREFIX;
+
+    match args.first().and_then(path_arg) {
+        Some(p) => Value::Bool(p.is_absolute() || p.to_string_lossy().starts_with(SMB_PREFIX)),
+        None => Value::Bool(false),
+    }
+}
+
 // Математические функции
 
 pub fn native_abs(args: &[Value]) -> Value {
@@ -669,6 +983,7 @@ pub fn native_abs(args: &[Value]) -> Value {
     
     match &args[0] {
         Value::Number(n) => Value::Number(n.abs()),
+        Value::Integer(i) => i.checked_abs().map_or(Value::Number((*i as f64).abs()), Value::Integer),
         _ => Value::Null,
     }
 }
@@ -678,8 +993,8 @@ pub fn native_sqrt(args: &[Value]) -> Value {
         return Value::Number(0.0);
     }
     
-    match &args[0] {
-        Value::Number(n) => {
+    match &args[0].as_f64() {
+        Some(n) => {
             if *n < 0.0 {
                 Value::Null // Отрицательное число - возвращаем Null
             } else {
@@ -695,43 +1010,75 @@ pub fn native_pow(args: &[Value]) -> Value {
         return Value::Null;
     }
     
-    let base = match &args[0] {
-        Value::Number(n) => *n,
-        _ => return Value::Null,
+    let base = match args[0].as_f64() {
+        Some(n) => n,
+        None => return Value::Null,
     };
     
-    let exp = match &args[1] {
-        Value::Number(n) => *n,
-        _ => return Value::Null,
+    let exp = match args[1].as_f64() {
+        Some(n) => n,
+        None => return Value::Null,
     };
     
     Value::Number(base.powf(exp))
 }
 
+/// divmod(a, b) - [a // b, a - b * (a // b)]. Частное округляется вниз, как у //, поэтому
+/// остаток имеет знак делителя: divmod(-7, 2) = [-4, 1]
+pub fn native_divmod(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+    let (a, b) = match args {
+        [a, b] if a.is_numeric() && b.is_numeric() => (a, b),
+        _ => {
+            set_native_error_with_type("divmod() expects two numbers".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    if b.as_f64() == Some(0.0) {
+        set_native_error_with_type("divmod(): division by zero".to_string(), ErrorType::ValueError);
+        return Value::Null;
+    }
+    if let (Value::Integer(a), Value::Integer(b)) = (a, b) {
+        // i64::MIN / -1 не помещается в Integer - считается в Number
+        if let (Some(mut q), Some(mut r)) = (a.checked_div(*b), a.checked_rem(*b)) {
+            if r != 0 && (r < 0) != (*b < 0) {
+                q -= 1;
+                r += b;
+            }
+            return Value::Array(Rc::new(RefCell::new(vec![Value::Integer(q), Value::Integer(r)])));
+        }
+    }
+    let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+    let q = (a / b).floor();
+    Value::Array(Rc::new(RefCell::new(vec![Value::Number(q), Value::Number(a - q * b)])))
+}
+
 pub fn native_min(args: &[Value]) -> Value {
     if args.is_empty() {
         return Value::Null;
     }
     
-    let mut min_val: Option<f64> = None;
+    // Запоминаем исходное значение, чтобы Integer остался Integer
+    let mut min_val: Option<(f64, &Value)> = None;
     
     for arg in args {
-        match arg {
-            Value::Number(n) => {
-                if let Some(current_min) = min_val {
-                    if *n < current_min {
-                        min_val = Some(*n);
+        match arg.as_f64() {
+            Some(n) => {
+                if let Some((current_min, _)) = min_val {
+                    if n < current_min {
+                        min_val = Some((n, arg));
                     }
                 } else {
-                    min_val = Some(*n);
+                    min_val = Some((n, arg));
                 }
             }
-            _ => return Value::Null, // Если есть нечисловой аргумент, возвращаем Null
+            None => return Value::Null, // Если есть нечисловой аргумент, возвращаем Null
         }
     }
     
     match min_val {
-        Some(n) => Value::Number(n),
+        Some((_, value)) => value.clone(),
         None => Value::Null,
     }
 }
@@ -741,25 +1088,26 @@ pub fn native_max(args: &[Value]) -> Value {
         return Value::Null;
     }
     
-    let mut max_val: Option<f64> = None;
+    // Запоминаем исходное значение, чтобы Integer остался Integer
+    let mut max_val: Option<(f64, &Value)> = None;
     
     for arg in args {
-        match arg {
-            Value::Number(n) => {
-                if let Some(current_max) = max_val {
-                    if *n > current_max {
-                        max_val = Some(*n);
+        match arg.as_f64() {
+            Some(n) => {
+                if let Some((current_max, _)) = max_val {
+                    if n > current_max {
+                        max_val = Some((n, arg));
                     }
                 } else {
-                    max_val = Some(*n);
+                    max_val = Some((n, arg));
                 }
             }
-            _ => return Value::Null, // Если есть нечисловой аргумент, возвращаем Null
+            None => return Value::Null, // Если есть нечисловой аргумент, возвращаем Null
         }
     }
     
     match max_val {
-        Some(n) => Value::Number(n),
+        Some((_, value)) => value.clone(),
         None => Value::Null,
     }
 }
@@ -770,6 +1118,7 @@ pub fn native_round(args: &[Value]) -> Value {
     }
     
     match &args[0] {
+        Value::Integer(i) => Value::Integer(*i),
         Value::Number(n) => {
             // Стандартное округление: к ближайшему целому
             // Для положительных: 3.5 -> 4, для отрицательных: -3.5 -> -3 (к нулю)
@@ -796,37 +1145,102 @@ pub fn native_round(args: &[Value]) -> Value {
     }
 }
 
+/// parse_currency(text) - денежная сумма из строки: "$1,234.56", "-€10", "1 234,50 EUR"
+pub fn native_parse_currency(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    match args.first() {
+        Some(Value::String(s)) => match crate::common::currency::parse(s) {
+            Some((amount, symbol)) => Value::Currency { amount, symbol: symbol.into() },
+            None => {
+                set_native_error_with_type(
+                    format!("parse_currency(): cannot parse '{}' as a currency amount", s),
+                    ErrorType::ValueError,
+                );
+                Value::Null
+            }
+        },
+        Some(value @ Value::Currency { .. }) => value.clone(),
+        _ => {
+            set_native_error_with_type("parse_currency() expects a string".to_string(), ErrorType::TypeError);
+            Value::Null
+        }
+    }
+}
+
+/// format_currency(amount, symbol, decimals=2) - строка с разделителями разрядов: "$1,234.56", "10.00 EUR".
+/// Для денежной суммы symbol можно не указывать (null) - берется ее собственный символ
+pub fn native_format_currency(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let (amount, own_symbol) = match args.first() {
+        Some(Value::Currency { amount, symbol }) => (*amount, Some(symbol.to_string())),
+        Some(value) if value.is_numeric() => (value.as_f64().unwrap_or(0.0), None),
+        _ => {
+            set_native_error_with_type(
+                "format_currency() expects a number or a currency amount".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    let symbol = match args.get(1) {
+        Some(Value::String(s)) => s.to_string(),
+        None | Some(Value::Null) => own_symbol.unwrap_or_default(),
+        Some(_) => {
+            set_native_error_with_type("format_currency(): symbol must be a string".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let decimals = match args.get(2) {
+        None | Some(Value::Null) => 2,
+        Some(value) => match value.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= 20.0 => n as usize,
+            _ => {
+                set_native_error_with_type(
+                    format!("format_currency(): decimals must be an integer from 0 to 20, got {}", value.to_string()),
+                    ErrorType::ValueError,
+                );
+                return Value::Null;
+            }
+        },
+    };
+    Value::String(crate::common::currency::format(amount, &symbol, decimals).into())
+}
+
 // Строковые функции
 
 pub fn native_upper(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
     match &args[0] {
-        Value::String(s) => Value::String(s.to_uppercase()),
+        Value::String(s) => Value::String(s.to_uppercase().into()),
         _ => Value::Null,
     }
 }
 
 pub fn native_lower(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
     match &args[0] {
-        Value::String(s) => Value::String(s.to_lowercase()),
+        Value::String(s) => Value::String(s.to_lowercase().into()),
         _ => Value::Null,
     }
 }
 
 pub fn native_trim(args: &[Value]) -> Value {
     if args.is_empty() {
-        return Value::String(String::new());
+        return Value::String("".into());
     }
     
     match &args[0] {
-        Value::String(s) => Value::String(s.trim().to_string()),
+        Value::String(s) => Value::String(s.trim().into()),
         _ => Value::Null,
     }
 }
@@ -846,8 +1260,8 @@ pub fn native_split(args: &[Value]) -> Value {
         _ => return Value::Null,
     };
     
-    let parts: Vec<Value> = s.split(delim)
-        .map(|part| Value::String(part.to_string()))
+    let parts: Vec<Value> = s.split(&**delim)
+        .map(|part| Value::String(part.into()))
         .collect();
     
     Value::Array(Rc::new(RefCell::new(parts)))
@@ -884,9 +1298,10 @@ pub fn native_join(args: &[Value]) -> Value {
         .map(|v| v.to_string())
         .collect();
     
-    Value::String(parts.join(delim))
+    Value::String(parts.join(delim).into())
 }
 
+/// contains(str, substr) - подстрока; contains(array, value) - элемент (как оператор in)
 pub fn native_contains(args: &[Value]) -> Value {
     if args.len() < 2 {
         return Value::Bool(false);
@@ -894,6 +1309,7 @@ pub fn native_contains(args: &[Value]) -> Value {
     
     let s = match &args[0] {
         Value::String(str) => str,
+        Value::Array(arr) => return Value::Bool(arr.borrow().iter().any(|item| item == &args[1])),
         _ => return Value::Bool(false),
     };
     
@@ -902,7 +1318,7 @@ pub fn native_contains(args: &[Value]) -> Value {
         _ => return Value::Bool(false),
     };
     
-    Value::Bool(s.contains(substr))
+    Value::Bool(s.contains(&**substr))
 }
 
 // Функции для работы с массивами
@@ -983,28 +1399,47 @@ pub fn native_unique(args: &[Value]) -> Value {
     Value::Array(Rc::new(RefCell::new(result)))
 }
 
+/// reverse(array) - новый массив в обратном порядке, исходный не изменяется
 pub fn native_reverse(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Array(Rc::new(RefCell::new(Vec::new())));
-    }
-    
-    let arr = match &args[0] {
-        Value::Array(a) => a,
-        _ => return Value::Null,
+    let arr = match args.first() {
+        Some(Value::Array(a)) => a,
+        Some(_) => return Value::Null,
+        None => return Value::Array(Rc::new(RefCell::new(Vec::new()))),
     };
     
-    // Copy-on-Write: если массив используется в нескольких местах, клонируем
-    let arr = if Rc::strong_count(arr) > 1 {
-        let cloned_vec: Vec<Value> = arr.borrow().iter().map(|v| v.clone()).collect();
-        Rc::new(RefCell::new(cloned_vec))
-    } else {
-        arr.clone()
+    let reversed: Vec<Value> = arr.borrow().iter().rev().cloned().collect();
+    Value::Array(Rc::new(RefCell::new(reversed)))
+}
+
+/// shuffle(array, seed=null) - перемешанная копия массива; с seed результат воспроизводим
+pub fn native_shuffle(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+    use rand::seq::SliceRandom;
+
+    let arr = match args.first() {
+        Some(Value::Array(a)) => a,
+        _ => {
+            set_native_error_with_type("shuffle() expects an array".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
     };
-    
-    // Мутируем in-place
-    arr.borrow_mut().reverse();
-    
-    Value::Array(arr)
+    let seed = match args.get(1) {
+        None | Some(Value::Null) => None,
+        Some(Value::Integer(i)) if *i >= 0 => Some(*i as u64),
+        Some(Value::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as u64),
+        Some(other) => {
+            set_native_error_with_type(
+                format!("shuffle() seed must be a non-negative integer, got {}", other.to_string()),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+    };
+
+    let mut shuffled = arr.borrow().clone();
+    shuffled.shuffle(&mut crate::common::random::rng(seed));
+    Value::Array(Rc::new(RefCell::new(shuffled)))
 }
 
 pub fn native_sort(args: &[Value]) -> Value {
@@ -1039,6 +1474,9 @@ pub fn native_sum(args: &[Value]) -> Value {
     if args.is_empty() {
         return Value::Number(0.0);
     }
+    if matches!(args[0], Value::Table(_) | Value::LazyTable(_)) {
+        return aggregate_table_column("sum", AggFunc::Sum, args);
+    }
     
     let arr = match &args[0] {
         Value::Array(a) => a,
@@ -1048,18 +1486,28 @@ pub fn native_sum(args: &[Value]) -> Value {
     let arr_ref = arr.borrow();
     let mut sum = 0.0;
     let mut has_numbers = false;
+    // Сумма одних Integer считается точно (None - было переполнение или встретился Number)
+    let mut integer_sum: Option<i64> = Some(0);
+    let mut has_integers = false;
     
     for item in arr_ref.iter() {
-        if let Value::Number(n) = item {
-            sum += n;
-            has_numbers = true;
+        match item {
+            Value::Number(n) => {
+                sum += n;
+                has_numbers = true;
+            }
+            Value::Integer(i) => {
+                sum += *i as f64;
+                integer_sum = integer_sum.and_then(|acc| acc.checked_add(*i));
+                has_integers = true;
+            }
+            _ => {}
         }
     }
     
-    if has_numbers {
-        Value::Number(sum)
-    } else {
-        Value::Number(0.0)
+    match integer_sum {
+        Some(total) if has_integers && !has_numbers => Value::Integer(total),
+        _ => Value::Number(sum),
     }
 }
 
@@ -1067,6 +1515,9 @@ pub fn native_average(args: &[Value]) -> Value {
     if args.is_empty() {
         return Value::Number(0.0);
     }
+    if matches!(args[0], Value::Table(_) | Value::LazyTable(_)) {
+        return aggregate_table_column("average", AggFunc::Average, args);
+    }
     
     let arr = match &args[0] {
         Value::Array(a) => a,
@@ -1078,7 +1529,7 @@ pub fn native_average(args: &[Value]) -> Value {
     let mut count = 0;
     
     for item in arr_ref.iter() {
-        if let Value::Number(n) = item {
+        if let Some(n) = item.as_f64() {
             sum += n;
             count += 1;
         }
@@ -1098,10 +1549,84 @@ pub fn native_count(args: &[Value]) -> Value {
     
     match &args[0] {
         Value::Array(arr) => Value::Number(arr.borrow().len() as f64),
+        Value::Table(_) | Value::LazyTable(_) => aggregate_table_column("count", AggFunc::Count, args),
         _ => Value::Number(0.0),
     }
 }
 
+/// Передает в visit строки таблицы или ленивой таблицы - значения колонок columns в этом порядке.
+/// Ленивая таблица над CSV файлом читается потоком, не собираясь в память; статистика
+/// прохода доступна через last_collect_report(). false - ошибка сохранена
+fn for_each_table_row(name: &str, table: &Value, columns: &[String], visit: &mut dyn FnMut(&[Value])) -> bool {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let headers = match table {
+        Value::Table(table) => table.borrow().headers.clone(),
+        Value::LazyTable(lazy) => lazy.headers.clone(),
+        _ => {
+            set_native_error_with_type(format!("{}() expects a table", name), ErrorType::TypeError);
+            return false;
+        }
+    };
+    if let Some(missing) = columns.iter().find(|c| !headers.contains(c)) {
+        set_native_error_with_type(format!("{}(): Column '{}' not found", name, missing), ErrorType::ColumnNotFoundError);
+        return false;
+    }
+    match table {
+        Value::LazyTable(lazy) => match lazy.stream(columns, visit) {
+            Ok(stats) => {
+                LAST_COLLECT_REPORT.with(|r| *r.borrow_mut() = Some(stats));
+                true
+            }
+            Err(message) => {
+                crate::websocket::set_native_error(message);
+                false
+            }
+        },
+        Value::Table(table) => {
+            let table = table.borrow();
+            let indices: Vec<usize> = columns.iter().filter_map(|c| table.headers.iter().position(|h| h == c)).collect();
+            let mut row_buffer = Vec::with_capacity(indices.len());
+            for row in &table.rows {
+                row_buffer.clear();
+                row_buffer.extend(indices.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)));
+                visit(&row_buffer);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// sum(table, column), average(table, column), count(table) и count(table, column):
+/// значения накапливаются по одной строке. count без колонки считает строки,
+/// с колонкой - значения, отличные от null
+fn aggregate_table_column(name: &str, func: AggFunc, args: &[Value]) -> Value {
+    let columns: Vec<String> = match args.get(1) {
+        Some(Value::String(column)) => vec![column.to_string()],
+        None | Some(Value::Null) if func == AggFunc::Count => Vec::new(),
+        _ => {
+            crate::websocket::set_native_error_with_type(
+                format!("{}() expects a column name for a table", name),
+                crate::common::error::ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    let mut accumulator = Accumulator::new(func);
+    let mut rows = 0usize;
+    let visited = for_each_table_row(name, &args[0], &columns, &mut |row: &[Value]| match row.first() {
+        Some(value) => accumulator.push(value),
+        None => rows += 1,
+    });
+    match (visited, columns.is_empty()) {
+        (false, _) => Value::Null,
+        (true, true) => Value::Number(rows as f64),
+        (true, false) => accumulator.finish(),
+    }
+}
+
 pub fn native_any(args: &[Value]) -> Value {
     if args.is_empty() {
         return Value::Bool(false);
@@ -1156,31 +1681,453 @@ pub fn native_all(args: &[Value]) -> Value {
     Value::Bool(true)
 }
 
-// Функции для работы с таблицами
+/// zip(a, b, ..., strict=false) - массив кортежей [a[i], b[i], ...] длиной по самому короткому массиву
+/// При strict=true массивы разной длины - ошибка
+pub fn native_zip(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
 
-pub fn native_table(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Null;
-    }
+    // Именованный strict передается последним аргументом
+    let (strict, arrays) = match args.split_last() {
+        Some((Value::Bool(strict), rest)) => (*strict, rest),
+        _ => (false, args),
+    };
 
-    // Первый аргумент - данные (массив массивов)
-    let data = match &args[0] {
-        Value::Array(arr) => {
-            // Преобразуем массив массивов в Vec<Vec<Value>>
-            let arr_ref = arr.borrow();
-            let mut rows = Vec::new();
-            for row_val in arr_ref.iter() {
-                match row_val {
-                    Value::Array(row) => rows.push(row.borrow().clone()),
-                    _ => {
-                        // Если элемент не массив, создаем строку с одним элементом
-                        rows.push(vec![row_val.clone()]);
-                    }
-                }
+    let mut columns = Vec::with_capacity(arrays.len());
+    for (i, value) in arrays.iter().enumerate() {
+        match value {
+            Value::Array(arr) => columns.push(arr.borrow()),
+            _ => {
+                set_native_error_with_type(format!("zip() argument {} must be an array", i + 1), ErrorType::TypeError);
+                return Value::Null;
             }
-            rows
         }
-        _ => return Value::Null,
+    }
+
+    let lengths: Vec<usize> = columns.iter().map(|c| c.len()).collect();
+    if strict && lengths.windows(2).any(|w| w[0] != w[1]) {
+        let lengths: Vec<String> = lengths.iter().map(|l| l.to_string()).collect();
+        set_native_error_with_type(
+            format!("zip(strict=true): arrays have different lengths: {}", lengths.join(", ")),
+            ErrorType::ValueError,
+        );
+        return Value::Null;
+    }
+
+    let len = lengths.iter().copied().min().unwrap_or(0);
+    let tuples = (0..len)
+        .map(|i| {
+            let tuple = columns.iter().map(|c| c[i].clone()).collect();
+            Value::Array(Rc::new(RefCell::new(tuple)))
+        })
+        .collect();
+    Value::Array(Rc::new(RefCell::new(tuples)))
+}
+
+/// unzip(tuples) - обратная к zip: [[a1, b1], [a2, b2]] -> [[a1, a2], [b1, b2]]
+pub fn native_unzip(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let tuples = match args.first() {
+        Some(Value::Array(arr)) => arr.borrow(),
+        _ => {
+            set_native_error_with_type("unzip() expects an array of arrays".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+
+    let mut columns: Vec<Vec<Value>> = Vec::new();
+    for (i, tuple) in tuples.iter().enumerate() {
+        let Value::Array(tuple) = tuple else {
+            set_native_error_with_type(format!("unzip(): element {} is not an array", i), ErrorType::TypeError);
+            return Value::Null;
+        };
+        let tuple = tuple.borrow();
+        if i == 0 {
+            columns = vec![Vec::with_capacity(tuples.len()); tuple.len()];
+        } else if tuple.len() != columns.len() {
+            set_native_error_with_type(
+                format!("unzip(): element {} has {} values, expected {}", i, tuple.len(), columns.len()),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+        for (column, value) in columns.iter_mut().zip(tuple.iter()) {
+            column.push(value.clone());
+        }
+    }
+
+    Value::Array(Rc::new(RefCell::new(
+        columns.into_iter().map(|c| Value::Array(Rc::new(RefCell::new(c)))).collect(),
+    )))
+}
+
+/// reduce(array, fn, initial) - свертка массива функцией fn(accumulator, element) (реализация в Vm::call_reduce)
+pub fn native_reduce(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// map(fn, array, ...) - применяет fn к соответствующим элементам массивов (реализация в Vm::call_map)
+pub fn native_map(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// sleep(ms) - пауза в миллисекундах (реализация в Vm::call_sleep)
+pub fn native_sleep(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// retry(fn, attempts, backoff_ms, factor, retry_on) - повтор вызова с экспоненциальной паузой
+/// (реализация в Vm::call_retry)
+pub fn native_retry(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// sort_by(array, fn, descending = false) - реализация в Vm::call_sort_by
+pub fn native_sort_by(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// index_of(array, value) - индекс первого равного элемента (равенство как у ==) или -1
+pub fn native_index_of(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let array = match args.first() {
+        Some(Value::Array(arr)) => arr,
+        _ => {
+            set_native_error_with_type("index_of() expects an array".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let needle = args.get(1).unwrap_or(&Value::Null);
+    let index = array.borrow().iter().position(|item| item == needle);
+    Value::Number(index.map_or(-1.0, |i| i as f64))
+}
+
+/// deep_equal(a, b, ignore_column_order = false) - структурное равенство как у ==, но NaN
+/// равен NaN, а таблицы по желанию сравниваются без учета порядка колонок
+pub fn native_deep_equal(args: &[Value]) -> Value {
+    use crate::common::value::{EqualityOptions, MAX_EQUALITY_DEPTH};
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let ignore_column_order = match args.get(2) {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(flag)) => *flag,
+        Some(_) => {
+            set_native_error_with_type("deep_equal() ignore_column_order must be a boolean".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let options = EqualityOptions { nan_equal: true, ignore_column_order };
+    let (a, b) = (args.first().unwrap_or(&Value::Null), args.get(1).unwrap_or(&Value::Null));
+    match a.deep_equal(b, options) {
+        Some(equal) => Value::Bool(equal),
+        None => {
+            set_native_error_with_type(
+                format!("deep_equal(): values are nested deeper than {} levels", MAX_EQUALITY_DEPTH),
+                ErrorType::ValueError,
+            );
+            Value::Null
+        }
+    }
+}
+
+/// same_reference(a, b) - true, если a и b - один и тот же массив или таблица (а не равные копии).
+/// Для значений без ссылочной идентичности (числа, строки, объекты) - false
+pub fn native_same_reference(args: &[Value]) -> Value {
+    let same = match (args.first(), args.get(1)) {
+        (Some(Value::Array(a)), Some(Value::Array(b))) => Rc::ptr_eq(a, b),
+        (Some(Value::Table(a)), Some(Value::Table(b))) => Rc::ptr_eq(a, b),
+        (Some(Value::LazyTable(a)), Some(Value::LazyTable(b))) => Rc::ptr_eq(a, b),
+        (
+            Some(Value::ColumnReference { table: a, column_name: col_a }),
+            Some(Value::ColumnReference { table: b, column_name: col_b }),
+        ) => Rc::ptr_eq(a, b) && col_a == col_b,
+        _ => false,
+    };
+    Value::Bool(same)
+}
+
+fn flatten_into(items: &[Value], depth: i64, out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(inner) if depth != 0 => flatten_into(&inner.borrow(), depth - 1, out),
+            _ => out.push(item.clone()),
+        }
+    }
+}
+
+/// flatten(array, depth=1) - раскрывает вложенные массивы до указанной глубины (-1 - полностью)
+pub fn native_flatten(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let array = match args.first() {
+        Some(Value::Array(arr)) => Rc::clone(arr),
+        _ => {
+            set_native_error_with_type("flatten() expects an array".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let depth = match args.get(1) {
+        None | Some(Value::Null) => 1,
+        Some(Value::Integer(i)) if *i >= -1 => *i,
+        Some(Value::Number(n)) if n.fract() == 0.0 && *n >= -1.0 => *n as i64,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("flatten() depth must be a non-negative integer or -1, got {}", other.to_string()),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+    };
+
+    let mut result = Vec::new();
+    flatten_into(&array.borrow(), depth, &mut result);
+    Value::Array(Rc::new(RefCell::new(result)))
+}
+
+/// chunk(array, size) - разбивает массив на части по size элементов (последняя может быть короче)
+pub fn native_chunk(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let array = match args.first() {
+        Some(Value::Array(arr)) => Rc::clone(arr),
+        _ => {
+            set_native_error_with_type("chunk() expects an array".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    let size = match args.get(1) {
+        Some(Value::Integer(i)) if *i >= 1 => *i as usize,
+        Some(Value::Number(n)) if n.fract() == 0.0 && *n >= 1.0 => *n as usize,
+        Some(value @ (Value::Number(_) | Value::Integer(_))) => {
+            set_native_error_with_type(
+                format!("chunk() size must be a positive integer, got {}", value.to_string()),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+        _ => {
+            set_native_error_with_type("chunk() expects a numeric size".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+
+    let chunks = array
+        .borrow()
+        .chunks(size)
+        .map(|part| Value::Array(Rc::new(RefCell::new(part.to_vec()))))
+        .collect();
+    Value::Array(Rc::new(RefCell::new(chunks)))
+}
+
+// Функции для работы с объектами
+
+/// Аргументы merge/deep_merge: все должны быть объектами
+fn object_args(name: &str, args: &[Value]) -> Option<Vec<ObjectMap>> {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let mut objects = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            Value::Object(map) => objects.push(map.clone()),
+            other => {
+                set_native_error_with_type(
+                    format!("{}() expects objects, got {}", name, other.to_string()),
+                    ErrorType::TypeError,
+                );
+                return None;
+            }
+        }
+    }
+    Some(objects)
+}
+
+/// merge(a, b, ...) - поверхностное слияние объектов: при совпадении ключей побеждает последний.
+/// Новые ключи добавляются в конец, существующие сохраняют свою позицию
+pub fn native_merge(args: &[Value]) -> Value {
+    let objects = match object_args("merge", args) {
+        Some(objects) => objects,
+        None => return Value::Null,
+    };
+    let mut result = ObjectMap::new();
+    for object in objects {
+        result.extend(object);
+    }
+    Value::Object(result)
+}
+
+fn deep_merge_into(target: &mut ObjectMap, source: ObjectMap) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            // Вложенные объекты сливаются рекурсивно, массивы и скаляры заменяются целиком
+            (Some(Value::Object(existing)), Value::Object(incoming)) => deep_merge_into(existing, incoming),
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// deep_merge(a, b, ...) - рекурсивное слияние объектов (например, слоев конфигурации из JSON)
+pub fn native_deep_merge(args: &[Value]) -> Value {
+    let objects = match object_args("deep_merge", args) {
+        Some(objects) => objects,
+        None => return Value::Null,
+    };
+    let mut result = ObjectMap::new();
+    for object in objects {
+        deep_merge_into(&mut result, object);
+    }
+    Value::Object(result)
+}
+
+/// Объект и массив ключей - аргументы pick/omit
+fn object_and_keys(name: &str, args: &[Value]) -> Option<(ObjectMap, Vec<String>)> {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let object = match args.first() {
+        Some(Value::Object(map)) => map.clone(),
+        _ => {
+            set_native_error_with_type(format!("{}() expects an object as the first argument", name), ErrorType::TypeError);
+            return None;
+        }
+    };
+    let keys = match args.get(1) {
+        Some(Value::Array(arr)) => {
+            let mut keys = Vec::new();
+            for key in arr.borrow().iter() {
+                match key {
+                    Value::String(s) => keys.push(s.to_string()),
+                    other => {
+                        set_native_error_with_type(
+                            format!("{}() keys must be strings, got {}", name, other.to_string()),
+                            ErrorType::TypeError,
+                        );
+                        return None;
+                    }
+                }
+            }
+            keys
+        }
+        _ => {
+            set_native_error_with_type(format!("{}() expects an array of keys", name), ErrorType::TypeError);
+            return None;
+        }
+    };
+    Some((object, keys))
+}
+
+/// pick(obj, keys, strict = false) - новый объект только с перечисленными ключами (в порядке keys).
+/// Отсутствующие ключи пропускаются, при strict=true - KeyError
+pub fn native_pick(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let (object, keys) = match object_and_keys("pick", args) {
+        Some(parsed) => parsed,
+        None => return Value::Null,
+    };
+    let strict = args.get(2).map(|v| v.is_truthy()).unwrap_or(false);
+
+    let mut result = ObjectMap::with_capacity(keys.len());
+    for key in keys {
+        match object.get(&key) {
+            Some(value) => {
+                result.insert(key, value.clone());
+            }
+            None if strict => {
+                set_native_error_with_type(format!("pick(): key '{}' not found in object", key), ErrorType::KeyError);
+                return Value::Null;
+            }
+            None => {}
+        }
+    }
+    Value::Object(result)
+}
+
+/// omit(obj, keys) - новый объект без перечисленных ключей, порядок остальных сохраняется
+pub fn native_omit(args: &[Value]) -> Value {
+    let (mut object, keys) = match object_and_keys("omit", args) {
+        Some(parsed) => parsed,
+        None => return Value::Null,
+    };
+    object.retain(|key, _| !keys.contains(key));
+    Value::Object(object)
+}
+
+/// Объект и строковый ключ - аргументы has_key/get
+fn object_and_key<'a>(name: &str, args: &'a [Value]) -> Option<(&'a ObjectMap, &'a str)> {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    match (args.first(), args.get(1)) {
+        (Some(Value::Object(map)), Some(Value::String(key))) => Some((map, &**key)),
+        (Some(Value::Object(_)), other) => {
+            set_native_error_with_type(
+                format!("{}() key must be a string, got {}", name, other.map_or("nothing".to_string(), |v| v.to_string())),
+                ErrorType::TypeError,
+            );
+            None
+        }
+        _ => {
+            set_native_error_with_type(format!("{}() expects an object as the first argument", name), ErrorType::TypeError);
+            None
+        }
+    }
+}
+
+/// has_key(obj, key) - есть ли ключ в объекте
+pub fn native_has_key(args: &[Value]) -> Value {
+    match object_and_key("has_key", args) {
+        Some((object, key)) => Value::Bool(object.contains_key(key)),
+        None => Value::Null,
+    }
+}
+
+/// get(obj, key, default = null) - значение по ключу или default, если ключа нет
+pub fn native_get(args: &[Value]) -> Value {
+    match object_and_key("get", args) {
+        Some((object, key)) => object
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| args.get(2).cloned().unwrap_or(Value::Null)),
+        None => Value::Null,
+    }
+}
+
+// Функции для работы с таблицами
+
+pub fn native_table(args: &[Value]) -> Value {
+    if args.is_empty() {
+        return Value::Null;
+    }
+
+    // Первый аргумент - данные (массив массивов)
+    let data = match &args[0] {
+        Value::Array(arr) => {
+            // Преобразуем массив массивов в Vec<Vec<Value>>
+            let arr_ref = arr.borrow();
+            let mut rows = Vec::new();
+            for row_val in arr_ref.iter() {
+                match row_val {
+                    Value::Array(row) => rows.push(row.borrow().clone()),
+                    _ => {
+                        // Если элемент не массив, создаем строку с одним элементом
+                        rows.push(vec![row_val.clone()]);
+                    }
+                }
+            }
+            rows
+        }
+        _ => return Value::Null,
     };
 
     // Второй аргумент (опциональный) - заголовки
@@ -1191,7 +2138,7 @@ pub fn native_table(args: &[Value]) -> Value {
                 let mut header_strings = Vec::new();
                 for header_val in headers_arr_ref.iter() {
                     match header_val {
-                        Value::String(s) => header_strings.push(s.clone()),
+                        Value::String(s) => header_strings.push(s.to_string()),
                         _ => header_strings.push(header_val.to_string()),
                     }
                 }
@@ -1215,119 +2162,115 @@ pub fn native_read_file(args: &[Value]) -> Value {
     // Первый аргумент - путь к файлу
     // Для Path значений используем PathBuf напрямую, чтобы не терять информацию при конвертации
     let file_path = match &args[0] {
-        Value::Path(p) => p.clone(),
-        Value::String(s) => PathBuf::from(s),
+        Value::Path(p) => smb_url_to_lib(p),
+        Value::String(s) => smb_url_to_lib(&from_user_str(s)),
         _ => return Value::Null,
     };
+    if !file_access_allowed("read_file", &file_path, false) {
+        return Value::Null;
+    }
     
     let file_path_str = file_path.to_string_lossy().to_string();
 
-    // Проверяем, является ли это SMB путем (lib://)
-    if file_path_str.starts_with("lib://") {
-        // Извлекаем имя шары и путь к файлу
-        let path_without_prefix = &file_path_str[6..]; // Убираем "lib://"
-        let parts: Vec<&str> = path_without_prefix.splitn(2, '/').collect();
-        
-        if parts.is_empty() {
-            return Value::Null;
-        }
-        
-        let share_name = parts[0];
-        let file_path_on_share = if parts.len() > 1 { parts[1] } else { "" };
-        
-        // Получаем SmbManager из thread-local storage
-        if let Some(smb_manager) = crate::vm::file_ops::get_smb_manager() {
-            match smb_manager.lock().unwrap().read_file(share_name, file_path_on_share) {
-                Ok(content) => {
-                    // Определяем тип файла по расширению
-                    let extension = std::path::Path::new(file_path_on_share)
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .unwrap_or("")
-                        .to_lowercase();
-                    
-                    match extension.as_str() {
-                        "csv" => {
-                            // Парсим CSV из байтов
-                            // Создаем временный файл для парсинга CSV
-                            use std::io::Write;
-                            let temp_file = std::env::temp_dir().join(format!("datacode_smb_{}.csv", std::process::id()));
-                            if let Ok(mut file) = fs::File::create(&temp_file) {
-                                if file.write_all(&content).is_ok() {
-                                    match read_csv_file(&temp_file) {
-                                        Ok(table) => {
-                                            let _ = fs::remove_file(&temp_file);
-                                            return Value::Table(Rc::new(RefCell::new(table)));
-                                        }
-                                        Err(_) => {
-                                            let _ = fs::remove_file(&temp_file);
-                                        }
+    // SMB путь (lib://share/... или smb://share/...): файл читается через подключение к шаре
+    if let Some((share_name, file_path_on_share)) = split_smb_path(&file_path_str) {
+        match crate::vm::file_ops::get_smb_manager().lock().unwrap().read_file(share_name, file_path_on_share) {
+            Ok(content) => {
+                // Определяем тип файла по расширению
+                let extension = std::path::Path::new(file_path_on_share)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+                
+                match extension.as_str() {
+                    "csv" => {
+                        // Парсим CSV из байтов
+                        // Создаем временный файл для парсинга CSV
+                        use std::io::Write;
+                        let temp_file = std::env::temp_dir().join(format!("datacode_smb_{}.csv", std::process::id()));
+                        if let Ok(mut file) = fs::File::create(&temp_file) {
+                            if file.write_all(&content).is_ok() {
+                                match read_csv_file(&temp_file) {
+                                    Ok(table) => {
+                                        let _ = fs::remove_file(&temp_file);
+                                        return Value::Table(Rc::new(RefCell::new(table)));
+                                    }
+                                    Err(_) => {
+                                        let _ = fs::remove_file(&temp_file);
                                     }
                                 }
                             }
-                            Value::Null
                         }
-                        "xlsx" => {
-                            // Создаем временный файл для парсинга XLSX
-                            use std::io::Write;
-                            let temp_file = std::env::temp_dir().join(format!("datacode_smb_{}.xlsx", std::process::id()));
-                            if let Ok(mut file) = fs::File::create(&temp_file) {
-                                if file.write_all(&content).is_ok() {
-                                    let header_row = if args.len() > 1 {
-                                        match &args[1] {
-                                            Value::Number(n) => *n as usize,
-                                            _ => 0,
-                                        }
-                                    } else {
-                                        0
-                                    };
-                                    
-                                    let sheet_name = if args.len() > 2 {
-                                        match &args[2] {
-                                            Value::String(s) => Some(s.clone()),
-                                            _ => None,
-                                        }
-                                    } else if args.len() > 1 {
-                                        match &args[1] {
-                                            Value::String(s) => Some(s.clone()),
-                                            _ => None,
-                                        }
-                                    } else {
-                                        None
-                                    };
-                                    
-                                    match read_xlsx_file(&temp_file, header_row, sheet_name.as_deref()) {
-                                        Ok(table) => {
-                                            let _ = fs::remove_file(&temp_file);
-                                            return Value::Table(Rc::new(RefCell::new(table)));
-                                        }
-                                        Err(_) => {
-                                            let _ = fs::remove_file(&temp_file);
-                                        }
+                        Value::Null
+                    }
+                    "xlsx" => {
+                        // Создаем временный файл для парсинга XLSX
+                        use std::io::Write;
+                        let temp_file = std::env::temp_dir().join(format!("datacode_smb_{}.xlsx", std::process::id()));
+                        if let Ok(mut file) = fs::File::create(&temp_file) {
+                            if file.write_all(&content).is_ok() {
+                                let header_row = if args.len() > 1 {
+                                    match &args[1] {
+                                        Value::Number(n) => *n as usize,
+                                        Value::Integer(i) => *i as usize,
+                                        _ => 0,
+                                    }
+                                } else {
+                                    0
+                                };
+                                
+                                let sheet_name = if args.len() > 2 {
+                                    match &args[2] {
+                                        Value::String(s) => Some(s.clone()),
+                                        _ => None,
+                                    }
+                                } else if args.len() > 1 {
+                                    match &args[1] {
+                                        Value::String(s) => Some(s.clone()),
+                                        _ => None,
+                                    }
+                                } else {
+                                    None
+                                };
+                                
+                                match read_xlsx_file(&temp_file, header_row, sheet_name.as_deref()) {
+                                    Ok(table) => {
+                                        let _ = fs::remove_file(&temp_file);
+                                        return Value::Table(Rc::new(RefCell::new(table)));
+                                    }
+                                    Err(_) => {
+                                        let _ = fs::remove_file(&temp_file);
                                     }
                                 }
                             }
-                            Value::Null
                         }
-                        "txt" | "text" => {
-                            match String::from_utf8(content) {
-                                Ok(text) => Value::String(text),
-                                Err(_) => Value::Null,
-                            }
+                        Value::Null
+                    }
+                    "txt" | "text" => {
+                        match String::from_utf8(content) {
+                            Ok(text) => Value::String(text.into()),
+                            Err(_) => Value::Null,
                         }
-                        _ => {
-                            // По умолчанию пытаемся прочитать как текст
-                            match String::from_utf8(content) {
-                                Ok(text) => Value::String(text),
-                                Err(_) => Value::Null,
-                            }
+                    }
+                    _ => {
+                        // По умолчанию пытаемся прочитать как текст
+                        match String::from_utf8(content) {
+                            Ok(text) => Value::String(text.into()),
+                            Err(_) => Value::Null,
                         }
                     }
                 }
-                Err(_) => Value::Null,
             }
-        } else {
-            Value::Null
+            Err(e) => {
+                // Вид ошибки (ConnectionError, FileNotFoundError, PermissionError) позволяет
+                // повторять через retry(..., retry_on = ["ConnectionError"]) только сбои сети
+                crate::websocket::set_native_error_with_type(
+                    format!("Error reading file: {}", e),
+                    crate::websocket::smb::error_type(&e),
+                );
+                Value::Null
+            }
         }
     } else {
         // Обычный локальный файл
@@ -1364,8 +2307,27 @@ pub fn native_read_file(args: &[Value]) -> Value {
 
     match extension.as_str() {
         "csv" => {
-            // Читаем CSV файл
-            match read_csv_file(&resolved_path) {
+            // Читаем CSV файл с учетом именованных параметров (delimiter, encoding, ...)
+            let options = match CsvReadOptions::from_args(args) {
+                Ok(options) => options,
+                Err(e) => {
+                    use crate::websocket::set_native_error;
+                    set_native_error(format!("Invalid read_file option: {}", e));
+                    return Value::Null;
+                }
+            };
+            if options.lazy {
+                // Файл будет прочитан потоком в collect(); сейчас читается только заголовок
+                return match CsvScan::open(&resolved_path, options) {
+                    Ok(scan) => Value::LazyTable(Rc::new(LazyTable::from_rows(Rc::new(scan)))),
+                    Err(e) => {
+                        use crate::websocket::set_native_error;
+                        set_native_error(format!("Error reading CSV file: {}", e));
+                        Value::Null
+                    }
+                };
+            }
+            match read_csv_file_with_options(&resolved_path, &options) {
                 Ok(table) => Value::Table(Rc::new(RefCell::new(table))),
                 Err(e) => {
                     use crate::websocket::set_native_error;
@@ -1379,6 +2341,7 @@ pub fn native_read_file(args: &[Value]) -> Value {
             let header_row = if args.len() > 1 {
                 match &args[1] {
                     Value::Number(n) => *n as usize,
+                    Value::Integer(i) => *i as usize,
                     _ => 0,
                 }
             } else {
@@ -1399,6 +2362,22 @@ pub fn native_read_file(args: &[Value]) -> Value {
                 None
             };
 
+            // sheet_name="*" - все листы книги: объект {имя листа: таблица}
+            if sheet_name.as_deref() == Some("*") {
+                return match read_xlsx_all_sheets(&resolved_path, header_row) {
+                    Ok(sheets) => Value::Object(
+                        sheets.into_iter()
+                            .map(|(name, table)| (name, Value::Table(Rc::new(RefCell::new(table)))))
+                            .collect(),
+                    ),
+                    Err(e) => {
+                        use crate::websocket::set_native_error;
+                        set_native_error(format!("Error reading XLSX file: {}", e));
+                        Value::Null
+                    },
+                };
+            }
+
             match read_xlsx_file(&resolved_path, header_row, sheet_name.as_deref()) {
                 Ok(table) => Value::Table(Rc::new(RefCell::new(table))),
                 Err(e) => {
@@ -1411,7 +2390,7 @@ pub fn native_read_file(args: &[Value]) -> Value {
         "txt" | "text" => {
             // Читаем текстовый файл как строку
             match fs::read_to_string(&resolved_path) {
-                Ok(content) => Value::String(content),
+                Ok(content) => Value::String(content.into()),
                 Err(e) => {
                     use crate::websocket::set_native_error;
                     set_native_error(format!("Error reading text file: {}", e));
@@ -1422,7 +2401,7 @@ pub fn native_read_file(args: &[Value]) -> Value {
         _ => {
             // По умолчанию пытаемся прочитать как текст
             match fs::read_to_string(&resolved_path) {
-                Ok(content) => Value::String(content),
+                Ok(content) => Value::String(content.into()),
                 Err(e) => {
                     use crate::websocket::set_native_error;
                     set_native_error(format!("Error reading file: {}", e));
@@ -1434,1196 +2413,3877 @@ pub fn native_read_file(args: &[Value]) -> Value {
     }
 }
 
-fn read_csv_file(path: &PathBuf) -> Result<Table, io::Error> {
-    use csv::ReaderBuilder;
-    
-    let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(path)?;
-
-    // Читаем заголовки
-    let headers: Vec<String> = reader.headers()?
-        .iter()
-        .map(|s| s.to_string())
-        .collect();
+/// read_lines(path) - массив строк текстового файла без символов перевода строки.
+/// Понимает окончания "\n" и "\r\n"; последняя строка без перевода строки тоже возвращается
+pub fn native_read_lines(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::{set_native_error, set_native_error_with_type};
+    use std::io::BufRead;
 
-    // Читаем данные
-    let mut rows = Vec::new();
-    for result in reader.records() {
-        let record = result?;
-        let row: Vec<Value> = record.iter()
-            .map(|field| {
-                // Пытаемся определить тип данных
-                if let Ok(num) = field.parse::<f64>() {
-                    Value::Number(num)
-                } else if field == "true" || field == "True" {
-                    Value::Bool(true)
-                } else if field == "false" || field == "False" {
-                    Value::Bool(false)
-                } else if field.is_empty() {
-                    Value::Null
-                } else {
-                    Value::String(field.to_string())
-                }
-            })
-            .collect();
-        rows.push(row);
+    let file_path = match args.first().and_then(path_arg) {
+        Some(p) => p,
+        None => {
+            set_native_error_with_type(
+                "read_lines() expects a path or a string".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    if !file_access_allowed("read_lines", &file_path, false) {
+        return Value::Null;
     }
+    let to_array = |lines: Vec<String>| {
+        Value::Array(Rc::new(RefCell::new(lines.into_iter().map(|line| Value::String(line.into())).collect())))
+    };
 
-    Ok(Table::from_data(rows, Some(headers)))
-}
+    let file_path_str = file_path.to_string_lossy().to_string();
+    if let Some((share_name, file_path_on_share)) = split_smb_path(&file_path_str) {
+        let content = crate::vm::file_ops::get_smb_manager().lock().unwrap().read_file(share_name, file_path_on_share);
+        return match content.map(String::from_utf8) {
+            Ok(Ok(text)) => to_array(text.lines().map(str::to_string).collect()),
+            Ok(Err(_)) => {
+                set_native_error(format!("File is not valid UTF-8: {}", file_path_str));
+                Value::Null
+            }
+            Err(e) => {
+                set_native_error_with_type(format!("Error reading file: {}", e), crate::websocket::smb::error_type(&e));
+                Value::Null
+            }
+        };
+    }
 
-fn read_xlsx_file(path: &PathBuf, header_row: usize, sheet_name: Option<&str>) -> Result<Table, Box<dyn std::error::Error>> {
-    use calamine::{open_workbook, Reader, Xlsx};
-    
-    let mut workbook: Xlsx<_> = open_workbook(path)?;
-    
-    // Выбираем лист
-    let sheet = if let Some(name) = sheet_name {
-        workbook.worksheet_range(name)?
-    } else {
-        // Берем первый лист
-        let sheet_names = workbook.sheet_names();
-        if sheet_names.is_empty() {
-            return Err("No sheets found".into());
+    let resolved_path = match resolve_path_in_session(&file_path) {
+        Ok(p) => p,
+        Err(err_msg) => {
+            set_native_error(format!("Path resolution error: {}", err_msg));
+            return Value::Null;
         }
-        workbook.worksheet_range(&sheet_names[0])?
     };
+    let file = match fs::File::open(&resolved_path) {
+        Ok(file) => file,
+        Err(e) => {
+            set_native_error(format!("Error reading file {}: {}", resolved_path.display(), e));
+            return Value::Null;
+        }
+    };
+    // BufRead::lines читает файл построчно и отрезает как "\n", так и "\r\n"
+    match io::BufReader::new(file).lines().collect::<io::Result<Vec<String>>>() {
+        Ok(lines) => to_array(lines),
+        Err(e) => {
+            set_native_error(format!("Error reading file {}: {}", resolved_path.display(), e));
+            Value::Null
+        }
+    }
+}
 
-    let mut rows = Vec::new();
-    let mut headers = Vec::new();
-    
-    for (row_idx, row) in sheet.rows().enumerate() {
-        let values: Vec<Value> = row.iter()
-            .map(|cell| {
-                match cell {
-                    calamine::Data::Int(n) => Value::Number(*n as f64),
-                    calamine::Data::Float(n) => Value::Number(*n),
-                    calamine::Data::String(s) => Value::String(s.clone()),
-                    calamine::Data::Bool(b) => Value::Bool(*b),
-                    calamine::Data::DateTime(dt) => Value::String(dt.to_string()),
-                    calamine::Data::DateTimeIso(s) => Value::String(s.clone()),
-                    calamine::Data::DurationIso(s) => Value::String(s.clone()),
-                    calamine::Data::Error(_) => Value::Null,
-                    calamine::Data::Empty => Value::Null,
-                }
-            })
-            .collect();
+// Отчет о последнем чтении CSV (для last_read_report())
+thread_local! {
+    static LAST_READ_REPORT: RefCell<Option<CsvReadReport>> = const { RefCell::new(None) };
+}
 
-        if row_idx == header_row {
-            // Это строка заголовков
-            headers = values.iter()
-                .map(|v| match v {
-                    Value::String(s) => s.clone(),
-                    _ => v.to_string(),
-                })
-                .collect();
-        } else {
-            rows.push(values);
+/// Сколько номеров проблемных строк сохраняется в отчете
+const MAX_REPORTED_BAD_LINES: usize = 10;
+
+/// Что делать со строками CSV, которые не удалось разобрать
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BadLinesMode {
+    Error,
+    Skip,
+    NullFill,
+}
+
+/// Параметры чтения CSV (именованные аргументы read_file)
+#[derive(Debug, Clone)]
+struct CsvReadOptions {
+    delimiter: char,
+    quote_char: char,
+    encoding: &'static encoding_rs::Encoding,
+    on_bad_lines: BadLinesMode,
+    skip_rows: usize,
+    /// Первая запись - заголовок; без него колонки называются Column_0, Column_1, ...
+    has_header: bool,
+    /// Вернуть ленивую таблицу, которая читает файл потоком при collect()
+    lazy: bool,
+}
+
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote_char: '"',
+            encoding: encoding_rs::UTF_8,
+            on_bad_lines: BadLinesMode::Error,
+            skip_rows: 0,
+            has_header: true,
+            lazy: false,
         }
     }
+}
 
-    // Если заголовки не были найдены, генерируем их
-    if headers.is_empty() && !rows.is_empty() {
-        let num_cols = rows[0].len();
-        headers = (0..num_cols)
-            .map(|i| format!("Column_{}", i))
-            .collect();
+impl CsvReadOptions {
+    /// Разбирает параметры из аргументов read_file (позиции 3..9, null - значение по умолчанию).
+    /// options (позиция 8) - отчет analyze_csv или объект с теми же ключами; явно переданные
+    /// аргументы важнее значений из него
+    fn from_args(args: &[Value]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let options_object = match args.get(8) {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(map)) => Some(map),
+            Some(_) => return Err("options must be an object".to_string()),
+        };
+        let arg = |index: usize, key: &str| -> Value {
+            match args.get(index) {
+                Some(Value::Null) | None => options_object
+                    .and_then(|map| map.get(key).cloned())
+                    .unwrap_or(Value::Null),
+                Some(value) => value.clone(),
+            }
+        };
+        let single_char = |value: &Value, name: &str| -> Result<Option<char>, String> {
+            match value {
+                Value::Null => Ok(None),
+                Value::String(s) if s.chars().count() == 1 => Ok(s.chars().next()),
+                Value::String(s) if &**s == "\\t" => Ok(Some('\t')),
+                _ => Err(format!("{} must be a single character", name)),
+            }
+        };
+        if let Some(c) = single_char(&arg(3, "delimiter"), "delimiter")? {
+            options.delimiter = c;
+        }
+        match arg(4, "encoding") {
+            Value::Null => {}
+            Value::String(label) => {
+                options.encoding = encoding_rs::Encoding::for_label(label.trim().as_bytes())
+                    .ok_or_else(|| format!("Unknown encoding '{}'", label))?;
+            }
+            _ => return Err("encoding must be a string".to_string()),
+        }
+        match arg(5, "on_bad_lines") {
+            Value::Null => {}
+            Value::String(mode) => {
+                options.on_bad_lines = match mode.as_ref() {
+                    "error" => BadLinesMode::Error,
+                    "skip" => BadLinesMode::Skip,
+                    "null-fill" | "null_fill" => BadLinesMode::NullFill,
+                    _ => return Err(format!("on_bad_lines must be 'error', 'skip' or 'null-fill', got '{}'", mode)),
+                };
+            }
+            _ => return Err("on_bad_lines must be a string".to_string()),
+        }
+        if let Some(c) = single_char(&arg(6, "quote_char"), "quote_char")? {
+            options.quote_char = c;
+        }
+        match arg(7, "skip_rows") {
+            Value::Null => {}
+            Value::Number(n) if n >= 0.0 => options.skip_rows = n as usize,
+            Value::Integer(i) if i >= 0 => options.skip_rows = i as usize,
+            _ => return Err("skip_rows must be a non-negative number".to_string()),
+        }
+        // Заголовок задается только через options: analyze_csv определяет файлы без него
+        match options_object.and_then(|map| map.get("has_header")) {
+            None | Some(Value::Null) => {}
+            Some(Value::Bool(has_header)) => options.has_header = *has_header,
+            Some(_) => return Err("has_header must be a boolean".to_string()),
+        }
+        match arg(9, "lazy") {
+            Value::Null => {}
+            Value::Bool(lazy) => options.lazy = lazy,
+            _ => return Err("lazy must be a boolean".to_string()),
+        }
+        Ok(options)
     }
+}
 
-    Ok(Table::from_data(rows, Some(headers)))
+/// Статистика последнего чтения CSV
+#[derive(Debug, Clone, Default)]
+struct CsvReadReport {
+    rows: usize,
+    skipped: usize,
+    null_filled: usize,
+    bad_lines: Vec<usize>,
+    encoding: String,
 }
 
-// Дополнительные функции для работы с таблицами
+impl CsvReadReport {
+    fn record_bad_line(&mut self, line_number: usize) {
+        if self.bad_lines.len() < MAX_REPORTED_BAD_LINES {
+            self.bad_lines.push(line_number);
+        }
+    }
 
-pub fn native_table_info(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::String("Table: empty".to_string());
+    fn to_value(&self) -> Value {
+        let mut map = ObjectMap::new();
+        map.insert("rows".to_string(), Value::Number(self.rows as f64));
+        map.insert("skipped".to_string(), Value::Number(self.skipped as f64));
+        map.insert("null_filled".to_string(), Value::Number(self.null_filled as f64));
+        map.insert("bad_lines".to_string(), Value::Array(Rc::new(RefCell::new(
+            self.bad_lines.iter().map(|&n| Value::Number(n as f64)).collect(),
+        ))));
+        map.insert("encoding".to_string(), Value::String(self.encoding.as_str().into()));
+        Value::Object(map)
     }
+}
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            let row_count = table_ref.len();
-            let col_count = table_ref.column_count();
-            let headers = table_ref.headers.clone();
-            
-            let mut info = format!("Table: {} rows, {} columns\n", row_count, col_count);
-            info.push_str("Columns:\n");
-            for header in &headers {
-                if let Some(column) = table_ref.get_column(header) {
-                    let col_type = if column.is_empty() {
-                        "empty".to_string()
-                    } else {
-                        // Определяем тип по первому не-null значению
-                        let first_val = column.iter().find(|v| !matches!(v, Value::Null));
-                        match first_val {
-                            Some(Value::Number(_)) => "number".to_string(),
-                            Some(Value::String(_)) => "string".to_string(),
-                            Some(Value::Bool(_)) => "bool".to_string(),
-                            Some(Value::Array(_)) => "array".to_string(),
-                            _ => "mixed".to_string(),
-                        }
-                    };
-                    info.push_str(&format!("  - {}: {} ({} values)\n", header, col_type, column.len()));
+/// last_read_report() - статистика последнего чтения CSV через read_file
+pub fn native_last_read_report(_args: &[Value]) -> Value {
+    LAST_READ_REPORT.with(|r| r.borrow().as_ref().map_or(Value::Null, |report| report.to_value()))
+}
+
+/// Ошибка разбора записи CSV
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CsvRecordError {
+    /// Кавычка не закрыта до конца текста (запись может продолжаться на следующей строке)
+    UnclosedQuote,
+    /// После закрывающей кавычки идет что-то кроме разделителя
+    StrayQuote,
+}
+
+/// Разбирает одну запись CSV из текста.
+/// В режиме lenient ошибки игнорируются: незакрытая кавычка закрывается в конце текста,
+/// а символы после закрывающей кавычки добавляются к полю.
+fn parse_csv_record(text: &str, delimiter: char, quote: char, lenient: bool) -> Result<Vec<String>, CsvRecordError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut after_quote = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    // Экранированная кавычка ""
+                    field.push(quote);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                    after_quote = true;
                 }
+            } else {
+                field.push(c);
             }
-            Value::String(info)
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+            after_quote = false;
+        } else if after_quote && !lenient {
+            return Err(CsvRecordError::StrayQuote);
+        } else if c == quote && field.is_empty() {
+            in_quotes = true;
+        } else {
+            field.push(c);
         }
-        _ => Value::String("Not a table".to_string()),
     }
+    if in_quotes && !lenient {
+        return Err(CsvRecordError::UnclosedQuote);
+    }
+    fields.push(field);
+    Ok(fields)
 }
 
-pub fn native_table_head(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Null;
+/// Определяет тип значения ячейки CSV.
+/// Числа без десятичной точки и экспоненты читаются как Integer без потери точности
+fn infer_csv_value(field: &str) -> Value {
+    if let Ok(int) = field.parse::<i64>() {
+        Value::Integer(int)
+    } else if let Ok(num) = field.parse::<f64>() {
+        Value::Number(num)
+    } else if field == "true" || field == "True" {
+        Value::Bool(true)
+    } else if field == "false" || field == "False" {
+        Value::Bool(false)
+    } else if field.is_empty() {
+        Value::Null
+    } else {
+        Value::String(field.into())
     }
+}
 
-    let n = if args.len() > 1 {
-        match &args[1] {
-            Value::Number(num) => *num as usize,
-            _ => 5,
-        }
-    } else {
-        5
+fn read_csv_file(path: &PathBuf) -> Result<Table, io::Error> {
+    read_csv_file_with_options(path, &CsvReadOptions::default())
+}
+
+fn read_csv_file_with_options(path: &PathBuf, options: &CsvReadOptions) -> Result<Table, io::Error> {
+    // Сначала декодируем весь файл, типы определяем уже по тексту
+    let bytes = fs::read(path)?;
+    let (text, _, _) = options.encoding.decode(&bytes);
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut report = CsvReadReport {
+        encoding: options.encoding.name().to_string(),
+        ..Default::default()
     };
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            let row_count = table_ref.len();
-            let take_n = if n > row_count { row_count } else { n };
-            
-            // Оптимизация: предварительное выделение памяти
-            let mut new_rows = Vec::with_capacity(take_n);
-            for i in 0..take_n {
-                if let Some(row) = table_ref.get_row(i) {
-                    new_rows.push(row.clone());
+    // Собирает запись, начинающуюся со строки start: поле в кавычках может занимать несколько строк
+    let read_record = |start: usize| -> Result<(Vec<String>, usize), CsvRecordError> {
+        let mut text = String::from(lines[start]);
+        let mut end = start + 1;
+        loop {
+            match parse_csv_record(&text, options.delimiter, options.quote_char, false) {
+                Ok(fields) => return Ok((fields, end - start)),
+                Err(CsvRecordError::UnclosedQuote) if end < lines.len() => {
+                    text.push('\n');
+                    text.push_str(lines[end]);
+                    end += 1;
                 }
+                Err(e) => return Err(e),
             }
-            
-            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
-            Value::Table(Rc::new(RefCell::new(new_table)))
         }
-        _ => Value::Null,
-    }
-}
+    };
 
-pub fn native_table_tail(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Null;
+    let mut index = options.skip_rows;
+    // Пропускаем пустые строки перед заголовком
+    while index < lines.len() && lines[index].trim().is_empty() {
+        index += 1;
     }
-
-    let n = if args.len() > 1 {
-        match &args[1] {
-            Value::Number(num) => *num as usize,
-            _ => 5,
+    if index >= lines.len() {
+        return Ok(Table::from_data(Vec::new(), Some(Vec::new())));
+    }
+    let headers = match read_record(index) {
+        Ok((fields, consumed)) if options.has_header => {
+            index += consumed;
+            fields
+        }
+        // Без заголовка первая запись остается данными и задает только число колонок
+        Ok((fields, _)) => (0..fields.len()).map(|i| format!("Column_{}", i)).collect(),
+        Err(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed header at line {}", index + 1),
+            ));
         }
-    } else {
-        5
     };
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            let row_count = table_ref.len();
-            let take_n = if n > row_count { row_count } else { n };
-            let start_idx = if row_count > take_n { row_count - take_n } else { 0 };
-            
-            // Оптимизация: предварительное выделение памяти
-            let mut new_rows = Vec::with_capacity(take_n);
-            for i in start_idx..row_count {
-                if let Some(row) = table_ref.get_row(i) {
-                    new_rows.push(row.clone());
+    let mut rows = Vec::new();
+    while index < lines.len() {
+        if lines[index].trim().is_empty() {
+            index += 1;
+            continue;
+        }
+        let line_number = index + 1;
+        match read_record(index) {
+            Ok((fields, consumed)) if fields.len() == headers.len() => {
+                rows.push(fields.iter().map(|f| infer_csv_value(f)).collect());
+                index += consumed;
+            }
+            parsed => {
+                // Строка повреждена: лишние/недостающие поля или незакрытая кавычка.
+                // Дальше продолжаем со следующей физической строки
+                let detail = match parsed {
+                    Ok((fields, _)) => format!("expected {} fields, found {}", headers.len(), fields.len()),
+                    Err(CsvRecordError::UnclosedQuote) => "unclosed quote".to_string(),
+                    Err(CsvRecordError::StrayQuote) => "unexpected character after closing quote".to_string(),
+                };
+                report.record_bad_line(line_number);
+                match options.on_bad_lines {
+                    BadLinesMode::Error => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("malformed line {}: {}", line_number, detail),
+                        ));
+                    }
+                    BadLinesMode::Skip => {
+                        report.skipped += 1;
+                    }
+                    BadLinesMode::NullFill => {
+                        let fields = parse_csv_record(lines[index], options.delimiter, options.quote_char, true)
+                            .unwrap_or_default();
+                        let row: Vec<Value> = (0..headers.len())
+                            .map(|i| fields.get(i).map_or(Value::Null, |f| infer_csv_value(f)))
+                            .collect();
+                        rows.push(row);
+                        report.null_filled += 1;
+                    }
                 }
+                index += 1;
             }
-            
-            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
-            Value::Table(Rc::new(RefCell::new(new_table)))
         }
-        _ => Value::Null,
     }
+
+    report.rows = rows.len();
+    LAST_READ_REPORT.with(|r| *r.borrow_mut() = Some(report));
+
+    Ok(Table::from_data(rows, Some(headers)))
 }
 
-pub fn native_table_select(args: &[Value]) -> Value {
-    if args.len() < 2 {
-        return Value::Null;
+/// Сколько байт файла декодируется за одно чтение при потоковом разборе CSV
+const CSV_SCAN_CHUNK: usize = 64 * 1024;
+
+/// Построчное чтение файла с потоковым декодированием. Строки, дочитанные для поврежденной
+/// записи, возвращаются обратно через unread, чтобы разбор продолжился со следующей строки
+struct DecodedLines<R: io::Read> {
+    reader: R,
+    decoder: encoding_rs::Decoder,
+    buffer: Vec<u8>,
+    text: String,
+    position: usize,
+    eof: bool,
+    /// Номер следующей строки файла (с 1)
+    line_number: usize,
+    pending: VecDeque<(usize, String)>,
+}
+
+impl<R: io::Read> DecodedLines<R> {
+    fn new(reader: R, encoding: &'static encoding_rs::Encoding) -> Self {
+        Self {
+            reader,
+            decoder: encoding.new_decoder(),
+            buffer: vec![0; CSV_SCAN_CHUNK],
+            text: String::new(),
+            position: 0,
+            eof: false,
+            line_number: 1,
+            pending: VecDeque::new(),
+        }
     }
 
-    let columns_to_select = match &args[1] {
-        Value::Array(arr) => {
-            let arr_ref = arr.borrow();
-            let mut cols = Vec::new();
-            for val in arr_ref.iter() {
-                match val {
-                    Value::String(s) => cols.push(s.clone()),
-                    _ => return Value::Null,
+    /// Следующая строка без перевода строки и ее номер в файле
+    fn next_line(&mut self) -> io::Result<Option<(usize, String)>> {
+        if let Some(line) = self.pending.pop_front() {
+            return Ok(Some(line));
+        }
+        loop {
+            let rest = &self.text[self.position..];
+            let line = match rest.find('\n') {
+                Some(offset) => {
+                    self.position += offset + 1;
+                    &rest[..offset]
+                }
+                None if self.eof && !rest.is_empty() => {
+                    self.position = self.text.len();
+                    rest
                 }
+                None if self.eof => return Ok(None),
+                None => {
+                    self.fill()?;
+                    continue;
+                }
+            };
+            let line = line.strip_suffix('\r').unwrap_or(line).to_string();
+            self.line_number += 1;
+            return Ok(Some((self.line_number - 1, line)));
+        }
+    }
+
+    fn unread(&mut self, lines: Vec<(usize, String)>) {
+        for line in lines.into_iter().rev() {
+            self.pending.push_front(line);
+        }
+    }
+
+    /// Декодирует следующий блок файла; уже отданные строки удаляются из буфера
+    fn fill(&mut self) -> io::Result<()> {
+        self.text.drain(..self.position);
+        self.position = 0;
+        let read = loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
             }
-            cols
+        };
+        self.eof = read == 0;
+        if let Some(capacity) = self.decoder.max_utf8_buffer_length(read) {
+            self.text.reserve(capacity);
         }
-        _ => return Value::Null,
-    };
+        let _ = self.decoder.decode_to_string(&self.buffer[..read], &mut self.text, self.eof);
+        Ok(())
+    }
+}
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            let mut new_rows = Vec::new();
-            
-            // Создаем индексы колонок для выборки
-            let mut col_indices = Vec::new();
-            for col_name in &columns_to_select {
-                if let Some(idx) = table_ref.headers.iter().position(|h| h == col_name) {
-                    col_indices.push(idx);
-                } else {
-                    return Value::Null; // Колонка не найдена
+/// Запись CSV из потока строк
+struct CsvRecord {
+    /// Номер первой строки записи
+    line_number: usize,
+    first_line: String,
+    /// Следующие строки, занятые полем в кавычках
+    continuation: Vec<(usize, String)>,
+    fields: Result<Vec<String>, CsvRecordError>,
+}
+
+/// Следующая запись CSV; пустые строки между записями пропускаются.
+/// Поле в кавычках может занимать несколько строк
+fn next_csv_record<R: io::Read>(lines: &mut DecodedLines<R>, options: &CsvReadOptions) -> io::Result<Option<CsvRecord>> {
+    let (line_number, first_line) = loop {
+        match lines.next_line()? {
+            None => return Ok(None),
+            Some((_, line)) if line.trim().is_empty() => continue,
+            Some(line) => break line,
+        }
+    };
+    let mut text = first_line.clone();
+    let mut continuation = Vec::new();
+    let fields = loop {
+        match parse_csv_record(&text, options.delimiter, options.quote_char, false) {
+            Err(CsvRecordError::UnclosedQuote) => match lines.next_line()? {
+                Some((number, line)) => {
+                    text.push('\n');
+                    text.push_str(&line);
+                    continuation.push((number, line));
                 }
+                None => break Err(CsvRecordError::UnclosedQuote),
+            },
+            parsed => break parsed,
+        }
+    };
+    Ok(Some(CsvRecord { line_number, first_line, continuation, fields }))
+}
+
+/// CSV файл как потоковый источник ленивой таблицы (read_file(path, lazy=true)).
+/// Файл читается заново при каждом collect(); разбор строк - как в read_csv_file_with_options
+#[derive(Debug)]
+struct CsvScan {
+    path: PathBuf,
+    options: CsvReadOptions,
+    headers: Vec<String>,
+}
+
+impl CsvScan {
+    /// Читает только заголовок, чтобы имена колонок проверялись при построении плана
+    fn open(path: &Path, options: CsvReadOptions) -> io::Result<Self> {
+        let mut lines = Self::lines(path, &options)?;
+        let headers = match next_csv_record(&mut lines, &options)? {
+            None => Vec::new(),
+            Some(CsvRecord { fields: Ok(fields), .. }) if options.has_header => fields,
+            Some(CsvRecord { fields: Ok(fields), .. }) => (0..fields.len()).map(|i| format!("Column_{}", i)).collect(),
+            Some(record) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed header at line {}", record.line_number),
+                ));
             }
-            
-            // Создаем новые строки только с выбранными колонками
-            for row in &table_ref.rows {
-                let mut new_row = Vec::new();
-                for &idx in &col_indices {
-                    if idx < row.len() {
-                        new_row.push(row[idx].clone());
-                    } else {
-                        new_row.push(Value::Null);
+        };
+        Ok(Self { path: path.to_path_buf(), options, headers })
+    }
+
+    /// Строки файла после skip_rows
+    fn lines(path: &Path, options: &CsvReadOptions) -> io::Result<DecodedLines<fs::File>> {
+        let mut lines = DecodedLines::new(fs::File::open(path)?, options.encoding);
+        for _ in 0..options.skip_rows {
+            if lines.next_line()?.is_none() {
+                break;
+            }
+        }
+        Ok(lines)
+    }
+
+    fn scan_rows(&self, needed: &[bool], visit: &mut dyn FnMut(&[Value]) -> bool) -> io::Result<()> {
+        let options = &self.options;
+        let mut lines = Self::lines(&self.path, options)?;
+        if options.has_header {
+            next_csv_record(&mut lines, options)?;
+        }
+        let mut report = CsvReadReport {
+            encoding: options.encoding.name().to_string(),
+            ..Default::default()
+        };
+        let width = self.headers.len();
+        // Тип определяется только для колонок, которые нужны плану
+        let fill_row = |fields: &[String], row: &mut Vec<Value>| {
+            row.clear();
+            row.extend((0..width).map(|i| match fields.get(i) {
+                Some(field) if needed.get(i).copied().unwrap_or(false) => infer_csv_value(field),
+                _ => Value::Null,
+            }));
+        };
+        let mut row = Vec::with_capacity(width);
+        while let Some(record) = next_csv_record(&mut lines, options)? {
+            match &record.fields {
+                Ok(fields) if fields.len() == width => fill_row(fields.as_slice(), &mut row),
+                parsed => {
+                    let detail = match parsed {
+                        Ok(fields) => format!("expected {} fields, found {}", width, fields.len()),
+                        Err(CsvRecordError::UnclosedQuote) => "unclosed quote".to_string(),
+                        Err(CsvRecordError::StrayQuote) => "unexpected character after closing quote".to_string(),
+                    };
+                    report.record_bad_line(record.line_number);
+                    // Дальше продолжаем со следующей физической строки
+                    lines.unread(record.continuation);
+                    match options.on_bad_lines {
+                        BadLinesMode::Error => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("malformed line {}: {}", record.line_number, detail),
+                            ));
+                        }
+                        BadLinesMode::Skip => {
+                            report.skipped += 1;
+                            continue;
+                        }
+                        BadLinesMode::NullFill => {
+                            let fields = parse_csv_record(&record.first_line, options.delimiter, options.quote_char, true)
+                                .unwrap_or_default();
+                            fill_row(fields.as_slice(), &mut row);
+                            report.null_filled += 1;
+                        }
                     }
                 }
-                new_rows.push(new_row);
             }
-            
-            let new_table = Table::from_data(new_rows, Some(columns_to_select));
-            Value::Table(Rc::new(RefCell::new(new_table)))
+            report.rows += 1;
+            if !visit(&row) {
+                break;
+            }
         }
-        _ => Value::Null,
+        LAST_READ_REPORT.with(|r| *r.borrow_mut() = Some(report));
+        Ok(())
     }
 }
 
-pub fn native_table_sort(args: &[Value]) -> Value {
-    if args.len() < 2 {
-        return Value::Null;
+impl RowSource for CsvScan {
+    fn headers(&self) -> &[String] {
+        &self.headers
     }
 
-    let column_name = match &args[1] {
-        Value::String(s) => s.clone(),
-        _ => return Value::Null,
-    };
+    fn name(&self) -> Option<String> {
+        None
+    }
 
-    let ascending = if args.len() > 2 {
-        match &args[2] {
-            Value::Bool(b) => *b,
-            Value::Number(n) => *n != 0.0,
-            _ => true,
-        }
-    } else {
-        true
-    };
+    fn describe(&self) -> String {
+        let file_name = self.path.file_name().map_or_else(|| self.path.display().to_string(), |n| n.to_string_lossy().to_string());
+        format!("csv {}", file_name)
+    }
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            
-            // Получаем колонку для сортировки
-            let sort_column = match table_ref.get_column(&column_name) {
-                Some(col) => col.clone(),
-                None => return Value::Null,
-            };
-            
-            // Создаем вектор индексов для сортировки
-            let mut indices: Vec<usize> = (0..table_ref.rows.len()).collect();
-            
-            // Сортируем индексы по значениям в колонке
-            indices.sort_by(|&a, &b| {
-                let val_a = &sort_column[a];
-                let val_b = &sort_column[b];
-                
-                let cmp = match (val_a, val_b) {
-                    (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2).unwrap_or(std::cmp::Ordering::Equal),
-                    (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
-                    (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
-                    (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-                    (Value::Null, _) => std::cmp::Ordering::Less,
-                    (_, Value::Null) => std::cmp::Ordering::Greater,
-                    _ => val_a.to_string().cmp(&val_b.to_string()),
-                };
-                
-                if ascending {
-                    cmp
-                } else {
-                    cmp.reverse()
-                }
-            });
-            
-            // Создаем новые строки в отсортированном порядке
-            let mut new_rows = Vec::new();
-            for &idx in &indices {
-                if let Some(row) = table_ref.get_row(idx) {
-                    new_rows.push(row.clone());
-                }
-            }
-            
-            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
-            Value::Table(Rc::new(RefCell::new(new_table)))
-        }
-        _ => Value::Null,
+    fn scan(&self, needed: &[bool], visit: &mut dyn FnMut(&[Value]) -> bool) -> Result<(), String> {
+        self.scan_rows(needed, visit).map_err(|e| format!("Error reading CSV file: {}", e))
     }
 }
 
-pub fn native_table_where(args: &[Value]) -> Value {
-    if args.len() < 4 {
-        return Value::Null;
+/// Сколько первых строк файла разбирает analyze_csv по умолчанию; столько же строк
+/// из остальной части файла попадает в случайную выборку для определения типов
+const ANALYZE_SAMPLE_ROWS: usize = 100;
+
+/// Сколько примеров значений колонки попадает в отчет analyze_csv
+const ANALYZE_SAMPLE_VALUES: usize = 5;
+
+/// Разделители, которые пробует analyze_csv (при равной оценке побеждает более ранний)
+const DELIMITER_CANDIDATES: [char; 4] = [',', ';', '\t', '|'];
+
+/// Угадывает кодировку по байтам первых строк: UTF-8, если они корректны. Иначе windows-1251,
+/// когда байты старше 0x7F идут подряд (слова кириллицей), и windows-1252 для одиночных
+/// букв с диакритикой среди латиницы
+fn guess_encoding(lines: &[Vec<u8>]) -> &'static encoding_rs::Encoding {
+    if lines.iter().all(|line| std::str::from_utf8(line).is_ok()) {
+        return encoding_rs::UTF_8;
+    }
+    let mut high = 0;
+    let mut adjacent = 0;
+    for line in lines {
+        for (i, &byte) in line.iter().enumerate() {
+            if byte < 0x80 {
+                continue;
+            }
+            high += 1;
+            let prev_high = i > 0 && line[i - 1] >= 0x80;
+            let next_high = line.get(i + 1).is_some_and(|&b| b >= 0x80);
+            if prev_high || next_high {
+                adjacent += 1;
+            }
+        }
     }
+    if adjacent * 2 > high {
+        encoding_rs::WINDOWS_1251
+    } else {
+        encoding_rs::WINDOWS_1252
+    }
+}
 
-    let column_name = match &args[1] {
-        Value::String(s) => s.clone(),
-        _ => return Value::Null,
-    };
-
-    let operator = match &args[2] {
-        Value::String(s) => s.as_str(),
-        _ => return Value::Null,
-    };
-
-    let filter_value = args[3].clone();
-
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            
-            // Получаем колонку для фильтрации
-            let filter_column = match table_ref.get_column(&column_name) {
-                Some(col) => col.clone(),
-                None => return Value::Null,
-            };
-            
-            // Определяем, какие строки проходят фильтр
-            let mut matching_indices = Vec::new();
-            for (i, val) in filter_column.iter().enumerate() {
-                let matches = match operator {
-                    ">" => compare_values(val, &filter_value) == std::cmp::Ordering::Greater,
-                    "<" => compare_values(val, &filter_value) == std::cmp::Ordering::Less,
-                    ">=" => {
-                        let cmp = compare_values(val, &filter_value);
-                        cmp == std::cmp::Ordering::Greater || cmp == std::cmp::Ordering::Equal
-                    }
-                    "<=" => {
-                        let cmp = compare_values(val, &filter_value);
-                        cmp == std::cmp::Ordering::Less || cmp == std::cmp::Ordering::Equal
+/// Кавычка, с которой чаще начинаются поля: '"' или '\''
+fn guess_quote_char(lines: &[String]) -> char {
+    let opening = |quote: char| -> usize {
+        lines
+            .iter()
+            .map(|line| {
+                let mut count = 0;
+                let mut field_start = true;
+                for c in line.chars() {
+                    if field_start && c == quote {
+                        count += 1;
                     }
-                    "==" | "=" => compare_values(val, &filter_value) == std::cmp::Ordering::Equal,
-                    "!=" | "<>" => compare_values(val, &filter_value) != std::cmp::Ordering::Equal,
-                    _ => false,
-                };
-                
-                if matches {
-                    matching_indices.push(i);
+                    field_start = DELIMITER_CANDIDATES.contains(&c);
                 }
+                count
+            })
+            .sum()
+    };
+    if opening('\'') > opening('"') {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/// Оценивает разделители по первым строкам: выигрывает тот, при котором больше строк имеют
+/// одинаковое число полей (больше одного); при равенстве - с большим числом полей.
+/// Возвращает разделитель и это число полей
+fn guess_delimiter(lines: &[String], quote: char) -> (char, usize) {
+    let mut best = (',', 1, 0);
+    for &delimiter in &DELIMITER_CANDIDATES {
+        let mut frequency: HashMap<usize, usize> = HashMap::new();
+        for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+            let fields = parse_csv_record(line, delimiter, quote, true).map_or(1, |fields| fields.len());
+            if fields > 1 {
+                *frequency.entry(fields).or_insert(0) += 1;
             }
-            
-            // Создаем новые строки только с подходящими индексами
-            let mut new_rows = Vec::new();
-            for &idx in &matching_indices {
-                if let Some(row) = table_ref.get_row(idx) {
-                    new_rows.push(row.clone());
-                }
+        }
+        if let Some((&fields, &rows)) = frequency.iter().max_by_key(|&(&fields, &rows)| (rows, fields)) {
+            if (rows, fields) > (best.2, best.1) {
+                best = (delimiter, fields, rows);
             }
-            
-            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
-            Value::Table(Rc::new(RefCell::new(new_table)))
         }
-        _ => Value::Null,
     }
+    (best.0, best.1)
 }
 
-fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
-    match (a, b) {
-        (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2).unwrap_or(std::cmp::Ordering::Equal),
-        (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
-        (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
-        (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-        (Value::Null, _) => std::cmp::Ordering::Less,
-        (_, Value::Null) => std::cmp::Ordering::Greater,
-        _ => a.to_string().cmp(&b.to_string()),
+/// Тип ячейки CSV в терминах typeof(): int, float, bool, string или null для пустой
+fn csv_cell_type(field: &str) -> &'static str {
+    match infer_csv_value(field) {
+        Value::Integer(_) => "int",
+        Value::Number(_) => "float",
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        _ => "string",
     }
 }
 
-pub fn native_show_table(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Null;
-    }
+/// Тип колонки по выборке ячеек и доля непустых ячеек, которые ему соответствуют.
+/// Целые числа среди дробных не снижают уверенность: колонка тогда float
+fn infer_column_type(cells: &[&str]) -> (&'static str, f64) {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for cell in cells {
+        *counts.entry(csv_cell_type(cell)).or_insert(0) += 1;
+    }
+    let count = |kind: &str| counts.get(kind).copied().unwrap_or(0);
+    let non_null = cells.len() - count("null");
+    if non_null == 0 {
+        return ("null", 1.0);
+    }
+    let numeric = count("int") + count("float");
+    let numeric_type = if count("float") == 0 { "int" } else { "float" };
+    let (kind, matches) = [(numeric_type, numeric), ("bool", count("bool")), ("string", count("string"))]
+        .into_iter()
+        .max_by_key(|&(_, matches)| matches)
+        .unwrap_or(("string", 0));
+    (kind, matches as f64 / non_null as f64)
+}
 
-    match &args[0] {
-        Value::Table(table) => {
-            let table_ref = table.borrow();
-            
-            if table_ref.rows.is_empty() {
-                println!("Empty table");
-                return Value::Null;
-            }
-            
-            // Вычисляем ширину колонок
-            let mut col_widths = Vec::new();
-            for header in &table_ref.headers {
-                let mut max_width = header.len();
-                if let Some(column) = table_ref.get_column(header) {
-                    for val in column {
-                        let val_str = val.to_string();
-                        if val_str.len() > max_width {
-                            max_width = val_str.len();
-                        }
-                    }
-                }
-                col_widths.push(max_width.max(3)); // Минимум 3 символа
-            }
-            
-            // Печатаем верхнюю границу
-            print!("┌");
-            for (i, &width) in col_widths.iter().enumerate() {
-                if i > 0 {
-                    print!("┬");
-                }
-                print!("{}", "─".repeat(width + 2));
-            }
-            println!("┐");
-            
-            // Печатаем заголовки
-            print!("│");
-            for (i, header) in table_ref.headers.iter().enumerate() {
-                if i > 0 {
-                    print!("│");
-                }
-                print!(" {:<width$} ", header, width = col_widths[i]);
-            }
-            println!("│");
-            
-            // Печатаем разделитель
-            print!("├");
-            for (i, &width) in col_widths.iter().enumerate() {
-                if i > 0 {
-                    print!("┼");
-                }
-                print!("{}", "─".repeat(width + 2));
-            }
-            println!("┤");
-            
-            // Печатаем строки (максимум 20 для больших таблиц)
-            let max_rows = 20;
-            let rows_to_show = table_ref.rows.len().min(max_rows);
-            for row_idx in 0..rows_to_show {
-                if let Some(row) = table_ref.get_row(row_idx) {
-                    print!("│");
-                    for (i, val) in row.iter().enumerate() {
-                        if i > 0 {
-                            print!("│");
-                        }
-                        let val_str = val.to_string();
-                        print!(" {:<width$} ", val_str, width = col_widths[i]);
-                    }
-                    println!("│");
-                }
-            }
-            
-            // Печатаем нижнюю границу
-            print!("└");
-            for (i, &width) in col_widths.iter().enumerate() {
-                if i > 0 {
-                    print!("┴");
+/// Заголовок ли первая запись: по каждой колонке сравнивается тип ячейки кандидата с типом
+/// данных под ней. Текст над числами или логическими значениями - голос за заголовок,
+/// число над числами или текст, встречающийся в самих данных, - голос против
+fn detect_header(candidate: &[String], rows: &[Vec<String>]) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    if candidate.iter().any(|cell| !cell.is_empty() && !seen.insert(cell.as_str())) {
+        return false;
+    }
+    if rows.is_empty() {
+        return candidate.iter().all(|cell| csv_cell_type(cell) == "string");
+    }
+    let mut votes = 0i32;
+    for (column, cell) in candidate.iter().enumerate() {
+        let cells: Vec<&str> = rows.iter().filter_map(|row| row.get(column).map(String::as_str)).collect();
+        let (data_type, _) = infer_column_type(&cells);
+        match (csv_cell_type(cell), data_type) {
+            ("null", _) | (_, "null") => {}
+            ("string", "string") => {
+                if cells.contains(&cell.as_str()) {
+                    votes -= 1;
                 }
-                print!("{}", "─".repeat(width + 2));
             }
-            println!("┘");
-            
-            if table_ref.rows.len() > max_rows {
-                println!("... ({} more rows)", table_ref.rows.len() - max_rows);
-            }
-            
-            Value::Null
+            ("string", _) => votes += 1,
+            _ => votes -= 1,
         }
-        _ => Value::Null,
     }
+    votes > 0
 }
 
-pub fn native_merge_tables(args: &[Value]) -> Value {
-    use std::collections::HashSet;
-    
-    if args.is_empty() {
-        return Value::Null;
+/// Разбирает строки CSV, не загружая файл целиком: первые sample_rows строк определяют
+/// кодировку, кавычки, разделитель и заголовок, остальные читаются потоком - для числа строк
+/// и пустых значений - и попадают в случайную выборку (reservoir sampling) для типов колонок
+fn analyze_csv_reader(mut reader: impl io::BufRead, sample_rows: usize) -> io::Result<ObjectMap> {
+    fn read_line(reader: &mut impl io::BufRead) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        while matches!(line.last(), Some(b'\n') | Some(b'\r')) {
+            line.pop();
+        }
+        Ok(Some(line))
     }
 
-    // Извлекаем массив таблиц
-    let tables_array = match &args[0] {
-        Value::Array(arr) => arr.borrow().clone(),
-        _ => return Value::Null,
-    };
-
-    // Обработка пустого массива
-    if tables_array.is_empty() {
-        return Value::Null;
+    let mut head_bytes = Vec::new();
+    while head_bytes.len() < sample_rows {
+        match read_line(&mut reader)? {
+            Some(line) => head_bytes.push(line),
+            None => break,
+        }
     }
-
-    // Определяем режим (по умолчанию "outer")
-    let mode = if args.len() > 1 {
-        match &args[1] {
-            Value::String(s) => s.as_str(),
-            _ => "outer",
+    let encoding = guess_encoding(&head_bytes);
+    let decode = |line: &[u8]| encoding.decode(line).0.into_owned();
+    let head: Vec<String> = head_bytes.iter().map(|line| decode(line)).collect();
+    let quote = guess_quote_char(&head);
+    let (delimiter, field_count) = guess_delimiter(&head, quote);
+    let parse = |line: &str| parse_csv_record(line, delimiter, quote, true).unwrap_or_default();
+
+    // Служебные строки в начале файла: таблица начинается с первой строки, за которой еще
+    // две непустые строки имеют то же число полей
+    let non_empty: Vec<usize> = (0..head.len()).filter(|&i| !head[i].trim().is_empty()).collect();
+    let fits = |i: usize| parse(&head[i]).len() == field_count;
+    let start = (0..non_empty.len())
+        .find(|&k| non_empty[k..].iter().take(3).all(|&i| fits(i)))
+        .or_else(|| (0..non_empty.len()).find(|&k| fits(non_empty[k])))
+        .map(|k| non_empty[k]);
+
+    let mut report = ObjectMap::new();
+    report.insert("encoding".to_string(), Value::String(encoding.name().into()));
+    report.insert("delimiter".to_string(), Value::String(delimiter.to_string().into()));
+    report.insert("quote_char".to_string(), Value::String(quote.to_string().into()));
+    let start = match start {
+        Some(start) => start,
+        None => {
+            // Пустой файл: только настройки чтения
+            report.insert("has_header".to_string(), Value::Bool(false));
+            report.insert("header_row".to_string(), Value::Null);
+            report.insert("skip_rows".to_string(), Value::Number(0.0));
+            report.insert("row_count".to_string(), Value::Number(0.0));
+            report.insert("bad_lines".to_string(), Value::Number(0.0));
+            report.insert("columns".to_string(), Value::Array(Rc::new(RefCell::new(Vec::new()))));
+            return Ok(report);
         }
+    };
+
+    let candidate = parse(&head[start]);
+    let head_rows: Vec<Vec<String>> = head[start + 1..]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse(line))
+        .collect();
+    let has_header = detect_header(&candidate, &head_rows);
+    let names: Vec<String> = if has_header {
+        candidate.clone()
     } else {
-        "outer"
+        (0..candidate.len()).map(|i| format!("Column_{}", i)).collect()
     };
 
-    // Извлекаем таблицы из массива
-    let mut tables = Vec::new();
-    for val in &tables_array {
-        match val {
-            Value::Table(table) => {
-                tables.push(table.clone());
+    // Строки данных из начала файла: все идут в выборку типов и примеров значений
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    if !has_header {
+        rows.push(candidate);
+    }
+    rows.extend(head_rows);
+    let mut nulls = vec![0usize; names.len()];
+    let mut bad_lines = 0;
+    let mut count_row = |row: &[String]| {
+        if row.len() != names.len() {
+            bad_lines += 1;
+        }
+        for (column, null_count) in nulls.iter_mut().enumerate() {
+            if row.get(column).is_none_or(|cell| cell.is_empty()) {
+                *null_count += 1;
             }
-            _ => return Value::Null, // Все элементы должны быть таблицами
         }
-    }
-
-    // Если только одна таблица, возвращаем её копию
-    if tables.len() == 1 {
-        let table_ref = tables[0].borrow();
-        let new_table = Table::from_data(table_ref.rows.clone(), Some(table_ref.headers.clone()));
-        return Value::Table(Rc::new(RefCell::new(new_table)));
-    }
-
-    // Собираем все уникальные колонки
-    let mut all_columns_set = HashSet::new();
-    let mut column_order = Vec::new();
-    
-    // Сначала добавляем колонки первой таблицы для сохранения порядка
-    let first_table = tables[0].borrow();
-    for header in &first_table.headers {
-        if all_columns_set.insert(header.clone()) {
-            column_order.push(header.clone());
+    };
+    for row in &rows {
+        count_row(row);
+    }
+    let mut row_count = rows.len();
+
+    // Остальная часть файла читается потоком; выборка фиксированного размера с
+    // детерминированным генератором, чтобы повторный анализ давал тот же отчет
+    let mut reservoir: Vec<Vec<String>> = Vec::new();
+    let mut seen_later = 0usize;
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    while let Some(line) = read_line(&mut reader)? {
+        let line = decode(&line);
+        if line.trim().is_empty() {
+            continue;
         }
-    }
-    
-    // Затем добавляем колонки из остальных таблиц
-    for table_rc in &tables[1..] {
-        let table_ref = table_rc.borrow();
-        for header in &table_ref.headers {
-            if all_columns_set.insert(header.clone()) {
-                column_order.push(header.clone());
+        let row = parse(&line);
+        count_row(&row);
+        row_count += 1;
+        seen_later += 1;
+        if reservoir.len() < sample_rows {
+            reservoir.push(row);
+        } else {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let slot = (state % seen_later as u64) as usize;
+            if slot < sample_rows {
+                reservoir[slot] = row;
             }
         }
     }
 
-    // Для inner mode - оставляем только колонки, присутствующие во всех таблицах
-    let result_columns = if mode == "inner" {
-        let mut common_columns = Vec::new();
-        for col in &column_order {
-            let mut in_all = true;
-            for table_rc in &tables {
-                let table_ref = table_rc.borrow();
-                if !table_ref.headers.contains(col) {
-                    in_all = false;
-                    break;
+    let columns: Vec<Value> = names
+        .iter()
+        .enumerate()
+        .map(|(column, name)| {
+            let cells: Vec<&str> = rows
+                .iter()
+                .chain(reservoir.iter())
+                .filter_map(|row| row.get(column).map(String::as_str))
+                .collect();
+            let (kind, confidence) = infer_column_type(&cells);
+            let mut samples: Vec<Value> = Vec::new();
+            for cell in cells.iter().filter(|cell| !cell.is_empty()) {
+                let value = infer_csv_value(cell);
+                if samples.len() < ANALYZE_SAMPLE_VALUES && !samples.contains(&value) {
+                    samples.push(value);
                 }
             }
-            if in_all {
-                common_columns.push(col.clone());
+            let mut info = ObjectMap::new();
+            info.insert("name".to_string(), Value::String(name.as_str().into()));
+            info.insert("type".to_string(), Value::String(kind.into()));
+            info.insert("confidence".to_string(), Value::Number((confidence * 100.0).round() / 100.0));
+            info.insert("nulls".to_string(), Value::Number(nulls[column] as f64));
+            info.insert("samples".to_string(), Value::Array(Rc::new(RefCell::new(samples))));
+            Value::Object(info)
+        })
+        .collect();
+
+    report.insert("has_header".to_string(), Value::Bool(has_header));
+    report.insert(
+        "header_row".to_string(),
+        if has_header { Value::Number(start as f64) } else { Value::Null },
+    );
+    report.insert("skip_rows".to_string(), Value::Number(start as f64));
+    report.insert("row_count".to_string(), Value::Number(row_count as f64));
+    report.insert("bad_lines".to_string(), Value::Number(bad_lines as f64));
+    report.insert("columns".to_string(), Value::Array(Rc::new(RefCell::new(columns))));
+    Ok(report)
+}
+
+/// Текстовая сводка отчета analyze_csv для print=true
+fn format_csv_analysis(report: &ObjectMap) -> String {
+    let field = |key: &str| report.get(key).map_or(String::new(), |value| value.to_string());
+    let delimiter = match field("delimiter").as_str() {
+        "\t" => "\\t".to_string(),
+        other => other.to_string(),
+    };
+    let mut lines = vec![
+        format!("File: {}", field("path")),
+        format!("Encoding: {}, delimiter: '{}', quote: '{}'", field("encoding"), delimiter, field("quote_char")),
+        match report.get("header_row") {
+            Some(Value::Number(row)) => format!("Header: line {}", *row as usize + 1),
+            _ => "Header: none".to_string(),
+        },
+        format!("Rows: {}, bad lines: {}", field("row_count"), field("bad_lines")),
+        "Columns:".to_string(),
+    ];
+    if let Some(Value::Array(columns)) = report.get("columns") {
+        for column in columns.borrow().iter() {
+            if let Value::Object(info) = column {
+                let get = |key: &str| info.get(key).map_or(String::new(), |value| value.to_string());
+                let confidence = match info.get("confidence") {
+                    Some(Value::Number(c)) => (c * 100.0).round() as i64,
+                    _ => 0,
+                };
+                lines.push(format!(
+                    "  {}: {} ({}%), nulls: {}, samples: {}",
+                    get("name"), get("type"), confidence, get("nulls"), get("samples")
+                ));
             }
         }
-        common_columns
-    } else {
-        column_order
-    };
+    }
+    lines.join("\n")
+}
 
-    // Создаем объединенные строки
-    let mut merged_rows = Vec::new();
-    
-    for table_rc in &tables {
-        let table_ref = table_rc.borrow();
-        
-        // Для каждой строки в таблице
-        for row in &table_ref.rows {
-            let mut new_row = Vec::new();
-            
-            // Для каждой колонки в результате
-            for col_name in &result_columns {
-                // Находим индекс колонки в исходной таблице
-                if let Some(col_idx) = table_ref.headers.iter().position(|h| h == col_name) {
-                    // Берем значение из соответствующей позиции в строке
-                    if col_idx < row.len() {
-                        new_row.push(row[col_idx].clone());
-                    } else {
-                        new_row.push(Value::Null);
-                    }
-                } else {
-                    // Колонки нет в этой таблице - добавляем null
-                    new_row.push(Value::Null);
-                }
-            }
-            
-            merged_rows.push(new_row);
+/// analyze_csv(path, sample_rows = 100, print = false) - отчет о CSV файле: кодировка,
+/// разделитель, кавычки, строка заголовка, число строк и профиль колонок (тип с уверенностью,
+/// число пустых значений, примеры). Отчет можно передать в read_file(path, options = report)
+pub fn native_analyze_csv(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::common::output_capture::OutputCapture;
+    use crate::websocket::{set_native_error, set_native_error_with_type};
+
+    let file_path = match args.first().and_then(path_arg) {
+        Some(p) => smb_url_to_lib(&p),
+        None => {
+            set_native_error_with_type("analyze_csv() expects a path or a string".to_string(), ErrorType::TypeError);
+            return Value::Null;
         }
-    }
-
-    // Создаем результирующую таблицу
-    let merged_table = Table::from_data(merged_rows, Some(result_columns));
-    Value::Table(Rc::new(RefCell::new(merged_table)))
-}
-
-pub fn native_now(_args: &[Value]) -> Value {
-    // Возвращаем текущее время в формате RFC3339 (ISO 8601)
-    // Формат: YYYY-MM-DDTHH:MM:SSZ
-    let now = Utc::now();
-    Value::String(now.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-}
-
-/// Безопасное получение parent пути в режиме --use-ve
-/// Возвращает parent только если он находится внутри папки сессии
-/// Если parent выходит за пределы папки сессии, возвращает None (не позволяет получить доступ к путям вне сессии)
-pub fn safe_path_parent(path: &PathBuf) -> Option<PathBuf> {
-    use crate::websocket::{get_user_session_path, get_use_ve};
-    
-    if !get_use_ve() {
-        // В обычном режиме просто возвращаем parent как есть
-        return path.parent().map(|p| p.to_path_buf());
-    }
-    
-    let session_path = match get_user_session_path() {
-        Some(p) => p,
-        None => return None, // Нет пути сессии - не возвращаем parent
     };
-    
-    // Нормализуем session_path для корректного сравнения
-    let session_path_normalized = match session_path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => session_path.clone(),
+    let sample_rows = match args.get(1) {
+        None | Some(Value::Null) => ANALYZE_SAMPLE_ROWS,
+        Some(Value::Number(n)) if *n >= 1.0 => *n as usize,
+        Some(Value::Integer(i)) if *i >= 1 => *i as usize,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("analyze_csv() sample_rows must be a positive number, got {}", other.preview(40)),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
     };
-    
-    // Получаем parent путь
-    let parent = match path.parent() {
-        Some(p) => p.to_path_buf(),
-        None => return None,
+    let print = args.get(2).is_some_and(Value::is_truthy);
+    if !file_access_allowed("analyze_csv", &file_path, false) {
+        return Value::Null;
+    }
+
+    let file_path_str = file_path.to_string_lossy().to_string();
+    let analysis = if let Some((share_name, file_path_on_share)) = split_smb_path(&file_path_str) {
+        match crate::vm::file_ops::get_smb_manager().lock().unwrap().read_file(share_name, file_path_on_share) {
+            Ok(content) => analyze_csv_reader(io::Cursor::new(content), sample_rows),
+            Err(e) => {
+                set_native_error_with_type(format!("analyze_csv(): {}", e), crate::websocket::smb::error_type(&e));
+                return Value::Null;
+            }
+        }
+    } else {
+        let resolved_path = match resolve_path_in_session(&file_path) {
+            Ok(p) => p,
+            Err(err_msg) => {
+                set_native_error(format!("Path resolution error: {}", err_msg));
+                return Value::Null;
+            }
+        };
+        match fs::File::open(&resolved_path) {
+            Ok(file) => analyze_csv_reader(io::BufReader::new(file), sample_rows),
+            Err(e) => {
+                set_native_error(format!("Error reading file {}: {}", resolved_path.display(), e));
+                return Value::Null;
+            }
+        }
     };
-    
-    // Канонизируем parent для проверки
-    let parent_normalized = match parent.canonicalize() {
-        Ok(p) => p,
-        Err(_) => parent.clone(),
+    let analysis = match analysis {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            set_native_error(format!("Error reading file {}: {}", file_path_str, e));
+            return Value::Null;
+        }
     };
-    
-    // Проверяем, что parent находится внутри папки сессии
-    if parent_normalized.starts_with(&session_path_normalized) {
-        // Если parent уже является самой папкой сессии, не возвращаем parent (защита от выхода наружу)
-        if parent_normalized == session_path_normalized {
-            None
+
+    let mut report = ObjectMap::new();
+    report.insert("path".to_string(), Value::String(file_path_str.into()));
+    report.extend(analysis);
+    if print {
+        let summary = format_csv_analysis(&report);
+        if OutputCapture::is_capturing() {
+            OutputCapture::write_output(&summary);
         } else {
-            Some(parent_normalized)
+            println!("{}", summary);
         }
-    } else {
-        // Parent выходит за пределы сессии - возвращаем None (не позволяем доступ к путям вне сессии)
-        None
     }
+    Value::Object(report)
 }
 
-/// Безопасное разрешение пути относительно папки сессии в режиме --use-ve
-fn resolve_path_in_session(path: &PathBuf) -> Result<PathBuf, String> {
-    use crate::websocket::{get_user_session_path, get_use_ve};
-    
-    if !get_use_ve() {
-        // В обычном режиме просто возвращаем путь как есть
-        return Ok(path.clone());
-    }
+fn read_xlsx_file(path: &PathBuf, header_row: usize, sheet_name: Option<&str>) -> Result<Table, Box<dyn std::error::Error>> {
+    use calamine::{open_workbook, Reader, Xlsx};
     
-    let session_path = match get_user_session_path() {
-        Some(p) => p,
-        None => return Err("Session path not available".to_string()),
-    };
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
     
-    // Нормализуем session_path для корректного сравнения
-    let session_path_normalized = match session_path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => {
-            // Если канонизация не удалась, используем исходный путь
-            // но нормализуем его (убираем лишние компоненты)
-            session_path.clone()
-        },
+    // Выбираем лист
+    let sheet = if let Some(name) = sheet_name {
+        workbook.worksheet_range(name)?
+    } else {
+        // Берем первый лист
+        let sheet_names = workbook.sheet_names();
+        if sheet_names.is_empty() {
+            return Err("No sheets found".into());
+        }
+        workbook.worksheet_range(&sheet_names[0])?
     };
+
+    Ok(xlsx_range_to_table(&sheet, header_row))
+}
+
+/// Читает все листы XLSX файла в порядке их следования в книге
+fn read_xlsx_all_sheets(path: &PathBuf, header_row: usize) -> Result<Vec<(String, Table)>, Box<dyn std::error::Error>> {
+    use calamine::{open_workbook, Reader, Xlsx};
     
-    // Проверяем на directory traversal атаки
-    let path_str = path.to_string_lossy().to_string();
-    if path_str.contains("..") {
-        return Err("Path traversal not allowed in --use-ve mode".to_string());
-    }
-    
-    // Обрабатываем пустой путь и "." как текущую директорию сессии
-    if path_str.is_empty() || path_str == "." {
-        return Ok(session_path_normalized.clone());
-    }
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let mut sheets = Vec::new();
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)?;
+        let mut table = xlsx_range_to_table(&range, header_row);
+        table.set_name(name.clone());
+        sheets.push((name, table));
+    }
+    Ok(sheets)
+}
+
+fn xlsx_range_to_table(sheet: &calamine::Range<calamine::Data>, header_row: usize) -> Table {
+    let mut rows = Vec::new();
+    let mut headers = Vec::new();
     
-    // Проверяем абсолютные пути
-    if path.is_absolute() {
-        // Для абсолютных путей проверяем, что они внутри сессии
-        // Пытаемся канонизировать оба пути для корректного сравнения
-        let path_normalized = if path.exists() {
-            match path.canonicalize() {
-                Ok(p) => p,
-                Err(_) => {
-                    // Если канонизация не удалась, но путь существует,
-                    // проверяем через starts_with с исходными путями
-                    if path.starts_with(&session_path) || path.starts_with(&session_path_normalized) {
-                        path.clone()
-                    } else {
-                        return Err("Access outside session directory not allowed".to_string());
-                    }
-                },
-            }
-        } else {
-            // Если файл не существует, пытаемся канонизировать родительскую директорию
-            // для проверки безопасности
-            if let Some(parent) = path.parent() {
-                match parent.canonicalize() {
-                    Ok(p) => {
-                        // Проверяем, что родительская директория внутри сессии
-                        if p.starts_with(&session_path_normalized) {
-                            path.clone()
-                        } else {
-                            // Дополнительная проверка через сравнение компонентов
-                            let parent_components: Vec<_> = p.components().collect();
-                            let session_components: Vec<_> = session_path_normalized.components().collect();
-                            if parent_components.len() >= session_components.len() {
-                                let mut matches = true;
-                                for (i, session_comp) in session_components.iter().enumerate() {
-                                    if i >= parent_components.len() || parent_components[i] != *session_comp {
-                                        matches = false;
-                                        break;
-                                    }
-                                }
-                                if matches {
-                                    path.clone()
-                                } else {
-                                    return Err("Path resolved outside session directory".to_string());
-                                }
-                            } else {
-                                return Err("Path resolved outside session directory".to_string());
-                            }
-                        }
-                    },
-                    Err(_) => {
-                        // Если родительская директория не может быть канонизирована,
-                        // проверяем через starts_with
-                        if parent.starts_with(&session_path) || parent.starts_with(&session_path_normalized) {
-                            path.clone()
-                        } else {
-                            return Err("Access outside session directory not allowed".to_string());
-                        }
-                    },
-                }
-            } else {
-                // Нет родительской директории - проверяем напрямую
-                if path.starts_with(&session_path) || path.starts_with(&session_path_normalized) {
-                    path.clone()
-                } else {
-                    return Err("Access outside session directory not allowed".to_string());
-                }
-            }
-        };
-        
-        // Финальная проверка безопасности через канонизированные пути
-        if path_normalized.starts_with(&session_path_normalized) {
-            Ok(path_normalized)
-        } else if path_normalized.starts_with(&session_path) {
-            // Если session_path не был канонизирован, но путь начинается с него
-            Ok(path_normalized)
-        } else {
-            // Дополнительная проверка через сравнение компонентов
-            let path_components: Vec<_> = path_normalized.components().collect();
-            let session_components: Vec<_> = session_path_normalized.components().collect();
-            if path_components.len() >= session_components.len() {
-                let mut matches = true;
-                for (i, session_comp) in session_components.iter().enumerate() {
-                    if i >= path_components.len() || path_components[i] != *session_comp {
-                        matches = false;
-                        break;
-                    }
-                }
-                if matches {
-                    Ok(path_normalized)
-                } else {
-                    Err("Path resolved outside session directory".to_string())
-                }
-            } else {
-                Err("Access outside session directory not allowed".to_string())
-            }
-        }
-    } else {
-        // Относительный путь разрешаем относительно папки сессии
-        let resolved = session_path.join(path);
-        
-        // Нормализуем путь и проверяем, что он все еще внутри сессии
-        let normalized = if resolved.exists() {
-            match resolved.canonicalize() {
-                Ok(p) => p,
-                Err(_) => resolved,
-            }
-        } else {
-            // Если файл не существует, проверяем родительскую директорию
-            if let Some(parent) = resolved.parent() {
-                match parent.canonicalize() {
-                    Ok(p) => {
-                        if p.starts_with(&session_path_normalized) {
-                            resolved
-                        } else {
-                            return Err("Path resolved outside session directory".to_string());
-                        }
-                    },
-                    Err(_) => {
-                        if parent.starts_with(&session_path) {
-                            resolved
-                        } else {
-                            return Err("Path resolved outside session directory".to_string());
-                        }
-                    },
+    for (row_idx, row) in sheet.rows().enumerate() {
+        let values: Vec<Value> = row.iter()
+            .map(|cell| {
+                match cell {
+                    calamine::Data::Int(n) => Value::Number(*n as f64),
+                    calamine::Data::Float(n) => Value::Number(*n),
+                    calamine::Data::String(s) => Value::String(s.as_str().into()),
+                    calamine::Data::Bool(b) => Value::Bool(*b),
+                    calamine::Data::DateTime(dt) => Value::String(dt.to_string().into()),
+                    calamine::Data::DateTimeIso(s) => Value::String(s.as_str().into()),
+                    calamine::Data::DurationIso(s) => Value::String(s.as_str().into()),
+                    calamine::Data::Error(_) => Value::Null,
+                    calamine::Data::Empty => Value::Null,
                 }
-            } else {
-                resolved
-            }
-        };
-        
-        if normalized.starts_with(&session_path_normalized) || normalized.starts_with(&session_path) {
-            Ok(normalized)
+            })
+            .collect();
+
+        if row_idx == header_row {
+            // Это строка заголовков
+            headers = values.iter()
+                .map(|v| match v {
+                    Value::String(s) => s.to_string(),
+                    _ => v.to_string(),
+                })
+                .collect();
         } else {
-            Err("Path resolved outside session directory".to_string())
+            rows.push(values);
         }
     }
-}
 
-pub fn native_getcwd(_args: &[Value]) -> Value {
-    use crate::websocket::{get_use_ve};
-    
-    // Если включен режим use_ve, возвращаем пустой путь для безопасности
-    if get_use_ve() {
-        Value::Path(PathBuf::new()) // Пустой путь в режиме use_ve для безопасности
-    } else {
-        // Возвращаем текущую рабочую директорию
-        match env::current_dir() {
-            Ok(path) => Value::Path(path),
-            Err(_) => Value::Path(PathBuf::new()), // При ошибке возвращаем пустой путь
-        }
+    // Если заголовки не были найдены, генерируем их
+    if headers.is_empty() && !rows.is_empty() {
+        let num_cols = rows[0].len();
+        headers = (0..num_cols)
+            .map(|i| format!("Column_{}", i))
+            .collect();
     }
+
+    Table::from_data(rows, Some(headers))
 }
 
-pub fn native_list_files(args: &[Value]) -> Value {
-    if args.is_empty() {
-        return Value::Array(Rc::new(RefCell::new(Vec::new())));
-    }
+/// Максимальная длина имени листа Excel
+const EXCEL_MAX_SHEET_NAME_LEN: usize = 31;
 
-    // Первый аргумент - путь к директории
-    let dir_path = match &args[0] {
-        Value::Path(p) => p.clone(),
-        Value::String(s) => PathBuf::from(s),
-        _ => return Value::Array(Rc::new(RefCell::new(Vec::new()))),
-    };
+/// Приводит имя листа к допустимому в Excel: запрещенные символы заменяются на '_',
+/// длина ограничивается 31 символом, совпадения (без учета регистра) получают суффикс ~2, ~3, ...
+fn sanitize_sheet_name(name: &str, used: &mut Vec<String>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim_matches('\'');
+    let base = if cleaned.is_empty() { "Sheet" } else { cleaned };
+
+    let mut candidate: String = base.chars().take(EXCEL_MAX_SHEET_NAME_LEN).collect();
+    let mut counter = 2;
+    while used.iter().any(|u| u.to_lowercase() == candidate.to_lowercase()) {
+        let suffix = format!("~{}", counter);
+        let prefix: String = base.chars().take(EXCEL_MAX_SHEET_NAME_LEN - suffix.chars().count()).collect();
+        candidate = format!("{}{}", prefix, suffix);
+        counter += 1;
+    }
+    used.push(candidate.clone());
+    candidate
+}
 
-    let dir_path_str = dir_path.to_string_lossy().to_string();
+fn write_xlsx_file(path: &PathBuf, sheets: &[(String, Rc<RefCell<Table>>)]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    use rust_xlsxwriter::Workbook;
 
-    // Проверяем, является ли это SMB путем (lib://)
-    if dir_path_str.starts_with("lib://") {
-        // Извлекаем имя шары и путь к директории
-        let path_without_prefix = &dir_path_str[6..]; // Убираем "lib://"
-        let parts: Vec<&str> = path_without_prefix.splitn(2, '/').collect();
-        
-        if parts.is_empty() {
-            return Value::Array(Rc::new(RefCell::new(Vec::new())));
+    let mut workbook = Workbook::new();
+    let mut used_names = Vec::new();
+    for (name, table) in sheets {
+        let sheet_name = sanitize_sheet_name(name, &mut used_names);
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet_name)?;
+
+        let table = table.borrow();
+        for (col, header) in table.headers.iter().enumerate() {
+            worksheet.write_string(0, col as u16, header)?;
         }
-        
-        let share_name = parts[0];
-        let dir_path_on_share = if parts.len() > 1 { parts[1] } else { "" };
-        
-        // Получаем SmbManager из thread-local storage
-        if let Some(smb_manager) = crate::vm::file_ops::get_smb_manager() {
-            match smb_manager.lock().unwrap().list_files(share_name, dir_path_on_share) {
-                Ok(files) => {
-                    let file_values: Vec<Value> = files.iter()
-                        .map(|f| {
-                            // Конструируем полный путь для SMB
-                            let full_path = if dir_path_on_share.is_empty() {
-                                format!("lib://{}/{}", share_name, f)
-                            } else {
-                                format!("lib://{}/{}/{}", share_name, dir_path_on_share, f)
-                            };
-                            Value::Path(PathBuf::from(full_path))
-                        })
-                        .collect();
-                    Value::Array(Rc::new(RefCell::new(file_values)))
+        for (row_idx, row) in table.rows.iter().enumerate() {
+            let excel_row = row_idx as u32 + 1;
+            for (col, cell) in row.iter().enumerate() {
+                let col = col as u16;
+                match cell {
+                    Value::Number(n) => { worksheet.write_number(excel_row, col, *n)?; }
+                    Value::Integer(i) => { worksheet.write_number(excel_row, col, *i as f64)?; }
+                    Value::Bool(b) => { worksheet.write_boolean(excel_row, col, *b)?; }
+                    Value::String(s) => { worksheet.write_string(excel_row, col, &**s)?; }
+                    // null - пустая ячейка
+                    Value::Null => {}
+                    other => { worksheet.write_string(excel_row, col, other.to_string())?; }
                 }
-                Err(_) => Value::Array(Rc::new(RefCell::new(Vec::new()))),
             }
-        } else {
-            Value::Array(Rc::new(RefCell::new(Vec::new())))
         }
-    } else {
-        // Обычная локальная директория
-        // Разрешаем путь относительно папки сессии в режиме --use-ve
-        let resolved_path = match resolve_path_in_session(&dir_path) {
-            Ok(p) => p,
-            Err(err_msg) => {
-                // При ошибке безопасности сохраняем сообщение об ошибке
-                use crate::websocket::set_native_error;
-                set_native_error(err_msg);
-                return Value::Array(Rc::new(RefCell::new(Vec::new())));
-            }
-        };
-        
-        if !resolved_path.exists() || !resolved_path.is_dir() {
-            return Value::Array(Rc::new(RefCell::new(Vec::new())));
+    }
+    workbook.save(path)?;
+    Ok(used_names)
+}
+
+/// list_sheets(path) - имена листов XLSX файла в порядке следования
+pub fn native_list_sheets(args: &[Value]) -> Value {
+    use calamine::{open_workbook, Reader, Xlsx};
+    use crate::websocket::set_native_error;
+
+    let file_path = match args.first() {
+        Some(Value::Path(p)) => p.clone(),
+        Some(Value::String(s)) => from_user_str(s),
+        _ => {
+            set_native_error("list_sheets() expects a path to an .xlsx file".to_string());
+            return Value::Null;
         }
-        
-        match fs::read_dir(&resolved_path) {
-            Ok(entries) => {
-                let mut files = Vec::new();
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let entry_path = entry.path();
-                        if let Some(file_name) = entry_path.file_name() {
-                            if let Some(name_str) = file_name.to_str() {
-                                // Пропускаем служебные файлы
-                                if !name_str.starts_with(".") && name_str != ".DS_Store" {
-                                    files.push(Value::Path(entry_path));
-                                }
-                            }
-                        }
+    };
+    if !file_access_allowed("list_sheets", &file_path, false) {
+        return Value::Null;
+    }
+    let resolved_path = match resolve_path_in_session(&file_path) {
+        Ok(p) => p,
+        Err(err_msg) => {
+            set_native_error(format!("Path resolution error: {}", err_msg));
+            return Value::Null;
+        }
+    };
+
+    match open_workbook::<Xlsx<_>, _>(&resolved_path) {
+        Ok(workbook) => Value::Array(Rc::new(RefCell::new(
+            workbook.sheet_names().into_iter().map(|name| Value::String(name.into())).collect(),
+        ))),
+        Err(e) => {
+            set_native_error(format!("Error reading XLSX file {}: {}", resolved_path.display(), e));
+            Value::Null
+        }
+    }
+}
+
+/// write_excel(path, data) - записывает таблицу (лист "Sheet1") или объект {имя листа: таблица}
+/// в .xlsx файл. Листы объекта записываются в порядке его ключей. Возвращает массив имен листов
+pub fn native_write_excel(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error;
+
+    if args.len() < 2 {
+        set_native_error("write_excel() expects a path and a table or an object of tables".to_string());
+        return Value::Null;
+    }
+    let file_path = match &args[0] {
+        Value::Path(p) => p.clone(),
+        Value::String(s) => from_user_str(s),
+        _ => {
+            set_native_error("write_excel() expects a path as the first argument".to_string());
+            return Value::Null;
+        }
+    };
+    if !file_access_allowed("write_excel", &file_path, true) {
+        return Value::Null;
+    }
+
+    let sheets: Vec<(String, Rc<RefCell<Table>>)> = match &args[1] {
+        Value::Table(table) => vec![("Sheet1".to_string(), Rc::clone(table))],
+        Value::Object(map) => {
+            let mut sheets = Vec::with_capacity(map.len());
+            for (name, value) in map {
+                match value {
+                    Value::Table(table) => sheets.push((name.clone(), Rc::clone(table))),
+                    _ => {
+                        set_native_error(format!("write_excel(): value for sheet '{}' is not a table", name));
+                        return Value::Null;
                     }
                 }
-                Value::Array(Rc::new(RefCell::new(files)))
             }
-            Err(_) => Value::Array(Rc::new(RefCell::new(Vec::new()))),
+            sheets
+        }
+        _ => {
+            set_native_error("write_excel() expects a table or an object of tables".to_string());
+            return Value::Null;
+        }
+    };
+
+    let resolved_path = match resolve_path_in_session(&file_path) {
+        Ok(p) => p,
+        Err(err_msg) => {
+            set_native_error(format!("Path resolution error: {}", err_msg));
+            return Value::Null;
+        }
+    };
+
+    let names = match write_xlsx_file(&resolved_path, &sheets) {
+        Ok(names) => names,
+        Err(e) => {
+            set_native_error(format!("Error writing XLSX file {}: {}", resolved_path.display(), e));
+            return Value::Null;
         }
+    };
+    // Размер .xlsx известен только после записи: при превышении лимита папки сессии файл удаляется
+    let quota = crate::vm::policy::get_execution_policy().max_session_disk_bytes;
+    let size = fs::metadata(&resolved_path).map(|m| m.len()).unwrap_or(0);
+    if let Err(e) = crate::websocket::check_session_disk_quota(quota, &resolved_path, size) {
+        let _ = fs::remove_file(&resolved_path);
+        crate::websocket::set_native_error_with_type(
+            format!("write_excel(): {}", e),
+            crate::common::error::ErrorType::PermissionError,
+        );
+        return Value::Null;
     }
+    Value::Array(Rc::new(RefCell::new(
+        names.into_iter().map(|name| Value::String(name.into())).collect(),
+    )))
 }
 
-// ============================================================================
-// JOIN Operations Infrastructure
-// ============================================================================
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum JoinType {
-    Inner,
-    Left,
-    Right,
-    Full,
-    Cross,
-    Semi,
-    Anti,
+/// write_file(path, content) - записывает текст в файл, заменяя содержимое
+/// (таблица записывается в формате CSV)
+pub fn native_write_file(args: &[Value]) -> Value {
+    write_text_file("write_file", args, false)
 }
 
-#[derive(Debug, Clone)]
-struct JoinKey {
-    left_col: String,
-    right_col: String,
+/// append_file(path, content) - дописывает текст в конец файла (создает файл, если его нет)
+pub fn native_append_file(args: &[Value]) -> Value {
+    write_text_file("append_file", args, true)
 }
 
-// Парсинг ключей JOIN из Value
-// Поддерживает форматы:
-// - String: имя колонки (автоматическое сопоставление)
-// - Array с кортежами: [("left_col", "right_col"), ...]
-// - Array с одним кортежем: [("left_col", "right_col")]
-fn parse_join_keys(value: &Value, left_table: &Table, right_table: &Table) -> Result<Vec<JoinKey>, String> {
-    match value {
-        Value::String(col_name) => {
-            // Автоматическое сопоставление: ищем колонку с таким именем в обеих таблицах
-            if left_table.get_column(col_name).is_some() && right_table.get_column(col_name).is_some() {
-                Ok(vec![JoinKey {
-                    left_col: col_name.clone(),
-                    right_col: col_name.clone(),
-                }])
-            } else {
-                Err(format!("Column '{}' not found in both tables", col_name))
-            }
+/// Общая часть write_file/append_file: недостающие родительские папки создаются,
+/// путь проверяется песочницей --use-ve, пути lib:// пишутся через подключенную SMB шару.
+/// Строки пишутся как есть, прочие значения - в том виде, в котором их печатает print()
+fn write_text_file(name: &str, args: &[Value], append: bool) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::{check_session_disk_quota, set_native_error, set_native_error_with_type};
+
+    let (file_path, content) = match (args.first().and_then(path_arg), args.get(1)) {
+        (Some(path), Some(content)) => (path, content),
+        _ => {
+            set_native_error_with_type(format!("{}() expects a path and content", name), ErrorType::TypeError);
+            return Value::Null;
         }
-        Value::Array(arr) => {
-            let arr_ref = arr.borrow();
-            if arr_ref.is_empty() {
-                return Err("Join keys array cannot be empty".to_string());
-            }
+    };
+    let text = match content {
+        Value::Table(table) if !append => render::table_to_csv(&table.borrow()),
+        Value::LazyTable(lazy) if !append => render::table_to_csv(&collect_lazy(lazy).borrow()),
+        Value::Table(_) | Value::LazyTable(_) => {
+            set_native_error_with_type(
+                format!("{}() cannot append a table; use write_file() or write_excel() for tables", name),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+        other => other.to_string(),
+    };
+    if !file_access_allowed(name, &file_path, true) {
+        return Value::Null;
+    }
+
+    let file_path_str = file_path.to_string_lossy().to_string();
+    if let Some((share_name, file_path_on_share)) = split_smb_path(&file_path_str) {
+        let result = crate::vm::file_ops::get_smb_manager().lock().unwrap().write_file(share_name, file_path_on_share, text.as_bytes(), append);
+        if let Err(e) = result {
+            set_native_error(format!("{}(): {}", name, e));
+        }
+        return Value::Null;
+    }
+
+    let resolved_path = match resolve_path_in_session(&file_path) {
+        Ok(p) => p,
+        Err(err_msg) => {
+            set_native_error_with_type(format!("{}(): {}", name, err_msg), ErrorType::PermissionError);
+            return Value::Null;
+        }
+    };
+    let existing_size = if append { fs::metadata(&resolved_path).map(|m| m.len()).unwrap_or(0) } else { 0 };
+    let quota = crate::vm::policy::get_execution_policy().max_session_disk_bytes;
+    if let Err(e) = check_session_disk_quota(quota, &resolved_path, existing_size + text.len() as u64) {
+        set_native_error_with_type(format!("{}(): {}", name, e), ErrorType::PermissionError);
+        return Value::Null;
+    }
+    let result = resolved_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&resolved_path)
+        })
+        .and_then(|mut file| io::Write::write_all(&mut file, text.as_bytes()));
+    if let Err(e) = result {
+        set_native_error(format!("{}(): error writing {}: {}", name, resolved_path.display(), e));
+    }
+    Value::Null
+}
+
+// Дополнительные функции для работы с таблицами
+
+pub fn native_table_info(args: &[Value]) -> Value {
+    if args.is_empty() {
+        return Value::String("Table: empty".into());
+    }
+
+    match &args[0] {
+        Value::Table(table) => {
+            let table_ref = table.borrow();
+            let row_count = table_ref.len();
+            let col_count = table_ref.column_count();
+            let headers = table_ref.headers.clone();
             
-            let mut keys = Vec::new();
-            for item in arr_ref.iter() {
-                match item {
-                    Value::Array(tuple) => {
-                        let tuple_ref = tuple.borrow();
-                        if tuple_ref.len() != 2 {
-                            return Err("Join key tuple must have exactly 2 elements".to_string());
-                        }
-                        let left_col = match &tuple_ref[0] {
-                            Value::String(s) => s.clone(),
-                            _ => return Err("Join key must be a string".to_string()),
-                        };
-                        let right_col = match &tuple_ref[1] {
-                            Value::String(s) => s.clone(),
-                            _ => return Err("Join key must be a string".to_string()),
-                        };
-                        
-                        if left_table.get_column(&left_col).is_none() {
-                            return Err(format!("Column '{}' not found in left table", left_col));
-                        }
-                        if right_table.get_column(&right_col).is_none() {
-                            return Err(format!("Column '{}' not found in right table", right_col));
-                        }
-                        
-                        keys.push(JoinKey { left_col, right_col });
-                    }
-                    Value::String(col_name) => {
-                        // Одиночная строка в массиве - автоматическое сопоставление
-                        if left_table.get_column(col_name).is_some() && right_table.get_column(col_name).is_some() {
-                            keys.push(JoinKey {
-                                left_col: col_name.clone(),
-                                right_col: col_name.clone(),
-                            });
-                        } else {
-                            return Err(format!("Column '{}' not found in both tables", col_name));
+            let mut info = format!("Table: {} rows, {} columns\n", row_count, col_count);
+            info.push_str("Columns:\n");
+            for header in &headers {
+                if let Some(column) = table_ref.get_column(header) {
+                    let col_type = if column.is_empty() {
+                        "empty".to_string()
+                    } else {
+                        // Определяем тип по первому не-null значению
+                        let first_val = column.iter().find(|v| !matches!(v, Value::Null));
+                        match first_val {
+                            Some(Value::Number(_)) => "number".to_string(),
+                            Some(Value::Integer(_)) => "integer".to_string(),
+                            Some(Value::String(_)) => "string".to_string(),
+                            Some(Value::Bool(_)) => "bool".to_string(),
+                            Some(Value::Array(_)) => "array".to_string(),
+                            _ => "mixed".to_string(),
                         }
-                    }
-                    _ => return Err("Join key must be a string or tuple [string, string]".to_string()),
+                    };
+                    info.push_str(&format!("  - {}: {} ({} values)\n", header, col_type, column.len()));
                 }
             }
-            Ok(keys)
+            Value::String(info.into())
         }
-        _ => Err("Join keys must be a string or array of tuples".to_string()),
+        _ => Value::String("Not a table".into()),
     }
 }
 
-// Применение алиасов таблиц к именам колонок для разрешения коллизий
-// Формат: <table_alias>.<column> (например, "left.id", "right.id")
-fn apply_column_aliases(
-    headers: &[String],
-    alias: &str,
-    existing_names: &std::collections::HashSet<String>,
-) -> Vec<String> {
-    headers
-        .iter()
-        .map(|h| {
-            if existing_names.contains(h) {
-                format!("{}.{}", alias, h)  // Конфликт: left.id
-            } else {
-                h.clone()  // Нет конфликта: name
+/// Колонки таблицы, которую возвращает describe()
+const DESCRIBE_HEADERS: [&str; 10] = ["column", "type", "count", "null_count", "min", "max", "mean", "std", "unique", "top"];
+
+/// Строка describe() для одной колонки. Числовая колонка (все не-null значения - числа):
+/// count, null_count, min, max, mean и выборочное std; остальные - count, unique и top
+/// (самое частое значение, при равенстве - встреченное первым)
+fn describe_column(name: &str, column: &[Value]) -> Vec<Value> {
+    let values: Vec<&Value> = column.iter().filter(|v| !matches!(v, Value::Null)).collect();
+    let count = values.len();
+    let null_count = column.len() - count;
+    let mut row = vec![Value::Null; DESCRIBE_HEADERS.len()];
+    row[0] = Value::String(name.into());
+    row[2] = Value::Integer(count as i64);
+    row[3] = Value::Integer(null_count as i64);
+
+    if values.is_empty() {
+        // Только null: статистик нет, деления на ноль тоже
+        row[1] = Value::String("null".into());
+        return row;
+    }
+
+    if values.iter().all(|v| v.is_numeric()) {
+        let all_integers = values.iter().all(|v| matches!(v, Value::Integer(_)));
+        row[1] = Value::String(if all_integers { "integer" } else { "number" }.into());
+        let numbers: Vec<f64> = values.iter().filter_map(|v| v.as_f64()).collect();
+        let min = values.iter().copied().min_by(|a, b| compare_values(a, b));
+        let max = values.iter().copied().max_by(|a, b| compare_values(a, b));
+        let mean = numbers.iter().sum::<f64>() / count as f64;
+        row[4] = min.cloned().unwrap_or(Value::Null);
+        row[5] = max.cloned().unwrap_or(Value::Null);
+        row[6] = Value::Number(mean);
+        // Выборочное стандартное отклонение (n - 1, как в pandas): для одного значения не определено
+        if count > 1 {
+            let variance = numbers.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / (count - 1) as f64;
+            row[7] = Value::Number(variance.sqrt());
+        }
+        return row;
+    }
+
+    let kind = match values[0] {
+        Value::String(_) if values.iter().all(|v| matches!(v, Value::String(_))) => "string",
+        Value::Bool(_) if values.iter().all(|v| matches!(v, Value::Bool(_))) => "bool",
+        _ => "mixed",
+    };
+    row[1] = Value::String(kind.into());
+    // Значения сравниваются по строковому представлению, как в unique()
+    let mut frequencies: Vec<(&Value, usize)> = Vec::new();
+    let mut positions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for &value in &values {
+        let key = value.to_string();
+        match positions.get(&key) {
+            Some(&position) => frequencies[position].1 += 1,
+            None => {
+                positions.insert(key, frequencies.len());
+                frequencies.push((value, 1));
             }
+        }
+    }
+    let mut top = 0;
+    for (index, (_, frequency)) in frequencies.iter().enumerate() {
+        if *frequency > frequencies[top].1 {
+            top = index;
+        }
+    }
+    row[8] = Value::Integer(frequencies.len() as i64);
+    row[9] = frequencies[top].0.clone();
+    row
+}
+
+/// describe(table) - сводная статистика: новая таблица с одной строкой на каждую колонку
+pub fn native_describe(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let Some(Value::Table(table)) = args.first() else {
+        set_native_error_with_type("describe() expects a table".to_string(), ErrorType::TypeError);
+        return Value::Null;
+    };
+    let table = table.borrow();
+    let rows: Vec<Vec<Value>> = table.headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            // Недостающие ячейки короткой строки считаются null
+            let column: Vec<Value> = table.rows.iter().map(|row| row.get(index).cloned().unwrap_or(Value::Null)).collect();
+            describe_column(header, &column)
         })
-        .collect()
+        .collect();
+    let headers = DESCRIBE_HEADERS.iter().map(|h| h.to_string()).collect();
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
 }
 
-// Сравнение значений для JOIN (с учетом nulls_equal)
-// Примечание: в текущей реализации JOIN используется хеш-таблица (build_right_hash_table),
-// поэтому эти функции не используются. Они оставлены на случай будущей реализации
-// non-equi joins или других типов JOIN, где может понадобиться прямое сравнение.
-#[allow(dead_code)]
-fn values_match(left_val: &Value, right_val: &Value, nulls_equal: bool) -> bool {
-    match (left_val, right_val) {
-        (Value::Null, Value::Null) => nulls_equal,
-        (Value::Null, _) | (_, Value::Null) => false,
-        _ => left_val == right_val,
+/// Необязательный логический аргумент: None/Null - значение по умолчанию, не bool - TypeError
+fn bool_arg(function: &str, name: &str, value: Option<&Value>, default: bool) -> Option<bool> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    match value {
+        None | Some(Value::Null) => Some(default),
+        Some(Value::Bool(flag)) => Some(*flag),
+        Some(_) => {
+            set_native_error_with_type(format!("{}() {} must be a boolean", function, name), ErrorType::TypeError);
+            None
+        }
     }
 }
 
-// Сравнение ключей для JOIN
-// Примечание: в текущей реализации JOIN используется хеш-таблица (build_right_hash_table),
-// поэтому эта функция не используется. Она оставлена на случай будущей реализации
-// non-equi joins или других типов JOIN, где может понадобиться прямое сравнение.
-#[allow(dead_code)]
-fn keys_match(left_row: &[Value], right_row: &[Value], keys: &[JoinKey], 
-              left_table: &Table, right_table: &Table, nulls_equal: bool) -> bool {
-    for key in keys {
-        let left_idx = left_table.headers.iter().position(|h| h == &key.left_col);
-        let right_idx = right_table.headers.iter().position(|h| h == &key.right_col);
-        
-        match (left_idx, right_idx) {
-            (Some(li), Some(ri)) => {
-                let left_val = &left_row[li];
-                let right_val = &right_row[ri];
-                if !values_match(left_val, right_val, nulls_equal) {
-                    return false;
+/// value_counts(table, column, normalize = false, dropna = false) - таблица из различных значений
+/// колонки и их частот по убыванию (при равенстве - в порядке первого появления).
+/// normalize = true - доли вместо количеств; null считается отдельным значением, если не dropna
+pub fn native_value_counts(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(Value::String(column))) = (args.first(), args.get(1)) else {
+        set_native_error_with_type(
+            "value_counts() expects a table and a column name".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    };
+    let Some(normalize) = bool_arg("value_counts", "normalize", args.get(2), false) else {
+        return Value::Null;
+    };
+    let Some(dropna) = bool_arg("value_counts", "dropna", args.get(3), false) else {
+        return Value::Null;
+    };
+    let table = table.borrow();
+    let Some(indices) = column_indices(&table, &[column.to_string()], "value_counts") else {
+        return Value::Null;
+    };
+    let index = indices[0];
+
+    // Значения сравниваются по строковому представлению, как в unique()
+    let mut counts: Vec<(Value, usize)> = Vec::new();
+    // Ключ null - None, чтобы он не совпадал со строкой "null"
+    let mut positions: std::collections::HashMap<Option<String>, usize> = std::collections::HashMap::new();
+    for row in &table.rows {
+        let value = row.get(index).cloned().unwrap_or(Value::Null);
+        let is_null = matches!(value, Value::Null);
+        if dropna && is_null {
+            continue;
+        }
+        let key = (!is_null).then(|| value.to_string());
+        match positions.get(&key) {
+            Some(&position) => counts[position].1 += 1,
+            None => {
+                positions.insert(key, counts.len());
+                counts.push((value, 1));
+            }
+        }
+    }
+    // Сортировка устойчивая: равные частоты остаются в порядке первого появления
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    let rows: Vec<Vec<Value>> = counts
+        .into_iter()
+        .map(|(value, count)| {
+            let frequency = if normalize {
+                Value::Number(count as f64 / total as f64)
+            } else {
+                Value::Integer(count as i64)
+            };
+            vec![value, frequency]
+        })
+        .collect();
+    let frequency_header = if normalize { "proportion" } else { "count" };
+    let headers = vec![column.to_string(), frequency_header.to_string()];
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
+}
+
+/// Число строк из аргумента table_head/table_tail/table_slice/show_table:
+/// None/Null - значение по умолчанию, отрицательное или дробное число - ValueError
+fn row_count_arg(function: &str, name: &str, value: Option<&Value>, default: usize) -> Option<usize> {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let n = match value {
+        None | Some(Value::Null) => return Some(default),
+        Some(Value::Integer(i)) => *i as f64,
+        Some(Value::Number(n)) => *n,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("{}() {} must be a number, got {}", function, name, other.to_string()),
+                ErrorType::TypeError,
+            );
+            return None;
+        }
+    };
+    if n < 0.0 || n.fract() != 0.0 {
+        set_native_error_with_type(
+            format!("{}() {} must be a non-negative integer, got {}", function, name, n),
+            ErrorType::ValueError,
+        );
+        return None;
+    }
+    Some(n as usize)
+}
+
+/// Новая таблица из строк [start, end) исходной: клонируются только выбранные строки,
+/// заголовки и имя таблицы сохраняются
+fn table_rows_range(table: &Table, start: usize, end: usize) -> Value {
+    let end = end.min(table.len());
+    let start = start.min(end);
+    let mut new_table = Table::from_data(table.rows[start..end].to_vec(), Some(table.headers.clone()));
+    new_table.name = table.name.clone();
+    Value::Table(Rc::new(RefCell::new(new_table)))
+}
+
+/// table_head(table, n = 10) - первые n строк в новой таблице
+pub fn native_table_head(args: &[Value]) -> Value {
+    let n = match row_count_arg("table_head", "n", args.get(1), 10) {
+        Some(n) => n,
+        None => return Value::Null,
+    };
+
+    match args.first() {
+        Some(Value::LazyTable(lazy)) => lazy_with_op(lazy, LazyOp::Head(n), "table_head"),
+        Some(Value::Table(table)) => table_rows_range(&table.borrow(), 0, n),
+        _ => Value::Null,
+    }
+}
+
+/// table_tail(table, n = 10) - последние n строк в новой таблице
+pub fn native_table_tail(args: &[Value]) -> Value {
+    let n = match row_count_arg("table_tail", "n", args.get(1), 10) {
+        Some(n) => n,
+        None => return Value::Null,
+    };
+
+    let table = match args.first() {
+        Some(Value::LazyTable(lazy)) => collect_lazy(lazy),
+        Some(Value::Table(table)) => Rc::clone(table),
+        _ => return Value::Null,
+    };
+    let table_ref = table.borrow();
+    let row_count = table_ref.len();
+    table_rows_range(&table_ref, row_count.saturating_sub(n), row_count)
+}
+
+/// table_slice(table, offset, limit) - страница из limit строк, начиная с offset
+/// (без limit - до конца таблицы)
+pub fn native_table_slice(args: &[Value]) -> Value {
+    let offset = match row_count_arg("table_slice", "offset", args.get(1), 0) {
+        Some(offset) => offset,
+        None => return Value::Null,
+    };
+    let limit = match row_count_arg("table_slice", "limit", args.get(2), usize::MAX) {
+        Some(limit) => limit,
+        None => return Value::Null,
+    };
+
+    let table = match args.first() {
+        Some(Value::LazyTable(lazy)) => collect_lazy(lazy),
+        Some(Value::Table(table)) => Rc::clone(table),
+        _ => return Value::Null,
+    };
+    let table_ref = table.borrow();
+    table_rows_range(&table_ref, offset, offset.saturating_add(limit))
+}
+
+pub fn native_table_select(args: &[Value]) -> Value {
+    if args.len() < 2 {
+        return Value::Null;
+    }
+
+    let columns_to_select = match &args[1] {
+        Value::Array(arr) => {
+            let arr_ref = arr.borrow();
+            let mut cols = Vec::new();
+            for val in arr_ref.iter() {
+                match val {
+                    Value::String(s) => cols.push(s.to_string()),
+                    _ => return Value::Null,
                 }
             }
-            _ => return false,
+            cols
+        }
+        _ => return Value::Null,
+    };
+
+    match &args[0] {
+        Value::LazyTable(lazy) => lazy_with_op(lazy, LazyOp::Select(columns_to_select), "table_select"),
+        Value::Table(table) => {
+            let table_ref = table.borrow();
+            let mut new_rows = Vec::new();
+            
+            // Создаем индексы колонок для выборки
+            let mut col_indices = Vec::new();
+            for col_name in &columns_to_select {
+                if let Some(idx) = table_ref.headers.iter().position(|h| h == col_name) {
+                    col_indices.push(idx);
+                } else {
+                    return Value::Null; // Колонка не найдена
+                }
+            }
+            
+            // Создаем новые строки только с выбранными колонками
+            for row in &table_ref.rows {
+                let mut new_row = Vec::new();
+                for &idx in &col_indices {
+                    if idx < row.len() {
+                        new_row.push(row[idx].clone());
+                    } else {
+                        new_row.push(Value::Null);
+                    }
+                }
+                new_rows.push(new_row);
+            }
+            
+            let new_table = Table::from_data(new_rows, Some(columns_to_select));
+            Value::Table(Rc::new(RefCell::new(new_table)))
+        }
+        _ => Value::Null,
+    }
+}
+
+pub fn native_table_sort(args: &[Value]) -> Value {
+    if args.len() < 2 {
+        return Value::Null;
+    }
+
+    let column_name = match &args[1] {
+        Value::String(s) => s.to_string(),
+        _ => return Value::Null,
+    };
+
+    // Флаг по общим правилам истинности; null или отсутствие - по возрастанию
+    let ascending = match args.get(2) {
+        None | Some(Value::Null) => true,
+        Some(value) => value.is_truthy(),
+    };
+
+    match &args[0] {
+        Value::LazyTable(lazy) => lazy_with_op(lazy, LazyOp::Sort { column: column_name, ascending }, "table_sort"),
+        Value::Table(table) => {
+            let table_ref = table.borrow();
+            
+            // Получаем колонку для сортировки
+            let sort_column = match table_ref.get_column(&column_name) {
+                Some(col) => col.clone(),
+                None => return Value::Null,
+            };
+            
+            // Создаем вектор индексов для сортировки
+            let mut indices: Vec<usize> = (0..table_ref.rows.len()).collect();
+            
+            // Сортируем индексы по значениям в колонке
+            indices.sort_by(|&a, &b| {
+                let val_a = &sort_column[a];
+                let val_b = &sort_column[b];
+                
+                let cmp = match (val_a, val_b) {
+                    (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2).unwrap_or(std::cmp::Ordering::Equal),
+                    (Value::Integer(i1), Value::Integer(i2)) => i1.cmp(i2),
+                    (a, b) if a.is_numeric() && b.is_numeric() => {
+                        a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(std::cmp::Ordering::Equal)
+                    }
+                    (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
+                    (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(b2),
+                    (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
+                    (Value::Null, _) => std::cmp::Ordering::Less,
+                    (_, Value::Null) => std::cmp::Ordering::Greater,
+                    _ => val_a.to_string().cmp(&val_b.to_string()),
+                };
+                
+                if ascending {
+                    cmp
+                } else {
+                    cmp.reverse()
+                }
+            });
+            
+            // Создаем новые строки в отсортированном порядке
+            let mut new_rows = Vec::new();
+            for &idx in &indices {
+                if let Some(row) = table_ref.get_row(idx) {
+                    new_rows.push(row.clone());
+                }
+            }
+            
+            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
+            Value::Table(Rc::new(RefCell::new(new_table)))
+        }
+        _ => Value::Null,
+    }
+}
+
+/// table_where(table, col, op, value). Форма table_where(table, expr) со строкой-предикатом
+/// для обычной таблицы выполняется в VM (как table_filter_expr) и сюда не попадает
+pub fn native_table_where(args: &[Value]) -> Value {
+    if args.len() < 4 {
+        crate::websocket::set_native_error_with_type(
+            "table_where() expects (table, col, op, value) or (table, expr) for an eager table".to_string(),
+            crate::common::error::ErrorType::TypeError,
+        );
+        return Value::Null;
+    }
+
+    let column_name = match &args[1] {
+        Value::String(s) => s.to_string(),
+        _ => return Value::Null,
+    };
+
+    let operator = match &args[2] {
+        Value::String(s) => s.as_ref(),
+        _ => return Value::Null,
+    };
+
+    let filter_value = args[3].clone();
+
+    match &args[0] {
+        Value::LazyTable(lazy) => match CompareOp::parse(operator) {
+            Some(op) => {
+                let predicate = Predicate { column: column_name, op, value: filter_value };
+                lazy_with_op(lazy, LazyOp::Filter(predicate), "table_where")
+            }
+            None => {
+                crate::websocket::set_native_error_with_type(
+                    format!("table_where(): unknown operator '{}'", operator),
+                    crate::common::error::ErrorType::ValueError,
+                );
+                Value::Null
+            }
+        },
+        Value::Table(table) => {
+            let table_ref = table.borrow();
+            
+            // Получаем колонку для фильтрации
+            let filter_column = match table_ref.get_column(&column_name) {
+                Some(col) => col.clone(),
+                None => return Value::Null,
+            };
+            
+            // Определяем, какие строки проходят фильтр
+            let mut matching_indices = Vec::new();
+            for (i, val) in filter_column.iter().enumerate() {
+                let matches = match operator {
+                    ">" => compare_values(val, &filter_value) == std::cmp::Ordering::Greater,
+                    "<" => compare_values(val, &filter_value) == std::cmp::Ordering::Less,
+                    ">=" => {
+                        let cmp = compare_values(val, &filter_value);
+                        cmp == std::cmp::Ordering::Greater || cmp == std::cmp::Ordering::Equal
+                    }
+                    "<=" => {
+                        let cmp = compare_values(val, &filter_value);
+                        cmp == std::cmp::Ordering::Less || cmp == std::cmp::Ordering::Equal
+                    }
+                    "==" | "=" => compare_values(val, &filter_value) == std::cmp::Ordering::Equal,
+                    "!=" | "<>" => compare_values(val, &filter_value) != std::cmp::Ordering::Equal,
+                    _ => false,
+                };
+                
+                if matches {
+                    matching_indices.push(i);
+                }
+            }
+            
+            // Создаем новые строки только с подходящими индексами
+            let mut new_rows = Vec::new();
+            for &idx in &matching_indices {
+                if let Some(row) = table_ref.get_row(idx) {
+                    new_rows.push(row.clone());
+                }
+            }
+            
+            let new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
+            Value::Table(Rc::new(RefCell::new(new_table)))
+        }
+        _ => Value::Null,
+    }
+}
+
+/// table_where_regex(table, column, pattern, skip_non_strings = false) - новая таблица из строк,
+/// в которых значение колонки содержит совпадение с регулярным выражением pattern.
+/// Нестроковые значения сравниваются по строковому представлению или, при skip_non_strings,
+/// не проходят фильтр; null не проходит никогда
+pub fn native_table_where_regex(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(Value::String(column)), Some(Value::String(pattern))) =
+        (args.first(), args.get(1), args.get(2))
+    else {
+        set_native_error_with_type(
+            "table_where_regex() expects a table, a column name and a pattern string".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    };
+    let Some(skip_non_strings) = bool_arg("table_where_regex", "skip_non_strings", args.get(3), false) else {
+        return Value::Null;
+    };
+    let regex = match regex::Regex::new(pattern) {
+        Ok(regex) => regex,
+        Err(e) => {
+            set_native_error_with_type(format!("table_where_regex(): invalid pattern: {}", e), ErrorType::ValueError);
+            return Value::Null;
         }
+    };
+    let table = table.borrow();
+    let Some(indices) = column_indices(&table, &[column.to_string()], "table_where_regex") else {
+        return Value::Null;
+    };
+    let index = indices[0];
+
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .filter(|row| match row.get(index) {
+            None | Some(Value::Null) => false,
+            Some(Value::String(text)) => regex.is_match(text),
+            Some(_) if skip_non_strings => false,
+            Some(value) => regex.is_match(&value.to_string()),
+        })
+        .cloned()
+        .collect();
+    let mut result = Table::from_data(rows, Some(table.headers.clone()));
+    result.name = table.name.clone();
+    Value::Table(Rc::new(RefCell::new(result)))
+}
+
+// table_interpolate(table, column, method = "linear") - заполняет Null в колонке
+// linear: линейная интерполяция между соседними числами (крайние Null остаются)
+// forward / backward: протягивание предыдущего / следующего значения
+pub fn native_table_interpolate(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(Value::String(column_name))) = (args.first(), args.get(1)) else {
+        set_native_error_with_type(
+            "table_interpolate() expects a table and a column name".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    };
+    let method = match args.get(2) {
+        Some(Value::String(s)) => s.to_lowercase(),
+        None | Some(Value::Null) => "linear".to_string(),
+        Some(other) => {
+            set_native_error_with_type(
+                format!("table_interpolate() method must be a string, got {}", other.preview(40)),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+
+    let table_ref = table.borrow();
+    if !check_columns_exist(&table_ref, &[column_name.to_string()], "table_interpolate") {
+        return Value::Null;
+    }
+    let column_index = table_ref.headers.iter().position(|h| **h == **column_name).unwrap_or(0);
+    let mut column = table_ref.get_column(column_name).cloned().unwrap_or_default();
+
+    match method.as_str() {
+        "linear" => {
+            // Индекс последнего известного числа перед текущим участком Null
+            let mut previous: Option<(usize, f64)> = None;
+            for i in 0..column.len() {
+                match column[i].as_f64() {
+                    Some(current) => {
+                        if let Some((start, start_value)) = previous {
+                            let gap = i - start;
+                            for (offset, cell) in column[start + 1..i].iter_mut().enumerate() {
+                                let ratio = (offset + 1) as f64 / gap as f64;
+                                *cell = Value::Number(start_value + (current - start_value) * ratio);
+                            }
+                        }
+                        previous = Some((i, current));
+                    }
+                    None if matches!(column[i], Value::Null) => {}
+                    // Нечисловое значение разрывает интерполяцию
+                    _ => previous = None,
+                }
+            }
+        }
+        "forward" | "ffill" => fill_from_neighbours(&mut column, &[], false),
+        "backward" | "bfill" => fill_from_neighbours(&mut column, &[], true),
+        other => {
+            set_native_error_with_type(
+                format!("table_interpolate() method must be \"linear\", \"forward\" or \"backward\", got {}", other),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+    }
+
+    let mut new_rows = table_ref.rows.clone();
+    for (row, value) in new_rows.iter_mut().zip(column) {
+        if column_index < row.len() {
+            row[column_index] = value;
+        }
+    }
+
+    let mut new_table = Table::from_data(new_rows, Some(table_ref.headers.clone()));
+    new_table.name = table_ref.name.clone();
+    Value::Table(Rc::new(RefCell::new(new_table)))
+}
+
+/// Заполняет null ближайшим непустым значением: forward - предыдущим, backward - следующим.
+/// groups - ключ группы каждой строки (partition_by): значение не переходит между группами;
+/// пустой срез - вся колонка одна группа. null без соседа в своей группе остается null
+fn fill_from_neighbours(column: &mut [Value], groups: &[Vec<Value>], backward: bool) {
+    use std::collections::HashMap;
+
+    let no_group = Vec::new();
+    let mut last: HashMap<&Vec<Value>, Value> = HashMap::new();
+    let length = column.len();
+    let order: Box<dyn Iterator<Item = usize>> = if backward { Box::new((0..length).rev()) } else { Box::new(0..length) };
+    for row in order {
+        let key = groups.get(row).unwrap_or(&no_group);
+        let cell = &mut column[row];
+        if !matches!(cell, Value::Null) {
+            last.insert(key, cell.clone());
+        } else if let Some(value) = last.get(key) {
+            *cell = value.clone();
+        }
+    }
+}
+
+// Ленивые таблицы (table_lazy, collect)
+
+thread_local! {
+    // Статистика последнего выполнения ленивой таблицы (для last_collect_report())
+    static LAST_COLLECT_REPORT: RefCell<Option<LazyStats>> = const { RefCell::new(None) };
+}
+
+/// Добавляет операцию к плану ленивой таблицы; неизвестная колонка - ColumnNotFoundError
+fn lazy_with_op(lazy: &LazyTable, op: LazyOp, function: &str) -> Value {
+    match lazy.with_op(op) {
+        Ok(next) => Value::LazyTable(Rc::new(next)),
+        Err(message) => {
+            crate::websocket::set_native_error_with_type(
+                format!("{}(): {}", function, message),
+                crate::common::error::ErrorType::ColumnNotFoundError,
+            );
+            Value::Null
+        }
+    }
+}
+
+/// Выполняет ленивую таблицу и запоминает статистику для last_collect_report().
+/// Ошибка чтения CSV источника сохраняется как ошибка вызова, результат - пустая таблица
+pub fn collect_lazy(lazy: &LazyTable) -> Rc<RefCell<Table>> {
+    match lazy.try_collect() {
+        Ok((table, stats)) => {
+            LAST_COLLECT_REPORT.with(|r| *r.borrow_mut() = Some(stats));
+            Rc::new(RefCell::new(table))
+        }
+        Err(message) => {
+            crate::websocket::set_native_error(message);
+            Rc::new(RefCell::new(Table::from_data(Vec::new(), Some(lazy.headers.clone()))))
+        }
+    }
+}
+
+/// table_lazy(table) - ленивая таблица: следующие table_where/table_select/table_sort/table_head
+/// только добавляют операции, выполнение - в collect()
+pub fn native_table_lazy(args: &[Value]) -> Value {
+    match args.first() {
+        Some(Value::Table(table)) => Value::LazyTable(Rc::new(LazyTable::new(Rc::clone(table)))),
+        Some(Value::LazyTable(lazy)) => Value::LazyTable(Rc::clone(lazy)),
+        _ => {
+            crate::websocket::set_native_error_with_type(
+                "table_lazy() expects a table".to_string(),
+                crate::common::error::ErrorType::TypeError,
+            );
+            Value::Null
+        }
+    }
+}
+
+/// collect(lazy) - выполняет план ленивой таблицы одним проходом; обычная таблица возвращается как есть
+pub fn native_collect(args: &[Value]) -> Value {
+    match args.first() {
+        Some(Value::LazyTable(lazy)) => Value::Table(collect_lazy(lazy)),
+        Some(Value::Table(table)) => Value::Table(Rc::clone(table)),
+        _ => {
+            crate::websocket::set_native_error_with_type(
+                "collect() expects a lazy table".to_string(),
+                crate::common::error::ErrorType::TypeError,
+            );
+            Value::Null
+        }
+    }
+}
+
+/// last_collect_report() - статистика последнего collect(): просмотрено и получено строк, стадии плана
+pub fn native_last_collect_report(_args: &[Value]) -> Value {
+    LAST_COLLECT_REPORT.with(|r| {
+        r.borrow().as_ref().map_or(Value::Null, |stats| {
+            let mut report = ObjectMap::new();
+            report.insert("rows_scanned".to_string(), Value::Number(stats.rows_scanned as f64));
+            report.insert("rows_produced".to_string(), Value::Number(stats.rows_produced as f64));
+            let stages = stats.stages.iter().map(|s| Value::String(s.as_str().into())).collect();
+            report.insert("stages".to_string(), Value::Array(Rc::new(RefCell::new(stages))));
+            Value::Object(report)
+        })
+    })
+}
+
+/// show_table(table, limit, columns, max_col_width, max_rows, format, index) - выводит таблицу
+/// в рамке, markdown или CSV. Вывод идет туда же, куда print(): в stdout или перехваченный буфер
+pub fn native_show_table(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::common::output_capture::OutputCapture;
+    use crate::common::render::{RenderFormat, RenderOptions, DEFAULT_MAX_ROWS};
+    use crate::websocket::set_native_error_with_type;
+
+    let table = match args.first() {
+        Some(Value::Table(table)) => Rc::clone(table),
+        // Ленивая таблица вычисляется перед выводом
+        Some(Value::LazyTable(lazy)) => collect_lazy(lazy),
+        _ => return Value::Null,
+    };
+    let format = match args.get(5) {
+        None | Some(Value::Null) => RenderFormat::Grid,
+        Some(Value::String(name)) => match RenderFormat::parse(name) {
+            Some(format) => format,
+            None => {
+                set_native_error_with_type(
+                    format!("show_table() format must be \"grid\", \"markdown\" or \"csv\", got \"{}\"", name),
+                    ErrorType::ValueError,
+                );
+                return Value::Null;
+            }
+        },
+        Some(_) => {
+            set_native_error_with_type("show_table() format must be a string".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    // max_rows - то же, что limit; без них CSV выводится целиком
+    let (rows_name, rows_value) = match args.get(4) {
+        Some(value) if !matches!(value, Value::Null) => ("max_rows", Some(value)),
+        _ => ("limit", args.get(1)),
+    };
+    let max_rows = match rows_value {
+        None | Some(Value::Null) if format == RenderFormat::Csv => None,
+        None | Some(Value::Null) => Some(DEFAULT_MAX_ROWS),
+        value => match row_count_arg("show_table", rows_name, value, 0) {
+            Some(rows) => Some(rows),
+            None => return Value::Null,
+        },
+    };
+    let max_col_width = match args.get(3) {
+        None | Some(Value::Null) => None,
+        value => match row_count_arg("show_table", "max_col_width", value, 0) {
+            Some(0) => {
+                set_native_error_with_type("show_table() max_col_width must be at least 1".to_string(), ErrorType::ValueError);
+                return Value::Null;
+            }
+            Some(width) => Some(width),
+            None => return Value::Null,
+        },
+    };
+    let Some(index) = bool_arg("show_table", "index", args.get(6), false) else {
+        return Value::Null;
+    };
+    let table_ref = table.borrow();
+    let columns = match args.get(2) {
+        None | Some(Value::Null) => None,
+        Some(value) => {
+            let names = match value {
+                Value::String(name) => vec![name.to_string()],
+                Value::Array(arr) if arr.borrow().iter().all(|v| matches!(v, Value::String(_))) => {
+                    arr.borrow().iter().map(|v| v.to_string()).collect()
+                }
+                _ => {
+                    set_native_error_with_type(
+                        "show_table() columns must be a column name or an array of names".to_string(),
+                        ErrorType::TypeError,
+                    );
+                    return Value::Null;
+                }
+            };
+            match column_indices(&table_ref, &names, "show_table") {
+                Some(indices) => Some(indices),
+                None => return Value::Null,
+            }
+        }
+    };
+
+    let options = RenderOptions { format, columns, max_col_width, max_rows, index };
+    let text = render::render_table(&table_ref, &options);
+    // write_output добавляет перевод строки сам
+    OutputCapture::write_output(text.strip_suffix('\n').unwrap_or(&text));
+    Value::Null
+}
+
+pub fn native_merge_tables(args: &[Value]) -> Value {
+    use std::collections::HashSet;
+    
+    if args.is_empty() {
+        return Value::Null;
+    }
+
+    // Извлекаем массив таблиц
+    let tables_array = match &args[0] {
+        Value::Array(arr) => arr.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Обработка пустого массива
+    if tables_array.is_empty() {
+        return Value::Null;
+    }
+
+    // Определяем режим (по умолчанию "outer")
+    let mode = if args.len() > 1 {
+        match &args[1] {
+            Value::String(s) => s.as_ref(),
+            _ => "outer",
+        }
+    } else {
+        "outer"
+    };
+
+    // Извлекаем таблицы из массива
+    let mut tables = Vec::new();
+    for val in &tables_array {
+        match val {
+            Value::Table(table) => {
+                tables.push(table.clone());
+            }
+            _ => return Value::Null, // Все элементы должны быть таблицами
+        }
+    }
+
+    // Если только одна таблица, возвращаем её копию
+    if tables.len() == 1 {
+        let table_ref = tables[0].borrow();
+        let new_table = Table::from_data(table_ref.rows.clone(), Some(table_ref.headers.clone()));
+        return Value::Table(Rc::new(RefCell::new(new_table)));
+    }
+
+    // Собираем все уникальные колонки
+    let mut all_columns_set = HashSet::new();
+    let mut column_order = Vec::new();
+    
+    // Сначала добавляем колонки первой таблицы для сохранения порядка
+    let first_table = tables[0].borrow();
+    for header in &first_table.headers {
+        if all_columns_set.insert(header.clone()) {
+            column_order.push(header.clone());
+        }
+    }
+    
+    // Затем добавляем колонки из остальных таблиц
+    for table_rc in &tables[1..] {
+        let table_ref = table_rc.borrow();
+        for header in &table_ref.headers {
+            if all_columns_set.insert(header.clone()) {
+                column_order.push(header.clone());
+            }
+        }
+    }
+
+    // Для inner mode - оставляем только колонки, присутствующие во всех таблицах
+    let result_columns = if mode == "inner" {
+        let mut common_columns = Vec::new();
+        for col in &column_order {
+            let mut in_all = true;
+            for table_rc in &tables {
+                let table_ref = table_rc.borrow();
+                if !table_ref.headers.contains(col) {
+                    in_all = false;
+                    break;
+                }
+            }
+            if in_all {
+                common_columns.push(col.clone());
+            }
+        }
+        common_columns
+    } else {
+        column_order
+    };
+
+    // Создаем объединенные строки
+    let mut merged_rows = Vec::new();
+    
+    for table_rc in &tables {
+        let table_ref = table_rc.borrow();
+        
+        // Для каждой строки в таблице
+        for row in &table_ref.rows {
+            let mut new_row = Vec::new();
+            
+            // Для каждой колонки в результате
+            for col_name in &result_columns {
+                // Находим индекс колонки в исходной таблице
+                if let Some(col_idx) = table_ref.headers.iter().position(|h| h == col_name) {
+                    // Берем значение из соответствующей позиции в строке
+                    if col_idx < row.len() {
+                        new_row.push(row[col_idx].clone());
+                    } else {
+                        new_row.push(Value::Null);
+                    }
+                } else {
+                    // Колонки нет в этой таблице - добавляем null
+                    new_row.push(Value::Null);
+                }
+            }
+            
+            merged_rows.push(new_row);
+        }
+    }
+
+    // Создаем результирующую таблицу
+    let merged_table = Table::from_data(merged_rows, Some(result_columns));
+    Value::Table(Rc::new(RefCell::new(merged_table)))
+}
+
+pub fn native_now(_args: &[Value]) -> Value {
+    // Возвращаем текущее время в формате RFC3339 (ISO 8601)
+    // Формат: YYYY-MM-DDTHH:MM:SSZ
+    let now = Utc::now();
+    Value::String(now.format("%Y-%m-%dT%H:%M:%SZ").to_string().into())
+}
+
+/// Безопасное получение parent пути в режиме --use-ve
+/// Возвращает parent только если он находится внутри папки сессии
+/// Если parent выходит за пределы папки сессии, возвращает None (не позволяет получить доступ к путям вне сессии)
+pub fn safe_path_parent(path: &PathBuf) -> Option<PathBuf> {
+    use crate::websocket::{get_user_session_path, get_use_ve};
+    
+    if !get_use_ve() {
+        // В обычном режиме просто возвращаем parent как есть
+        return path.parent().map(|p| p.to_path_buf());
+    }
+    
+    let session_path = match get_user_session_path() {
+        Some(p) => p,
+        None => return None, // Нет пути сессии - не возвращаем parent
+    };
+    
+    // Нормализуем session_path для корректного сравнения
+    let session_path_normalized = match session_path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => session_path.clone(),
+    };
+    
+    // Получаем parent путь
+    let parent = match path.parent() {
+        Some(p) => p.to_path_buf(),
+        None => return None,
+    };
+    
+    // Канонизируем parent для проверки
+    let parent_normalized = match parent.canonicalize() {
+        Ok(p) => p,
+        Err(_) => parent.clone(),
+    };
+    
+    // Проверяем, что parent находится внутри папки сессии
+    if parent_normalized.starts_with(&session_path_normalized) {
+        // Если parent уже является самой папкой сессии, не возвращаем parent (защита от выхода наружу)
+        if parent_normalized == session_path_normalized {
+            None
+        } else {
+            Some(parent_normalized)
+        }
+    } else {
+        // Parent выходит за пределы сессии - возвращаем None (не позволяем доступ к путям вне сессии)
+        None
+    }
+}
+
+/// Проверка ExecutionPolicy перед чтением (write = false) или записью файла функцией name.
+/// При запрете сохраняет PermissionError и возвращает false; разрешенное чтение отмечается
+/// для cached_eval()
+fn file_access_allowed(name: &str, path: &Path, write: bool) -> bool {
+    match crate::vm::policy::get_execution_policy().file_access_denial(path, write) {
+        Some(reason) => {
+            crate::websocket::set_native_error_with_type(
+                format!("{}(): {}", name, reason),
+                crate::common::error::ErrorType::PermissionError,
+            );
+            false
+        }
+        None => {
+            if !write {
+                crate::watch::record_read(path);
+            }
+            true
+        }
+    }
+}
+
+/// Безопасное разрешение пути относительно папки сессии в режиме --use-ve
+fn resolve_path_in_session(path: &Path) -> Result<PathBuf, String> {
+    use crate::websocket::{confine_to_session, get_use_ve};
+    
+    if !get_use_ve() {
+        // В обычном режиме просто возвращаем путь как есть
+        return Ok(path.to_path_buf());
+    }
+    
+    // Пустой путь и "." - папка сессии; остальные пути проверяются по каноническому виду
+    confine_to_session(&crate::common::path::normalize_separators(path))
+}
+
+pub fn native_getcwd(_args: &[Value]) -> Value {
+    use crate::websocket::{get_use_ve};
+    
+    // Если включен режим use_ve, возвращаем пустой путь для безопасности
+    if get_use_ve() {
+        Value::Path(PathBuf::new()) // Пустой путь в режиме use_ve для безопасности
+    } else {
+        // Возвращаем текущую рабочую директорию
+        match env::current_dir() {
+            Ok(path) => Value::Path(path),
+            Err(_) => Value::Path(PathBuf::new()), // При ошибке возвращаем пустой путь
+        }
+    }
+}
+
+pub fn native_list_files(args: &[Value]) -> Value {
+    if args.is_empty() {
+        return Value::Array(Rc::new(RefCell::new(Vec::new())));
+    }
+
+    // Первый аргумент - путь к директории
+    let dir_path = match &args[0] {
+        Value::Path(p) => p.clone(),
+        Value::String(s) => from_user_str(s),
+        _ => return Value::Array(Rc::new(RefCell::new(Vec::new()))),
+    };
+    if !file_access_allowed("list_files", &dir_path, false) {
+        return Value::Null;
+    }
+
+    let dir_path_str = dir_path.to_string_lossy().to_string();
+
+    // Проверяем, является ли это SMB путем (lib://)
+    if dir_path_str.starts_with("lib://") {
+        // Извлекаем имя шары и путь к директории
+        let path_without_prefix = &dir_path_str[6..]; // Убираем "lib://"
+        let parts: Vec<&str> = path_without_prefix.splitn(2, '/').collect();
+        
+        if parts.is_empty() {
+            return Value::Array(Rc::new(RefCell::new(Vec::new())));
+        }
+        
+        let share_name = parts[0];
+        let dir_path_on_share = if parts.len() > 1 { parts[1] } else { "" };
+        
+        // SmbManager WebSocket клиента или общий менеджер процесса
+        let smb_manager = crate::vm::file_ops::get_smb_manager();
+        let files = smb_manager.lock().unwrap().list_files(share_name, dir_path_on_share);
+        match files {
+            Ok(files) => {
+                let file_values: Vec<Value> = files.iter()
+                    .map(|f| {
+                        // Конструируем полный путь для SMB
+                        let full_path = if dir_path_on_share.is_empty() {
+                            format!("lib://{}/{}", share_name, f)
+                        } else {
+                            format!("lib://{}/{}/{}", share_name, dir_path_on_share, f)
+                        };
+                        Value::Path(PathBuf::from(full_path))
+                    })
+                    .collect();
+                Value::Array(Rc::new(RefCell::new(file_values)))
+            }
+            Err(_) => Value::Array(Rc::new(RefCell::new(Vec::new()))),
+        }
+    } else {
+        // Обычная локальная директория
+        // Разрешаем путь относительно папки сессии в режиме --use-ve
+        let resolved_path = match resolve_path_in_session(&dir_path) {
+            Ok(p) => p,
+            Err(err_msg) => {
+                // При ошибке безопасности сохраняем сообщение об ошибке
+                use crate::websocket::set_native_error;
+                set_native_error(err_msg);
+                return Value::Array(Rc::new(RefCell::new(Vec::new())));
+            }
+        };
+        
+        if !resolved_path.exists() || !resolved_path.is_dir() {
+            return Value::Array(Rc::new(RefCell::new(Vec::new())));
+        }
+        
+        match fs::read_dir(&resolved_path) {
+            Ok(entries) => {
+                let mut files = Vec::new();
+                for entry in entries {
+                    if let Ok(entry) = entry {
+                        let entry_path = entry.path();
+                        if let Some(file_name) = entry_path.file_name() {
+                            if let Some(name_str) = file_name.to_str() {
+                                // Пропускаем служебные файлы
+                                if !name_str.starts_with(".") && name_str != ".DS_Store" {
+                                    files.push(Value::Path(entry_path));
+                                }
+                            }
+                        }
+                    }
+                }
+                Value::Array(Rc::new(RefCell::new(files)))
+            }
+            Err(_) => Value::Array(Rc::new(RefCell::new(Vec::new()))),
+        }
+    }
+}
+
+/// Строковые аргументы smb_* функций по именам names; None (с TypeError) для пропущенного
+/// или нестрокового аргумента. Значения аргументов в текст ошибки не попадают
+fn smb_string_args<'a>(function: &str, names: &[&str], args: &'a [Value]) -> Option<Vec<&'a str>> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| match args.get(i) {
+            Some(Value::String(s)) => Some(s.as_ref()),
+            _ => {
+                crate::websocket::set_native_error_with_type(
+                    format!("{}() {} must be a string", function, name),
+                    crate::common::error::ErrorType::TypeError,
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// smb_connect(ip, login, password, domain, share) - подключает SMB шару для read_file("lib://share/...")
+/// и smb_* функций. Вне WebSocket сервера подключение общее для процесса. Возвращает путь lib://share
+pub fn native_smb_connect(args: &[Value]) -> Value {
+    use crate::websocket::smb::{error_type, redact_credentials, SmbConnection};
+
+    let values = match smb_string_args("smb_connect", &["ip", "login", "password", "domain", "share"], args) {
+        Some(values) => values,
+        None => return Value::Null,
+    };
+    if !crate::vm::policy::get_execution_policy().allow_smb {
+        crate::websocket::set_native_error_with_type(
+            "smb_connect(): SMB access is disabled by the execution policy".to_string(),
+            crate::common::error::ErrorType::PermissionError,
+        );
+        return Value::Null;
+    }
+    let connection = SmbConnection::new(
+        values[0].to_string(),
+        values[1].to_string(),
+        values[2].to_string(),
+        values[3].to_string(),
+        values[4].to_string(),
+    );
+    let share_path = connection.get_lib_path();
+    let result = crate::vm::file_ops::get_smb_manager().lock().unwrap().connect(connection.clone());
+    match result {
+        Ok(_) => Value::Path(PathBuf::from(share_path)),
+        Err(e) => {
+            // Реализация подключения может вернуть вывод smbclient с учетными данными
+            let message = redact_credentials(&e, &connection);
+            crate::websocket::set_native_error_with_type(format!("smb_connect(): {}", message), error_type(&message));
+            Value::Null
+        }
+    }
+}
+
+/// smb_list(share, path = "") - имена файлов и папок в папке path подключенной шары
+pub fn native_smb_list(args: &[Value]) -> Value {
+    let (share, path) = match args.get(1) {
+        None | Some(Value::Null) => match smb_string_args("smb_list", &["share"], args) {
+            Some(values) => (values[0], ""),
+            None => return Value::Null,
+        },
+        Some(_) => match smb_string_args("smb_list", &["share", "path"], args) {
+            Some(values) => (values[0], values[1]),
+            None => return Value::Null,
+        },
+    };
+    if !file_access_allowed("smb_list", Path::new(&format!("lib://{}/{}", share, path)), false) {
+        return Value::Null;
+    }
+    let result = crate::vm::file_ops::get_smb_manager().lock().unwrap().list_files(share, path);
+    match result {
+        Ok(names) => Value::Array(Rc::new(RefCell::new(names.into_iter().map(|name| Value::String(name.into())).collect()))),
+        Err(e) => {
+            crate::websocket::set_native_error_with_type(format!("smb_list(): {}", e), crate::websocket::smb::error_type(&e));
+            Value::Null
+        }
+    }
+}
+
+/// smb_read(share, path) - содержимое файла шары строкой; файл не в UTF-8 возвращается
+/// как "base64:<данные>", в том же виде, что и ответ smb_read_file WebSocket сервера
+pub fn native_smb_read(args: &[Value]) -> Value {
+    use base64::Engine;
+
+    let values = match smb_string_args("smb_read", &["share", "path"], args) {
+        Some(values) => values,
+        None => return Value::Null,
+    };
+    let (share, path) = (values[0], values[1]);
+    if !file_access_allowed("smb_read", Path::new(&format!("lib://{}/{}", share, path)), false) {
+        return Value::Null;
+    }
+    let result = crate::vm::file_ops::get_smb_manager().lock().unwrap().read_file(share, path);
+    match result {
+        Ok(content) => match String::from_utf8(content) {
+            Ok(text) => Value::String(text.into()),
+            Err(e) => {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(e.as_bytes());
+                Value::String(format!("base64:{}", encoded).into())
+            }
+        },
+        Err(e) => {
+            crate::websocket::set_native_error_with_type(format!("smb_read(): {}", e), crate::websocket::smb::error_type(&e));
+            Value::Null
+        }
+    }
+}
+
+/// glob(pattern) - отсортированный массив путей, подходящих под шаблон ("data/*.csv", "logs/**/*.txt").
+/// Часть шаблона до первого компонента с '*', '?' или '[' - обычный путь; в режиме --use-ve он
+/// разрешается от папки сессии и не может выйти за ее пределы, совпадения вне сессии отбрасываются
+pub fn native_glob(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::common::path::{normalize_separators, SMB_PREFIX};
+    use crate::websocket::{confine_to_session, get_use_ve, set_native_error_with_type};
+
+    let pattern = match args.first().and_then(path_arg) {
+        Some(p) => normalize_separators(&p),
+        None => {
+            set_native_error_with_type("glob() expects a pattern string or path".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+    if pattern.to_string_lossy().starts_with(SMB_PREFIX) {
+        set_native_error_with_type("glob() does not support lib:// paths".to_string(), ErrorType::ValueError);
+        return Value::Null;
+    }
+    if !file_access_allowed("glob", &pattern, false) {
+        return Value::Null;
+    }
+
+    // Делим шаблон на базовую папку без спецсимволов и остаток с ними
+    let mut base = PathBuf::new();
+    let mut rest = PathBuf::new();
+    for component in pattern.components() {
+        let text = component.as_os_str().to_string_lossy();
+        if rest.as_os_str().is_empty() && !text.contains(['*', '?', '[']) {
+            base.push(component);
+        } else {
+            rest.push(component);
+        }
+    }
+    let base = match resolve_path_in_session(&base) {
+        Ok(p) => p,
+        Err(msg) => {
+            set_native_error_with_type(format!("glob(): {}", msg), ErrorType::PermissionError);
+            return Value::Null;
+        }
+    };
+
+    // Спецсимволы в имени базовой папки экранируются, чтобы совпадать буквально
+    let mut full_pattern = glob::Pattern::escape(&base.to_string_lossy());
+    if !rest.as_os_str().is_empty() {
+        if !full_pattern.is_empty() && !full_pattern.ends_with(['/', std::path::MAIN_SEPARATOR]) {
+            full_pattern.push(std::path::MAIN_SEPARATOR);
+        }
+        full_pattern.push_str(&rest.to_string_lossy());
+    }
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+    let entries = match glob::glob_with(&full_pattern, options) {
+        Ok(entries) => entries,
+        Err(e) => {
+            set_native_error_with_type(format!("glob(): invalid pattern: {}", e), ErrorType::ValueError);
+            return Value::Null;
+        }
+    };
+
+    let use_ve = get_use_ve();
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|p| !use_ve || confine_to_session(p).is_ok())
+        .collect();
+    paths.sort();
+    Value::Array(Rc::new(RefCell::new(paths.into_iter().map(Value::Path).collect())))
+}
+
+// ============================================================================
+// JOIN Operations Infrastructure
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JoinType {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+    Semi,
+    Anti,
+}
+
+#[derive(Debug, Clone)]
+struct JoinKey {
+    left_col: String,
+    right_col: String,
+}
+
+// Парсинг ключей JOIN из Value
+// Поддерживает форматы:
+// - String: имя колонки (автоматическое сопоставление)
+// - Array с кортежами: [("left_col", "right_col"), ...]
+// - Array с одним кортежем: [("left_col", "right_col")]
+fn parse_join_keys(value: &Value, left_table: &Table, right_table: &Table) -> Result<Vec<JoinKey>, String> {
+    match value {
+        Value::String(col_name) => {
+            // Автоматическое сопоставление: ищем колонку с таким именем в обеих таблицах
+            if left_table.get_column(col_name).is_some() && right_table.get_column(col_name).is_some() {
+                Ok(vec![JoinKey {
+                    left_col: col_name.to_string(),
+                    right_col: col_name.to_string(),
+                }])
+            } else {
+                Err(format!("Column '{}' not found in both tables", col_name))
+            }
+        }
+        Value::Array(arr) => {
+            let arr_ref = arr.borrow();
+            if arr_ref.is_empty() {
+                return Err("Join keys array cannot be empty".to_string());
+            }
+            
+            let mut keys = Vec::new();
+            for item in arr_ref.iter() {
+                match item {
+                    Value::Array(tuple) => {
+                        let tuple_ref = tuple.borrow();
+                        if tuple_ref.len() != 2 {
+                            return Err("Join key tuple must have exactly 2 elements".to_string());
+                        }
+                        let left_col = match &tuple_ref[0] {
+                            Value::String(s) => s.to_string(),
+                            _ => return Err("Join key must be a string".to_string()),
+                        };
+                        let right_col = match &tuple_ref[1] {
+                            Value::String(s) => s.to_string(),
+                            _ => return Err("Join key must be a string".to_string()),
+                        };
+                        
+                        if left_table.get_column(&left_col).is_none() {
+                            return Err(format!("Column '{}' not found in left table", left_col));
+                        }
+                        if right_table.get_column(&right_col).is_none() {
+                            return Err(format!("Column '{}' not found in right table", right_col));
+                        }
+                        
+                        keys.push(JoinKey { left_col, right_col });
+                    }
+                    Value::String(col_name) => {
+                        // Одиночная строка в массиве - автоматическое сопоставление
+                        if left_table.get_column(col_name).is_some() && right_table.get_column(col_name).is_some() {
+                            keys.push(JoinKey {
+                                left_col: col_name.to_string(),
+                                right_col: col_name.to_string(),
+                            });
+                        } else {
+                            return Err(format!("Column '{}' not found in both tables", col_name));
+                        }
+                    }
+                    _ => return Err("Join key must be a string or tuple [string, string]".to_string()),
+                }
+            }
+            Ok(keys)
+        }
+        _ => Err("Join keys must be a string or array of tuples".to_string()),
+    }
+}
+
+// Применение алиасов таблиц к именам колонок для разрешения коллизий
+// Формат: <table_alias>.<column> (например, "left.id", "right.id")
+fn apply_column_aliases(
+    headers: &[String],
+    alias: &str,
+    existing_names: &std::collections::HashSet<String>,
+) -> Vec<String> {
+    headers
+        .iter()
+        .map(|h| {
+            if existing_names.contains(h) {
+                format!("{}.{}", alias, h)  // Конфликт: left.id
+            } else {
+                h.clone()  // Нет конфликта: name
+            }
+        })
+        .collect()
+}
+
+// Сравнение значений для JOIN (с учетом nulls_equal)
+// Примечание: в текущей реализации JOIN используется хеш-таблица (build_right_hash_table),
+// поэтому эти функции не используются. Они оставлены на случай будущей реализации
+// non-equi joins или других типов JOIN, где может понадобиться прямое сравнение.
+#[allow(dead_code)]
+fn values_match(left_val: &Value, right_val: &Value, nulls_equal: bool) -> bool {
+    match (left_val, right_val) {
+        (Value::Null, Value::Null) => nulls_equal,
+        (Value::Null, _) | (_, Value::Null) => false,
+        _ => left_val == right_val,
+    }
+}
+
+// Сравнение ключей для JOIN
+// Примечание: в текущей реализации JOIN используется хеш-таблица (build_right_hash_table),
+// поэтому эта функция не используется. Она оставлена на случай будущей реализации
+// non-equi joins или других типов JOIN, где может понадобиться прямое сравнение.
+#[allow(dead_code)]
+fn keys_match(left_row: &[Value], right_row: &[Value], keys: &[JoinKey], 
+              left_table: &Table, right_table: &Table, nulls_equal: bool) -> bool {
+    for key in keys {
+        let left_idx = left_table.headers.iter().position(|h| h == &key.left_col);
+        let right_idx = right_table.headers.iter().position(|h| h == &key.right_col);
+        
+        match (left_idx, right_idx) {
+            (Some(li), Some(ri)) => {
+                let left_val = &left_row[li];
+                let right_val = &right_row[ri];
+                if !values_match(left_val, right_val, nulls_equal) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+// Построение хеш-таблицы для правой таблицы по ключам
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct KeyHash {
+    values: Vec<Value>,
+}
+
+fn build_right_hash_table(
+    right_table: &Table,
+    keys: &[JoinKey],
+    nulls_equal: bool,
+) -> HashMap<KeyHash, Vec<usize>> {
+    let mut hash_map = HashMap::new();
+    
+    for (row_idx, row) in right_table.rows.iter().enumerate() {
+        let mut key_values = Vec::new();
+        let mut valid_key = true;
+        
+        for key in keys {
+            if let Some(col_idx) = right_table.headers.iter().position(|h| h == &key.right_col) {
+                if col_idx < row.len() {
+                    let val = &row[col_idx];
+                    // Если nulls_equal=false и значение NULL, пропускаем эту строку
+                    if matches!(val, Value::Null) && !nulls_equal {
+                        valid_key = false;
+                        break;
+                    }
+                    key_values.push(val.clone());
+                } else {
+                    valid_key = false;
+                    break;
+                }
+            } else {
+                valid_key = false;
+                break;
+            }
+        }
+        
+        if valid_key {
+            let key_hash = KeyHash { values: key_values };
+            hash_map.entry(key_hash).or_insert_with(Vec::new).push(row_idx);
+        }
+    }
+    
+    hash_map
+}
+
+// Извлечение ключа из строки левой таблицы
+fn extract_left_key(left_row: &[Value], keys: &[JoinKey], left_table: &Table) -> Option<KeyHash> {
+    let mut key_values = Vec::new();
+    
+    for key in keys {
+        if let Some(col_idx) = left_table.headers.iter().position(|h| h == &key.left_col) {
+            if col_idx < left_row.len() {
+                key_values.push(left_row[col_idx].clone());
+            } else {
+                return None;
+            }
+        } else {
+            return None;
+        }
+    }
+    
+    Some(KeyHash { values: key_values })
+}
+
+// Универсальная функция JOIN для таблиц
+// join(left: Table, right: Table, on: JoinKeys, type: JoinType = "inner", 
+//      suffixes: (string, string) = ("_left", "_right"), nulls_equal: boolean = false) -> Table
+pub fn native_table_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+
+    // Извлекаем left и right таблицы
+    let left_table = match &args[0] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+    
+    let right_table = match &args[1] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Парсим тип JOIN (по умолчанию inner) - нужно проверить ДО парсинга ключей
+    let join_type = if args.len() > 3 {
+        match &args[3] {
+            Value::String(s) => match s.as_ref() {
+                "inner" => JoinType::Inner,
+                "left" => JoinType::Left,
+                "right" => JoinType::Right,
+                "full" => JoinType::Full,
+                "cross" => JoinType::Cross,
+                "semi" => JoinType::Semi,
+                "anti" => JoinType::Anti,
+                _ => JoinType::Inner,
+            },
+            _ => JoinType::Inner,
+        }
+    } else {
+        JoinType::Inner
+    };
+
+    // Для CROSS JOIN не нужны ключи - пропускаем парсинг
+    let keys = if join_type == JoinType::Cross {
+        Vec::new()
+    } else {
+        // Парсим параметр on (ключи)
+        match parse_join_keys(&args[2], &left_table, &right_table) {
+            Ok(k) => k,
+            Err(err_msg) => {
+                use crate::websocket::set_native_error;
+                set_native_error(err_msg);
+                return Value::Null;
+            }
+        }
+    };
+
+    // Используем имена таблиц из table.name, если они установлены, иначе используем переданные алиасы или значения по умолчанию
+    let left_alias = left_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 4 {
+                match &args[4] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 1 {
+                            match &arr_ref[0] {
+                                Value::String(s) => s.to_string(),
+                                _ => "left".to_string(),
+                            }
+                        } else {
+                            "left".to_string()
+                        }
+                    }
+                    _ => "left".to_string(),
+                }
+            } else {
+                "left".to_string()
+            }
+        });
+    
+    let right_alias = right_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 4 {
+                match &args[4] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 2 {
+                            match &arr_ref[1] {
+                                Value::String(s) => s.to_string(),
+                                _ => "right".to_string(),
+                            }
+                        } else {
+                            "right".to_string()
+                        }
+                    }
+                    _ => "right".to_string(),
+                }
+            } else {
+                "right".to_string()
+            }
+        });
+
+    // Парсим nulls_equal (по умолчанию false)
+    let nulls_equal = args.get(5).is_some_and(Value::is_truthy);
+
+    // CROSS JOIN - особый случай (декартово произведение)
+    if join_type == JoinType::Cross {
+        return perform_cross_join(&left_table, &right_table, &left_alias, &right_alias);
+    }
+
+    // Для остальных типов JOIN нужны ключи
+    if keys.is_empty() {
+        return Value::Null;
+    }
+
+    // Выполняем JOIN в зависимости от типа
+    match join_type {
+        JoinType::Inner => perform_inner_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
+        JoinType::Left => perform_left_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
+        JoinType::Right => perform_right_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
+        JoinType::Full => perform_full_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
+        JoinType::Semi => perform_semi_join(&left_table, &right_table, &keys, nulls_equal),
+        JoinType::Anti => perform_anti_join(&left_table, &right_table, &keys, nulls_equal),
+        JoinType::Cross => unreachable!(), // Уже обработано выше
+    }
+}
+
+// Выполнение INNER JOIN
+fn perform_inner_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    left_alias: &str,
+    right_alias: &str,
+    nulls_equal: bool,
+) -> Value {
+    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
+    
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+    
+    // Проходим по левой таблице и ищем совпадения
+    for left_row in left_table.rows.iter() {
+        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
+            if let Some(right_indices) = right_hash.get(&left_key) {
+                for &right_idx in right_indices {
+                    if let Some(right_row) = right_table.get_row(right_idx) {
+                        let mut new_row = left_row.clone();
+                        new_row.extend_from_slice(right_row);
+                        result_rows.push(new_row);
+                    }
+                }
+            }
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+}
+
+// Выполнение LEFT JOIN
+fn perform_left_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    left_alias: &str,
+    right_alias: &str,
+    nulls_equal: bool,
+) -> Value {
+    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
+    
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+    
+    // Создаем NULL-строку для правой таблицы
+    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
+    
+    // Проходим по левой таблице
+    for left_row in left_table.rows.iter() {
+        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
+            if let Some(right_indices) = right_hash.get(&left_key) {
+                // Есть совпадения - добавляем все совпадения
+                for &right_idx in right_indices {
+                    if let Some(right_row) = right_table.get_row(right_idx) {
+                        let mut new_row = left_row.clone();
+                        new_row.extend_from_slice(right_row);
+                        result_rows.push(new_row);
+                    }
+                }
+            } else {
+                // Нет совпадений - добавляем строку с NULL справа
+                let mut new_row = left_row.clone();
+                new_row.extend_from_slice(&null_right_row);
+                result_rows.push(new_row);
+            }
+        } else {
+            // Невалидный ключ - добавляем строку с NULL справа
+            let mut new_row = left_row.clone();
+            new_row.extend_from_slice(&null_right_row);
+            result_rows.push(new_row);
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+}
+
+// Выполнение RIGHT JOIN
+fn perform_right_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    left_alias: &str,
+    right_alias: &str,
+    nulls_equal: bool,
+) -> Value {
+    // RIGHT JOIN - это LEFT JOIN с переставленными таблицами
+    let result = perform_left_join(right_table, left_table, 
+        &keys.iter().map(|k| JoinKey {
+            left_col: k.right_col.clone(),
+            right_col: k.left_col.clone(),
+        }).collect::<Vec<_>>(),
+        right_alias, left_alias, nulls_equal);
+    
+    // Переставляем заголовки обратно
+    if let Value::Table(t) = result {
+        let table_ref = t.borrow();
+        // Меняем порядок заголовков: сначала right, потом left
+        let mut new_headers = Vec::new();
+        let right_count = right_table.headers.len();
+        let left_count = left_table.headers.len();
+        
+        // Сначала заголовки правой таблицы
+        for i in 0..right_count {
+            if i < table_ref.headers.len() {
+                new_headers.push(table_ref.headers[i].clone());
+            }
+        }
+        // Потом заголовки левой таблицы
+        for i in right_count..(right_count + left_count) {
+            if i < table_ref.headers.len() {
+                new_headers.push(table_ref.headers[i].clone());
+            }
+        }
+        
+        // Переставляем данные в строках тоже
+        let mut new_rows = Vec::new();
+        for row in &table_ref.rows {
+            let mut new_row = Vec::new();
+            // Сначала данные правой таблицы
+            for i in 0..right_count {
+                if i < row.len() {
+                    new_row.push(row[i].clone());
+                }
+            }
+            // Потом данные левой таблицы
+            for i in right_count..(right_count + left_count) {
+                if i < row.len() {
+                    new_row.push(row[i].clone());
+                }
+            }
+            new_rows.push(new_row);
+        }
+        
+        // Освобождаем заимствование перед созданием новой таблицы
+        drop(table_ref);
+        Value::Table(Rc::new(RefCell::new(Table::from_data(new_rows, Some(new_headers)))))
+    } else {
+        result
+    }
+}
+
+// Выполнение FULL JOIN
+fn perform_full_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    left_alias: &str,
+    right_alias: &str,
+    nulls_equal: bool,
+) -> Value {
+    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
+    
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+    
+    // Создаем NULL-строки
+    let null_left_row: Vec<Value> = (0..left_table.headers.len()).map(|_| Value::Null).collect();
+    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
+    
+    let mut matched_right_indices = std::collections::HashSet::new();
+    
+    // Проходим по левой таблице
+    for left_row in &left_table.rows {
+        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
+            if let Some(right_indices) = right_hash.get(&left_key) {
+                for &right_idx in right_indices {
+                    matched_right_indices.insert(right_idx);
+                    if let Some(right_row) = right_table.get_row(right_idx) {
+                        let mut new_row = left_row.clone();
+                        new_row.extend_from_slice(right_row);
+                        result_rows.push(new_row);
+                    }
+                }
+            } else {
+                // Нет совпадений справа - добавляем строку с NULL справа
+                let mut new_row = left_row.clone();
+                new_row.extend_from_slice(&null_right_row);
+                result_rows.push(new_row);
+            }
+        } else {
+            // Невалидный ключ - добавляем строку с NULL справа
+            let mut new_row = left_row.clone();
+            new_row.extend_from_slice(&null_right_row);
+            result_rows.push(new_row);
+        }
+    }
+    
+    // Проходим по правой таблице и добавляем несовпадающие строки
+    for (right_idx, right_row) in right_table.rows.iter().enumerate() {
+        if !matched_right_indices.contains(&right_idx) {
+            let mut new_row = null_left_row.clone();
+            new_row.extend_from_slice(right_row);
+            result_rows.push(new_row);
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+}
+
+// Выполнение SEMI JOIN (только строки left, колонки right не включаются)
+fn perform_semi_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    nulls_equal: bool,
+) -> Value {
+    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    
+    let mut result_rows = Vec::new();
+    
+    // Проходим по левой таблице и проверяем наличие совпадений
+    for left_row in &left_table.rows {
+        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
+            if right_hash.contains_key(&left_key) {
+                // Есть совпадение - добавляем строку из left
+                result_rows.push(left_row.clone());
+            }
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(left_table.headers.clone())))))
+}
+
+// Выполнение ANTI JOIN (строки left без совпадений в right)
+fn perform_anti_join(
+    left_table: &Table,
+    right_table: &Table,
+    keys: &[JoinKey],
+    nulls_equal: bool,
+) -> Value {
+    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    
+    let mut result_rows = Vec::new();
+    
+    // Проходим по левой таблице и проверяем отсутствие совпадений
+    for left_row in &left_table.rows {
+        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
+            if !right_hash.contains_key(&left_key) {
+                // Нет совпадения - добавляем строку из left
+                result_rows.push(left_row.clone());
+            }
+        } else {
+            // Невалидный ключ - добавляем строку
+            result_rows.push(left_row.clone());
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(left_table.headers.clone())))))
+}
+
+// Выполнение CROSS JOIN (декартово произведение)
+fn perform_cross_join(
+    left_table: &Table,
+    right_table: &Table,
+    left_alias: &str,
+    right_alias: &str,
+) -> Value {
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
+    
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+    
+    // Декартово произведение
+    for left_row in &left_table.rows {
+        for right_row in &right_table.rows {
+            let mut new_row = left_row.clone();
+            new_row.extend_from_slice(right_row);
+            result_rows.push(new_row);
+        }
+    }
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+}
+
+// ============================================================================
+// Specialized JOIN Functions (syntactic sugar over native_table_join)
+// ============================================================================
+
+pub fn native_inner_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    // Вызываем native_table_join с type="inner"
+    let mut new_args = args.to_vec();
+    
+    // Если переданы два отдельных строковых аргумента (left_col, right_col)
+    if new_args.len() == 4 {
+        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
+            // Создаем массив кортежей: [["left_col", "right_col"]]
+            let tuple = Value::Array(Rc::new(RefCell::new(vec![
+                new_args[2].clone(),
+                new_args[3].clone(),
+            ])));
+            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
+            new_args[2] = keys_array;
+            new_args[3] = Value::String("inner".into());
+        } else {
+            // Иначе перезаписываем тип join
+            new_args[3] = Value::String("inner".into());
+        }
+    } else if new_args.len() == 3 {
+        new_args.push(Value::String("inner".into()));
+    } else {
+        new_args[3] = Value::String("inner".into());
+    }
+    native_table_join(&new_args)
+}
+
+pub fn native_left_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    let mut new_args = args.to_vec();
+    
+    // Если переданы два отдельных строковых аргумента (left_col, right_col)
+    if new_args.len() == 4 {
+        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
+            // Создаем массив кортежей: [["left_col", "right_col"]]
+            let tuple = Value::Array(Rc::new(RefCell::new(vec![
+                new_args[2].clone(),
+                new_args[3].clone(),
+            ])));
+            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
+            new_args[2] = keys_array;
+            new_args[3] = Value::String("left".into());
+        } else {
+            // Иначе перезаписываем тип join
+            new_args[3] = Value::String("left".into());
+        }
+    } else if new_args.len() == 3 {
+        new_args.push(Value::String("left".into()));
+    } else {
+        new_args[3] = Value::String("left".into());
+    }
+    native_table_join(&new_args)
+}
+
+pub fn native_right_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    let mut new_args = args.to_vec();
+    
+    // Если переданы два отдельных строковых аргумента (left_col, right_col)
+    if new_args.len() == 4 {
+        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
+            // Создаем массив кортежей: [["left_col", "right_col"]]
+            let tuple = Value::Array(Rc::new(RefCell::new(vec![
+                new_args[2].clone(),
+                new_args[3].clone(),
+            ])));
+            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
+            new_args[2] = keys_array;
+            new_args[3] = Value::String("right".into());
+        } else {
+            // Иначе перезаписываем тип join
+            new_args[3] = Value::String("right".into());
+        }
+    } else if new_args.len() == 3 {
+        new_args.push(Value::String("right".into()));
+    } else {
+        new_args[3] = Value::String("right".into());
+    }
+    native_table_join(&new_args)
+}
+
+pub fn native_full_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    let mut new_args = args.to_vec();
+    if new_args.len() == 3 {
+        new_args.push(Value::String("full".into()));
+    } else {
+        new_args[3] = Value::String("full".into());
+    }
+    native_table_join(&new_args)
+}
+
+pub fn native_cross_join(args: &[Value]) -> Value {
+    if args.len() < 2 {
+        return Value::Null;
+    }
+    // CROSS JOIN не требует ключей, используем пустой массив
+    let mut new_args = vec![args[0].clone(), args[1].clone()];
+    new_args.push(Value::Array(Rc::new(RefCell::new(Vec::new()))));
+    new_args.push(Value::String("cross".into()));
+    native_table_join(&new_args)
+}
+
+pub fn native_semi_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    let mut new_args = args.to_vec();
+    
+    // Если переданы два отдельных строковых аргумента (left_col, right_col)
+    if new_args.len() == 4 {
+        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
+            // Создаем массив кортежей: [["left_col", "right_col"]]
+            let tuple = Value::Array(Rc::new(RefCell::new(vec![
+                new_args[2].clone(),
+                new_args[3].clone(),
+            ])));
+            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
+            new_args[2] = keys_array;
+            new_args[3] = Value::String("semi".into());
+        } else {
+            // Иначе перезаписываем тип join
+            new_args[3] = Value::String("semi".into());
+        }
+    } else if new_args.len() == 3 {
+        new_args.push(Value::String("semi".into()));
+    } else {
+        new_args[3] = Value::String("semi".into());
+    }
+    native_table_join(&new_args)
+}
+
+pub fn native_anti_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+    let mut new_args = args.to_vec();
+    
+    // Если переданы два отдельных строковых аргумента (left_col, right_col)
+    if new_args.len() == 4 {
+        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
+            // Создаем массив кортежей: [["left_col", "right_col"]]
+            let tuple = Value::Array(Rc::new(RefCell::new(vec![
+                new_args[2].clone(),
+                new_args[3].clone(),
+            ])));
+            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
+            new_args[2] = keys_array;
+            new_args[3] = Value::String("anti".into());
+        } else {
+            // Иначе перезаписываем тип join
+            new_args[3] = Value::String("anti".into());
+        }
+    } else if new_args.len() == 3 {
+        new_args.push(Value::String("anti".into()));
+    } else {
+        new_args[3] = Value::String("anti".into());
+    }
+    native_table_join(&new_args)
+}
+
+// ZIP JOIN - позиционное соединение по индексу строки
+pub fn native_zip_join(args: &[Value]) -> Value {
+    if args.len() < 2 {
+        return Value::Null;
+    }
+
+    let left_table = match &args[0] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+    
+    let right_table = match &args[1] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Используем имена таблиц из table.name, если они установлены
+    let left_alias = left_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 2 {
+                match &args[2] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 1 {
+                            match &arr_ref[0] {
+                                Value::String(s) => s.to_string(),
+                                _ => "left".to_string(),
+                            }
+                        } else {
+                            "left".to_string()
+                        }
+                    }
+                    _ => "left".to_string(),
+                }
+            } else {
+                "left".to_string()
+            }
+        });
+    
+    let right_alias = right_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 2 {
+                match &args[2] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 2 {
+                            match &arr_ref[1] {
+                                Value::String(s) => s.to_string(),
+                                _ => "right".to_string(),
+                            }
+                        } else {
+                            "right".to_string()
+                        }
+                    }
+                    _ => "right".to_string(),
+                }
+            } else {
+                "right".to_string()
+            }
+        });
+
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
+    
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+    
+    // Соединяем строки по позиции (индексу)
+    let min_len = std::cmp::min(left_table.rows.len(), right_table.rows.len());
+    for i in 0..min_len {
+        let mut new_row = left_table.rows[i].clone();
+        new_row.extend_from_slice(&right_table.rows[i]);
+        result_rows.push(new_row);
     }
-    true
+    
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
 }
 
-// Построение хеш-таблицы для правой таблицы по ключам
-use std::collections::HashMap;
-use std::hash::Hash;
+// APPLY JOIN / LATERAL JOIN - для каждой строки left вызывает функцию
+// apply_join(left: Table, fn: Function, type: "inner" | "left" = "inner") -> Table
+pub fn native_apply_join(_args: &[Value]) -> Value {
+    if _args.len() < 2 {
+        return Value::Null;
+    }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct KeyHash {
-    values: Vec<Value>,
+    let _left_table = match &_args[0] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Функция должна быть передана как Value::Function
+    // Но в текущей реализации функции не могут быть переданы как значения напрямую
+    // Это требует дополнительной инфраструктуры
+    // Пока возвращаем Null с предупреждением
+    // TODO: Реализовать поддержку функций как значений для apply_join
+    
+    Value::Null
 }
 
-fn build_right_hash_table(
-    right_table: &Table,
-    keys: &[JoinKey],
-    nulls_equal: bool,
-) -> HashMap<KeyHash, Vec<usize>> {
-    let mut hash_map = HashMap::new();
+// ASOF JOIN - временное соединение
+// asof_join(left: Table, right: Table, on: Column, by: Column | List<Column>, 
+//           direction: "backward" | "forward" | "nearest" = "backward") -> Table
+pub fn native_asof_join(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+
+    let left_table = match &args[0] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
     
-    for (row_idx, row) in right_table.rows.iter().enumerate() {
-        let mut key_values = Vec::new();
-        let mut valid_key = true;
-        
-        for key in keys {
-            if let Some(col_idx) = right_table.headers.iter().position(|h| h == &key.right_col) {
-                if col_idx < row.len() {
-                    let val = &row[col_idx];
-                    // Если nulls_equal=false и значение NULL, пропускаем эту строку
-                    if matches!(val, Value::Null) && !nulls_equal {
-                        valid_key = false;
-                        break;
+    let right_table = match &args[1] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Парсим временную колонку
+    let time_column = match &args[2] {
+        Value::String(s) => s.to_string(),
+        _ => return Value::Null,
+    };
+
+    // Проверяем наличие временной колонки в обеих таблицах
+    if left_table.get_column(&time_column).is_none() {
+        return Value::Null;
+    }
+    if right_table.get_column(&time_column).is_none() {
+        return Value::Null;
+    }
+
+    // Парсим by (группирующие колонки) - опционально
+    let by_columns: Vec<String> = if args.len() > 3 {
+        match &args[3] {
+            Value::String(s) => vec![s.to_string()],
+            Value::Array(arr) => {
+                let arr_ref = arr.borrow();
+                let mut cols = Vec::new();
+                for val in arr_ref.iter() {
+                    if let Value::String(s) = val {
+                        cols.push(s.to_string());
                     }
-                    key_values.push(val.clone());
-                } else {
-                    valid_key = false;
-                    break;
                 }
-            } else {
-                valid_key = false;
-                break;
+                cols
             }
+            _ => Vec::new(),
         }
-        
-        if valid_key {
-            let key_hash = KeyHash { values: key_values };
-            hash_map.entry(key_hash).or_insert_with(Vec::new).push(row_idx);
+    } else {
+        Vec::new()
+    };
+
+    // Парсим direction (по умолчанию "backward")
+    let direction = if args.len() > 4 {
+        match &args[4] {
+            Value::String(s) => s.as_ref(),
+            _ => "backward",
         }
-    }
+    } else {
+        "backward"
+    };
+
+    // Используем имена таблиц из table.name, если они установлены
+    let left_alias = left_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 5 {
+                match &args[5] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 1 {
+                            match &arr_ref[0] {
+                                Value::String(s) => s.to_string(),
+                                _ => "left".to_string(),
+                            }
+                        } else {
+                            "left".to_string()
+                        }
+                    }
+                    _ => "left".to_string(),
+                }
+            } else {
+                "left".to_string()
+            }
+        });
     
-    hash_map
-}
+    let right_alias = right_table.name.as_ref()
+        .map(|n| n.clone())
+        .unwrap_or_else(|| {
+            if args.len() > 5 {
+                match &args[5] {
+                    Value::Array(arr) => {
+                        let arr_ref = arr.borrow();
+                        if arr_ref.len() >= 2 {
+                            match &arr_ref[1] {
+                                Value::String(s) => s.to_string(),
+                                _ => "right".to_string(),
+                            }
+                        } else {
+                            "right".to_string()
+                        }
+                    }
+                    _ => "right".to_string(),
+                }
+            } else {
+                "right".to_string()
+            }
+        });
 
-// Извлечение ключа из строки левой таблицы
-fn extract_left_key(left_row: &[Value], keys: &[JoinKey], left_table: &Table) -> Option<KeyHash> {
-    let mut key_values = Vec::new();
+    // Создаем индексы для временной колонки
+    let left_time_idx = left_table.headers.iter().position(|h| h == &time_column).unwrap();
+    let right_time_idx = right_table.headers.iter().position(|h| h == &time_column).unwrap();
+
+    // Если есть by колонки, группируем данные
+    let mut result_rows = Vec::new();
+    let mut result_headers = Vec::new();
     
-    for key in keys {
-        if let Some(col_idx) = left_table.headers.iter().position(|h| h == &key.left_col) {
-            if col_idx < left_row.len() {
-                key_values.push(left_row[col_idx].clone());
+    // Создаем заголовки с учетом алиасов таблиц
+    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
+    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
+    
+    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
+    
+    result_headers.append(&mut left_headers);
+    result_headers.append(&mut right_headers);
+
+    // Создаем NULL-строку для правой таблицы
+    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
+
+    if by_columns.is_empty() {
+        // Нет группировки - простой ASOF join
+        // Сортируем правую таблицу по времени (для бинарного поиска)
+        let mut right_indices: Vec<usize> = (0..right_table.rows.len()).collect();
+        right_indices.sort_by(|&a, &b| {
+            let time_a = &right_table.rows[a][right_time_idx];
+            let time_b = &right_table.rows[b][right_time_idx];
+            compare_values(time_a, time_b)
+        });
+
+        for left_row in &left_table.rows {
+            let left_time = &left_row[left_time_idx];
+            
+            // Ищем ближайшую строку в правой таблице
+            let mut best_match: Option<usize> = None;
+            let mut best_diff: Option<f64> = None;
+
+            for &right_idx in &right_indices {
+                let right_row = &right_table.rows[right_idx];
+                let right_time = &right_row[right_time_idx];
+
+                // Вычисляем разницу времени (упрощенная версия - только для чисел)
+                let diff = match (left_time.as_f64(), right_time.as_f64()) {
+                    (Some(l), Some(r)) => {
+                        let diff_val = match direction {
+                            "backward" => l - r,  // left_time >= right_time
+                            "forward" => r - l,   // right_time >= left_time
+                            "nearest" => (l - r).abs(),
+                            _ => l - r,
+                        };
+                        Some(diff_val)
+                    }
+                    _ => None,
+                };
+
+                if let Some(d) = diff {
+                    let matches_direction = match direction {
+                        "backward" => d >= 0.0,
+                        "forward" => d >= 0.0,
+                        "nearest" => true,
+                        _ => d >= 0.0,
+                    };
+
+                    if matches_direction {
+                        let should_update = match best_diff {
+                            None => true,
+                            Some(bd) => match direction {
+                                "nearest" => d < bd,
+                                _ => d < bd,
+                            },
+                        };
+
+                        if should_update {
+                            best_match = Some(right_idx);
+                            best_diff = Some(d);
+                        }
+                    }
+                }
+            }
+
+            if let Some(right_idx) = best_match {
+                let mut new_row = left_row.clone();
+                new_row.extend_from_slice(&right_table.rows[right_idx]);
+                result_rows.push(new_row);
             } else {
-                return None;
+                // Нет совпадения - добавляем строку с NULL справа (для left join семантики)
+                let mut new_row = left_row.clone();
+                new_row.extend_from_slice(&null_right_row);
+                result_rows.push(new_row);
             }
-        } else {
-            return None;
         }
+    } else {
+        // Есть группировка - группируем по by колонкам
+        // Упрощенная реализация: для каждой группы выполняем ASOF join
+        // TODO: Реализовать полную поддержку группировки
+        // Пока возвращаем пустую таблицу
+        return Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))));
     }
-    
-    Some(KeyHash { values: key_values })
+
+    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
 }
 
-// Универсальная функция JOIN для таблиц
-// join(left: Table, right: Table, on: JoinKeys, type: JoinType = "inner", 
-//      suffixes: (string, string) = ("_left", "_right"), nulls_equal: boolean = false) -> Table
-pub fn native_table_join(args: &[Value]) -> Value {
+// JOIN ON - non-equi join с произвольным условием
+// join_on(left: Table, right: Table, condition: Expr, type: JoinType = "inner") -> Table
+// Примечание: Полная поддержка выражений требует дополнительной инфраструктуры.
+// Пока реализована упрощенная версия для простых условий сравнения колонок.
+pub fn native_join_on(args: &[Value]) -> Value {
     if args.len() < 3 {
         return Value::Null;
     }
 
-    // Извлекаем left и right таблицы
     let left_table = match &args[0] {
         Value::Table(t) => t.borrow().clone(),
         _ => return Value::Null,
@@ -2634,17 +6294,18 @@ pub fn native_table_join(args: &[Value]) -> Value {
         _ => return Value::Null,
     };
 
-    // Парсим тип JOIN (по умолчанию inner) - нужно проверить ДО парсинга ключей
+    // Парсим условие - пока упрощенная версия
+    // Ожидаем строку вида "left_col >= right_col" или массив ["left_col", ">=", "right_col"]
+    let condition = &args[2];
+    
+    // Парсим тип JOIN (по умолчанию inner)
     let join_type = if args.len() > 3 {
         match &args[3] {
-            Value::String(s) => match s.as_str() {
+            Value::String(s) => match s.as_ref() {
                 "inner" => JoinType::Inner,
                 "left" => JoinType::Left,
                 "right" => JoinType::Right,
                 "full" => JoinType::Full,
-                "cross" => JoinType::Cross,
-                "semi" => JoinType::Semi,
-                "anti" => JoinType::Anti,
                 _ => JoinType::Inner,
             },
             _ => JoinType::Inner,
@@ -2653,22 +6314,7 @@ pub fn native_table_join(args: &[Value]) -> Value {
         JoinType::Inner
     };
 
-    // Для CROSS JOIN не нужны ключи - пропускаем парсинг
-    let keys = if join_type == JoinType::Cross {
-        Vec::new()
-    } else {
-        // Парсим параметр on (ключи)
-        match parse_join_keys(&args[2], &left_table, &right_table) {
-            Ok(k) => k,
-            Err(err_msg) => {
-                use crate::websocket::set_native_error;
-                set_native_error(err_msg);
-                return Value::Null;
-            }
-        }
-    };
-
-    // Используем имена таблиц из table.name, если они установлены, иначе используем переданные алиасы или значения по умолчанию
+    // Используем имена таблиц из table.name, если они установлены
     let left_alias = left_table.name.as_ref()
         .map(|n| n.clone())
         .unwrap_or_else(|| {
@@ -2678,7 +6324,7 @@ pub fn native_table_join(args: &[Value]) -> Value {
                         let arr_ref = arr.borrow();
                         if arr_ref.len() >= 1 {
                             match &arr_ref[0] {
-                                Value::String(s) => s.clone(),
+                                Value::String(s) => s.to_string(),
                                 _ => "left".to_string(),
                             }
                         } else {
@@ -2701,7 +6347,7 @@ pub fn native_table_join(args: &[Value]) -> Value {
                         let arr_ref = arr.borrow();
                         if arr_ref.len() >= 2 {
                             match &arr_ref[1] {
-                                Value::String(s) => s.clone(),
+                                Value::String(s) => s.to_string(),
                                 _ => "right".to_string(),
                             }
                         } else {
@@ -2715,213 +6361,52 @@ pub fn native_table_join(args: &[Value]) -> Value {
             }
         });
 
-    // Парсим nulls_equal (по умолчанию false)
-    let nulls_equal = if args.len() > 5 {
-        match &args[5] {
-            Value::Bool(b) => *b,
-            Value::Number(n) => *n != 0.0,
-            _ => false,
-        }
-    } else {
-        false
-    };
-
-    // CROSS JOIN - особый случай (декартово произведение)
-    if join_type == JoinType::Cross {
-        return perform_cross_join(&left_table, &right_table, &left_alias, &right_alias);
-    }
-
-    // Для остальных типов JOIN нужны ключи
-    if keys.is_empty() {
-        return Value::Null;
-    }
-
-    // Выполняем JOIN в зависимости от типа
-    match join_type {
-        JoinType::Inner => perform_inner_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
-        JoinType::Left => perform_left_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
-        JoinType::Right => perform_right_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
-        JoinType::Full => perform_full_join(&left_table, &right_table, &keys, &left_alias, &right_alias, nulls_equal),
-        JoinType::Semi => perform_semi_join(&left_table, &right_table, &keys, nulls_equal),
-        JoinType::Anti => perform_anti_join(&left_table, &right_table, &keys, nulls_equal),
-        JoinType::Cross => unreachable!(), // Уже обработано выше
-    }
-}
-
-// Выполнение INNER JOIN
-fn perform_inner_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    left_alias: &str,
-    right_alias: &str,
-    nulls_equal: bool,
-) -> Value {
-    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
-    
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
-    
-    // Проходим по левой таблице и ищем совпадения
-    for left_row in left_table.rows.iter() {
-        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
-            if let Some(right_indices) = right_hash.get(&left_key) {
-                for &right_idx in right_indices {
-                    if let Some(right_row) = right_table.get_row(right_idx) {
-                        let mut new_row = left_row.clone();
-                        new_row.extend_from_slice(right_row);
-                        result_rows.push(new_row);
-                    }
-                }
-            }
-        }
-    }
-    
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
-}
-
-// Выполнение LEFT JOIN
-fn perform_left_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    left_alias: &str,
-    right_alias: &str,
-    nulls_equal: bool,
-) -> Value {
-    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
-    
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
-    
-    // Создаем NULL-строку для правой таблицы
-    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
-    
-    // Проходим по левой таблице
-    for left_row in left_table.rows.iter() {
-        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
-            if let Some(right_indices) = right_hash.get(&left_key) {
-                // Есть совпадения - добавляем все совпадения
-                for &right_idx in right_indices {
-                    if let Some(right_row) = right_table.get_row(right_idx) {
-                        let mut new_row = left_row.clone();
-                        new_row.extend_from_slice(right_row);
-                        result_rows.push(new_row);
-                    }
-                }
+    // Парсим условие - упрощенная версия
+    // Формат: ["left_col", ">=", "right_col"] или строка "left_col >= right_col"
+    let (left_col, op, right_col) = match condition {
+        Value::String(s) => {
+            // Парсим строку вида "left_col >= right_col"
+            let parts: Vec<&str> = s.split_whitespace().collect();
+            if parts.len() >= 3 {
+                (parts[0].to_string(), parts[1].to_string(), parts[2].to_string())
             } else {
-                // Нет совпадений - добавляем строку с NULL справа
-                let mut new_row = left_row.clone();
-                new_row.extend_from_slice(&null_right_row);
-                result_rows.push(new_row);
-            }
-        } else {
-            // Невалидный ключ - добавляем строку с NULL справа
-            let mut new_row = left_row.clone();
-            new_row.extend_from_slice(&null_right_row);
-            result_rows.push(new_row);
-        }
-    }
-    
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
-}
-
-// Выполнение RIGHT JOIN
-fn perform_right_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    left_alias: &str,
-    right_alias: &str,
-    nulls_equal: bool,
-) -> Value {
-    // RIGHT JOIN - это LEFT JOIN с переставленными таблицами
-    let result = perform_left_join(right_table, left_table, 
-        &keys.iter().map(|k| JoinKey {
-            left_col: k.right_col.clone(),
-            right_col: k.left_col.clone(),
-        }).collect::<Vec<_>>(),
-        right_alias, left_alias, nulls_equal);
-    
-    // Переставляем заголовки обратно
-    if let Value::Table(t) = result {
-        let table_ref = t.borrow();
-        // Меняем порядок заголовков: сначала right, потом left
-        let mut new_headers = Vec::new();
-        let right_count = right_table.headers.len();
-        let left_count = left_table.headers.len();
-        
-        // Сначала заголовки правой таблицы
-        for i in 0..right_count {
-            if i < table_ref.headers.len() {
-                new_headers.push(table_ref.headers[i].clone());
-            }
-        }
-        // Потом заголовки левой таблицы
-        for i in right_count..(right_count + left_count) {
-            if i < table_ref.headers.len() {
-                new_headers.push(table_ref.headers[i].clone());
+                return Value::Null;
             }
         }
-        
-        // Переставляем данные в строках тоже
-        let mut new_rows = Vec::new();
-        for row in &table_ref.rows {
-            let mut new_row = Vec::new();
-            // Сначала данные правой таблицы
-            for i in 0..right_count {
-                if i < row.len() {
-                    new_row.push(row[i].clone());
-                }
-            }
-            // Потом данные левой таблицы
-            for i in right_count..(right_count + left_count) {
-                if i < row.len() {
-                    new_row.push(row[i].clone());
-                }
+        Value::Array(arr) => {
+            let arr_ref = arr.borrow();
+            if arr_ref.len() >= 3 {
+                let left = match &arr_ref[0] {
+                    Value::String(s) => s.to_string(),
+                    _ => return Value::Null,
+                };
+                let op = match &arr_ref[1] {
+                    Value::String(s) => s.to_string(),
+                    _ => return Value::Null,
+                };
+                let right = match &arr_ref[2] {
+                    Value::String(s) => s.to_string(),
+                    _ => return Value::Null,
+                };
+                (left, op, right)
+            } else {
+                return Value::Null;
             }
-            new_rows.push(new_row);
         }
-        
-        // Освобождаем заимствование перед созданием новой таблицы
-        drop(table_ref);
-        Value::Table(Rc::new(RefCell::new(Table::from_data(new_rows, Some(new_headers)))))
-    } else {
-        result
+        _ => return Value::Null,
+    };
+
+    // Проверяем наличие колонок
+    if left_table.get_column(&left_col).is_none() {
+        return Value::Null;
+    }
+    if right_table.get_column(&right_col).is_none() {
+        return Value::Null;
     }
-}
 
-// Выполнение FULL JOIN
-fn perform_full_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    left_alias: &str,
-    right_alias: &str,
-    nulls_equal: bool,
-) -> Value {
-    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
-    
+    let left_col_idx = left_table.headers.iter().position(|h| h == &left_col).unwrap();
+    let right_col_idx = right_table.headers.iter().position(|h| h == &right_col).unwrap();
+
     let mut result_rows = Vec::new();
     let mut result_headers = Vec::new();
     
@@ -2929,945 +6414,1818 @@ fn perform_full_join(
     let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
     let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
     
-    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
+    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
+    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
     
     result_headers.append(&mut left_headers);
     result_headers.append(&mut right_headers);
-    
+
     // Создаем NULL-строки
     let null_left_row: Vec<Value> = (0..left_table.headers.len()).map(|_| Value::Null).collect();
     let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
-    
+
+    // Nested loop join с проверкой условия
     let mut matched_right_indices = std::collections::HashSet::new();
-    
-    // Проходим по левой таблице
+
     for left_row in &left_table.rows {
-        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
-            if let Some(right_indices) = right_hash.get(&left_key) {
-                for &right_idx in right_indices {
-                    matched_right_indices.insert(right_idx);
-                    if let Some(right_row) = right_table.get_row(right_idx) {
-                        let mut new_row = left_row.clone();
-                        new_row.extend_from_slice(right_row);
-                        result_rows.push(new_row);
-                    }
+        let left_val = &left_row[left_col_idx];
+        let mut found_match = false;
+
+        for (right_idx, right_row) in right_table.rows.iter().enumerate() {
+            let right_val = &right_row[right_col_idx];
+            
+            // Проверяем условие
+            let condition_met = match op.as_str() {
+                ">" => compare_values(left_val, right_val) == std::cmp::Ordering::Greater,
+                "<" => compare_values(left_val, right_val) == std::cmp::Ordering::Less,
+                ">=" => {
+                    let cmp = compare_values(left_val, right_val);
+                    cmp == std::cmp::Ordering::Greater || cmp == std::cmp::Ordering::Equal
                 }
-            } else {
-                // Нет совпадений справа - добавляем строку с NULL справа
+                "<=" => {
+                    let cmp = compare_values(left_val, right_val);
+                    cmp == std::cmp::Ordering::Less || cmp == std::cmp::Ordering::Equal
+                }
+                "==" | "=" => compare_values(left_val, right_val) == std::cmp::Ordering::Equal,
+                "!=" | "<>" => compare_values(left_val, right_val) != std::cmp::Ordering::Equal,
+                _ => false,
+            };
+
+            if condition_met {
+                found_match = true;
+                matched_right_indices.insert(right_idx);
                 let mut new_row = left_row.clone();
-                new_row.extend_from_slice(&null_right_row);
+                new_row.extend_from_slice(right_row);
                 result_rows.push(new_row);
             }
-        } else {
-            // Невалидный ключ - добавляем строку с NULL справа
+        }
+
+        // Для LEFT JOIN добавляем строки без совпадений
+        if !found_match && (join_type == JoinType::Left || join_type == JoinType::Full) {
             let mut new_row = left_row.clone();
             new_row.extend_from_slice(&null_right_row);
             result_rows.push(new_row);
         }
     }
-    
-    // Проходим по правой таблице и добавляем несовпадающие строки
-    for (right_idx, right_row) in right_table.rows.iter().enumerate() {
-        if !matched_right_indices.contains(&right_idx) {
-            let mut new_row = null_left_row.clone();
-            new_row.extend_from_slice(right_row);
-            result_rows.push(new_row);
+
+    // Для RIGHT и FULL JOIN добавляем несовпадающие строки справа
+    if join_type == JoinType::Right || join_type == JoinType::Full {
+        for (right_idx, right_row) in right_table.rows.iter().enumerate() {
+            if !matched_right_indices.contains(&right_idx) {
+                let mut new_row = null_left_row.clone();
+                new_row.extend_from_slice(right_row);
+                result_rows.push(new_row);
+            }
         }
     }
-    
+
     Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
 }
 
-// Выполнение SEMI JOIN (только строки left, колонки right не включаются)
-fn perform_semi_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    nulls_equal: bool,
-) -> Value {
-    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
-    
-    let mut result_rows = Vec::new();
+// Применение суффиксов к колонкам таблицы после join
+// Заменяет префиксы "left." и "right." на пользовательские суффиксы
+pub fn native_table_suffixes(args: &[Value]) -> Value {
+    if args.len() < 3 {
+        return Value::Null;
+    }
+
+    // Аргументы приходят в порядке: [table, left_suffix, right_suffix]
+    // Извлекаем таблицу
+    let table = match &args[0] {
+        Value::Table(t) => t.borrow().clone(),
+        _ => return Value::Null,
+    };
+
+    // Извлекаем суффиксы
+    let left_suffix = match &args[1] {
+        Value::String(s) => s.to_string(),
+        _ => return Value::Null,
+    };
+
+    let right_suffix = match &args[2] {
+        Value::String(s) => s.to_string(),
+        _ => return Value::Null,
+    };
+
+    // Создаем новую таблицу с переименованными колонками
+    let mut new_headers = Vec::new();
+    let mut column_mapping = std::collections::HashMap::new(); // старое имя -> новое имя
+
+    // Определяем, какие колонки относятся к левой таблице, а какие к правой
+    // Префиксы могут быть вида "left.", "right." или "table_name."
+    // Определяем первые уникальные префиксы как левую таблицу, остальные - как правую
+    let mut seen_prefixes = Vec::new();
+    let mut prefix_to_table = std::collections::HashMap::new(); // prefix -> "left" или "right"
     
-    // Проходим по левой таблице и проверяем наличие совпадений
-    for left_row in &left_table.rows {
-        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
-            if right_hash.contains_key(&left_key) {
-                // Есть совпадение - добавляем строку из left
-                result_rows.push(left_row.clone());
+    // Сначала проходим по всем заголовкам и собираем уникальные префиксы
+    for header in &table.headers {
+        if let Some(dot_pos) = header.find('.') {
+            let prefix = &header[..dot_pos];
+            if !seen_prefixes.contains(&prefix.to_string()) {
+                seen_prefixes.push(prefix.to_string());
             }
         }
     }
     
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(left_table.headers.clone())))))
-}
-
-// Выполнение ANTI JOIN (строки left без совпадений в right)
-fn perform_anti_join(
-    left_table: &Table,
-    right_table: &Table,
-    keys: &[JoinKey],
-    nulls_equal: bool,
-) -> Value {
-    let right_hash = build_right_hash_table(right_table, keys, nulls_equal);
+    // Первый уникальный префикс относится к левой таблице, остальные - к правой
+    for (i, prefix) in seen_prefixes.iter().enumerate() {
+        if i == 0 {
+            prefix_to_table.insert(prefix.clone(), "left");
+        } else {
+            prefix_to_table.insert(prefix.clone(), "right");
+        }
+    }
     
-    let mut result_rows = Vec::new();
+    // Также добавляем стандартные префиксы
+    prefix_to_table.insert("left".to_string(), "left");
+    prefix_to_table.insert("right".to_string(), "right");
     
-    // Проходим по левой таблице и проверяем отсутствие совпадений
-    for left_row in &left_table.rows {
-        if let Some(left_key) = extract_left_key(left_row, keys, left_table) {
-            if !right_hash.contains_key(&left_key) {
-                // Нет совпадения - добавляем строку из left
-                result_rows.push(left_row.clone());
+    // Проходим по всем заголовкам и переименовываем колонки с префиксами
+    for header in &table.headers {
+        let new_header = if let Some(dot_pos) = header.find('.') {
+            let prefix = &header[..dot_pos];
+            let base_name = &header[dot_pos + 1..];
+            
+            // Определяем, к какой таблице относится колонка
+            if let Some(table_side) = prefix_to_table.get(prefix) {
+                if *table_side == "left" {
+                    // Колонка левой таблицы - применяем left_suffix
+                    let new_name = format!("{}{}", base_name, left_suffix);
+                    column_mapping.insert(header.clone(), new_name.clone());
+                    new_name
+                } else {
+                    // Колонка правой таблицы - применяем right_suffix
+                    let new_name = format!("{}{}", base_name, right_suffix);
+                    column_mapping.insert(header.clone(), new_name.clone());
+                    new_name
+                }
+            } else {
+                // Неизвестный префикс - оставляем без изменений
+                column_mapping.insert(header.clone(), header.clone());
+                header.clone()
             }
         } else {
-            // Невалидный ключ - добавляем строку
-            result_rows.push(left_row.clone());
+            // Колонка без префикса - оставляем без изменений
+            column_mapping.insert(header.clone(), header.clone());
+            header.clone()
+        };
+        new_headers.push(new_header);
+    }
+
+    // Создаем новую структуру колонок с переименованными ключами
+    let mut new_columns = std::collections::HashMap::new();
+    for (old_header, new_header) in &column_mapping {
+        if let Some(column_data) = table.columns.get(old_header) {
+            new_columns.insert(new_header.clone(), column_data.clone());
         }
     }
-    
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(left_table.headers.clone())))))
+
+    // Создаем новую таблицу
+    let mut new_table = Table::new();
+    new_table.headers = new_headers;
+    new_table.columns = new_columns;
+    new_table.rows = table.rows.clone();
+    new_table.name = table.name.clone();
+
+    Value::Table(Rc::new(RefCell::new(new_table)))
 }
 
-// Выполнение CROSS JOIN (декартово произведение)
-fn perform_cross_join(
-    left_table: &Table,
-    right_table: &Table,
-    left_alias: &str,
-    right_alias: &str,
-) -> Value {
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
-    
-    // Декартово произведение
-    for left_row in &left_table.rows {
-        for right_row in &right_table.rows {
-            let mut new_row = left_row.clone();
-            new_row.extend_from_slice(right_row);
-            result_rows.push(new_row);
+/// Разбирает описание переименований: объект {старое: новое} или массив пар [[старое, новое], ...]
+fn parse_rename_pairs(mapping: &Value) -> Option<Vec<(String, String)>> {
+    match mapping {
+        Value::Object(map) => {
+            let mut pairs = Vec::with_capacity(map.len());
+            for (old, new) in map {
+                match new {
+                    Value::String(new) => pairs.push((old.clone(), new.to_string())),
+                    _ => return None,
+                }
+            }
+            Some(pairs)
+        }
+        Value::Array(arr) => {
+            let mut pairs = Vec::new();
+            for pair in arr.borrow().iter() {
+                let Value::Array(pair) = pair else { return None };
+                match pair.borrow().as_slice() {
+                    [Value::String(old), Value::String(new)] => pairs.push((old.to_string(), new.to_string())),
+                    _ => return None,
+                }
+            }
+            Some(pairs)
         }
+        _ => None,
     }
-    
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
 }
 
-// ============================================================================
-// Specialized JOIN Functions (syntactic sugar over native_table_join)
-// ============================================================================
+/// Ошибка изменения таблицы, которая уже занята в текущей операции (вместо паники RefCell)
+pub const TABLE_IN_USE: &str = "table is already in use in this operation";
 
-pub fn native_inner_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
+/// Изменяемый доступ к таблице; если таблица уже занята, выставляет StateError и возвращает None
+fn borrow_table_mut<'a>(table: &'a Rc<RefCell<Table>>, function: &str) -> Option<std::cell::RefMut<'a, Table>> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    match table.try_borrow_mut() {
+        Ok(mut table) => {
+            // Любое изменение на месте делает прежние ключи @cache для этой таблицы недействительными
+            table.generation += 1;
+            Some(table)
+        }
+        Err(_) => {
+            set_native_error_with_type(format!("{}(): {}", function, TABLE_IN_USE), ErrorType::StateError);
+            None
+        }
+    }
+}
+
+/// Переименовывает колонки таблицы на месте и возвращает ту же таблицу (для цепочек вызовов)
+pub fn rename_table_columns(table: &Rc<RefCell<Table>>, pairs: &[(String, String)]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let old_names: Vec<String> = pairs.iter().map(|(old, _)| old.clone()).collect();
+    if !check_columns_exist(&table.borrow(), &old_names, "table_rename") {
         return Value::Null;
     }
-    // Вызываем native_table_join с type="inner"
-    let mut new_args = args.to_vec();
-    
-    // Если переданы два отдельных строковых аргумента (left_col, right_col)
-    if new_args.len() == 4 {
-        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
-            // Создаем массив кортежей: [["left_col", "right_col"]]
-            let tuple = Value::Array(Rc::new(RefCell::new(vec![
-                new_args[2].clone(),
-                new_args[3].clone(),
-            ])));
-            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
-            new_args[2] = keys_array;
-            new_args[3] = Value::String("inner".to_string());
-        } else {
-            // Иначе перезаписываем тип join
-            new_args[3] = Value::String("inner".to_string());
-        }
-    } else if new_args.len() == 3 {
-        new_args.push(Value::String("inner".to_string()));
-    } else {
-        new_args[3] = Value::String("inner".to_string());
+    let Some(mut table_mut) = borrow_table_mut(table, "table_rename") else {
+        return Value::Null;
+    };
+    // Старые имена проверены выше, остается только конфликт новых имен
+    if let Err(message) = table_mut.rename_columns(pairs) {
+        set_native_error_with_type(format!("table_rename(): {}", message), ErrorType::ValueError);
+        return Value::Null;
     }
-    native_table_join(&new_args)
+    Value::Table(Rc::clone(table))
 }
 
-pub fn native_left_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
+/// table_rename(table, mapping) - переименовывает колонки на месте
+/// mapping: объект {"старое": "новое"} или массив пар [["старое", "новое"], ...]
+pub fn native_table_rename(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    if args.len() < 2 {
+        set_native_error_with_type(
+            "table_rename() expects a table and a mapping of column names".to_string(),
+            ErrorType::TypeError,
+        );
         return Value::Null;
     }
-    let mut new_args = args.to_vec();
-    
-    // Если переданы два отдельных строковых аргумента (left_col, right_col)
-    if new_args.len() == 4 {
-        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
-            // Создаем массив кортежей: [["left_col", "right_col"]]
-            let tuple = Value::Array(Rc::new(RefCell::new(vec![
-                new_args[2].clone(),
-                new_args[3].clone(),
-            ])));
-            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
-            new_args[2] = keys_array;
-            new_args[3] = Value::String("left".to_string());
-        } else {
-            // Иначе перезаписываем тип join
-            new_args[3] = Value::String("left".to_string());
+    let table = match &args[0] {
+        Value::Table(table) => table,
+        _ => {
+            set_native_error_with_type(
+                "table_rename() expects a table as the first argument".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    match parse_rename_pairs(&args[1]) {
+        Some(pairs) => rename_table_columns(table, &pairs),
+        None => {
+            set_native_error_with_type(
+                "table_rename() mapping must be an object or an array of [old, new] string pairs".to_string(),
+                ErrorType::TypeError,
+            );
+            Value::Null
         }
-    } else if new_args.len() == 3 {
-        new_args.push(Value::String("left".to_string()));
-    } else {
-        new_args[3] = Value::String("left".to_string());
     }
-    native_table_join(&new_args)
 }
 
-pub fn native_right_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
-        return Value::Null;
+/// table_reorder(table, columns, all = false) - новая таблица с колонками в порядке columns.
+/// Неперечисленные колонки отбрасываются; при all = true нужно перечислить все колонки
+pub fn native_table_reorder(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (table, columns) = match (args.first(), args.get(1)) {
+        (Some(Value::Table(table)), Some(Value::Array(columns))) => (table, columns),
+        _ => {
+            set_native_error_with_type(
+                "table_reorder() expects a table and an array of column names".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    let require_all = match args.get(2) {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(all)) => *all,
+        Some(_) => {
+            set_native_error_with_type("table_reorder() all must be a boolean".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
+
+    let table = table.borrow();
+    let mut headers: Vec<String> = Vec::new();
+    let mut indices = Vec::new();
+    for value in columns.borrow().iter() {
+        let Value::String(column) = value else {
+            set_native_error_with_type("table_reorder() column names must be strings".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        };
+        let Some(index) = table.headers.iter().position(|h| **h == **column) else {
+            set_native_error_with_type(format!("table_reorder(): unknown column: {}", column), ErrorType::ValueError);
+            return Value::Null;
+        };
+        if indices.contains(&index) {
+            set_native_error_with_type(format!("table_reorder(): column '{}' is listed twice", column), ErrorType::ValueError);
+            return Value::Null;
+        }
+        headers.push(column.to_string());
+        indices.push(index);
+    }
+    if require_all {
+        let missing: Vec<&str> = table.headers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !indices.contains(i))
+            .map(|(_, h)| h.as_str())
+            .collect();
+        if !missing.is_empty() {
+            set_native_error_with_type(
+                format!("table_reorder(): missing column(s) with all = true: {}", missing.join(", ")),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
     }
-    let mut new_args = args.to_vec();
-    
-    // Если переданы два отдельных строковых аргумента (left_col, right_col)
-    if new_args.len() == 4 {
-        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
-            // Создаем массив кортежей: [["left_col", "right_col"]]
-            let tuple = Value::Array(Rc::new(RefCell::new(vec![
-                new_args[2].clone(),
-                new_args[3].clone(),
-            ])));
-            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
-            new_args[2] = keys_array;
-            new_args[3] = Value::String("right".to_string());
-        } else {
-            // Иначе перезаписываем тип join
-            new_args[3] = Value::String("right".to_string());
+
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .map(|row| indices.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect())
+        .collect();
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
+}
+
+/// Имена колонок из строки или массива строк (аргумент columns табличных функций)
+fn column_names_arg(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(name) => Some(vec![name.to_string()]),
+        Value::Array(arr) => arr
+            .borrow()
+            .iter()
+            .map(|value| match value {
+                Value::String(name) => Some(name.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// table_drop_column(table, columns) - новая таблица без колонок columns (имя или массив имен).
+/// В отличие от table_drop исходная таблица не меняется; несуществующая колонка - ошибка
+pub fn native_table_drop_column(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (table, names) = match (args.first(), args.get(1).and_then(column_names_arg)) {
+        (Some(Value::Table(table)), Some(names)) => (table.borrow(), names),
+        _ => {
+            set_native_error_with_type(
+                "table_drop_column() expects a table and a column name or an array of names".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
         }
-    } else if new_args.len() == 3 {
-        new_args.push(Value::String("right".to_string()));
-    } else {
-        new_args[3] = Value::String("right".to_string());
+    };
+    let unknown: Vec<&str> = names.iter().filter(|n| !table.headers.contains(n)).map(|n| n.as_str()).collect();
+    if !unknown.is_empty() {
+        set_native_error_with_type(
+            format!("table_drop_column(): unknown column(s): {}", unknown.join(", ")),
+            ErrorType::ColumnNotFoundError,
+        );
+        return Value::Null;
     }
-    native_table_join(&new_args)
+
+    let kept: Vec<usize> = (0..table.headers.len()).filter(|&i| !names.contains(&table.headers[i])).collect();
+    let headers: Vec<String> = kept.iter().map(|&i| table.headers[i].clone()).collect();
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .map(|row| kept.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect())
+        .collect();
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
 }
 
-pub fn native_full_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
+/// table_drop_duplicates(table, columns?) - новая таблица без повторов: из строк с одинаковыми
+/// значениями колонок columns (по умолчанию всех) остается первая. Значения сравниваются как ==
+pub fn native_table_drop_duplicates(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let Some(Value::Table(table)) = args.first() else {
+        set_native_error_with_type(
+            "table_drop_duplicates() expects a table as the first argument".to_string(),
+            ErrorType::TypeError,
+        );
         return Value::Null;
+    };
+    let table = table.borrow();
+    let key_columns: Vec<usize> = match args.get(1) {
+        None | Some(Value::Null) => (0..table.headers.len()).collect(),
+        Some(value) => {
+            let Some(names) = column_names_arg(value) else {
+                set_native_error_with_type(
+                    "table_drop_duplicates() columns must be a column name or an array of names".to_string(),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            };
+            let Some(indices) = column_indices(&table, &names, "table_drop_duplicates") else {
+                return Value::Null;
+            };
+            indices
+        }
+    };
+
+    // Значения могут быть массивами и объектами, поэтому ключи сравниваются попарно, без хеширования
+    let mut seen: Vec<Vec<Value>> = Vec::new();
+    let mut rows = Vec::new();
+    for row in &table.rows {
+        let key: Vec<Value> = key_columns.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect();
+        if !seen.contains(&key) {
+            seen.push(key);
+            rows.push(row.clone());
+        }
     }
-    let mut new_args = args.to_vec();
-    if new_args.len() == 3 {
-        new_args.push(Value::String("full".to_string()));
-    } else {
-        new_args[3] = Value::String("full".to_string());
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(table.headers.clone())))))
+}
+
+/// Индексы колонок по именам; None и ColumnNotFoundError, если колонки нет
+fn column_indices(table: &Table, names: &[String], function: &str) -> Option<Vec<usize>> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let mut indices = Vec::with_capacity(names.len());
+    for name in names {
+        match table.headers.iter().position(|h| h == name) {
+            Some(index) => indices.push(index),
+            None => {
+                set_native_error_with_type(format!("{}(): unknown column: {}", function, name), ErrorType::ColumnNotFoundError);
+                return None;
+            }
+        }
     }
-    native_table_join(&new_args)
+    Some(indices)
 }
 
-pub fn native_cross_join(args: &[Value]) -> Value {
-    if args.len() < 2 {
+/// table_fillna(table, value) - новая таблица, в которой null заменены на value.
+/// value-объект задает значение для каждой колонки: {"колонка": значение}; остальные колонки не меняются
+pub fn native_table_fillna(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(fill)) = (args.first(), args.get(1)) else {
+        set_native_error_with_type(
+            "table_fillna() expects a table and a value or an object {column: value}".to_string(),
+            ErrorType::TypeError,
+        );
         return Value::Null;
+    };
+    let table = table.borrow();
+    let fills: Vec<Option<Value>> = match fill {
+        Value::Object(map) => {
+            let names: Vec<String> = map.keys().cloned().collect();
+            let Some(indices) = column_indices(&table, &names, "table_fillna") else {
+                return Value::Null;
+            };
+            let mut fills = vec![None; table.headers.len()];
+            for (index, value) in indices.into_iter().zip(map.values()) {
+                fills[index] = Some(value.clone());
+            }
+            fills
+        }
+        value => vec![Some(value.clone()); table.headers.len()],
+    };
+
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&fills)
+                .map(|(cell, fill)| match (cell, fill) {
+                    (Value::Null, Some(fill)) => fill.clone(),
+                    _ => cell.clone(),
+                })
+                .collect()
+        })
+        .collect();
+    let mut result = Table::from_data(rows, Some(table.headers.clone()));
+    result.name = table.name.clone();
+    Value::Table(Rc::new(RefCell::new(result)))
+}
+
+/// Неизвестные колонки из names - все сразу в одной ошибке ColumnNotFoundError; true, если таких нет
+fn check_columns_exist(table: &Table, names: &[String], function: &str) -> bool {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let unknown: Vec<&str> = names.iter().filter(|n| !table.headers.contains(n)).map(String::as_str).collect();
+    if !unknown.is_empty() {
+        set_native_error_with_type(
+            format!("{}(): unknown column(s): {}", function, unknown.join(", ")),
+            ErrorType::ColumnNotFoundError,
+        );
     }
-    // CROSS JOIN не требует ключей, используем пустой массив
-    let mut new_args = vec![args[0].clone(), args[1].clone()];
-    new_args.push(Value::Array(Rc::new(RefCell::new(Vec::new()))));
-    new_args.push(Value::String("cross".to_string()));
-    native_table_join(&new_args)
+    unknown.is_empty()
 }
 
-pub fn native_semi_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
+/// table_fill_nulls(table, fills) - заменяет null на месте значением своей колонки из объекта
+/// {"колонка": значение}; остальные колонки не меняются. Возвращает ту же таблицу
+pub fn native_table_fill_nulls(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(Value::Object(fills))) = (args.first(), args.get(1)) else {
+        set_native_error_with_type(
+            "table_fill_nulls() expects a table and an object {column: value}".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    };
+    let Some(mut table_mut) = borrow_table_mut(table, "table_fill_nulls") else {
+        return Value::Null;
+    };
+    let names: Vec<String> = fills.keys().cloned().collect();
+    if !check_columns_exist(&table_mut, &names, "table_fill_nulls") {
         return Value::Null;
     }
-    let mut new_args = args.to_vec();
-    
-    // Если переданы два отдельных строковых аргумента (left_col, right_col)
-    if new_args.len() == 4 {
-        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
-            // Создаем массив кортежей: [["left_col", "right_col"]]
-            let tuple = Value::Array(Rc::new(RefCell::new(vec![
-                new_args[2].clone(),
-                new_args[3].clone(),
-            ])));
-            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
-            new_args[2] = keys_array;
-            new_args[3] = Value::String("semi".to_string());
-        } else {
-            // Иначе перезаписываем тип join
-            new_args[3] = Value::String("semi".to_string());
+
+    for (name, fill) in fills.iter() {
+        let Some(column) = table_mut.get_column(name) else { continue };
+        if !column.iter().any(|value| matches!(value, Value::Null)) {
+            continue;
         }
-    } else if new_args.len() == 3 {
-        new_args.push(Value::String("semi".to_string()));
-    } else {
-        new_args[3] = Value::String("semi".to_string());
+        let values: Vec<Value> = column
+            .iter()
+            .map(|value| match value {
+                Value::Null => fill.clone(),
+                _ => value.clone(),
+            })
+            .collect();
+        table_mut.set_column(name, values);
     }
-    native_table_join(&new_args)
+    drop(table_mut);
+    Value::Table(Rc::clone(table))
 }
 
-pub fn native_anti_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
+/// table_fill_direction(table, columns, direction = "forward", partition_by = null) - заполняет
+/// null на месте ближайшим непустым значением колонки: forward - предыдущим, backward - следующим.
+/// С partition_by значение берется только из строк с теми же значениями этих колонок (строки
+/// одного датчика), поэтому не переходит между группами. null в начале (для backward - в конце)
+/// группы остаются null. Возвращает ту же таблицу
+pub fn native_table_fill_direction(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(names)) = (args.first(), args.get(1).and_then(column_names_arg)) else {
+        set_native_error_with_type(
+            "table_fill_direction() expects a table and a column name or an array of names".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    };
+    let backward = match args.get(2) {
+        None | Some(Value::Null) => false,
+        Some(Value::String(direction)) if matches!(&**direction, "forward" | "ffill") => false,
+        Some(Value::String(direction)) if matches!(&**direction, "backward" | "bfill") => true,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("table_fill_direction() direction must be \"forward\" or \"backward\", got {}", other.preview(40)),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+    };
+    let partition_by = match args.get(3) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(value) => match column_names_arg(value) {
+            Some(partition_by) => partition_by,
+            None => {
+                set_native_error_with_type(
+                    "table_fill_direction() partition_by must be a column name or an array of names".to_string(),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            }
+        },
+    };
+    if let Some(name) = names.iter().find(|name| partition_by.contains(name)) {
+        set_native_error_with_type(
+            format!("table_fill_direction(): column '{}' is also in partition_by", name),
+            ErrorType::ValueError,
+        );
         return Value::Null;
     }
-    let mut new_args = args.to_vec();
-    
-    // Если переданы два отдельных строковых аргумента (left_col, right_col)
-    if new_args.len() == 4 {
-        if let (Value::String(_), Value::String(_)) = (&new_args[2], &new_args[3]) {
-            // Создаем массив кортежей: [["left_col", "right_col"]]
-            let tuple = Value::Array(Rc::new(RefCell::new(vec![
-                new_args[2].clone(),
-                new_args[3].clone(),
-            ])));
-            let keys_array = Value::Array(Rc::new(RefCell::new(vec![tuple])));
-            new_args[2] = keys_array;
-            new_args[3] = Value::String("anti".to_string());
-        } else {
-            // Иначе перезаписываем тип join
-            new_args[3] = Value::String("anti".to_string());
+
+    let Some(mut table_mut) = borrow_table_mut(table, "table_fill_direction") else {
+        return Value::Null;
+    };
+    let all_names: Vec<String> = names.iter().chain(&partition_by).cloned().collect();
+    if !check_columns_exist(&table_mut, &all_names, "table_fill_direction") {
+        return Value::Null;
+    }
+
+    let partitions: Vec<&Vec<Value>> = partition_by.iter().filter_map(|name| table_mut.get_column(name)).collect();
+    // Ключ группы каждой строки; без partition_by группа одна
+    let mut groups: Vec<Vec<Value>> = Vec::new();
+    if !partitions.is_empty() {
+        for row in 0..table_mut.rows.len() {
+            let key: Vec<Value> = partitions.iter().map(|column| column.get(row).cloned().unwrap_or(Value::Null)).collect();
+            if let Some(bad) = key.iter().find(|value| !value.is_hashable()) {
+                set_native_error_with_type(
+                    format!("table_fill_direction(): partition values must be numbers, strings, booleans or null, got {}", bad.preview(40)),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            }
+            groups.push(key);
         }
-    } else if new_args.len() == 3 {
-        new_args.push(Value::String("anti".to_string()));
-    } else {
-        new_args[3] = Value::String("anti".to_string());
     }
-    native_table_join(&new_args)
+    let columns: Vec<Vec<Value>> = names
+        .iter()
+        .map(|name| {
+            let mut column = table_mut.get_column(name).cloned().unwrap_or_default();
+            fill_from_neighbours(&mut column, &groups, backward);
+            column
+        })
+        .collect();
+
+    for (name, values) in names.iter().zip(columns) {
+        table_mut.set_column(name, values);
+    }
+    drop(table_mut);
+    Value::Table(Rc::clone(table))
+}
+
+/// table_dropna(table, how = "any", subset = все колонки) - новая таблица без строк с null:
+/// how = "any" - если null хотя бы одна ячейка из subset, how = "all" - если все
+pub fn native_table_dropna(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let Some(Value::Table(table)) = args.first() else {
+        set_native_error_with_type("table_dropna() expects a table as the first argument".to_string(), ErrorType::TypeError);
+        return Value::Null;
+    };
+    let drop_if_all = match args.get(1) {
+        None | Some(Value::Null) => false,
+        Some(Value::String(how)) if &**how == "any" => false,
+        Some(Value::String(how)) if &**how == "all" => true,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("table_dropna() how must be \"any\" or \"all\", got {}", other.preview(40)),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        }
+    };
+    let table = table.borrow();
+    let subset = match args.get(2) {
+        None | Some(Value::Null) => (0..table.headers.len()).collect(),
+        Some(value) => {
+            let Some(names) = column_names_arg(value) else {
+                set_native_error_with_type(
+                    "table_dropna() subset must be a column name or an array of names".to_string(),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            };
+            let Some(indices) = column_indices(&table, &names, "table_dropna") else {
+                return Value::Null;
+            };
+            indices
+        }
+    };
+
+    // Недостающие ячейки короткой строки считаются null
+    let is_null = |row: &Vec<Value>, index: &usize| matches!(row.get(*index), None | Some(Value::Null));
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .filter(|row| {
+            let dropped = if drop_if_all {
+                !subset.is_empty() && subset.iter().all(|index| is_null(row, index))
+            } else {
+                subset.iter().any(|index| is_null(row, index))
+            };
+            !dropped
+        })
+        .cloned()
+        .collect();
+    let mut result = Table::from_data(rows, Some(table.headers.clone()));
+    result.name = table.name.clone();
+    Value::Table(Rc::new(RefCell::new(result)))
+}
+
+/// Вычисление table_window() над строками партиции
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WindowOp {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Lag(usize),
+    Lead(usize),
+    RowNumber,
+    Rank,
+}
+
+/// Спецификация вычисляемой колонки table_window(): имя, операция и колонка-аргумент
+#[derive(Debug, Clone)]
+struct WindowSpec {
+    name: String,
+    spec: String,
+    op: WindowOp,
+    column: Option<usize>,
 }
 
-// ZIP JOIN - позиционное соединение по индексу строки
-pub fn native_zip_join(args: &[Value]) -> Value {
-    if args.len() < 2 {
-        return Value::Null;
-    }
+/// Разбирает спецификацию "sum:amount", "lag:amount:2", "row_number" и т.п.
+/// Ошибки: ValueError для неверной записи, ColumnNotFoundError для неизвестной колонки
+fn parse_window_spec(table: &Table, name: &str, spec: &Value) -> Option<WindowSpec> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
 
-    let left_table = match &args[0] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+    let text = match spec {
+        Value::String(text) => text.to_string(),
+        other => other.to_string(),
     };
-    
-    let right_table = match &args[1] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+    let parts: Vec<&str> = text.split(':').map(str::trim).collect();
+    let offset = |part: Option<&&str>| match part {
+        None => Some(1),
+        Some(part) => part.parse::<usize>().ok(),
+    };
+    let op = match (parts[0].to_lowercase().as_str(), parts.len()) {
+        ("sum", 2) => Some(WindowOp::Sum),
+        ("avg", 2) => Some(WindowOp::Avg),
+        ("min", 2) => Some(WindowOp::Min),
+        ("max", 2) => Some(WindowOp::Max),
+        ("lag", 2 | 3) => offset(parts.get(2)).map(WindowOp::Lag),
+        ("lead", 2 | 3) => offset(parts.get(2)).map(WindowOp::Lead),
+        ("row_number", 1) => Some(WindowOp::RowNumber),
+        ("rank", 1) => Some(WindowOp::Rank),
+        _ => None,
+    };
+    let (Some(op), Value::String(_)) = (op, spec) else {
+        set_native_error_with_type(
+            format!(
+                "table_window(): invalid spec \"{}\" for column '{}': expected sum|avg|min|max:<column>, lag|lead:<column>[:offset], row_number or rank",
+                text, name
+            ),
+            ErrorType::ValueError,
+        );
+        return None;
     };
+    let column = match parts.get(1) {
+        Some(column) => match table.headers.iter().position(|h| h == column) {
+            Some(index) => Some(index),
+            None => {
+                set_native_error_with_type(
+                    format!("table_window(): unknown column '{}' in spec \"{}\"", column, text),
+                    ErrorType::ColumnNotFoundError,
+                );
+                return None;
+            }
+        },
+        None => None,
+    };
+    Some(WindowSpec { name: name.to_string(), spec: text, op, column })
+}
 
-    // Используем имена таблиц из table.name, если они установлены
-    let left_alias = left_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 2 {
-                match &args[2] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 1 {
-                            match &arr_ref[0] {
-                                Value::String(s) => s.clone(),
-                                _ => "left".to_string(),
-                            }
-                        } else {
-                            "left".to_string()
-                        }
+/// Значения вычисляемой колонки для партиции (индексы строк в порядке окна).
+/// same_order(a, b) - строки a и b равны по ключу сортировки (для rank)
+fn window_values(rows: &[Vec<Value>], partition: &[usize], spec: &WindowSpec, same_order: impl Fn(usize, usize) -> bool) -> Option<Vec<Value>> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let value_at = |position: usize| {
+        spec.column
+            .and_then(|column| rows[partition[position]].get(column))
+            .cloned()
+            .unwrap_or(Value::Null)
+    };
+    let len = partition.len();
+    let values = match spec.op {
+        WindowOp::RowNumber => (1..=len).map(|n| Value::Integer(n as i64)).collect(),
+        WindowOp::Rank => {
+            // Равные по ключу сортировки строки получают один ранг, следующий ранг пропускается: 1, 1, 3
+            let mut rank = 1;
+            (0..len)
+                .map(|position| {
+                    if position > 0 && !same_order(partition[position - 1], partition[position]) {
+                        rank = position + 1;
                     }
-                    _ => "left".to_string(),
-                }
-            } else {
-                "left".to_string()
-            }
-        });
-    
-    let right_alias = right_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 2 {
-                match &args[2] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 2 {
-                            match &arr_ref[1] {
-                                Value::String(s) => s.clone(),
-                                _ => "right".to_string(),
-                            }
-                        } else {
-                            "right".to_string()
+                    Value::Integer(rank as i64)
+                })
+                .collect()
+        }
+        WindowOp::Lag(offset) => (0..len)
+            .map(|position| if position >= offset { value_at(position - offset) } else { Value::Null })
+            .collect(),
+        WindowOp::Lead(offset) => (0..len)
+            .map(|position| if position + offset < len { value_at(position + offset) } else { Value::Null })
+            .collect(),
+        WindowOp::Sum | WindowOp::Avg => {
+            // Нарастающий итог по строкам до текущей включительно; null пропускаются
+            let (mut sum, mut count) = (0.0, 0);
+            let mut values = Vec::with_capacity(len);
+            for position in 0..len {
+                match value_at(position) {
+                    Value::Null => {}
+                    value => match value.as_f64() {
+                        Some(n) => {
+                            sum += n;
+                            count += 1;
                         }
-                    }
-                    _ => "right".to_string(),
+                        None => {
+                            set_native_error_with_type(
+                                format!("table_window(): \"{}\" expects numbers, got {}", spec.spec, value.preview(40)),
+                                ErrorType::TypeError,
+                            );
+                            return None;
+                        }
+                    },
                 }
-            } else {
-                "right".to_string()
+                values.push(match (count, spec.op) {
+                    (0, _) => Value::Null,
+                    (_, WindowOp::Sum) => Value::Number(sum),
+                    _ => Value::Number(sum / count as f64),
+                });
             }
-        });
-
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
-    
-    // Соединяем строки по позиции (индексу)
-    let min_len = std::cmp::min(left_table.rows.len(), right_table.rows.len());
-    for i in 0..min_len {
-        let mut new_row = left_table.rows[i].clone();
-        new_row.extend_from_slice(&right_table.rows[i]);
-        result_rows.push(new_row);
-    }
-    
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+            values
+        }
+        WindowOp::Min | WindowOp::Max => {
+            let wanted = if spec.op == WindowOp::Min { std::cmp::Ordering::Less } else { std::cmp::Ordering::Greater };
+            let mut best = Value::Null;
+            (0..len)
+                .map(|position| {
+                    let value = value_at(position);
+                    if !matches!(value, Value::Null)
+                        && (matches!(best, Value::Null) || compare_values(&value, &best) == wanted)
+                    {
+                        best = value;
+                    }
+                    best.clone()
+                })
+                .collect()
+        }
+    };
+    Some(values)
 }
 
-// APPLY JOIN / LATERAL JOIN - для каждой строки left вызывает функцию
-// apply_join(left: Table, fn: Function, type: "inner" | "left" = "inner") -> Table
-pub fn native_apply_join(_args: &[Value]) -> Value {
-    if _args.len() < 2 {
+/// table_window(table, partition_by, order_by, specs) - новая таблица с исходными колонками
+/// и оконными вычислениями {"колонка": "спецификация"} внутри каждой партиции.
+/// Строки идут по партициям (в порядке первого появления ключа), внутри партиции - по order_by
+/// (устойчиво, null первыми, как в table_sort)
+pub fn native_table_window(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (Some(Value::Table(table)), Some(Value::Object(specs))) = (args.first(), args.get(3)) else {
+        set_native_error_with_type(
+            "table_window() expects a table, partition_by, order_by and an object {column: spec}".to_string(),
+            ErrorType::TypeError,
+        );
         return Value::Null;
+    };
+    let table = table.borrow();
+    let mut key_columns = Vec::with_capacity(2);
+    for (name, value) in [("partition_by", args.get(1)), ("order_by", args.get(2))] {
+        let indices = match value {
+            None | Some(Value::Null) => Vec::new(),
+            Some(value) => {
+                let Some(names) = column_names_arg(value) else {
+                    set_native_error_with_type(
+                        format!("table_window() {} must be a column name, an array of names or null", name),
+                        ErrorType::TypeError,
+                    );
+                    return Value::Null;
+                };
+                let Some(indices) = column_indices(&table, &names, "table_window") else {
+                    return Value::Null;
+                };
+                indices
+            }
+        };
+        key_columns.push(indices);
     }
+    let (partition_by, order_by) = (&key_columns[0], &key_columns[1]);
 
-    let _left_table = match &_args[0] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+    // Все спецификации проверяются до вычислений
+    let mut window_specs = Vec::with_capacity(specs.len());
+    for (name, spec) in specs.iter() {
+        if table.headers.contains(name) {
+            set_native_error_with_type(format!("table_window(): column '{}' already exists", name), ErrorType::ValueError);
+            return Value::Null;
+        }
+        let Some(spec) = parse_window_spec(&table, name, spec) else {
+            return Value::Null;
+        };
+        window_specs.push(spec);
+    }
+
+    let cell = |row: usize, column: usize| table.rows[row].get(column).unwrap_or(&Value::Null);
+    // Ключ партиции: null не совпадает со строкой "null"
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    let mut positions: std::collections::HashMap<Vec<Option<String>>, usize> = std::collections::HashMap::new();
+    for row in 0..table.rows.len() {
+        let key: Vec<Option<String>> = partition_by
+            .iter()
+            .map(|&column| match cell(row, column) {
+                Value::Null => None,
+                value => Some(value.to_string()),
+            })
+            .collect();
+        match positions.get(&key) {
+            Some(&position) => partitions[position].push(row),
+            None => {
+                positions.insert(key, partitions.len());
+                partitions.push(vec![row]);
+            }
+        }
+    }
+    let compare_rows = |a: usize, b: usize| {
+        order_by
+            .iter()
+            .map(|&column| compare_values(cell(a, column), cell(b, column)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
     };
 
-    // Функция должна быть передана как Value::Function
-    // Но в текущей реализации функции не могут быть переданы как значения напрямую
-    // Это требует дополнительной инфраструктуры
-    // Пока возвращаем Null с предупреждением
-    // TODO: Реализовать поддержку функций как значений для apply_join
-    
-    Value::Null
+    let width = table.headers.len();
+    let mut rows = Vec::with_capacity(table.rows.len());
+    for partition in &mut partitions {
+        partition.sort_by(|&a, &b| compare_rows(a, b));
+        let mut computed = Vec::with_capacity(window_specs.len());
+        for spec in &window_specs {
+            let Some(values) = window_values(&table.rows, partition, spec, |a, b| compare_rows(a, b).is_eq()) else {
+                return Value::Null;
+            };
+            computed.push(values);
+        }
+        for (position, &row) in partition.iter().enumerate() {
+            let mut new_row = table.rows[row].clone();
+            new_row.resize(width, Value::Null);
+            new_row.extend(computed.iter().map(|values| values[position].clone()));
+            rows.push(new_row);
+        }
+    }
+
+    let mut headers = table.headers.clone();
+    headers.extend(window_specs.into_iter().map(|spec| spec.name));
+    let mut result = Table::from_data(rows, Some(headers));
+    result.name = table.name.clone();
+    Value::Table(Rc::new(RefCell::new(result)))
 }
 
-// ASOF JOIN - временное соединение
-// asof_join(left: Table, right: Table, on: Column, by: Column | List<Column>, 
-//           direction: "backward" | "forward" | "nearest" = "backward") -> Table
-pub fn native_asof_join(args: &[Value]) -> Value {
-    if args.len() < 3 {
-        return Value::Null;
+/// Целевой тип колонки table_cast()
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CastType {
+    Number,
+    Integer,
+    Bool,
+    String,
+}
+
+impl CastType {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "number" | "float" => Some(CastType::Number),
+            "integer" | "int" => Some(CastType::Integer),
+            "bool" | "boolean" => Some(CastType::Bool),
+            "string" | "str" => Some(CastType::String),
+            _ => None,
+        }
     }
+}
 
-    let left_table = match &args[0] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+/// Что делать с ячейкой, которую нельзя привести к типу (on_error)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CastOnError {
+    Error,
+    Null,
+    Keep,
+}
+
+/// Запись чисел в строках (locale): "us" - 1,024.50, "eu" - 1 024,50 и 1.024,50
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberLocale {
+    Us,
+    Eu,
+}
+
+/// Приводит запись числа к виду "-1024.50". Разделители разрядов допускаются только
+/// между группами из трех цифр, поэтому "1.5" в локали eu не становится числом 15
+fn normalize_number(text: &str, locale: NumberLocale) -> Option<String> {
+    let (decimal, thousands): (char, &[char]) = match locale {
+        NumberLocale::Us => ('.', &[',', ' ', '\u{a0}', '_', '\'']),
+        NumberLocale::Eu => (',', &['.', ' ', '\u{a0}', '_', '\'']),
     };
-    
-    let right_table = match &args[1] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+    let text = text.trim();
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.strip_prefix('+').unwrap_or(text)),
     };
-
-    // Парсим временную колонку
-    let time_column = match &args[2] {
-        Value::String(s) => s.clone(),
-        _ => return Value::Null,
+    let (integer, fraction) = match unsigned.split_once(decimal) {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (unsigned, ""),
     };
+    let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    let groups: Vec<&str> = integer.split(|c| thousands.contains(&c)).collect();
+    let grouped = groups.len() > 1;
+    let groups_valid = groups.iter().enumerate().all(|(i, group)| {
+        all_digits(group) && (!grouped || if i == 0 { (1..=3).contains(&group.len()) } else { group.len() == 3 })
+    });
+    let has_digits = !integer.is_empty() || !fraction.is_empty();
+    if !groups_valid || !all_digits(fraction) || !has_digits || unsigned.ends_with(decimal) {
+        return None;
+    }
+    let integer = groups.concat();
+    let integer = if integer.is_empty() { "0".to_string() } else { integer };
+    Some(if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    })
+}
 
-    // Проверяем наличие временной колонки в обеих таблицах
-    if left_table.get_column(&time_column).is_none() {
-        return Value::Null;
-    }
-    if right_table.get_column(&time_column).is_none() {
-        return Value::Null;
+/// Приводит значение ячейки к типу. None - значение не приводится.
+/// null и пустые строки становятся null при любом типе
+fn cast_cell(value: &Value, target: CastType, locale: NumberLocale) -> Option<Value> {
+    match (target, value) {
+        (_, Value::Null) => Some(Value::Null),
+        (_, Value::String(s)) if s.trim().is_empty() => Some(Value::Null),
+        (CastType::String, Value::String(s)) => Some(Value::String(s.clone())),
+        (CastType::String, other) => Some(Value::String(other.to_string().into())),
+        (CastType::Number, Value::Number(n)) => Some(Value::Number(*n)),
+        (CastType::Number, Value::Integer(i)) => Some(Value::Number(*i as f64)),
+        (CastType::Number, Value::String(s)) => normalize_number(s, locale)?.parse::<f64>().ok().map(Value::Number),
+        (CastType::Integer, Value::Integer(i)) => Some(Value::Integer(*i)),
+        (CastType::Integer, Value::Number(n)) if n.fract() == 0.0 && n.abs() < 9.0e15 => Some(Value::Integer(*n as i64)),
+        (CastType::Integer, Value::String(s)) => {
+            // Дробная часть допускается только из нулей: "3,00" -> 3
+            let normalized = normalize_number(s, locale)?;
+            let (integer, fraction) = normalized.split_once('.').unwrap_or((normalized.as_str(), ""));
+            if !fraction.chars().all(|c| c == '0') {
+                return None;
+            }
+            integer.parse::<i64>().ok().map(Value::Integer)
+        }
+        (CastType::Bool, Value::Bool(b)) => Some(Value::Bool(*b)),
+        (CastType::Bool, Value::Integer(i)) if *i == 0 || *i == 1 => Some(Value::Bool(*i == 1)),
+        (CastType::Bool, Value::Number(n)) if *n == 0.0 || *n == 1.0 => Some(Value::Bool(*n == 1.0)),
+        (CastType::Bool, Value::String(s)) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => Some(Value::Bool(true)),
+            "false" | "no" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
     }
+}
 
-    // Парсим by (группирующие колонки) - опционально
-    let by_columns: Vec<String> = if args.len() > 3 {
-        match &args[3] {
-            Value::String(s) => vec![s.clone()],
-            Value::Array(arr) => {
-                let arr_ref = arr.borrow();
-                let mut cols = Vec::new();
-                for val in arr_ref.iter() {
-                    if let Value::String(s) = val {
-                        cols.push(s.clone());
-                    }
-                }
-                cols
-            }
-            _ => Vec::new(),
+/// Разбирает необязательные аргументы on_error и locale функций table_cast/table_autocast
+fn cast_options(function: &str, on_error: Option<&Value>, locale: Option<&Value>) -> Option<(CastOnError, NumberLocale)> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let on_error = match on_error {
+        None | Some(Value::Null) => CastOnError::Error,
+        Some(Value::String(mode)) if &**mode == "error" => CastOnError::Error,
+        Some(Value::String(mode)) if &**mode == "null" => CastOnError::Null,
+        Some(Value::String(mode)) if &**mode == "keep" => CastOnError::Keep,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("{}() on_error must be \"error\", \"null\" or \"keep\", got {}", function, other.preview(40)),
+                ErrorType::ValueError,
+            );
+            return None;
+        }
+    };
+    let locale = match locale {
+        None | Some(Value::Null) => NumberLocale::Us,
+        Some(Value::String(name)) if name.eq_ignore_ascii_case("us") => NumberLocale::Us,
+        Some(Value::String(name)) if name.eq_ignore_ascii_case("eu") => NumberLocale::Eu,
+        Some(other) => {
+            set_native_error_with_type(
+                format!("{}() locale must be \"us\" or \"eu\", got {}", function, other.preview(40)),
+                ErrorType::ValueError,
+            );
+            return None;
         }
-    } else {
-        Vec::new()
     };
+    Some((on_error, locale))
+}
 
-    // Парсим direction (по умолчанию "backward")
-    let direction = if args.len() > 4 {
-        match &args[4] {
-            Value::String(s) => s.as_str(),
-            _ => "backward",
+/// Приводит значения колонки к типу. Err - первая ячейка, которую нельзя привести при on_error = "error"
+fn cast_column(column: &[Value], target: CastType, on_error: CastOnError, locale: NumberLocale) -> Result<Vec<Value>, (usize, Value)> {
+    column
+        .iter()
+        .enumerate()
+        .map(|(row, value)| match cast_cell(value, target, locale) {
+            Some(converted) => Ok(converted),
+            None => match on_error {
+                CastOnError::Error => Err((row, value.clone())),
+                CastOnError::Null => Ok(Value::Null),
+                CastOnError::Keep => Ok(value.clone()),
+            },
+        })
+        .collect()
+}
+
+/// table_cast(table, types, on_error = "error", locale = "us") - приводит колонки к типам на месте.
+/// types: объект {"колонка": "number" | "integer" | "bool" | "string"}.
+/// При on_error = "error" таблица не меняется, если хотя бы одну ячейку нельзя привести
+pub fn native_table_cast(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (table, types) = match (args.first(), args.get(1)) {
+        (Some(Value::Table(table)), Some(Value::Object(types))) => (table, types),
+        _ => {
+            set_native_error_with_type(
+                "table_cast() expects a table and an object {column: type}".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
         }
-    } else {
-        "backward"
+    };
+    let Some((on_error, locale)) = cast_options("table_cast", args.get(2), args.get(3)) else {
+        return Value::Null;
+    };
+    let Some(mut table_mut) = borrow_table_mut(table, "table_cast") else {
+        return Value::Null;
     };
 
-    // Используем имена таблиц из table.name, если они установлены
-    let left_alias = left_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 5 {
-                match &args[5] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 1 {
-                            match &arr_ref[0] {
-                                Value::String(s) => s.clone(),
-                                _ => "left".to_string(),
-                            }
-                        } else {
-                            "left".to_string()
-                        }
-                    }
-                    _ => "left".to_string(),
-                }
-            } else {
-                "left".to_string()
-            }
-        });
-    
-    let right_alias = right_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 5 {
-                match &args[5] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 2 {
-                            match &arr_ref[1] {
-                                Value::String(s) => s.clone(),
-                                _ => "right".to_string(),
-                            }
-                        } else {
-                            "right".to_string()
-                        }
-                    }
-                    _ => "right".to_string(),
-                }
-            } else {
-                "right".to_string()
+    // Сначала приводятся все колонки, затем результат записывается в таблицу
+    let mut converted = Vec::with_capacity(types.len());
+    for (column, type_name) in types {
+        let target = match type_name {
+            Value::String(name) => CastType::parse(name),
+            _ => None,
+        };
+        let Some(target) = target else {
+            set_native_error_with_type(
+                format!(
+                    "table_cast(): unknown type {} for column '{}' (expected number, integer, bool or string)",
+                    type_name.preview(40),
+                    column
+                ),
+                ErrorType::ValueError,
+            );
+            return Value::Null;
+        };
+        let Some(values) = table_mut.get_column(column) else {
+            set_native_error_with_type(format!("table_cast(): unknown column: {}", column), ErrorType::ColumnNotFoundError);
+            return Value::Null;
+        };
+        match cast_column(values, target, on_error, locale) {
+            Ok(values) => converted.push((column.clone(), values)),
+            Err((row, value)) => {
+                set_native_error_with_type(
+                    format!(
+                        "table_cast(): cannot convert {} in column '{}' (row {}) to {}",
+                        value.preview(40),
+                        column,
+                        row,
+                        type_name.to_string()
+                    ),
+                    ErrorType::DataFormatError,
+                );
+                return Value::Null;
             }
-        });
-
-    // Создаем индексы для временной колонки
-    let left_time_idx = left_table.headers.iter().position(|h| h == &time_column).unwrap();
-    let right_time_idx = right_table.headers.iter().position(|h| h == &time_column).unwrap();
+        }
+    }
+    for (column, values) in converted {
+        table_mut.set_column(&column, values);
+    }
+    drop(table_mut);
+    Value::Table(Rc::clone(table))
+}
 
-    // Если есть by колонки, группируем данные
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
+/// table_autocast(table, locale = "us") - приводит каждую колонку к самому точному типу, к которому
+/// приводятся все ее непустые значения: integer, затем number, затем bool. Колонки без строк
+/// и колонки, которые не приводятся целиком, не меняются. Возвращает ту же таблицу
+pub fn native_table_autocast(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
 
-    // Создаем NULL-строку для правой таблицы
-    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
+    let Some(Value::Table(table)) = args.first() else {
+        set_native_error_with_type("table_autocast() expects a table as the first argument".to_string(), ErrorType::TypeError);
+        return Value::Null;
+    };
+    let Some((_, locale)) = cast_options("table_autocast", None, args.get(1)) else {
+        return Value::Null;
+    };
+    let Some(mut table_mut) = borrow_table_mut(table, "table_autocast") else {
+        return Value::Null;
+    };
 
-    if by_columns.is_empty() {
-        // Нет группировки - простой ASOF join
-        // Сортируем правую таблицу по времени (для бинарного поиска)
-        let mut right_indices: Vec<usize> = (0..right_table.rows.len()).collect();
-        right_indices.sort_by(|&a, &b| {
-            let time_a = &right_table.rows[a][right_time_idx];
-            let time_b = &right_table.rows[b][right_time_idx];
-            compare_values(time_a, time_b)
-        });
+    for header in table_mut.headers.clone() {
+        let Some(column) = table_mut.get_column(&header) else { continue };
+        // Только колонки с непустыми строками: уже типизированные колонки не трогаются
+        if !column.iter().any(|value| matches!(value, Value::String(s) if !s.trim().is_empty())) {
+            continue;
+        }
+        let inferred = [CastType::Integer, CastType::Number, CastType::Bool]
+            .into_iter()
+            .find_map(|target| cast_column(column, target, CastOnError::Error, locale).ok());
+        if let Some(values) = inferred {
+            table_mut.set_column(&header, values);
+        }
+    }
+    drop(table_mut);
+    Value::Table(Rc::clone(table))
+}
 
-        for left_row in &left_table.rows {
-            let left_time = &left_row[left_time_idx];
-            
-            // Ищем ближайшую строку в правой таблице
-            let mut best_match: Option<usize> = None;
-            let mut best_diff: Option<f64> = None;
+/// table_drop(table, columns) - удаляет колонки на месте
+/// columns: массив имен или строка (имя колонки либо glob-шаблон вроде "tmp_*")
+/// Возвращает объект {table, dropped, warning}; warning = true, если шаблон не совпал ни с одной колонкой
+pub fn native_table_drop(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
 
-            for &right_idx in &right_indices {
-                let right_row = &right_table.rows[right_idx];
-                let right_time = &right_row[right_time_idx];
+    if args.len() < 2 {
+        set_native_error_with_type(
+            "table_drop() expects a table and column names or a pattern".to_string(),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    }
+    let table = match &args[0] {
+        Value::Table(table) => table,
+        _ => {
+            set_native_error_with_type(
+                "table_drop() expects a table as the first argument".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
 
-                // Вычисляем разницу времени (упрощенная версия - только для чисел)
-                let diff = match (left_time, right_time) {
-                    (Value::Number(l), Value::Number(r)) => {
-                        let diff_val = match direction {
-                            "backward" => *l - *r,  // left_time >= right_time
-                            "forward" => *r - *l,   // right_time >= left_time
-                            "nearest" => (*l - *r).abs(),
-                            _ => *l - *r,
-                        };
-                        Some(diff_val)
+    let headers = table.borrow().headers.clone();
+    let (names, is_pattern) = match &args[1] {
+        Value::String(spec) if spec.contains(['*', '?', '[']) => {
+            let pattern = match glob::Pattern::new(spec) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    set_native_error_with_type(
+                        format!("table_drop(): invalid pattern '{}': {}", spec, e),
+                        ErrorType::ValueError,
+                    );
+                    return Value::Null;
+                }
+            };
+            let matched: Vec<String> = headers.iter().filter(|h| pattern.matches(h)).cloned().collect();
+            (matched, true)
+        }
+        Value::String(name) => (vec![name.to_string()], false),
+        Value::Array(arr) => {
+            let mut names = Vec::new();
+            for value in arr.borrow().iter() {
+                match value {
+                    Value::String(name) => names.push(name.to_string()),
+                    _ => {
+                        set_native_error_with_type(
+                            "table_drop() column names must be strings".to_string(),
+                            ErrorType::TypeError,
+                        );
+                        return Value::Null;
                     }
-                    _ => None,
-                };
+                }
+            }
+            (names, false)
+        }
+        _ => {
+            set_native_error_with_type(
+                "table_drop() expects an array of column names or a pattern string".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
 
-                if let Some(d) = diff {
-                    let matches_direction = match direction {
-                        "backward" => d >= 0.0,
-                        "forward" => d >= 0.0,
-                        "nearest" => true,
-                        _ => d >= 0.0,
-                    };
+    // Явно перечисленные колонки должны существовать
+    if !check_columns_exist(&table.borrow(), &names, "table_drop") {
+        return Value::Null;
+    }
 
-                    if matches_direction {
-                        let should_update = match best_diff {
-                            None => true,
-                            Some(bd) => match direction {
-                                "nearest" => d < bd,
-                                _ => d < bd,
-                            },
-                        };
+    let dropped = match borrow_table_mut(table, "table_drop") {
+        Some(mut table_mut) => table_mut.drop_columns(&names),
+        None => return Value::Null,
+    };
+    let mut result = ObjectMap::new();
+    result.insert("warning".to_string(), Value::Bool(is_pattern && dropped.is_empty()));
+    result.insert("dropped".to_string(), Value::Array(Rc::new(RefCell::new(
+        dropped.into_iter().map(|name| Value::String(name.into())).collect(),
+    ))));
+    result.insert("table".to_string(), Value::Table(Rc::clone(table)));
+    Value::Object(result)
+}
 
-                        if should_update {
-                            best_match = Some(right_idx);
-                            best_diff = Some(d);
-                        }
+/// Строки таблицы, сгруппированные по ключевым колонкам: группы идут в порядке первого
+/// появления ключа, значения колонки внутри группы - в порядке строк. null в колонке значений
+/// пропускаются, поэтому группа может остаться пустой
+struct ColumnGroups {
+    keys: Vec<String>,
+    column: String,
+    groups: Vec<(Vec<Value>, Vec<Value>)>,
+}
+
+/// Ключевые колонки группировки: имя колонки или массив имен
+fn key_column_names(name: &str, keys: &Value) -> Option<Vec<String>> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    match keys {
+        Value::String(key) => Some(vec![key.to_string()]),
+        Value::Array(arr) => {
+            let mut keys = Vec::new();
+            for value in arr.borrow().iter() {
+                match value {
+                    Value::String(key) => keys.push(key.to_string()),
+                    _ => {
+                        set_native_error_with_type(format!("{}() key column names must be strings", name), ErrorType::TypeError);
+                        return None;
                     }
                 }
             }
-
-            if let Some(right_idx) = best_match {
-                let mut new_row = left_row.clone();
-                new_row.extend_from_slice(&right_table.rows[right_idx]);
-                result_rows.push(new_row);
-            } else {
-                // Нет совпадения - добавляем строку с NULL справа (для left join семантики)
-                let mut new_row = left_row.clone();
-                new_row.extend_from_slice(&null_right_row);
-                result_rows.push(new_row);
-            }
+            Some(keys)
+        }
+        _ => {
+            set_native_error_with_type(
+                format!("{}() expects a key column name or an array of names", name),
+                ErrorType::TypeError,
+            );
+            None
         }
-    } else {
-        // Есть группировка - группируем по by колонкам
-        // Упрощенная реализация: для каждой группы выполняем ASOF join
-        // TODO: Реализовать полную поддержку группировки
-        // Пока возвращаем пустую таблицу
-        return Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))));
     }
+}
 
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+/// Общая часть table_string_agg/table_collect: разбор (table, keys, column) и группировка
+fn group_column_values(name: &str, args: &[Value]) -> Option<ColumnGroups> {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+    use std::collections::HashMap;
+
+    let (table, keys, column) = match (args.first(), args.get(1), args.get(2)) {
+        (Some(Value::Table(table)), Some(keys), Some(Value::String(column))) => (table, keys, column.to_string()),
+        _ => {
+            set_native_error_with_type(
+                format!("{}() expects a table, key column(s) and a column name", name),
+                ErrorType::TypeError,
+            );
+            return None;
+        }
+    };
+    let keys = key_column_names(name, keys)?;
+    if keys.contains(&column) {
+        set_native_error_with_type(format!("{}(): column '{}' is also a key", name, column), ErrorType::ValueError);
+        return None;
+    }
+
+    let table = table.borrow();
+    let position = |header: &String| table.headers.iter().position(|h| h == header);
+    let unknown: Vec<&str> = keys.iter().chain([&column]).filter(|h| position(h).is_none()).map(String::as_str).collect();
+    if !unknown.is_empty() {
+        set_native_error_with_type(format!("{}(): unknown column(s): {}", name, unknown.join(", ")), ErrorType::ValueError);
+        return None;
+    }
+    let key_indices: Vec<usize> = keys.iter().filter_map(position).collect();
+    let column_index = position(&column)?;
+
+    let mut group_index: HashMap<Vec<Value>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Value>, Vec<Value>)> = Vec::new();
+    for row in &table.rows {
+        let key: Vec<Value> = key_indices.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect();
+        if let Some(bad) = key.iter().find(|v| !v.is_hashable()) {
+            set_native_error_with_type(
+                format!("{}(): key values must be numbers, strings, booleans or null, got {}", name, bad.preview(40)),
+                ErrorType::TypeError,
+            );
+            return None;
+        }
+        let index = *group_index.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        match row.get(column_index) {
+            Some(Value::Null) | None => {}
+            Some(value) => groups[index].1.push(value.clone()),
+        }
+    }
+    Some(ColumnGroups { keys, column, groups })
 }
 
-// JOIN ON - non-equi join с произвольным условием
-// join_on(left: Table, right: Table, condition: Expr, type: JoinType = "inner") -> Table
-// Примечание: Полная поддержка выражений требует дополнительной инфраструктуры.
-// Пока реализована упрощенная версия для простых условий сравнения колонок.
-pub fn native_join_on(args: &[Value]) -> Value {
-    if args.len() < 3 {
-        return Value::Null;
+impl ColumnGroups {
+    /// Таблица: ключевые колонки и колонка column с результатом агрегации каждой группы
+    fn into_table(self, aggregate: impl Fn(Vec<Value>) -> Value) -> Value {
+        let mut headers = self.keys;
+        headers.push(self.column);
+        let rows: Vec<Vec<Value>> = self.groups
+            .into_iter()
+            .map(|(mut key, values)| {
+                key.push(aggregate(values));
+                key
+            })
+            .collect();
+        Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
     }
+}
 
-    let left_table = match &args[0] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
-    };
-    
-    let right_table = match &args[1] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
+/// table_string_agg(table, keys, column, separator = ", ") - одна строка на группу ключей,
+/// значения column группы склеены через separator в порядке строк ("1001; 1007; 1033")
+pub fn native_table_string_agg(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let separator = match args.get(3) {
+        None | Some(Value::Null) => ", ".to_string(),
+        Some(Value::String(separator)) => separator.to_string(),
+        Some(_) => {
+            set_native_error_with_type("table_string_agg() separator must be a string".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
     };
+    match group_column_values("table_string_agg", args) {
+        Some(groups) => groups.into_table(|values| {
+            let parts: Vec<String> = values.iter().map(Value::to_string).collect();
+            Value::String(parts.join(&separator).into())
+        }),
+        None => Value::Null,
+    }
+}
 
-    // Парсим условие - пока упрощенная версия
-    // Ожидаем строку вида "left_col >= right_col" или массив ["left_col", ">=", "right_col"]
-    let condition = &args[2];
-    
-    // Парсим тип JOIN (по умолчанию inner)
-    let join_type = if args.len() > 3 {
-        match &args[3] {
-            Value::String(s) => match s.as_str() {
-                "inner" => JoinType::Inner,
-                "left" => JoinType::Left,
-                "right" => JoinType::Right,
-                "full" => JoinType::Full,
-                _ => JoinType::Inner,
-            },
-            _ => JoinType::Inner,
+/// table_collect(table, keys, column) - одна строка на группу ключей, в column - массив
+/// значений группы в порядке строк
+pub fn native_table_collect(args: &[Value]) -> Value {
+    match group_column_values("table_collect", args) {
+        Some(groups) => groups.into_table(|values| Value::Array(Rc::new(RefCell::new(values)))),
+        None => Value::Null,
+    }
+}
+
+/// table_group_by(table, keys, aggregations) - одна строка на группу ключей с агрегатами.
+/// aggregations - объект {колонка: функция или массив функций} (sum, average/avg, count, min, max),
+/// колонки результата называются "колонка_функция"; {"*": "count"} добавляет колонку count
+/// с числом строк группы. Ленивая таблица над CSV файлом агрегируется за один проход чтения
+pub fn native_table_group_by(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (table, keys, aggregations) = match (args.first(), args.get(1), args.get(2)) {
+        (Some(table @ (Value::Table(_) | Value::LazyTable(_))), Some(keys), Some(Value::Object(aggregations))) => {
+            (table, keys, aggregations)
         }
-    } else {
-        JoinType::Inner
+        _ => {
+            set_native_error_with_type(
+                "table_group_by() expects a table, key column(s) and an object of aggregations".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    let keys = match key_column_names("table_group_by", keys) {
+        Some(keys) => keys,
+        None => return Value::Null,
     };
 
-    // Используем имена таблиц из table.name, если они установлены
-    let left_alias = left_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 4 {
-                match &args[4] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 1 {
-                            match &arr_ref[0] {
-                                Value::String(s) => s.clone(),
-                                _ => "left".to_string(),
-                            }
-                        } else {
-                            "left".to_string()
-                        }
-                    }
-                    _ => "left".to_string(),
+    // Строки передаются как значения колонок columns: сначала ключи, затем колонки агрегатов
+    let mut columns = keys.clone();
+    let mut headers = keys.clone();
+    let mut specs = Vec::new();
+    for (column, spec) in aggregations.iter() {
+        let names: Vec<Value> = match spec {
+            Value::String(_) => vec![spec.clone()],
+            Value::Array(arr) => arr.borrow().clone(),
+            _ => {
+                set_native_error_with_type(
+                    format!("table_group_by(): aggregation for '{}' must be a function name or an array of names", column),
+                    ErrorType::TypeError,
+                );
+                return Value::Null;
+            }
+        };
+        for function in names {
+            let func = match &function {
+                Value::String(function) => AggFunc::parse(function),
+                _ => None,
+            };
+            let func = match func {
+                Some(func) => func,
+                None => {
+                    set_native_error_with_type(
+                        format!(
+                            "table_group_by(): unknown aggregation {} for '{}' (expected sum, average, count, min or max)",
+                            function.preview(40),
+                            column
+                        ),
+                        ErrorType::ValueError,
+                    );
+                    return Value::Null;
                 }
-            } else {
-                "left".to_string()
+            };
+            if column == "*" {
+                if func != AggFunc::Count {
+                    set_native_error_with_type(
+                        "table_group_by(): '*' can only be used with count".to_string(),
+                        ErrorType::ValueError,
+                    );
+                    return Value::Null;
+                }
+                specs.push(AggSpec { func, column: None });
+                headers.push("count".to_string());
+                continue;
             }
-        });
-    
-    let right_alias = right_table.name.as_ref()
-        .map(|n| n.clone())
-        .unwrap_or_else(|| {
-            if args.len() > 4 {
-                match &args[4] {
-                    Value::Array(arr) => {
-                        let arr_ref = arr.borrow();
-                        if arr_ref.len() >= 2 {
-                            match &arr_ref[1] {
-                                Value::String(s) => s.clone(),
-                                _ => "right".to_string(),
-                            }
-                        } else {
-                            "right".to_string()
-                        }
-                    }
-                    _ => "right".to_string(),
+            let position = match columns.iter().position(|c| c == column) {
+                Some(position) => position,
+                None => {
+                    columns.push(column.clone());
+                    columns.len() - 1
                 }
-            } else {
-                "right".to_string()
+            };
+            specs.push(AggSpec { func, column: Some(position) });
+            headers.push(format!("{}_{}", column, func.name()));
+        }
+    }
+    if let Some(duplicate) = headers.iter().enumerate().find(|(i, h)| headers[..*i].contains(h)).map(|(_, h)| h) {
+        set_native_error_with_type(
+            format!("table_group_by(): duplicate result column '{}'", duplicate),
+            ErrorType::ValueError,
+        );
+        return Value::Null;
+    }
+
+    let mut groups = GroupAggregator::new((0..keys.len()).collect(), specs);
+    let mut bad_key: Option<Value> = None;
+    let visited = for_each_table_row("table_group_by", table, &columns, &mut |row: &[Value]| {
+        if bad_key.is_none() {
+            if let Err(bad) = groups.push(row) {
+                bad_key = Some(bad);
             }
-        });
+        }
+    });
+    if let Some(bad) = bad_key {
+        set_native_error_with_type(
+            format!("table_group_by(): key values must be numbers, strings, booleans or null, got {}", bad.preview(40)),
+            ErrorType::TypeError,
+        );
+        return Value::Null;
+    }
+    if !visited {
+        return Value::Null;
+    }
+    Value::Table(Rc::new(RefCell::new(groups.into_table(headers))))
+}
+
+/// table_merge_columns(table, columns, name, separator = " ") - новая таблица с колонкой name,
+/// в которой значения columns каждой строки склеены через separator (null пропускаются)
+pub fn native_table_merge_columns(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    let (table, columns, name) = match (args.first(), args.get(1), args.get(2)) {
+        (Some(Value::Table(table)), Some(Value::Array(columns)), Some(Value::String(name))) => (table, columns, name.to_string()),
+        _ => {
+            set_native_error_with_type(
+                "table_merge_columns() expects a table, an array of column names and a new column name".to_string(),
+                ErrorType::TypeError,
+            );
+            return Value::Null;
+        }
+    };
+    let separator = match args.get(3) {
+        None | Some(Value::Null) => " ".to_string(),
+        Some(Value::String(separator)) => separator.to_string(),
+        Some(_) => {
+            set_native_error_with_type("table_merge_columns() separator must be a string".to_string(), ErrorType::TypeError);
+            return Value::Null;
+        }
+    };
 
-    // Парсим условие - упрощенная версия
-    // Формат: ["left_col", ">=", "right_col"] или строка "left_col >= right_col"
-    let (left_col, op, right_col) = match condition {
-        Value::String(s) => {
-            // Парсим строку вида "left_col >= right_col"
-            let parts: Vec<&str> = s.split_whitespace().collect();
-            if parts.len() >= 3 {
-                (parts[0].to_string(), parts[1].to_string(), parts[2].to_string())
-            } else {
+    let table = table.borrow();
+    let mut indices = Vec::new();
+    for value in columns.borrow().iter() {
+        let index = match value {
+            Value::String(column) => table.headers.iter().position(|h| **h == **column),
+            _ => {
+                set_native_error_with_type("table_merge_columns() column names must be strings".to_string(), ErrorType::TypeError);
                 return Value::Null;
             }
-        }
-        Value::Array(arr) => {
-            let arr_ref = arr.borrow();
-            if arr_ref.len() >= 3 {
-                let left = match &arr_ref[0] {
-                    Value::String(s) => s.clone(),
-                    _ => return Value::Null,
-                };
-                let op = match &arr_ref[1] {
-                    Value::String(s) => s.clone(),
-                    _ => return Value::Null,
-                };
-                let right = match &arr_ref[2] {
-                    Value::String(s) => s.clone(),
-                    _ => return Value::Null,
-                };
-                (left, op, right)
-            } else {
+        };
+        match index {
+            Some(index) => indices.push(index),
+            None => {
+                set_native_error_with_type(format!("table_merge_columns(): unknown column: {}", value.to_string()), ErrorType::ValueError);
                 return Value::Null;
             }
         }
-        _ => return Value::Null,
-    };
-
-    // Проверяем наличие колонок
-    if left_table.get_column(&left_col).is_none() {
-        return Value::Null;
     }
-    if right_table.get_column(&right_col).is_none() {
+    if table.headers.contains(&name) {
+        set_native_error_with_type(format!("table_merge_columns(): column '{}' already exists", name), ErrorType::ValueError);
         return Value::Null;
     }
 
-    let left_col_idx = left_table.headers.iter().position(|h| h == &left_col).unwrap();
-    let right_col_idx = right_table.headers.iter().position(|h| h == &right_col).unwrap();
+    let rows: Vec<Vec<Value>> = table.rows
+        .iter()
+        .map(|row| {
+            let parts: Vec<String> = indices
+                .iter()
+                .filter_map(|&i| row.get(i))
+                .filter(|v| !matches!(v, Value::Null))
+                .map(Value::to_string)
+                .collect();
+            let mut row = row.clone();
+            row.push(Value::String(parts.join(&separator).into()));
+            row
+        })
+        .collect();
+    let mut headers = table.headers.clone();
+    headers.push(name);
+    Value::Table(Rc::new(RefCell::new(Table::from_data(rows, Some(headers)))))
+}
 
-    let mut result_rows = Vec::new();
-    let mut result_headers = Vec::new();
-    
-    // Создаем заголовки с учетом алиасов таблиц
-    let left_headers_set: std::collections::HashSet<String> = left_table.headers.iter().cloned().collect();
-    let right_headers_set: std::collections::HashSet<String> = right_table.headers.iter().cloned().collect();
-    
-    let mut left_headers = apply_column_aliases(&left_table.headers, &left_alias, &right_headers_set);
-    let mut right_headers = apply_column_aliases(&right_table.headers, &right_alias, &left_headers_set);
-    
-    result_headers.append(&mut left_headers);
-    result_headers.append(&mut right_headers);
+/// table_rename_map(table, fn) - применяет функцию к каждому имени колонки (реализация в Vm::call_table_rename_map)
+pub fn native_table_rename_map(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    // Создаем NULL-строки
-    let null_left_row: Vec<Value> = (0..left_table.headers.len()).map(|_| Value::Null).collect();
-    let null_right_row: Vec<Value> = (0..right_table.headers.len()).map(|_| Value::Null).collect();
+/// table_add_column(table, name, fn) - новая таблица с вычисляемой колонкой (реализация в Vm::call_table_add_column)
+pub fn native_table_add_column(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    // Nested loop join с проверкой условия
-    let mut matched_right_indices = std::collections::HashSet::new();
+/// table_drop_rows(table, predicate) - новая таблица без строк, для которых predicate(строка) истинно
+/// (реализация в Vm::call_table_drop_rows)
+pub fn native_table_drop_rows(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    for left_row in &left_table.rows {
-        let left_val = &left_row[left_col_idx];
-        let mut found_match = false;
+/// table_filter_expr(table, expr, missing = null) - строки, для которых выражение истинно
+/// (реализация в Vm::call_table_filter_expr)
+pub fn native_table_filter_expr(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-        for (right_idx, right_row) in right_table.rows.iter().enumerate() {
-            let right_val = &right_row[right_col_idx];
-            
-            // Проверяем условие
-            let condition_met = match op.as_str() {
-                ">" => compare_values(left_val, right_val) == std::cmp::Ordering::Greater,
-                "<" => compare_values(left_val, right_val) == std::cmp::Ordering::Less,
-                ">=" => {
-                    let cmp = compare_values(left_val, right_val);
-                    cmp == std::cmp::Ordering::Greater || cmp == std::cmp::Ordering::Equal
-                }
-                "<=" => {
-                    let cmp = compare_values(left_val, right_val);
-                    cmp == std::cmp::Ordering::Less || cmp == std::cmp::Ordering::Equal
-                }
-                "==" | "=" => compare_values(left_val, right_val) == std::cmp::Ordering::Equal,
-                "!=" | "<>" => compare_values(left_val, right_val) != std::cmp::Ordering::Equal,
-                _ => false,
-            };
+/// table_apply(table, fn) - новая таблица с fn(значение) в каждой ячейке (реализация в Vm::call_table_apply)
+pub fn native_table_apply(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-            if condition_met {
-                found_match = true;
-                matched_right_indices.insert(right_idx);
-                let mut new_row = left_row.clone();
-                new_row.extend_from_slice(right_row);
-                result_rows.push(new_row);
-            }
-        }
+/// table_apply_column(table, column, fn) - fn(значение) только в одной колонке
+/// (реализация в Vm::call_table_apply)
+pub fn native_table_apply_column(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-        // Для LEFT JOIN добавляем строки без совпадений
-        if !found_match && (join_type == JoinType::Left || join_type == JoinType::Full) {
-            let mut new_row = left_row.clone();
-            new_row.extend_from_slice(&null_right_row);
-            result_rows.push(new_row);
-        }
+/// cache_stats(fn?) - попадания, промахи и число записей кэшей функций @cache
+/// (реализация в Vm::call_memoization)
+pub fn native_cache_stats(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// cache_clear(fn?) - очищает кэш одной функции или всех функций (реализация в Vm::call_memoization)
+pub fn native_cache_clear(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// set_memoization(fn, enabled) - включает или отключает кэширование функции
+/// (реализация в Vm::call_memoization)
+pub fn native_set_memoization(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+
+// Проверки для тестов на DataCode (assert, assert_eq, assert_throws)
+
+/// Представление значения в сообщениях проверок: строки в кавычках, вложенные значения как JSON
+pub fn assert_repr(value: &Value) -> String {
+    match value {
+        Value::Table(_) | Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_) | Value::Path(_) => value.to_string(),
+        _ => serde_json::to_string(&value.to_json()).unwrap_or_else(|_| value.to_string()),
     }
+}
 
-    // Для RIGHT и FULL JOIN добавляем несовпадающие строки справа
-    if join_type == JoinType::Right || join_type == JoinType::Full {
-        for (right_idx, right_row) in right_table.rows.iter().enumerate() {
-            if !matched_right_indices.contains(&right_idx) {
-                let mut new_row = null_left_row.clone();
-                new_row.extend_from_slice(right_row);
-                result_rows.push(new_row);
-            }
-        }
+fn assertion_message(custom: Option<&Value>, default: &str) -> String {
+    match custom {
+        Some(Value::Null) | None => format!("Assertion failed: {}", default),
+        Some(message) => format!("Assertion failed: {}\n  {}", message.to_string(), default),
     }
+}
 
-    Value::Table(Rc::new(RefCell::new(Table::from_data(result_rows, Some(result_headers)))))
+/// assert(condition, message=null) - AssertionError, если условие ложно
+pub fn native_assert(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let condition = args.first().unwrap_or(&Value::Null);
+    if !condition.is_truthy() {
+        let default = format!("condition is {}", assert_repr(condition));
+        set_native_error_with_type(assertion_message(args.get(1), &default), ErrorType::AssertionError);
+    }
+    Value::Null
 }
 
-// Применение суффиксов к колонкам таблицы после join
-// Заменяет префиксы "left." и "right." на пользовательские суффиксы
-pub fn native_table_suffixes(args: &[Value]) -> Value {
-    if args.len() < 3 {
-        return Value::Null;
+/// assert_eq(left, right, message=null) - AssertionError с обоими значениями, если они не равны
+pub fn native_assert_eq(args: &[Value]) -> Value {
+    use crate::websocket::set_native_error_with_type;
+    use crate::common::error::ErrorType;
+
+    let left = args.first().unwrap_or(&Value::Null);
+    let right = args.get(1).unwrap_or(&Value::Null);
+    if left != right {
+        let diff = format!(
+            "values are not equal\n  - left:  {}\n  + right: {}",
+            assert_repr(left),
+            assert_repr(right)
+        );
+        set_native_error_with_type(assertion_message(args.get(2), &diff), ErrorType::AssertionError);
     }
+    Value::Null
+}
 
-    // Аргументы приходят в порядке: [table, left_suffix, right_suffix]
-    // Извлекаем таблицу
-    let table = match &args[0] {
-        Value::Table(t) => t.borrow().clone(),
-        _ => return Value::Null,
-    };
+/// assert_throws(fn, args=[]) - проверяет, что вызов завершается ошибкой (реализация в Vm::call_assert_throws)
+pub fn native_assert_throws(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    // Извлекаем суффиксы
-    let left_suffix = match &args[1] {
-        Value::String(s) => s.clone(),
-        _ => return Value::Null,
-    };
+// Динамическое выполнение кода
+// eval() и exec() выполняются самой VM (нужен доступ к фреймам, глобальным переменным и компилятору),
+// здесь только заглушки, чтобы индексы в таблице нативных функций совпадали с компилятором
 
-    let right_suffix = match &args[2] {
-        Value::String(s) => s.clone(),
-        _ => return Value::Null,
-    };
+/// eval(code) - вычисляет выражение из строки (реализация в Vm::call_dynamic)
+pub fn native_eval(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    // Создаем новую таблицу с переименованными колонками
-    let mut new_headers = Vec::new();
-    let mut column_mapping = std::collections::HashMap::new(); // старое имя -> новое имя
+/// exec(code) - выполняет инструкции из строки (реализация в Vm::call_dynamic)
+pub fn native_exec(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    // Определяем, какие колонки относятся к левой таблице, а какие к правой
-    // Префиксы могут быть вида "left.", "right." или "table_name."
-    // Определяем первые уникальные префиксы как левую таблицу, остальные - как правую
-    let mut seen_prefixes = Vec::new();
-    let mut prefix_to_table = std::collections::HashMap::new(); // prefix -> "left" или "right"
-    
-    // Сначала проходим по всем заголовкам и собираем уникальные префиксы
-    for header in &table.headers {
-        if let Some(dot_pos) = header.find('.') {
-            let prefix = &header[..dot_pos];
-            if !seen_prefixes.contains(&prefix.to_string()) {
-                seen_prefixes.push(prefix.to_string());
-            }
-        }
-    }
-    
-    // Первый уникальный префикс относится к левой таблице, остальные - к правой
-    for (i, prefix) in seen_prefixes.iter().enumerate() {
-        if i == 0 {
-            prefix_to_table.insert(prefix.clone(), "left");
-        } else {
-            prefix_to_table.insert(prefix.clone(), "right");
-        }
+/// set_option(name, value) - настройка интерпретатора (реализация в Vm::call_set_option)
+pub fn native_set_option(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// memory_usage(value?) - оценка памяти значений программы или одного значения в байтах
+/// (реализация в Vm::call_memory_usage)
+pub fn native_memory_usage(_args: &[Value]) -> Value {
+    Value::Null
+}
+
+/// Неотрицательное целое из аргумента progress()
+fn progress_count(value: Option<&Value>) -> Option<u64> {
+    match value {
+        Some(Value::Number(n)) if *n >= 0.0 => Some(*n as u64),
+        Some(Value::Integer(i)) if *i >= 0 => Some(*i as u64),
+        _ => None,
     }
-    
-    // Также добавляем стандартные префиксы
-    prefix_to_table.insert("left".to_string(), "left");
-    prefix_to_table.insert("right".to_string(), "right");
-    
-    // Проходим по всем заголовкам и переименовываем колонки с префиксами
-    for header in &table.headers {
-        let new_header = if let Some(dot_pos) = header.find('.') {
-            let prefix = &header[..dot_pos];
-            let base_name = &header[dot_pos + 1..];
-            
-            // Определяем, к какой таблице относится колонка
-            if let Some(table_side) = prefix_to_table.get(prefix) {
-                if *table_side == "left" {
-                    // Колонка левой таблицы - применяем left_suffix
-                    let new_name = format!("{}{}", base_name, left_suffix);
-                    column_mapping.insert(header.clone(), new_name.clone());
-                    new_name
-                } else {
-                    // Колонка правой таблицы - применяем right_suffix
-                    let new_name = format!("{}{}", base_name, right_suffix);
-                    column_mapping.insert(header.clone(), new_name.clone());
-                    new_name
-                }
-            } else {
-                // Неизвестный префикс - оставляем без изменений
-                column_mapping.insert(header.clone(), header.clone());
-                header.clone()
-            }
-        } else {
-            // Колонка без префикса - оставляем без изменений
-            column_mapping.insert(header.clone(), header.clone());
-            header.clone()
-        };
-        new_headers.push(new_header);
+}
+
+/// progress(label, current, total) - обновляемая полоса прогресса в терминале,
+/// при перехваченном выводе - строка не чаще раза в 2 секунды
+pub fn native_progress(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    match (args.first(), progress_count(args.get(1)), progress_count(args.get(2))) {
+        (Some(Value::String(label)), Some(current), Some(total)) => crate::progress::report(label, current, total),
+        _ => set_native_error_with_type(
+            "progress() expects a label and non-negative current and total counts".to_string(),
+            ErrorType::TypeError,
+        ),
     }
+    Value::Null
+}
 
-    // Создаем новую структуру колонок с переименованными ключами
-    let mut new_columns = std::collections::HashMap::new();
-    for (old_header, new_header) in &column_mapping {
-        if let Some(column_data) = table.columns.get(old_header) {
-            new_columns.insert(new_header.clone(), column_data.clone());
-        }
+/// progress_done(label) - завершает полосу прогресса label
+pub fn native_progress_done(args: &[Value]) -> Value {
+    use crate::common::error::ErrorType;
+    use crate::websocket::set_native_error_with_type;
+
+    match args.first() {
+        Some(Value::String(label)) => crate::progress::finish(label),
+        _ => set_native_error_with_type("progress_done() expects a label".to_string(), ErrorType::TypeError),
     }
+    Value::Null
+}
 
-    // Создаем новую таблицу
-    let mut new_table = Table::new();
-    new_table.headers = new_headers;
-    new_table.columns = new_columns;
-    new_table.rows = table.rows.clone();
-    new_table.name = table.name.clone();
+/// filter(fn, array) - элементы массива, для которых fn(элемент) истинно (реализация в Vm::call_filter)
+pub fn native_filter(_args: &[Value]) -> Value {
+    Value::Null
+}
 
-    Value::Table(Rc::new(RefCell::new(new_table)))
+/// set_parallel_threshold(n) - длина массива, с которой map()/filter() работают параллельно
+/// (реализация в Vm::call_set_parallel_threshold)
+pub fn native_set_parallel_threshold(_args: &[Value]) -> Value {
+    Value::Null
 }
 
+/// cached_eval(key, fn) - результат fn(), сохраненный до изменения прочитанных в нем файлов
+/// (реализация в Vm::call_cached_eval)
+pub fn native_cached_eval(_args: &[Value]) -> Value {
+    Value::Null
+}
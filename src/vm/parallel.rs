@@ -0,0 +1,108 @@
+// Параллельный map()/filter() для длинных массивов через rayon
+//
+// Value хранит строки и массивы в Rc, поэтому значения нельзя передавать между потоками,
+// а пользовательские функции выполняются самой VM. Параллельно выполняются только чистые
+// встроенные функции из PARALLEL_SAFE_NATIVE_INDICES над скалярными значениями: аргументы
+// копируются в Scalar, каждый поток строит из них свои Value, вызывает функцию и возвращает
+// результат снова как Scalar. Во всех остальных случаях вызывающий код работает последовательно
+
+use crate::common::error::ErrorType;
+use crate::common::value::Value;
+use crate::vm::vm::NativeFn;
+use rayon::prelude::*;
+
+/// Порог по умолчанию: массивы короче обрабатываются последовательно
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 10_000;
+
+/// Встроенные функции без побочных эффектов, которые можно вызывать из нескольких потоков
+pub const PARALLEL_SAFE_NATIVE_INDICES: [usize; 15] = [
+    1,  // len
+    3,  // int
+    4,  // float
+    5,  // bool
+    6,  // str
+    8,  // typeof
+    21, // abs
+    22, // sqrt
+    26, // round
+    27, // upper
+    28, // lower
+    29, // trim
+    77, // to_number
+    78, // to_string
+    79, // to_bool
+];
+
+/// Значение, которое можно передать в другой поток
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Integer(i64),
+    String(String),
+}
+
+impl Scalar {
+    /// None для массивов, таблиц, объектов и других значений с разделяемым состоянием
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(Scalar::Null),
+            Value::Bool(b) => Some(Scalar::Bool(*b)),
+            Value::Number(n) => Some(Scalar::Number(*n)),
+            Value::Integer(i) => Some(Scalar::Integer(*i)),
+            Value::String(s) => Some(Scalar::String(s.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn into_value(self) -> Value {
+        match self {
+            Scalar::Null => Value::Null,
+            Scalar::Bool(b) => Value::Bool(b),
+            Scalar::Number(n) => Value::Number(n),
+            Scalar::Integer(i) => Value::Integer(i),
+            Scalar::String(s) => Value::String(s.into()),
+        }
+    }
+}
+
+/// Результат вызова в потоке: значение или ошибка встроенной функции.
+/// Внутренний None - результат не скаляр, его нельзя вернуть из потока
+type CallResult = Result<Option<Scalar>, (String, ErrorType)>;
+
+/// native(columns[0][i], columns[1][i], ...) для каждого i, параллельно.
+/// None, если среди аргументов или результатов есть не скалярные значения - тогда
+/// вызывающий код повторяет вычисление последовательно. Из нескольких ошибок возвращается
+/// ошибка самого раннего элемента, как при последовательном выполнении
+pub fn parallel_map(native: NativeFn, columns: &[Vec<Value>]) -> Option<Result<Vec<Value>, (String, ErrorType)>> {
+    let length = columns.first().map_or(0, Vec::len);
+    let mut rows: Vec<Vec<Scalar>> = Vec::with_capacity(length);
+    for index in 0..length {
+        let row: Option<Vec<Scalar>> = columns.iter().map(|column| Scalar::from_value(&column[index])).collect();
+        rows.push(row?);
+    }
+
+    let results: Vec<CallResult> = rows
+        .into_par_iter()
+        .map(|row| {
+            let args: Vec<Value> = row.into_iter().map(Scalar::into_value).collect();
+            let result = native(&args);
+            // Ошибка встроенной функции хранится в thread_local текущего потока
+            match crate::websocket::take_native_error() {
+                Some(error) => Err(error),
+                None => Ok(Scalar::from_value(&result)),
+            }
+        })
+        .collect();
+
+    let mut values = Vec::with_capacity(results.len());
+    for result in results {
+        match result {
+            Ok(Some(scalar)) => values.push(scalar.into_value()),
+            Ok(None) => return None,
+            Err(error) => return Some(Err(error)),
+        }
+    }
+    Some(Ok(values))
+}
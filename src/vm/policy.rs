@@ -0,0 +1,100 @@
+// Политика выполнения: ограничения, которые встраивающий код может наложить на программу
+
+use crate::common::error::LangError;
+use crate::common::path::split_smb_path;
+use std::path::Path;
+use std::time::Duration;
+
+/// Сообщение ошибки, которой VM прерывает выполнение по истечении ExecutionPolicy::timeout
+pub const EXECUTION_TIMED_OUT: &str = "execution timed out";
+/// Сообщение ошибки, которой VM прерывает выполнение после Vm::request_cancel()
+pub const EXECUTION_CANCELLED: &str = "execution cancelled";
+/// Сообщение ошибки Interrupted после Vm::request_interrupt() (Ctrl-C)
+pub const EXECUTION_INTERRUPTED: &str = "execution interrupted by user";
+
+/// Настройки песочницы для текущего потока
+#[derive(Debug, Clone)]
+pub struct ExecutionPolicy {
+    /// Разрешено ли динамическое выполнение кода через eval()/exec()
+    pub allow_dynamic_eval: bool,
+    /// Лимит памяти значений программы в байтах (приблизительная оценка); None - без лимита
+    pub max_memory_bytes: Option<usize>,
+    /// Время выполнения одного запуска программы; по истечении VM прерывает выполнение
+    /// ошибкой "execution timed out", которую нельзя перехватить try/catch. None - без ограничения
+    pub timeout: Option<Duration>,
+    /// Разрешено ли чтение файлов (read_file, read_lines, list_files, glob и т.д.)
+    pub allow_file_read: bool,
+    /// Разрешена ли запись файлов (write_file, append_file, write_excel)
+    pub allow_file_write: bool,
+    /// Разрешен ли доступ к SMB шарам (пути lib://, функции smb_* и SMB запросы WebSocket сервера)
+    pub allow_smb: bool,
+    /// Лимит суммарного размера файлов папки сессии --use-ve в байтах; None - без лимита
+    pub max_session_disk_bytes: Option<u64>,
+}
+
+impl Default for ExecutionPolicy {
+    fn default() -> Self {
+        Self {
+            allow_dynamic_eval: true,
+            max_memory_bytes: None,
+            timeout: None,
+            allow_file_read: true,
+            allow_file_write: true,
+            allow_smb: true,
+            max_session_disk_bytes: None,
+        }
+    }
+}
+
+impl ExecutionPolicy {
+    /// Текст запрета доступа к файлу path для чтения (write = false) или записи; None - доступ разрешен.
+    /// Пути lib:// и smb:// дополнительно требуют allow_smb
+    pub fn file_access_denial(&self, path: &Path, write: bool) -> Option<&'static str> {
+        if split_smb_path(&path.to_string_lossy()).is_some() && !self.allow_smb {
+            Some("SMB access is disabled by the execution policy")
+        } else if write && !self.allow_file_write {
+            Some("writing files is disabled by the execution policy")
+        } else if !write && !self.allow_file_read {
+            Some("reading files is disabled by the execution policy")
+        } else {
+            None
+        }
+    }
+}
+
+thread_local! {
+    static EXECUTION_POLICY: std::cell::RefCell<ExecutionPolicy> = std::cell::RefCell::new(ExecutionPolicy::default());
+}
+
+/// Установить политику выполнения для текущего потока
+pub fn set_execution_policy(policy: ExecutionPolicy) {
+    EXECUTION_POLICY.with(|p| *p.borrow_mut() = policy);
+}
+
+/// Получить политику выполнения для текущего потока
+pub fn get_execution_policy() -> ExecutionPolicy {
+    EXECUTION_POLICY.with(|p| p.borrow().clone())
+}
+
+/// Сбросить политику выполнения к значениям по умолчанию
+pub fn reset_execution_policy() {
+    set_execution_policy(ExecutionPolicy::default());
+}
+
+/// Ошибка прерывания по ExecutionPolicy::timeout (в том числе внутри eval()/exec())
+pub fn is_timeout_error(error: &LangError) -> bool {
+    matches!(error, LangError::RuntimeError { message, .. } if message.contains(EXECUTION_TIMED_OUT))
+}
+
+/// Ошибка прерывания по Vm::request_cancel()
+pub fn is_cancelled_error(error: &LangError) -> bool {
+    matches!(error, LangError::RuntimeError { message, .. } if message.contains(EXECUTION_CANCELLED))
+}
+
+/// Ошибка Interrupted после Vm::request_interrupt(), не перехваченная программой
+pub fn is_interrupted_error(error: &LangError) -> bool {
+    matches!(
+        error,
+        LangError::RuntimeError { error_type: Some(crate::common::error::ErrorType::Interrupted), .. }
+    )
+}
@@ -0,0 +1,180 @@
+// Профилирование выполнения по стекам вызовов для flamegraph
+//
+// Включается переменной окружения DATACODE_PROFILE=путь (CLI записывает туда результат)
+// или Session::profile(true); без нее VM не тратит время на учет. Время между сменами
+// глубины стека фреймов начисляется текущему пути вызовов "<main>;load;parse" как
+// собственное время функции на вершине; вызов встроенной функции учитывается отдельным
+// листом пути. Встроенные функции, которые выполняет сама VM (map, sort_by, sleep, ...),
+// входят во время вызвавшей их функции
+//
+// Параллельно ведется профиль по строкам исходного кода (datacode --profile): время между
+// сменами строки начисляется выполнявшейся строке, а число выполнений строки растет, когда
+// фрейм переходит на нее с другой строки (каждая итерация цикла, каждый вызов функции)
+
+use crate::vm::frame::CallFrame;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Переменная окружения: путь файла профиля в свернутом формате стеков
+pub const PROFILE_ENV: &str = "DATACODE_PROFILE";
+
+/// Путь из DATACODE_PROFILE (пустое значение равносильно отсутствию)
+pub fn profile_path_from_env() -> Option<PathBuf> {
+    std::env::var(PROFILE_ENV).ok().filter(|path| !path.is_empty()).map(PathBuf::from)
+}
+
+/// Число выполнений и общее время строки исходного кода
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineStats {
+    pub hits: u64,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// Имена функций текущего стека вызовов, от <main> к вершине
+    stack: Vec<String>,
+    /// stack, соединенный через ';' - ключ в self_times
+    path: String,
+    /// Собственное время каждого пути вызовов
+    self_times: HashMap<String, Duration>,
+    last: Instant,
+    /// Статистика строк по номеру строки
+    lines: HashMap<usize, LineStats>,
+    /// Строка, которой начисляется время, и момент перехода на нее
+    current_line: Option<usize>,
+    line_started: Instant,
+    /// Текущая строка каждого фрейма: возврат из вызова не считается новым выполнением строки
+    frame_lines: Vec<usize>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            path: String::new(),
+            self_times: HashMap::new(),
+            last: Instant::now(),
+            lines: HashMap::new(),
+            current_line: None,
+            line_started: Instant::now(),
+            frame_lines: Vec::new(),
+        }
+    }
+
+    /// Глубина стека, известная профилировщику; VM вызывает sync, когда она расходится с фреймами
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Начисляет время с прошлой отметки текущему пути и берет новый стек из фреймов
+    pub fn sync(&mut self, frames: &[CallFrame]) {
+        self.charge();
+        self.stack = frames.iter().map(|frame| frame_name(&frame.function.name)).collect();
+        self.path = self.stack.join(";");
+        if !self.path.is_empty() {
+            self.self_times.entry(self.path.clone()).or_default();
+        }
+    }
+
+    /// Начисляет время с прошлой отметки текущему пути
+    pub fn charge(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last);
+        self.last = now;
+        if !self.path.is_empty() {
+            *self.self_times.entry(self.path.clone()).or_default() += elapsed;
+        }
+    }
+
+    /// Учитывает вызов встроенной функции name из текущего пути, длившийся elapsed.
+    /// Перед вызовом VM вызывает charge(), чтобы время до вызова осталось у вызывающей функции
+    pub fn record_native(&mut self, name: &str, elapsed: Duration) {
+        let path = if self.path.is_empty() { frame_name(name) } else { format!("{};{}", self.path, frame_name(name)) };
+        *self.self_times.entry(path).or_default() += elapsed;
+        self.last = Instant::now();
+    }
+
+    /// Инструкция строки line во фрейме глубины depth (line 0 - строка неизвестна)
+    pub fn line(&mut self, depth: usize, line: usize) {
+        if line == 0 || (self.current_line == Some(line) && self.frame_lines.len() == depth) {
+            return;
+        }
+        self.charge_line();
+        self.current_line = Some(line);
+        self.frame_lines.resize(depth, 0);
+        if let Some(frame_line) = self.frame_lines.last_mut() {
+            if *frame_line != line {
+                *frame_line = line;
+                self.lines.entry(line).or_default().hits += 1;
+            }
+        }
+    }
+
+    /// Начисляет время с перехода на текущую строку
+    fn charge_line(&mut self) {
+        let now = Instant::now();
+        if let Some(line) = self.current_line {
+            self.lines.entry(line).or_default().time += now.saturating_duration_since(self.line_started);
+        }
+        self.line_started = now;
+    }
+
+    /// Завершение выполнения: остаток времени начисляется текущему пути и строке
+    pub fn finish(&mut self) {
+        self.charge();
+        self.stack.clear();
+        self.path.clear();
+        self.charge_line();
+        self.current_line = None;
+        self.frame_lines.clear();
+    }
+
+    /// Статистика строк, от самой долгой; при равном времени - по номеру строки
+    pub fn line_stats(&self) -> Vec<(usize, LineStats)> {
+        let mut lines: Vec<(usize, LineStats)> = self.lines.iter().map(|(line, stats)| (*line, *stats)).collect();
+        lines.sort_by(|(a_line, a), (b_line, b)| b.time.cmp(&a.time).then(a_line.cmp(b_line)));
+        lines
+    }
+
+    /// Отчет datacode --profile: top самых долгих строк с числом выполнений, временем,
+    /// долей от общего времени и текстом строки из source
+    pub fn line_report(&self, source: &str, top: usize) -> String {
+        let lines = self.line_stats();
+        let total: Duration = lines.iter().map(|(_, stats)| stats.time).sum();
+        let source_lines: Vec<&str> = source.lines().collect();
+        let mut report = format!("Профиль по строкам: {} из {} строк, всего {:.3} мс\n", top.min(lines.len()), lines.len(), millis(total));
+        report.push_str(&format!("{:>6} {:>10} {:>12} {:>7}  {}\n", "строка", "выполнений", "время, мс", "%", "код"));
+        for (line, stats) in lines.iter().take(top) {
+            let share = if total.is_zero() { 0.0 } else { stats.time.as_secs_f64() / total.as_secs_f64() * 100.0 };
+            let code = source_lines.get(line - 1).map_or("", |code| code.trim());
+            report.push_str(&format!("{:>6} {:>10} {:>12.3} {:>6.1}%  {}\n", line, stats.hits, millis(stats.time), share, code));
+        }
+        report
+    }
+
+    /// Профиль в свернутом формате стеков (flamegraph.pl, inferno, speedscope): строка
+    /// "<main>;outer;inner 1500" на каждый путь вызовов, число - собственное время в микросекундах.
+    /// Строки отсортированы по пути
+    pub fn export_folded(&self) -> String {
+        let mut paths: Vec<(&String, &Duration)> = self.self_times.iter().collect();
+        paths.sort();
+        paths.iter().map(|(path, time)| format!("{} {}\n", path, time.as_micros())).collect()
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+/// Имя кадра без символов формата: ';' разделяет кадры, пробел - путь и число
+fn frame_name(name: &str) -> String {
+    name.replace([';', ' '], "_")
+}
@@ -1,14 +1,90 @@
 // Виртуальная машина
 
 use crate::bytecode::{Chunk, OpCode};
-use crate::common::{error::{LangError, StackTraceEntry, ErrorType}, value::Value};
+use crate::common::{error::{LangError, StackTraceEntry, ErrorType}, value::{Closure, Value}};
+use crate::common::operators::{self, BinaryOp, OperatorError};
 use crate::vm::frame::CallFrame;
 use crate::vm::natives;
+use crate::vm::parallel;
+use crate::vm::profiler::{self, Profiler};
+use crate::vm::memory::{self, MemoryTracker};
+use crate::watch;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 pub type NativeFn = fn(&[Value]) -> Value;
 
+// Индексы нативных функций, которые выполняются самой VM
+const EVAL_NATIVE_INDEX: usize = 70;
+const EXEC_NATIVE_INDEX: usize = 71;
+const TABLE_RENAME_MAP_NATIVE_INDEX: usize = 76;
+const ASSERT_THROWS_NATIVE_INDEX: usize = 87;
+const REDUCE_NATIVE_INDEX: usize = 94;
+const MAP_NATIVE_INDEX: usize = 100;
+const SORT_BY_NATIVE_INDEX: usize = 108;
+const TABLE_FILTER_EXPR_NATIVE_INDEX: usize = 112;
+const SET_OPTION_NATIVE_INDEX: usize = 113;
+const MEMORY_USAGE_NATIVE_INDEX: usize = 120;
+const PUSH_NATIVE_INDEX: usize = 33;
+const RELATE_NATIVE_INDEX: usize = 68;
+const PRIMARY_KEY_NATIVE_INDEX: usize = 69;
+const DECLARE_EXPORT_NATIVE_INDEX: usize = 126;
+const REGISTER_FOREIGN_KEY_NATIVE_INDEX: usize = 127;
+const SLEEP_NATIVE_INDEX: usize = 128;
+const RETRY_NATIVE_INDEX: usize = 129;
+const TABLE_ADD_COLUMN_NATIVE_INDEX: usize = 131;
+const TABLE_DROP_ROWS_NATIVE_INDEX: usize = 133;
+const TABLE_WHERE_NATIVE_INDEX: usize = 50;
+const TABLE_APPLY_NATIVE_INDEX: usize = 146;
+const TABLE_APPLY_COLUMN_NATIVE_INDEX: usize = 147;
+const CACHE_STATS_NATIVE_INDEX: usize = 148;
+const CACHE_CLEAR_NATIVE_INDEX: usize = 149;
+const SET_MEMOIZATION_NATIVE_INDEX: usize = 150;
+const FILTER_NATIVE_INDEX: usize = 153;
+const SET_PARALLEL_THRESHOLD_NATIVE_INDEX: usize = 154;
+const CACHED_EVAL_NATIVE_INDEX: usize = 156;
+const VM_NATIVE_INDICES: [usize; 22] = [
+    EVAL_NATIVE_INDEX,
+    EXEC_NATIVE_INDEX,
+    TABLE_RENAME_MAP_NATIVE_INDEX,
+    ASSERT_THROWS_NATIVE_INDEX,
+    REDUCE_NATIVE_INDEX,
+    MAP_NATIVE_INDEX,
+    SORT_BY_NATIVE_INDEX,
+    TABLE_FILTER_EXPR_NATIVE_INDEX,
+    SET_OPTION_NATIVE_INDEX,
+    MEMORY_USAGE_NATIVE_INDEX,
+    SLEEP_NATIVE_INDEX,
+    RETRY_NATIVE_INDEX,
+    TABLE_ADD_COLUMN_NATIVE_INDEX,
+    TABLE_DROP_ROWS_NATIVE_INDEX,
+    TABLE_APPLY_NATIVE_INDEX,
+    TABLE_APPLY_COLUMN_NATIVE_INDEX,
+    CACHE_STATS_NATIVE_INDEX,
+    CACHE_CLEAR_NATIVE_INDEX,
+    SET_MEMOIZATION_NATIVE_INDEX,
+    FILTER_NATIVE_INDEX,
+    SET_PARALLEL_THRESHOLD_NATIVE_INDEX,
+    CACHED_EVAL_NATIVE_INDEX,
+];
+
+/// Максимальная глубина вложенности eval()/exec()
+const MAX_DYNAMIC_EVAL_DEPTH: usize = 32;
+/// Каждый уровень eval()/exec() рекурсивно вызывает execute(): если до конца стека потока
+/// осталось меньше DYNAMIC_EVAL_RED_ZONE байт, уровень выполняется на новом сегменте стека
+const DYNAMIC_EVAL_RED_ZONE: usize = 1024 * 1024;
+const DYNAMIC_EVAL_STACK_SIZE: usize = 8 * 1024 * 1024;
+/// Сколько векторов слотов завершившихся вызовов хранить для повторного использования
+const MAX_FREE_SLOT_VECTORS: usize = 64;
+/// Циклы for по массивам короче этого не сообщают о прогрессе при auto_progress
+const AUTO_PROGRESS_MIN_ITEMS: usize = 1000;
+/// Как часто (в инструкциях) проверять флаг отмены и ExecutionPolicy::timeout
+const INTERRUPT_CHECK_INTERVAL: u32 = 1024;
+/// Шаг, которым спит sleep(), проверяя между шагами отмену и deadline
+const SLEEP_SLICE: std::time::Duration = std::time::Duration::from_millis(20);
+
 /// Структура для хранения явной связи между колонками таблиц
 #[derive(Debug, Clone)]
 pub struct ExplicitRelation {
@@ -25,6 +101,19 @@ pub struct ExplicitPrimaryKey {
     pub column_name: String,
 }
 
+/// Объявление экспорта таблицы в SQLite, созданное через declare_export()
+#[derive(Debug, Clone)]
+pub struct ExportDeclaration {
+    /// Имя переменной DataCode с этой таблицей (None - таблица не сохранена в глобальной переменной)
+    pub variable_name: Option<String>,
+    /// Имя таблицы в SQLite
+    pub table_name: String,
+    /// Таблица или ленивая таблица
+    pub table: Value,
+    /// Колонка первичного ключа
+    pub primary_key: Option<String>,
+}
+
 // Структура для обработчика исключений в VM
 struct ExceptionHandler {
     catch_ips: Vec<usize>,           // IP начала каждого catch блока
@@ -34,11 +123,13 @@ struct ExceptionHandler {
     stack_height: usize,             // Высота стека при входе в try
     had_error: bool,                 // Флаг, указывающий, была ли ошибка в try блоке
     frame_index: usize,              // Индекс фрейма, к которому относится этот обработчик
+    caught: Option<LangError>,       // Ошибка, обрабатываемая catch блоком (для throw без значения)
 }
 
 pub struct Vm {
     stack: Vec<Value>,
     frames: Vec<CallFrame>,
+    free_slots: Vec<Vec<Value>>, // Пустые векторы слотов завершившихся вызовов для повторного использования
     globals: Vec<Value>,
     functions: Vec<crate::bytecode::Function>,
     natives: Vec<NativeFn>,
@@ -48,6 +139,20 @@ pub struct Vm {
     explicit_global_names: std::collections::HashMap<usize, String>, // Маппинг индексов переменных, явно объявленных с ключевым словом 'global'
     explicit_relations: Vec<ExplicitRelation>, // Явные связи, созданные через relate()
     explicit_primary_keys: Vec<ExplicitPrimaryKey>, // Явные первичные ключи, созданные через primary_key()
+    export_declarations: Vec<ExportDeclaration>, // Таблицы для экспорта, объявленные через declare_export()
+    global_indices: std::collections::HashMap<String, usize>, // Таблица глобальных имен (имя → индекс) для eval()/exec()
+    base_frame_depth: usize, // Индекс нижнего фрейма текущего цикла выполнения (> 0 внутри eval()/exec())
+    dynamic_depth: usize, // Текущая глубина вложенности eval()/exec()
+    undefined_vars_null: bool, // Неизвестная переменная читается как null (set_option("undefined_vars", "null"))
+    auto_progress: bool, // Прогресс длинных циклов for без изменений кода (set_option("auto_progress", true))
+    cache_limits: crate::bytecode::function::CacheLimits, // Ограничения кэшей @cache (set_option("cache_max_entries", ...))
+    parallel_threshold: usize, // Длина массива, с которой map()/filter() работают параллельно (set_parallel_threshold)
+    profiler: Option<Profiler>, // Профиль по стекам вызовов (DATACODE_PROFILE или enable_profiling)
+    memory: MemoryTracker, // Оценка памяти значений для ExecutionPolicy::max_memory_bytes
+    deadline: Option<std::time::Instant>, // Момент истечения ExecutionPolicy::timeout для текущего run()
+    steps_until_interrupt_check: u32, // Инструкций до следующей проверки отмены и deadline
+    cancel: Arc<AtomicBool>, // Флаг кооперативной отмены, выставляется из другого потока (request_cancel)
+    interrupt: Arc<AtomicBool>, // Флаг прерывания пользователем (Ctrl-C): перехватываемая ошибка Interrupted
 }
 
 impl Vm {
@@ -55,6 +160,7 @@ impl Vm {
         let mut vm = Self {
             stack: Vec::new(),
             frames: Vec::new(),
+            free_slots: Vec::new(),
             globals: Vec::new(),
             functions: Vec::new(),
             natives: Vec::new(),
@@ -64,6 +170,20 @@ impl Vm {
             explicit_global_names: std::collections::HashMap::new(),
             explicit_relations: Vec::new(),
             explicit_primary_keys: Vec::new(),
+            export_declarations: Vec::new(),
+            global_indices: std::collections::HashMap::new(),
+            base_frame_depth: 0,
+            dynamic_depth: 0,
+            undefined_vars_null: false,
+            auto_progress: false,
+            cache_limits: crate::bytecode::function::CacheLimits::default(),
+            parallel_threshold: parallel::DEFAULT_PARALLEL_THRESHOLD,
+            profiler: profiler::profile_path_from_env().map(|_| Profiler::new()),
+            memory: MemoryTracker::new(crate::vm::policy::get_execution_policy().max_memory_bytes),
+            deadline: None,
+            steps_until_interrupt_check: INTERRUPT_CHECK_INTERVAL,
+            cancel: Arc::new(AtomicBool::new(false)),
+            interrupt: Arc::new(AtomicBool::new(false)),
         };
         vm.register_natives();
         vm
@@ -147,6 +267,115 @@ impl Vm {
         self.natives.push(natives::native_table_suffixes); // 67
         self.natives.push(natives::native_relate);      // 68
         self.natives.push(natives::native_primary_key); // 69
+        // Динамическое выполнение кода (обрабатываются в VM)
+        self.natives.push(natives::native_eval);        // 70
+        self.natives.push(natives::native_exec);        // 71
+        // Очистка данных
+        self.natives.push(natives::native_table_interpolate); // 72
+        self.natives.push(natives::native_last_read_report); // 73
+        // Переименование и удаление колонок таблиц
+        self.natives.push(natives::native_table_rename); // 74
+        self.natives.push(natives::native_table_drop); // 75
+        self.natives.push(natives::native_table_rename_map); // 76
+        // Преобразование типов
+        self.natives.push(natives::native_to_number); // 77
+        self.natives.push(natives::native_str); // 78
+        self.natives.push(natives::native_to_bool); // 79
+        self.natives.push(natives::native_to_array); // 80
+        // Excel
+        self.natives.push(natives::native_list_sheets); // 81
+        self.natives.push(natives::native_write_excel); // 82
+        // Функции массивов: zip/unzip
+        self.natives.push(natives::native_zip); // 83
+        self.natives.push(natives::native_unzip); // 84
+        // Проверки для тестов (assert_throws обрабатывается в VM)
+        self.natives.push(natives::native_assert); // 85
+        self.natives.push(natives::native_assert_eq); // 86
+        self.natives.push(natives::native_assert_throws); // 87
+        // Функции массивов: flatten/chunk
+        self.natives.push(natives::native_flatten); // 88
+        self.natives.push(natives::native_chunk); // 89
+        // Функции массивов: shuffle
+        self.natives.push(natives::native_shuffle); // 90
+        // Ленивые таблицы
+        self.natives.push(natives::native_table_lazy); // 91
+        self.natives.push(natives::native_collect); // 92
+        self.natives.push(natives::native_last_collect_report); // 93
+        // Функции массивов: reduce (обрабатывается в VM)
+        self.natives.push(natives::native_reduce); // 94
+        // Функции для работы с путями
+        self.natives.push(natives::native_path_join); // 95
+        self.natives.push(natives::native_path_filename); // 96
+        self.natives.push(natives::native_path_ext); // 97
+        self.natives.push(natives::native_path_absolute); // 98
+        self.natives.push(natives::native_is_absolute); // 99
+        // Функции массивов: map (обрабатывается в VM)
+        self.natives.push(natives::native_map); // 100
+        // Функции для работы с объектами
+        self.natives.push(natives::native_merge); // 101
+        self.natives.push(natives::native_deep_merge); // 102
+        // Постраничный доступ к таблицам
+        self.natives.push(natives::native_table_slice); // 103
+        // Функции для работы с объектами: выбор полей
+        self.natives.push(natives::native_pick); // 104
+        self.natives.push(natives::native_omit); // 105
+        // Функции для работы с объектами: безопасный доступ к ключам
+        self.natives.push(natives::native_has_key); // 106
+        self.natives.push(natives::native_get); // 107
+        // Функции массивов: sort_by (обрабатывается в VM), index_of
+        self.natives.push(natives::native_sort_by); // 108
+        self.natives.push(natives::native_index_of); // 109
+        // Денежные суммы (Value::Currency)
+        self.natives.push(natives::native_parse_currency); // 110
+        self.natives.push(natives::native_format_currency); // 111
+        // Фильтрация таблицы выражением и настройки интерпретатора (обрабатываются в VM)
+        self.natives.push(natives::native_table_filter_expr); // 112
+        self.natives.push(natives::native_set_option); // 113
+        self.natives.push(natives::native_glob); // 114
+        self.natives.push(natives::native_read_lines); // 115
+        self.natives.push(natives::native_progress); // 116
+        self.natives.push(natives::native_progress_done); // 117
+        self.natives.push(natives::native_write_file); // 118
+        self.natives.push(natives::native_append_file); // 119
+        self.natives.push(natives::native_memory_usage); // 120
+        self.natives.push(natives::native_table_string_agg); // 121
+        self.natives.push(natives::native_table_collect); // 122
+        self.natives.push(natives::native_table_merge_columns); // 123
+        self.natives.push(natives::native_deep_equal); // 124
+        self.natives.push(natives::native_same_reference); // 125
+        self.natives.push(natives::native_declare_export); // 126
+        self.natives.push(natives::native_register_foreign_key); // 127
+        self.natives.push(natives::native_sleep); // 128
+        self.natives.push(natives::native_retry); // 129
+        self.natives.push(natives::native_table_reorder); // 130
+        self.natives.push(natives::native_table_add_column); // 131
+        self.natives.push(natives::native_table_drop_column); // 132
+        self.natives.push(natives::native_table_drop_rows); // 133
+        self.natives.push(natives::native_table_drop_duplicates); // 134
+        self.natives.push(natives::native_table_cast); // 135
+        self.natives.push(natives::native_table_autocast); // 136
+        self.natives.push(natives::native_table_fillna); // 137
+        self.natives.push(natives::native_table_dropna); // 138
+        self.natives.push(natives::native_describe); // 139
+        self.natives.push(natives::native_value_counts); // 140
+        self.natives.push(natives::native_table_window); // 141
+        self.natives.push(natives::native_table_where_regex); // 142
+        self.natives.push(natives::native_smb_connect); // 143
+        self.natives.push(natives::native_smb_list); // 144
+        self.natives.push(natives::native_smb_read); // 145
+        self.natives.push(natives::native_table_apply); // 146
+        self.natives.push(natives::native_table_apply_column); // 147
+        self.natives.push(natives::native_cache_stats); // 148
+        self.natives.push(natives::native_cache_clear); // 149
+        self.natives.push(natives::native_set_memoization); // 150
+        self.natives.push(natives::native_analyze_csv); // 151
+        self.natives.push(natives::native_table_group_by); // 152
+        self.natives.push(natives::native_filter); // 153
+        self.natives.push(natives::native_set_parallel_threshold); // 154
+        self.natives.push(natives::native_divmod); // 155
+        self.natives.push(natives::native_cached_eval); // 156
+        self.natives.push(natives::native_table_fill_nulls); // 157
+        self.natives.push(natives::native_table_fill_direction); // 158
     }
 
     pub fn set_functions(&mut self, functions: Vec<crate::bytecode::Function>) {
@@ -161,7 +390,7 @@ impl Vm {
     pub fn register_native_globals(&mut self) {
         // Регистрируем нативные функции в глобальных переменных
         // Порядок должен соответствовать register_natives()
-        self.globals.resize(70, Value::Null);
+        self.globals.resize(159, Value::Null);
         
         self.globals[0] = Value::NativeFunction(0);  // print
         self.globals[1] = Value::NativeFunction(1);  // len
@@ -238,6 +467,115 @@ impl Vm {
         self.globals[67] = Value::NativeFunction(67);  // table_suffixes
         self.globals[68] = Value::NativeFunction(68);  // relate
         self.globals[69] = Value::NativeFunction(69);  // primary_key
+        // Динамическое выполнение кода
+        self.globals[70] = Value::NativeFunction(70);  // eval
+        self.globals[71] = Value::NativeFunction(71);  // exec
+        // Очистка данных
+        self.globals[72] = Value::NativeFunction(72);  // table_interpolate
+        self.globals[73] = Value::NativeFunction(73);  // last_read_report
+        // Переименование и удаление колонок таблиц
+        self.globals[74] = Value::NativeFunction(74);  // table_rename
+        self.globals[75] = Value::NativeFunction(75);  // table_drop
+        self.globals[76] = Value::NativeFunction(76);  // table_rename_map
+        // Преобразование типов
+        self.globals[77] = Value::NativeFunction(77);  // to_number
+        self.globals[78] = Value::NativeFunction(78);  // to_string
+        self.globals[79] = Value::NativeFunction(79);  // to_bool
+        self.globals[80] = Value::NativeFunction(80);  // to_array
+        // Excel
+        self.globals[81] = Value::NativeFunction(81);  // list_sheets
+        self.globals[82] = Value::NativeFunction(82);  // write_excel
+        // Функции массивов: zip/unzip
+        self.globals[83] = Value::NativeFunction(83);  // zip
+        self.globals[84] = Value::NativeFunction(84);  // unzip
+        // Проверки для тестов (assert_throws обрабатывается в VM)
+        self.globals[85] = Value::NativeFunction(85);  // assert
+        self.globals[86] = Value::NativeFunction(86);  // assert_eq
+        self.globals[87] = Value::NativeFunction(87);  // assert_throws
+        // Функции массивов: flatten/chunk
+        self.globals[88] = Value::NativeFunction(88);  // flatten
+        self.globals[89] = Value::NativeFunction(89);  // chunk
+        // Функции массивов: shuffle
+        self.globals[90] = Value::NativeFunction(90);  // shuffle
+        // Ленивые таблицы
+        self.globals[91] = Value::NativeFunction(91);  // table_lazy
+        self.globals[92] = Value::NativeFunction(92);  // collect
+        self.globals[93] = Value::NativeFunction(93);  // last_collect_report
+        // Функции массивов: reduce (обрабатывается в VM)
+        self.globals[94] = Value::NativeFunction(94);  // reduce
+        // Функции для работы с путями
+        self.globals[95] = Value::NativeFunction(95);  // path_join
+        self.globals[96] = Value::NativeFunction(96);  // path_filename
+        self.globals[97] = Value::NativeFunction(97);  // path_ext
+        self.globals[98] = Value::NativeFunction(98);  // path_absolute
+        self.globals[99] = Value::NativeFunction(99);  // is_absolute
+        // Функции массивов: map (обрабатывается в VM)
+        self.globals[100] = Value::NativeFunction(100);  // map
+        // Функции для работы с объектами
+        self.globals[101] = Value::NativeFunction(101);  // merge
+        self.globals[102] = Value::NativeFunction(102);  // deep_merge
+        // Постраничный доступ к таблицам
+        self.globals[103] = Value::NativeFunction(103);  // table_slice
+        // Функции для работы с объектами: выбор полей
+        self.globals[104] = Value::NativeFunction(104);  // pick
+        self.globals[105] = Value::NativeFunction(105);  // omit
+        // Функции для работы с объектами: безопасный доступ к ключам
+        self.globals[106] = Value::NativeFunction(106);  // has_key
+        self.globals[107] = Value::NativeFunction(107);  // get
+        // Функции массивов: sort_by (обрабатывается в VM), index_of
+        self.globals[108] = Value::NativeFunction(108);  // sort_by
+        self.globals[109] = Value::NativeFunction(109);  // index_of
+        // Денежные суммы (Value::Currency)
+        self.globals[110] = Value::NativeFunction(110);  // parse_currency
+        self.globals[111] = Value::NativeFunction(111);  // format_currency
+        // Фильтрация таблицы выражением и настройки интерпретатора (обрабатываются в VM)
+        self.globals[112] = Value::NativeFunction(112);  // table_filter_expr
+        self.globals[113] = Value::NativeFunction(113);  // set_option
+        self.globals[114] = Value::NativeFunction(114);  // glob
+        self.globals[115] = Value::NativeFunction(115);  // read_lines
+        self.globals[116] = Value::NativeFunction(116);  // progress
+        self.globals[117] = Value::NativeFunction(117);  // progress_done
+        self.globals[118] = Value::NativeFunction(118);  // write_file
+        self.globals[119] = Value::NativeFunction(119);  // append_file
+        self.globals[120] = Value::NativeFunction(120);  // memory_usage
+        self.globals[121] = Value::NativeFunction(121);  // table_string_agg
+        self.globals[122] = Value::NativeFunction(122);  // table_collect
+        self.globals[123] = Value::NativeFunction(123);  // table_merge_columns
+        self.globals[124] = Value::NativeFunction(124);  // deep_equal
+        self.globals[125] = Value::NativeFunction(125);  // same_reference
+        self.globals[126] = Value::NativeFunction(126);  // declare_export
+        self.globals[127] = Value::NativeFunction(127);  // register_foreign_key
+        self.globals[128] = Value::NativeFunction(128);  // sleep
+        self.globals[129] = Value::NativeFunction(129);  // retry
+        self.globals[130] = Value::NativeFunction(130);  // table_reorder
+        self.globals[131] = Value::NativeFunction(131);  // table_add_column
+        self.globals[132] = Value::NativeFunction(132);  // table_drop_column
+        self.globals[133] = Value::NativeFunction(133);  // table_drop_rows
+        self.globals[134] = Value::NativeFunction(134);  // table_drop_duplicates
+        self.globals[135] = Value::NativeFunction(135);  // table_cast
+        self.globals[136] = Value::NativeFunction(136);  // table_autocast
+        self.globals[137] = Value::NativeFunction(137);  // table_fillna
+        self.globals[138] = Value::NativeFunction(138);  // table_dropna
+        self.globals[139] = Value::NativeFunction(139);  // describe
+        self.globals[140] = Value::NativeFunction(140);  // value_counts
+        self.globals[141] = Value::NativeFunction(141);  // table_window
+        self.globals[142] = Value::NativeFunction(142);  // table_where_regex
+        self.globals[143] = Value::NativeFunction(143);  // smb_connect
+        self.globals[144] = Value::NativeFunction(144);  // smb_list
+        self.globals[145] = Value::NativeFunction(145);  // smb_read
+        self.globals[146] = Value::NativeFunction(146);  // table_apply
+        self.globals[147] = Value::NativeFunction(147);  // table_apply_column
+        self.globals[148] = Value::NativeFunction(148);  // cache_stats
+        self.globals[149] = Value::NativeFunction(149);  // cache_clear
+        self.globals[150] = Value::NativeFunction(150);  // set_memoization
+        self.globals[151] = Value::NativeFunction(151);  // analyze_csv
+        self.globals[152] = Value::NativeFunction(152);  // table_group_by
+        self.globals[153] = Value::NativeFunction(153);  // filter
+        self.globals[154] = Value::NativeFunction(154);  // set_parallel_threshold
+        self.globals[155] = Value::NativeFunction(155);  // divmod
+        self.globals[156] = Value::NativeFunction(156);  // cached_eval
+        self.globals[157] = Value::NativeFunction(157);  // table_fill_nulls
+        self.globals[158] = Value::NativeFunction(158);  // table_fill_direction
     }
 
     fn build_stack_trace(&self) -> Vec<StackTraceEntry> {
@@ -265,6 +603,31 @@ impl Vm {
         LangError::runtime_error_with_type_and_trace(message, line, error_type, self.build_stack_trace())
     }
 
+    /// Ошибка оператора из common::operators с типом, если он есть
+    fn operator_error(&self, error: OperatorError, line: usize) -> LangError {
+        match error.error_type {
+            Some(error_type) => self.runtime_error_with_type(error.message, line, error_type),
+            None => self.runtime_error(error.message, line),
+        }
+    }
+
+    /// Бинарный оператор над двумя значениями на вершине стека. Обработанная catch ошибка
+    /// возвращает Ok: выполнение продолжается с перехода в catch
+    fn binary_instruction(&mut self, op: BinaryOp, line: usize) -> Result<(), LangError> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        match operators::binary(op, &a, &b) {
+            Ok(result) => {
+                self.push(result);
+                Ok(())
+            }
+            Err(error) => {
+                let error = self.operator_error(error, line);
+                self.handle_exception(error)
+            }
+        }
+    }
+
     /// Обрабатывает исключение - проверяет стек обработчиков и переходит к соответствующему catch блоку
     fn handle_exception(&mut self, error: LangError) -> Result<(), LangError> {
         // Получаем текущий IP для проверки, не находимся ли мы уже внутри catch блока
@@ -279,7 +642,8 @@ impl Vm {
         for handler in self.exception_handlers.iter_mut().rev() {
             let handler_frame_index = handler.frame_index;
             
-            if handler_frame_index >= self.frames.len() {
+            // Обработчики ниже текущего цикла выполнения (вне eval()/exec()) обрабатывает вызывающий код
+            if handler_frame_index >= self.frames.len() || handler_frame_index < self.base_frame_depth {
                 continue;
             }
             
@@ -334,7 +698,8 @@ impl Vm {
                             if let Some(et) = ErrorType::from_name(error_type_name) {
                                 error.is_instance_of(&et)
                             } else {
-                                false
+                                // Собственный вид ошибки из throw {"kind": ...}
+                                error.kind_name() == *error_type_name
                             }
                         } else {
                             false
@@ -351,6 +716,7 @@ impl Vm {
                     // Нашли подходящий catch блок
                     // Устанавливаем флаг ошибки
                     handler.had_error = true;
+                    handler.caught = Some(error.clone());
                     
                     // Очищаем стек до нужной высоты
                     while self.stack.len() > handler.stack_height {
@@ -364,13 +730,16 @@ impl Vm {
                     
                     // Сохраняем ошибку в переменную (если указана)
                     if let Some(Some(slot)) = error_var_slot {
-                        // Преобразуем ошибку в строку для сохранения в переменную
-                        let error_string = format!("{}", error);
+                        // Брошенное значение (объект и т.п.) сохраняется как есть, остальные ошибки - строкой
+                        let error_value = match error.thrown_value() {
+                            Some(value) => value.clone(),
+                            None => Value::String(format!("{}", error).into()),
+                        };
                         let frame = self.frames.last_mut().unwrap();
                         if *slot >= frame.slots.len() {
                             frame.slots.resize(*slot + 1, Value::Null);
                         }
-                        frame.slots[*slot] = Value::String(error_string);
+                        frame.slots[*slot] = error_value;
                     }
                     
                     // Переходим к catch блоку в правильном фрейме
@@ -386,234 +755,1785 @@ impl Vm {
         Err(error)
     }
 
-    pub fn run(&mut self, chunk: &Chunk) -> Result<Value, LangError> {
-        // Заполняем имена глобальных переменных из chunk
-        self.global_names = chunk.global_names.clone();
-        self.explicit_global_names = chunk.explicit_global_names.clone();
+    /// Выполняет eval()/exec(): компилирует код из строки и запускает его в контексте вызывающего фрейма.
+    /// Последний аргумент добавляется компилятором - карта видимых локальных переменных (имя → слот).
+    fn call_dynamic(&mut self, is_exec: bool, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let kind = if is_exec { "exec" } else { "eval" };
         
-        // Создаем начальный frame
-        let function = crate::bytecode::Function::new("<main>".to_string(), 0);
-        let mut function = function;
-        function.chunk = chunk.clone();
-        let frame = CallFrame::new(function, 0);
+        if !crate::vm::policy::get_execution_policy().allow_dynamic_eval {
+            return Err(self.runtime_error_with_type(
+                format!("{}() is disabled by the execution policy", kind),
+                line,
+                ErrorType::PermissionError,
+            ));
+        }
+        
+        let source = match args.first() {
+            Some(Value::String(source)) if args.len() == 2 => source.clone(),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    format!("{}() expects a single string argument with code", kind),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let mut locals = std::collections::HashMap::new();
+        if let Some(Value::Object(map)) = args.get(1) {
+            for (name, slot) in map {
+                if let Value::Number(slot) = slot {
+                    locals.insert(name.clone(), *slot as usize);
+                }
+            }
+        }
+        
+        if self.dynamic_depth >= MAX_DYNAMIC_EVAL_DEPTH {
+            return Err(self.runtime_error_with_type(
+                format!("Maximum {}() nesting depth ({}) exceeded", kind, MAX_DYNAMIC_EVAL_DEPTH),
+                line,
+                ErrorType::StateError,
+            ));
+        }
+        
+        let chunk = self
+            .compile_dynamic(&source, &locals, is_exec)
+            .map_err(|e| Self::wrap_dynamic_error(kind, &source, e, line))?;
+        
+        // Код выполняется в отдельном фрейме, который временно забирает слоты вызывающего:
+        // так присваивания попадают прямо в его локальные переменные
+        let caller_index = self.frames.len() - 1;
+        let saved_stack_height = self.stack.len();
+        let saved_handlers = self.exception_handlers.len();
+        let saved_base = self.base_frame_depth;
+        
+        let mut function = crate::bytecode::Function::new(format!("<{}>", kind), 0);
+        function.chunk = Rc::new(chunk);
+        let mut frame = CallFrame::new(function, saved_stack_height);
+        frame.slots = std::mem::take(&mut self.frames[caller_index].slots);
         self.frames.push(frame);
+        
+        self.base_frame_depth = caller_index + 1;
+        self.dynamic_depth += 1;
+        let result = stacker::maybe_grow(DYNAMIC_EVAL_RED_ZONE, DYNAMIC_EVAL_STACK_SIZE, || self.execute());
+        self.dynamic_depth -= 1;
+        self.base_frame_depth = saved_base;
+        
+        // Возвращаем слоты вызывающему и восстанавливаем состояние VM
+        let slots = std::mem::take(&mut self.frames[caller_index + 1].slots);
+        self.frames.truncate(caller_index + 1);
+        self.frames[caller_index].slots = slots;
+        self.exception_handlers.truncate(saved_handlers);
+        self.stack.truncate(saved_stack_height);
+        
+        result.map_err(|e| Self::wrap_dynamic_error(kind, &source, e, line))
+    }
 
-        loop {
-            // Проверяем, что есть frame
-            if self.frames.is_empty() {
-                break;
+    /// Создает фрейм вызова пользовательской функции: захваченные переменные и параметры в слотах.
+    /// Функция переходит во фрейм без повторного копирования; cache_key - ключ, под которым
+    /// результат сохранится в кэш функции при возврате
+    fn new_call_frame(
+        &mut self,
+        function: crate::bytecode::Function,
+        closure: Option<Rc<Closure>>,
+        args: &[Value],
+        cache_key: Option<crate::bytecode::function::CacheKey>,
+    ) -> CallFrame {
+        // Создаем новый CallFrame
+        let stack_start = self.stack.len();
+        let slots = self.free_slots.pop().unwrap_or_default();
+        let mut new_frame = CallFrame::with_slots(function, stack_start, slots);
+        new_frame.cache_key = cache_key;
+        let function = &new_frame.function;
+        
+        // Копируем таблицу типов ошибок из chunk функции в VM
+        if !function.chunk.error_type_table.is_empty() {
+            self.error_type_table = function.chunk.error_type_table.clone();
+        }
+        
+        // Захваченные переменные берутся только из замыкания, а не из фрейма вызывающего
+        if let Some(closure) = &closure {
+            for (captured_var, value) in function.captured_vars.iter().zip(&closure.captured) {
+                if captured_var.local_slot_index >= new_frame.slots.len() {
+                    new_frame.slots.resize(captured_var.local_slot_index + 1, Value::Null);
+                }
+                new_frame.slots[captured_var.local_slot_index] = value.clone();
+            }
+        }
+        
+        // Инициализируем параметры функции в slots (после захваченных переменных)
+        let param_start_index = function.captured_vars.len();
+        for (i, arg) in args.iter().enumerate() {
+            let slot_index = param_start_index + i;
+            if slot_index >= new_frame.slots.len() {
+                new_frame.slots.resize(slot_index + 1, Value::Null);
             }
+            new_frame.slots[slot_index] = arg.clone();
+        }
+        
+        new_frame.closure = closure;
+        new_frame
+    }
 
-            let (instruction, line) = {
-                let frame = self.frames.last().unwrap();
-                if frame.ip >= frame.function.chunk.code.len() {
-                    break;
-                }
-                let ip = frame.ip;
-                let instruction = frame.function.chunk.code[ip].clone();
-                let line = frame.function.chunk.get_line(ip);
-                (instruction, line)
-            };
+    /// Замыкание для вызова вложенной функции по индексу (Value::Function): так вызываются
+    /// рекурсия и соседние вложенные функции. Рекурсивный вызов повторяет значения своего
+    /// замыкания, остальные читают слоты фрейма объемлющей функции, найденного по цепочке
+    /// замыканий. Фреймы, не связанные с функцией лексически, не читаются
+    fn closure_for_call(&self, function_index: usize) -> Result<Option<Rc<Closure>>, String> {
+        let function = &self.functions[function_index];
+        if function.parent.is_none() && function.captured_vars.is_empty() {
+            return Ok(None);
+        }
+        if let Some(closure) = self.frames.last().and_then(|frame| frame.closure.as_ref()) {
+            if closure.function == function_index {
+                return Ok(Some(Rc::clone(closure)));
+            }
+        }
+        match self.enclosing_frame(function.parent) {
+            Some(frame) => {
+                let captured = function
+                    .captured_vars
+                    .iter()
+                    .map(|var| frame.slots.get(var.parent_slot_index).cloned().unwrap_or(Value::Null))
+                    .collect();
+                Ok(Some(Rc::new(Closure { function: function_index, captured, frame: frame.id })))
+            }
+            None if function.captured_vars.is_empty() => Ok(None),
+            None => Err(format!(
+                "Function '{}' uses variables of the function that declared it and cannot be called outside of it",
+                function.name
+            )),
+        }
+    }
 
-            let frame = self.frames.last_mut().unwrap();
-            frame.ip += 1;
+    /// Активный фрейм объемлющей функции для вызова из текущего фрейма: текущий фрейм или
+    /// фрейм, в котором создано его замыкание (и так далее вверх). None для parent - верхний уровень
+    fn enclosing_frame(&self, parent: Option<usize>) -> Option<&CallFrame> {
+        let Some(parent) = parent else {
+            return self.frames.first();
+        };
+        let parent_chunk = &self.functions.get(parent)?.chunk;
+        let mut index = self.frames.len().checked_sub(1)?;
+        loop {
+            let frame = &self.frames[index];
+            if Rc::ptr_eq(&frame.function.chunk, parent_chunk) {
+                return Some(frame);
+            }
+            let id = frame.closure.as_ref()?.frame;
+            index = self.frames[..index].iter().rposition(|frame| frame.id == id)?;
+        }
+    }
 
-            match instruction {
-                OpCode::Constant(index) => {
-                    let value = frame.function.chunk.constants[index].clone();
-                    self.push(value);
+    /// Замыкание вызываемого значения Function или Closure
+    fn callee_closure(&self, callee: &Value) -> Result<Option<Rc<Closure>>, String> {
+        match callee {
+            Value::Closure(closure) => Ok(Some(Rc::clone(closure))),
+            Value::Function(index) => self.closure_for_call(*index),
+            _ => Ok(None),
+        }
+    }
+
+    /// Синхронно вызывает функцию из кода VM (колбэки встроенных функций) и возвращает ее результат
+    fn call_value(&mut self, callee: &Value, args: &[Value], line: usize) -> Result<Value, LangError> {
+        if let Some(function_index) = callee.function_index().filter(|&index| index < self.functions.len()) {
+            let closure = self.callee_closure(callee).map_err(|message| self.runtime_error(message, line))?;
+            let function = self.functions[function_index].clone();
+            if args.len() != function.arity {
+                return Err(self.runtime_error_with_type(
+                    format!("Expected {} arguments but got {}", function.arity, args.len()),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+            
+            // Функция выполняется во вложенном цикле, который завершается при возврате из ее фрейма
+            let caller_frames = self.frames.len();
+            let saved_stack_height = self.stack.len();
+            let saved_handlers = self.exception_handlers.len();
+            let saved_base = self.base_frame_depth;
+            
+            // Результат вызова из встроенной функции в кэш не сохраняется
+            let new_frame = self.new_call_frame(function, closure, args, None);
+            self.frames.push(new_frame);
+            self.base_frame_depth = caller_frames;
+            let result = self.execute();
+            self.base_frame_depth = saved_base;
+            
+            self.frames.truncate(caller_frames);
+            self.exception_handlers.truncate(saved_handlers);
+            self.stack.truncate(saved_stack_height);
+            return result;
+        }
+        match callee {
+            Value::NativeFunction(native_index)
+                if *native_index < self.natives.len() && !VM_NATIVE_INDICES.contains(native_index) =>
+            {
+                let result = (self.natives[*native_index])(args);
+                match crate::websocket::take_native_error() {
+                    Some((message, error_type)) => Err(self.runtime_error_with_type(message, line, error_type)),
+                    None => Ok(result),
                 }
-                OpCode::LoadLocal(index) => {
-                    // Для сложных типов (Array, Table) возвращаем ссылку (shallow copy Rc)
-                    // Для простых типов клонируем значение
-                    let value = &frame.slots[index];
-                    let loaded_value = match value {
-                        Value::Array(arr_rc) => Value::Array(Rc::clone(arr_rc)),
-                        Value::Table(table_rc) => Value::Table(Rc::clone(table_rc)),
-                        _ => value.clone(), // Простые типы клонируем
-                    };
-                    self.push(loaded_value);
+            }
+            _ => Err(self.runtime_error_with_type(
+                "Can only call functions".to_string(),
+                line,
+                ErrorType::TypeError,
+            )),
+        }
+    }
+
+    /// table_rename_map(table, fn): переименовывает каждую колонку в результат fn(имя)
+    fn call_table_rename_map(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let table = match args {
+            [Value::Table(table), _] => Rc::clone(table),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "table_rename_map() expects a table and a function".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let headers = table.borrow().headers.clone();
+        let mut pairs = Vec::with_capacity(headers.len());
+        for header in headers {
+            match self.call_value(&args[1], &[Value::String(header.as_str().into())], line)? {
+                Value::String(new_name) => pairs.push((header, new_name.to_string())),
+                other => {
+                    return Err(self.runtime_error_with_type(
+                        format!(
+                            "table_rename_map() function must return a string, got {} for column '{}'",
+                            natives::native_typeof(&[other]).to_string(),
+                            header
+                        ),
+                        line,
+                        ErrorType::TypeError,
+                    ));
                 }
-                OpCode::StoreLocal(index) => {
-                    let value = self.pop()?;
-                    // Clone уже создает глубокую копию для массивов и таблиц
-                    let frame = self.frames.last_mut().unwrap();
-                    if index >= frame.slots.len() {
-                        frame.slots.resize(index + 1, Value::Null);
-                    }
-                    frame.slots[index] = value;
+            }
+        }
+        
+        let renamed = match table.try_borrow_mut() {
+            Ok(mut table_mut) => table_mut.rename_columns(&pairs),
+            Err(_) => {
+                return Err(self.runtime_error_with_type(
+                    format!("table_rename_map(): {}", natives::TABLE_IN_USE),
+                    line,
+                    ErrorType::StateError,
+                ));
+            }
+        };
+        if let Err(message) = renamed {
+            return Err(self.runtime_error_with_type(
+                format!("table_rename_map(): {}", message),
+                line,
+                ErrorType::ValueError,
+            ));
+        }
+        Ok(Value::Table(table))
+    }
+
+    /// table_add_column(table, name, fn): новая таблица с колонкой name, значение которой - fn(строка).
+    /// Строка передается объектом {колонка: значение}; при ошибке в fn исходная таблица не меняется
+    fn call_table_add_column(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (table, name, callee) = match args {
+            [Value::Table(table), Value::String(name), callee, ..] => (Rc::clone(table), name.to_string(), callee.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "table_add_column() expects a table, a column name and a function".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let (headers, rows) = {
+            let table = table.borrow();
+            (table.headers.clone(), table.rows.clone())
+        };
+        if headers.contains(&name) {
+            return Err(self.runtime_error_with_type(
+                format!("table_add_column(): column '{}' already exists", name),
+                line,
+                ErrorType::ValueError,
+            ));
+        }
+        
+        // Значения считаются в новые строки: исходная таблица не меняется
+        let mut new_rows = Vec::with_capacity(rows.len());
+        for mut row in rows {
+            let value = self.call_value(&callee, &[Self::row_object(&headers, &row)], line)?;
+            row.resize(headers.len(), Value::Null);
+            row.push(value);
+            new_rows.push(row);
+        }
+        let mut new_headers = headers;
+        new_headers.push(name);
+        let new_table = crate::common::table::Table::from_data(new_rows, Some(new_headers));
+        Ok(Value::Table(Rc::new(RefCell::new(new_table))))
+    }
+
+    /// table_drop_rows(table, predicate): новая таблица без строк, для которых predicate(строка) истинно.
+    /// Строка передается объектом {колонка: значение}, как в table_add_column
+    fn call_table_drop_rows(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (table, predicate) = match args {
+            [Value::Table(table), predicate, ..] => (Rc::clone(table), predicate.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "table_drop_rows() expects a table and a predicate function".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let (headers, rows, name) = {
+            let table = table.borrow();
+            (table.headers.clone(), table.rows.clone(), table.name.clone())
+        };
+        let mut kept = Vec::with_capacity(rows.len());
+        for row in rows {
+            if !self.call_value(&predicate, &[Self::row_object(&headers, &row)], line)?.is_truthy() {
+                kept.push(row);
+            }
+        }
+        let mut result = crate::common::table::Table::from_data(kept, Some(headers));
+        result.name = name;
+        Ok(Value::Table(Rc::new(RefCell::new(result))))
+    }
+
+    /// table_apply(table, fn) и table_apply_column(table, column, fn): новая таблица, в которой значения
+    /// всех ячеек (или одной колонки) заменены на fn(значение). Функция с тремя параметрами получает
+    /// fn(значение, номер строки, имя колонки). Ошибка в fn сообщает строку и колонку ячейки
+    fn call_table_apply(&mut self, function: &str, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let is_function = |value: &Value| matches!(value, Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_));
+        let (table, column, callee) = match (function, args) {
+            ("table_apply", [Value::Table(table), callee, ..]) if is_function(callee) => {
+                (Rc::clone(table), None, callee.clone())
+            }
+            ("table_apply_column", [Value::Table(table), Value::String(column), callee, ..]) if is_function(callee) => {
+                (Rc::clone(table), Some(column.to_string()), callee.clone())
+            }
+            _ => {
+                let expected = if function == "table_apply" { "a table and a function" } else { "a table, a column name and a function" };
+                return Err(self.runtime_error_with_type(
+                    format!("{}() expects {}", function, expected),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let with_location = callee.function_index().and_then(|index| self.functions.get(index)).is_some_and(|f| f.arity == 3);
+        
+        let (headers, rows, name) = {
+            let table = table.borrow();
+            (table.headers.clone(), table.rows.clone(), table.name.clone())
+        };
+        let targets: Vec<usize> = match &column {
+            Some(column) => match headers.iter().position(|header| header == column) {
+                Some(index) => vec![index],
+                None => {
+                    return Err(self.runtime_error_with_type(
+                        format!("{}(): unknown column: {}", function, column),
+                        line,
+                        ErrorType::ColumnNotFoundError,
+                    ));
                 }
-                OpCode::LoadGlobal(index) => {
-                    if index >= self.globals.len() {
-                        let error = self.runtime_error(
-                            format!("Undefined variable"),
-                            line,
-                        );
-                        match self.handle_exception(error) {
-                            Ok(()) => {
-                                // Исключение обработано, кладем Null на стек
-                                self.push(Value::Null);
-                            }
-                            Err(e) => return Err(e), // Исключение не обработано
-                        }
-                    } else {
-                        // Для сложных типов (Array, Table) возвращаем ссылку (shallow copy Rc)
-                        // Для простых типов клонируем значение
-                        let value = &self.globals[index];
-                        let loaded_value = match value {
-                            Value::Array(arr_rc) => Value::Array(Rc::clone(arr_rc)),
-                            Value::Table(table_rc) => Value::Table(Rc::clone(table_rc)),
-                            _ => value.clone(), // Простые типы клонируем
-                        };
-                        self.push(loaded_value);
+            },
+            None => (0..headers.len()).collect(),
+        };
+        
+        let mut new_rows = Vec::with_capacity(rows.len());
+        for (row_index, mut row) in rows.into_iter().enumerate() {
+            row.resize(headers.len(), Value::Null);
+            for &column_index in &targets {
+                let value = std::mem::replace(&mut row[column_index], Value::Null);
+                let call_args = if with_location {
+                    vec![value, Value::Number(row_index as f64), Value::String(headers[column_index].as_str().into())]
+                } else {
+                    vec![value]
+                };
+                row[column_index] = self.call_value(&callee, &call_args, line).map_err(|error| match error {
+                    LangError::RuntimeError { message, line, error_type, stack_trace, thrown } => LangError::RuntimeError {
+                        message: format!("{}(): at row {}, column '{}': {}", function, row_index, headers[column_index], message),
+                        line,
+                        error_type,
+                        stack_trace,
+                        thrown,
+                    },
+                    other => other,
+                })?;
+            }
+            new_rows.push(row);
+        }
+        let mut result = crate::common::table::Table::from_data(new_rows, Some(headers));
+        result.name = name;
+        Ok(Value::Table(Rc::new(RefCell::new(result))))
+    }
+
+    /// Extend: [...a, ...b] дополняет массив элементами массива, {...a, ...b} - объект ключами объекта
+    fn extend_with_spread(target: Value, value: Value) -> Result<Value, String> {
+        match (target, value) {
+            (Value::Array(target), Value::Array(items)) => {
+                let mut elements = target.borrow().clone();
+                elements.extend(items.borrow().iter().cloned());
+                Ok(Value::Array(Rc::new(RefCell::new(elements))))
+            }
+            (Value::Object(mut target), Value::Object(items)) => {
+                // Последующие ключи заменяют значения предыдущих
+                target.extend(items);
+                Ok(Value::Object(target))
+            }
+            (Value::Array(_), value) => Err(format!("Cannot spread {} into an array, expected an array", Self::spread_type_name(&value))),
+            (_, value) => Err(format!("Cannot spread {} into an object, expected an object", Self::spread_type_name(&value))),
+        }
+    }
+
+    /// SpreadArg: массив добавляется к позиционным аргументам вызова, объект - к именованным
+    fn add_spread_argument(positional: Value, named: Value, value: Value) -> Result<(Value, Value), String> {
+        let (Value::Array(positional), Value::Object(mut named)) = (positional, named) else {
+            return Err("Invalid spread call arguments".to_string());
+        };
+        match value {
+            Value::Array(items) => positional.borrow_mut().extend(items.borrow().iter().cloned()),
+            Value::Object(items) => {
+                for (key, item) in items {
+                    if named.contains_key(&key) {
+                        return Err(format!("Got multiple values for keyword argument '{}'", key));
                     }
+                    named.insert(key, item);
                 }
-                OpCode::StoreGlobal(index) => {
-                    let mut value = self.pop()?;
-                    // Если значение - таблица, устанавливаем её имя из global_names
-                    if let Value::Table(table_rc) = &mut value {
-                        if let Some(var_name) = self.global_names.get(&index) {
-                            table_rc.borrow_mut().set_name(var_name.clone());
+            }
+            other => {
+                return Err(format!(
+                    "Cannot spread {} into function arguments, expected an array or an object",
+                    Self::spread_type_name(&other)
+                ));
+            }
+        }
+        Ok((Value::Array(positional), Value::Object(named)))
+    }
+
+    /// Элементы массива для распаковки `a, b = value`: число элементов должно совпадать с числом имен
+    fn unpack_values(value: Value, count: usize) -> Result<Vec<Value>, (String, ErrorType)> {
+        let Value::Array(elements) = value else {
+            return Err((
+                format!("Cannot unpack {} into {} variables, expected an array", Self::spread_type_name(&value), count),
+                ErrorType::TypeError,
+            ));
+        };
+        let elements = elements.borrow();
+        if elements.len() != count {
+            let noun = if elements.len() == 1 { "value" } else { "values" };
+            return Err((
+                format!("Cannot unpack {} {} into {} variables", elements.len(), noun, count),
+                ErrorType::ValueError,
+            ));
+        }
+        Ok(elements.clone())
+    }
+
+    fn spread_type_name(value: &Value) -> String {
+        natives::native_typeof(std::slice::from_ref(value)).to_string()
+    }
+
+    /// CallSpread: снимает со стека функцию, объект именованных и массив позиционных аргументов,
+    /// привязывает их к параметрам (как компилятор для обычного вызова) и кладет аргументы и
+    /// функцию обратно. Возвращает число аргументов для Call
+    fn bind_spread_call(&mut self, line: usize) -> Result<usize, LangError> {
+        let callee = self.pop()?;
+        let (Value::Object(named), Value::Array(positional)) = (self.pop()?, self.pop()?) else {
+            return Err(self.runtime_error("Invalid spread call arguments".to_string(), line));
+        };
+        let positional = positional.borrow().clone();
+        let args = match &callee {
+            Value::Function(_) | Value::Closure(_) if callee.function_index().is_some_and(|index| index < self.functions.len()) => {
+                let index = callee.function_index().unwrap_or_default();
+                let function = &self.functions[index];
+                let (name, defaults) = (function.name.clone(), function.default_values.clone());
+                let bound = Self::bind_named_arguments(&name, &function.param_names, positional, named);
+                let mut args = Vec::new();
+                for (i, arg) in bound.map_err(|message| self.runtime_error_with_type(message, line, ErrorType::TypeError))?.into_iter().enumerate() {
+                    match arg.or_else(|| defaults.get(i).cloned().flatten()) {
+                        Some(value) => args.push(value),
+                        None => {
+                            let message = format!("Function '{}' missing required argument '{}'", name, self.functions[index].param_names[i]);
+                            return Err(self.runtime_error_with_type(message, line, ErrorType::TypeError));
                         }
                     }
-                    // Clone уже создает глубокую копию для массивов и таблиц
-                    if index >= self.globals.len() {
-                        self.globals.resize(index + 1, Value::Null);
-                    }
-                    // Важно: присваиваем value после установки имени, чтобы имя сохранилось
-                    self.globals[index] = value;
-                }
-                OpCode::Add => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_add(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Sub => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_sub(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Mul => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_mul(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Div => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_div(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::IntDiv => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_int_div(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Mod => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_mod(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Pow => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_pow(&a, &b)?;
-                    self.push(result);
                 }
-                OpCode::Negate => {
-                    let value = self.pop()?;
-                    match value {
-                        Value::Number(n) => self.push(Value::Number(-n)),
-                        _ => {
-                            let error = self.runtime_error(
-                                "Operand must be a number".to_string(),
-                                line,
-                            );
-                            match self.handle_exception(error) {
-                                Ok(()) => continue, // Исключение обработано, продолжаем выполнение
-                                Err(e) => return Err(e), // Исключение не обработано
+                args
+            }
+            Value::NativeFunction(index) if !named.is_empty() => {
+                let name = self.global_indices.iter().find(|(_, &i)| i == *index).map(|(name, _)| name.clone()).unwrap_or_default();
+                let bound = match crate::compiler::Compiler::get_native_function_params(&name) {
+                    // Вариативная функция: именованные аргументы идут после позиционных в порядке параметров
+                    Some(params) if params.first().is_some_and(|p| p.starts_with('*')) => {
+                        let mut args = positional;
+                        let (_, rest) = params.split_first().unwrap();
+                        match named.keys().find(|key| !rest.contains(key)) {
+                            Some(key) => Err(format!("Function '{}' got an unexpected keyword argument '{}'", name, key)),
+                            None => {
+                                args.extend(rest.iter().filter_map(|param| named.get(param).cloned()));
+                                Ok(args)
                             }
                         }
                     }
+                    // Пропущенные параметры перед последним переданным заполняются null
+                    Some(params) => Self::bind_named_arguments(&name, &params, positional, named).map(|bound| {
+                        let provided = bound.iter().rposition(|arg| arg.is_some()).map_or(0, |last| last + 1);
+                        bound.into_iter().take(provided).map(|arg| arg.unwrap_or(Value::Null)).collect()
+                    }),
+                    None => Err(format!("Named arguments are not supported for built-in function '{}'", name)),
+                };
+                bound.map_err(|message| self.runtime_error_with_type(message, line, ErrorType::TypeError))?
+            }
+            _ => positional,
+        };
+        let arity = args.len();
+        for arg in args {
+            self.push(arg);
+        }
+        self.push(callee);
+        Ok(arity)
+    }
+
+    /// Раскладывает позиционные и именованные аргументы по параметрам; None - параметр не передан
+    fn bind_named_arguments(
+        function_name: &str,
+        param_names: &[String],
+        positional: Vec<Value>,
+        named: crate::common::value::ObjectMap,
+    ) -> Result<Vec<Option<Value>>, String> {
+        if positional.len() > param_names.len() {
+            return Err(format!(
+                "Function '{}' takes {} arguments but {} positional arguments were provided",
+                function_name,
+                param_names.len(),
+                positional.len()
+            ));
+        }
+        let mut bound: Vec<Option<Value>> = positional.into_iter().map(Some).collect();
+        bound.resize(param_names.len(), None);
+        for (key, value) in named {
+            match param_names.iter().position(|param| *param == key) {
+                Some(i) if bound[i].is_some() => {
+                    return Err(format!("Function '{}' got multiple values for argument '{}'", function_name, key));
                 }
-                OpCode::Not => {
-                    let value = self.pop()?;
-                    self.push(Value::Bool(!value.is_truthy()));
-                }
-                OpCode::Or => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    // Если a истинно, возвращаем a, иначе возвращаем b
-                    if a.is_truthy() {
-                        self.push(a);
-                    } else {
-                        self.push(b);
-                    }
-                }
-                OpCode::And => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    // Если a ложно, возвращаем a, иначе возвращаем b
-                    if !a.is_truthy() {
-                        self.push(a);
-                    } else {
-                        self.push(b);
-                    }
-                }
-                OpCode::Equal => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.push(Value::Bool(a == b));
-                }
-                OpCode::NotEqual => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.push(Value::Bool(a != b));
-                }
-                OpCode::Greater => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_greater(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::Less => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_less(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::GreaterEqual => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_greater_equal(&a, &b)?;
-                    self.push(result);
-                }
-                OpCode::LessEqual => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    let result = self.binary_less_equal(&a, &b)?;
-                    self.push(result);
+                Some(i) => bound[i] = Some(value),
+                None => {
+                    return Err(format!("Function '{}' got an unexpected keyword argument '{}'", function_name, key));
                 }
-                OpCode::In => {
-                    let array = self.pop()?; // Правый операнд - массив
-                    let value = self.pop()?; // Левый операнд - значение для поиска
-                    
-                    match array {
-                        Value::Array(arr) => {
-                            let arr_ref = arr.borrow();
-                            let found = arr_ref.iter().any(|item| item == &value);
-                            self.push(Value::Bool(found));
-                        }
-                        _ => {
-                            let error = self.runtime_error(
-                                "Right operand of 'in' operator must be an array".to_string(),
-                                line,
-                            );
+            }
+        }
+        Ok(bound)
+    }
+
+    /// Строка таблицы как объект {колонка: значение} для пользовательских функций
+    fn row_object(headers: &[String], row: &[Value]) -> Value {
+        let mut object = crate::common::value::ObjectMap::new();
+        for (header, value) in headers.iter().zip(row) {
+            object.insert(header.clone(), value.clone());
+        }
+        Value::Object(object)
+    }
+
+    /// Находит вызываемое значение по имени: пользовательская функция или глобальная переменная
+    fn lookup_callable(&self, name: &str) -> Option<Value> {
+        if let Some(index) = self.functions.iter().position(|f| f.name == name && !f.is_local) {
+            return Some(Value::Function(index));
+        }
+        self.global_indices
+            .get(name)
+            .and_then(|index| self.globals.get(*index))
+            .filter(|value| matches!(value, Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_)))
+            .cloned()
+    }
+
+    /// Вызывает пользовательскую функцию программы по имени (используется тестовым раннером)
+    pub fn call_function_by_name(&mut self, name: &str, args: &[Value]) -> Result<Value, LangError> {
+        let callee = self.lookup_callable(name).ok_or_else(|| {
+            self.runtime_error_with_type(format!("Undefined function: {}", name), 0, ErrorType::ValueError)
+        })?;
+        self.call_value(&callee, args, 0)
+    }
+
+    /// assert_throws(fn, args): вызывает функцию (или функцию с указанным именем) и ожидает ошибку.
+    /// Возвращает сообщение пойманной ошибки
+    fn call_assert_throws(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let callee = match args.first() {
+            Some(Value::String(name)) => match self.lookup_callable(name) {
+                Some(callee) => callee,
+                None => {
+                    return Err(self.runtime_error_with_type(
+                        format!("assert_throws(): undefined function '{}'", name),
+                        line,
+                        ErrorType::ValueError,
+                    ));
+                }
+            },
+            Some(callee @ (Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_))) => callee.clone(),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "assert_throws() expects a function or a function name".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let call_args = match args.get(1) {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items.borrow().clone(),
+            Some(_) => {
+                return Err(self.runtime_error_with_type(
+                    "assert_throws() expects args to be an array".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        match self.call_value(&callee, &call_args, line) {
+            Err(LangError::RuntimeError { message, .. }) => Ok(Value::String(message.as_str().into())),
+            Err(error) => Ok(Value::String(error.to_string().as_str().into())),
+            Ok(result) => Err(self.runtime_error_with_type(
+                format!(
+                    "Assertion failed: expected an error, but the call returned {}",
+                    natives::assert_repr(&result)
+                ),
+                line,
+                ErrorType::AssertionError,
+            )),
+        }
+    }
+
+    /// Пауза длиной duration. Прерывается отменой и ExecutionPolicy::timeout: сон не продлевает
+    /// выполнение за deadline
+    fn sleep_interruptible(&self, duration: std::time::Duration, line: usize) -> Result<(), LangError> {
+        let until = std::time::Instant::now() + duration;
+        loop {
+            if self.is_cancel_requested() {
+                return Err(self.runtime_error(crate::vm::policy::EXECUTION_CANCELLED.to_string(), line));
+            }
+            if let Some(error) = self.take_interrupt(line) {
+                return Err(error);
+            }
+            let now = std::time::Instant::now();
+            if self.deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(self.runtime_error(crate::vm::policy::EXECUTION_TIMED_OUT.to_string(), line));
+            }
+            if now >= until {
+                return Ok(());
+            }
+            let mut step = until.saturating_duration_since(now).min(SLEEP_SLICE);
+            if let Some(deadline) = self.deadline {
+                step = step.min(deadline.saturating_duration_since(now));
+            }
+            std::thread::sleep(step);
+        }
+    }
+
+    /// sleep(ms): пауза в миллисекундах
+    fn call_sleep(&self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let ms = match args.first() {
+            Some(Value::Number(n)) => *n,
+            Some(Value::Integer(i)) => *i as f64,
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "sleep() expects a number of milliseconds".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        if !ms.is_finite() || ms < 0.0 {
+            return Err(self.runtime_error_with_type(
+                format!("sleep(): ms must be a non-negative number, got {}", ms),
+                line,
+                ErrorType::ValueError,
+            ));
+        }
+        self.sleep_interruptible(std::time::Duration::from_secs_f64(ms / 1000.0), line)?;
+        Ok(Value::Null)
+    }
+
+    /// retry(fn, attempts = 3, backoff_ms = 500, factor = 2.0, retry_on = null): вызывает функцию
+    /// (или функцию с указанным именем) без аргументов, пока она не завершится без ошибки. Между
+    /// попытками ждет backoff_ms, каждый раз умножая паузу на factor. retry_on - имя вида ошибки или
+    /// массив имен (как в catch): остальные ошибки пробрасываются сразу. После последней попытки
+    /// пробрасывается ее ошибка с числом попыток в сообщении
+    fn call_retry(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let callee = match args.first() {
+            Some(Value::String(name)) => match self.lookup_callable(name) {
+                Some(callee) => callee,
+                None => {
+                    return Err(self.runtime_error_with_type(
+                        format!("retry(): undefined function '{}'", name),
+                        line,
+                        ErrorType::ValueError,
+                    ));
+                }
+            },
+            Some(callee @ (Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_))) => callee.clone(),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "retry() expects a function or a function name".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let number_arg = |index: usize, default: f64| match args.get(index) {
+            None | Some(Value::Null) => Some(default),
+            Some(Value::Number(n)) => Some(*n),
+            Some(Value::Integer(i)) => Some(*i as f64),
+            Some(_) => None,
+        };
+        let attempts = match number_arg(1, 3.0) {
+            Some(n) if n >= 1.0 && n.fract() == 0.0 && n.is_finite() => n as usize,
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "retry(): attempts must be a positive integer".to_string(),
+                    line,
+                    ErrorType::ValueError,
+                ));
+            }
+        };
+        let mut delay_ms = match number_arg(2, 500.0) {
+            Some(ms) if ms >= 0.0 && ms.is_finite() => ms,
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "retry(): backoff_ms must be a non-negative number".to_string(),
+                    line,
+                    ErrorType::ValueError,
+                ));
+            }
+        };
+        let factor = match number_arg(3, 2.0) {
+            Some(factor) if factor > 0.0 && factor.is_finite() => factor,
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "retry(): factor must be a positive number".to_string(),
+                    line,
+                    ErrorType::ValueError,
+                ));
+            }
+        };
+        let retry_on: Option<Vec<String>> = match args.get(4) {
+            None | Some(Value::Null) => None,
+            Some(Value::String(name)) => Some(vec![name.to_string()]),
+            Some(Value::Array(names)) if names.borrow().iter().all(|name| matches!(name, Value::String(_))) => {
+                Some(names.borrow().iter().filter_map(|name| match name {
+                    Value::String(name) => Some(name.to_string()),
+                    _ => None,
+                }).collect())
+            }
+            Some(_) => {
+                return Err(self.runtime_error_with_type(
+                    "retry(): retry_on must be an error type name or an array of names".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let mut attempt = 1;
+        loop {
+            let error = match self.call_value(&callee, &[], line) {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            // Отмена и таймаут не повторяются; виды ошибок сопоставляются так же, как в typed catch
+            let retryable = matches!(error, LangError::RuntimeError { .. })
+                && !crate::vm::policy::is_cancelled_error(&error)
+                && !crate::vm::policy::is_timeout_error(&error)
+                && !crate::vm::policy::is_interrupted_error(&error)
+                && retry_on.as_ref().is_none_or(|names| {
+                    names.iter().any(|name| match ErrorType::from_name(name) {
+                        Some(error_type) => error.is_instance_of(&error_type),
+                        None => error.kind_name() == *name,
+                    })
+                });
+            if !retryable {
+                return Err(error);
+            }
+            if attempt >= attempts {
+                return Err(match error {
+                    LangError::RuntimeError { message, line, stack_trace, error_type, thrown } => LangError::RuntimeError {
+                        message: format!("retry(): failed after {} attempts: {}", attempts, message),
+                        line,
+                        stack_trace,
+                        error_type,
+                        thrown,
+                    },
+                    other => other,
+                });
+            }
+            self.sleep_interruptible(std::time::Duration::from_secs_f64(delay_ms / 1000.0), line)?;
+            delay_ms *= factor;
+            attempt += 1;
+        }
+    }
+
+    /// reduce(array, fn) / reduce(array, fn, initial): свертка fn(accumulator, element).
+    /// Без initial аккумулятор начинается с первого элемента, пустой массив - ошибка
+    fn call_reduce(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (items, callee) = match args {
+            [Value::Array(items), callee, ..] => (items.borrow().clone(), callee.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "reduce() expects an array and a function".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let mut elements = items.into_iter();
+        let mut accumulator = match args.get(2) {
+            Some(initial) => initial.clone(),
+            None => match elements.next() {
+                Some(first) => first,
+                None => {
+                    return Err(self.runtime_error_with_type(
+                        "reduce() of empty array with no initial value".to_string(),
+                        line,
+                        ErrorType::TypeError,
+                    ));
+                }
+            },
+        };
+        for element in elements {
+            accumulator = self.call_value(&callee, &[accumulator, element], line)?;
+        }
+        Ok(accumulator)
+    }
+
+    /// map(fn, a) / map(fn, a, b, ...): fn вызывается с i-ми элементами всех массивов.
+    /// Число параметров пользовательской функции должно совпадать с числом массивов,
+    /// массивы должны быть одной длины
+    fn call_map(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (callee, arrays) = match args {
+            [callee, arrays @ ..] if !arrays.is_empty() => (callee.clone(), arrays),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "map() expects a function and at least one array".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        let mut columns: Vec<Vec<Value>> = Vec::with_capacity(arrays.len());
+        for (position, array) in arrays.iter().enumerate() {
+            match array {
+                Value::Array(items) => columns.push(items.borrow().clone()),
+                _ => {
+                    return Err(self.runtime_error_with_type(
+                        format!("map() argument {} must be an array", position + 2),
+                        line,
+                        ErrorType::TypeError,
+                    ));
+                }
+            }
+        }
+        if let Some(function_index) = callee.function_index() {
+            if let Some(function) = self.functions.get(function_index) {
+                if function.arity != columns.len() {
+                    return Err(self.runtime_error_with_type(
+                        format!(
+                            "map() function '{}' takes {} arguments but {} arrays were given",
+                            function.name, function.arity, columns.len()
+                        ),
+                        line,
+                        ErrorType::TypeError,
+                    ));
+                }
+            }
+        }
+        let length = columns[0].len();
+        if let Some(other) = columns.iter().find(|c| c.len() != length) {
+            return Err(self.runtime_error_with_type(
+                format!("map() arrays must have the same length, got {} and {}", length, other.len()),
+                line,
+                ErrorType::ValueError,
+            ));
+        }
+        
+        if let Some(result) = self.parallel_call(&callee, &columns, line)? {
+            return Ok(Value::Array(Rc::new(RefCell::new(result))));
+        }
+        let mut result = Vec::with_capacity(length);
+        for index in 0..length {
+            let call_args: Vec<Value> = columns.iter().map(|column| column[index].clone()).collect();
+            result.push(self.call_value(&callee, &call_args, line)?);
+        }
+        Ok(Value::Array(Rc::new(RefCell::new(result))))
+    }
+
+    /// filter(fn, array): новый массив из элементов, для которых fn(элемент) истинно
+    fn call_filter(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (callee, items) = match args {
+            [callee, Value::Array(items)] => (callee.clone(), items.borrow().clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "filter() expects a function and an array".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let columns = [items];
+        let keep = match self.parallel_call(&callee, &columns, line)? {
+            Some(keep) => keep,
+            None => {
+                let mut keep = Vec::with_capacity(columns[0].len());
+                for item in &columns[0] {
+                    keep.push(self.call_value(&callee, std::slice::from_ref(item), line)?);
+                }
+                keep
+            }
+        };
+        let [items] = columns;
+        let kept = items.into_iter().zip(keep).filter(|(_, keep)| keep.is_truthy()).map(|(item, _)| item).collect();
+        Ok(Value::Array(Rc::new(RefCell::new(kept))))
+    }
+
+    /// Вызовы callee для строк columns в нескольких потоках, если массивы не короче
+    /// parallel_threshold и callee - чистая встроенная функция (см. vm/parallel.rs).
+    /// Ok(None) - параллельное выполнение невозможно, нужно вызывать последовательно
+    fn parallel_call(&mut self, callee: &Value, columns: &[Vec<Value>], line: usize) -> Result<Option<Vec<Value>>, LangError> {
+        let native_index = match callee {
+            Value::NativeFunction(index) if parallel::PARALLEL_SAFE_NATIVE_INDICES.contains(index) => *index,
+            _ => return Ok(None),
+        };
+        if columns.first().map_or(0, Vec::len) < self.parallel_threshold.max(1) {
+            return Ok(None);
+        }
+        match parallel::parallel_map(self.natives[native_index], columns) {
+            Some(Ok(values)) => Ok(Some(values)),
+            Some(Err((message, error_type))) => Err(self.runtime_error_with_type(message, line, error_type)),
+            None => Ok(None),
+        }
+    }
+
+    /// set_parallel_threshold(n): map()/filter() с чистыми встроенными функциями обрабатывают
+    /// массивы длиной от n элементов в нескольких потоках. Возвращает прежнее значение
+    fn call_set_parallel_threshold(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let threshold = match args {
+            [Value::Number(n)] if *n >= 0.0 && n.fract() == 0.0 => *n as usize,
+            [Value::Integer(i)] if *i >= 0 => *i as usize,
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "set_parallel_threshold() expects a non-negative integer".to_string(),
+                    line,
+                    ErrorType::ValueError,
+                ));
+            }
+        };
+        let previous = std::mem::replace(&mut self.parallel_threshold, threshold);
+        Ok(Value::Integer(previous as i64))
+    }
+
+    /// cached_eval(key, fn): результат fn() из кэша процесса (src/watch.rs), пока не изменились
+    /// файлы, прочитанные при его вычислении; иначе вызывает fn() и сохраняет результат.
+    /// Ошибка fn() не кэшируется
+    fn call_cached_eval(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (key, callee) = match args {
+            [Value::String(key), callee @ (Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_))] => (key.to_string(), callee.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "cached_eval() expects a string key and a function without arguments".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        if let Some(value) = watch::cache_lookup(&key) {
+            return Ok(value);
+        }
+        watch::begin_tracking();
+        let result = self.call_value(&callee, &[], line);
+        let stamps = watch::end_tracking();
+        let value = result?;
+        if !watch::cache_store(&key, &value, stamps) {
+            return Err(self.runtime_error_with_type(
+                format!("cached_eval(): result of '{}' contains a function, lazy table or column reference and cannot be cached", key),
+                line,
+                ErrorType::ValueError,
+            ));
+        }
+        Ok(value)
+    }
+
+    /// sort_by(array, fn, descending = false): новый массив, отсортированный по fn(элемент).
+    /// Ключи должны быть все числами или все строками; сортировка устойчивая
+    fn call_sort_by(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (items, callee) = match args {
+            [Value::Array(items), callee, ..] => (items.borrow().clone(), callee.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    "sort_by() expects an array and a key function".to_string(),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let descending = args.get(2).is_some_and(|value| value.is_truthy());
+        
+        // Ключ вычисляется один раз для каждого элемента
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let key = self.call_value(&callee, std::slice::from_ref(&item), line)?;
+            keyed.push((key, item));
+        }
+        
+        let all_numbers = keyed.iter().all(|(key, _)| key.is_numeric());
+        let all_strings = keyed.iter().all(|(key, _)| matches!(key, Value::String(_)));
+        if !all_numbers && !all_strings {
+            let kinds: Vec<String> = keyed
+                .iter()
+                .map(|(key, _)| natives::native_typeof(std::slice::from_ref(key)).to_string())
+                .fold(Vec::new(), |mut kinds, kind| {
+                    if !kinds.contains(&kind) {
+                        kinds.push(kind);
+                    }
+                    kinds
+                });
+            return Err(self.runtime_error_with_type(
+                format!("sort_by() keys must be all numbers or all strings, got {}", kinds.join(", ")),
+                line,
+                ErrorType::TypeError,
+            ));
+        }
+        
+        keyed.sort_by(|(a, _), (b, _)| {
+            let ordering = crate::common::table::compare_values(a, b);
+            if descending { ordering.reverse() } else { ordering }
+        });
+        let sorted = keyed.into_iter().map(|(_, item)| item).collect();
+        Ok(Value::Array(Rc::new(RefCell::new(sorted))))
+    }
+
+    /// table_filter_expr(table, expr, missing = null): новая таблица из строк, для которых expr истинно.
+    /// Значения колонок строки доступны в выражении как переменные. missing = "null" - неизвестные
+    /// имена на время вызова читаются как null, "error" - ошибка, null - как задано set_option()
+    /// Та же функция обслуживает table_where(table, expr); function - имя для сообщений об ошибках
+    fn call_table_filter_expr(&mut self, function: &str, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let (table, source) = match args {
+            [Value::Table(table), Value::String(source), ..] => (Rc::clone(table), source.clone()),
+            _ => {
+                return Err(self.runtime_error_with_type(
+                    format!("{}() expects a table and an expression string", function),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        let undefined_vars_null = match args.get(2) {
+            None | Some(Value::Null) => self.undefined_vars_null,
+            Some(Value::String(mode)) => match parse_undefined_vars_mode(mode) {
+                Some(as_null) => as_null,
+                None => {
+                    return Err(self.runtime_error_with_type(
+                        format!("{}(): missing must be \"null\" or \"error\", got \"{}\"", function, mode),
+                        line,
+                        ErrorType::ValueError,
+                    ));
+                }
+            },
+            Some(_) => {
+                return Err(self.runtime_error_with_type(
+                    format!("{}(): missing must be a string", function),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        if self.dynamic_depth >= MAX_DYNAMIC_EVAL_DEPTH {
+            return Err(self.runtime_error_with_type(
+                format!("Maximum {}() nesting depth ({}) exceeded", function, MAX_DYNAMIC_EVAL_DEPTH),
+                line,
+                ErrorType::StateError,
+            ));
+        }
+        
+        let (headers, rows, name) = {
+            let table = table.borrow();
+            (table.headers.clone(), table.rows.clone(), table.name.clone())
+        };
+        // Колонка i читается из слота i фрейма выражения
+        let locals: std::collections::HashMap<String, usize> = headers.iter().enumerate().map(|(slot, header)| (header.clone(), slot)).collect();
+        let chunk = self
+            .compile_source(&source, &locals, None, false)
+            .map_err(|e| Self::wrap_dynamic_error(function, &source, e, line))?;
+        let mut predicate = crate::bytecode::Function::new(format!("<{}>", function), 0);
+        predicate.chunk = Rc::new(chunk);
+        
+        // Режим возвращается и при ошибке в выражении
+        let saved_mode = std::mem::replace(&mut self.undefined_vars_null, undefined_vars_null);
+        self.dynamic_depth += 1;
+        let mut kept = Vec::new();
+        let mut result = Ok(());
+        for row in rows {
+            let mut frame = CallFrame::new(predicate.clone(), self.stack.len());
+            frame.slots = row.clone();
+            frame.slots.resize(headers.len().max(row.len()), Value::Null);
+            match stacker::maybe_grow(DYNAMIC_EVAL_RED_ZONE, DYNAMIC_EVAL_STACK_SIZE, || self.run_frame(frame)) {
+                Ok(value) if value.is_truthy() => kept.push(row),
+                Ok(_) => {}
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        self.dynamic_depth -= 1;
+        self.undefined_vars_null = saved_mode;
+        result.map_err(|e| Self::wrap_dynamic_error(function, &source, e, line))?;
+        
+        let mut filtered = crate::common::table::Table::from_data(kept, Some(headers));
+        filtered.name = name;
+        Ok(Value::Table(Rc::new(RefCell::new(filtered))))
+    }
+
+    /// Выполняет фрейм во вложенном цикле до возврата из него и восстанавливает состояние VM
+    fn run_frame(&mut self, frame: CallFrame) -> Result<Value, LangError> {
+        let caller_frames = self.frames.len();
+        let saved_stack_height = self.stack.len();
+        let saved_handlers = self.exception_handlers.len();
+        let saved_base = self.base_frame_depth;
+        
+        self.frames.push(frame);
+        self.base_frame_depth = caller_frames;
+        let result = self.execute();
+        self.base_frame_depth = saved_base;
+        
+        self.frames.truncate(caller_frames);
+        self.exception_handlers.truncate(saved_handlers);
+        self.stack.truncate(saved_stack_height);
+        result
+    }
+
+    /// Прогресс цикла for при auto_progress: для массивов от AUTO_PROGRESS_MIN_ITEMS элементов
+    /// отчет примерно на каждый процент, на последней итерации задача завершается
+    fn report_loop_progress(&self, array_slot: usize, index_slot: usize, line: usize) {
+        let frame = match self.frames.last() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let total = match frame.slots.get(array_slot) {
+            Some(Value::Array(arr)) => arr.borrow().len(),
+            _ => return,
+        };
+        let done = match frame.slots.get(index_slot) {
+            Some(Value::Number(n)) => *n as usize + 1,
+            _ => return,
+        };
+        if total < AUTO_PROGRESS_MIN_ITEMS {
+            return;
+        }
+        let label = format!("for loop (line {})", line);
+        if done >= total {
+            crate::progress::report(&label, total as u64, total as u64);
+            crate::progress::finish(&label);
+        } else if done % (total / 100).max(1) == 0 {
+            crate::progress::report(&label, done as u64, total as u64);
+        }
+    }
+
+    /// Оценка памяти всех живых значений: глобальные переменные, стек и слоты фреймов.
+    /// Разделяемые массивы и таблицы считаются один раз
+    fn live_memory(&self) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        self.globals.iter()
+            .chain(self.stack.iter())
+            .chain(self.frames.iter().flat_map(|frame| frame.slots.iter()))
+            .map(|value| value.size_of_estimate_with(&mut seen))
+            .sum()
+    }
+
+    /// Ошибка "memory limit exceeded", если оценка превышает лимит и это подтверждает пересчет
+    fn check_memory_limit(&mut self, line: usize) -> Result<(), LangError> {
+        if !self.memory.exceeded() {
+            return Ok(());
+        }
+        if self.memory.should_recount() {
+            let used = self.live_memory();
+            self.memory.recounted(used);
+        }
+        match self.memory.limit() {
+            Some(limit) if self.memory.used() > limit => {
+                Err(self.runtime_error(memory::limit_exceeded_message(self.memory.used(), limit), line))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// memory_usage(): точная (в пределах оценки) память живых значений в байтах;
+    /// заодно сбрасывает накопленное завышение оценки для лимита.
+    /// memory_usage(value): оценка памяти одного значения вместе с вложенными
+    fn call_memory_usage(&mut self, args: &[Value]) -> Value {
+        if let Some(value) = args.first() {
+            return Value::Integer(value.size_of_estimate() as i64);
+        }
+        let used = self.live_memory();
+        if self.memory.limit().is_some() {
+            self.memory.recounted(used);
+        }
+        Value::Integer(used as i64)
+    }
+
+    /// cache_stats(fn?), cache_clear(fn?) и set_memoization(fn, enabled) для функций с @cache.
+    /// Функция задается именем или значением; без нее cache_stats/cache_clear относятся ко всем кэшам
+    fn call_memoization(&mut self, function: &str, args: &[Value], line: usize) -> Result<Value, LangError> {
+        let targets: Vec<usize> = match args.first() {
+            None | Some(Value::Null) if function != "set_memoization" => {
+                (0..self.functions.len()).filter(|&index| self.functions[index].cache.is_some()).collect()
+            }
+            Some(callee @ (Value::Function(_) | Value::Closure(_))) if callee.function_index().is_some_and(|index| index < self.functions.len()) => {
+                callee.function_index().into_iter().collect()
+            }
+            Some(Value::String(name)) => {
+                let found: Vec<usize> = (0..self.functions.len()).filter(|&index| *self.functions[index].name == **name).collect();
+                if found.is_empty() {
+                    return Err(self.runtime_error_with_type(
+                        format!("{}(): unknown function: {}", function, name),
+                        line,
+                        ErrorType::ValueError,
+                    ));
+                }
+                found
+            }
+            _ => {
+                let expected = if function == "set_memoization" { "a function name and true or false" } else { "a function name or no arguments" };
+                return Err(self.runtime_error_with_type(
+                    format!("{}() expects {}", function, expected),
+                    line,
+                    ErrorType::TypeError,
+                ));
+            }
+        };
+        
+        match function {
+            "cache_stats" => {
+                let stats = |function: &crate::bytecode::Function| {
+                    let (hits, misses, entries, evictions, bytes) = function.cache.as_ref().map_or((0, 0, 0, 0, 0), |cache| {
+                        let cache = cache.borrow();
+                        (cache.hits, cache.misses, cache.map.len(), cache.evictions, cache.bytes)
+                    });
+                    let mut object = crate::common::value::ObjectMap::new();
+                    object.insert("hits".to_string(), Value::Integer(hits as i64));
+                    object.insert("misses".to_string(), Value::Integer(misses as i64));
+                    object.insert("entries".to_string(), Value::Integer(entries as i64));
+                    object.insert("evictions".to_string(), Value::Integer(evictions as i64));
+                    object.insert("bytes".to_string(), Value::Integer(bytes as i64));
+                    object.insert("enabled".to_string(), Value::Bool(function.is_cached));
+                    object
+                };
+                if let [Value::String(_) | Value::Function(_) | Value::Closure(_), ..] = args {
+                    return Ok(Value::Object(stats(&self.functions[targets[0]])));
+                }
+                // Итог по всем кэшам и статистика каждой функции
+                let mut total = crate::common::value::ObjectMap::new();
+                let mut functions = crate::common::value::ObjectMap::new();
+                const TOTALS: [&str; 5] = ["hits", "misses", "entries", "evictions", "bytes"];
+                for key in TOTALS {
+                    total.insert(key.to_string(), Value::Integer(0));
+                }
+                for &index in &targets {
+                    let function_stats = stats(&self.functions[index]);
+                    for key in TOTALS {
+                        if let (Some(Value::Integer(sum)), Some(Value::Integer(n))) = (total.get_mut(key), function_stats.get(key)) {
+                            *sum += n;
+                        }
+                    }
+                    functions.insert(self.functions[index].name.clone(), Value::Object(function_stats));
+                }
+                total.insert("functions".to_string(), Value::Object(functions));
+                Ok(Value::Object(total))
+            }
+            "cache_clear" => {
+                // Удаляет сохраненные результаты и сбрасывает счетчики; возвращает число удаленных записей
+                let mut removed = 0;
+                for &index in &targets {
+                    if let Some(cache) = &self.functions[index].cache {
+                        let mut cache = cache.borrow_mut();
+                        removed += cache.clear();
+                        cache.hits = 0;
+                        cache.misses = 0;
+                        cache.evictions = 0;
+                    }
+                }
+                Ok(Value::Integer(removed as i64))
+            }
+            _ => {
+                let enabled = match args.get(1) {
+                    Some(Value::Bool(enabled)) => *enabled,
+                    _ => {
+                        return Err(self.runtime_error_with_type(
+                            "set_memoization() expects a function name and true or false".to_string(),
+                            line,
+                            ErrorType::TypeError,
+                        ));
+                    }
+                };
+                // Возвращает прежнее состояние; отключение очищает сохраненные результаты,
+                // чтобы повторное включение не вернуло устаревшие значения
+                let previous = self.functions[targets[0]].is_cached;
+                for &index in &targets {
+                    let function = &mut self.functions[index];
+                    function.is_cached = enabled;
+                    if enabled && function.cache.is_none() {
+                        function.cache = Some(Rc::new(RefCell::new(crate::bytecode::function::FnCache::new())));
+                    }
+                    if !enabled {
+                        if let Some(cache) = &function.cache {
+                            cache.borrow_mut().clear();
+                        }
+                    }
+                }
+                Ok(Value::Bool(previous))
+            }
+        }
+    }
+
+    /// set_option(name, value): меняет настройку интерпретатора до конца выполнения, возвращает прежнее значение.
+    /// "undefined_vars": "error" (по умолчанию) или "null" - неизвестные переменные читаются как null;
+    /// "auto_progress": true - циклы for по массивам от AUTO_PROGRESS_MIN_ITEMS элементов сообщают о прогрессе;
+    /// "cache_max_entries", "cache_max_bytes", "cache_max_result_bytes": ограничения кэша каждой функции
+    /// @cache (число или null - без ограничения)
+    fn call_set_option(&mut self, args: &[Value], line: usize) -> Result<Value, LangError> {
+        match args {
+            [Value::String(name), value]
+                if matches!(&**name, "cache_max_entries" | "cache_max_bytes" | "cache_max_result_bytes") =>
+            {
+                let limit = match value {
+                    Value::Null => None,
+                    Value::Number(n) if *n >= 1.0 && n.fract() == 0.0 => Some(*n as usize),
+                    Value::Integer(i) if *i >= 1 => Some(*i as usize),
+                    _ => {
+                        return Err(self.runtime_error_with_type(
+                            format!("set_option(): {} must be a positive integer or null", name),
+                            line,
+                            ErrorType::ValueError,
+                        ));
+                    }
+                };
+                let slot = match &**name {
+                    "cache_max_entries" => &mut self.cache_limits.max_entries,
+                    "cache_max_bytes" => &mut self.cache_limits.max_bytes,
+                    _ => &mut self.cache_limits.max_result_bytes,
+                };
+                let previous = std::mem::replace(slot, limit);
+                Ok(previous.map_or(Value::Null, |n| Value::Integer(n as i64)))
+            }
+            [Value::String(name), Value::String(value)] if &**name == "undefined_vars" => {
+                match parse_undefined_vars_mode(value) {
+                    Some(as_null) => {
+                        let previous = std::mem::replace(&mut self.undefined_vars_null, as_null);
+                        Ok(Value::String(if previous { "null" } else { "error" }.into()))
+                    }
+                    None => Err(self.runtime_error_with_type(
+                        format!("set_option(): undefined_vars must be \"null\" or \"error\", got \"{}\"", value),
+                        line,
+                        ErrorType::ValueError,
+                    )),
+                }
+            }
+            [Value::String(name), Value::Bool(enabled)] if &**name == "auto_progress" => {
+                Ok(Value::Bool(std::mem::replace(&mut self.auto_progress, *enabled)))
+            }
+            [Value::String(name), _] if &**name == "auto_progress" => Err(self.runtime_error_with_type(
+                "set_option(): auto_progress must be true or false".to_string(),
+                line,
+                ErrorType::TypeError,
+            )),
+            [Value::String(name), _] if &**name == "undefined_vars" => Err(self.runtime_error_with_type(
+                "set_option(): undefined_vars must be \"null\" or \"error\"".to_string(),
+                line,
+                ErrorType::TypeError,
+            )),
+            [Value::String(name), _] => Err(self.runtime_error_with_type(
+                format!("set_option(): unknown option '{}'", name),
+                line,
+                ErrorType::ValueError,
+            )),
+            _ => Err(self.runtime_error_with_type(
+                "set_option() expects an option name and a value".to_string(),
+                line,
+                ErrorType::TypeError,
+            )),
+        }
+    }
+
+    /// Компилирует код eval()/exec(), продолжая таблицы глобальных переменных и функций программы
+    fn compile_dynamic(
+        &mut self,
+        source: &str,
+        locals: &std::collections::HashMap<String, usize>,
+        is_exec: bool,
+    ) -> Result<Chunk, LangError> {
+        // Ищем ближайший фрейм пользовательской функции (фреймы eval()/exec() работают со слотами вызывающего)
+        let caller_function = self.frames.iter()
+            .enumerate()
+            .rev()
+            .find(|(_, frame)| frame.function.name != "<eval>" && frame.function.name != "<exec>")
+            .and_then(|(index, frame)| {
+                if index == 0 {
+                    None
+                } else {
+                    self.functions.iter().position(|f| f.name == frame.function.name)
+                }
+            });
+        
+        self.compile_source(source, locals, caller_function, is_exec)
+    }
+
+    /// Компилирует код из строки в контексте программы: locals - имена, читаемые из слотов фрейма,
+    /// caller_function - функция, чьи захваченные переменные видны коду
+    fn compile_source(
+        &mut self,
+        source: &str,
+        locals: &std::collections::HashMap<String, usize>,
+        caller_function: Option<usize>,
+        is_exec: bool,
+    ) -> Result<Chunk, LangError> {
+        use crate::lexer::Lexer;
+        use crate::parser::Parser;
+        use crate::semantic::resolver::Resolver;
+        use crate::compiler::Compiler;
+        
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize()?;
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse()?;
+        let mut resolver = Resolver::new();
+        resolver.resolve(&ast)?;
+        
+        let mut compiler = Compiler::with_context(self.global_indices.clone(), self.functions.clone());
+        let chunk = compiler.compile_dynamic(&ast, locals, caller_function, is_exec)?;
+        
+        // Новые глобальные переменные и функции из кода становятся частью программы
+        self.global_indices = chunk.global_indices.clone();
+        self.global_names.extend(chunk.global_names.iter().map(|(k, v)| (*k, v.clone())));
+        self.explicit_global_names.extend(chunk.explicit_global_names.iter().map(|(k, v)| (*k, v.clone())));
+        self.functions = compiler.get_functions();
+        
+        Ok(chunk)
+    }
+
+    /// Оборачивает ошибку из кода eval()/exec(), сохраняя ее тип (для typed catch)
+    fn wrap_dynamic_error(kind: &str, source: &str, error: LangError, line: usize) -> LangError {
+        const EXCERPT_LEN: usize = 40;
+        let first_line = source.trim().lines().next().unwrap_or("");
+        let excerpt = if first_line.chars().count() > EXCERPT_LEN || source.trim().lines().count() > 1 {
+            format!("{}...", first_line.chars().take(EXCERPT_LEN).collect::<String>())
+        } else {
+            first_line.to_string()
+        };
+        
+        let (message, inner_line, error_type, stack_trace, thrown) = match error {
+            LangError::LexError { message, line } => (message, line, Some(ErrorType::TokenError), Vec::new(), None),
+            LangError::ParseError { message, line } => (message, line, Some(ErrorType::SyntaxError), Vec::new(), None),
+            LangError::SemanticError { message, line } => (message, line, Some(ErrorType::ParseError), Vec::new(), None),
+            LangError::RuntimeError { message, line, error_type, stack_trace, thrown } => (message, line, error_type, stack_trace, thrown),
+        };
+        
+        // Ошибки вложенных вызовов уже обернуты - не дублируем префикс
+        let message = if message.starts_with("in eval(") || message.starts_with("in exec(") {
+            message
+        } else {
+            format!("in {}(\"{}\") at line {}: {}", kind, excerpt, inner_line, message)
+        };
+        
+        LangError::RuntimeError {
+            message,
+            line,
+            stack_trace,
+            error_type,
+            thrown,
+        }
+    }
+
+    pub fn run(&mut self, chunk: &Chunk) -> Result<Value, LangError> {
+        // Заполняем имена глобальных переменных из chunk
+        self.global_names = chunk.global_names.clone();
+        self.explicit_global_names = chunk.explicit_global_names.clone();
+        self.global_indices = chunk.global_indices.clone();
+        
+        // Отсчет таймаута начинается с запуска программы
+        self.deadline = crate::vm::policy::get_execution_policy()
+            .timeout
+            .map(|timeout| std::time::Instant::now() + timeout);
+        
+        // Создаем начальный frame
+        let function = crate::bytecode::Function::new("<main>".to_string(), 0);
+        let mut function = function;
+        function.chunk = Rc::new(chunk.clone());
+        let frame = CallFrame::new(function, 0);
+        self.frames.push(frame);
+
+        let result = self.execute();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.finish();
+        }
+        result
+    }
+
+    /// Включает профилирование по стекам вызовов (как DATACODE_PROFILE)
+    pub fn enable_profiling(&mut self) {
+        self.profiler.get_or_insert_with(Profiler::new);
+    }
+
+    /// Профиль выполнения, если профилирование включено
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Имя встроенной функции для профиля; для синонимов (str/to_string) - первое по алфавиту
+    fn native_name(&self, native_index: usize) -> String {
+        self.global_indices
+            .iter()
+            .filter(|(_, index)| **index == native_index)
+            .map(|(name, _)| name.as_str())
+            .min()
+            .map_or_else(|| format!("native_{}", native_index), str::to_string)
+    }
+
+    /// Компилирует и выполняет следующий фрагмент программы на этой VM (встраивание, Session).
+    /// Код компилируется в контексте уже выполненного: глобальные переменные и функции
+    /// предыдущих фрагментов сохраняют индексы и доступны по именам
+    pub fn run_continued(&mut self, source: &str) -> Result<Value, LangError> {
+        use crate::lexer::Lexer;
+        use crate::parser::Parser;
+        use crate::semantic::resolver::Resolver;
+        use crate::compiler::Compiler;
+        
+        let tokens = Lexer::new(source).tokenize()?;
+        let ast = Parser::new(tokens).parse()?;
+        Resolver::new().resolve(&ast)?;
+        
+        let mut compiler = Compiler::with_context(self.global_indices.clone(), self.functions.clone());
+        let mut chunk = compiler.compile(&ast)?;
+        self.functions = compiler.get_functions();
+        
+        // run() берет имена глобальных переменных из chunk: добавляем к ним имена прошлых фрагментов
+        let mut global_names = self.global_names.clone();
+        global_names.extend(chunk.global_names.drain());
+        chunk.global_names = global_names;
+        let mut explicit_global_names = self.explicit_global_names.clone();
+        explicit_global_names.extend(chunk.explicit_global_names.drain());
+        chunk.explicit_global_names = explicit_global_names;
+        
+        // Фреймы и стек, оставшиеся после ошибки в предыдущем фрагменте
+        self.frames.clear();
+        self.stack.clear();
+        self.exception_handlers.clear();
+        self.run(&chunk)
+    }
+
+    /// Основной цикл выполнения. Завершается при возврате из фрейма base_frame_depth
+    fn execute(&mut self) -> Result<Value, LangError> {
+        loop {
+            // Проверяем, что есть frame
+            if self.frames.is_empty() {
+                break;
+            }
+
+            let (instruction, line) = {
+                let frame = self.frames.last().unwrap();
+                if frame.ip >= frame.function.chunk.code.len() {
+                    break;
+                }
+                let ip = frame.ip;
+                let instruction = frame.function.chunk.code[ip].clone();
+                let line = frame.function.chunk.get_line(ip);
+                (instruction, line)
+            };
+
+            // Смена глубины стека (вызов, возврат, раскрутка исключением) - новый путь профиля
+            if let Some(profiler) = &mut self.profiler {
+                if profiler.depth() != self.frames.len() {
+                    profiler.sync(&self.frames);
+                }
+                profiler.line(self.frames.len(), line);
+            }
+
+            // Отмена и таймаут проверяются раз в INTERRUPT_CHECK_INTERVAL инструкций. Ошибка минует
+            // обработчики исключений: catch не должен продлевать прерванную программу
+            self.steps_until_interrupt_check -= 1;
+            if self.steps_until_interrupt_check == 0 {
+                self.steps_until_interrupt_check = INTERRUPT_CHECK_INTERVAL;
+                if self.is_cancel_requested() {
+                    return Err(self.runtime_error(crate::vm::policy::EXECUTION_CANCELLED.to_string(), line));
+                }
+                if self.deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+                    return Err(self.runtime_error(crate::vm::policy::EXECUTION_TIMED_OUT.to_string(), line));
+                }
+                // Прерывание пользователем - обычная ошибка: программа может перехватить ее
+                // через catch Interrupted. Флаг сбрасывается, чтобы catch мог продолжить работу
+                if let Some(error) = self.take_interrupt(line) {
+                    match self.handle_exception(error) {
+                        Ok(()) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+
+            // Вызов со spread: аргументы привязываются к параметрам функции и кладутся на стек,
+            // дальше вызов выполняется как обычный Call
+            let instruction = match instruction {
+                OpCode::CallSpread => match self.bind_spread_call(line) {
+                    Ok(arity) => OpCode::Call(arity),
+                    Err(error) => {
+                        self.frames.last_mut().unwrap().ip += 1;
+                        match self.handle_exception(error) {
+                            Ok(()) => continue,
+                            Err(e) => return Err(e),
+                        }
+                    }
+                },
+                other => other,
+            };
+
+            let frame = self.frames.last_mut().unwrap();
+            frame.ip += 1;
+
+            match instruction {
+                OpCode::Constant(index) => {
+                    let value = frame.function.chunk.constants[index].clone();
+                    self.push(value);
+                }
+                OpCode::LoadLocal(index) => {
+                    // Для сложных типов (Array, Table) возвращаем ссылку (shallow copy Rc)
+                    // Для простых типов клонируем значение
+                    let value = &frame.slots[index];
+                    let loaded_value = match value {
+                        Value::Array(arr_rc) => Value::Array(Rc::clone(arr_rc)),
+                        Value::Table(table_rc) => Value::Table(Rc::clone(table_rc)),
+                        _ => value.clone(), // Простые типы клонируем
+                    };
+                    self.push(loaded_value);
+                }
+                OpCode::StoreLocal(index) => {
+                    let value = self.pop()?;
+                    // Clone уже создает глубокую копию для массивов и таблиц
+                    let frame = self.frames.last_mut().unwrap();
+                    if index >= frame.slots.len() {
+                        frame.slots.resize(index + 1, Value::Null);
+                    }
+                    frame.slots[index] = value;
+                }
+                OpCode::LoadGlobal(index) => {
+                    if index >= self.globals.len() && self.undefined_vars_null {
+                        // Режим undefined_vars = "null": неизвестная переменная читается как null
+                        self.push(Value::Null);
+                    } else if index >= self.globals.len() {
+                        // Индекс неизвестного имени есть только в таблице имен компилятора
+                        let name = self.global_indices.iter().find(|(_, &i)| i == index).map(|(name, _)| name);
+                        let message = match name {
+                            Some(name) => format!("Undefined variable '{}'", name),
+                            None => "Undefined variable".to_string(),
+                        };
+                        let error = self.runtime_error(message, line);
+                        match self.handle_exception(error) {
+                            Ok(()) => {
+                                // Исключение обработано, кладем Null на стек
+                                self.push(Value::Null);
+                            }
+                            Err(e) => return Err(e), // Исключение не обработано
+                        }
+                    } else {
+                        // Для сложных типов (Array, Table) возвращаем ссылку (shallow copy Rc)
+                        // Для простых типов клонируем значение
+                        let value = &self.globals[index];
+                        let loaded_value = match value {
+                            Value::Array(arr_rc) => Value::Array(Rc::clone(arr_rc)),
+                            Value::Table(table_rc) => Value::Table(Rc::clone(table_rc)),
+                            _ => value.clone(), // Простые типы клонируем
+                        };
+                        self.push(loaded_value);
+                    }
+                }
+                OpCode::StoreGlobal(index) => {
+                    let mut value = self.pop()?;
+                    // Если значение - таблица, устанавливаем её имя из global_names
+                    if let Value::Table(table_rc) = &mut value {
+                        if let Some(var_name) = self.global_names.get(&index) {
+                            if let Ok(mut table) = table_rc.try_borrow_mut() {
+                                table.set_name(var_name.clone());
+                            }
+                        }
+                    }
+                    // Clone уже создает глубокую копию для массивов и таблиц
+                    if index >= self.globals.len() {
+                        self.globals.resize(index + 1, Value::Null);
+                    }
+                    // Учет памяти: новое значение заменяет старое. Тот же массив или таблица
+                    // (push() с присваиванием обратно) оценку не меняет - рост учтен в push()
+                    // Проверяется только рост: присваивание, которое не увеличивает оценку
+                    // (tripped = true в catch после ошибки лимита), не должно снова падать
+                    let mut grew = false;
+                    if self.memory.limit().is_some() && !same_container(&self.globals[index], &value) {
+                        let (old_size, new_size) = (self.globals[index].size_of_estimate(), value.size_of_estimate());
+                        self.memory.replace(old_size, new_size);
+                        grew = new_size > old_size;
+                    }
+                    // Важно: присваиваем value после установки имени, чтобы имя сохранилось
+                    self.globals[index] = value;
+                    if grew {
+                        if let Err(error) = self.check_memory_limit(line) {
+                            match self.handle_exception(error) {
+                                Ok(()) => continue,
+                                Err(e) => return Err(e),
+                            }
+                        }
+                    }
+                }
+                OpCode::Add => self.binary_instruction(BinaryOp::Add, line)?,
+                OpCode::Sub => self.binary_instruction(BinaryOp::Sub, line)?,
+                OpCode::Mul => self.binary_instruction(BinaryOp::Mul, line)?,
+                OpCode::Div => self.binary_instruction(BinaryOp::Div, line)?,
+                OpCode::IntDiv => self.binary_instruction(BinaryOp::IntDiv, line)?,
+                OpCode::Mod => self.binary_instruction(BinaryOp::Mod, line)?,
+                OpCode::Pow => self.binary_instruction(BinaryOp::Pow, line)?,
+                OpCode::BitAnd => self.binary_instruction(BinaryOp::BitAnd, line)?,
+                OpCode::BitOr => self.binary_instruction(BinaryOp::BitOr, line)?,
+                OpCode::BitXor => self.binary_instruction(BinaryOp::BitXor, line)?,
+                OpCode::ShiftLeft => self.binary_instruction(BinaryOp::ShiftLeft, line)?,
+                OpCode::ShiftRight => self.binary_instruction(BinaryOp::ShiftRight, line)?,
+                OpCode::Negate => {
+                    let value = self.pop()?;
+                    match operators::negate(&value) {
+                        Ok(result) => self.push(result),
+                        Err(error) => {
+                            let error = self.operator_error(error, line);
+                            match self.handle_exception(error) {
+                                Ok(()) => continue, // Исключение обработано, продолжаем выполнение
+                                Err(e) => return Err(e), // Исключение не обработано
+                            }
+                        }
+                    }
+                }
+                OpCode::Not => {
+                    let value = self.pop()?;
+                    self.push(operators::not(&value));
+                }
+                OpCode::Equal => self.binary_instruction(BinaryOp::Equal, line)?,
+                OpCode::NotEqual => self.binary_instruction(BinaryOp::NotEqual, line)?,
+                OpCode::Greater => self.binary_instruction(BinaryOp::Greater, line)?,
+                OpCode::Less => self.binary_instruction(BinaryOp::Less, line)?,
+                OpCode::GreaterEqual => self.binary_instruction(BinaryOp::GreaterEqual, line)?,
+                OpCode::LessEqual => self.binary_instruction(BinaryOp::LessEqual, line)?,
+                OpCode::In => {
+                    let container = self.pop()?; // Правый операнд - где ищем
+                    let value = self.pop()?; // Левый операнд - что ищем
+                    
+                    match Self::membership(&value, &container) {
+                        Ok(found) => self.push(Value::Bool(found)),
+                        Err(message) => {
+                            let error = self.runtime_error_with_type(message, line, ErrorType::TypeError);
                             match self.handle_exception(error) {
                                 Ok(()) => continue,
                                 Err(e) => return Err(e),
@@ -661,7 +2581,8 @@ impl Vm {
                     // Получаем функцию со стека
                     let function_value = self.pop()?;
                     match function_value {
-                        Value::Function(function_index) => {
+                        Value::Function(_) | Value::Closure(_) => {
+                            let function_index = function_value.function_index().unwrap_or_default();
                             if function_index >= self.functions.len() {
                                 let error = self.runtime_error(
                                     format!("Function index {} out of bounds", function_index),
@@ -673,6 +2594,17 @@ impl Vm {
                                 }
                             }
                             
+                            let closure = match self.callee_closure(&function_value) {
+                                Ok(closure) => closure,
+                                Err(message) => {
+                                    let error = self.runtime_error(message, line);
+                                    match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    }
+                                }
+                            };
+                            
                             let function = self.functions[function_index].clone();
                             
                             // Проверяем количество аргументов
@@ -690,122 +2622,41 @@ impl Vm {
                                 }
                             }
                             
-                            // Собираем аргументы со стека (в обратном порядке, так как они были положены последними)
-                            let mut args = Vec::new();
-                            for _ in 0..arity {
-                                args.push(self.pop()?);
+                            // Аргументы - последние arity значений стека, args[0] - первый аргумент
+                            if self.stack.len() < arity {
+                                return Err(self.runtime_error("Stack underflow".to_string(), line));
                             }
-                            args.reverse(); // Теперь args[0] - первый аргумент
+                            let args = self.stack.split_off(self.stack.len() - arity);
                             
                             // Проверяем кэш, если функция помечена как кэшируемая
+                            let mut cache_key = None;
                             if function.is_cached {
                                 use crate::bytecode::function::CacheKey;
                                 
-                                // Пытаемся создать ключ кэша
-                                if let Some(cache_key) = CacheKey::new(&args) {
-                                    // Получаем доступ к кэшу функции
-                                    if let Some(cache_rc) = &function.cache {
-                                        let cache = cache_rc.borrow();
-                                        
-                                        // Проверяем, есть ли результат в кэше
-                                        if let Some(cached_result) = cache.map.get(&cache_key) {
-                                            // Результат найден в кэше - возвращаем его без выполнения функции
-                                            self.push(cached_result.clone());
-                                            continue; // Пропускаем выполнение функции
-                                        }
-                                        
-                                        // Результат не найден - освобождаем borrow и продолжим выполнение
-                                        drop(cache);
-                                        
-                                        // Выполним функцию и сохраним результат в кэш
-                                        // (продолжаем выполнение ниже)
-                                    }
-                                }
-                                // Если ключ не удалось создать (не-hashable аргументы),
-                                // просто выполняем функцию без кэширования
-                            }
-                            
-                            // Создаем новый CallFrame
-                            let stack_start = self.stack.len();
-                            let mut new_frame = if function.is_cached {
-                                // Сохраняем аргументы для кэширования
-                                CallFrame::new_with_cache(function.clone(), stack_start, args.clone())
-                            } else {
-                                CallFrame::new(function.clone(), stack_start)
-                            };
-                            
-                            // Копируем таблицу типов ошибок из chunk функции в VM
-                            if !function.chunk.error_type_table.is_empty() {
-                                self.error_type_table = function.chunk.error_type_table.clone();
-                            }
-                            
-                            // Копируем захваченные переменные из родительских frames (если есть)
-                            // Используем ancestor_depth для поиска переменной в правильном предке
-                            if !self.frames.is_empty() && !function.captured_vars.is_empty() {
-                                #[cfg(debug_assertions)]
-                                eprintln!("[DEBUG] Function '{}' has {} captured vars, frames.len() = {}", 
-                                    function.name, function.captured_vars.len(), self.frames.len());
-                                
-                                for captured_var in &function.captured_vars {
-                                    // Убеждаемся, что слот существует в новом frame
-                                    if captured_var.local_slot_index >= new_frame.slots.len() {
-                                        new_frame.slots.resize(captured_var.local_slot_index + 1, Value::Null);
+                                // Ключ строится по аргументам вызывающего: контейнеры - по отпечатку
+                                // содержимого (параметры функции получат их копии). Захваченные
+                                // значения замыкания входят в ключ наравне с аргументами
+                                let key = match &closure {
+                                    Some(closure) if !closure.captured.is_empty() => {
+                                        CacheKey::new(&closure.captured.iter().chain(&args).cloned().collect::<Vec<_>>())
                                     }
-                                    
-                                    // Находим предка на нужной глубине
-                                    // ancestor_depth = 0 означает ближайший родитель (последний frame в стеке)
-                                    // ancestor_depth = 1 означает дедушку (предпоследний frame) и т.д.
-                                    let ancestor_index = self.frames.len().saturating_sub(1 + captured_var.ancestor_depth);
-                                    
-                                    #[cfg(debug_assertions)]
-                                    eprintln!("[DEBUG] Captured var '{}': ancestor_depth={}, ancestor_index={}, parent_slot={}, local_slot={}", 
-                                        captured_var.name, captured_var.ancestor_depth, ancestor_index, 
-                                        captured_var.parent_slot_index, captured_var.local_slot_index);
-                                    
-                                    if ancestor_index < self.frames.len() {
-                                        let ancestor_frame = &self.frames[ancestor_index];
-                                        
-                                        #[cfg(debug_assertions)]
-                                        eprintln!("[DEBUG] Ancestor frame '{}' has {} slots", 
-                                            ancestor_frame.function.name, ancestor_frame.slots.len());
-                                        
-                                        // Копируем значение из предка
-                                        if captured_var.parent_slot_index < ancestor_frame.slots.len() {
-                                            let captured_value = ancestor_frame.slots[captured_var.parent_slot_index].clone();
-                                            
-                                            #[cfg(debug_assertions)]
-                                            eprintln!("[DEBUG] Copying value {:?} from ancestor slot {} to local slot {}", 
-                                                captured_value, captured_var.parent_slot_index, captured_var.local_slot_index);
-                                            
-                                            new_frame.slots[captured_var.local_slot_index] = captured_value;
-                                        } else {
-                                            // Если слот не существует в предке, используем Null
-                                            #[cfg(debug_assertions)]
-                                            eprintln!("[DEBUG] WARNING: parent_slot {} >= ancestor slots.len() {}", 
-                                                captured_var.parent_slot_index, ancestor_frame.slots.len());
-                                            new_frame.slots[captured_var.local_slot_index] = Value::Null;
-                                        }
-                                    } else {
-                                        // Если предок не существует, используем Null
-                                        #[cfg(debug_assertions)]
-                                        eprintln!("[DEBUG] WARNING: ancestor_index {} >= frames.len() {}", 
-                                            ancestor_index, self.frames.len());
-                                        new_frame.slots[captured_var.local_slot_index] = Value::Null;
+                                    _ => CacheKey::new(&args),
+                                };
+                                if let (Some(key), Some(cache_rc)) = (key, &function.cache) {
+                                    let cached_result = cache_rc.borrow_mut().lookup(&key);
+                                    if let Some(cached_result) = cached_result {
+                                        // Результат найден в кэше - возвращаем его без выполнения функции
+                                        self.push(cached_result);
+                                        continue; // Пропускаем выполнение функции
                                     }
+                                    // Выполним функцию и сохраним результат в кэш при возврате
+                                    cache_key = Some(key);
                                 }
+                                // Если ключ не удалось создать (ленивый план в аргументах),
+                                // просто выполняем функцию без кэширования
                             }
                             
-                            // Инициализируем параметры функции в slots (после захваченных переменных)
-                            let param_start_index = function.captured_vars.len();
-                            for (i, arg) in args.iter().enumerate() {
-                                let slot_index = param_start_index + i;
-                                if slot_index >= new_frame.slots.len() {
-                                    new_frame.slots.resize(slot_index + 1, Value::Null);
-                                }
-                                new_frame.slots[slot_index] = arg.clone();
-                            }
-                            
-                            // Добавляем новый frame
+                            let new_frame = self.new_call_frame(function, closure, &args, cache_key);
                             self.frames.push(new_frame);
                         }
                         Value::NativeFunction(native_index) => {
@@ -827,6 +2678,241 @@ impl Vm {
                             }
                             args.reverse(); // Теперь args[0] - первый аргумент
                             
+                            // eval()/exec() компилируют и выполняют код в контексте вызывающего фрейма
+                            if native_index == EVAL_NATIVE_INDEX || native_index == EXEC_NATIVE_INDEX {
+                                let is_exec = native_index == EXEC_NATIVE_INDEX;
+                                match self.call_dynamic(is_exec, &args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_rename_map() вызывает пользовательскую функцию для каждого имени колонки
+                            if native_index == TABLE_RENAME_MAP_NATIVE_INDEX {
+                                match self.call_table_rename_map(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_add_column() вызывает пользовательскую функцию для каждой строки
+                            if native_index == TABLE_ADD_COLUMN_NATIVE_INDEX {
+                                match self.call_table_add_column(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_apply()/table_apply_column() вызывают функцию для каждой ячейки
+                            if native_index == TABLE_APPLY_NATIVE_INDEX || native_index == TABLE_APPLY_COLUMN_NATIVE_INDEX {
+                                let function = if native_index == TABLE_APPLY_NATIVE_INDEX { "table_apply" } else { "table_apply_column" };
+                                match self.call_table_apply(function, &args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_drop_rows() вызывает предикат для каждой строки
+                            if native_index == TABLE_DROP_ROWS_NATIVE_INDEX {
+                                match self.call_table_drop_rows(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // reduce() вызывает функцию для каждого элемента массива
+                            if native_index == REDUCE_NATIVE_INDEX {
+                                match self.call_reduce(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // map() вызывает функцию для каждой группы соответствующих элементов
+                            if native_index == MAP_NATIVE_INDEX {
+                                match self.call_map(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_filter_expr() вычисляет выражение для каждой строки таблицы
+                            if native_index == TABLE_FILTER_EXPR_NATIVE_INDEX {
+                                match self.call_table_filter_expr("table_filter_expr", &args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // table_where(table, expr) - строка-предикат вместо (колонка, оператор, значение)
+                            if native_index == TABLE_WHERE_NATIVE_INDEX
+                                && matches!(args.as_slice(), [Value::Table(_), Value::String(_)])
+                            {
+                                match self.call_table_filter_expr("table_where", &args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // cache_stats()/cache_clear()/set_memoization() работают с кэшами функций @cache
+                            if native_index == CACHE_STATS_NATIVE_INDEX
+                                || native_index == CACHE_CLEAR_NATIVE_INDEX
+                                || native_index == SET_MEMOIZATION_NATIVE_INDEX
+                            {
+                                let function = match native_index {
+                                    CACHE_STATS_NATIVE_INDEX => "cache_stats",
+                                    CACHE_CLEAR_NATIVE_INDEX => "cache_clear",
+                                    _ => "set_memoization",
+                                };
+                                match self.call_memoization(function, &args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // memory_usage() пересчитывает память всех живых значений VM
+                            if native_index == MEMORY_USAGE_NATIVE_INDEX {
+                                let used = self.call_memory_usage(&args);
+                                self.push(used);
+                                continue;
+                            }
+                            
+                            // set_option() меняет настройки самой VM
+                            if native_index == SET_OPTION_NATIVE_INDEX {
+                                match self.call_set_option(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // filter() вызывает функцию-условие для каждого элемента
+                            if native_index == FILTER_NATIVE_INDEX {
+                                match self.call_filter(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // set_parallel_threshold() меняет порог параллельного map()/filter()
+                            if native_index == SET_PARALLEL_THRESHOLD_NATIVE_INDEX {
+                                match self.call_set_parallel_threshold(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // cached_eval() вызывает функцию, если результата нет в кэше
+                            if native_index == CACHED_EVAL_NATIVE_INDEX {
+                                match self.call_cached_eval(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // sort_by() вызывает функцию-ключ для каждого элемента
+                            if native_index == SORT_BY_NATIVE_INDEX {
+                                match self.call_sort_by(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            // assert_throws() вызывает функцию и ожидает от нее ошибку
+                            // sleep() и retry() ждут, проверяя отмену и таймаут; их ошибки прерывания
+                            // не перехватываются try/catch
+                            if native_index == SLEEP_NATIVE_INDEX || native_index == RETRY_NATIVE_INDEX {
+                                let result = if native_index == SLEEP_NATIVE_INDEX {
+                                    self.call_sleep(&args, line)
+                                } else {
+                                    self.call_retry(&args, line)
+                                };
+                                match result {
+                                    Ok(result) => self.push(result),
+                                    Err(error)
+                                        if crate::vm::policy::is_cancelled_error(&error)
+                                            || crate::vm::policy::is_timeout_error(&error) =>
+                                    {
+                                        return Err(error);
+                                    }
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
+                            if native_index == ASSERT_THROWS_NATIVE_INDEX {
+                                match self.call_assert_throws(&args, line) {
+                                    Ok(result) => self.push(result),
+                                    Err(error) => match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    },
+                                }
+                                continue;
+                            }
+                            
                             // Специальная проверка для range (принимает 1, 2 или 3 аргумента)
                             if native_index == 2 {
                                 // range - индекс 2
@@ -842,7 +2928,7 @@ impl Vm {
                                 }
                                 // Проверяем типы аргументов - все должны быть числами
                                 for arg in &args {
-                                    if !matches!(arg, Value::Number(_)) {
+                                    if !arg.is_numeric() {
                                         let error = self.runtime_error(
                                             "range() arguments must be numbers".to_string(),
                                             line,
@@ -855,8 +2941,8 @@ impl Vm {
                                 }
                                 // Проверяем, что step не равен 0 (если передан)
                                 if arity == 3 {
-                                    if let Value::Number(step) = &args[2] {
-                                        if *step == 0.0 {
+                                    if let Some(step) = args[2].as_f64() {
+                                        if step == 0.0 {
                                             let error = self.runtime_error(
                                                 "range() step cannot be zero".to_string(),
                                                 line,
@@ -872,53 +2958,65 @@ impl Vm {
                             
                             // Вызываем нативную функцию
                             let native_fn = self.natives[native_index];
+                            let started = self.profiler.as_mut().map(|profiler| {
+                                profiler.charge();
+                                std::time::Instant::now()
+                            });
                             let result = native_fn(&args);
+                            if let Some(started) = started {
+                                let elapsed = started.elapsed();
+                                let name = self.native_name(native_index);
+                                if let Some(profiler) = &mut self.profiler {
+                                    profiler.record_native(&name, elapsed);
+                                }
+                            }
                             
                             // Если это relate(), получаем связи из thread-local storage
-                            if native_index == 65 {
-                                // relate() - индекс 65
+                            // register_foreign_key() сохраняет связи туда же
+                            if native_index == RELATE_NATIVE_INDEX || native_index == REGISTER_FOREIGN_KEY_NATIVE_INDEX {
                                 use crate::vm::natives::take_relations;
                                 let relations = take_relations();
                                 
                                 // Находим имена таблиц по указателям
+                                let mut unresolved = false;
                                 for (table1_ptr, col1_name, table2_ptr, col2_name) in relations {
-                                    let mut found_table1_name = None;
-                                    let mut found_table2_name = None;
-                                    
-                                    // Ищем таблицы в глобальных переменных
-                                    for (index, value) in self.globals.iter().enumerate() {
-                                        if let Value::Table(table) = value {
-                                            if Rc::as_ptr(table) == table1_ptr {
-                                                if let Some(var_name) = self.explicit_global_names.get(&index) {
-                                                    found_table1_name = Some(var_name.clone());
-                                                }
-                                            }
-                                            if Rc::as_ptr(table) == table2_ptr {
-                                                if let Some(var_name) = self.explicit_global_names.get(&index) {
-                                                    found_table2_name = Some(var_name.clone());
-                                                }
-                                            }
-                                        }
-                                    }
+                                    let found_table1_name = self.global_table_name(table1_ptr);
+                                    let found_table2_name = self.global_table_name(table2_ptr);
                                     
                                     // Если нашли обе таблицы, сохраняем связь
                                     // relate(pk_table["pk_column"], fk_table["fk_column"])
                                     // Первый аргумент - первичный ключ (целевая таблица)
                                     // Второй аргумент - внешний ключ (таблица, которая ссылается)
                                     if let (Some(table1_name), Some(table2_name)) = (found_table1_name, found_table2_name) {
+                                        // Повторное объявление внешнего ключа колонки заменяет прежнее
+                                        self.explicit_relations.retain(|r| {
+                                            r.source_table_name != table2_name || r.source_column_name != col2_name
+                                        });
                                         self.explicit_relations.push(ExplicitRelation {
                                             source_table_name: table2_name, // Таблица с внешним ключом
                                             source_column_name: col2_name,  // Внешний ключ
                                             target_table_name: table1_name, // Таблица с первичным ключом
                                             target_column_name: col1_name,   // Первичный ключ
                                         });
+                                    } else {
+                                        unresolved = true;
+                                    }
+                                }
+                                if unresolved && native_index == REGISTER_FOREIGN_KEY_NATIVE_INDEX {
+                                    let error = self.runtime_error_with_type(
+                                        "register_foreign_key(): both tables must be stored in global variables".to_string(),
+                                        line,
+                                        ErrorType::ValueError,
+                                    );
+                                    match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
                                     }
                                 }
                             }
                             
                             // Если это primary_key(), получаем первичные ключи из thread-local storage
-                            if native_index == 66 {
-                                // primary_key() - индекс 66
+                            if native_index == PRIMARY_KEY_NATIVE_INDEX {
                                 use crate::vm::natives::take_primary_keys;
                                 let primary_keys = take_primary_keys();
                                 
@@ -947,13 +3045,26 @@ impl Vm {
                                 }
                             }
                             
+                            // Если это declare_export(), сохраняем объявления экспорта
+                            if native_index == DECLARE_EXPORT_NATIVE_INDEX {
+                                let declared: Result<(), LangError> = crate::vm::natives::take_export_declarations()
+                                    .into_iter()
+                                    .try_for_each(|(table, name, primary_key)| self.add_export_declaration(table, name, primary_key, line));
+                                if let Err(error) = declared {
+                                    match self.handle_exception(error) {
+                                        Ok(()) => continue,
+                                        Err(e) => return Err(e),
+                                    }
+                                }
+                            }
+                            
                             // Проверяем, не было ли ошибки в нативной функции (например, path traversal)
                             use crate::websocket::take_native_error;
-                            if let Some(error_msg) = take_native_error() {
+                            if let Some((error_msg, error_type)) = take_native_error() {
                                 let error = self.runtime_error_with_type(
                                     error_msg,
                                     line,
-                                    crate::common::error::ErrorType::IOError,
+                                    error_type,
                                 );
                                 match self.handle_exception(error) {
                                     Ok(()) => continue, // Исключение обработано
@@ -961,6 +3072,19 @@ impl Vm {
                                 }
                             }
                             
+                            // push() растит массив на месте: учитываем размер добавленного элемента
+                            if native_index == PUSH_NATIVE_INDEX && self.memory.limit().is_some() {
+                                if let (Some(Value::Array(_)), Some(item)) = (args.first(), args.get(1)) {
+                                    self.memory.grow(item.size_of_estimate());
+                                    if let Err(error) = self.check_memory_limit(line) {
+                                        match self.handle_exception(error) {
+                                            Ok(()) => continue,
+                                            Err(e) => return Err(e),
+                                        }
+                                    }
+                                }
+                            }
+                            
                             // Помещаем результат на стек
                             self.push(result);
                         }
@@ -985,38 +3109,34 @@ impl Vm {
                     };
                     
                     let frames_count = self.frames.len();
-                    if frames_count > 1 {
+                    if frames_count > self.base_frame_depth + 1 {
                         // Сохраняем результат в кэш, если функция кэшируемая
-                        if let Some(frame) = self.frames.last() {
+                        if let Some(frame) = self.frames.last_mut() {
                             if frame.function.is_cached {
-                                if let Some(ref cached_args) = frame.cached_args {
-                                    use crate::bytecode::function::CacheKey;
-                                    
-                                    // Пытаемся создать ключ кэша
-                                    if let Some(cache_key) = CacheKey::new(cached_args) {
-                                        // Получаем доступ к кэшу функции
-                                        if let Some(cache_rc) = &frame.function.cache {
-                                            let mut cache = cache_rc.borrow_mut();
-                                            
-                                            // Сохраняем результат в кэш
-                                            if let Some(ref result) = return_value {
-                                                cache.map.insert(cache_key, result.clone());
-                                            }
-                                        }
-                                    }
+                                if let (Some(cache_key), Some(cache_rc), Some(result)) =
+                                    (frame.cache_key.take(), &frame.function.cache, &return_value)
+                                {
+                                    // Ограничения кэша (LRU по числу записей и объему) - из set_option()
+                                    cache_rc.borrow_mut().insert(cache_key, result.clone(), &self.cache_limits);
                                 }
                             }
                         }
                         
-                        // Возврат из функции - удаляем текущий frame
-                        self.frames.pop();
+                        // Возврат из функции - удаляем текущий frame, его вектор слотов
+                        // достанется следующему вызову
+                        if let Some(mut frame) = self.frames.pop() {
+                            if self.free_slots.len() < MAX_FREE_SLOT_VECTORS {
+                                frame.slots.clear();
+                                self.free_slots.push(std::mem::take(&mut frame.slots));
+                            }
+                        }
                         
                         // Помещаем возвращаемое значение на стек для вызывающей функции
                         if let Some(value) = return_value {
                             self.push(value);
                         }
                     } else {
-                        // Возврат из главной функции - завершаем выполнение
+                        // Возврат из главной функции (или из кода eval()/exec()) - завершаем выполнение
                         // Возвращаем значение со стека, если есть
                         if let Some(value) = return_value {
                             return Ok(value);
@@ -1028,6 +3148,11 @@ impl Vm {
                 OpCode::Pop => {
                     self.pop()?;
                 }
+                OpCode::Dup => {
+                    let value = self.pop()?;
+                    self.push(value.clone());
+                    self.push(value);
+                }
                 OpCode::MakeArray(count) => {
                     let mut elements = Vec::new();
                     for _ in 0..count {
@@ -1036,6 +3161,79 @@ impl Vm {
                     elements.reverse(); // Восстанавливаем правильный порядок
                     self.push(Value::Array(Rc::new(RefCell::new(elements))));
                 }
+                OpCode::MakeClosure(function_index) => {
+                    // Значения захваченных переменных копируются сейчас: замыкание не читает
+                    // слоты фрейма, из которого его потом вызовут
+                    let captured = self.functions[function_index]
+                        .captured_vars
+                        .iter()
+                        .map(|var| frame.slots.get(var.parent_slot_index).cloned().unwrap_or(Value::Null))
+                        .collect();
+                    let closure = Closure { function: function_index, captured, frame: frame.id };
+                    self.push(Value::Closure(Rc::new(closure)));
+                }
+                OpCode::MakeObject(count) => {
+                    let mut pairs = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let value = self.pop()?;
+                        let key = self.pop()?;
+                        pairs.push((key.to_string(), value));
+                    }
+                    // Ключи в порядке записи; повторный ключ заменяет значение
+                    let object = pairs.into_iter().rev().collect();
+                    self.push(Value::Object(object));
+                }
+                OpCode::Unpack(count) => {
+                    let value = self.pop()?;
+                    match Self::unpack_values(value, count) {
+                        Ok(elements) => {
+                            // Первый элемент - на вершину: его сохраняет первая следующая инструкция
+                            for element in elements.into_iter().rev() {
+                                self.push(element);
+                            }
+                        }
+                        Err((message, error_type)) => {
+                            let error = self.runtime_error_with_type(message, line, error_type);
+                            match self.handle_exception(error) {
+                                Ok(()) => continue,
+                                Err(e) => return Err(e),
+                            }
+                        }
+                    }
+                }
+                OpCode::Extend => {
+                    let value = self.pop()?;
+                    let target = self.pop()?;
+                    match Self::extend_with_spread(target, value) {
+                        Ok(result) => self.push(result),
+                        Err(message) => {
+                            let error = self.runtime_error_with_type(message, line, ErrorType::TypeError);
+                            match self.handle_exception(error) {
+                                Ok(()) => continue,
+                                Err(e) => return Err(e),
+                            }
+                        }
+                    }
+                }
+                OpCode::SpreadArg => {
+                    let value = self.pop()?;
+                    let named = self.pop()?;
+                    let positional = self.pop()?;
+                    match Self::add_spread_argument(positional, named, value) {
+                        Ok((positional, named)) => {
+                            self.push(positional);
+                            self.push(named);
+                        }
+                        Err(message) => {
+                            let error = self.runtime_error_with_type(message, line, ErrorType::TypeError);
+                            match self.handle_exception(error) {
+                                Ok(()) => continue,
+                                Err(e) => return Err(e),
+                            }
+                        }
+                    }
+                }
+                OpCode::CallSpread => unreachable!("CallSpread заменяется на Call перед выполнением"),
                 OpCode::GetArrayLength => {
                     let array = self.pop()?;
                     match array {
@@ -1069,8 +3267,17 @@ impl Vm {
                         }
                     }
                 }
+                OpCode::LoopProgress(array_slot, index_slot) => {
+                    if self.auto_progress {
+                        self.report_loop_progress(array_slot, index_slot, line);
+                    }
+                }
                 OpCode::GetArrayElement => {
-                    let index_value = self.pop()?;
+                    let index_value = match self.pop()? {
+                        // Целочисленный индекс обрабатываем так же, как числовой
+                        Value::Integer(i) => Value::Number(i as f64),
+                        other => other,
+                    };
                     let container = self.pop()?;
                     
                     match container {
@@ -1120,7 +3327,7 @@ impl Vm {
                             let value = match element {
                                 Value::Array(arr_rc) => Value::Array(Rc::clone(arr_rc)),
                                 Value::Table(table_rc) => Value::Table(Rc::clone(table_rc)),
-                                Value::Object(_) => element.clone(), // Object uses HashMap, clone is needed
+                                Value::Object(_) => element.clone(), // Object uses IndexMap, clone is needed
                                 _ => element.clone(), // Простые типы клонируем
                             };
                             self.push(value);
@@ -1132,7 +3339,7 @@ impl Vm {
                                     let table_ref = table.borrow();
                                     
                                     // Специальные свойства таблицы
-                                    if property == "rows" {
+                                    if property == "rows".into() {
                                         // Возвращаем массив строк (каждая строка - массив значений)
                                         let rows: Vec<Value> = table_ref.rows.iter()
                                             .map(|row| {
@@ -1140,10 +3347,10 @@ impl Vm {
                                             })
                                             .collect();
                                         self.push(Value::Array(Rc::new(RefCell::new(rows))));
-                                    } else if property == "columns" {
+                                    } else if property == "columns".into() {
                                         // Возвращаем массив имен колонок (заголовки)
                                         let columns: Vec<Value> = table_ref.headers.iter()
-                                            .map(|header| Value::String(header.clone()))
+                                            .map(|header| Value::String(header.as_str().into()))
                                             .collect();
                                         self.push(Value::Array(Rc::new(RefCell::new(columns))));
                                     } else {
@@ -1152,7 +3359,7 @@ impl Vm {
                                             // Возвращаем ColumnReference для использования в relate()
                                             self.push(Value::ColumnReference {
                                                 table: table.clone(),
-                                                column_name: property,
+                                                column_name: property.to_string(),
                                             });
                                         } else {
                                             let error = self.runtime_error_with_type(
@@ -1194,8 +3401,7 @@ impl Vm {
                                     }
                                     if let Some(row) = table_ref.get_row(idx as usize) {
                                         // Создаем словарь из строки таблицы
-                                        use std::collections::HashMap;
-                                        let mut row_dict = HashMap::new();
+                                        let mut row_dict = crate::common::value::ObjectMap::new();
                                         for (i, header) in table_ref.headers.iter().enumerate() {
                                             if i < row.len() {
                                                 row_dict.insert(header.clone(), row[i].clone());
@@ -1230,7 +3436,7 @@ impl Vm {
                             // Доступ к значению объекта по строковому ключу
                             match index_value {
                                 Value::String(key) => {
-                                    if let Some(value) = map.get(&key) {
+                                    if let Some(value) = map.get(&*key) {
                                         self.push(value.clone());
                                     } else {
                                         let error = self.runtime_error_with_type(
@@ -1315,7 +3521,7 @@ impl Vm {
                             // Доступ к свойствам Path по строковому ключу
                             match index_value {
                                 Value::String(property_name) => {
-                                    match property_name.as_str() {
+                                    match property_name.as_ref() {
                                         "is_file" => {
                                             self.push(Value::Bool(path.is_file()));
                                         }
@@ -1324,14 +3530,14 @@ impl Vm {
                                         }
                                         "extension" => {
                                             if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                                                self.push(Value::String(ext.to_string()));
+                                                self.push(Value::String(ext.into()));
                                             } else {
                                                 self.push(Value::Null);
                                             }
                                         }
                                         "name" => {
                                             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                                                self.push(Value::String(name.to_string()));
+                                                self.push(Value::String(name.into()));
                                             } else {
                                                 self.push(Value::Null);
                                             }
@@ -1383,6 +3589,27 @@ impl Vm {
                         }
                     }
                 }
+                OpCode::IterSnapshot => {
+                    // Таблица в for перебирается по снимку строк (объекты колонка → значение):
+                    // число итераций фиксируется до начала цикла, тело может менять таблицу
+                    if let Some(Value::Table(table)) = self.stack.last() {
+                        let rows: Vec<Value> = {
+                            let table_ref = table.borrow();
+                            table_ref.rows
+                                .iter()
+                                .map(|row| {
+                                    let mut row_dict = crate::common::value::ObjectMap::new();
+                                    for (header, value) in table_ref.headers.iter().zip(row) {
+                                        row_dict.insert(header.clone(), value.clone());
+                                    }
+                                    Value::Object(row_dict)
+                                })
+                                .collect()
+                        };
+                        self.pop()?;
+                        self.push(Value::Array(Rc::new(RefCell::new(rows))));
+                    }
+                }
                 OpCode::Clone => {
                     // Глубокое клонирование значения на стеке
                     let value = self.pop()?;
@@ -1415,6 +3642,7 @@ impl Vm {
                             else_ip: handler_info.else_ip,
                             stack_height,
                             had_error: false,
+                            caught: None,
                             frame_index,
                         };
                         self.exception_handlers.push(handler);
@@ -1429,6 +3657,7 @@ impl Vm {
                             else_ip: None,
                             stack_height,
                             had_error: false,
+                            caught: None,
                             frame_index,
                         };
                         self.exception_handlers.push(handler);
@@ -1463,11 +3692,8 @@ impl Vm {
                     // Получаем значение со стека (сообщение об ошибке)
                     let error_value = self.pop()?;
                     
-                    // Преобразуем значение в строку
-                    let error_message = error_value.to_string();
-                    
-                    // Создаем LangError
-                    let error = LangError::runtime_error(error_message, line);
+                    // Строка становится сообщением, остальные значения сохраняются в ошибке
+                    let error = LangError::thrown(error_value, line, Vec::new());
                     
                     // Пытаемся найти обработчик исключения
                     match self.handle_exception(error) {
@@ -1481,6 +3707,24 @@ impl Vm {
                         }
                     }
                 }
+                OpCode::Rethrow => {
+                    // Ошибка, которую обрабатывает ближайший catch блок текущего фрейма
+                    let frame_index = self.frames.len() - 1;
+                    let caught = self.exception_handlers.iter()
+                        .rev()
+                        .filter(|handler| handler.frame_index == frame_index)
+                        .find_map(|handler| handler.caught.clone());
+                    let error = match caught {
+                        Some(error) => error,
+                        None => self.runtime_error_with_type(
+                            "No active exception to rethrow".to_string(),
+                            line,
+                            ErrorType::StateError,
+                        ),
+                    };
+                    // Исходная ошибка выбрасывается без изменений (строка и тип сохраняются)
+                    self.handle_exception(error)?;
+                }
                 OpCode::PopExceptionHandler => {
                     // Удаление обработчика исключений со стека
                     self.exception_handlers.pop();
@@ -1516,347 +3760,120 @@ impl Vm {
         ))
     }
 
-    fn binary_add(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 + n2)),
-            (Value::String(s1), Value::String(s2)) => Ok(Value::String(format!("{}{}", s1, s2))),
-            (Value::String(s), Value::Number(n)) => Ok(Value::String(format!("{}{}", s, n))),
-            (Value::Number(n), Value::String(s)) => Ok(Value::String(format!("{}{}", n, s))),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or strings".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
+    /// Проверка принадлежности для оператора in:
+    /// массив - элемент (структурное равенство), строка - подстрока, объект - ключ, таблица - имя колонки
+    fn membership(value: &Value, container: &Value) -> Result<bool, String> {
+        match (container, value) {
+            (Value::Array(arr), _) => Ok(arr.borrow().iter().any(|item| item == value)),
+            (Value::String(haystack), Value::String(needle)) => Ok(haystack.contains(&**needle)),
+            (Value::Object(map), Value::String(key)) => Ok(map.contains_key(&**key)),
+            (Value::Table(table), Value::String(column)) => {
+                Ok(table.borrow().headers.iter().any(|header| **header == **column))
             }
+            (Value::String(_), _) | (Value::Object(_), _) | (Value::Table(_), _) => Err(format!(
+                "Left operand of 'in' must be a string when searching in {}, got {}",
+                natives::native_typeof(std::slice::from_ref(container)).to_string(),
+                natives::native_typeof(std::slice::from_ref(value)).to_string(),
+            )),
+            _ => Err(format!(
+                "Right operand of 'in' must be an array, string, object or table, got {}",
+                natives::native_typeof(std::slice::from_ref(container)).to_string(),
+            )),
         }
     }
 
-    fn binary_sub(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 - n2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Флаг отмены этой VM: его можно передать в другой поток (обработчик Ctrl-C, таймер)
+    /// и выставить там, пока run() выполняется
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
     }
 
-    fn binary_mul(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 * n2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Использовать общий флаг отмены (например, один флаг на все запуски REPL)
+    pub fn set_cancel_handle(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = cancel;
     }
 
-    fn binary_div(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => {
-                if *n2 == 0.0 {
-                    let error = self.runtime_error(
-                        "Division by zero".to_string(),
-                        line,
-                    );
-                    match self.handle_exception(error) {
-                        Ok(()) => {
-                            // Исключение обработано, возвращаем Null как значение после обработки
-                            Ok(Value::Null)
-                        }
-                        Err(e) => Err(e),
-                    }
-                } else {
-                    Ok(Value::Number(n1 / n2))
-                }
-            }
-            // Конкатенация путей: Path / String -> Path
-            (Value::Path(p), Value::String(s)) => {
-                let mut new_path = p.clone();
-                new_path.push(s);
-                Ok(Value::Path(new_path))
-            }
-            // Конкатенация путей: String / String -> Path (если контекст предполагает путь)
-            (Value::String(s1), Value::String(s2)) => {
-                use std::path::PathBuf;
-                let mut path = PathBuf::from(s1);
-                path.push(s2);
-                Ok(Value::Path(path))
-            }
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or paths".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Флаг прерывания пользователем (Ctrl-C) этой VM, см. request_interrupt()
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
     }
 
-    fn binary_int_div(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => {
-                if *n2 == 0.0 {
-                    let error = self.runtime_error(
-                        "Division by zero".to_string(),
-                        line,
-                    );
-                    match self.handle_exception(error) {
-                        Ok(()) => Ok(Value::Null),
-                        Err(e) => Err(e),
-                    }
-                } else {
-                    // Целочисленное деление: отбрасываем дробную часть
-                    Ok(Value::Number((n1 / n2).floor()))
-                }
-            }
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Использовать общий флаг прерывания (например, выставляемый обработчиком SIGINT)
+    pub fn set_interrupt_handle(&mut self, interrupt: Arc<AtomicBool>) {
+        self.interrupt = interrupt;
     }
 
-    fn binary_mod(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => {
-                if *n2 == 0.0 {
-                    let error = self.runtime_error(
-                        "Modulo by zero".to_string(),
-                        line,
-                    );
-                    match self.handle_exception(error) {
-                        Ok(()) => Ok(Value::Null),
-                        Err(e) => Err(e),
-                    }
-                } else {
-                    Ok(Value::Number(n1 % n2))
-                }
-            }
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Прерывание пользователем: на ближайшей проверке VM выбросит ошибку Interrupted
+    /// ("execution interrupted by user"). В отличие от request_cancel() ее можно перехватить
+    /// try/catch; флаг сбрасывается, как только ошибка выброшена
+    pub fn request_interrupt(&self) {
+        self.interrupt.store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
-    fn binary_pow(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1.powf(*n2))),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
+    /// Ошибка Interrupted, если выставлен флаг прерывания (флаг при этом сбрасывается)
+    fn take_interrupt(&self, line: usize) -> Option<LangError> {
+        if !self.interrupt.swap(false, std::sync::atomic::Ordering::SeqCst) {
+            return None;
         }
+        Some(self.runtime_error_with_type(
+            crate::vm::policy::EXECUTION_INTERRUPTED.to_string(),
+            line,
+            ErrorType::Interrupted,
+        ))
     }
 
-    fn binary_greater(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Bool(n1 > n2)),
-            (Value::String(s1), Value::String(s2)) => Ok(Value::Bool(s1 > s2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or strings".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Просит прервать выполнение: VM завершит run() ошибкой "execution cancelled",
+    /// которую нельзя перехватить try/catch. Флаг остается выставленным до clear_cancel()
+    pub fn request_cancel(&self) {
+        self.cancel.store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
-    fn binary_less(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Bool(n1 < n2)),
-            (Value::String(s1), Value::String(s2)) => Ok(Value::Bool(s1 < s2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or strings".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
-        }
+    /// Сбрасывает флаг отмены, чтобы VM можно было запускать снова
+    pub fn clear_cancel(&self) {
+        self.cancel.store(false, std::sync::atomic::Ordering::SeqCst);
     }
 
-    fn binary_greater_equal(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Bool(n1 >= n2)),
-            (Value::String(s1), Value::String(s2)) => Ok(Value::Bool(s1 >= s2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or strings".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
-            }
+    /// Выставлен ли флаг отмены
+    pub fn is_cancel_requested(&self) -> bool {
+        self.cancel.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Получить доступ к глобальным переменным (для экспорта)
+    pub fn get_globals(&self) -> &Vec<Value> {
+        &self.globals
+    }
+
+    /// Задать значение глобальной переменной по индексу до запуска (например, `_` в REPL)
+    pub fn set_global(&mut self, index: usize, value: Value) {
+        if index >= self.globals.len() {
+            self.globals.resize(index + 1, Value::Null);
         }
+        self.globals[index] = value;
     }
 
-    fn binary_less_equal(&mut self, a: &Value, b: &Value) -> Result<Value, LangError> {
-        let line = if let Some(frame) = self.frames.last() {
-            if frame.ip > 0 {
-                frame.function.chunk.get_line(frame.ip - 1)
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-        match (a, b) {
-            (Value::Number(n1), Value::Number(n2)) => Ok(Value::Bool(n1 <= n2)),
-            (Value::String(s1), Value::String(s2)) => Ok(Value::Bool(s1 <= s2)),
-            _ => {
-                let error = self.runtime_error(
-                    "Operands must be numbers or strings".to_string(),
-                    line,
-                );
-                match self.handle_exception(error) {
-                    Ok(()) => Ok(Value::Null),
-                    Err(e) => Err(e),
-                }
+    /// Значение глобальной переменной по имени
+    pub fn get_global_by_name(&self, name: &str) -> Option<&Value> {
+        self.global_indices.get(name).and_then(|index| self.globals.get(*index))
+    }
+
+    /// Задать глобальную переменную по имени. Новое имя получает следующий свободный индекс
+    /// и видно коду, выполняемому после (run_continued)
+    pub fn set_global_by_name(&mut self, name: &str, value: Value) {
+        if self.global_indices.is_empty() {
+            // До первого запуска таблица имен пуста: встроенные функции занимают первые индексы
+            if let Ok(chunk) = crate::compiler::Compiler::new().compile(&[]) {
+                self.global_indices = chunk.global_indices;
             }
         }
+        let next_index = self.global_indices.len();
+        let index = *self.global_indices.entry(name.to_string()).or_insert(next_index);
+        self.global_names.insert(index, name.to_string());
+        self.set_global(index, value);
     }
 
-    /// Получить доступ к глобальным переменным (для экспорта)
-    pub fn get_globals(&self) -> &Vec<Value> {
-        &self.globals
+    /// Получить доступ к скомпилированным функциям
+    pub fn get_functions(&self) -> &Vec<crate::bytecode::Function> {
+        &self.functions
     }
 
     /// Получить доступ к именам глобальных переменных
@@ -1888,5 +3905,67 @@ impl Vm {
     pub fn get_explicit_primary_keys(&self) -> &Vec<ExplicitPrimaryKey> {
         &self.explicit_primary_keys
     }
+
+    /// Объявления экспорта из declare_export() в порядке вызовов
+    pub fn get_export_declarations(&self) -> &Vec<ExportDeclaration> {
+        &self.export_declarations
+    }
+
+    /// Имя глобальной переменной, в которой хранится таблица (объявленные через global важнее)
+    fn global_table_name(&self, table_ptr: *const RefCell<crate::common::table::Table>) -> Option<String> {
+        let index = self.globals.iter().enumerate().position(|(index, value)| {
+            matches!(value, Value::Table(table) if Rc::as_ptr(table) == table_ptr) && self.explicit_global_names.contains_key(&index)
+        }).or_else(|| {
+            self.globals.iter().position(|value| matches!(value, Value::Table(table) if Rc::as_ptr(table) == table_ptr))
+        })?;
+        self.explicit_global_names.get(&index).or_else(|| self.global_names.get(&index)).cloned()
+    }
+
+    /// Сохраняет объявление declare_export(). Имя переменной ищется среди глобальных
+    /// переменных; оно же - имя таблицы SQLite, если name не указан.
+    /// Повторное объявление с тем же именем таблицы заменяет прежнее
+    fn add_export_declaration(&mut self, table: Value, name: Option<String>, primary_key: Option<String>, line: usize) -> Result<(), LangError> {
+        let variable_name = self.globals.iter().enumerate().find_map(|(index, value)| {
+            let same = match (value, &table) {
+                (Value::Table(a), Value::Table(b)) => Rc::ptr_eq(a, b),
+                (Value::LazyTable(a), Value::LazyTable(b)) => Rc::ptr_eq(a, b),
+                _ => false,
+            };
+            if !same {
+                return None;
+            }
+            self.explicit_global_names.get(&index).or_else(|| self.global_names.get(&index)).cloned()
+        });
+        let table_name = match name.or_else(|| variable_name.clone()) {
+            Some(name) => name,
+            None => {
+                return Err(self.runtime_error_with_type(
+                    "declare_export(): name is required for a table that is not stored in a global variable".to_string(),
+                    line,
+                    ErrorType::ValueError,
+                ));
+            }
+        };
+        self.export_declarations.retain(|d| d.table_name != table_name);
+        self.export_declarations.push(ExportDeclaration { variable_name, table_name, table, primary_key });
+        Ok(())
+    }
+}
+
+/// Значение настройки undefined_vars: "null" → true, "error" → false
+fn parse_undefined_vars_mode(mode: &str) -> Option<bool> {
+    match mode {
+        "null" => Some(true),
+        "error" => Some(false),
+        _ => None,
+    }
 }
 
+/// Тот же массив или таблица (например, push() с присваиванием результата обратно)
+fn same_container(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => Rc::ptr_eq(a, b),
+        (Value::Table(a), Value::Table(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
@@ -0,0 +1,160 @@
+// Режим наблюдения (datacode --watch main.dc) и кэш cached_eval()
+//
+// Наблюдатель опрашивает время изменения файлов: при изменении текущее выполнение
+// отменяется через флаг отмены VM, и скрипт запускается заново. cached_eval(key, fn)
+// хранит результат fn() до конца процесса; вместе с ним запоминаются времена изменения
+// файлов, прочитанных внутри fn (read_file, read_lines, list_files, ...). Пока ни один
+// из них не изменился, повторный запуск получает результат из кэша без чтения файлов
+
+use crate::common::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Интервал опроса файлов наблюдателем
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Время изменения файла (None - файл недоступен)
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Файлы и времена их изменения на момент вычисления
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStamps(Vec<(PathBuf, Option<SystemTime>)>);
+
+impl FileStamps {
+    pub fn capture(paths: &[PathBuf]) -> Self {
+        let mut stamps: Vec<(PathBuf, Option<SystemTime>)> = Vec::with_capacity(paths.len());
+        for path in paths {
+            if !stamps.iter().any(|(known, _)| known == path) {
+                stamps.push((path.clone(), modified_time(path)));
+            }
+        }
+        FileStamps(stamps)
+    }
+
+    /// Ни один файл не изменился, не появился и не был удален
+    pub fn unchanged(&self) -> bool {
+        self.0.iter().all(|(path, time)| modified_time(path) == *time)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.0.iter().map(|(path, _)| path.as_path())
+    }
+}
+
+struct CacheEntry {
+    value: Value,
+    stamps: FileStamps,
+}
+
+thread_local! {
+    // Кэш cached_eval() живет до конца процесса: VM каждого перезапуска создается заново,
+    // а Value содержит Rc, поэтому кэш принадлежит потоку, выполняющему скрипты
+    static EVAL_CACHE: RefCell<HashMap<String, CacheEntry>> = RefCell::new(HashMap::new());
+    // Файлы, прочитанные внутри выполняющихся cached_eval() (по списку на уровень вложенности)
+    static READ_TRACKER: RefCell<Vec<Vec<PathBuf>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Результат из кэша, если прочитанные при его вычислении файлы не изменились.
+/// Устаревшая запись удаляется
+pub fn cache_lookup(key: &str) -> Option<Value> {
+    EVAL_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.get(key)?.stamps.unchanged() {
+            let entry = cache.get(key)?;
+            // Прочитанные файлы - тоже файлы этого вычисления для внешнего cached_eval()
+            record_reads(entry.stamps.paths());
+            detach(&entry.value)
+        } else {
+            cache.remove(key);
+            None
+        }
+    })
+}
+
+/// Сохраняет результат; false, если значение нельзя хранить между запусками
+/// (функции, отложенные таблицы - они ссылаются на код и состояние своей VM)
+pub fn cache_store(key: &str, value: &Value, stamps: FileStamps) -> bool {
+    match detach(value) {
+        Some(value) => {
+            EVAL_CACHE.with(|cache| cache.borrow_mut().insert(key.to_string(), CacheEntry { value, stamps }));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Очищает кэш cached_eval(); возвращает число удаленных записей
+pub fn cache_clear() -> usize {
+    EVAL_CACHE.with(|cache| cache.borrow_mut().drain().count())
+}
+
+pub fn cache_len() -> usize {
+    EVAL_CACHE.with(|cache| cache.borrow().len())
+}
+
+/// Начало вычисления cached_eval(): с этого момента учитываются прочитанные файлы
+pub fn begin_tracking() {
+    READ_TRACKER.with(|tracker| tracker.borrow_mut().push(Vec::new()));
+}
+
+/// Конец вычисления: файлы, прочитанные с парного begin_tracking(), и их времена изменения.
+/// Файлы вложенного вычисления остаются и в списке внешнего
+pub fn end_tracking() -> FileStamps {
+    let paths = READ_TRACKER.with(|tracker| tracker.borrow_mut().pop()).unwrap_or_default();
+    record_reads(paths.iter().map(PathBuf::as_path));
+    FileStamps::capture(&paths)
+}
+
+/// Встроенные функции отмечают чтение файла (вне cached_eval() ничего не делает)
+pub fn record_read(path: &Path) {
+    record_reads(std::iter::once(path));
+}
+
+fn record_reads<'a>(paths: impl Iterator<Item = &'a Path>) {
+    READ_TRACKER.with(|tracker| {
+        if let Some(current) = tracker.borrow_mut().last_mut() {
+            current.extend(paths.map(Path::to_path_buf));
+        }
+    });
+}
+
+/// Копия значения без общих с программой массивов, объектов и таблиц: изменение результата
+/// скриптом не меняет кэш. None для значений, которые нельзя переносить между запусками
+fn detach(value: &Value) -> Option<Value> {
+    Some(match value {
+        Value::Array(items) => {
+            let items: Option<Vec<Value>> = items.borrow().iter().map(detach).collect();
+            Value::Array(Rc::new(RefCell::new(items?)))
+        }
+        Value::Object(map) => {
+            let mut copy = crate::common::value::ObjectMap::with_capacity(map.len());
+            for (key, item) in map {
+                copy.insert(key.clone(), detach(item)?);
+            }
+            Value::Object(copy)
+        }
+        Value::Table(table) => Value::Table(Rc::new(RefCell::new(table.borrow().clone()))),
+        Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_) | Value::LazyTable(_) | Value::ColumnReference { .. } => return None,
+        other => other.clone(),
+    })
+}
+
+/// Опрашивает файлы каждые POLL_INTERVAL и выставляет changed, когда время изменения
+/// одного из них отличается от исходного; после этого поток завершается. --watch передает
+/// сюда флаг отмены VM, поэтому изменение во время выполнения останавливает запуск
+pub fn spawn_poller(paths: Vec<PathBuf>, changed: Arc<AtomicBool>) -> std::thread::JoinHandle<()> {
+    let stamps = FileStamps::capture(&paths);
+    std::thread::spawn(move || {
+        while stamps.unchanged() {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        changed.store(true, Ordering::SeqCst);
+    })
+}
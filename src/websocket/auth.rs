@@ -0,0 +1,185 @@
+// Аутентификация клиентов WebSocket сервера: токены и профили разрешений подключений
+
+use super::AUTH_TOKEN_ENV;
+use crate::vm::policy::ExecutionPolicy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Время после подключения, за которое клиент должен пройти аутентификацию
+pub const DEFAULT_AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Разрешения подключения, которые выдает токен. Не указанные в файле поля разрешены
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PermissionProfile {
+    pub allow_file_read: bool,
+    pub allow_file_write: bool,
+    pub allow_smb: bool,
+    pub allow_dynamic_eval: bool,
+    /// Лимит суммарного размера файлов папки сессии (--use-ve) в мегабайтах
+    pub max_session_disk_mb: Option<f64>,
+}
+
+impl Default for PermissionProfile {
+    fn default() -> Self {
+        Self {
+            allow_file_read: true,
+            allow_file_write: true,
+            allow_smb: true,
+            allow_dynamic_eval: true,
+            max_session_disk_mb: None,
+        }
+    }
+}
+
+impl PermissionProfile {
+    /// Политика подключения: политика сервера (таймаут, лимит памяти) с ограничениями профиля.
+    /// Профиль только сужает права: запрещенное сервером остается запрещенным
+    pub fn apply(&self, policy: ExecutionPolicy) -> ExecutionPolicy {
+        let disk_limit = self.max_session_disk_mb.map(|mb| (mb * 1024.0 * 1024.0) as u64);
+        ExecutionPolicy {
+            allow_file_read: policy.allow_file_read && self.allow_file_read,
+            allow_file_write: policy.allow_file_write && self.allow_file_write,
+            allow_smb: policy.allow_smb && self.allow_smb,
+            allow_dynamic_eval: policy.allow_dynamic_eval && self.allow_dynamic_eval,
+            max_session_disk_bytes: match (policy.max_session_disk_bytes, disk_limit) {
+                (Some(server), Some(profile)) => Some(server.min(profile)),
+                (server, profile) => server.or(profile),
+            },
+            ..policy
+        }
+    }
+}
+
+/// Формат файла --auth-file: {"tokens": {"<токен>": {<профиль>}, ...}}
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AuthFile {
+    tokens: HashMap<String, PermissionProfile>,
+}
+
+/// Настройки аутентификации сервера. Без токенов сервер открыт, как раньше
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    tokens: Vec<(String, PermissionProfile)>,
+    /// Время на аутентификацию после подключения
+    pub timeout: Duration,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            tokens: Vec::new(),
+            timeout: DEFAULT_AUTH_TIMEOUT,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Один токен с полными правами
+    pub fn with_token(token: &str) -> Self {
+        let mut config = Self::default();
+        config.add_token(token, PermissionProfile::default());
+        config
+    }
+
+    /// Токен из DATACODE_WS_TOKEN (пустое значение равносильно отсутствию)
+    pub fn from_env() -> Self {
+        match env::var(AUTH_TOKEN_ENV) {
+            Ok(token) => Self::with_token(&token),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Токены и профили разрешений из JSON файла --auth-file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("не удалось прочитать {}: {}", path.display(), e))?;
+        let file: AuthFile = serde_json::from_str(&text)
+            .map_err(|e| format!("некорректный файл {}: {}", path.display(), e))?;
+        let mut tokens: Vec<_> = file.tokens.into_iter().collect();
+        // Порядок HashMap случаен; сортировка делает конфигурацию воспроизводимой
+        tokens.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut config = Self::default();
+        for (token, profile) in tokens {
+            if token.is_empty() {
+                return Err(format!("{}: пустой токен", path.display()));
+            }
+            if profile.max_session_disk_mb.is_some_and(|mb| !(mb > 0.0 && mb.is_finite())) {
+                return Err(format!("{}: max_session_disk_mb должен быть положительным числом", path.display()));
+            }
+            config.add_token(&token, profile);
+        }
+        Ok(config)
+    }
+
+    /// Добавляет токен (пустой токен игнорируется); повторный токен заменяет профиль
+    pub fn add_token(&mut self, token: &str, profile: PermissionProfile) {
+        if token.is_empty() {
+            return;
+        }
+        self.tokens.retain(|(known, _)| known != token);
+        self.tokens.push((token.to_string(), profile));
+    }
+
+    /// Требуется ли аутентификация
+    pub fn is_required(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Профиль подключения с этим токеном. Проверяются все токены, сравнение каждого
+    /// не зависит от позиции первого различия
+    pub fn authenticate(&self, token: &str) -> Option<&PermissionProfile> {
+        self.tokens
+            .iter()
+            .fold(None, |found, (known, profile)| if tokens_match(known, token) { Some(profile) } else { found })
+    }
+}
+
+/// Сравнение токенов за время, не зависящее от позиции первого различия
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected.bytes().zip(given.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Значение параметра token из строки запроса адреса подключения (ws://host:port/?token=...)
+pub fn token_from_query(query: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == "token")
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// Декодирует %XX и '+' в значении параметра; некорректные последовательности остаются как есть
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,22 +1,41 @@
-use crate::run;
+use crate::run_with_vm;
+use crate::common::value::{table_rows_to_json, Value};
+use crate::common::table::Table;
+use crate::common::error::{ErrorType, LangError};
+use crate::parser::ParserLimits;
+use crate::vm::Vm;
+use crate::vm::policy::{get_execution_policy, is_timeout_error, set_execution_policy, ExecutionPolicy, EXECUTION_TIMED_OUT};
 use serde::{Deserialize, Serialize};
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
 use futures_util::{SinkExt, StreamExt};
 use tokio::net::{TcpListener, TcpStream};
 use std::sync::{Arc, Mutex};
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
+use std::time::Duration;
 
-pub mod output_capture;
+pub mod auth;
 pub mod smb;
 
-use output_capture::OutputCapture;
+// Перехват вывода перенесен в common (нужен и при встраивании через Session); путь сохранен
+pub use crate::common::output_capture;
+
+pub use auth::{AuthConfig, PermissionProfile};
+use auth::token_from_query;
+use crate::common::output_capture::OutputCapture;
 use smb::{SmbManager, SmbConnection};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 enum WebSocketRequest {
+    #[serde(rename = "auth")]
+    Auth { token: String },
     #[serde(rename = "execute")]
     Execute { code: String },
     #[serde(rename = "smb_connect")]
@@ -37,11 +56,49 @@ enum WebSocketRequest {
         share_name: String,
         file_path: String,
     },
+    #[serde(rename = "smb_list_connections")]
+    SmbListConnections,
+    #[serde(rename = "smb_write_file")]
+    SmbWriteFile {
+        share_name: String,
+        file_path: String,
+        content: String,
+    },
     #[serde(rename = "upload_file")]
     UploadFile {
         filename: String,
         content: String,
     },
+    #[serde(rename = "inspect")]
+    Inspect,
+    #[serde(rename = "preview_table")]
+    PreviewTable {
+        name: String,
+        #[serde(default = "default_preview_limit")]
+        limit: usize,
+        #[serde(default)]
+        offset: usize,
+        /// "grid", "markdown" или "csv": страница дополнительно возвращается текстом в поле text
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+fn default_preview_limit() -> usize {
+    50
+}
+
+/// Содержимое файла из запроса: текст как есть, бинарные данные с префиксом "base64:"
+/// (тот же формат, что возвращает smb_read_file)
+fn decode_file_content(content: &str) -> Result<Vec<u8>, String> {
+    match content.strip_prefix("base64:") {
+        Some(encoded) => {
+            use base64::Engine;
+            base64::engine::general_purpose::STANDARD.decode(encoded)
+                .map_err(|e| format!("Ошибка декодирования base64: {}", e))
+        }
+        None => Ok(content.as_bytes().to_vec()),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,7 +110,34 @@ struct ExecuteRequest {
 struct ExecuteResponse {
     success: bool,
     output: String,
+    /// Текст ошибки, либо сам объект из throw {...} со всеми его полями
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AuthResponse {
+    success: bool,
     error: Option<String>,
+    /// "AuthenticationError" при отказе
+    error_type: Option<String>,
+}
+
+impl AuthResponse {
+    fn accepted() -> Self {
+        Self { success: true, error: None, error_type: None }
+    }
+
+    fn rejected(error: String) -> Self {
+        Self { success: false, error: Some(error), error_type: Some("AuthenticationError".to_string()) }
+    }
+}
+
+/// Ответ на запрос, запрещенный профилем разрешений подключения
+#[derive(Debug, Serialize, Deserialize)]
+struct PermissionDeniedResponse {
+    success: bool,
+    error: String,
+    error_type: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -75,6 +159,21 @@ struct SmbReadFileResponse {
     success: bool,
     content: Option<String>,
     error: Option<String>,
+    /// "ConnectionError", "FileNotFoundError", "PermissionError" или "IOError" при ошибке
+    error_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SmbListConnectionsResponse {
+    success: bool,
+    connections: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SmbWriteFileResponse {
+    success: bool,
+    bytes_written: usize,
+    error: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -84,11 +183,92 @@ struct UploadFileResponse {
     error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct VariableInfo {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    preview: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FunctionInfo {
+    name: String,
+    params: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TableInfo {
+    name: String,
+    rows: usize,
+    columns: usize,
+    column_names: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InspectResponse {
+    success: bool,
+    globals: Vec<VariableInfo>,
+    functions: Vec<FunctionInfo>,
+    tables: Vec<TableInfo>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PreviewTableResponse {
+    success: bool,
+    name: String,
+    total_rows: usize,
+    columns: Vec<String>,
+    rows: Vec<serde_json::Value>,
+    /// Страница, выведенная как show_table() в запрошенном формате
+    text: Option<String>,
+    error: Option<String>,
+    error_type: Option<String>,
+}
+
+/// Максимальная длина строки предпросмотра значения в ответе inspect
+const INSPECT_PREVIEW_CHARS: usize = 80;
+
+/// Переменная окружения с токеном: если задана, клиент должен сначала отправить
+/// {"type": "auth", "token": "..."}
+pub const AUTH_TOKEN_ENV: &str = "DATACODE_WS_TOKEN";
+
+/// Число неудачных попыток аутентификации, после которого соединение закрывается
+pub const MAX_AUTH_ATTEMPTS: usize = 3;
+
+/// Переменная окружения с таймаутом выполнения одного запроса в секундах (0 - без таймаута)
+pub const EXECUTION_TIMEOUT_ENV: &str = "DATACODE_WS_TIMEOUT";
+
+/// Таймаут выполнения по умолчанию
+pub const DEFAULT_EXECUTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Таймаут из DATACODE_WS_TIMEOUT, при отсутствии или ошибке разбора - DEFAULT_EXECUTION_TIMEOUT
+fn execution_timeout_from_env() -> Option<Duration> {
+    let value = match env::var(EXECUTION_TIMEOUT_ENV) {
+        Ok(value) => value,
+        Err(_) => return Some(DEFAULT_EXECUTION_TIMEOUT),
+    };
+    match value.trim().parse::<f64>() {
+        Ok(0.0) => None,
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Some(Duration::from_secs_f64(seconds)),
+        _ => {
+            eprintln!(
+                "⚠️  Некорректное значение {}={}, используется {} с",
+                EXECUTION_TIMEOUT_ENV,
+                value,
+                DEFAULT_EXECUTION_TIMEOUT.as_secs()
+            );
+            Some(DEFAULT_EXECUTION_TIMEOUT)
+        }
+    }
+}
+
 // Thread-local storage для хранения пути к папке пользователя
 thread_local! {
     static USER_SESSION_PATH: std::cell::RefCell<Option<PathBuf>> = std::cell::RefCell::new(None);
     static USE_VE_FLAG: std::cell::RefCell<bool> = std::cell::RefCell::new(false);
-    static NATIVE_ERROR: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+    static NATIVE_ERROR: std::cell::RefCell<Option<(String, ErrorType)>> = std::cell::RefCell::new(None);
 }
 
 pub fn set_user_session_path(path: Option<PathBuf>) {
@@ -107,18 +287,124 @@ pub fn get_use_ve() -> bool {
     USE_VE_FLAG.with(|f| *f.borrow())
 }
 
+/// Проверяет, что путь остается внутри папки сессии (режим --use-ve).
+/// Относительные пути разрешаются от папки сессии. Сравниваются канонические пути,
+/// поэтому выход наружу через '..' (в том числе '..\' на Windows) и символические ссылки
+/// обнаруживается независимо от вида разделителей
+pub fn confine_to_session(path: &Path) -> Result<PathBuf, String> {
+    use crate::common::path::canonicalize_lenient;
+
+    let session_path = get_user_session_path().ok_or_else(|| "Session path not available".to_string())?;
+    let session_canonical = canonicalize_lenient(&session_path);
+    let candidate = if path.as_os_str().is_empty() {
+        session_canonical.clone()
+    } else if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        session_canonical.join(path)
+    };
+    let resolved = canonicalize_lenient(&candidate);
+    if resolved.starts_with(&session_canonical) {
+        Ok(resolved)
+    } else {
+        Err("Access outside session directory not allowed".to_string())
+    }
+}
+
+/// Проверяет лимит ExecutionPolicy::max_session_disk_bytes: файл target в папке сессии
+/// будет иметь размер new_size. Прежнее содержимое target при подсчете заменяется новым.
+/// Без папки сессии (сервер без --use-ve) лимит не применяется
+pub fn check_session_disk_quota(limit: Option<u64>, target: &Path, new_size: u64) -> Result<(), String> {
+    let (limit, session_path) = match (limit, get_user_session_path()) {
+        (Some(limit), Some(session_path)) => (limit, session_path),
+        _ => return Ok(()),
+    };
+    let old_size = fs::metadata(target).map(|m| if m.is_file() { m.len() } else { 0 }).unwrap_or(0);
+    let used = directory_size(&session_path).saturating_sub(old_size).saturating_add(new_size);
+    if used > limit {
+        Err(format!(
+            "session disk quota exceeded ({:.2} of {:.2} MB)",
+            used as f64 / (1024.0 * 1024.0),
+            limit as f64 / (1024.0 * 1024.0)
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Суммарный размер файлов папки (рекурсивно; символические ссылки не раскрываются)
+fn directory_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => directory_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
 pub fn set_native_error(msg: String) {
-    NATIVE_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+    set_native_error_with_type(msg, ErrorType::IOError);
 }
 
-pub fn take_native_error() -> Option<String> {
+/// Сохраняет ошибку нативной функции с явным типом (для typed catch)
+pub fn set_native_error_with_type(msg: String, error_type: ErrorType) {
+    NATIVE_ERROR.with(|e| *e.borrow_mut() = Some((msg, error_type)));
+}
+
+pub fn take_native_error() -> Option<(String, ErrorType)> {
     NATIVE_ERROR.with(|e| e.borrow_mut().take())
 }
 
 /// Запустить WebSocket сервер на указанном адресе
 pub async fn start_server(address: &str, use_ve: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    start_server_with_limits(address, use_ve, ParserLimits::server()).await
+}
+
+/// Запустить WebSocket сервер с заданными ограничениями парсера для кода клиентов.
+/// Токен аутентификации берется из переменной окружения DATACODE_WS_TOKEN
+pub async fn start_server_with_limits(
+    address: &str,
+    use_ve: bool,
+    limits: ParserLimits,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    start_server_with_auth(address, use_ve, limits, AuthConfig::from_env()).await
+}
+
+/// Запустить WebSocket сервер; если в auth заданы токены, запросы выполняются только
+/// после успешного {"type": "auth", "token": "..."} (или с ?token=... в адресе подключения),
+/// а политика подключения сужается профилем разрешений его токена
+pub async fn start_server_with_auth(
+    address: &str,
+    use_ve: bool,
+    limits: ParserLimits,
+    auth: AuthConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Все подключения обрабатываются в LocalSet текущего потока
+    crate::parser::set_parser_limits(limits);
+    // Таймаут выполнения запроса: заданный встраивающим кодом в политике потока,
+    // иначе DATACODE_WS_TIMEOUT или 30 секунд
+    let mut policy = get_execution_policy();
+    if policy.timeout.is_none() {
+        policy.timeout = execution_timeout_from_env();
+        set_execution_policy(policy.clone());
+    }
     let listener = TcpListener::bind(address).await?;
     println!("🚀 DataCode WebSocket Server запущен на {}", address);
+    if let Some(timeout) = policy.timeout {
+        println!("⏱️  Таймаут выполнения запроса: {:.1} с", timeout.as_secs_f64());
+    }
+    if auth.is_required() {
+        println!(
+            "🔒 Требуется аутентификация: первым сообщением {{\"type\": \"auth\", \"token\": \"...\"}} в течение {:.1} с",
+            auth.timeout.as_secs_f64()
+        );
+    }
     println!("📡 Ожидание подключений...");
     println!("💡 Отправьте JSON запрос: {{\"code\": \"ваш код\"}}");
     println!("💡 Ответ будет в формате: {{\"success\": true/false, \"output\": \"...\", \"error\": null/\"...\"}}");
@@ -151,7 +437,7 @@ pub async fn start_server(address: &str, use_ve: bool) -> Result<(), Box<dyn std
             };
             
             println!("✅ Новое подключение от {}", addr);
-            local_set.spawn_local(handle_client(stream, use_ve));
+            local_set.spawn_local(handle_client(stream, use_ve, auth.clone()));
         }
     }).await;
 
@@ -159,8 +445,28 @@ pub async fn start_server(address: &str, use_ve: bool) -> Result<(), Box<dyn std
 }
 
 /// Обработать клиентское подключение
-async fn handle_client(stream: TcpStream, use_ve: bool) {
-    let ws_stream = match accept_async(stream).await {
+async fn handle_client(stream: TcpStream, use_ve: bool, auth: AuthConfig) {
+    // Токен можно передать в адресе подключения (ws://host:port/?token=...): верный токен
+    // аутентифицирует подключение сразу, неверный отклоняет его ответом 401 до установки WebSocket
+    let mut query_profile: Option<PermissionProfile> = None;
+    let check_query_token = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let token = match request.uri().query().and_then(token_from_query) {
+            Some(token) if auth.is_required() => token,
+            _ => return Ok(response),
+        };
+        match auth.authenticate(&token) {
+            Some(profile) => {
+                query_profile = Some(profile.clone());
+                Ok(response)
+            }
+            None => {
+                let mut rejection = ErrorResponse::new(Some("Неверный токен".to_string()));
+                *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(rejection)
+            }
+        }
+    };
+    let ws_stream = match accept_hdr_async(stream, check_query_token).await {
         Ok(ws) => ws,
         Err(e) => {
             eprintln!("❌ Ошибка при принятии WebSocket соединения: {}", e);
@@ -189,23 +495,19 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
         let user_id = format!("user_{}", timestamp);
         let user_dir = Path::new("src/temp_sessions").join(&user_id);
         
-        // Преобразуем в абсолютный путь для корректной работы с путями от list_files
-        let user_dir_absolute = match user_dir.canonicalize() {
-            Ok(p) => p,
-            Err(_) => {
-                // Если канонизация не удалась (папка еще не существует), 
-                // создаем абсолютный путь через current_dir
-                match env::current_dir() {
-                    Ok(cwd) => cwd.join(&user_dir),
-                    Err(_) => user_dir, // Fallback к относительному пути
-                }
-            },
-        };
-        
-        if let Err(e) = fs::create_dir_all(&user_dir_absolute) {
+        if let Err(e) = fs::create_dir_all(&user_dir) {
             eprintln!("❌ Ошибка создания папки пользователя: {}", e);
             None
         } else {
+            // Канонический абсолютный путь: с ним сравниваются пути в confine_to_session
+            // и пути, которые возвращает list_files
+            let user_dir_absolute = match user_dir.canonicalize() {
+                Ok(p) => p,
+                Err(_) => match env::current_dir() {
+                    Ok(cwd) => cwd.join(&user_dir),
+                    Err(_) => user_dir, // Fallback к относительному пути
+                },
+            };
             println!("📁 Создана папка пользователя: {}", user_dir_absolute.display());
             Some(user_dir_absolute)
         }
@@ -216,15 +518,116 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
     // Устанавливаем путь к папке пользователя в thread-local storage
     set_user_session_path(user_session_path.clone());
 
-    while let Some(msg) = read.next().await {
+    // Состояние сессии: VM последнего успешного выполнения (для inspect и preview_table)
+    let mut session_vm: Option<Vm> = None;
+
+    // Политика подключения появляется после аутентификации; без токенов сервер открыт, как раньше
+    let mut connection_policy = match query_profile {
+        Some(profile) => Some(profile.apply(get_execution_policy())),
+        None if !auth.is_required() => Some(get_execution_policy()),
+        None => None,
+    };
+    let auth_deadline = tokio::time::Instant::now() + auth.timeout;
+    let mut failed_auth_attempts = 0;
+
+    loop {
+        let msg = if connection_policy.is_some() {
+            read.next().await
+        } else {
+            match tokio::time::timeout_at(auth_deadline, read.next()).await {
+                Ok(msg) => msg,
+                Err(_) => {
+                    // Клиент не прошел аутентификацию вовремя
+                    let response = AuthResponse::rejected(
+                        "Время ожидания аутентификации истекло, соединение закрыто".to_string(),
+                    );
+                    if let Ok(json) = serde_json::to_string(&response) {
+                        let _ = write.send(Message::Text(json)).await;
+                    }
+                    println!("🔒 Клиент не прошел аутентификацию вовремя, соединение закрыто");
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                }
+            }
+        };
+        let msg = match msg {
+            Some(msg) => msg,
+            None => break,
+        };
         match msg {
+            Ok(Message::Text(text)) if connection_policy.is_none() => {
+                // До аутентификации принимается только запрос auth, остальные отклоняются
+                let profile = match serde_json::from_str::<WebSocketRequest>(&text) {
+                    Ok(WebSocketRequest::Auth { token }) => auth.authenticate(&token).cloned().ok_or(true),
+                    _ => Err(false),
+                };
+                let response = match profile {
+                    Ok(profile) => {
+                        connection_policy = Some(profile.apply(get_execution_policy()));
+                        AuthResponse::accepted()
+                    }
+                    Err(wrong_token) => {
+                        failed_auth_attempts += 1;
+                        let reason = if wrong_token {
+                            "Неверный токен"
+                        } else {
+                            "Требуется аутентификация: отправьте {\"type\": \"auth\", \"token\": \"...\"}"
+                        };
+                        let remaining = MAX_AUTH_ATTEMPTS.saturating_sub(failed_auth_attempts);
+                        let error = if remaining == 0 {
+                            format!("{}. Превышено число попыток аутентификации, соединение закрыто", reason)
+                        } else {
+                            format!("{}. Осталось попыток: {}", reason, remaining)
+                        };
+                        AuthResponse::rejected(error)
+                    }
+                };
+
+                if let Ok(json) = serde_json::to_string(&response) {
+                    if let Err(e) = write.send(Message::Text(json)).await {
+                        eprintln!("❌ Ошибка отправки ответа: {}", e);
+                        break;
+                    }
+                }
+                if failed_auth_attempts >= MAX_AUTH_ATTEMPTS {
+                    println!("🔒 Клиент не прошел аутентификацию, соединение закрыто");
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                }
+            }
             Ok(Message::Text(text)) => {
+                let policy = connection_policy.clone().unwrap_or_default();
                 // Пытаемся распарсить как новый формат с типом команды
                 if let Ok(request) = serde_json::from_str::<WebSocketRequest>(&text) {
+                    if let Some(error) = request_denial(&request, &policy) {
+                        let response = PermissionDeniedResponse {
+                            success: false,
+                            error: error.to_string(),
+                            error_type: "PermissionError".to_string(),
+                        };
+                        if let Ok(json) = serde_json::to_string(&response) {
+                            if let Err(e) = write.send(Message::Text(json)).await {
+                                eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                break;
+                            }
+                        }
+                        continue;
+                    }
                     match request {
+                        WebSocketRequest::Auth { .. } => {
+                            // Клиент уже аутентифицирован (или токен не требуется)
+                            let response = AuthResponse::accepted();
+
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                if let Err(e) = write.send(Message::Text(json)).await {
+                                    eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                    break;
+                                }
+                            }
+                        }
                         WebSocketRequest::Execute { code } => {
                             // Выполняем код
-                            let response = execute_code(&code, &smb_manager);
+                            let response = execute_code(&code, &smb_manager, &policy, &mut session_vm);
                             
                             // Отправляем ответ
                             if let Ok(json) = serde_json::to_string(&response) {
@@ -292,6 +695,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                             success: true,
                                             content: Some(text),
                                             error: None,
+                                            error_type: None,
                                         },
                                         Err(_) => {
                                             // Если не UTF-8, возвращаем base64
@@ -301,6 +705,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                                 success: true,
                                                 content: Some(format!("base64:{}", base64_content)),
                                                 error: None,
+                                                error_type: None,
                                             }
                                         }
                                     }
@@ -308,6 +713,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                 Err(e) => SmbReadFileResponse {
                                     success: false,
                                     content: None,
+                                    error_type: Some(smb::error_type(&e).name().to_string()),
                                     error: Some(e),
                                 },
                             };
@@ -319,6 +725,45 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                 }
                             }
                         }
+                        WebSocketRequest::SmbListConnections => {
+                            let response = SmbListConnectionsResponse {
+                                success: true,
+                                connections: smb_manager.lock().unwrap().list_connections(),
+                            };
+
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                if let Err(e) = write.send(Message::Text(json)).await {
+                                    eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        WebSocketRequest::SmbWriteFile { share_name, file_path, content } => {
+                            let result = decode_file_content(&content).and_then(|bytes| {
+                                smb_manager.lock().unwrap().write_file(&share_name, &file_path, &bytes, false)?;
+                                Ok(bytes.len())
+                            });
+
+                            let response = match result {
+                                Ok(bytes_written) => SmbWriteFileResponse {
+                                    success: true,
+                                    bytes_written,
+                                    error: None,
+                                },
+                                Err(e) => SmbWriteFileResponse {
+                                    success: false,
+                                    bytes_written: 0,
+                                    error: Some(e),
+                                },
+                            };
+
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                if let Err(e) = write.send(Message::Text(json)).await {
+                                    eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                    break;
+                                }
+                            }
+                        }
                         WebSocketRequest::UploadFile { filename, content } => {
                             let response = if use_ve {
                                 if let Some(session_path) = get_user_session_path() {
@@ -328,18 +773,15 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                     if let Some(parent) = file_path.parent() {
                                         match fs::create_dir_all(parent) {
                                             Ok(_) => {
-                                                // Декодируем base64 контент если нужно
-                                                let file_content_result = if content.starts_with("base64:") {
-                                                    use base64::Engine;
-                                                    base64::engine::general_purpose::STANDARD.decode(&content[7..])
-                                                        .map_err(|e| format!("Ошибка декодирования base64: {}", e))
-                                                } else {
-                                                    Ok(content.as_bytes().to_vec())
-                                                };
-                                                
-                                                match file_content_result {
+                                                match decode_file_content(&content) {
                                                     Ok(file_content) => {
-                                                        match fs::write(&file_path, file_content) {
+                                                        let quota = policy.max_session_disk_bytes;
+                                                        let written = check_session_disk_quota(quota, &file_path, file_content.len() as u64)
+                                                            .and_then(|_| {
+                                                                fs::write(&file_path, file_content)
+                                                                    .map_err(|e| format!("Ошибка записи файла: {}", e))
+                                                            });
+                                                        match written {
                                                             Ok(_) => UploadFileResponse {
                                                                 success: true,
                                                                 message: format!("Файл {} успешно загружен", filename),
@@ -348,7 +790,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                                             Err(e) => UploadFileResponse {
                                                                 success: false,
                                                                 message: String::new(),
-                                                                error: Some(format!("Ошибка записи файла: {}", e)),
+                                                                error: Some(e),
                                                             },
                                                         }
                                                     }
@@ -387,6 +829,26 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                                 }
                             };
                             
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                if let Err(e) = write.send(Message::Text(json)).await {
+                                    eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        WebSocketRequest::Inspect => {
+                            let response = inspect_session(session_vm.as_ref());
+
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                if let Err(e) = write.send(Message::Text(json)).await {
+                                    eprintln!("❌ Ошибка отправки ответа: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        WebSocketRequest::PreviewTable { name, limit, offset, format } => {
+                            let response = preview_table(session_vm.as_ref(), &name, limit, offset, format.as_deref());
+
                             if let Ok(json) = serde_json::to_string(&response) {
                                 if let Err(e) = write.send(Message::Text(json)).await {
                                     eprintln!("❌ Ошибка отправки ответа: {}", e);
@@ -398,7 +860,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                 } else {
                     // Пытаемся распарсить как старый формат для обратной совместимости
                     if let Ok(request) = serde_json::from_str::<ExecuteRequest>(&text) {
-                        let response = execute_code(&request.code, &smb_manager);
+                        let response = execute_code(&request.code, &smb_manager, &policy, &mut session_vm);
                         
                         if let Ok(json) = serde_json::to_string(&response) {
                             if let Err(e) = write.send(Message::Text(json)).await {
@@ -410,7 +872,7 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
                         let error_response = ExecuteResponse {
                             success: false,
                             output: String::new(),
-                            error: Some(format!("Ошибка парсинга запроса. Ожидается JSON с полями: type, code (или smb_connect, smb_list_files, smb_read_file)")),
+                            error: Some(serde_json::Value::String("Ошибка парсинга запроса. Ожидается JSON с полями: type, code (или smb_connect, smb_list_files, smb_read_file, smb_write_file, smb_list_connections)".to_string())),
                         };
                         if let Ok(json) = serde_json::to_string(&error_response) {
                             let _ = write.send(Message::Text(json)).await;
@@ -485,13 +947,39 @@ async fn handle_client(stream: TcpStream, use_ve: bool) {
     set_use_ve(false);
 }
 
-/// Выполнить код и вернуть результат
+/// Запрет запроса профилем разрешений подключения; None - запрос разрешен.
+/// Execute проверяется при выполнении: политика применяется к каждой встроенной функции
+fn request_denial(request: &WebSocketRequest, policy: &ExecutionPolicy) -> Option<&'static str> {
+    match request {
+        WebSocketRequest::SmbConnect { .. }
+        | WebSocketRequest::SmbListFiles { .. }
+        | WebSocketRequest::SmbReadFile { .. }
+        | WebSocketRequest::SmbListConnections
+        | WebSocketRequest::SmbWriteFile { .. } if !policy.allow_smb => {
+            Some("Доступ к SMB запрещен профилем разрешений подключения")
+        }
+        WebSocketRequest::SmbReadFile { .. } if !policy.allow_file_read => {
+            Some("Чтение файлов запрещено профилем разрешений подключения")
+        }
+        WebSocketRequest::SmbWriteFile { .. } | WebSocketRequest::UploadFile { .. } if !policy.allow_file_write => {
+            Some("Запись файлов запрещена профилем разрешений подключения")
+        }
+        _ => None,
+    }
+}
+
+/// Выполнить код с политикой подключения и вернуть результат
 fn execute_code(
     code: &str,
     smb_manager: &Arc<Mutex<SmbManager>>,
+    policy: &ExecutionPolicy,
+    session_vm: &mut Option<Vm>,
 ) -> ExecuteResponse {
     // Устанавливаем SmbManager в thread-local storage для доступа из функций файловых операций
     crate::vm::file_ops::set_smb_manager(smb_manager.clone());
+    // Все подключения выполняются в одном потоке: политика потока заменяется на время запуска
+    let server_policy = get_execution_policy();
+    set_execution_policy(policy.clone());
     
     // Создаем буфер для перехвата вывода
     let output_capture = OutputCapture::new();
@@ -499,8 +987,11 @@ fn execute_code(
     // Устанавливаем буфер для текущего потока
     output_capture.set_capture(true);
 
-    // Выполняем код используя новую архитектуру VM
-    let result = run(code);
+    // Выполняем код используя новую архитектуру VM. Паника внутри интерпретатора становится
+    // ошибкой ответа, а не обрывает соединение клиента
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_with_vm(code)))
+        .unwrap_or_else(|payload| Err(internal_error(payload.as_ref())));
+    set_execution_policy(server_policy);
 
     // Получаем вывод
     let output = output_capture.get_output();
@@ -508,16 +999,147 @@ fn execute_code(
 
     // Формируем ответ
     match result {
-        Ok(_) => ExecuteResponse {
-            success: true,
-            output,
-            error: None,
-        },
-        Err(e) => ExecuteResponse {
-            success: false,
-            output,
-            error: Some(e.to_string()),
-        },
+        Ok((_, vm)) => {
+            // Сохраняем VM, чтобы клиент мог просматривать переменные сессии
+            *session_vm = Some(vm);
+            ExecuteResponse {
+                success: true,
+                output,
+                error: None,
+            }
+        }
+        Err(e) => {
+            let error = match e.thrown_value() {
+                Some(value @ Value::Object(_)) => value.to_json(),
+                _ if is_timeout_error(&e) => serde_json::Value::String(EXECUTION_TIMED_OUT.to_string()),
+                _ => serde_json::Value::String(e.to_string()),
+            };
+            ExecuteResponse {
+                success: false,
+                output,
+                error: Some(error),
+            }
+        }
     }
 }
 
+/// Ошибка выполнения из перехваченной паники интерпретатора
+fn internal_error(payload: &(dyn std::any::Any + Send)) -> LangError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    LangError::runtime_error(format!("internal error: {}", message), 0)
+}
+
+/// Пользовательские глобальные переменные сессии (имя, значение), отсортированные по имени
+fn session_globals(vm: &Vm) -> Vec<(&String, &Value)> {
+    let globals = vm.get_globals();
+    let mut entries: Vec<(&String, &Value)> = vm.get_global_names().iter()
+        .filter(|(_, name)| !name.starts_with("__")) // Скрытые переменные компилятора
+        .filter_map(|(index, name)| globals.get(*index).map(|value| (name, value)))
+        .filter(|(_, value)| !matches!(value, Value::NativeFunction(_)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Найти таблицу сессии по имени глобальной переменной
+fn find_session_table(vm: &Vm, name: &str) -> Option<Rc<RefCell<Table>>> {
+    session_globals(vm).into_iter().find_map(|(global_name, value)| match value {
+        Value::Table(table) if global_name.as_str() == name => Some(table.clone()),
+        _ => None,
+    })
+}
+
+/// Собрать описание состояния сессии: переменные, функции и таблицы
+fn inspect_session(session_vm: Option<&Vm>) -> InspectResponse {
+    let mut response = InspectResponse {
+        success: true,
+        globals: Vec::new(),
+        functions: Vec::new(),
+        tables: Vec::new(),
+        error: None,
+    };
+    let vm = match session_vm {
+        Some(vm) => vm,
+        None => return response, // Код еще не выполнялся - сессия пуста
+    };
+
+    for (name, value) in session_globals(vm) {
+        match value {
+            Value::Function(_) | Value::Closure(_) => {
+                let params = value.function_index().and_then(|index| vm.get_functions().get(index))
+                    .map(|f| f.param_names.clone())
+                    .unwrap_or_default();
+                response.functions.push(FunctionInfo { name: name.clone(), params });
+            }
+            _ => {
+                if let Value::Table(table) = value {
+                    let t = table.borrow();
+                    response.tables.push(TableInfo {
+                        name: name.clone(),
+                        rows: t.len(),
+                        columns: t.column_count(),
+                        column_names: t.headers.clone(),
+                    });
+                }
+                let type_name = crate::vm::natives::native_typeof(std::slice::from_ref(value)).to_string();
+                response.globals.push(VariableInfo {
+                    name: name.clone(),
+                    type_name,
+                    preview: value.preview(INSPECT_PREVIEW_CHARS),
+                });
+            }
+        }
+    }
+
+    response
+}
+
+/// Вернуть страницу строк таблицы сессии в виде массива JSON объектов и, если задан format,
+/// текстом show_table() в этом формате
+fn preview_table(session_vm: Option<&Vm>, name: &str, limit: usize, offset: usize, format: Option<&str>) -> PreviewTableResponse {
+    use crate::common::render::{render_table, RenderFormat, RenderOptions};
+
+    let failure = |error: String, error_type: &str| PreviewTableResponse {
+        success: false,
+        name: name.to_string(),
+        total_rows: 0,
+        columns: Vec::new(),
+        rows: Vec::new(),
+        text: None,
+        error: Some(error),
+        error_type: Some(error_type.to_string()),
+    };
+    let format = match format.map(|f| (f, RenderFormat::parse(f))) {
+        None => None,
+        Some((_, Some(format))) => Some(format),
+        Some((f, None)) => {
+            return failure(format!("Неизвестный формат '{}': ожидается grid, markdown или csv", f), "ValueError");
+        }
+    };
+    let table = match session_vm.and_then(|vm| find_session_table(vm, name)) {
+        Some(table) => table,
+        None => return failure(format!("Таблица '{}' не найдена", name), "TableNotFound"),
+    };
+
+    let t = table.borrow();
+    let text = format.map(|format| {
+        let start = offset.min(t.rows.len());
+        let end = start.saturating_add(limit).min(t.rows.len());
+        let page = Table::from_data(t.rows[start..end].to_vec(), Some(t.headers.clone()));
+        render_table(&page, &RenderOptions { format, max_rows: None, ..RenderOptions::default() })
+    });
+    PreviewTableResponse {
+        success: true,
+        name: name.to_string(),
+        total_rows: t.len(),
+        columns: t.headers.clone(),
+        rows: table_rows_to_json(&t, offset, limit),
+        text,
+        error: None,
+        error_type: None,
+    }
+}
@@ -3,7 +3,72 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::fs;
 use std::io::Write;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use crate::common::error::ErrorType;
+
+/// Сколько раз восстанавливать потерянное соединение перед ошибкой
+const MAX_RECONNECT_ATTEMPTS: usize = 2;
+/// Пауза перед первой попыткой переподключения; перед каждой следующей - кратно больше
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Признаки потерянного соединения в ошибках smbclient и файловых операций Windows:
+/// такую операцию имеет смысл повторить после переподключения, в отличие от
+/// отсутствующего файла или неверного пароля
+const CONNECTION_ERROR_MARKERS: &[&str] = &[
+    "NT_STATUS_CONNECTION_RESET",
+    "NT_STATUS_CONNECTION_DISCONNECTED",
+    "NT_STATUS_CONNECTION_ABORTED",
+    "NT_STATUS_CONNECTION_REFUSED",
+    "NT_STATUS_IO_TIMEOUT",
+    "NT_STATUS_HOST_UNREACHABLE",
+    "NT_STATUS_NETWORK_UNREACHABLE",
+    "NT_STATUS_NETWORK_NAME_DELETED",
+    "NT_STATUS_PIPE_BROKEN",
+    "NT_STATUS_USER_SESSION_DELETED",
+    "os error 53)",  // ERROR_BAD_NETPATH
+    "os error 64)",  // ERROR_NETNAME_DELETED
+    "os error 1231)", // ERROR_NETWORK_UNREACHABLE
+];
+
+/// Признаки отсутствующего файла или папки
+const NOT_FOUND_ERROR_MARKERS: &[&str] = &[
+    "NT_STATUS_OBJECT_NAME_NOT_FOUND",
+    "NT_STATUS_OBJECT_PATH_NOT_FOUND",
+    "NT_STATUS_NO_SUCH_FILE",
+    "NT_STATUS_BAD_NETWORK_NAME",
+    "os error 2)",  // ERROR_FILE_NOT_FOUND
+    "os error 3)",  // ERROR_PATH_NOT_FOUND
+];
+
+/// Признаки отказа в доступе (права на файл или неверные учетные данные)
+const PERMISSION_ERROR_MARKERS: &[&str] = &[
+    "NT_STATUS_ACCESS_DENIED",
+    "NT_STATUS_LOGON_FAILURE",
+    "NT_STATUS_ACCOUNT_LOCKED_OUT",
+    "NT_STATUS_PASSWORD_EXPIRED",
+    "os error 5)",  // ERROR_ACCESS_DENIED
+    "os error 1326)", // ERROR_LOGON_FAILURE
+];
+
+/// Похожа ли ошибка SMB операции на потерянное соединение
+pub fn is_connection_error(message: &str) -> bool {
+    CONNECTION_ERROR_MARKERS.iter().any(|marker| message.contains(marker))
+}
+
+/// Вид ошибки SMB операции для DataCode: ConnectionError (имеет смысл повторить),
+/// FileNotFoundError, PermissionError или общий IOError
+pub fn error_type(message: &str) -> ErrorType {
+    if is_connection_error(message) {
+        ErrorType::ConnectionError
+    } else if NOT_FOUND_ERROR_MARKERS.iter().any(|marker| message.contains(marker)) {
+        ErrorType::FileNotFoundError
+    } else if PERMISSION_ERROR_MARKERS.iter().any(|marker| message.contains(marker)) {
+        ErrorType::PermissionError
+    } else {
+        ErrorType::IOError
+    }
+}
 
 /// Параметры подключения к SMB
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,12 +98,33 @@ impl SmbConnection {
     }
 
     /// Получить путь для lib:// протокола
-    #[allow(dead_code)]
     pub fn get_lib_path(&self) -> String {
         format!("lib://{}", self.share_name)
     }
 }
 
+/// Убирает из текста ошибки логин и пароль подключения: вывод smbclient и net use
+/// попадает в ошибки DataCode и в ответы WebSocket сервера
+pub fn redact_credentials(message: &str, connection: &SmbConnection) -> String {
+    let mut message = message.to_string();
+    for secret in [&connection.password, &connection.login] {
+        if !secret.is_empty() {
+            message = message.replace(secret.as_str(), "***");
+        }
+    }
+    message
+}
+
+/// SMB шары, с которыми работает код DataCode: read_file("lib://..."), smb_connect(),
+/// smb_list() и другие функции обращаются к подключениям через этот трейт,
+/// поэтому в тестах SmbManager можно заменить своей реализацией
+pub trait SmbShares: Send {
+    fn connect(&mut self, connection: SmbConnection) -> Result<String, String>;
+    fn list_files(&self, share_name: &str, path: &str) -> Result<Vec<String>, String>;
+    fn read_file(&self, share_name: &str, file_path: &str) -> Result<Vec<u8>, String>;
+    fn write_file(&self, share_name: &str, file_path: &str, content: &[u8], append: bool) -> Result<(), String>;
+}
+
 /// Менеджер SMB подключений для websocket сессий
 pub struct SmbManager {
     /// Хранилище подключений по имени шары
@@ -68,8 +154,19 @@ impl SmbManager {
             return Err(format!("SMB share '{}' уже подключена", share_name));
         }
 
-        // Пытаемся подключиться используя системные команды
-        let mount_path = self.mount_base.join(&share_name);
+        self.establish(&connection).map_err(|e| redact_credentials(&e, &connection))?;
+
+        // Сохраняем подключение
+        self.connections.insert(share_name.clone(), connection);
+
+        Ok(format!("Успешно подключено к SMB шаре '{}'", share_name))
+    }
+
+    /// Устанавливает подключение системными средствами: net use на Windows,
+    /// проверка через smbclient или монтирование mount.cifs на Linux/Mac
+    fn establish(&self, connection: &SmbConnection) -> Result<(), String> {
+        let share_name = &connection.share_name;
+        let mount_path = self.mount_base.join(share_name);
         let _ = fs::create_dir_all(&mount_path);
 
         #[cfg(target_os = "windows")]
@@ -199,10 +296,47 @@ impl SmbManager {
             }
         }
 
-        // Сохраняем подключение
-        self.connections.insert(share_name.clone(), connection);
+        Ok(())
+    }
 
-        Ok(format!("Успешно подключено к SMB шаре '{}'", share_name))
+    /// Выполняет операцию с шарой. Если операция упала из-за потерянного соединения,
+    /// подключение восстанавливается по сохраненным учетным данным и операция повторяется,
+    /// не больше MAX_RECONNECT_ATTEMPTS раз
+    fn with_reconnect<T>(
+        &self,
+        share_name: &str,
+        operation: impl Fn(&SmbConnection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let connection = self.connections.get(share_name)
+            .ok_or_else(|| format!("SMB share '{}' не подключена", share_name))?;
+        self.run_reconnecting(connection, operation)
+            .map_err(|error| redact_credentials(&error, connection))
+    }
+
+    fn run_reconnecting<T>(
+        &self,
+        connection: &SmbConnection,
+        operation: impl Fn(&SmbConnection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut error = match operation(connection) {
+            Ok(result) => return Ok(result),
+            Err(error) => error,
+        };
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            if !is_connection_error(&error) {
+                return Err(error);
+            }
+            std::thread::sleep(RECONNECT_DELAY * attempt as u32);
+            if let Err(e) = self.establish(connection) {
+                error = e;
+                continue;
+            }
+            match operation(connection) {
+                Ok(result) => return Ok(result),
+                Err(e) => error = e,
+            }
+        }
+        Err(format!("{} (переподключение не помогло после {} попыток)", error, MAX_RECONNECT_ATTEMPTS))
     }
 
     /// Получить подключение по имени шары
@@ -219,9 +353,10 @@ impl SmbManager {
 
     /// Получить список файлов из SMB шары
     pub fn list_files(&self, share_name: &str, path: &str) -> Result<Vec<String>, String> {
-        let connection = self.connections.get(share_name)
-            .ok_or_else(|| format!("SMB share '{}' не подключена", share_name))?;
+        self.with_reconnect(share_name, |connection| self.list_files_once(connection, path))
+    }
 
+    fn list_files_once(&self, connection: &SmbConnection, path: &str) -> Result<Vec<String>, String> {
         let mut files = Vec::new();
 
         #[cfg(target_os = "windows")]
@@ -530,9 +665,10 @@ impl SmbManager {
 
     /// Прочитать файл из SMB шары
     pub fn read_file(&self, share_name: &str, file_path: &str) -> Result<Vec<u8>, String> {
-        let connection = self.connections.get(share_name)
-            .ok_or_else(|| format!("SMB share '{}' не подключена", share_name))?;
+        self.with_reconnect(share_name, |connection| self.read_file_once(connection, file_path))
+    }
 
+    fn read_file_once(&self, connection: &SmbConnection, file_path: &str) -> Result<Vec<u8>, String> {
         #[cfg(target_os = "windows")]
         {
             // Windows: читаем через UNC путь
@@ -598,6 +734,94 @@ impl SmbManager {
         }
     }
 
+    /// Записать файл в SMB шару, создавая недостающие папки.
+    /// append = true дописывает content в конец существующего файла
+    pub fn write_file(&self, share_name: &str, file_path: &str, content: &[u8], append: bool) -> Result<(), String> {
+        self.with_reconnect(share_name, |connection| self.write_file_once(connection, file_path, content, append))
+    }
+
+    fn write_file_once(&self, connection: &SmbConnection, file_path: &str, content: &[u8], append: bool) -> Result<(), String> {
+        #[cfg(target_os = "windows")]
+        {
+            // Windows: пишем через UNC путь
+            let full_path = PathBuf::from(format!("{}\\{}", connection.get_unc_path(), file_path.replace("/", "\\")));
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Ошибка создания папки: {}", e))?;
+            }
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&full_path)
+                .map_err(|e| format!("Ошибка записи файла: {}", e))?;
+            file.write_all(content).map_err(|e| format!("Ошибка записи файла: {}", e))
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            // Linux/Mac: smbclient put из временного файла. put не умеет дописывать,
+            // поэтому при append файл сначала читается и отправляется целиком
+            let mut data = if append {
+                self.read_file_once(connection, file_path).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            data.extend_from_slice(content);
+
+            let temp_file = std::env::temp_dir().join(format!("datacode_smb_put_{}", std::process::id()));
+            fs::write(&temp_file, &data).map_err(|e| format!("Ошибка записи временного файла: {}", e))?;
+
+            // mkdir для каждой родительской папки; ошибки "уже существует" smbclient пропускает
+            let mut smb_command = String::new();
+            let parts: Vec<&str> = file_path.split('/').filter(|p| !p.is_empty()).collect();
+            for depth in 1..parts.len() {
+                smb_command.push_str(&format!("mkdir \"{}\"; ", parts[..depth].join("/")));
+            }
+            smb_command.push_str(&format!("put \"{}\" \"{}\"", temp_file.display(), file_path));
+
+            let user_string = if connection.domain.is_empty() {
+                connection.login.clone()
+            } else {
+                format!("{}\\{}", connection.domain, connection.login)
+            };
+
+            let mut args = vec![
+                format!("//{}/{}", connection.ip, connection.share_name),
+                "-U".to_string(),
+                user_string,
+                "-c".to_string(),
+                smb_command,
+            ];
+            if !connection.domain.is_empty() {
+                args.push("-W".to_string());
+                args.push(connection.domain.clone());
+            }
+
+            let result = Command::new("smbclient")
+                .args(args)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Ошибка запуска smbclient: {}", e))
+                .and_then(|mut child| {
+                    if let Some(mut stdin) = child.stdin.take() {
+                        let _ = writeln!(stdin, "{}", connection.password);
+                    }
+                    child.wait_with_output().map_err(|e| format!("Ошибка выполнения smbclient: {}", e))
+                });
+            let _ = fs::remove_file(&temp_file);
+
+            let output = result?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!("Ошибка записи файла: {}", String::from_utf8_lossy(&output.stderr)))
+            }
+        }
+    }
+
     /// Отключиться от SMB шары
     pub fn disconnect(&mut self, share_name: &str) -> Result<String, String> {
         if !self.connections.contains_key(share_name) {
@@ -627,9 +851,29 @@ impl SmbManager {
         Ok(format!("Отключено от SMB шары '{}'", share_name))
     }
 
-    /// Получить все подключенные шары
+    /// Получить все подключенные шары (по имени)
     pub fn list_connections(&self) -> Vec<String> {
-        self.connections.keys().cloned().collect()
+        let mut shares: Vec<String> = self.connections.keys().cloned().collect();
+        shares.sort();
+        shares
+    }
+}
+
+impl SmbShares for SmbManager {
+    fn connect(&mut self, connection: SmbConnection) -> Result<String, String> {
+        SmbManager::connect(self, connection)
+    }
+
+    fn list_files(&self, share_name: &str, path: &str) -> Result<Vec<String>, String> {
+        SmbManager::list_files(self, share_name, path)
+    }
+
+    fn read_file(&self, share_name: &str, file_path: &str) -> Result<Vec<u8>, String> {
+        SmbManager::read_file(self, share_name, file_path)
+    }
+
+    fn write_file(&self, share_name: &str, file_path: &str, content: &[u8], append: bool) -> Result<(), String> {
+        SmbManager::write_file(self, share_name, file_path, content, append)
     }
 }
 
@@ -0,0 +1,118 @@
+// Тесты статического анализа: проверка структуры блоков, datacode --check и --fmt
+#[cfg(test)]
+mod tests {
+    use data_code::analysis::{check_block_structure, check_source, format_source, is_incomplete};
+    use std::fs;
+    use std::process::Command;
+
+    const MESSY: &str = include_str!("test_data/fmt/messy.dc");
+    const EXPECTED: &str = include_str!("test_data/fmt/expected.dc");
+
+    fn lines(diagnostics: &[data_code::analysis::Diagnostic]) -> Vec<usize> {
+        diagnostics.iter().map(|d| d.line).collect()
+    }
+
+    #[test]
+    fn test_format_fixture() {
+        assert_eq!(format_source(MESSY), EXPECTED);
+        // Форматирование идемпотентно
+        assert_eq!(format_source(EXPECTED), EXPECTED);
+    }
+
+    #[test]
+    fn test_formatted_code_passes_check() {
+        assert!(check_source(MESSY).is_empty(), "{:?}", check_source(MESSY));
+        let formatted = format_source(MESSY);
+        assert!(check_source(&formatted).is_empty(), "{:?}", check_source(&formatted));
+    }
+
+    #[test]
+    fn test_format_spacing_rules() {
+        let cases = [
+            ("let a=b//2", "let a = b // 2"),
+            ("let m=a<<2|b&-1^c>>1", "let m = a << 2 | b & -1 ^ c >> 1"),
+            ("x=obj.field.method( 1,2 )", "x = obj.field.method(1, 2)"),
+            ("let r = not(a)and b", "let r = not (a) and b"),
+            ("f(-1 , +2)", "f(-1, +2)"),
+            ("let o = {\"k\" :a?b:c}", "let o = {\"k\": a ? b : c}"),
+            ("@cache\nfn f(){ return 1 }", "@cache\nfn f() { return 1 }"),
+            ("print(\"x\")  //// комментарий", "print(\"x\")  //// комментарий"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(format_source(source), format!("{}\n", expected), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_format_keeps_multiline_strings() {
+        let source = "if true {\nlet s = \"first\n   second {\nthird\"\nprint(s)\n}\n";
+        let expected = "if true {\n    let s = \"first\n   second {\nthird\"\n    print(s)\n}\n";
+        assert_eq!(format_source(source), expected);
+        assert_eq!(format_source(expected), expected);
+    }
+
+    #[test]
+    fn test_block_structure_errors() {
+        // Все ошибки структуры с номерами строк, а не только первая
+        let source = "fn f() {\n    if x {\n        print(1\n    }\n}\n]\nlet s = \"open";
+        let diagnostics = check_block_structure(source);
+        assert_eq!(lines(&diagnostics), vec![3, 6, 7], "{:?}", diagnostics);
+        assert!(diagnostics[0].message.contains("'(' is not closed"), "{}", diagnostics[0]);
+        assert!(diagnostics[1].message.contains("Unexpected ']'"), "{}", diagnostics[1]);
+        assert!(diagnostics[2].message.contains("Unterminated string"), "{}", diagnostics[2]);
+
+        let unclosed = check_block_structure("for x in [1, 2] {\n    print(x)\n");
+        assert_eq!(lines(&unclosed), vec![1]);
+        assert!(unclosed[0].message.contains("'{' is never closed"));
+
+        // Скобки в строках и комментариях не учитываются
+        assert!(check_block_structure("let s = \"{[(\" # )}]\nprint(s)").is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_syntax_errors_with_line() {
+        let diagnostics = check_source("let a = 1\nlet = 2\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2, "{}", diagnostics[0]);
+        // Код не выполняется: ошибка времени выполнения не находится,
+        // а неизвестная функция - ошибка компиляции
+        assert!(check_source("let a = 1 / 0\nprint(a)").is_empty());
+        let unknown = check_source("let a = 1 / 0\nundefined_function()");
+        assert_eq!(lines(&unknown), vec![2]);
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete("fn f() {"));
+        assert!(is_incomplete("fn f() {\n    return [1,"));
+        assert!(is_incomplete("let s = \"multi"));
+        assert!(!is_incomplete("fn f() {\n    return 1\n}"));
+        assert!(!is_incomplete("print(\"{\")"));
+        // Лишняя закрывающая скобка - ошибка, REPL выполняет строку и показывает ее
+        assert!(!is_incomplete("}"));
+        assert!(!is_incomplete("# {"));
+    }
+
+    #[test]
+    fn test_cli_check_and_fmt() {
+        let dir = std::env::temp_dir().join(format!("datacode_analysis_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("broken.dc");
+        fs::write(&broken, "fn f() {\n    print(1\n").unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_datacode")).arg("--check").arg(&broken).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("broken.dc:1:") && stderr.contains("broken.dc:2:"), "{}", stderr);
+
+        let messy = dir.join("messy.dc");
+        fs::write(&messy, MESSY).unwrap();
+        let status = Command::new(env!("CARGO_BIN_EXE_datacode")).arg("--fmt").arg(&messy).status().unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_to_string(&messy).unwrap(), EXPECTED);
+
+        let check = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_datacode")).args(args).arg(&messy).status().unwrap();
+        assert!(check(&["--check"]).success());
+        assert!(check(&["--fmt", "--check"]).success());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
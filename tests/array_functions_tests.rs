@@ -10,6 +10,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -20,7 +24,7 @@ mod tests {
         let result = run(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, expected, "Expected '{}', got '{}'", expected, s);
+                assert_eq!(&*s, expected, "Expected '{}', got '{}'", expected, s);
             }
             Ok(v) => panic!("Expected String('{}'), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
@@ -89,9 +93,9 @@ mod tests {
             push(arr, "c")
         "#;
         assert_array_result(source, &[
-            Value::String("a".to_string()),
-            Value::String("b".to_string()),
-            Value::String("c".to_string()),
+            Value::String("a".into()),
+            Value::String("b".into()),
+            Value::String("c".into()),
         ]);
     }
 
@@ -189,9 +193,9 @@ mod tests {
             unique(["a", "b", "a", "c", "b"])
         "#;
         assert_array_result(source, &[
-            Value::String("a".to_string()),
-            Value::String("b".to_string()),
-            Value::String("c".to_string()),
+            Value::String("a".into()),
+            Value::String("b".into()),
+            Value::String("c".into()),
         ]);
     }
 
@@ -248,12 +252,52 @@ mod tests {
             reverse(arr)
         "#;
         assert_array_result(source, &[
-            Value::String("c".to_string()),
-            Value::String("b".to_string()),
-            Value::String("a".to_string()),
+            Value::String("c".into()),
+            Value::String("b".into()),
+            Value::String("a".into()),
         ]);
     }
 
+    #[test]
+    fn test_reverse_does_not_mutate_input() {
+        let source = r#"
+            let arr = [1, 2, 3]
+            let reversed = reverse(arr)
+            arr[0] * 10 + reversed[0]
+        "#;
+        assert_number_result(source, 13.0);
+    }
+
+    // ========== Тесты для shuffle ==========
+
+    #[test]
+    fn test_shuffle_is_permutation_copy() {
+        let source = r#"
+            let arr = [1, 2, 3, 4, 5, 6, 7, 8]
+            let mixed = shuffle(arr)
+            len(mixed) == 8 and sort(mixed) == [1, 2, 3, 4, 5, 6, 7, 8] and arr == [1, 2, 3, 4, 5, 6, 7, 8]
+        "#;
+        match run(source) {
+            Ok(Value::Bool(true)) => {}
+            other => panic!("Expected Bool(true), got {:?}", other),
+        }
+        assert_array_result("shuffle([])", &[]);
+    }
+
+    #[test]
+    fn test_shuffle_seed_is_reproducible() {
+        let source = r#"
+            let data = range(20)
+            shuffle(data, 42) == shuffle(data, seed=42) and shuffle(data, 42) != shuffle(data, 7)
+        "#;
+        match run(source) {
+            Ok(Value::Bool(true)) => {}
+            other => panic!("Expected Bool(true), got {:?}", other),
+        }
+        assert!(run("shuffle([1, 2], -1)").is_err());
+        assert!(run(r#"shuffle("abc")"#).is_err());
+    }
+
     // ========== Тесты для sort ==========
 
     #[test]
@@ -278,9 +322,9 @@ mod tests {
             sort(arr)
         "#;
         assert_array_result(source, &[
-            Value::String("a".to_string()),
-            Value::String("b".to_string()),
-            Value::String("c".to_string()),
+            Value::String("a".into()),
+            Value::String("b".into()),
+            Value::String("c".into()),
         ]);
     }
 
@@ -854,5 +898,294 @@ mod tests {
             Err(_) => {}
         }
     }
-}
 
+    // ========== Тесты для zip и unzip ==========
+
+    #[test]
+    fn test_zip_pairs_elements() {
+        let source = r#"
+            let pairs = zip([1, 2, 3], ["a", "b", "c"])
+            pairs[2][0] * 10 + len(pairs)
+        "#;
+        assert_number_result(source, 33.0);
+        assert_string_result(r#"zip([1, 2], ["a", "b"], [true, false])[1][1]"#, "b");
+    }
+
+    #[test]
+    fn test_zip_truncates_to_shortest() {
+        assert_number_result("len(zip([1, 2, 3, 4], [5, 6]))", 2.0);
+        assert_number_result("len(zip([1, 2], []))", 0.0);
+        assert_number_result("len(zip())", 0.0);
+        assert_number_result("len(zip([1, 2], [3, 4], strict=true))", 2.0);
+    }
+
+    #[test]
+    fn test_zip_strict_length_mismatch_errors() {
+        let result = run("zip([1, 2, 3], [4, 5], strict=true)");
+        let message = format!("{:?}", result.unwrap_err());
+        assert!(message.contains("different lengths: 3, 2"), "{}", message);
+        assert!(run(r#"zip([1], "abc")"#).is_err());
+        assert!(run("zip([1], [2], mode=true)").is_err());
+    }
+
+    #[test]
+    fn test_zip_unzip_error_types() {
+        // Несовпадение длин - ValueError, аргумент не того типа - TypeError
+        for (source, expected) in [
+            ("zip([1, 2, 3], [4, 5], strict=true)", "[ValueError]"),
+            (r#"zip([1], "abc")"#, "[TypeError]"),
+            ("unzip(5)", "[TypeError]"),
+            ("unzip([[1, 2], 3])", "[TypeError]"),
+            ("unzip([[1, 2], [3]])", "[ValueError]"),
+        ] {
+            let message = format!("{}", run(source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", source, message);
+        }
+        let source = r#"
+            let r = "none"
+            try {
+                zip([1, 2], [3], strict=true)
+            } catch ValueError e {
+                r = "caught"
+            }
+            r
+        "#;
+        assert_string_result(source, "caught");
+    }
+
+    #[test]
+    fn test_unzip_is_inverse_of_zip() {
+        let source = r#"
+            let xs = [1, 2, 3]
+            let ys = [10, 20, 30]
+            let back = unzip(zip(xs, ys))
+            back[0] == xs and back[1] == ys
+        "#;
+        match run(source) {
+            Ok(Value::Bool(true)) => {}
+            other => panic!("Expected Bool(true), got {:?}", other),
+        }
+        assert_array_result("unzip([])", &[]);
+        assert!(run("unzip([[1, 2], [3]])").is_err());
+    }
+
+    #[test]
+    fn test_zip_in_for_loop() {
+        let source = r#"
+            let total = 0
+            for pair in zip([1, 2, 3], [4, 5, 6]) {
+                total += pair[0] * pair[1]
+            }
+            total
+        "#;
+        assert_number_result(source, 32.0);
+    }
+
+    // ========== Тесты для flatten и chunk ==========
+
+    #[test]
+    fn test_flatten_default_depth_is_one() {
+        let inner = Value::Array(std::rc::Rc::new(std::cell::RefCell::new(vec![Value::Number(4.0)])));
+        assert_array_result(
+            "flatten([1, [2, 3], [[4]]])",
+            &[Value::Number(1.0), Value::Number(2.0), Value::Number(3.0), inner],
+        );
+        assert_array_result("flatten([])", &[]);
+    }
+
+    #[test]
+    fn test_flatten_depth() {
+        assert_number_result("len(flatten([1, [2, [3, [4]]]], 2))", 4.0);
+        assert_number_result("flatten([1, [2, [3, [4]]]], -1)[3]", 4.0);
+        assert_number_result("len(flatten([[1], [2]], 0))", 2.0);
+        assert_number_result("len(flatten([1, [2, [3]]], depth=-1))", 3.0);
+        assert!(run("flatten([1], 1.5)").is_err());
+        assert!(run("flatten([1], -2)").is_err());
+        assert!(run(r#"flatten("abc")"#).is_err());
+    }
+
+    #[test]
+    fn test_chunk_splits_with_shorter_tail() {
+        let source = r#"
+            let parts = chunk([1, 2, 3, 4, 5], 2)
+            len(parts) * 100 + len(parts[0]) * 10 + len(parts[2])
+        "#;
+        assert_number_result(source, 321.0);
+        assert_number_result("len(chunk([], 3))", 0.0);
+        assert_number_result("len(chunk([1, 2], 10)[0])", 2.0);
+        assert_number_result("sum(flatten(chunk([1, 2, 3, 4], 3)))", 10.0);
+    }
+
+    #[test]
+    fn test_chunk_invalid_size_errors() {
+        let message = format!("{}", run("chunk([1, 2], 0)").unwrap_err());
+        assert!(message.contains("ValueError") && message.contains("positive integer"), "{}", message);
+        assert!(run("chunk([1, 2], -1)").is_err());
+        assert!(run(r#"chunk([1, 2], "2")"#).is_err());
+    }
+
+    // ========== Тесты для reduce ==========
+
+    #[test]
+    fn test_reduce_without_initial_uses_first_element() {
+        let source = r#"
+            fn add(acc, x) {
+                return acc + x
+            }
+            reduce([1, 2, 3, 4], add)
+        "#;
+        assert_number_result(source, 10.0);
+        assert_number_result("fn add(a, b) {\nreturn a + b\n}\nreduce([7], add)", 7.0);
+    }
+
+    #[test]
+    fn test_reduce_with_initial_and_different_accumulator_type() {
+        let source = r#"
+            fn collect_names(acc, person) {
+                push(acc, upper(person))
+                return acc
+            }
+            let names = reduce(["ann", "bob"], collect_names, [])
+            join(names, ",")
+        "#;
+        assert_string_result(source, "ANN,BOB");
+        assert_number_result("fn add(a, b) {\nreturn a + b\n}\nreduce([1, 2], add, initial=100)", 103.0);
+    }
+
+    #[test]
+    fn test_reduce_empty_array() {
+        assert_number_result("fn add(a, b) {\nreturn a + b\n}\nreduce([], add, 42)", 42.0);
+        let message = format!("{}", run("fn add(a, b) {\nreturn a + b\n}\nreduce([], add)").unwrap_err());
+        assert!(message.contains("empty array with no initial value"), "{}", message);
+        assert!(run("reduce(5, print)").is_err());
+    }
+
+    // ========== Тесты для map ==========
+
+    #[test]
+    fn test_map_single_array() {
+        let source = r#"
+            fn square(x) {
+                return x * x
+            }
+            let out = map(square, [1, 2, 3])
+            out
+        "#;
+        assert_array_result(source, &[Value::Number(1.0), Value::Number(4.0), Value::Number(9.0)]);
+        assert_array_result("fn inc(x) {\nreturn x + 1\n}\nmap(inc, [])", &[]);
+        assert_string_result(r#"join(map(upper, ["a", "b"]), "")"#, "AB");
+    }
+
+    #[test]
+    fn test_map_pairs_elements_of_several_arrays() {
+        let source = r#"
+            fn add(x, y) {
+                return x + y
+            }
+            let out = map(add, [1, 2, 3], [10, 20, 30])
+            out
+        "#;
+        assert_array_result(source, &[Value::Number(11.0), Value::Number(22.0), Value::Number(33.0)]);
+        let source = r#"
+            fn label(name, age, city) {
+                return name + ":" + str(age) + "@" + city
+            }
+            join(map(label, ["ann", "bob"], [30, 25], ["Omsk", "Kazan"]), ",")
+        "#;
+        assert_string_result(source, "ann:30@Omsk,bob:25@Kazan");
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let add = "fn add(x, y) {\nreturn x + y\n}\n";
+        let message = format!("{}", run(&format!("{}map(add, [1, 2, 3], [1, 2])", add)).unwrap_err());
+        assert!(message.contains("same length, got 3 and 2"), "{}", message);
+        let message = format!("{}", run(&format!("{}map(add, [1, 2])", add)).unwrap_err());
+        assert!(message.contains("takes 2 arguments but 1 arrays were given"), "{}", message);
+        assert!(run(&format!("{}map(add, [1], 2)", add)).is_err());
+        assert!(run(&format!("{}map(add)", add)).is_err());
+    }
+
+    #[test]
+    fn test_index_of() {
+        assert_number_result("index_of([10, 20, 30, 20], 20)", 1.0);
+        assert_number_result("index_of([10, 20, 30], 99)", -1.0);
+        assert_number_result("index_of([], 1)", -1.0);
+        assert_number_result(r#"index_of(["a", [1, 2], null], [1, 2])"#, 1.0);
+        assert_number_result(r#"index_of(["a", [1, 2], null], null)"#, 2.0);
+        assert!(run(r#"index_of("abc", "b")"#).is_err());
+    }
+
+    #[test]
+    fn test_contains_array() {
+        assert_string_result(
+            r#"str([contains([1, 2, 3], 2), contains([1, 2, 3], 5), contains([], 1), contains(["a", "b"], "b")])"#,
+            "[true, false, false, true]",
+        );
+        // Для строк contains по-прежнему ищет подстроку
+        assert_string_result(r#"str(contains("hello", "ell"))"#, "true");
+    }
+
+    #[test]
+    fn test_sort_by_user_key_over_objects() {
+        let source = r#"
+            fn by_age(person) {
+                return person["age"]
+            }
+            let people = [{name: "Ann", age: 31}, {name: "Bob", age: 25}, {name: "Cid", age: 28}]
+            let sorted = sort_by(people, by_age)
+            let names = []
+            for p in sorted {
+                push(names, p["name"])
+            }
+            join(names, ",") + " " + people[0]["name"]
+        "#;
+        assert_string_result(source, "Bob,Cid,Ann Ann");
+    }
+
+    #[test]
+    fn test_sort_by_descending_and_stable() {
+        let source = r#"
+            fn first(pair) {
+                return pair[0]
+            }
+            let pairs = [[2, "a"], [1, "b"], [2, "c"], [1, "d"]]
+            let names = []
+            for p in sort_by(pairs, first, descending=true) {
+                push(names, p[1])
+            }
+            join(names, "")
+        "#;
+        assert_string_result(source, "acbd");
+        assert_string_result(r#"join(sort_by(["ccc", "a", "bb"], len), ",")"#, "a,bb,ccc");
+        assert_string_result(r#"join(sort_by(["b", "C", "a"], lower), ",")"#, "a,b,C");
+        assert_array_result("fn id(x) {\nreturn x\n}\nsort_by([], id)", &[]);
+    }
+
+    #[test]
+    fn test_sort_by_errors() {
+        let source = r#"
+            fn id(x) {
+                return x
+            }
+            sort_by([1, "a", 2], id)
+        "#;
+        let message = format!("{}", run(source).unwrap_err());
+        assert!(message.contains("keys must be all numbers or all strings"), "{}", message);
+        assert!(run("fn id(x) {\nreturn x\n}\nsort_by(5, id)").is_err());
+        // Ошибка в функции-ключе доходит до вызывающего кода и перехватывается try
+        let source = r#"
+            fn bad(x) {
+                throw "bad key"
+            }
+            let result = "none"
+            try {
+                sort_by([1, 2], bad)
+            } catch e {
+                result = "caught"
+            }
+            result
+        "#;
+        assert_string_result(source, "caught");
+    }
+}
@@ -0,0 +1,81 @@
+// Тесты целочисленного деления, divmod() и побитовых операторов &, |, ^, <<, >>
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn eval_string(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_string(),
+            Err(e) => panic!("{}\n=> {}", source, e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_floor_division_rounds_down() {
+        assert_eq!(eval_string("[7 // 2, -7 // 2, 7 // -2, -7 // -2]"), "[3, -4, -4, 3]");
+        assert_eq!(eval_string("[-7.5 // 2, 7.5 // -2, -0.5 // 1]"), "[-4, -4, -1]");
+        let source = "let x = -9\nx //= 4\nx";
+        assert_eq!(run(source).unwrap(), Value::Number(-3.0));
+    }
+
+    #[test]
+    fn test_divmod() {
+        assert_eq!(eval_string("[divmod(7, 2), divmod(-7, 2), divmod(7, -2), divmod(-7, -2)]"), "[[3, 1], [-4, 1], [-4, -1], [3, -1]]");
+        assert_eq!(eval_string("divmod(7.5, 2)"), "[3, 1.5]");
+        // Частное и остаток согласованы с //: a == q * b + r
+        let source = "let ok = true\nfor a in [-9, -4, 0, 5, 13] {\n    for b in [-3, 2, 5] {\n        let q, r = divmod(a, b)\n        ok = ok and q == a // b and q * b + r == a\n    }\n}\nok";
+        assert_eq!(run(source).unwrap(), Value::Bool(true));
+        for (source, expected) in [
+            ("divmod(1, 0)", "divmod(): division by zero"),
+            ("divmod(\"7\", 2)", "divmod() expects two numbers"),
+            ("divmod(7)", "divmod(a, b) expects 2 arguments, got 1"),
+        ] {
+            let message = error_message(source);
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+    }
+
+    #[test]
+    fn test_user_function_shadows_divmod() {
+        // Пользовательская функция с именем встроенной занимает ее слот и не пересекается
+        // со слотами глобальных переменных, объявленных после нее
+        let source = "fn divmod(a, b) {\n    return a - b\n}\nlet out = []\nfor n in [7, 9] {\n    let d = divmod(n, 5)\n    push(out, d)\n}\nout";
+        assert_eq!(eval_string(source), "[2, 4]");
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(eval_string("[12 & 10, 12 | 10, 12 ^ 10, 1 << 10, 1024 >> 3]"), "[8, 14, 6, 1024, 128]");
+        // Отрицательные операнды в дополнительном коде, >> - арифметический сдвиг
+        assert_eq!(eval_string("[-1 & 255, -8 >> 1, -1 >> 63, -3 | 1, 5 ^ -1]"), "[255, -4, -1, -3, -6]");
+        // Число с целым значением допустимо в любой форме
+        assert_eq!(eval_string("[6.0 & 3, 2 ** 3 | 1, 10 / 2 << 1]"), "[2, 9, 10]");
+        // Флаги: & сильнее сравнения, | слабее &
+        let source = "let READ = 1\nlet WRITE = 2\nlet EXEC = 4\nlet mode = READ | WRITE\n[mode & WRITE != 0, mode & EXEC != 0, mode | EXEC & READ, mode ^ READ]";
+        assert_eq!(eval_string(source), "[true, false, 3, 2]");
+    }
+
+    #[test]
+    fn test_bitwise_operand_errors() {
+        for (source, expected) in [
+            ("1.5 & 1", "Operator '&' requires integers, got 1.5"),
+            ("3 | 0.25", "Operator '|' requires integers, got 0.25"),
+            ("7 ^ \"1\"", "Operator '^' requires integers, got string"),
+            ("null >> 1", "Operator '>>' requires integers, got null"),
+            ("1 << -1", "Shift amount must be between 0 and 63, got -1"),
+            ("1 >> 64", "Shift amount must be between 0 and 63, got 64"),
+            ("3 << 62", "Integer overflow in 3 << 62"),
+            ("10 ** 30 & 1", "Operator '&' operand 1000000000000000000000000000000 is out of the integer range"),
+        ] {
+            let message = error_message(source);
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+        // Дробный операнд - ValueError, нечисловой - TypeError; обе ошибки перехватываются
+        let source = "let r = []\ntry {\n    let x = 2.5 << 1\n} catch ValueError e {\n    push(r, \"value\")\n}\ntry {\n    let y = [1] & 1\n} catch TypeError e {\n    push(r, \"type\")\n}\nr";
+        assert_eq!(eval_string(source), "[value, type]");
+    }
+}
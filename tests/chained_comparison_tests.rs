@@ -0,0 +1,98 @@
+// Тесты для цепочек сравнений 0 < x < 10
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn assert_bool_result(source: &str, expected: bool) {
+        match run(source) {
+            Ok(Value::Bool(b)) => assert_eq!(b, expected, "Expected {}, got {} for {}", expected, b, source),
+            Ok(v) => panic!("Expected Bool({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_number_result(source: &str, expected: f64) {
+        match run(source) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected, "Expected {}, got {}", expected, n),
+            Ok(Value::Integer(i)) => assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_chained_comparison_in_range() {
+        assert_bool_result("let x = 5\n0 < x < 10", true);
+        assert_bool_result("let x = 15\n0 < x < 10", false);
+        assert_bool_result("let x = -1\n0 < x < 10", false);
+    }
+
+    #[test]
+    fn test_chained_comparison_is_not_left_associative() {
+        // (0 < 5) < 2 было бы true < 2; цепочка дает 0 < 5 and 5 < 2
+        assert_bool_result("0 < 5 < 2", false);
+        assert_bool_result("3 > 2 > 1", true);
+    }
+
+    #[test]
+    fn test_chained_comparison_mixed_operators() {
+        assert_bool_result("1 < 2 <= 2", true);
+        assert_bool_result("1 <= 1 < 2 <= 3", true);
+        assert_bool_result("1 < 2 >= 3", false);
+        assert_bool_result("5 >= 5 > 4 >= 4", true);
+    }
+
+    #[test]
+    fn test_chained_comparison_evaluates_middle_once() {
+        let source = r#"
+            let calls = []
+            fn mid() {
+                push(calls, 1)
+                return 5
+            }
+            let inside = 0 < mid() < 10
+            len(calls)
+        "#;
+        assert_number_result(source, 1.0);
+    }
+
+    #[test]
+    fn test_chained_comparison_short_circuits() {
+        let source = r#"
+            let calls = []
+            fn last() {
+                push(calls, 1)
+                return 100
+            }
+            let r = 5 < 1 < last()
+            len(calls)
+        "#;
+        assert_number_result(source, 0.0);
+    }
+
+    #[test]
+    fn test_chained_comparison_in_function_and_loop() {
+        let source = r#"
+            fn count_in_range(values, lo, hi) {
+                let count = 0
+                for v in values {
+                    if lo <= v < hi {
+                        count += 1
+                    }
+                }
+                return count
+            }
+            count_in_range([1, 5, 10, 15, 20], 5, 15)
+        "#;
+        assert_number_result(source, 2.0);
+    }
+
+    #[test]
+    fn test_chain_broken_by_other_operators() {
+        // and и == не входят в цепочку
+        assert_bool_result("1 < 2 and 2 < 3", true);
+        assert_bool_result("1 < 2 == 2 < 3", true);
+        assert_bool_result("1 + 1 < 3 - 0 < 4 * 1", true);
+    }
+}
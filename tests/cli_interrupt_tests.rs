@@ -0,0 +1,98 @@
+// Тесты Ctrl-C при выполнении файла: программа прерывается, SQLite экспорт пропускается
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("datacode_cli_interrupt_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Запускает скрипт, ждет его первой строки вывода и отправляет SIGINT
+    fn spawn_and_interrupt(script: &std::path::Path, extra_args: &[&str]) -> Child {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_datacode"))
+            .arg(script)
+            .args(extra_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut first_line = String::new();
+        BufReader::new(child.stdout.as_mut().unwrap()).read_line(&mut first_line).unwrap();
+        assert_eq!(first_line.trim(), "started");
+        // Обработчик сигнала устанавливается в отдельном потоке
+        thread::sleep(Duration::from_millis(300));
+        send_sigint(&child);
+        child
+    }
+
+    fn send_sigint(child: &Child) {
+        let status = Command::new("kill").arg("-INT").arg(child.id().to_string()).status().unwrap();
+        assert!(status.success());
+    }
+
+    fn wait_with_deadline(mut child: Child) -> std::process::Output {
+        let started = Instant::now();
+        while child.try_wait().unwrap().is_none() {
+            if started.elapsed() > Duration::from_secs(10) {
+                let _ = child.kill();
+                panic!("process did not stop after Ctrl-C");
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        child.wait_with_output().unwrap()
+    }
+
+    const LOOPING_SCRIPT: &str = "global data = table([[1, \"a\"]], [\"id\", \"name\"])\nprint(\"started\")\nlet i = 0\nwhile true {\n    i = i + 1\n}\n";
+
+    #[test]
+    fn test_ctrl_c_skips_sqlite_export() {
+        let dir = temp_dir("export");
+        let script = dir.join("loop.dc");
+        let db = dir.join("loop.db");
+        fs::write(&script, LOOPING_SCRIPT).unwrap();
+
+        let child = spawn_and_interrupt(&script, &["--build_model", db.to_str().unwrap()]);
+        let output = wait_with_deadline(child);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(130), "{}", stderr);
+        assert!(stderr.contains("Выполнение прервано"), "{}", stderr);
+        assert!(stderr.contains("SQLite экспорт пропущен"), "{}", stderr);
+        assert!(!db.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ctrl_c_stops_plain_run() {
+        let dir = temp_dir("plain");
+        let script = dir.join("loop.dc");
+        fs::write(&script, LOOPING_SCRIPT).unwrap();
+
+        let output = wait_with_deadline(spawn_and_interrupt(&script, &[]));
+        assert_eq!(output.status.code(), Some(130));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Выполнение прервано"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_script_can_catch_ctrl_c() {
+        let dir = temp_dir("catch");
+        let script = dir.join("catch.dc");
+        fs::write(
+            &script,
+            "print(\"started\")\ntry {\n    while true {\n    }\n} catch Interrupted e {\n    print(\"cleanup done\")\n}\n",
+        )
+        .unwrap();
+
+        let output = wait_with_deadline(spawn_and_interrupt(&script, &[]));
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("cleanup done"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,95 @@
+// Тесты для денежных сумм: parse_currency, format_currency и арифметика над Currency
+#[cfg(test)]
+mod tests {
+    use data_code::run;
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(value) => assert_eq!(value.to_string(), expected),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_error_contains(source: &str, expected: &str) {
+        let message = format!("{}", run(source).unwrap_err());
+        assert!(message.contains(expected), "{}", message);
+    }
+
+    #[test]
+    fn test_parse_currency_formats() {
+        assert_string_result(r#"parse_currency("$1,234.56")"#, "$1,234.56");
+        assert_string_result(r#"parse_currency("-€10")"#, "-€10.00");
+        assert_string_result(r#"parse_currency("1 234,50 EUR")"#, "1,234.50 EUR");
+        assert_string_result(r#"parse_currency("USD 1.234,5")"#, "1,234.50 USD");
+        assert_string_result(r#"parse_currency("£1,000")"#, "£1,000.00");
+        assert_string_result(r#"typeof(parse_currency("$5"))"#, "money");
+        assert_string_result(r#"isinstance(parse_currency("$5"), "currency")"#, "true");
+    }
+
+    #[test]
+    fn test_parse_currency_errors() {
+        assert_error_contains(r#"parse_currency("1,234.56")"#, "cannot parse '1,234.56'");
+        assert_error_contains(r#"parse_currency("$abc")"#, "cannot parse");
+        assert_error_contains("parse_currency(12)", "parse_currency() expects a string");
+    }
+
+    #[test]
+    fn test_arithmetic_keeps_symbol() {
+        assert_string_result(r#"parse_currency("$10.50") + parse_currency("$2.25")"#, "$12.75");
+        assert_string_result(r#"parse_currency("$10") - parse_currency("$12.5")"#, "-$2.50");
+        assert_string_result(r#"parse_currency("$10") + 5"#, "$15.00");
+        assert_string_result(r#"3 * parse_currency("€1,000.10")"#, "€3,000.30");
+        assert_string_result(r#"parse_currency("10 EUR") / 4"#, "2.50 EUR");
+        assert_string_result(r#"-parse_currency("$3")"#, "-$3.00");
+        // Отношение сумм в одной валюте - обычное число
+        assert_string_result(r#"parse_currency("$30") / parse_currency("$12")"#, "2.5");
+        assert_string_result(r#""Total: " + parse_currency("$7")"#, "Total: $7.00");
+    }
+
+    #[test]
+    fn test_mixed_currencies_are_rejected() {
+        assert_error_contains(
+            r#"parse_currency("$10") + parse_currency("€10")"#,
+            "Cannot add amounts in different currencies: $ and €",
+        );
+        assert_error_contains(r#"parse_currency("$10") > parse_currency("10 EUR")"#, "different currencies");
+        assert_error_contains(r#"parse_currency("$10") * parse_currency("$2")"#, "Unsupported operand types");
+        assert_error_contains(r#"parse_currency("$10") / 0"#, "Division by zero");
+    }
+
+    #[test]
+    fn test_comparison_and_equality() {
+        let source = r#"
+            let a = parse_currency("$10")
+            let b = parse_currency("$9.99")
+            str([a > b, a <= b, a == parse_currency("$10.00"), a == parse_currency("10 USD"), a > 5])
+        "#;
+        assert_string_result(source, "[true, false, true, false, true]");
+    }
+
+    #[test]
+    fn test_mixed_currency_error_is_catchable() {
+        let source = r#"
+            let result = "ok"
+            try {
+                result = parse_currency("$1") - parse_currency("£1")
+            } catch e {
+                result = "caught"
+            }
+            result
+        "#;
+        assert_string_result(source, "caught");
+    }
+
+    #[test]
+    fn test_format_currency() {
+        assert_string_result(r#"format_currency(1234.5, "$")"#, "$1,234.50");
+        assert_string_result(r#"format_currency(-1234567.891, "EUR", 1)"#, "-1,234,567.9 EUR");
+        assert_string_result(r#"format_currency(999.995, "₽", 0)"#, "₽1,000");
+        assert_string_result(r#"format_currency(parse_currency("$1234.5678"), decimals=3)"#, "$1,234.568");
+        assert_string_result(r#"format_currency(-0.001, "$")"#, "$0.00");
+        assert_string_result(r#"to_number(parse_currency("$1,234.50"))"#, "1234.5");
+        assert_error_contains(r#"format_currency(10, "$", -1)"#, "decimals must be an integer");
+        assert_error_contains(r#"format_currency("10", "$")"#, "expects a number or a currency amount");
+    }
+}
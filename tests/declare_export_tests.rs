@@ -0,0 +1,169 @@
+// Тесты выборочного экспорта в SQLite: declare_export(), --tables и метаданные _datacode_variables
+#[cfg(test)]
+mod tests {
+    use data_code::sqlite_export::{export_to_sqlite, export_to_sqlite_with, parse_table_map, ExportOptions};
+    use data_code::run_with_vm;
+    use rusqlite::Connection;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const SCRIPT: &str = r#"
+        global sales = table([[1, 10, 99.5], [2, 11, 15.0], [3, 10, 7.25]], ["id", "customer_id", "amount"])
+        global customers = table([[10, "ann"], [11, "bob"]], ["customer_id", "name"])
+        global staging = table([[1, "tmp"]], ["id", "note"])
+        let scratch = table([["x", 1]], ["key", "value"])
+    "#;
+
+    fn db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("datacode_declare_export_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn table_names(conn: &Connection) -> Vec<String> {
+        conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn primary_key_columns(conn: &Connection, table: &str) -> Vec<String> {
+        conn.prepare(&format!("SELECT name FROM pragma_table_info('{}') WHERE pk > 0", table))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn with_tables(map: &[(String, String)]) -> ExportOptions {
+        ExportOptions { tables: Some(map.to_vec()), ..ExportOptions::default() }
+    }
+
+    fn metadata(conn: &Connection) -> Vec<(String, Option<String>)> {
+        conn.prepare("SELECT variable_name, table_name FROM _datacode_variables ORDER BY variable_name")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_only_declared_tables_are_exported() {
+        let source = format!(r#"{}
+            declare_export(sales, "fact_sales", "id")
+            declare_export(customers, "dim_customers", "customer_id")
+            declare_export(scratch, "lookup")
+        "#, SCRIPT);
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        let path = db_path("declared");
+        export_to_sqlite(&vm, path.to_str().unwrap()).expect("export failed");
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "dim_customers", "fact_sales", "lookup"]);
+        assert_eq!(primary_key_columns(&conn, "fact_sales"), vec!["id"]);
+        assert_eq!(primary_key_columns(&conn, "dim_customers"), vec!["customer_id"]);
+        let total: f64 = conn.query_row("SELECT SUM(amount) FROM fact_sales", [], |row| row.get(0)).unwrap();
+        assert_eq!(total, 121.75);
+
+        // Переменная DataCode и имя таблицы SQLite; неэкспортированная таблица без table_name
+        assert_eq!(
+            metadata(&conn),
+            vec![
+                ("customers".to_string(), Some("dim_customers".to_string())),
+                ("sales".to_string(), Some("fact_sales".to_string())),
+                ("scratch".to_string(), Some("lookup".to_string())),
+                ("staging".to_string(), None),
+            ]
+        );
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_without_declarations_everything_is_exported() {
+        let (_, vm) = run_with_vm(SCRIPT).expect("script failed");
+        let path = db_path("all");
+        export_to_sqlite(&vm, path.to_str().unwrap()).expect("export failed");
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "customers", "sales", "staging"]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_cli_table_map() {
+        let map = parse_table_map("sales:fact_sales, customers").unwrap();
+        assert_eq!(
+            map,
+            vec![
+                ("sales".to_string(), "fact_sales".to_string()),
+                ("customers".to_string(), "customers".to_string()),
+            ]
+        );
+        assert!(parse_table_map("sales:").is_err());
+        assert!(parse_table_map(" , ").is_err());
+
+        let (_, vm) = run_with_vm(SCRIPT).expect("script failed");
+        let path = db_path("cli");
+        export_to_sqlite_with(&vm, path.to_str().unwrap(), &with_tables(&map)).expect("export failed");
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "customers", "fact_sales"]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+
+        let missing = parse_table_map("nope:x").unwrap();
+        let error = export_to_sqlite_with(&vm, db_path("missing").to_str().unwrap(), &with_tables(&missing)).unwrap_err();
+        assert!(error.contains("'nope'"), "{}", error);
+    }
+
+    #[test]
+    fn test_cli_table_patterns() {
+        let map = parse_table_map("s*, customers:dim_customers").unwrap();
+        let (_, vm) = run_with_vm(SCRIPT).expect("script failed");
+        let path = db_path("pattern");
+        export_to_sqlite_with(&vm, path.to_str().unwrap(), &with_tables(&map)).expect("export failed");
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "dim_customers", "sales", "staging"]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+
+        // Шаблон без совпадений ничего не выбирает; шаблон нельзя переименовать
+        let map = parse_table_map("final_*").unwrap();
+        let error = export_to_sqlite_with(&vm, db_path("empty").to_str().unwrap(), &with_tables(&map)).unwrap_err();
+        assert!(error.contains("Нет таблиц для экспорта"), "{}", error);
+        assert!(parse_table_map("s*:facts").unwrap_err().contains("нельзя переименовать"));
+        assert!(parse_table_map("[s").is_err());
+    }
+
+    #[test]
+    fn test_declare_export_errors() {
+        for (call, expected) in [
+            (r#"declare_export([1, 2])"#, "declare_export() expects a table"),
+            (r#"declare_export(sales, "s", "missing")"#, "unknown primary key column: missing"),
+            (r#"declare_export(table([[1]], ["a"]))"#, "name is required"),
+        ] {
+            let message = format!("{}", run_with_vm(&format!("{}\n{}", SCRIPT, call)).err().unwrap());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+
+        // Повторное объявление под тем же именем заменяет прежнее
+        let source = format!("{}\ndeclare_export(sales, \"facts\")\ndeclare_export(staging, \"facts\")", SCRIPT);
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        let declarations = vm.get_export_declarations();
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(declarations[0].variable_name.as_deref(), Some("staging"));
+    }
+
+    #[test]
+    fn test_primary_key_builtin_is_recorded() {
+        let (_, vm) = run_with_vm(&format!("{}\nprimary_key(customers[\"customer_id\"])", SCRIPT)).expect("script failed");
+        let keys = vm.get_explicit_primary_keys();
+        assert_eq!(keys.len(), 1);
+        assert_eq!((keys[0].table_name.as_str(), keys[0].column_name.as_str()), ("customers", "customer_id"));
+    }
+}
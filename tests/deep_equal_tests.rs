@@ -0,0 +1,95 @@
+// Тесты структурного равенства: == для массивов, объектов и таблиц, deep_equal и same_reference
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn string_result(source: &str) -> String {
+        match run(source) {
+            Ok(Value::String(s)) => s.to_string(),
+            Ok(v) => panic!("Expected String, got {:?}", v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_nested_structures_compare_by_content() {
+        let source = r#"
+            let a = [1, [2, 3], {"x": [4, {"y": null}]}]
+            let b = [1, [2, 3], {"x": [4, {"y": null}]}]
+            let c = [1, [2, 3], {"x": [4, {"y": 0}]}]
+            str([a == b, a != b, a == c, a != c, [1, 2] == [1, 2, 3], [1] == [1.0]])
+        "#;
+        assert_eq!(string_result(source), "[true, false, false, true, false, true]");
+
+        // Объекты равны при одинаковом наборе ключей независимо от порядка добавления
+        let source = r#"
+            str([{"a": 1, "b": 2} == {"b": 2, "a": 1}, {"a": 1} == {"a": 1, "b": null}, deep_equal({"a": [1]}, {"a": [1]})])
+        "#;
+        assert_eq!(string_result(source), "[true, false, true]");
+    }
+
+    #[test]
+    fn test_nan_policy() {
+        // == следует IEEE 754: NaN не равен себе; deep_equal считает NaN равным NaN
+        let source = r#"
+            let nan = float("NaN")
+            str([nan == nan, [nan] == [nan], deep_equal(nan, nan), deep_equal([1, nan], [1, nan]), deep_equal(nan, 1)])
+        "#;
+        assert_eq!(string_result(source), "[false, false, true, true, false]");
+    }
+
+    #[test]
+    fn test_tables_differing_in_column_order() {
+        let source = r#"
+            let a = table([[1, "x"], [2, "y"]], ["id", "name"])
+            let b = table([["x", 1], ["y", 2]], ["name", "id"])
+            let c = table([[1, "x"], [2, "y"]], ["id", "name"])
+            let d = table([["x", 1], ["z", 2]], ["name", "id"])
+            str([a == c, a == b, deep_equal(a, b), deep_equal(a, b, true), deep_equal(a, d, true)])
+        "#;
+        assert_eq!(string_result(source), "[true, false, false, true, false]");
+    }
+
+    #[test]
+    fn test_same_reference() {
+        let source = r#"
+            let a = [1, 2]
+            let alias = a
+            let copy = [1, 2]
+            let t = table([[1]], ["id"])
+            str([same_reference(a, alias), same_reference(a, copy), a == copy, same_reference(t, t), same_reference(1, 1)])
+        "#;
+        assert_eq!(string_result(source), "[true, false, true, true, false]");
+    }
+
+    #[test]
+    fn test_depth_is_bounded() {
+        let build = r#"
+            fn nest(n) {
+                let value = []
+                for i in range(n) {
+                    value = [value]
+                }
+                return value
+            }
+        "#;
+        // Равные структуры в пределах лимита сравниваются как обычно
+        assert_eq!(string_result(&format!("{}\nstr(nest(100) == nest(100))", build)), "true");
+        // Глубже лимита == дает false, а deep_equal сообщает об ошибке вместо переполнения стека
+        assert_eq!(string_result(&format!("{}\nstr(nest(400) == nest(400))", build)), "false");
+        let message = error_message(&format!("{}\ndeep_equal(nest(400), nest(400))", build));
+        assert!(message.contains("deep_equal(): values are nested deeper than 256 levels"), "{}", message);
+        // Один и тот же массив равен себе без обхода
+        assert_eq!(string_result(&format!("{}\nlet deep = nest(400)\nstr(deep == deep)", build)), "true");
+    }
+
+    #[test]
+    fn test_deep_equal_argument_errors() {
+        let message = error_message(r#"deep_equal([1], [1], "yes")"#);
+        assert!(message.contains("ignore_column_order must be a boolean"), "{}", message);
+    }
+}
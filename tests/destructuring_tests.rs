@@ -0,0 +1,73 @@
+// Тесты распаковки: let/global/присваивание нескольких имен из массива и return a, b
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn eval_string(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_string(),
+            Err(e) => panic!("{}\n=> {}", source, e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    const LOAD: &str = "fn load_and_validate(rows) {\n    let t = table(rows, [\"id\", \"amount\"])\n    let report = {\"rows\": len(t), \"ok\": len(t) > 0}\n    return t, report\n}\n";
+
+    #[test]
+    fn test_destructuring_at_top_level() {
+        let source = format!("{}global t, report = load_and_validate([[1, 10], [2, 20]])\n[len(t), t[\"amount\"][1], report[\"rows\"], report[\"ok\"]]", LOAD);
+        assert_eq!(eval_string(&source), "[2, 20, 2, true]");
+        // Любой массив подходящей длины, в том числе результат обычного return
+        assert_eq!(eval_string("let a, b, c = [1, \"x\", [2]]\n[c, b, a]"), "[[2], x, 1]");
+        // Правая часть вычисляется до сохранения: обмен значений без временной переменной
+        assert_eq!(eval_string("let a = 1\nlet b = 2\na, b = [b, a]\n[a, b]"), "[2, 1]");
+        // Распаковка - инструкция, а не выражение
+        assert!(run("let x = a, b").is_err());
+    }
+
+    #[test]
+    fn test_destructuring_inside_functions() {
+        let source = "fn min_max(xs) {\n    let lo, hi = [min(...xs), max(...xs)]\n    return lo, hi - lo\n}\nfn describe(xs) {\n    let lo, spread = min_max(xs)\n    return str(lo) + \"+\" + str(spread)\n}\ndescribe([4, 9, 6])";
+        assert_eq!(eval_string(source), "4+5");
+        // global внутри функции создает глобальные переменные
+        let source = format!("{}fn init() {{\n    global t, report = load_and_validate([[1, 5]])\n}}\ninit()\n[len(t), report[\"rows\"]]", LOAD);
+        assert_eq!(eval_string(&source), "[1, 1]");
+        // Присваивание меняет переменные, объявленные снаружи
+        let source = "let total = 0\nlet last = null\nfn record(x) {\n    total, last = [total + x, x]\n}\nrecord(3)\nrecord(4)\n[total, last]";
+        assert_eq!(eval_string(source), "[7, 4]");
+        // Вложенная функция видит распакованные локальные переменные родителя
+        let source = "fn outer() {\n    let a, b = [2, 3]\n    fn inner() {\n        return a * b\n    }\n    return inner()\n}\nouter()";
+        assert_eq!(run(source).unwrap(), Value::Number(6.0));
+    }
+
+    #[test]
+    fn test_destructuring_inside_loops() {
+        let source = "fn divmod(a, b) {\n    return a // b, a % b\n}\nlet out = []\nfor n in [7, 9, 12] {\n    let q, r = divmod(n, 5)\n    push(out, q * 10 + r)\n}\nout";
+        assert_eq!(eval_string(source), "[12, 14, 22]");
+        // Фибоначчи присваиванием пар в while
+        let source = "let a = 0\nlet b = 1\nlet i = 0\nwhile i < 10 {\n    a, b = [b, a + b]\n    i += 1\n}\na";
+        assert_eq!(run(source).unwrap(), Value::Number(55.0));
+        let source = "fn pairs() {\n    let s = 0\n    for p in [[1, 2], [3, 4]] {\n        let x, y = p\n        s += x * y\n    }\n    return s\n}\npairs()";
+        assert_eq!(run(source).unwrap(), Value::Number(14.0));
+    }
+
+    #[test]
+    fn test_destructuring_errors() {
+        for (source, expected) in [
+            ("let a, b = [1, 2, 3]", "Cannot unpack 3 values into 2 variables"),
+            ("fn one() {\n    return [1]\n}\nglobal a, b, c = one()", "Cannot unpack 1 value into 3 variables"),
+            ("let a, b = 5", "Cannot unpack int into 2 variables, expected an array"),
+            ("let a, a = [1, 2]", "Variable 'a' is listed twice in destructuring assignment"),
+            ("let a, = [1, 2]", "Expect variable name after ','"),
+        ] {
+            let message = error_message(source);
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+        // Несовпадение длины - ValueError, его можно перехватить
+        let source = "let r = \"none\"\nfor xs in [[1, 2], [1, 2, 3]] {\n    try {\n        let a, b = xs\n        r = \"ok\"\n    } catch ValueError e {\n        r = \"caught\"\n    }\n}\nr";
+        assert_eq!(run(source).unwrap(), Value::String("caught".into()));
+    }
+}
@@ -0,0 +1,154 @@
+// Тесты для машиночитаемых ошибок (run_collect, LangError::to_json) и текста ошибок вызова функций
+#[cfg(test)]
+mod tests {
+    use data_code::common::suggest::{closest_match, levenshtein};
+    use data_code::{run, run_collect, StructuredError};
+
+    fn error_message(source: &str) -> String {
+        match run(source) {
+            Ok(value) => panic!("Expected error, got {}", value.to_string()),
+            Err(e) => format!("{}", e),
+        }
+    }
+
+    fn collect_error(source: &str) -> StructuredError {
+        match run_collect(source) {
+            Ok(value) => panic!("Expected error, got {}", value.to_string()),
+            Err(e) => *e,
+        }
+    }
+
+    #[test]
+    fn test_runtime_error_fields_inside_function() {
+        let source = r#"
+            fn inner() {
+                let x = 10 / 0
+                return x
+            }
+            fn outer() {
+                return inner()
+            }
+            outer()
+        "#;
+        let error = collect_error(source);
+        assert_eq!(error.kind, "RuntimeError");
+        assert_eq!(error.message, "Division by zero");
+        assert_eq!(error.line, Some(3));
+        assert_eq!(error.column, None);
+        assert_eq!(error.function.as_deref(), Some("inner"));
+        assert_eq!(error.snippet.as_deref(), Some("let x = 10 / 0"));
+    }
+
+    #[test]
+    fn test_typed_and_thrown_error_kinds() {
+        let error = collect_error("let t = [1, 2]\nt[5]");
+        assert_eq!(error.kind, "IndexError");
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.snippet.as_deref(), Some("t[5]"));
+
+        let error = collect_error(r#"throw {kind: "ValidationError", message: "age must be positive"}"#);
+        assert_eq!(error.kind, "ValidationError");
+        assert_eq!(error.message, "age must be positive");
+    }
+
+    #[test]
+    fn test_parse_error_has_no_function() {
+        let error = collect_error("let x = 1\nlet y = (2 +\n");
+        assert_eq!(error.kind, "ParseError");
+        assert!(error.line.is_some());
+        assert_eq!(error.function, None);
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let error = collect_error("let x = 1\nx - \"a\"");
+        let json = error.to_json();
+        let object = json.as_object().expect("structured error is a JSON object");
+        let keys: Vec<&str> = object.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["kind", "message", "line", "column", "function", "snippet"]);
+        assert_eq!(object["line"], 2);
+        assert!(object["column"].is_null());
+        assert_eq!(object["snippet"], "x - \"a\"");
+
+        // LangError::to_json не знает исходник, поэтому snippet - null
+        let json = run("let x = 1\nx - \"a\"").unwrap_err().to_json();
+        assert_eq!(json["kind"], error.kind.as_str());
+        assert!(json["snippet"].is_null());
+    }
+
+    #[test]
+    fn test_successful_run_and_warning() {
+        assert_eq!(run_collect("1 + 2").unwrap().to_string(), "3");
+
+        let warning = StructuredError::warning("file 'a.txt' does not have the .dc extension".to_string());
+        assert_eq!(warning.kind, "Warning");
+        assert_eq!(warning.line, None);
+        assert_eq!(
+            warning.to_string(),
+            r#"{"kind":"Warning","message":"file 'a.txt' does not have the .dc extension","line":null,"column":null,"function":null,"snippet":null}"#
+        );
+    }
+
+    #[test]
+    fn test_levenshtein_and_closest_match() {
+        assert_eq!(levenshtein("tabel_where", "table_where"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("путь", "пути"), 1);
+        assert_eq!(closest_match("lenn", ["len", "lower", "pop"]), Some("len"));
+        assert_eq!(closest_match("zz", ["len", "pop"]), None);
+    }
+
+    #[test]
+    fn test_function_not_found_suggests_close_names() {
+        let message = error_message("let t = table([[1]], [\"a\"])\ntabel_where(t, \"a > 0\")");
+        assert!(message.contains("Function 'tabel_where' not found, did you mean 'table_where'?"), "{}", message);
+        assert!(error_message("uper(\"a\")").contains("did you mean 'upper'?"));
+
+        // Пользовательские функции тоже предлагаются
+        let source = r#"
+            fn normalize_row(row) {
+                return row
+            }
+            normalise_row(1)
+        "#;
+        assert!(error_message(source).contains("did you mean 'normalize_row'?"));
+    }
+
+    #[test]
+    fn test_function_not_found_without_close_names() {
+        let message = error_message("completely_unrelated_name(1)");
+        assert!(message.contains("Function 'completely_unrelated_name' not found"), "{}", message);
+        assert!(!message.contains("did you mean"), "{}", message);
+    }
+
+    #[test]
+    fn test_builtin_argument_count_shows_signature() {
+        let message = error_message("pow(2)");
+        assert!(message.contains("pow(base, exp) expects 2 arguments, got 1"), "{}", message);
+        let message = error_message("let t = table([[1]], [\"a\"])\ntable_where(t)");
+        assert!(message.contains("table_where(table, col, op?, value?) expects 2 to 4 arguments, got 1"), "{}", message);
+        let message = error_message("has_key({a: 1})");
+        assert!(message.contains("has_key(object, key) expects 2 arguments, got 1"), "{}", message);
+        let message = error_message("path_exists()");
+        assert!(message.contains("path_exists(path) expects 1 argument, got 0"), "{}", message);
+        let message = error_message("table_head()");
+        assert!(message.contains("table_head(table, n?) expects 1 to 2 arguments, got 0"), "{}", message);
+    }
+
+    #[test]
+    fn test_argument_count_check_leaves_valid_calls_alone() {
+        assert_eq!(run("len(range(3))").unwrap().to_string(), "3");
+        assert_eq!(run("str(get({a: 1}, \"b\", default=2))").unwrap().to_string(), "2");
+        // Локальная переменная с именем встроенной функции не проверяется по сигнатуре
+        let source = r#"
+            fn apply(pow) {
+                return pow(3)
+            }
+            fn cube(x) {
+                return x * x * x
+            }
+            apply(cube)
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "27");
+    }
+}
@@ -0,0 +1,188 @@
+// Тесты для динамического выполнения кода через eval() и exec()
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+    use data_code::vm::policy::{set_execution_policy, reset_execution_policy, ExecutionPolicy};
+
+    fn assert_number_result(source: &str, expected: f64) {
+        match run(source) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected, "Expected {}, got {}", expected, n),
+            Ok(Value::Integer(i)) => assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            Ok(v) => panic!("Expected String({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_eval_expression_with_globals() {
+        let source = r#"
+            let x = 10
+            eval("x * 2 + 1")
+        "#;
+        assert_number_result(source, 21.0);
+    }
+
+    #[test]
+    fn test_eval_reads_function_locals() {
+        let source = r#"
+            fn f(a) {
+                let b = 5
+                return eval("a + b")
+            }
+            f(3)
+        "#;
+        assert_number_result(source, 8.0);
+    }
+
+    #[test]
+    fn test_exec_modifies_caller_locals() {
+        let source = r#"
+            fn f(a) {
+                let b = 5
+                exec("b = b + a")
+                exec("a = 100")
+                return a + b
+            }
+            f(3)
+        "#;
+        assert_number_result(source, 108.0);
+    }
+
+    #[test]
+    fn test_exec_in_loop_modifies_variable() {
+        let source = r#"
+            let s = 0
+            for i in range(4) {
+                exec("s = s + i")
+            }
+            s
+        "#;
+        assert_number_result(source, 6.0);
+    }
+
+    #[test]
+    fn test_exec_defines_global_variable() {
+        let source = r#"
+            exec("z = 42")
+            eval("z + 1")
+        "#;
+        assert_number_result(source, 43.0);
+    }
+
+    #[test]
+    fn test_exec_defines_function() {
+        let source = r#"
+            exec("fn triple(n) { return n * 3 }")
+            eval("triple(4)")
+        "#;
+        assert_number_result(source, 12.0);
+    }
+
+    #[test]
+    fn test_exec_returns_null() {
+        let result = run(r#"exec("let q = 1")"#);
+        assert!(matches!(result, Ok(Value::Null)), "Expected Null, got {:?}", result);
+    }
+
+    #[test]
+    fn test_nested_eval() {
+        assert_number_result(r#"eval("eval(\"1 + 1\") * 10")"#, 20.0);
+    }
+
+    #[test]
+    fn test_eval_rejects_statements() {
+        let result = run(r#"eval("let a = 1")"#);
+        assert!(result.is_err());
+        let message = format!("{}", result.unwrap_err());
+        assert!(message.contains("use exec()"), "Unexpected message: {}", message);
+    }
+
+    #[test]
+    fn test_eval_syntax_error_is_catchable() {
+        let source = r#"
+            let result = ""
+            try {
+                eval("1 +")
+            } catch SyntaxError e {
+                result = e
+            }
+            result
+        "#;
+        let result = run(source).unwrap();
+        match result {
+            Value::String(message) => {
+                assert!(message.contains("SyntaxError"), "Unexpected message: {}", message);
+                assert!(message.contains("in eval(\"1 +\")"), "Unexpected message: {}", message);
+            }
+            v => panic!("Expected String, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_exec_runtime_error_keeps_type() {
+        let source = r#"
+            let result = "none"
+            try {
+                exec("let a = [1, 2]
+                      a[10]")
+            } catch IndexError e {
+                result = "index"
+            }
+            result
+        "#;
+        assert_string_result(source, "index");
+    }
+
+    #[test]
+    fn test_try_catch_inside_exec() {
+        let source = r#"
+            let result = 0
+            exec("try { throw 'boom' } catch e { result = 1 }")
+            result
+        "#;
+        assert_number_result(source, 1.0);
+    }
+
+    #[test]
+    fn test_uncaught_error_in_exec() {
+        let result = run(r#"exec("throw 'boom'")"#);
+        assert!(result.is_err());
+        let message = format!("{}", result.unwrap_err());
+        assert!(message.contains("in exec(\"throw 'boom'\")"), "Unexpected message: {}", message);
+        assert!(message.contains("boom"));
+    }
+
+    #[test]
+    fn test_dynamic_eval_depth_limit() {
+        let source = r#"
+            let code = "exec(code)"
+            let result = "none"
+            try {
+                exec(code)
+            } catch e {
+                result = "limit"
+            }
+            result
+        "#;
+        assert_string_result(source, "limit");
+    }
+
+    #[test]
+    fn test_dynamic_eval_disabled_by_policy() {
+        set_execution_policy(ExecutionPolicy { allow_dynamic_eval: false, ..ExecutionPolicy::default() });
+        let result = run(r#"eval("1 + 1")"#);
+        reset_execution_policy();
+        assert!(result.is_err());
+        let message = format!("{}", result.unwrap_err());
+        assert!(message.contains("PermissionError"), "Unexpected message: {}", message);
+    }
+}
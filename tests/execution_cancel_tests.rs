@@ -0,0 +1,98 @@
+// Тесты кооперативной отмены выполнения: Vm::request_cancel и флаг отмены из другого потока
+#[cfg(test)]
+mod tests {
+    use data_code::vm::policy::{is_cancelled_error, is_timeout_error};
+    use data_code::{compile, run_with_cancel, Value, Vm};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Запускает код и выставляет флаг отмены из другого потока через delay
+    fn run_cancelled_after(source: &str, delay: Duration) -> Result<Value, data_code::LangError> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        let canceller = thread::spawn(move || {
+            thread::sleep(delay);
+            flag.store(true, Ordering::SeqCst);
+        });
+        let result = run_with_cancel(source, cancel);
+        canceller.join().unwrap();
+        result
+    }
+
+    fn assert_cancelled(source: &str) {
+        let started = Instant::now();
+        let error = run_cancelled_after(source, Duration::from_millis(100)).unwrap_err();
+        assert!(is_cancelled_error(&error), "{}", error);
+        assert!(!is_timeout_error(&error), "{}", error);
+        assert!(format!("{}", error).contains("execution cancelled"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_infinite_loop_is_cancelled() {
+        assert_cancelled("let i = 0\nwhile true {\n    i = i + 1\n}");
+        // range() строит массив целиком: 10^7 элементов хватает, чтобы цикл шел дольше задержки,
+        // и не требует гигабайтов памяти
+        assert_cancelled("for i in range(10000000) {\n}");
+    }
+
+    #[test]
+    fn test_cancel_inside_function_and_eval() {
+        assert_cancelled(r#"
+            fn spin(n) {
+                while true {
+                    n = n + 1
+                }
+            }
+            spin(0)
+        "#);
+        assert_cancelled(r#"exec("while true { }")"#);
+    }
+
+    #[test]
+    fn test_cancel_is_not_caught_by_try() {
+        assert_cancelled(r#"
+            while true {
+                try {
+                    while true {
+                    }
+                } catch e {
+                }
+            }
+        "#);
+    }
+
+    #[test]
+    fn test_request_cancel_and_clear() {
+        let (chunk, functions) = compile("let s = 0\nfor i in range(5000) {\n    s = s + i\n}\nstr(s)").unwrap();
+        let mut vm = Vm::new();
+        vm.set_functions(functions);
+        vm.register_native_globals();
+
+        // Флаг, выставленный до запуска, прерывает программу на первой проверке
+        vm.request_cancel();
+        assert!(vm.is_cancel_requested());
+        let error = vm.run(&chunk).unwrap_err();
+        assert!(is_cancelled_error(&error), "{}", error);
+
+        // После сброса та же программа выполняется полностью
+        let (chunk, functions) = compile("let s = 0\nfor i in range(5000) {\n    s = s + i\n}\nstr(s)").unwrap();
+        let mut vm = Vm::new();
+        let handle = vm.cancel_handle();
+        vm.set_functions(functions);
+        vm.register_native_globals();
+        handle.store(true, Ordering::SeqCst);
+        vm.clear_cancel();
+        assert!(!handle.load(Ordering::SeqCst));
+        let result = vm.run(&chunk);
+        assert!(matches!(result, Ok(Value::String(ref s)) if &**s == "12497500"), "{:?}", result);
+    }
+
+    #[test]
+    fn test_fast_program_is_not_affected() {
+        let result = run_with_cancel("str(1 + 2)", Arc::new(AtomicBool::new(false)));
+        assert!(matches!(result, Ok(Value::String(ref s)) if &**s == "3"), "{:?}", result);
+    }
+}
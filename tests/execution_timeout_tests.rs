@@ -0,0 +1,71 @@
+// Тесты для ExecutionPolicy::timeout: прерывание зациклившейся программы
+#[cfg(test)]
+mod tests {
+    use data_code::vm::policy::{is_timeout_error, reset_execution_policy, set_execution_policy, ExecutionPolicy};
+    use data_code::{run, Value};
+    use std::time::{Duration, Instant};
+
+    fn run_with_timeout(source: &str, timeout: Duration) -> Result<Value, data_code::LangError> {
+        set_execution_policy(ExecutionPolicy { timeout: Some(timeout), ..ExecutionPolicy::default() });
+        let result = run(source);
+        reset_execution_policy();
+        result
+    }
+
+    fn assert_times_out(source: &str) {
+        let started = Instant::now();
+        let error = run_with_timeout(source, Duration::from_millis(200)).unwrap_err();
+        assert!(is_timeout_error(&error), "{}", error);
+        assert!(format!("{}", error).contains("execution timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_infinite_while_loop_times_out() {
+        assert_times_out("let i = 0\nwhile true {\n    i = i + 1\n}");
+    }
+
+    #[test]
+    fn test_infinite_recursion_in_loop_times_out() {
+        assert_times_out(r#"
+            fn spin(n) {
+                return n + 1
+            }
+            let i = 0
+            while true {
+                i = spin(i)
+            }
+        "#);
+    }
+
+    #[test]
+    fn test_timeout_is_not_caught_by_try() {
+        assert_times_out(r#"
+            while true {
+                try {
+                    while true {
+                    }
+                } catch e {
+                }
+            }
+        "#);
+    }
+
+    #[test]
+    fn test_timeout_inside_callback_and_eval() {
+        assert_times_out(r#"
+            fn slow(x) {
+                while true {
+                }
+            }
+            map(slow, [1, 2, 3])
+        "#);
+        assert_times_out(r#"exec("while true { }")"#);
+    }
+
+    #[test]
+    fn test_fast_program_is_not_affected() {
+        let result = run_with_timeout("let s = 0\nfor i in range(1000) {\n    s = s + i\n}\nstr(s)", Duration::from_secs(10));
+        assert!(matches!(result, Ok(Value::String(ref s)) if &**s == "499500"), "{:?}", result);
+    }
+}
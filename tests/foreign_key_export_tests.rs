@@ -0,0 +1,134 @@
+// Тесты внешних ключей при экспорте в SQLite: register_foreign_key(), отключение автоопределения
+// и отметка явных/найденных автоматически ключей в _datacode_variables
+#[cfg(test)]
+mod tests {
+    use data_code::run_with_vm;
+    use data_code::sqlite_export::{export_to_sqlite, export_to_sqlite_with, ExportOptions};
+    use rusqlite::Connection;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // orders.customer_id автоопределение связывает с customers.id; buyer_ref без объявления не связывается
+    const SCRIPT: &str = r#"
+        global customers = table([[10, "ann"], [11, "bob"]], ["id", "name"])
+        global orders = table([[1, 10, 11], [2, 11, 10]], ["id", "customer_id", "buyer_ref"])
+    "#;
+
+    fn db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("datacode_fk_export_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Внешние ключи таблицы: (колонка, таблица, колонка таблицы)
+    fn foreign_keys(conn: &Connection, table: &str) -> Vec<(String, String, String)> {
+        conn.prepare(&format!("SELECT \"from\", \"table\", \"to\" FROM pragma_foreign_key_list('{}') ORDER BY \"from\"", table))
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn metadata_foreign_keys(conn: &Connection, variable: &str) -> serde_json::Value {
+        let json: String = conn
+            .query_row("SELECT foreign_keys FROM _datacode_variables WHERE variable_name = ?1", [variable], |row| row.get(0))
+            .unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn fk(column: &str, table: &str, to: &str) -> (String, String, String) {
+        (column.to_string(), table.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn test_explicit_and_inferred_keys() {
+        let source = format!("{}\nregister_foreign_key(orders, \"buyer_ref\", customers, \"id\")", SCRIPT);
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        let path = db_path("both");
+        export_to_sqlite(&vm, path.to_str().unwrap()).expect("export failed");
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(
+            foreign_keys(&conn, "orders"),
+            vec![fk("buyer_ref", "customers", "id"), fk("customer_id", "customers", "id")]
+        );
+        assert_eq!(
+            metadata_foreign_keys(&conn, "orders"),
+            serde_json::json!([
+                {"column": "buyer_ref", "references_table": "customers", "references_column": "id", "explicit": true},
+                {"column": "customer_id", "references_table": "customers", "references_column": "id", "explicit": false}
+            ])
+        );
+        assert_eq!(metadata_foreign_keys(&conn, "customers"), serde_json::json!([]));
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_explicit_key_overrides_detection() {
+        // customer_id ссылается на accounts.id вместо найденного автоматически customers.id
+        let source = format!(
+            "{}\nglobal accounts = table([[10], [11]], [\"id\"])\nregister_foreign_key(orders, \"customer_id\", accounts, \"id\")",
+            SCRIPT
+        );
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        let path = db_path("override");
+        export_to_sqlite(&vm, path.to_str().unwrap()).expect("export failed");
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(foreign_keys(&conn, "orders"), vec![fk("customer_id", "accounts", "id")]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_auto_detection_can_be_disabled() {
+        let options = ExportOptions { auto_foreign_keys: false, ..ExportOptions::default() };
+
+        let (_, vm) = run_with_vm(SCRIPT).expect("script failed");
+        let path = db_path("no_auto");
+        export_to_sqlite_with(&vm, path.to_str().unwrap(), &options).expect("export failed");
+        let conn = Connection::open(&path).unwrap();
+        assert!(foreign_keys(&conn, "orders").is_empty());
+        drop(conn);
+        let _ = fs::remove_file(&path);
+
+        // Явные ключи сохраняются и без автоопределения
+        let source = format!("{}\nregister_foreign_key(orders, \"buyer_ref\", customers, \"id\")", SCRIPT);
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        let path = db_path("no_auto_explicit");
+        export_to_sqlite_with(&vm, path.to_str().unwrap(), &options).expect("export failed");
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(foreign_keys(&conn, "orders"), vec![fk("buyer_ref", "customers", "id")]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_register_foreign_key_errors() {
+        for (call, expected) in [
+            (
+                r#"register_foreign_key(orders, "buyer_ref", customers)"#,
+                "register_foreign_key(child_table, child_col, parent_table, parent_col) expects 4 arguments, got 3",
+            ),
+            (r#"register_foreign_key(orders, "missing", customers, "id")"#, "unknown column: missing"),
+            (r#"register_foreign_key(orders, "buyer_ref", customers, "missing")"#, "unknown column: missing"),
+            (
+                r#"register_foreign_key(orders, "buyer_ref", table([[1]], ["id"]), "id")"#,
+                "both tables must be stored in global variables",
+            ),
+        ] {
+            let message = format!("{}", run_with_vm(&format!("{}\n{}", SCRIPT, call)).err().unwrap());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+
+        // Ошибку можно перехватить
+        let source = format!(
+            "{}\nglobal caught = false\ntry {{\n    register_foreign_key(orders, \"id\", table([[1]], [\"id\"]), \"id\")\n}} catch e {{\n    caught = true\n}}",
+            SCRIPT
+        );
+        let (_, vm) = run_with_vm(&source).expect("script failed");
+        assert!(vm.get_explicit_relations().is_empty());
+    }
+}
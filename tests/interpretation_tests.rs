@@ -12,6 +12,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -28,7 +32,7 @@ mod tests {
         let result = run(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, expected, "Expected '{}', got '{}'", expected, s);
+                assert_eq!(&*s, expected, "Expected '{}', got '{}'", expected, s);
             }
             Ok(v) => panic!("Expected String('{}'), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
@@ -304,7 +308,7 @@ mod tests {
         let result = run(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, "hello\nworld\t!", "Expected special chars");
+                assert_eq!(&*s, "hello\nworld\t!", "Expected special chars");
             }
             Ok(v) => panic!("Expected String, got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
@@ -612,6 +616,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unary_minus_binds_looser_than_exponent() {
+        // -x ** 2 = -(x ** 2)
+        assert_number_result("let x = 3\nlet r = -x ** 2\nr", -9.0);
+        assert_number_result("-2 ** 2", -4.0);
+        assert_number_result("(-2) ** 2", 4.0);
+        // Унарный минус в показателе степени
+        assert_number_result("2 ** -1", 0.5);
+        assert_number_result("2 ** -1 ** 2", 0.5);
+    }
+
+    #[test]
+    fn test_double_negation() {
+        assert_number_result("let x = 4\nlet r = --x\nr", 4.0);
+        assert_number_result("let x = 4\nlet r = - -x\nr", 4.0);
+        assert_bool_result("!!true", true);
+        assert_bool_result("not not true", true);
+        assert_bool_result("not not 0", false);
+    }
+
+    #[test]
+    fn test_not_keyword() {
+        assert_bool_result("not true", false);
+        assert_bool_result("not 0", true);
+        assert_bool_result("not \"\"", true);
+    }
+
+    #[test]
+    fn test_not_binds_looser_than_comparison() {
+        // not a == b = not (a == b)
+        assert_bool_result("let a = 1\nlet b = 2\nnot a == b", true);
+        assert_bool_result("let a = 2\nlet b = 2\nnot a == b", false);
+        assert_bool_result("not 1 < 2", false);
+        // но сильнее and / or
+        assert_bool_result("not true or true", true);
+        assert_bool_result("not false and false", false);
+    }
+
+    #[test]
+    fn test_bang_binds_tighter_than_comparison() {
+        // !a == b = (!a) == b
+        assert_bool_result("!1 == false", true);
+        assert_bool_result("!0 == false", false);
+    }
+
     // ========== Тесты для логических операций ==========
 
     #[test]
@@ -1586,10 +1635,10 @@ mod tests {
         "#;
         let result = run(source);
         match result {
-            Ok(Value::Number(n)) => {
-                assert_eq!(n, 55.0, "fibonacci(10) should be 55");
+            Ok(Value::Integer(n)) => {
+                assert_eq!(n, 55, "fibonacci(10) should be 55");
             }
-            Ok(v) => panic!("Expected Number(55), got {:?}", v),
+            Ok(v) => panic!("Expected Integer(55), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
@@ -1608,10 +1657,10 @@ mod tests {
         "#;
         let result = run(source);
         match result {
-            Ok(Value::Number(n)) => {
-                assert_eq!(n, 3.0, "add(1, 2) should be 3");
+            Ok(Value::Integer(n)) => {
+                assert_eq!(n, 3, "add(1, 2) should be 3");
             }
-            Ok(v) => panic!("Expected Number(3), got {:?}", v),
+            Ok(v) => panic!("Expected Integer(3), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
@@ -1629,7 +1678,7 @@ mod tests {
         let result = run(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, "helloworld", "concat('hello', 'world') should be 'helloworld'");
+                assert_eq!(&*s, "helloworld", "concat('hello', 'world') should be 'helloworld'");
             }
             Ok(v) => panic!("Expected String('helloworld'), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
@@ -1647,10 +1696,10 @@ mod tests {
         "#;
         let result = run(source);
         match result {
-            Ok(Value::Number(n)) => {
-                assert_eq!(n, 3.0, "add(1, 2) should be 3");
+            Ok(Value::Integer(n)) => {
+                assert_eq!(n, 3, "add(1, 2) should be 3");
             }
-            Ok(v) => panic!("Expected Number(3), got {:?}", v),
+            Ok(v) => panic!("Expected Integer(3), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
@@ -1670,14 +1719,245 @@ mod tests {
         "#;
         let result = run(source);
         match result {
-            Ok(Value::Number(n)) => {
-                assert_eq!(n, 120.0, "factorial(5) should be 120");
+            Ok(Value::Integer(n)) => {
+                assert_eq!(n, 120, "factorial(5) should be 120");
             }
-            Ok(v) => panic!("Expected Number(120), got {:?}", v),
+            Ok(v) => panic!("Expected Integer(120), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
 
+    #[test]
+    fn test_cache_stats_clear_and_opt_out() {
+        // Функция с побочным эффектом: журнал показывает, сколько раз она действительно выполнялась
+        let prelude = r#"
+        global log = []
+        @cache
+        fn tick(n) {
+            push(log, n)
+            return n * 2
+        }
+        "#;
+        let source = format!(r#"{}
+        tick(1)
+        tick(1)
+        let before = cache_stats("tick")
+        let previous = set_memoization("tick", false)
+        tick(1)
+        tick(1)
+        let after = cache_stats(tick)
+        let total = cache_stats()
+        [len(log), before["hits"], before["misses"], before["entries"], previous, after["enabled"], after["entries"], total["functions"]["tick"]["misses"], cache_clear()]
+        "#, prelude);
+        assert_eq!(run(&source).unwrap().to_string(), "[3, 1, 1, 1, true, false, 0, 1, 0]");
+
+        let source = format!(r#"{}
+        set_memoization(tick, false)
+        let previous = set_memoization(tick, true)
+        tick(2)
+        tick(2)
+        let hits = cache_stats("tick")["hits"]
+        let removed = cache_clear("tick")
+        [previous, len(log), hits, removed, cache_stats()["entries"]]
+        "#, prelude);
+        assert_eq!(run(&source).unwrap().to_string(), "[false, 1, 1, 1, 0]");
+
+        for (call, expected) in [
+            (r#"cache_stats("nope")"#, "cache_stats(): unknown function: nope"),
+            (r#"set_memoization("tick", "no")"#, "set_memoization() expects a function name and true or false"),
+            ("cache_clear(5)", "cache_clear() expects a function name or no arguments"),
+        ] {
+            let message = format!("{}", run(&format!("{}\n{}", prelude, call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_cache_not_stale_after_in_place_mutation() {
+        // Массив и таблица меняются на месте между двумя одинаковыми вызовами:
+        // второй вызов должен увидеть новое содержимое, а не результат из кэша
+        // (для массива сохраняются два результата - по отпечатку каждого содержимого)
+        let source = r#"
+        @cache
+        fn total(xs) {
+            return sum(xs)
+        }
+        @cache
+        fn first_column(t) {
+            return t["columns"][0]
+        }
+        let xs = [1, 2]
+        let before_push = total(xs)
+        push(xs, 3)
+        let after_push = total(xs)
+        let t = table([[1, 2]], ["a", "b"])
+        let before_rename = first_column(t)
+        table_rename_map(t, upper)
+        let after_rename = first_column(t)
+        [before_push, after_push, before_rename, after_rename, cache_stats(total)["entries"], cache_stats(first_column)["hits"]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[3, 6, a, A, 2, 0]");
+    }
+
+    #[test]
+    fn test_cache_table_arguments_by_fingerprint() {
+        // Большая таблица: ключ - ссылка на таблицу и счетчик изменений, кэш не хранит копию
+        // таблицы; маленькие таблицы сравниваются по содержимому
+        let source = r#"
+        let rows = []
+        for i in range(2000) {
+            push(rows, [i, i * 2])
+        }
+        let big = table(rows, ["a", "b"])
+        @cache
+        fn first_header(t) {
+            return t["columns"][0]
+        }
+        let first = first_header(big)
+        let second = first_header(big)
+        let before = cache_stats(first_header)
+        table_rename_map(big, upper)
+        let renamed = first_header(big)
+        let after = cache_stats(first_header)
+        first_header(table([[1]], ["x"]))
+        first_header(table([[1]], ["x"]))
+        [first, second, before["hits"], before["bytes"] < 1000, renamed, after["hits"], after["misses"], cache_stats(first_header)["hits"]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[a, a, 1, true, A, 1, 2, 2]");
+    }
+
+    #[test]
+    fn test_cache_key_compares_arguments_exactly() {
+        // Равные по содержимому массивы попадают в одну запись, а изменение большой таблицы
+        // любой встроенной функцией на месте (не только переименование) дает новый ключ
+        let source = r#"
+        let rows = []
+        for i in range(2000) {
+            push(rows, [i, i * 2])
+        }
+        push(rows, [2000, null])
+        let big = table(rows, ["a", "b"])
+        @cache
+        fn last_b(t) {
+            let column = t["b"]
+            return column[len(column) - 1]
+        }
+        @cache
+        fn total(xs) {
+            return sum(xs)
+        }
+        let before = last_b(big)
+        table_fill_nulls(big, {"b": 0})
+        let after = last_b(big)
+        total([1, 2, 3])
+        total([1, 2, 3])
+        total([1, 2, 4])
+        [before, after, cache_stats(last_b)["hits"], cache_stats(total)["hits"], cache_stats(total)["entries"]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[null, 0, 0, 1, 2]");
+    }
+
+    #[test]
+    fn test_cache_key_keeps_object_key_order() {
+        // Порядок ключей виден в результате: объекты с разным порядком - разные записи кэша
+        let source = r#"
+        @cache
+        fn show(o) {
+            return str(o)
+        }
+        [show({"a": 1, "b": 2}), show({"b": 2, "a": 1}), show({"a": 1, "b": 2}), cache_stats(show)["hits"]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), r#"[{"a": 1, "b": 2}, {"b": 2, "a": 1}, {"a": 1, "b": 2}, 1]"#);
+    }
+
+    #[test]
+    fn test_cache_lru_eviction_and_limits() {
+        let source = r#"
+        @cache
+        fn block(n) {
+            let xs = []
+            for i in range(200) {
+                push(xs, n)
+            }
+            return xs
+        }
+        block(1)
+        let one = cache_stats(block)["bytes"]
+        set_option("cache_max_bytes", one * 2 + 100)
+        block(2)
+        block(1)
+        block(3)
+        let after = cache_stats(block)
+        block(1)
+        block(2)
+        let final = cache_stats(block)
+        set_option("cache_max_result_bytes", 1000)
+        block(4)
+        let previous = set_option("cache_max_result_bytes", null)
+        [after["entries"], after["evictions"], after["bytes"] <= one * 2 + 100, final["hits"], final["misses"], cache_stats(block)["entries"], previous]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[2, 1, true, 2, 4, 2, 1000]");
+
+        let message = format!("{}", run(r#"set_option("cache_max_entries", 0)"#).unwrap_err());
+        assert!(message.contains("cache_max_entries must be a positive integer or null"), "{}", message);
+    }
+
+    #[test]
+    fn test_cache_skipped_for_impure_functions() {
+        let source = r#"
+        global log = []
+        @cache
+        fn noisy(n) {
+            push(log, n)
+            print("noisy", n)
+            return n
+        }
+        @cache
+        fn stamp(n) {
+            return [n, now()]
+        }
+        @cache
+        fn loader(name) {
+            fn load() {
+                return read_file(name)
+            }
+            return load
+        }
+        @cache
+        fn fib(n) {
+            if n < 2 {
+                return n
+            }
+            return fib(n - 1) + fib(n - 2)
+        }
+        @cache
+        fn shadowed(print) {
+            return print
+        }
+        noisy(1)
+        noisy(1)
+        fib(20)
+        [len(log), cache_stats(noisy)["enabled"], cache_stats(stamp)["enabled"], cache_stats(loader)["enabled"], cache_stats(fib)["enabled"], cache_stats(fib)["hits"] > 0, cache_stats(shadowed)["enabled"]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[2, false, false, false, true, true, true]");
+
+        // set_memoization() может включить кэш явно
+        let source = r#"
+        global log = []
+        @cache
+        fn noisy(n) {
+            push(log, n)
+            print("noisy", n)
+            return n
+        }
+        set_memoization("noisy", true)
+        noisy(1)
+        noisy(1)
+        len(log)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::Number(1.0));
+    }
+
     // ========== Тесты для независимости массивов после присваивания ==========
 
     #[test]
@@ -1839,6 +2119,41 @@ mod tests {
         assert_error("\"2\" ** 3");
     }
 
+    #[test]
+    fn test_exponentiation_zero_to_negative_power_error() {
+        assert_error("0 ** -1");
+        assert_error("0.0 ** -2.5");
+        assert_number_result("0 ** 0", 1.0);
+        assert_number_result("2 ** -1", 0.5);
+    }
+
+    // ========== Тесты для оператора целочисленного деления (//) ==========
+
+    #[test]
+    fn test_floor_division_basic() {
+        assert_number_result("7 // 2", 3.0);
+        assert_number_result("2 ** 10 // 3", 341.0);
+    }
+
+    #[test]
+    fn test_floor_division_rounds_down() {
+        assert_number_result("-7 // 2", -4.0);
+        assert_number_result("7.5 // 2", 3.0);
+    }
+
+    #[test]
+    fn test_floor_division_precedence() {
+        // // имеет приоритет умножения, ** выше
+        assert_number_result("1 + 7 // 2", 4.0);
+        assert_number_result("2 ** 3 // 3", 2.0);
+    }
+
+    #[test]
+    fn test_floor_division_by_zero_error() {
+        assert_error("7 // 0");
+        assert_error("7.5 // 0.0");
+    }
+
     // ========== Тесты для оператора **= ==========
 
     #[test]
@@ -0,0 +1,109 @@
+// Тесты прерывания пользователем (Ctrl-C): флаг прерывания VM, перехватываемая ошибка Interrupted
+// и сохранение переменных REPL после прерванной строки
+#[cfg(test)]
+mod tests {
+    use data_code::repl::{run_repl_line, ReplSession};
+    use data_code::vm::policy::{is_cancelled_error, is_interrupted_error};
+    use data_code::{compile, Session, Value, Vm};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Выставляет флаг прерывания из другого потока через delay, как обработчик SIGINT
+    fn interrupt_after(flag: &Arc<AtomicBool>, delay: Duration) -> thread::JoinHandle<()> {
+        let flag = Arc::clone(flag);
+        thread::spawn(move || {
+            thread::sleep(delay);
+            flag.store(true, Ordering::SeqCst);
+        })
+    }
+
+    fn run_interrupted(source: &str) -> Result<Value, data_code::LangError> {
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let mut session = Session::new().interrupt_handle(Arc::clone(&interrupt));
+        let trigger = interrupt_after(&interrupt, Duration::from_millis(100));
+        let started = Instant::now();
+        let result = session.run(source);
+        trigger.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+        result
+    }
+
+    #[test]
+    fn test_uncaught_interrupt_is_interrupted_error() {
+        for source in [
+            "let i = 0\nwhile true {\n    i = i + 1\n}",
+            "fn spin() {\n    for i in range(10000000) {\n    }\n}\nspin()",
+            "sleep(60000)",
+        ] {
+            let error = run_interrupted(source).unwrap_err();
+            assert!(is_interrupted_error(&error), "{} => {}", source, error);
+            assert!(!is_cancelled_error(&error), "{}", error);
+            assert_eq!(error.kind_name(), "Interrupted");
+            assert!(format!("{}", error).contains("execution interrupted by user"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_interrupt_is_catchable() {
+        let result = run_interrupted(r#"
+            let caught = "no"
+            try {
+                while true {
+                }
+            } catch Interrupted e {
+                caught = "yes"
+            }
+            caught
+        "#);
+        assert!(matches!(result, Ok(Value::String(ref s)) if &**s == "yes"), "{:?}", result);
+
+        // Interrupted не является RuntimeError: catch RuntimeError его не перехватывает
+        let error = run_interrupted(r#"
+            try {
+                sleep(60000)
+            } catch RuntimeError e {
+                print("not reached")
+            }
+        "#)
+        .unwrap_err();
+        assert!(is_interrupted_error(&error), "{}", error);
+    }
+
+    #[test]
+    fn test_request_interrupt_resets_flag() {
+        let (chunk, functions) = compile("let s = 0\nfor i in range(5000) {\n    s = s + i\n}\nstr(s)").unwrap();
+        let mut vm = Vm::new();
+        vm.set_functions(functions);
+        vm.register_native_globals();
+        let handle = vm.interrupt_handle();
+
+        // Флаг, выставленный до запуска, срабатывает на первой проверке и сбрасывается
+        vm.request_interrupt();
+        let error = vm.run(&chunk).unwrap_err();
+        assert!(is_interrupted_error(&error), "{}", error);
+        assert!(!handle.load(Ordering::SeqCst));
+        assert!(!vm.is_cancel_requested());
+    }
+
+    #[test]
+    fn test_repl_keeps_globals_after_interrupt() {
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let mut session = ReplSession::with_interrupt(Arc::clone(&interrupt));
+        assert_eq!(run_repl_line(&mut session, "let counter = 0\nfn bump(n) { return n + 1 }").unwrap(), None);
+
+        let trigger = interrupt_after(&interrupt, Duration::from_millis(100));
+        let error = run_repl_line(&mut session, "while true {\n    counter = bump(counter)\n}").unwrap_err();
+        trigger.join().unwrap();
+        assert!(is_interrupted_error(&error), "{}", error);
+
+        // Переменные и функции, заданные до прерывания, доступны следующим строкам
+        match run_repl_line(&mut session, "counter > 0") {
+            Ok(Some(Value::Bool(true))) => {}
+            other => panic!("Expected counter > 0, got {:?}", other),
+        }
+        assert!(matches!(session.get_global("counter"), Some(Value::Integer(n)) if n > 0));
+        assert_eq!(run_repl_line(&mut session, "bump(1)").unwrap(), Some(Value::Integer(2)));
+    }
+}
@@ -0,0 +1,246 @@
+// Тесты для ленивых таблиц: table_lazy, collect, last_collect_report
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+    use std::path::PathBuf;
+
+    const PEOPLE: &str = r#"
+        let people = table([
+            [1, "Alice", 30, "Moscow"],
+            [2, "Bob", 25, "Kazan"],
+            [3, "Carol", 35, "Moscow"],
+            [4, "Dave", 40, "Omsk"],
+            [5, "Eve", 28, "Moscow"],
+            [6, "Frank", 35, "Kazan"]
+        ], ["id", "name", "age", "city"])
+    "#;
+
+    fn get_test_data_path(filename: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests");
+        path.push("test_data");
+        path.push(filename);
+        path.to_string_lossy().to_string()
+    }
+
+    /// CSV на 5000 строк и 6 колонок во временной папке
+    fn write_wide_csv(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("datacode_{}_{}.csv", name, std::process::id()));
+        let mut text = String::from("id,region,amount,comment,flag,code\n");
+        for i in 1..=5000 {
+            text.push_str(&format!("{},r{},{},\"note, {}\",{},C{}\n", i, i % 7, i, i, i % 2 == 0, i * 3));
+        }
+        std::fs::write(&path, text).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn run_with_people(body: &str) -> Result<Value, data_code::LangError> {
+        run(&format!("{}\n{}", PEOPLE, body))
+    }
+
+    fn assert_bool_result(body: &str) {
+        match run_with_people(body) {
+            Ok(Value::Bool(true)) => {}
+            other => panic!("Expected Bool(true), got {:?}", other),
+        }
+    }
+
+    fn assert_number_result(body: &str, expected: f64) {
+        match run_with_people(body) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected, "Expected {}, got {}", expected, n),
+            Ok(Value::Integer(i)) => assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_lazy_chain_matches_eager() {
+        assert_bool_result(r#"
+            let eager = table_head(table_sort(table_select(table_where(people, "age", ">=", 28), ["name", "age"]), "age", false), 3)
+            let plan = table_head(table_sort(table_select(table_where(table_lazy(people), "age", ">=", 28), ["name", "age"]), "age", false), 3)
+            collect(plan) == eager
+        "#);
+        assert_bool_result(r#"
+            let eager = table_sort(table_where(table_where(people, "city", "==", "Moscow"), "age", "<", 35), "name")
+            let plan = table_sort(table_where(table_where(table_lazy(people), "city", "==", "Moscow"), "age", "<", 35), "name")
+            collect(plan) == eager
+        "#);
+    }
+
+    #[test]
+    fn test_lazy_operations_are_deferred() {
+        assert_bool_result(r#"
+            let plan = table_where(table_lazy(people), "age", ">", 30)
+            typeof(plan) == "lazy_table" and typeof(collect(plan)) == "table" and isinstance(plan, "lazy_table")
+        "#);
+        // Без операций collect возвращает копию исходной таблицы
+        assert_bool_result("collect(table_lazy(people)) == people");
+    }
+
+    #[test]
+    fn test_lazy_head_stops_scan_early() {
+        assert_number_result(r#"
+            let plan = table_head(table_select(table_where(table_lazy(people), "city", "==", "Moscow"), ["name"]), 2)
+            let result = collect(plan)
+            let report = last_collect_report()
+            report["rows_scanned"] * 10 + report["rows_produced"]
+        "#, 32.0);
+    }
+
+    #[test]
+    fn test_lazy_head_before_filter_keeps_order_of_operations() {
+        // head до фильтра ограничивает исходные строки, а не результат фильтра
+        assert_bool_result(r#"
+            let eager = table_where(table_head(people, 3), "city", "==", "Moscow")
+            let lazy = collect(table_where(table_head(table_lazy(people), 3), "city", "==", "Moscow"))
+            lazy == eager and len(lazy["name"]) == 2
+        "#);
+    }
+
+    #[test]
+    fn test_lazy_sort_is_separate_stage() {
+        assert_number_result(r#"
+            let plan = table_head(table_sort(table_where(table_lazy(people), "age", ">", 26), "age"), 2)
+            collect(plan)
+            let report = last_collect_report()
+            len(report["stages"]) * 100 + report["rows_scanned"] * 10 + report["rows_produced"]
+        "#, 362.0);
+    }
+
+    #[test]
+    fn test_lazy_unknown_column_errors_when_building_plan() {
+        assert!(run_with_people(r#"table_where(table_lazy(people), "salary", ">", 1)"#).is_err());
+        assert!(run_with_people(r#"table_sort(table_select(table_lazy(people), ["name"]), "age")"#).is_err());
+        assert!(run_with_people(r#"table_where(table_lazy(people), "age", "~", 1)"#).is_err());
+        assert!(run("table_lazy([1, 2])").is_err());
+    }
+
+    #[test]
+    fn test_show_table_collects_lazy_table() {
+        assert_number_result(r#"
+            show_table(table_head(table_lazy(people), 2))
+            last_collect_report()["rows_produced"]
+        "#, 2.0);
+    }
+
+    #[test]
+    fn test_lazy_csv_pushdown_matches_eager() {
+        let path = write_wide_csv("lazy_pushdown");
+        let result = run(&format!(
+            r#"
+            let plan = table_select(table_where(read_file("{0}", lazy=true), "amount", ">", 4900), ["id", "amount"])
+            let lazy = collect(plan)
+            let report = last_collect_report()
+            let eager = table_select(table_where(read_file("{0}"), "amount", ">", 4900), ["id", "amount"])
+            [typeof(plan), lazy == eager, report["rows_scanned"], report["rows_produced"], report["stages"][0]]
+            "#,
+            path
+        ));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            result.unwrap().to_string(),
+            format!(
+                "[lazy_table, true, 5000, 100, scan csv {} (2 of 6 columns) [where amount > 4900 -> select id, amount]]",
+                PathBuf::from(&path).file_name().unwrap().to_string_lossy()
+            )
+        );
+    }
+
+    #[test]
+    fn test_lazy_csv_head_stops_reading() {
+        let path = write_wide_csv("lazy_head");
+        let result = run(&format!(
+            r#"
+            let plan = read_file("{0}", lazy=true)
+            let first = collect(table_head(table_where(plan, "flag", "==", true), 3))
+            let scanned = last_collect_report()["rows_scanned"]
+            let sorted = collect(table_head(table_sort(plan, "amount", false), 2))
+            [first["id"][0], first["id"][2], len(first), scanned, sorted["id"][0], sorted["id"][1], sorted["comment"][0], len(last_collect_report()["stages"])]
+            "#,
+            path
+        ));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(result.unwrap().to_string(), "[2, 6, 3, 6, 5000, 4999, note, 5000, 3]");
+    }
+
+    #[test]
+    fn test_lazy_csv_read_options_match_eager() {
+        let broken = get_test_data_path("semicolon_broken.csv");
+        for mode in ["skip", "null-fill"] {
+            let source = format!(
+                "collect(read_file(\"{0}\", delimiter=\";\", on_bad_lines=\"{1}\", lazy=true)) == read_file(\"{0}\", delimiter=\";\", on_bad_lines=\"{1}\")",
+                broken, mode
+            );
+            assert_eq!(run(&source).unwrap(), Value::Bool(true), "{}", mode);
+        }
+        let cp1251 = get_test_data_path("semicolon_cp1251.csv");
+        let source = format!(
+            "collect(read_file(\"{0}\", delimiter=\";\", encoding=\"cp1251\", lazy=true)) == read_file(\"{0}\", delimiter=\";\", encoding=\"cp1251\")",
+            cp1251
+        );
+        assert_eq!(run(&source).unwrap(), Value::Bool(true));
+
+        // Поврежденная строка обнаруживается при collect(): ошибку можно перехватить
+        let source = format!(
+            "let plan = read_file(\"{}\", delimiter=\";\", lazy=true)\nlet r = \"none\"\ntry {{\n    collect(plan)\n}} catch IOError e {{\n    r = e\n}}\nr",
+            broken
+        );
+        let message = run(&source).unwrap().to_string();
+        assert!(message.contains("malformed line 3"), "{}", message);
+        let message = format!("{}", run(&format!("table_where(read_file(\"{}\", lazy=true), \"salary\", \">\", 1)", broken)).unwrap_err());
+        assert!(message.contains("Column 'salary' not found"), "{}", message);
+    }
+
+    #[test]
+    fn test_lazy_csv_streaming_aggregates() {
+        let path = write_wide_csv("lazy_aggregate");
+        let result = run(&format!(
+            r#"
+            let events = read_file("{0}", lazy=true)
+            let aggregations = {{"*": "count", "amount": ["sum", "max"], "code": "min"}}
+            let g = table_group_by(table_where(events, "flag", "==", true), "region", aggregations)
+            let report = last_collect_report()
+            let eager = table_group_by(table_where(read_file("{0}"), "flag", "==", true), "region", aggregations)
+            [g == eager, g["columns"], g["region"][0], g["count"][0], g["amount_sum"][0], g["amount_max"][0], g["code_min"][0], len(g),
+             report["rows_scanned"], report["rows_produced"], report["stages"][0]]
+            "#,
+            path
+        ));
+        let totals = run(&format!(
+            r#"
+            let events = read_file("{0}", lazy=true)
+            [sum(events, "amount"), average(events, "amount"), count(events), count(table_head(events, 10)), count(events, "region"),
+             sum(read_file("{0}"), "amount")]
+            "#,
+            path
+        ));
+        std::fs::remove_file(&path).ok();
+        let name = PathBuf::from(&path).file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(
+            result.unwrap().to_string(),
+            format!(
+                "[true, [region, count, amount_sum, amount_max, code_min], r2, 358, 895358, 5000, C10002, 7, 5000, 2500, scan csv {} (4 of 6 columns) [where flag == true]]",
+                name
+            )
+        );
+        assert_eq!(totals.unwrap().to_string(), "[12502500, 2500.5, 5000, 10, 5000, 12502500]");
+    }
+
+    #[test]
+    fn test_group_by_errors() {
+        for (body, expected) in [
+            (r#"table_group_by(people, "city", {"age": "median"})"#, "unknown aggregation median for 'age'"),
+            (r#"table_group_by(people, "city", {"*": "sum"})"#, "'*' can only be used with count"),
+            (r#"table_group_by(people, "city", {"salary": "sum"})"#, "table_group_by(): Column 'salary' not found"),
+            (r#"table_group_by(people, "city", "age")"#, "expects a table, key column(s) and an object of aggregations"),
+            (r#"sum(people)"#, "sum() expects a column name for a table"),
+            (r#"count(table_lazy(people), "salary")"#, "count(): Column 'salary' not found"),
+        ] {
+            let message = format!("{}", run_with_people(body).unwrap_err());
+            assert!(message.contains(expected), "{}\n=> {}", body, message);
+        }
+        assert_number_result(r#"table_group_by(people, ["city"], {"age": "average"})["age_average"][0]"#, 31.0);
+    }
+}
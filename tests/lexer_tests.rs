@@ -36,7 +36,7 @@ mod tests {
 
     #[test]
     fn test_operators() {
-        let source = "+ - * / == != < >";
+        let source = "+ - * / == != < > & | ^ << >>";
         let tokens = tokenize(source);
         assert_eq!(tokens, vec![
             TokenKind::Plus,
@@ -47,6 +47,11 @@ mod tests {
             TokenKind::BangEqual,
             TokenKind::Less,
             TokenKind::Greater,
+            TokenKind::Ampersand,
+            TokenKind::Pipe,
+            TokenKind::Caret,
+            TokenKind::LessLess,
+            TokenKind::GreaterGreater,
         ]);
     }
 
@@ -11,6 +11,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -319,6 +323,85 @@ mod tests {
         assert_number_result(source, 5.0);
     }
 
+    // ========== Тесты области видимости переменной цикла ==========
+
+    #[test]
+    fn test_for_variable_does_not_overwrite_outer() {
+        let source = r#"
+            let i = 100
+            for i in [1, 2, 3] {
+                if i == 2 {
+                    break
+                }
+            }
+            i
+        "#;
+        assert_number_result(source, 100.0);
+    }
+
+    #[test]
+    fn test_for_variable_does_not_overwrite_outer_in_function() {
+        let source = r#"
+            fn f() {
+                let i = 100
+                for i in [1, 2, 3] { }
+                return i
+            }
+            f()
+        "#;
+        assert_number_result(source, 100.0);
+    }
+
+    #[test]
+    fn test_for_variable_not_visible_after_loop() {
+        assert_error(r#"
+            for q in [1, 2, 3] { }
+            q
+        "#);
+        assert_error(r#"
+            fn f() {
+                for q in [1, 2, 3] { }
+                return q
+            }
+            f()
+        "#);
+    }
+
+    #[test]
+    fn test_nested_for_reusing_variable_name() {
+        let source = r#"
+            let total = 0
+            for j in [1, 2] {
+                for j in [10, 20] {
+                    total += j
+                }
+                total += j * 1000
+            }
+            total
+        "#;
+        // Внутренний j не затирает внешний: 2 * (10 + 20) + 1000 + 2000
+        assert_number_result(source, 3060.0);
+    }
+
+    #[test]
+    fn test_for_accumulates_outer_variable() {
+        let source = r#"
+            let sum = 0
+            for k in [1, 2, 3] {
+                sum = sum + k
+            }
+            fn g() {
+                let acc = 0
+                for k in [1, 2, 3] {
+                    acc += k
+                }
+                return acc
+            }
+            sum * 10 + g()
+        "#;
+        assert_number_result(source, 66.0);
+    }
+
     // ========== Тесты для ошибок ==========
 
     #[test]
@@ -352,5 +435,121 @@ mod tests {
         "#;
         assert_error(source);
     }
+
+    #[test]
+    fn test_continue_in_function_declared_in_loop() {
+        // continue/break относятся к циклу внутри той же функции
+        assert_error(r#"
+            for i in [1, 2] {
+                fn skip() {
+                    continue
+                }
+            }
+        "#);
+        assert_error(r#"
+            while true {
+                fn stop() {
+                    break
+                }
+                break
+            }
+        "#);
+    }
+
+    // ========== Тесты для break/continue в try/catch ==========
+
+    #[test]
+    fn test_continue_in_try() {
+        let source = r#"
+            let sum = 0
+            for i in [1, 2, 3, 4] {
+                try {
+                    if i == 2 {
+                        continue
+                    }
+                    sum += i
+                } catch e {
+                    sum += 100
+                }
+            }
+            sum
+        "#;
+        assert_number_result(source, 8.0);
+    }
+
+    #[test]
+    fn test_continue_and_break_in_catch() {
+        let source = r#"
+            let sum = 0
+            let i = 0
+            while i < 10 {
+                i += 1
+                try {
+                    if i % 2 == 0 {
+                        throw "even"
+                    }
+                    sum += i
+                } catch e {
+                    if i == 8 {
+                        break
+                    }
+                    continue
+                }
+                sum += 1000
+            }
+            sum
+        "#;
+        // Нечетные 1, 3, 5, 7 проходят try целиком; на 8 цикл прерывается
+        assert_number_result(source, 4016.0);
+    }
+
+    #[test]
+    fn test_try_handler_removed_after_continue_and_break() {
+        // Обработчик try не должен перехватывать ошибки после выхода из блока через continue/break
+        assert_error(r#"
+            for i in [1, 2] {
+                try {
+                    continue
+                } catch e {
+                }
+            }
+            let x = 10 / 0
+        "#);
+        assert_error(r#"
+            while true {
+                try {
+                    throw "stop"
+                } catch e {
+                    break
+                }
+            }
+            let x = 10 / 0
+        "#);
+    }
+
+    #[test]
+    fn test_continue_in_nested_loop_inside_try() {
+        let source = r#"
+            let count = 0
+            for i in [1, 2, 3] {
+                try {
+                    for j in [1, 2, 3] {
+                        if j == 2 {
+                            continue
+                        }
+                        count += 1
+                    }
+                    if i == 2 {
+                        continue
+                    }
+                    count += 10
+                } catch e {
+                    count += 1000
+                }
+            }
+            count
+        "#;
+        assert_number_result(source, 26.0);
+    }
 }
 
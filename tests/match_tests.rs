@@ -0,0 +1,135 @@
+// Тесты для оператора match
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            Ok(v) => panic!("Expected String({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_match_selects_case() {
+        let source = r#"
+            let x = 2
+            let result = ""
+            match x {
+                case 1 -> { result = "one" }
+                case 2 -> { result = "two" }
+                default -> { result = "other" }
+            }
+            result
+        "#;
+        assert_string_result(source, "two");
+    }
+
+    #[test]
+    fn test_match_multiple_values_per_case() {
+        let source = r#"
+            fn kind(day) {
+                let result = ""
+                match day {
+                    case "sat", "sun" -> result = "weekend"
+                    default -> result = "workday"
+                }
+                return result
+            }
+            kind("sun") + "," + kind("mon") + "," + kind("sat")
+        "#;
+        assert_string_result(source, "weekend,workday,weekend");
+    }
+
+    #[test]
+    fn test_match_structural_equality() {
+        let source = r#"
+            let point = [0, 1]
+            let result = ""
+            match point {
+                case [1, 0] -> { result = "x" }
+                case [0, 1] -> { result = "y" }
+            }
+            result
+        "#;
+        assert_string_result(source, "y");
+    }
+
+    #[test]
+    fn test_match_no_implicit_fallthrough() {
+        let source = r#"
+            let result = ""
+            match 1 {
+                case 1 -> { result = result + "a" }
+                case 2 -> { result = result + "b" }
+                default -> { result = result + "c" }
+            }
+            result
+        "#;
+        assert_string_result(source, "a");
+    }
+
+    #[test]
+    fn test_match_explicit_fallthrough() {
+        let source = r#"
+            let result = ""
+            match 1 {
+                case 1 -> {
+                    result = result + "a"
+                    fallthrough
+                }
+                case 2 -> {
+                    result = result + "b"
+                    fallthrough
+                }
+                default -> { result = result + "c" }
+            }
+            result
+        "#;
+        assert_string_result(source, "abc");
+    }
+
+    #[test]
+    fn test_match_without_default_does_nothing() {
+        let source = r#"
+            let result = "unchanged"
+            match 42 {
+                case 1 -> { result = "one" }
+            }
+            result
+        "#;
+        assert_string_result(source, "unchanged");
+    }
+
+    #[test]
+    fn test_match_evaluates_value_once() {
+        let source = r#"
+            let calls = []
+            fn next() {
+                push(calls, 1)
+                return len(calls)
+            }
+            let result = ""
+            match next() {
+                case 5 -> { result = "five" }
+                case 1 -> { result = "first" }
+            }
+            result + str(len(calls))
+        "#;
+        assert_string_result(source, "first1");
+    }
+
+    #[test]
+    fn test_match_fallthrough_in_last_arm_is_error() {
+        let source = r#"
+            match 1 {
+                case 1 -> {
+                    fallthrough
+                }
+            }
+        "#;
+        assert!(run(source).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+// Тесты для операторов принадлежности in и not in
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn assert_bool_result(source: &str, expected: bool) {
+        match run(source) {
+            Ok(Value::Bool(b)) => assert_eq!(b, expected, "Expected {}, got {} for {}", expected, b, source),
+            Ok(v) => panic!("Expected Bool({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_number_result(source: &str, expected: f64) {
+        match run(source) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected, "Expected {}, got {}", expected, n),
+            Ok(Value::Integer(i)) => assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_in_array_structural_equality() {
+        assert_bool_result("2 in [1, 2, 3]", true);
+        assert_bool_result("5 in [1, 2, 3]", false);
+        assert_bool_result(r#""b" in ["a", "b"]"#, true);
+        assert_bool_result("[1, 2] in [[0], [1, 2]]", true);
+        assert_bool_result("null in [1, null]", true);
+    }
+
+    #[test]
+    fn test_in_string_is_substring() {
+        assert_bool_result(r#""ell" in "hello""#, true);
+        assert_bool_result(r#""xyz" in "hello""#, false);
+        assert_bool_result(r#""" in "hello""#, true);
+    }
+
+    #[test]
+    fn test_in_table_and_object_keys() {
+        let source = r#"
+            let t = table([[1, "Alice"]], ["id", "name"])
+            "name" in t and "age" not in t
+        "#;
+        assert_bool_result(source, true);
+        // table_drop() возвращает объект с ключами table, dropped и warning
+        let source = r#"
+            let t = table([[1, 2]], ["a", "b"])
+            let result = table_drop(t, "tmp_*")
+            "warning" in result and "missing" not in result
+        "#;
+        assert_bool_result(source, true);
+    }
+
+    #[test]
+    fn test_not_in() {
+        assert_bool_result("4 not in [1, 2, 3]", true);
+        assert_bool_result("2 not in [1, 2, 3]", false);
+        assert_bool_result(r#""z" not in "abc""#, true);
+    }
+
+    #[test]
+    fn test_in_precedence() {
+        // in связывает сильнее and/or/not и слабее арифметики
+        assert_bool_result("1 + 1 in [2, 3] and not 4 in [1]", true);
+        assert_bool_result("not 2 in [1, 2]", false);
+        assert_number_result(r#"
+            let count = 0
+            for v in [1, 2, 3, 4, 5] {
+                if v not in [2, 4] {
+                    count += 1
+                }
+            }
+            count
+        "#, 3.0);
+    }
+
+    #[test]
+    fn test_in_invalid_operands_error() {
+        assert!(run("1 in null").is_err());
+        assert!(run("1 in 5").is_err());
+        assert!(run(r#"1 in "123""#).is_err());
+        let caught = r#"
+            let r = 0
+            try {
+                r = 1 in null
+            } catch e {
+                r = -1
+            }
+            r
+        "#;
+        assert_number_result(caught, -1.0);
+    }
+}
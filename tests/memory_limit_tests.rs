@@ -0,0 +1,113 @@
+// Тесты для учета памяти: memory_usage() и ExecutionPolicy::max_memory_bytes
+#[cfg(test)]
+mod tests {
+    use data_code::vm::policy::{reset_execution_policy, set_execution_policy, ExecutionPolicy};
+    use data_code::{run, Value};
+
+    fn run_with_memory_limit(source: &str, limit: usize) -> Result<Value, data_code::LangError> {
+        set_execution_policy(ExecutionPolicy { max_memory_bytes: Some(limit), ..ExecutionPolicy::default() });
+        let result = run(source);
+        reset_execution_policy();
+        result
+    }
+
+    fn assert_bool_result(result: Result<Value, data_code::LangError>) {
+        assert!(matches!(result, Ok(Value::Bool(true))), "{:?}", result);
+    }
+
+    #[test]
+    fn test_size_estimate() {
+        let text = Value::String("x".repeat(1000).into());
+        assert!(text.size_of_estimate() >= 1000);
+        let array = run("let a = []\nfor i in range(100) {\n    a = push(a, 'row')\n}\na").unwrap();
+        assert!(array.size_of_estimate() > 100 * std::mem::size_of::<Value>());
+        // Массив, содержащий сам себя, не зацикливает оценку
+        let cyclic = run("let a = [1]\npush(a, a)\na").unwrap();
+        assert!(cyclic.size_of_estimate() > 0);
+    }
+
+    #[test]
+    fn test_memory_usage_of_value() {
+        // Таблица: ячейки строк и колонок растут с числом строк
+        assert_bool_result(run(r#"
+            let small = table([[1, "a"]], ["id", "name"])
+            let rows = []
+            for i in range(1000) {
+                push(rows, [i, "name " + str(i)])
+            }
+            let big = table(rows, ["id", "name"])
+            memory_usage(big) > 1000 * 2 * 2 * memory_usage(1) and memory_usage(small) < memory_usage(big) / 100
+        "#));
+        // Массивы и объекты - сумма вложенных элементов
+        assert_bool_result(run(r#"
+            let text = "x"
+            for i in range(13) {
+                text = text + text
+            }
+            let nested = {"a": [text, [text]], "b": 1}
+            memory_usage(nested) >= 2 * 8192 and memory_usage([text]) >= 8192 and memory_usage(nested) <= memory_usage()
+        "#));
+        assert_eq!(run("memory_usage(null) == memory_usage(1)").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_freeing_a_variable_lowers_usage() {
+        assert_bool_result(run(r#"
+            let rows = []
+            for i in range(5000) {
+                rows = push(rows, "row number " + str(i))
+            }
+            let before = memory_usage()
+            rows = null
+            let after = memory_usage()
+            before - after > 5000 * 10
+        "#));
+    }
+
+    #[test]
+    fn test_loop_appending_to_array_trips_limit() {
+        let result = run_with_memory_limit(r#"
+            let rows = []
+            while true {
+                rows = push(rows, "some row of data")
+            }
+        "#, 256 * 1024);
+        let message = format!("{}", result.unwrap_err());
+        assert!(message.contains("memory limit exceeded (used "), "{}", message);
+        assert!(message.contains(" of 0.25 MB)"), "{}", message);
+    }
+
+    #[test]
+    fn test_limit_error_is_catchable_and_freeing_recovers() {
+        assert_bool_result(run_with_memory_limit(r#"
+            let rows = []
+            let tripped = false
+            try {
+                while true {
+                    rows = push(rows, "some row of data")
+                }
+            } catch e {
+                tripped = true
+            }
+            rows = null
+            let small = [1, 2, 3]
+            tripped and memory_usage() < 256 * 1024
+        "#, 256 * 1024));
+    }
+
+    #[test]
+    fn test_large_assignment_trips_limit() {
+        let result = run_with_memory_limit(r#"
+            let text = "x"
+            for i in range(20) {
+                text = text + text
+            }
+        "#, 512 * 1024);
+        assert!(format!("{}", result.unwrap_err()).contains("memory limit exceeded"));
+    }
+
+    #[test]
+    fn test_no_limit_by_default() {
+        assert!(run("let rows = []\nfor i in range(20000) {\n    rows = push(rows, i)\n}\nlen(rows)").is_ok());
+    }
+}
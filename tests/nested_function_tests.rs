@@ -0,0 +1,272 @@
+// Тесты вложенных функций: область видимости, рекурсия и функции как значения
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn string_result(source: &str) -> String {
+        match run(source) {
+            Ok(Value::String(s)) => s.to_string(),
+            Ok(v) => panic!("Expected String, got {:?}", v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_nested_function_shadows_outer_name() {
+        let source = r#"
+            fn helper() {
+                return "global"
+            }
+            fn outer() {
+                fn helper() {
+                    return "local"
+                }
+                return helper()
+            }
+            outer() + " " + helper()
+        "#;
+        assert_eq!(string_result(source), "local global");
+    }
+
+    #[test]
+    fn test_nested_function_is_not_visible_after_call() {
+        let source = r#"
+            fn outer() {
+                fn inner() {
+                    return 1
+                }
+                return inner()
+            }
+            outer()
+            inner()
+        "#;
+        assert!(error_message(source).contains("Function 'inner' not found"));
+
+        // Имя вложенной функции не становится глобальной переменной
+        let source = r#"
+            fn outer() {
+                fn inner() {
+                    return 1
+                }
+                return inner()
+            }
+            outer()
+            let found = null
+            try {
+                found = eval("inner")
+            } catch e {
+                found = null
+            }
+            str(found)
+        "#;
+        assert_eq!(string_result(source), "null");
+    }
+
+    #[test]
+    fn test_same_name_in_different_functions() {
+        let source = r#"
+            fn first() {
+                fn step(x) {
+                    return x + 1
+                }
+                return step(1)
+            }
+            fn second() {
+                fn step(x) {
+                    return x * 10
+                }
+                return step(1)
+            }
+            str(first()) + " " + str(second())
+        "#;
+        assert_eq!(string_result(source), "2 10");
+    }
+
+    #[test]
+    fn test_recursion_into_nested_function() {
+        let source = r#"
+            fn outer(n) {
+                fn fact(k) {
+                    if k <= 1 {
+                        return 1
+                    }
+                    return k * fact(k - 1)
+                }
+                return fact(n)
+            }
+            str(outer(5))
+        "#;
+        assert_eq!(string_result(source), "120");
+    }
+
+    #[test]
+    fn test_sibling_nested_functions_call_each_other() {
+        let source = r#"
+            fn parity(n) {
+                fn is_even(k) {
+                    return k == 0 ? true : is_odd(k - 1)
+                }
+                fn is_odd(k) {
+                    return k == 0 ? false : is_even(k - 1)
+                }
+                return is_even(n) ? "even" : "odd"
+            }
+            parity(7) + " " + parity(10)
+        "#;
+        assert_eq!(string_result(source), "odd even");
+    }
+
+    #[test]
+    fn test_nested_function_as_value() {
+        // Фабрика возвращает вспомогательную функцию, ее можно сохранить и передать дальше
+        let source = r#"
+            fn make_formatter() {
+                fn wrap(x) {
+                    return "<" + str(x) + ">"
+                }
+                return wrap
+            }
+            fn apply(f, x) {
+                return f(x)
+            }
+            let fmt = make_formatter()
+            fmt(1) + apply(fmt, 2)
+        "#;
+        assert_eq!(string_result(source), "<1><2>");
+
+        let source = r#"
+            fn total(values) {
+                fn add(acc, x) {
+                    return acc + x
+                }
+                return reduce(values, add, 0)
+            }
+            str(total([1, 2, 3]))
+        "#;
+        assert_eq!(string_result(source), "6");
+    }
+
+    #[test]
+    fn test_global_fn_inside_function() {
+        let source = r#"
+            fn setup() {
+                global fn greet(name) {
+                    return "hi " + name
+                }
+                return null
+            }
+            setup()
+            greet("ann")
+        "#;
+        assert_eq!(string_result(source), "hi ann");
+    }
+
+    #[test]
+    fn test_returned_function_keeps_captured_values() {
+        // Захваченные значения копируются при создании замыкания: вызов из другой функции
+        // не читает ее локальные переменные
+        let source = r#"
+            fn make(n) {
+                fn add(x) {
+                    return x + n
+                }
+                return add
+            }
+            let a5 = make(5)
+            fn use_it(secret) {
+                return a5(1)
+            }
+            str(use_it(1000))
+        "#;
+        assert_eq!(string_result(source), "6");
+
+        // Вызов с верхнего уровня
+        let source = r#"
+            fn make(n) {
+                fn add(x) {
+                    return x + n
+                }
+                return add
+            }
+            let a5 = make(5)
+            let a7 = make(7)
+            str(a5(1)) + " " + str(a7(1)) + " " + str(map(a5, [1, 2]))
+        "#;
+        assert_eq!(string_result(source), "6 8 [6, 7]");
+    }
+
+    #[test]
+    fn test_returned_recursive_function_keeps_captured_values() {
+        let source = r#"
+            fn counter(start) {
+                fn step(k) {
+                    return k == 0 ? start : step(k - 1) + 1
+                }
+                return step
+            }
+            let from_100 = counter(100)
+            fn use_it(start) {
+                return from_100(3)
+            }
+            str(use_it(0))
+        "#;
+        assert_eq!(string_result(source), "103");
+    }
+
+    #[test]
+    fn test_sibling_reads_captured_values_of_enclosing_call() {
+        let source = r#"
+            fn outer(n) {
+                fn add(k) {
+                    return k + n
+                }
+                fn twice(k) {
+                    return add(k) * 2
+                }
+                return twice(1)
+            }
+            str(outer(3))
+        "#;
+        assert_eq!(string_result(source), "8");
+
+        // После возврата объемлющей функции ее переменных нет: ошибка вместо чужих слотов
+        let source = r#"
+            fn outer(n) {
+                fn add(k) {
+                    return k + n
+                }
+                fn twice(k) {
+                    return add(k) * 2
+                }
+                return twice
+            }
+            let twice = outer(3)
+            fn use_it(n) {
+                return twice(1)
+            }
+            use_it(1000)
+        "#;
+        assert!(error_message(source).contains("Function 'add' uses variables of the function that declared it"));
+    }
+
+    #[test]
+    fn test_cached_closures_do_not_share_results() {
+        let source = r#"
+            fn make(n) {
+                @cache
+                fn add(x) {
+                    return x + n
+                }
+                return add
+            }
+            let a1 = make(1)
+            let a2 = make(2)
+            str(a1(10)) + " " + str(a2(10)) + " " + str(a1(10))
+        "#;
+        assert_eq!(string_result(source), "11 12 11");
+    }
+}
@@ -0,0 +1,133 @@
+// Тесты для функций работы с объектами: merge, deep_merge, pick, omit, has_key и get
+#[cfg(test)]
+mod tests {
+    use data_code::run;
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(value) => assert_eq!(value.to_string(), expected),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_merge_later_wins() {
+        assert_string_result(
+            r#"str(merge({a: 1, b: 2}, {b: 20, c: 30}))"#,
+            r#"{"a": 1, "b": 20, "c": 30}"#,
+        );
+        assert_string_result(
+            r#"str(merge({a: 1}, {b: 2}, {a: 3}))"#,
+            r#"{"a": 3, "b": 2}"#,
+        );
+        assert_string_result("str(merge())", "{}");
+    }
+
+    #[test]
+    fn test_merge_is_shallow_and_keeps_arguments() {
+        let source = r#"
+            let base = {db: {host: "localhost", port: 5432}}
+            let merged = merge(base, {db: {port: 6543}})
+            let result = [merged == {db: {port: 6543}}, base == {db: {host: "localhost", port: 5432}}]
+            result
+        "#;
+        // Вложенные строки str() печатает без кавычек, поэтому сравниваем структурно
+        assert_string_result(source, "[true, true]");
+    }
+
+    #[test]
+    fn test_deep_merge_merges_nested_objects() {
+        let source = r#"
+            let defaults = {db: {host: "localhost", port: 5432}, debug: false}
+            let overrides = {db: {port: 6543}, debug: true}
+            deep_merge(defaults, overrides) == {db: {host: "localhost", port: 6543}, debug: true}
+        "#;
+        assert_string_result(source, "true");
+    }
+
+    #[test]
+    fn test_deep_merge_replaces_arrays_and_scalars() {
+        assert_string_result(
+            r#"str(deep_merge({tags: [1, 2], x: {y: 1}}, {tags: [3], x: 5}))"#,
+            r#"{"tags": [3], "x": 5}"#,
+        );
+        assert_string_result(
+            r#"str(deep_merge({x: 5}, {x: {y: 1}}, {x: {z: 2}}))"#,
+            r#"{"x": {"y": 1, "z": 2}}"#,
+        );
+    }
+
+    #[test]
+    fn test_merge_rejects_non_objects() {
+        let message = format!("{}", run("merge({a: 1}, [1, 2])").unwrap_err());
+        assert!(message.contains("merge() expects objects"), "{}", message);
+        assert!(run("deep_merge({a: 1}, 5)").is_err());
+    }
+
+    #[test]
+    fn test_pick_selects_listed_keys() {
+        let source = r#"
+            let user = {name: "Ann", age: 30, city: "Omsk"}
+            let picked = pick(user, ["city", "name"])
+            let result = [picked == {city: "Omsk", name: "Ann"}, user == {name: "Ann", age: 30, city: "Omsk"}]
+            result
+        "#;
+        // Вложенные строки str() печатает без кавычек, поэтому сравниваем структурно
+        assert_string_result(source, "[true, true]");
+        // Ключи идут в порядке списка
+        assert_string_result(r#"str(pick({c: 3, a: 1, b: 2}, ["b", "c"]))"#, r#"{"b": 2, "c": 3}"#);
+        assert_string_result(r#"str(pick({a: 1}, []))"#, "{}");
+    }
+
+    #[test]
+    fn test_pick_skips_missing_keys_unless_strict() {
+        assert_string_result(r#"str(pick({a: 1, b: 2}, ["a", "zzz"]))"#, r#"{"a": 1}"#);
+        assert_string_result(r#"str(pick({a: 1, b: 2}, ["b"], strict=true))"#, r#"{"b": 2}"#);
+        let message = format!("{}", run(r#"pick({a: 1}, ["a", "zzz"], strict=true)"#).unwrap_err());
+        assert!(message.contains("key 'zzz' not found"), "{}", message);
+    }
+
+    #[test]
+    fn test_omit_removes_listed_keys() {
+        assert_string_result(
+            r#"omit({name: "Ann", password: "x", age: 30}, ["password", "missing"]) == {name: "Ann", age: 30}"#,
+            "true",
+        );
+        assert_string_result(r#"str(omit({a: 1}, ["a"]))"#, "{}");
+    }
+
+    #[test]
+    fn test_pick_omit_argument_errors() {
+        assert!(run(r#"pick([1, 2], ["a"])"#).is_err());
+        assert!(run(r#"omit({a: 1}, "a")"#).is_err());
+        assert!(run(r#"pick({a: 1}, [1])"#).is_err());
+    }
+
+    #[test]
+    fn test_has_key() {
+        assert_string_result(
+            r#"str([has_key({a: 1, b: null}, "a"), has_key({a: 1, b: null}, "b"), has_key({a: 1}, "c"), has_key({}, "a")])"#,
+            "[true, true, false, false]",
+        );
+    }
+
+    #[test]
+    fn test_get_returns_value_or_default() {
+        let source = r#"
+            let config = {host: "localhost", port: 5432, debug: null}
+            str([get(config, "host"), get(config, "user", "admin"), get(config, "user"), get(config, "debug", true)])
+        "#;
+        // Существующий ключ со значением null возвращается как есть, default не подставляется
+        assert_string_result(source, "[localhost, admin, null, null]");
+        assert_string_result(r#"str(get({a: 1}, "b", default=[1, 2]))"#, "[1, 2]");
+    }
+
+    #[test]
+    fn test_get_and_has_key_argument_errors() {
+        let message = format!("{}", run(r#"get([1, 2], "a", 0)"#).unwrap_err());
+        assert!(message.contains("get() expects an object"), "{}", message);
+        assert!(run(r#"get(null, "a")"#).is_err());
+        assert!(run(r#"has_key("abc", "a")"#).is_err());
+        assert!(run(r#"get({a: 1}, 1)"#).is_err());
+    }
+}
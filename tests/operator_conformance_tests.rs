@@ -0,0 +1,79 @@
+// Конформность операторов: выражение над литералами (его сворачивает компилятор) и то же
+// выражение над переменными (его вычисляет VM) дают одинаковый результат или одинаковую ошибку
+#[cfg(test)]
+mod tests {
+    use data_code::run;
+
+    const OPERANDS: &[&str] = &[
+        "7", "-7", "0", "2.5", "-0.5", "0.0", "3.0", "9223372036854775807", "true", "false", "null", "'ab'", "''",
+    ];
+
+    const OPERATORS: &[&str] = &[
+        "+", "-", "*", "/", "//", "%", "**", "==", "!=", "<", ">", "<=", ">=", "&", "|", "^", "<<", ">>",
+    ];
+
+    /// Результат программы или текст ошибки
+    fn outcome(source: &str) -> String {
+        match run(source) {
+            Ok(value) => format!("ok {}", value.to_string()),
+            Err(error) => format!("error {}", error),
+        }
+    }
+
+    /// Выражение на третьей строке в обоих вариантах: номер строки в ошибке совпадает
+    fn folded(expr: &str) -> String {
+        outcome(&format!("\n\nstr({})", expr))
+    }
+
+    fn assert_conforms(folded_expr: &str, source: &str) {
+        assert_eq!(folded(folded_expr), outcome(source), "{}", folded_expr);
+    }
+
+    #[test]
+    fn test_binary_operators_match_runtime() {
+        for op in OPERATORS {
+            for a in OPERANDS {
+                for b in OPERANDS {
+                    let source = format!("let a = {}\nlet b = {}\nstr(a {} b)", a, b, op);
+                    // Скобки: -7 ** 2 = -(7 ** 2), а переменная a = -7 дает (-7) ** 2
+                    assert_conforms(&format!("({}) {} ({})", a, op, b), &source);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unary_operators_match_runtime() {
+        for operand in OPERANDS {
+            for op in ["-", "not ", "!"] {
+                let source = format!("let a = {}\n\nstr({}a)", operand, op);
+                assert_conforms(&format!("{}({})", op, operand), &source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nested_constant_expressions_match_runtime() {
+        for (expr, vars) in [
+            ("1 + 2 * 3 ** 2 - 10 // 4", "let a = 1\nlet b = 2\nstr(a + b * 3 ** b - 10 // 4)"),
+            ("-(2 ** 63) + 1", "let a = 2\nlet b = 63\nstr(-(a ** b) + 1)"),
+            ("(1 << 62) * 4", "let a = 1\nlet b = 62\nstr((a << b) * 4)"),
+            ("'x' + 1 > 0", "let a = 'x'\nlet b = 1\nstr(a + b > 0)"),
+            ("1 / 0 + 1", "let a = 1\nlet b = 0\nstr(a / b + 1)"),
+            ("true ? 7 // 2 : 1 / 0", "let a = 7\nlet b = 2\nstr(true ? a // b : 1 / 0)"),
+        ] {
+            assert_conforms(expr, vars);
+        }
+    }
+
+    #[test]
+    fn test_folded_errors_are_catchable() {
+        // Ошибка константного выражения не сворачивается и перехватывается как ошибка VM
+        for (expr, error_type) in [("1 << 64", "ValueError"), ("'a' & 1", "TypeError"), ("9223372036854775807 << 1", "OverflowError")] {
+            let source = format!("let r = 'none'\ntry {{\n    {}\n}} catch {} e {{\n    r = 'caught'\n}}\nr", expr, error_type);
+            assert_eq!(outcome(&source), "ok caught", "{}", expr);
+        }
+        let source = "let r = 'none'\ntry {\n    1 // 0\n} catch e {\n    r = 'caught'\n}\nr";
+        assert_eq!(outcome(source), "ok caught");
+    }
+}
@@ -0,0 +1,79 @@
+// Тесты параллельного map()/filter(): результат совпадает с последовательным, пользовательские
+// функции выполняются последовательно, ошибки и порог set_parallel_threshold()
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn eval_string(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_string(),
+            Err(e) => panic!("{}\n=> {}", source, e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    /// Массив из 2000 чисел, строк и null
+    const DATA: &str = "let xs = []\nfor i in range(2000) {\n    if i % 7 == 0 {\n        push(xs, null)\n    } else if i % 3 == 0 {\n        push(xs, \" Item\" + str(i) + \" \")\n    } else {\n        push(xs, 1000 - i)\n    }\n}\n";
+
+    /// Результат call при пороге 0 (параллельно) и при пороге больше длины массива (последовательно)
+    fn both(call: &str) -> (String, String) {
+        let parallel = eval_string(&format!("{}set_parallel_threshold(0)\n{}", DATA, call));
+        let sequential = eval_string(&format!("{}set_parallel_threshold(1000000)\n{}", DATA, call));
+        (parallel, sequential)
+    }
+
+    #[test]
+    fn test_parallel_results_match_sequential() {
+        for call in [
+            "map(upper, xs)",
+            "map(abs, xs)",
+            "map(trim, map(str, xs))",
+            "map(typeof, xs)",
+            "filter(bool, xs)",
+            "filter(round, xs)",
+            "map(float, map(str, xs))",
+        ] {
+            let (parallel, sequential) = both(call);
+            assert_eq!(parallel, sequential, "{}", call);
+        }
+        assert_eq!(eval_string("set_parallel_threshold(0)\nfilter(trim, [\"a\", \"  \", \"\", \"b\"])"), "[a, b]");
+        assert_eq!(eval_string("set_parallel_threshold(0)\nmap(sqrt, [4, 9, -1])"), "[2, 3, null]");
+    }
+
+    #[test]
+    fn test_non_scalar_values_and_user_functions_run_sequentially() {
+        // Пользовательская функция меняет глобальную переменную - порядок вызовов сохраняется
+        let source = "let seen = []\nfn track(x) {\n    push(seen, x)\n    return x > 1\n}\nset_parallel_threshold(0)\n[filter(track, [3, 1, 2]), seen]";
+        assert_eq!(eval_string(source), "[[3, 2], [3, 1, 2]]");
+        // Массивы внутри массива - последовательно через встроенную функцию
+        assert_eq!(eval_string("set_parallel_threshold(0)\nmap(len, [[1, 2], \"abc\", [], null])"), "[2, 3, 0, null]");
+        let source = "fn add(x, y) {\n    return x + y\n}\nset_parallel_threshold(0)\nmap(add, [1, 2], [10, 20])";
+        assert_eq!(eval_string(source), "[11, 22]");
+    }
+
+    #[test]
+    fn test_parallel_errors() {
+        // Ошибка самого раннего элемента, как при последовательном выполнении
+        let source = "set_parallel_threshold(0)\nmap(to_number, [\"1\", \"x\", \"2\", \"y\"])";
+        assert!(error_message(source).contains("to_number(): cannot convert x to a number"));
+        let source = "set_parallel_threshold(0)\nlet r = \"none\"\ntry {\n    filter(to_number, [\"a\"])\n} catch e {\n    r = \"caught\"\n}\nr";
+        assert_eq!(run(source).unwrap(), Value::String("caught".into()));
+        for (source, expected) in [
+            ("filter(upper)", "filter(fn, array) expects 2 arguments, got 1"),
+            ("filter(upper, \"abc\")", "filter() expects a function and an array"),
+            ("set_parallel_threshold(-1)", "set_parallel_threshold() expects a non-negative integer"),
+            ("set_parallel_threshold(1.5)", "set_parallel_threshold() expects a non-negative integer"),
+        ] {
+            let message = error_message(source);
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+    }
+
+    #[test]
+    fn test_set_parallel_threshold_returns_previous_value() {
+        assert_eq!(eval_string("let a = set_parallel_threshold(5)\nlet b = set_parallel_threshold(0)\n[a, b]"), "[10000, 5]");
+    }
+}
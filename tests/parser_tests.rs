@@ -1,9 +1,11 @@
 // Тесты для парсера
 #[cfg(test)]
 mod tests {
-    use data_code::parser::{Parser, Stmt};
+    use data_code::parser::{Parser, ParserLimits, Stmt};
     use data_code::lexer::Lexer;
     use data_code::parser::ast::{Expr, Arg};
+    use data_code::{run, LangError, Value};
+    use std::time::{Duration, Instant};
 
     fn parse(source: &str) -> Vec<Stmt> {
         let mut lexer = Lexer::new(source);
@@ -208,5 +210,169 @@ mod tests {
         // parse() использует unwrap(), поэтому ошибка парсинга приведет к panic
         let _stmts = parse(source);
     }
-}
 
+    fn parse_with_limits(source: &str, limits: ParserLimits) -> Result<Vec<Stmt>, LangError> {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize().unwrap();
+        Parser::with_limits(tokens, limits).parse()
+    }
+
+    // Ошибка парсера с ожидаемым текстом, полученная за ограниченное время
+    fn assert_limit_error(result: Result<Vec<Stmt>, LangError>, started: Instant, expected: &str) {
+        match result {
+            Err(LangError::ParseError { message, .. }) => assert!(message.contains(expected), "{}", message),
+            other => panic!("Expected ParseError '{}', got {:?}", expected, other.map(|s| s.len())),
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_deeply_nested_parentheses_are_rejected() {
+        let source = format!("{}1{}", "(".repeat(50_000), ")".repeat(50_000));
+        let started = Instant::now();
+        assert_limit_error(parse_with_limits(&source, ParserLimits::default()), started, "expression too deeply nested");
+
+        // То же через run и eval: чистая ошибка вместо переполнения стека
+        assert!(run(&source).is_err());
+        assert!(run(&format!("eval(\"{}\")", source)).is_err());
+    }
+
+    #[test]
+    fn test_nesting_near_default_limit_fits_in_thread_stack() {
+        // Тестовый поток имеет 2MB стека: разбор, компиляция и выполнение не должны его исчерпать
+        let depth = ParserLimits::default().max_depth - 10;
+        let arrays = format!("let x = {}1{}\nlen(x)", "[".repeat(depth), "]".repeat(depth));
+        assert!(run(&arrays).is_ok());
+        let negations = format!("let x = {}1\nx", "-".repeat(depth));
+        assert!(run(&negations).is_ok());
+        let groups = format!("let x = {}1{}\nx", "(1 + ".repeat(depth / 2), ")".repeat(depth / 2));
+        assert!(run(&groups).is_ok());
+    }
+
+    #[test]
+    fn test_nesting_within_limit_is_accepted() {
+        let limits = ParserLimits { max_depth: 64, ..ParserLimits::default() };
+        let nested = |depth: usize| format!("let x = {}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_with_limits(&nested(40), limits).is_ok());
+        assert!(parse_with_limits(&nested(80), limits).is_err());
+        assert!(parse_with_limits(&format!("let x = {}1", "-".repeat(80)), limits).is_err());
+        // Плоская цепочка операторов - не вложенность
+        assert!(parse_with_limits(&format!("let x = 1{}", " + 1".repeat(80)), limits).is_ok());
+        assert!(parse_with_limits(&format!("{}{}", "if true { ".repeat(80), "}".repeat(80)), limits).is_err());
+    }
+
+    #[test]
+    fn test_flat_operator_chains_are_not_limited_by_depth() {
+        // Левоассоциативная цепочка разбирается циклом, а не рекурсией
+        let sum = format!("let x = 1{}\nx", " + 1".repeat(299));
+        assert_eq!(run(&sum).unwrap(), Value::Integer(300));
+        let concat = format!("let s = \"\"{}\nlen(s)", " + \"ab\"".repeat(300));
+        assert_eq!(run(&concat).unwrap(), Value::Integer(600));
+        let logic = format!("let b = true{}\nb", " and true or false".repeat(300));
+        assert_eq!(run(&logic).unwrap(), Value::Bool(true));
+        // Длинная цепочка глубоко вкладывает дерево: компиляция не должна исчерпать стек
+        let long_sum = format!("let x = 1{}\nx", " + 1".repeat(50_000));
+        assert_eq!(run(&long_sum).unwrap(), Value::Integer(50_001));
+    }
+
+    #[test]
+    fn test_huge_collection_literal_is_rejected() {
+        // ~10MB литерал в одну строку: 10M токенов, 5M элементов.
+        // По умолчанию раньше срабатывает ограничение длины инструкции (1M токенов)
+        let source = format!("let data = [{}1]", "1,".repeat(5_000_000));
+        let started = Instant::now();
+        assert_limit_error(parse_with_limits(&source, ParserLimits::default()), started, "statement too long");
+
+        let limits = ParserLimits { max_statement_tokens: usize::MAX, ..ParserLimits::default() };
+        let started = Instant::now();
+        assert_limit_error(parse_with_limits(&source, limits), started, "collection literal too long");
+
+        let object = format!("let o = {{{}}}", (0..20).map(|i| format!("k{}: {}", i, i)).collect::<Vec<_>>().join(", "));
+        let limits = ParserLimits { max_collection_len: 10, ..ParserLimits::default() };
+        assert_limit_error(parse_with_limits(&object, limits), Instant::now(), "collection literal too long");
+    }
+
+    #[test]
+    fn test_statement_token_limit() {
+        let limits = ParserLimits { max_statement_tokens: 1_000, ..ParserLimits::default() };
+        let long_call = format!("print({}1)", "1, ".repeat(100));
+        assert!(parse_with_limits(&long_call, limits).is_ok());
+        // Лимит считается для каждой инструкции отдельно, а не для всей программы
+        assert!(parse_with_limits(&long_call.repeat(20), limits).is_ok());
+        let long_literal = format!("let data = [{}1]", "1, ".repeat(1_000));
+        assert_limit_error(parse_with_limits(&long_literal, limits), Instant::now(), "statement too long");
+    }
+
+    fn run_string(source: &str) -> String {
+        match run(source) {
+            Ok(data_code::Value::String(s)) => s.to_string(),
+            other => panic!("Expected String, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_comments_inside_multiline_literals() {
+        let source = "global cfg = [\n    1, # first\n    2, # second\n]\nstr(cfg)";
+        assert_eq!(run_string(source), "[1, 2]");
+
+        let source = r#"
+            let settings = {
+                "name": "report", # имя отчета
+                # отдельная строка комментария
+                "limit": 10,
+            }
+            str(settings["name"]) + " " + str(settings["limit"])
+        "#;
+        assert_eq!(run_string(source), "report 10");
+    }
+
+    #[test]
+    fn test_trailing_commas() {
+        assert_eq!(run_string("str([1, 2, 3,])"), "[1, 2, 3]");
+        assert_eq!(run_string("str(len({\"a\": 1, b: 2,}))"), "2");
+        assert_eq!(run_string("str([[1, 2,], [3,],])"), "[[1, 2], [3]]");
+        let source = r#"
+            fn add(a, b) {
+                return a + b
+            }
+            str(add(
+                1,
+                2, # второй аргумент
+            )) + " " + str(add(a = 3, b = 4,))
+        "#;
+        assert_eq!(run_string(source), "3 7");
+
+        // Одной запятой без элементов недостаточно
+        for source in ["let a = [,]", "let o = {,}", "print(,)", "let a = [1,,]"] {
+            let mut lexer = Lexer::new(source);
+            let tokens = lexer.tokenize().unwrap();
+            assert!(Parser::new(tokens).parse().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_hash_inside_string_is_preserved() {
+        let source = r##"
+            let tags = [
+                "#red", # комментарий
+                "a # b",
+            ]
+            tags[0] + "|" + tags[1]
+        "##;
+        assert_eq!(run_string(source), "#red|a # b");
+    }
+
+    #[test]
+    fn test_bracket_on_new_line_starts_array_literal() {
+        // '[' в начале строки - новая инструкция, а не индекс предыдущего выражения
+        let source = r#"
+            let t = [10, 20]
+            let n = len(t)
+            [n, t[1]]
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[2, 20]");
+        assert_eq!(run("let a = 5\n[a, a * 2]").unwrap().to_string(), "[5, 10]");
+        // На той же строке это по-прежнему индекс
+        assert_eq!(run_string("let t = [[1, 2]]\nstr(t[0] [1])"), "2");
+    }
+}
@@ -0,0 +1,237 @@
+// Тесты для функций работы с путями: path_join, path_filename, path_name, path_stem,
+// path_ext, path_exists, path_absolute, is_absolute, glob и нормализации разделителей
+
+#[cfg(test)]
+mod tests {
+    use data_code::websocket::{set_use_ve, set_user_session_path};
+    use data_code::{run, Value};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn assert_bool_result(source: &str) {
+        match run(source) {
+            Ok(Value::Bool(true)) => {}
+            other => panic!("Expected Bool(true), got {:?}", other),
+        }
+    }
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            Ok(v) => panic!("Expected String({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    /// Временная папка сессии --use-ve с файлом inside.txt
+    fn session_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("datacode_path_tests_{}_{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("inside.txt"), "ok").unwrap();
+        dir
+    }
+
+    fn with_session<T>(dir: &Path, body: impl FnOnce() -> T) -> T {
+        set_use_ve(true);
+        set_user_session_path(Some(dir.to_path_buf()));
+        let result = body();
+        set_use_ve(false);
+        set_user_session_path(None);
+        result
+    }
+
+    #[test]
+    fn test_path_join_matches_path_division() {
+        assert_bool_result(r#"path_join("data", "raw", "a.csv") == path("data") / "raw" / "a.csv""#);
+        assert_bool_result(r#"path_join(path("data"), "a.csv") == path_join("data", path("a.csv"))"#);
+        assert_bool_result(r#"typeof(path_join("data")) == "path" and str(path_join()) == """#);
+    }
+
+    #[test]
+    fn test_path_join_absolute_part_replaces_prefix() {
+        assert_bool_result(r#"path_join("data", "raw", "/tmp/out") == path("/tmp/out")"#);
+    }
+
+    #[test]
+    fn test_path_join_rejects_other_types() {
+        let source = r#"
+            let kind = ""
+            try {
+                path_join("data", 42)
+            } catch TypeError e {
+                kind = "type"
+            }
+            kind
+        "#;
+        assert_string_result(source, "type");
+    }
+
+    #[test]
+    fn test_path_filename_ext_and_parent_accept_strings() {
+        assert_string_result(r#"path_filename("data/raw/report.final.csv")"#, "report.final.csv");
+        assert_string_result(r#"path_ext(path("data/raw/report.final.csv"))"#, "csv");
+        assert_string_result(r#"path_ext("data/README")"#, "");
+        assert_bool_result(r#"path_parent("data/raw/a.csv") == path("data/raw")"#);
+    }
+
+    #[test]
+    fn test_path_name_stem_and_exists_accept_strings() {
+        assert_string_result(r#"path_name("data/raw/report.final.csv")"#, "report.final.csv");
+        assert_string_result(r#"path_stem("data/raw/report.final.csv")"#, "report.final");
+        assert_string_result(r#"path_stem(path("data/README"))"#, "README");
+        assert_bool_result(r#"path_exists("tests") and path_exists(path("Cargo.toml")) and not path_exists("no/such/file.csv")"#);
+        assert_bool_result(r#"not path_exists(42) and path_name(42) == """#);
+    }
+
+    #[test]
+    fn test_path_absolute_resolves_dot_segments() {
+        assert_bool_result(r#"
+            let p = path_absolute("data/./raw/../a.csv")
+            is_absolute(p) and p == path_join(getcwd(), "data", "a.csv")
+        "#);
+        assert_bool_result(r#"path_absolute(getcwd()) == getcwd()"#);
+    }
+
+    #[test]
+    fn test_is_absolute() {
+        assert_bool_result(r#"not is_absolute("data/a.csv") and not is_absolute(path("."))"#);
+        assert_bool_result(r#"is_absolute(getcwd()) and is_absolute("lib://share/a.csv")"#);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_unix_backslash_is_part_of_file_name() {
+        // На Unix '\' - обычный символ имени файла, разделитель только '/'
+        assert_string_result(r#"path_filename("data/a\\b.csv")"#, "a\\b.csv");
+        assert_bool_result(r#"is_absolute("/tmp") and path("a/b") != path("a\\b")"#);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_mixed_separators_are_normalized() {
+        assert_bool_result(r#"path("C:/data/raw/a.csv") == path("C:\\data\\raw\\a.csv")"#);
+        assert_string_result(r#"str(path("C:/data") / "raw/a.csv")"#, "C:\\data\\raw\\a.csv");
+        assert_string_result(r#"str(path_join("C:\\data", "raw/a.csv"))"#, "C:\\data\\raw\\a.csv");
+        assert_string_result(r#"path_filename("C:/data/raw/a.csv")"#, "a.csv");
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_drive_letter_paths_are_absolute() {
+        assert_bool_result(r#"is_absolute("C:\\data") and is_absolute("C:/data") and is_absolute("\\\\server\\share\\a.csv")"#);
+        assert_bool_result(r#"not is_absolute("C:data") and not is_absolute("data\\a.csv")"#);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_getcwd_round_trips_into_list_files() {
+        assert_bool_result(r#"
+            let mixed = str(getcwd()) + "/tests/test_data"
+            len(list_files(mixed)) > 0 and list_files(mixed) == list_files(getcwd() / "tests" / "test_data")
+        "#);
+    }
+
+    #[test]
+    fn test_use_ve_confines_paths_to_session() {
+        let dir = session_dir("confine");
+        let escaped = with_session(&dir, || run(r#"list_files("nested/../..")"#));
+        assert!(escaped.is_err(), "{:?}", escaped);
+        let inside = with_session(&dir, || run(r#"len(list_files("nested/.."))"#));
+        assert!(matches!(inside, Ok(Value::Number(n)) if n == 2.0), "{:?}", inside);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_use_ve_path_join_cannot_escape_session() {
+        let dir = session_dir("join");
+        let inside = with_session(&dir, || run(r#"str(path_join("nested", "..", "inside.txt"))"#));
+        assert!(matches!(inside, Ok(Value::String(ref s)) if s.ends_with("inside.txt")), "{:?}", inside);
+        let exists = with_session(&dir, || run(r#"path_exists(path_join("nested", "..", "inside.txt"))"#));
+        assert!(matches!(exists, Ok(Value::Bool(true))), "{:?}", exists);
+
+        let escaped = with_session(&dir, || run(r#"path_join("nested", "..", "..", "secret.txt")"#));
+        let message = format!("{}", escaped.unwrap_err());
+        assert!(message.contains("outside session directory"), "{}", message);
+        assert!(with_session(&dir, || run(r#"path_join("/etc", "passwd")"#)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Имена файлов из glob(pattern) в порядке результата
+    fn glob_names(pattern: &str) -> Result<Value, data_code::LangError> {
+        run(&format!(r#"
+            let names = []
+            for file in glob("{}") {{
+                push(names, path_name(file))
+            }}
+            str(names)
+        "#, pattern))
+    }
+
+    #[test]
+    fn test_glob_matches_sorted_paths() {
+        let dir = session_dir("glob");
+        fs::create_dir_all(dir.join("nested").join("deep")).unwrap();
+        for file in ["b.csv", "a.csv", ".hidden.csv", "nested/c.csv", "nested/deep/d.csv"] {
+            fs::write(dir.join(file), "x").unwrap();
+        }
+        let root = dir.to_string_lossy().replace('\\', "/");
+
+        let flat = glob_names(&format!("{}/*.csv", root));
+        assert!(matches!(flat, Ok(Value::String(ref s)) if &**s == "[a.csv, b.csv]"), "{:?}", flat);
+        let recursive = glob_names(&format!("{}/**/*.csv", root));
+        assert!(matches!(recursive, Ok(Value::String(ref s)) if &**s == "[a.csv, b.csv, c.csv, d.csv]"), "{:?}", recursive);
+        let none = glob_names(&format!("{}/*.xlsx", root));
+        assert!(matches!(none, Ok(Value::String(ref s)) if &**s == "[]"), "{:?}", none);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_glob_rejects_bad_arguments() {
+        let source = r#"
+            let kinds = []
+            try {
+                glob("data/a**b")
+            } catch ValueError e {
+                push(kinds, "value")
+            }
+            try {
+                glob(42)
+            } catch TypeError e {
+                push(kinds, "type")
+            }
+            str(kinds)
+        "#;
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, "[value, type]"),
+            other => panic!("Expected String, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_use_ve_glob_is_confined_to_session() {
+        let dir = session_dir("glob_confine");
+        fs::write(dir.join("nested").join("more.txt"), "ok").unwrap();
+
+        let inside = with_session(&dir, || glob_names("**/*.txt"));
+        assert!(matches!(inside, Ok(Value::String(ref s)) if &**s == "[inside.txt, more.txt]"), "{:?}", inside);
+        let dotted = with_session(&dir, || glob_names("nested/../*.txt"));
+        assert!(matches!(dotted, Ok(Value::String(ref s)) if &**s == "[inside.txt]"), "{:?}", dotted);
+
+        let escaped = with_session(&dir, || run(r#"glob("../*")"#));
+        let message = format!("{}", escaped.unwrap_err());
+        assert!(message.contains("outside session directory"), "{}", message);
+        assert!(with_session(&dir, || run(r#"glob("/etc/*")"#)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_use_ve_catches_backslash_escape_on_windows() {
+        let dir = session_dir("confine_windows");
+        let escaped = with_session(&dir, || run(r#"read_file("nested\\..\\..\\outside.txt")"#));
+        assert!(escaped.is_err(), "{:?}", escaped);
+        let inside = with_session(&dir, || run(r#"read_file("nested\\..\\inside.txt")"#));
+        assert!(matches!(inside, Ok(Value::String(ref s)) if &**s == "ok"), "{:?}", inside);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,8 +3,41 @@
 
 #[cfg(test)]
 mod tests {
-    use data_code::run;
+    use data_code::{run, Value};
     use std::time::Instant;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    // Считает байты, выделенные текущим потоком (тесты выполняются в разных потоках)
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED_BYTES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATED_BYTES.try_with(|bytes| bytes.set(bytes.get() + layout.size()));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    // Выполняет замыкание и возвращает число выделенных байт и время
+    fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, std::time::Duration) {
+        let before = ALLOCATED_BYTES.with(|bytes| bytes.get());
+        let start = Instant::now();
+        let result = f();
+        let duration = start.elapsed();
+        let after = ALLOCATED_BYTES.with(|bytes| bytes.get());
+        (result, after - before, duration)
+    }
 
     // Helper function to execute a test and measure time
     fn run_performance_test(source: &str, test_name: &str) -> Result<(), data_code::LangError> {
@@ -42,5 +75,125 @@ mod tests {
         run_performance_test(source, "Large dataset test")
             .expect("Large dataset test should complete without errors");
     }
-}
 
+    #[test]
+    fn test_string_grouping_does_not_copy_strings() {
+        // Группировка по строковому ключу читает и сравнивает строки, но не копирует их содержимое:
+        // дополнительные проходы по строкам почти не выделяют память
+        const ROWS: usize = 20_000;
+        let program = |passes: usize| {
+            format!(
+                r#"
+let categories = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta"]
+let suffix = "_with_a_reasonably_long_suffix_that_would_be_expensive_to_copy_on_every_read"
+let rows = []
+for i in range({rows}) {{
+    push(rows, "category_" + categories[i % 8] + suffix)
+}}
+let keys = unique(rows)
+let counts = []
+for pass in range({passes}) {{
+    for key in keys {{
+        let count = 0
+        for row in rows {{
+            if row == key {{
+                count += 1
+            }}
+        }}
+        push(counts, count)
+    }}
+}}
+sum(counts)
+"#,
+                rows = ROWS,
+                passes = passes,
+            )
+        };
+
+        let (one, one_bytes, one_time) = measure(|| run(&program(1)));
+        let (five, five_bytes, five_time) = measure(|| run(&program(5)));
+
+        println!("1 pass:   {} bytes in {:?}", one_bytes, one_time);
+        println!("5 passes: {} bytes in {:?}", five_bytes, five_time);
+        assert_eq!(one.unwrap(), Value::Integer(ROWS as i64));
+        assert_eq!(five.unwrap(), Value::Integer(5 * ROWS as i64));
+        // 4 лишних прохода - это 4 * 8 * ROWS чтений и сравнений строки длиннее 90 байт
+        let reads = 4 * 8 * ROWS;
+        let extra_bytes = five_bytes.saturating_sub(one_bytes);
+        assert!(extra_bytes < reads, "{} extra bytes for {} string reads", extra_bytes, reads);
+    }
+
+    #[test]
+    fn test_string_clone_shares_buffer() {
+        // Клонирование строкового значения не копирует содержимое
+        let value = Value::String("category_alpha".into());
+        match (&value, &value.clone()) {
+            (Value::String(a), Value::String(b)) => assert!(std::rc::Rc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_lazy_chain_allocates_less_than_eager() {
+        use data_code::common::table::Table;
+        use data_code::vm::natives::{
+            native_collect, native_table_head, native_table_lazy, native_table_select, native_table_where,
+        };
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let rows: Vec<Vec<Value>> = (0..50_000)
+            .map(|i| vec![
+                Value::Number(i as f64),
+                Value::String(format!("name_{}", i).into()),
+                Value::Number((i % 100) as f64),
+            ])
+            .collect();
+        let headers = vec!["id".to_string(), "name".to_string(), "score".to_string()];
+        let table = Rc::new(RefCell::new(Table::from_data(rows, Some(headers))));
+        // Value::clone копирует таблицу целиком, поэтому передаем ссылку на ту же таблицу
+        let source = || Value::Table(Rc::clone(&table));
+        let where_args = |table: Value| vec![table, Value::String("score".into()), Value::String(">=".into()), Value::Number(50.0)];
+        let columns = || Value::Array(Rc::new(RefCell::new(vec![Value::String("id".into()), Value::String("name".into())])));
+
+        // filter -> select -> head: каждый шаг копирует промежуточную таблицу
+        let (eager, eager_bytes, eager_time) = measure(|| {
+            let filtered = native_table_where(&where_args(source()));
+            let selected = native_table_select(&[filtered, columns()]);
+            native_table_head(&[selected, Value::Number(10.0)])
+        });
+        // Та же цепочка лениво: один проход, просмотр останавливается после 10 подходящих строк
+        let (lazy, lazy_bytes, lazy_time) = measure(|| {
+            let plan = native_table_lazy(&[source()]);
+            let filtered = native_table_where(&where_args(plan));
+            let selected = native_table_select(&[filtered, columns()]);
+            native_collect(&[native_table_head(&[selected, Value::Number(10.0)])])
+        });
+
+        println!("eager filter->select->head: {} bytes in {:?}", eager_bytes, eager_time);
+        println!("lazy  filter->select->head: {} bytes in {:?}", lazy_bytes, lazy_time);
+        assert_eq!(lazy, eager);
+        assert!(lazy_bytes * 10 < eager_bytes, "lazy {} bytes, eager {} bytes", lazy_bytes, eager_bytes);
+    }
+
+    #[test]
+    fn test_function_call_does_not_copy_bytecode() {
+        // Байткод функции общий для всех вызовов: выделения на вызов не зависят от размера тела
+        const CALLS: usize = 200_000;
+        let program = |body: &str| {
+            format!("fn poly(x) {{\n    return {}\n}}\nlet s = 0\nfor i in range({}) {{\n    s = s + poly(i)\n}}\ns", body, CALLS)
+        };
+        let short_body = "x * x * 3 + x * 2 - 7";
+        let long_body = vec![short_body; 40].join(" + ");
+
+        let (short, short_bytes, short_time) = measure(|| run(&program(short_body)));
+        let (long, long_bytes, long_time) = measure(|| run(&program(&long_body)));
+
+        println!("{} calls, short body: {} bytes in {:?}", CALLS, short_bytes, short_time);
+        println!("{} calls, long body:  {} bytes in {:?}", CALLS, long_bytes, long_time);
+        assert!(matches!(short, Ok(Value::Number(_))), "{:?}", short);
+        assert!(matches!(long, Ok(Value::Number(_))), "{:?}", long);
+        assert!(short_bytes / CALLS < 1024, "{} bytes per call", short_bytes / CALLS);
+        assert!(long_bytes < short_bytes * 3 / 2, "long {} bytes, short {} bytes", long_bytes, short_bytes);
+    }
+}
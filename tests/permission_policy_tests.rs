@@ -0,0 +1,110 @@
+// Тесты разрешений ExecutionPolicy для файловых функций: allow_file_read, allow_file_write,
+// allow_smb и лимит папки сессии max_session_disk_bytes
+#[cfg(test)]
+mod tests {
+    use data_code::vm::policy::{reset_execution_policy, set_execution_policy, ExecutionPolicy};
+    use data_code::websocket::{check_session_disk_quota, set_use_ve, set_user_session_path};
+    use data_code::{run, Value};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn run_with_policy(source: &str, policy: ExecutionPolicy) -> Result<Value, data_code::LangError> {
+        set_execution_policy(policy);
+        let result = run(source);
+        reset_execution_policy();
+        result
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("datacode_permission_tests_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn slashed(path: &PathBuf) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    fn error_message(result: Result<Value, data_code::LangError>) -> String {
+        format!("{}", result.unwrap_err())
+    }
+
+    #[test]
+    fn test_read_disabled() {
+        let dir = temp_dir("read");
+        fs::write(dir.join("data.txt"), "a\nb").unwrap();
+        let policy = ExecutionPolicy { allow_file_read: false, ..ExecutionPolicy::default() };
+        for call in ["read_file", "read_lines", "list_files"] {
+            let target = if call == "list_files" { dir.clone() } else { dir.join("data.txt") };
+            let source = format!(r#"{}("{}")"#, call, slashed(&target));
+            let message = error_message(run_with_policy(&source, policy.clone()));
+            assert!(message.contains(&format!("{}(): reading files is disabled by the execution policy", call)), "{}", message);
+        }
+        // Запись при этом разрешена
+        let source = format!(r#"write_file("{}", "ok")"#, slashed(&dir.join("out.txt")));
+        assert!(run_with_policy(&source, policy).is_ok());
+        assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "ok");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_disabled_is_catchable_permission_error() {
+        let dir = temp_dir("write");
+        let target = dir.join("out.txt");
+        let source = format!(
+            r#"
+            let result = "none"
+            try {{
+                append_file("{}", "text")
+            }} catch PermissionError e {{
+                result = e
+            }}
+            result
+            "#,
+            slashed(&target)
+        );
+        let policy = ExecutionPolicy { allow_file_write: false, ..ExecutionPolicy::default() };
+        match run_with_policy(&source, policy) {
+            Ok(Value::String(message)) => {
+                assert!(message.contains("append_file(): writing files is disabled"), "{}", message)
+            }
+            other => panic!("Expected String, got {:?}", other),
+        }
+        assert!(!target.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_smb_disabled() {
+        let policy = ExecutionPolicy { allow_smb: false, ..ExecutionPolicy::default() };
+        let message = error_message(run_with_policy(r#"read_file("lib://share/data.csv")"#, policy));
+        assert!(message.contains("SMB access is disabled by the execution policy"), "{}", message);
+    }
+
+    #[test]
+    fn test_session_disk_quota() {
+        let dir = temp_dir("quota");
+        fs::write(dir.join("existing.txt"), "x".repeat(600)).unwrap();
+        set_use_ve(true);
+        set_user_session_path(Some(dir.clone()));
+
+        // Прежнее содержимое перезаписываемого файла не учитывается
+        assert!(check_session_disk_quota(Some(1000), &dir.join("new.txt"), 300).is_ok());
+        assert!(check_session_disk_quota(Some(1000), &dir.join("new.txt"), 500).is_err());
+        assert!(check_session_disk_quota(Some(1000), &dir.join("existing.txt"), 900).is_ok());
+        assert!(check_session_disk_quota(None, &dir.join("new.txt"), 5000).is_ok());
+
+        let policy = ExecutionPolicy { max_session_disk_bytes: Some(1000), ..ExecutionPolicy::default() };
+        assert!(run_with_policy(r#"write_file("small.txt", "y")"#, policy.clone()).is_ok());
+        let message = error_message(run_with_policy(
+            "let text = \"\"\nfor i in range(500) {\n    text = text + \"y\"\n}\nwrite_file(\"big.txt\", text)",
+            policy,
+        ));
+
+        set_use_ve(false);
+        set_user_session_path(None);
+        assert!(message.contains("write_file(): session disk quota exceeded"), "{}", message);
+        assert!(!dir.join("big.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,192 @@
+// Конформность приоритета операторов: одно и то же выражение дает одинаковый результат
+// в программе, в eval() внутри функции и в table_filter_expr по строке таблицы
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    /// Выражение над x = 3, y = 4, name = 'ab' и ожидаемое str() результата
+    const CASES: &[(&str, &str)] = &[
+        // Арифметика
+        ("x + y * 2", "11"),
+        ("(x + y) * 2", "14"),
+        ("x - y - 1", "-2"),
+        ("x * y % 5", "2"),
+        ("x + y % 4", "3"),
+        ("(x + y) % 4", "3"),
+        ("y / 2 + x", "5"),
+        ("x / y * 2", "1.5"),
+        ("100 / 10 / 2", "5"),
+        ("10 - x * 2", "4"),
+        ("y // x * x", "3"),
+        ("x * (y - 1) // 2", "4"),
+        ("1 + 2 * 3 ** 2", "19"),
+        ("(1 + 2) * 3 ** 2", "27"),
+        ("2 * 3 ** 2 * 2", "36"),
+        ("2 ** 3 ** 2", "512"),
+        ("x ** 2 ** 2", "81"),
+        ("2 ** 10 - 1", "1023"),
+        ("2 ** -1", "0.5"),
+        ("x // 2 ** 2", "0"),
+        ("(x // 2) ** 2", "1"),
+        ("y % x ** 2", "4"),
+        ("-7 // 2", "-4"),
+        ("7 // -2 * x", "-12"),
+        // Побитовые операторы: сдвиги, затем &, ^, | - все сильнее сравнений
+        ("x | y", "7"),
+        ("x & y", "0"),
+        ("x ^ y", "7"),
+        ("1 << x + 1", "16"),
+        ("x * 2 >> 1", "3"),
+        ("x | y & 1", "3"),
+        ("(x | y) & 1", "1"),
+        ("x | y ^ x", "7"),
+        ("x ^ y & x", "3"),
+        ("x & y == 0", "true"),
+        ("1 << y > x", "true"),
+        ("y >> 1 == 2 and x & 1 == 1", "true"),
+        ("-x >> 1", "-2"),
+        ("x & 1 in [1]", "true"),
+        // Унарные операторы
+        ("-x ** 2", "-9"),
+        ("-2 ** 2", "-4"),
+        ("(-2) ** 2", "4"),
+        ("x * -y", "-12"),
+        ("- x - y", "-7"),
+        ("x - -y", "7"),
+        ("-[x, y][0] + 1", "-2"),
+        // Вызовы и индексы
+        ("len(name) + x", "5"),
+        ("len(name) * x + y", "10"),
+        ("[1, 2, 3][1] + x", "5"),
+        ("[x, y][1] ** 2", "16"),
+        // Конкатенация строк и сравнения
+        ("name + 'c' == 'abc'", "true"),
+        ("'x' + y == 'x4'", "true"),
+        ("name + x + y", "ab34"),
+        ("x + y + name", "7ab"),
+        ("name + (x + y)", "ab7"),
+        ("str(x) + str(y) == '34'", "true"),
+        ("name + x > 'ab2'", "true"),
+        ("x + y > 6", "true"),
+        ("x + y > 7 == false", "true"),
+        ("x % 2 == 1", "true"),
+        ("x < y == y < x", "false"),
+        ("x == 3 == true", "true"),
+        // Цепочки сравнений
+        ("1 < x < 5", "true"),
+        ("1 < x < 2", "false"),
+        ("x < y < x + y", "true"),
+        // in и not in
+        ("x in [1, 2, 3] and y not in [1, 2, 3]", "true"),
+        ("'a' in name == true", "true"),
+        ("name + 'c' in 'xabcx'", "true"),
+        ("x + 1 in [4]", "true"),
+        ("not x in [1, 2]", "true"),
+        ("x not in [1, 2] == true", "true"),
+        // Логические операторы
+        ("x == 3 and y == 4", "true"),
+        ("x == 3 or y == 5 and false", "true"),
+        ("false and true or true", "true"),
+        ("not false and false", "false"),
+        ("not (false and false)", "true"),
+        ("not x > 2 and y > 2", "false"),
+        ("!(x > y)", "true"),
+        ("!true == false", "true"),
+        ("x > y or !false", "true"),
+        ("x != y and x != 3", "false"),
+        ("x + y != 7 or name == 'ab'", "true"),
+        ("name == 'ab' and x + y > 6 or false", "true"),
+        // Тернарный оператор
+        ("x > 2 ? 'big' : 'small'", "big"),
+        ("x > 5 ? 1 : y > 3 ? 2 : 3", "2"),
+        ("x + y == 7 ? 'yes' : 'no'", "yes"),
+        ("x > 1 and y > 1 ? 'both' : 'no'", "both"),
+        ("x * 2 > y ? x * 2 : y", "6"),
+        ("true ? x : y + 10", "3"),
+        ("false ? x : y + 10", "14"),
+    ];
+
+    fn string_result(source: &str) -> String {
+        match run(source) {
+            Ok(Value::String(s)) => s.to_string(),
+            Ok(v) => panic!("Expected String, got {:?} for {}", v, source),
+            Err(e) => panic!("Error: {} for {}", e, source),
+        }
+    }
+
+    /// Выражение в основной программе
+    fn via_run(expr: &str) -> Result<Value, data_code::LangError> {
+        run(&format!("let x = 3\nlet y = 4\nlet name = 'ab'\nstr({})", expr))
+    }
+
+    /// Выражение в eval() над параметрами функции
+    fn via_eval_in_function(expr: &str) -> Result<Value, data_code::LangError> {
+        run(&format!(
+            "fn f(x, y, name) {{\n    return str(eval(\"{}\"))\n}}\nf(3, 4, 'ab')",
+            expr
+        ))
+    }
+
+    /// Выражение над колонками строки таблицы; возвращает число подходящих строк
+    fn via_table_filter(expr: &str, expected: &str) -> Result<Value, data_code::LangError> {
+        run(&format!(
+            "let t = table([[3, 4, 'ab']], ['x', 'y', 'name'])\nlen(table_filter_expr(t, \"str({}) == '{}'\"))",
+            expr, expected
+        ))
+    }
+
+    #[test]
+    fn test_suite_size() {
+        assert!(CASES.len() >= 60, "{}", CASES.len());
+    }
+
+    #[test]
+    fn test_main_program() {
+        for (expr, expected) in CASES {
+            let result = via_run(expr);
+            assert!(matches!(result, Ok(Value::String(ref s)) if &**s == *expected), "{} => {:?}", expr, result);
+        }
+    }
+
+    #[test]
+    fn test_eval_in_function_body() {
+        for (expr, expected) in CASES {
+            let result = via_eval_in_function(expr);
+            assert!(matches!(result, Ok(Value::String(ref s)) if &**s == *expected), "{} => {:?}", expr, result);
+        }
+    }
+
+    #[test]
+    fn test_table_filter_expr_rows() {
+        for (expr, expected) in CASES {
+            let result = via_table_filter(expr, expected);
+            let matched = match result {
+                Ok(Value::Number(n)) => n == 1.0,
+                Ok(Value::Integer(n)) => n == 1,
+                _ => false,
+            };
+            assert!(matched, "{} => {:?}", expr, result);
+        }
+    }
+
+    #[test]
+    fn test_string_plus_number_compared_with_number_explains_precedence() {
+        // 'total: ' + x > 10 = ('total: ' + x) > 10: строка сравнивается с числом
+        let expr = "'total: ' + x > 10";
+        for result in [via_run(expr), via_eval_in_function(expr), via_table_filter(expr, "true")] {
+            let message = format!("{}", result.unwrap_err());
+            assert!(message.contains("cannot compare a string with a number"), "{}", message);
+            assert!(message.contains("('total: ' + n) > 10"), "{}", message);
+            assert!(message.contains("Operator precedence from highest: call/index, **"), "{}", message);
+        }
+        // Явные скобки дают ожидаемый порядок
+        assert_eq!(string_result("let x = 42\n'total: ' + str(x > 10)"), "total: true");
+    }
+
+    #[test]
+    fn test_other_comparison_errors_keep_short_message() {
+        let message = format!("{}", run("[1] < 2").unwrap_err());
+        assert!(message.contains("Operands must be numbers or strings"), "{}", message);
+        assert!(!message.contains("precedence"), "{}", message);
+    }
+}
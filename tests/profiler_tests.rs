@@ -0,0 +1,115 @@
+// Тесты профилирования по стекам вызовов (пути в свернутом формате для flamegraph)
+// и по строкам исходного кода (datacode --profile)
+#[cfg(test)]
+mod tests {
+    use data_code::Session;
+
+    /// Профиль выполнения source: путь вызовов -> собственное время в микросекундах
+    fn profile(source: &str) -> Vec<(String, u128)> {
+        let mut session = Session::new().profile(true);
+        if let Err(e) = session.run(source) {
+            panic!("{}\n=> {}", source, e);
+        }
+        let folded = session.vm().unwrap().profiler().unwrap().export_folded();
+        folded
+            .lines()
+            .map(|line| {
+                let (path, micros) = line.rsplit_once(' ').unwrap_or_else(|| panic!("bad line: {}", line));
+                (path.to_string(), micros.parse().unwrap_or_else(|_| panic!("bad line: {}", line)))
+            })
+            .collect()
+    }
+
+    fn paths(profile: &[(String, u128)]) -> Vec<&str> {
+        profile.iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_nested_calls_and_natives() {
+        let source = "fn inner(x) {\n    return to_number(x)\n}\nfn outer() {\n    let s = 0\n    for i in range(3) {\n        s += inner(\"1\")\n    }\n    return s\n}\nouter()";
+        let profile = profile(source);
+        // Строки отсортированы по пути
+        assert_eq!(
+            paths(&profile),
+            ["<main>", "<main>;outer", "<main>;outer;inner", "<main>;outer;inner;to_number", "<main>;outer;range"]
+        );
+    }
+
+    #[test]
+    fn test_recursion_and_time_attribution() {
+        let source = "fn fact(n) {\n    if n <= 1 {\n        return 1\n    }\n    return n * fact(n - 1)\n}\nfn slow() {\n    sleep(30)\n}\nfact(3)\nslow()";
+        let profile = profile(source);
+        let found = paths(&profile);
+        assert!(found.contains(&"<main>;fact;fact;fact"), "{:?}", found);
+        // sleep() выполняется VM, его время - собственное время slow
+        let slow = profile.iter().find(|(path, _)| path == "<main>;slow").unwrap().1;
+        assert!(slow >= 30_000, "{}", slow);
+        let main = profile.iter().find(|(path, _)| path == "<main>").unwrap().1;
+        assert!(main < slow, "{} {}", main, slow);
+    }
+
+    #[test]
+    fn test_exception_unwinding_and_continued_runs() {
+        let mut session = Session::new().profile(true);
+        let source = "fn boom() {\n    return to_number(\"x\")\n}\nfn safe() {\n    try {\n        boom()\n    } catch e {\n        return 0\n    }\n}\nsafe()";
+        session.run(source).unwrap();
+        session.run("fn later() {\n    return 1\n}\nlater()").unwrap();
+        let folded = session.vm().unwrap().profiler().unwrap().export_folded();
+        let found: Vec<&str> = folded.lines().map(|line| line.rsplit_once(' ').unwrap().0).collect();
+        assert_eq!(found, ["<main>", "<main>;later", "<main>;safe", "<main>;safe;boom", "<main>;safe;boom;to_number"]);
+    }
+
+    /// Профиль по строкам: номер строки -> число выполнений
+    fn line_hits(source: &str) -> Vec<(usize, u64)> {
+        let mut session = Session::new().profile(true);
+        if let Err(e) = session.run(source) {
+            panic!("{}\n=> {}", source, e);
+        }
+        let mut hits: Vec<(usize, u64)> =
+            session.vm().unwrap().profiler().unwrap().line_stats().into_iter().map(|(line, stats)| (line, stats.hits)).collect();
+        hits.sort();
+        hits
+    }
+
+    #[test]
+    fn test_line_hit_counts() {
+        let source = "fn parse(x) {\n    return to_number(x)\n}\nlet s = 0\nfor i in range(5) {\n    s += parse(\"1\")\n}\ns";
+        let hits = line_hits(source);
+        let hits_of = |line: usize| hits.iter().find(|(l, _)| *l == line).map_or(0, |(_, h)| *h);
+        assert_eq!(hits_of(2), 5, "{:?}", hits);
+        assert_eq!(hits_of(4), 1, "{:?}", hits);
+        // Заголовок цикла выполняется перед каждой итерацией и при выходе; возврат из parse()
+        // на строку 6 не считается новым выполнением
+        assert_eq!(hits_of(5), 6, "{:?}", hits);
+        assert_eq!(hits_of(6), 5, "{:?}", hits);
+        assert_eq!(hits_of(8), 1, "{:?}", hits);
+        // while: условие на каждой итерации
+        let hits = line_hits("let i = 0\nwhile i < 3 {\n    i += 1\n}\ni");
+        assert_eq!(hits, [(1, 1), (2, 4), (3, 3), (5, 1)]);
+    }
+
+    #[test]
+    fn test_line_time_and_report() {
+        let source = "let a = 1\nsleep(30)\nlet b = a + 1";
+        let mut session = Session::new().profile(true);
+        session.run(source).unwrap();
+        let profiler = session.vm().unwrap().profiler().unwrap();
+        let stats = profiler.line_stats();
+        // Самая долгая строка - первая
+        assert_eq!(stats[0].0, 2);
+        assert!(stats[0].1.time.as_millis() >= 30, "{:?}", stats);
+        assert_eq!(stats[0].1.hits, 1);
+        let report = profiler.line_report(source, 2);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", report);
+        assert!(lines[0].starts_with("Профиль по строкам: 2 из 3 строк"), "{}", report);
+        assert!(lines[2].trim_start().starts_with("2 ") && lines[2].ends_with("%  sleep(30)"), "{}", report);
+    }
+
+    #[test]
+    fn test_profiling_is_off_by_default() {
+        let mut session = Session::new();
+        session.run("let x = 1").unwrap();
+        assert!(session.vm().unwrap().profiler().is_none());
+    }
+}
@@ -0,0 +1,103 @@
+// Тесты для progress(), progress_done() и set_option("auto_progress", true)
+#[cfg(test)]
+mod tests {
+    use data_code::progress::{format_duration, render_bar, render_done, render_line, RateLimiter, PLAIN_INTERVAL};
+    use data_code::common::output_capture::OutputCapture;
+    use data_code::{run, Value};
+    use std::time::{Duration, Instant};
+
+    /// Выполняет код с перехваченным выводом и возвращает напечатанные строки
+    fn captured_lines(source: &str) -> Vec<String> {
+        let capture = OutputCapture::new();
+        capture.set_capture(true);
+        assert!(!OutputCapture::is_terminal());
+        let result = run(source);
+        let output = capture.get_output();
+        capture.set_capture(false);
+        if let Err(e) = result {
+            panic!("Error: {}", e);
+        }
+        output.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(PLAIN_INTERVAL);
+        assert!(limiter.allow(start));
+        assert!(!limiter.allow(start + Duration::from_millis(1999)));
+        assert!(limiter.allow(start + Duration::from_secs(2)));
+        assert!(!limiter.allow(start + Duration::from_secs(3)));
+        assert!(limiter.allow(start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_rendering() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 02m");
+
+        assert_eq!(render_line("loading", 25, 100, Duration::from_secs(10)), "loading: 25/100 (25%), ETA 30s");
+        assert_eq!(render_line("loading", 0, 100, Duration::ZERO), "loading: 0/100 (0%), ETA ?");
+        assert_eq!(
+            render_bar("loading", 5, 10, Duration::from_secs(6)),
+            "loading [###############---------------] 50% 5/10 ETA 6s"
+        );
+        assert_eq!(render_done("loading", 10, Duration::from_secs(12)), "loading: done, 10 items in 12s");
+    }
+
+    #[test]
+    fn test_captured_output_is_plain_and_rate_limited() {
+        let lines = captured_lines(r#"
+            for i in range(1, 101) {
+                progress("loading", i, 100)
+            }
+            progress_done("loading")
+        "#);
+        // Первое обновление печатается сразу, остальные в пределах 2 секунд подавляются
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        assert!(lines[0].starts_with("loading: 1/100 (1%), ETA "), "{:?}", lines);
+        assert!(lines[1].starts_with("loading: done, 100 items in "), "{:?}", lines);
+    }
+
+    #[test]
+    fn test_auto_progress_for_loops() {
+        let lines = captured_lines(r#"
+            set_option("auto_progress", true)
+            let total = 0
+            for x in range(2000) {
+                total = total + x
+            }
+            for y in range(10) {
+                total = total + y
+            }
+            print(total)
+        "#);
+        assert_eq!(lines.len(), 3, "{:?}", lines);
+        assert!(lines[0].starts_with("for loop (line 4): 20/2000 (1%)"), "{:?}", lines);
+        assert!(lines[1].starts_with("for loop (line 4): done, 2000 items in "), "{:?}", lines);
+        assert_eq!(lines[2], "1999045");
+    }
+
+    #[test]
+    fn test_auto_progress_is_off_by_default() {
+        let lines = captured_lines(r#"
+            let total = 0
+            for x in range(2000) {
+                total = total + x
+            }
+            print(total)
+        "#);
+        assert_eq!(lines, ["1999000"]);
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        assert!(run(r#"progress("loading", -1, 10)"#).is_err());
+        assert!(run(r#"progress(1, 2, 3)"#).is_err());
+        assert!(run("progress_done(1)").is_err());
+        assert!(matches!(run(r#"progress_done("never started")"#), Ok(Value::Null)));
+        assert!(run(r#"set_option("auto_progress", "yes")"#).is_err());
+        assert!(matches!(run(r#"set_option("auto_progress", true)"#), Ok(Value::Bool(false))));
+    }
+}
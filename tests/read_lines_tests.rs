@@ -0,0 +1,97 @@
+// Тесты для read_lines: построчное чтение текстовых файлов
+#[cfg(test)]
+mod tests {
+    use data_code::websocket::{set_use_ve, set_user_session_path};
+    use data_code::{run, Value};
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Временный файл с заданным содержимым; путь с '/' для подстановки в строку DataCode
+    fn temp_file(name: &str, content: &[u8]) -> (PathBuf, String) {
+        let path = std::env::temp_dir().join(format!("datacode_read_lines_{}_{}.txt", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let source_path = path.to_string_lossy().replace('\\', "/");
+        (path, source_path)
+    }
+
+    fn lines_of(content: &[u8]) -> Result<Value, data_code::LangError> {
+        let (path, source_path) = temp_file("lines", content);
+        let result = run(&format!(r#"str(read_lines("{}"))"#, source_path));
+        fs::remove_file(path).unwrap();
+        result
+    }
+
+    fn assert_lines(content: &[u8], expected: &str) {
+        match lines_of(content) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            other => panic!("Expected String({}), got {:?}", expected, other),
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_lines(b"a\nb\nc\n", "[a, b, c]");
+        assert_lines(b"a\r\nb\r\nc\r\n", "[a, b, c]");
+        // Смешанные окончания и последняя строка без перевода строки
+        assert_lines(b"a\r\nb\nc", "[a, b, c]");
+        assert_lines(b"a\n\nb", "[a, , b]");
+        assert_lines(b"", "[]");
+    }
+
+    #[test]
+    fn test_for_loop_over_lines() {
+        let (path, source_path) = temp_file("loop", b"id,amount\n1,10\n2,32\n");
+        let source = format!(r#"
+            let total = 0
+            let header = true
+            for line in read_lines(path("{}")) {{
+                if header {{
+                    header = false
+                    continue
+                }}
+                total = total + int(split(line, ",")[1])
+            }}
+            str(total)
+        "#, source_path);
+        let result = run(&source);
+        fs::remove_file(path).unwrap();
+        assert!(matches!(result, Ok(Value::String(ref s)) if &**s == "42"), "{:?}", result);
+    }
+
+    #[test]
+    fn test_errors() {
+        let missing = std::env::temp_dir().join("datacode_read_lines_missing.txt");
+        let result = run(&format!(r#"read_lines("{}")"#, missing.to_string_lossy().replace('\\', "/")));
+        assert!(result.is_err(), "{:?}", result);
+        assert!(lines_of(&[0x66, 0xff, 0x0a]).is_err());
+
+        let source = r#"
+            let kind = ""
+            try {
+                read_lines(42)
+            } catch TypeError e {
+                kind = "type"
+            }
+            kind
+        "#;
+        assert!(matches!(run(source), Ok(Value::String(ref s)) if &**s == "type"));
+    }
+
+    #[test]
+    fn test_use_ve_reads_relative_to_session() {
+        let dir = std::env::temp_dir().join(format!("datacode_read_lines_session_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "first\r\nsecond").unwrap();
+
+        set_use_ve(true);
+        set_user_session_path(Some(dir.clone()));
+        let inside = run(r#"str(read_lines("notes.txt"))"#);
+        let outside = run(r#"read_lines("../notes.txt")"#);
+        set_use_ve(false);
+        set_user_session_path(None);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(inside, Ok(Value::String(ref s)) if &**s == "[first, second]"), "{:?}", inside);
+        assert!(outside.is_err(), "{:?}", outside);
+    }
+}
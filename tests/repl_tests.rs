@@ -0,0 +1,63 @@
+// Тесты REPL: какие результаты строк выводятся и переменная `_` с последним выведенным значением
+#[cfg(test)]
+mod tests {
+    use data_code::repl::{run_repl_line, ReplSession};
+    use data_code::{run, Value};
+
+    fn echoed(session: &mut ReplSession, line: &str) -> Option<String> {
+        run_repl_line(session, line)
+            .unwrap_or_else(|e| panic!("{} => {}", line, e))
+            .map(|value| value.to_string())
+    }
+
+    #[test]
+    fn test_echo_rules() {
+        let mut session = ReplSession::new();
+        assert_eq!(echoed(&mut session, "1 + 2"), Some("3".to_string()));
+        assert_eq!(echoed(&mut session, "\"abc\""), Some("abc".to_string()));
+        assert_eq!(echoed(&mut session, "[1, 2]"), Some("[1, 2]".to_string()));
+
+        // Присваивания, объявления и null не выводятся
+        for line in ["let x = 5", "global y = 5", "x = 10", "x += 1", "print(\"hi\")", "null", "fn f() { return 1 }"] {
+            assert_eq!(echoed(&mut session, line), None, "{}", line);
+        }
+
+        // Выводится только последняя инструкция строки
+        assert_eq!(echoed(&mut session, "let a = 2\na * 21"), Some("42".to_string()));
+        assert_eq!(echoed(&mut session, "7\nlet b = 1"), None);
+
+        // Таблица выводится как значение
+        let table = run_repl_line(&mut session, "table([[1, \"a\"]], [\"id\", \"name\"])").unwrap();
+        assert!(matches!(table, Some(Value::Table(_))));
+    }
+
+    #[test]
+    fn test_last_value_binding() {
+        let mut session = ReplSession::new();
+        // До первого вывода `_` не определена
+        assert!(run_repl_line(&mut session, "_").is_err());
+        assert!(session.last_value().is_none());
+
+        assert_eq!(echoed(&mut session, "6 * 7"), Some("42".to_string()));
+        assert_eq!(echoed(&mut session, "_ + 1"), Some("43".to_string()));
+        assert_eq!(echoed(&mut session, "_ * 2"), Some("86".to_string()));
+
+        // Невыведенные результаты и ошибки не меняют `_`
+        assert_eq!(echoed(&mut session, "let half = _ / 2"), None);
+        assert_eq!(echoed(&mut session, "print(_)"), None);
+        assert!(run_repl_line(&mut session, "undefined_function()").is_err());
+        assert_eq!(echoed(&mut session, "_"), Some("86".to_string()));
+
+        // Массивы доступны через `_` так же, как числа
+        echoed(&mut session, "[3, 1, 2]");
+        assert_eq!(echoed(&mut session, "len(_)"), Some("3".to_string()));
+        assert!(matches!(session.last_value(), Some(Value::Number(n)) if *n == 3.0));
+    }
+
+    #[test]
+    fn test_file_run_discards_expression_values() {
+        // run() возвращает результат последней инструкции, но ничего не выводит сам
+        assert!(matches!(run("let x = 1\nx + 1"), Ok(Value::Integer(2))));
+        assert!(run("_").is_err());
+    }
+}
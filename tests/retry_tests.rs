@@ -0,0 +1,160 @@
+// Тесты sleep() и retry(): экспоненциальная пауза между попытками, выборочный повтор по виду
+// ошибки (retry_on), прерывание по таймауту и виды ошибок SMB
+#[cfg(test)]
+mod tests {
+    use data_code::common::error::ErrorType;
+    use data_code::vm::policy::{is_timeout_error, reset_execution_policy, set_execution_policy, ExecutionPolicy};
+    use data_code::websocket::smb;
+    use data_code::{run, Value};
+    use std::time::{Duration, Instant};
+
+    // flaky() падает первые fail_count вызовов ошибкой вида kind, затем возвращает номер вызова
+    fn flaky(fail_count: usize, kind: &str) -> String {
+        format!(
+            r#"
+            global calls = 0
+            fn flaky() {{
+                global calls = calls + 1
+                if calls <= {} {{
+                    throw {{"kind": "{}", "message": "share unavailable"}}
+                }}
+                return calls
+            }}
+            "#,
+            fail_count, kind
+        )
+    }
+
+    fn string_result(source: &str) -> String {
+        match run(source) {
+            Ok(Value::String(s)) => s.to_string(),
+            Ok(v) => panic!("Expected String, got {:?}", v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_retry_succeeds_after_backoff() {
+        let started = Instant::now();
+        let source = format!("{}\nstr(retry(flaky, 4, 30, 2))", flaky(3, "ConnectionError"));
+        assert_eq!(string_result(&source), "4");
+        // Паузы 30 + 60 + 120 мс между четырьмя попытками
+        assert!(started.elapsed() >= Duration::from_millis(210), "{:?}", started.elapsed());
+
+        // Функция по имени; первая успешная попытка не ждет
+        let started = Instant::now();
+        let source = format!("{}\nstr(retry(\"flaky\", backoff_ms = 5000))", flaky(0, "ConnectionError"));
+        assert_eq!(string_result(&source), "1");
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_retry_gives_up_with_last_error() {
+        let source = format!(
+            r#"{}
+            let message = ""
+            try {{
+                retry(flaky, 3, 1)
+            }} catch ConnectionError e {{
+                message = e["message"]
+            }}
+            message + " " + str(calls)
+            "#,
+            flaky(10, "ConnectionError")
+        );
+        assert_eq!(string_result(&source), "share unavailable 3");
+
+        let message = error_message(&format!("{}\nretry(flaky, 2, 1)", flaky(10, "ConnectionError")));
+        assert!(message.contains("retry(): failed after 2 attempts: share unavailable"), "{}", message);
+    }
+
+    #[test]
+    fn test_retry_on_is_selective() {
+        // Ошибка другого вида пробрасывается после первой попытки
+        let source = format!(
+            r#"{}
+            try {{
+                retry(flaky, 5, 1, retry_on = ["ConnectionError"])
+            }} catch e {{
+            }}
+            str(calls)
+            "#,
+            flaky(10, "ValidationError")
+        );
+        assert_eq!(string_result(&source), "1");
+
+        // Встроенные виды сопоставляются с учетом иерархии, как в typed catch
+        let source = r#"
+            global calls = 0
+            fn missing() {
+                global calls = calls + 1
+                return read_file("definitely_missing_file.csv")
+            }
+            try {
+                retry(missing, 3, 1, retry_on = "IOError")
+            } catch IOError e {
+            }
+            let by_io = calls
+            global calls = 0
+            try {
+                retry(missing, 3, 1, retry_on = ["ConnectionError"])
+            } catch e {
+            }
+            str([by_io, calls])
+        "#;
+        assert_eq!(string_result(source), "[3, 1]");
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let base = flaky(0, "ConnectionError");
+        for (call, expected) in [
+            ("retry(42)", "retry() expects a function or a function name"),
+            ("retry(\"nope\")", "retry(): undefined function 'nope'"),
+            ("retry(flaky, 0)", "attempts must be a positive integer"),
+            ("retry(flaky, 2.5)", "attempts must be a positive integer"),
+            ("retry(flaky, 3, -1)", "backoff_ms must be a non-negative number"),
+            ("retry(flaky, 3, 10, 0)", "factor must be a positive number"),
+            ("retry(flaky, retry_on = 5)", "retry_on must be an error type name or an array of names"),
+            ("sleep(\"1s\")", "sleep() expects a number of milliseconds"),
+            ("sleep(-5)", "ms must be a non-negative number"),
+        ] {
+            let message = error_message(&format!("{}\n{}", base, call));
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_sleep_is_capped_by_timeout() {
+        let started = Instant::now();
+        assert!(matches!(run("sleep(40)"), Ok(Value::Null)));
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        // Таймаут прерывает сон и не перехватывается try/catch, в том числе внутри retry()
+        set_execution_policy(ExecutionPolicy { timeout: Some(Duration::from_millis(150)), ..ExecutionPolicy::default() });
+        let started = Instant::now();
+        let slept = run("try {\n    sleep(60000)\n} catch e {\n}");
+        let retried = run(&format!("{}\nretry(flaky, 100, 10000)", flaky(100, "ConnectionError")));
+        reset_execution_policy();
+        for result in [slept, retried] {
+            let error = result.unwrap_err();
+            assert!(is_timeout_error(&error), "{}", error);
+        }
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_smb_error_kinds() {
+        assert_eq!(smb::error_type("Ошибка чтения файла: NT_STATUS_IO_TIMEOUT"), ErrorType::ConnectionError);
+        assert_eq!(smb::error_type("Ошибка чтения файла: NT_STATUS_OBJECT_NAME_NOT_FOUND"), ErrorType::FileNotFoundError);
+        assert_eq!(smb::error_type("session setup failed: NT_STATUS_LOGON_FAILURE"), ErrorType::PermissionError);
+        assert_eq!(smb::error_type("Ошибка чтения файла: NT_STATUS_ACCESS_DENIED"), ErrorType::PermissionError);
+        assert_eq!(smb::error_type("smbclient не найден"), ErrorType::IOError);
+        assert!(ErrorType::ConnectionError.is_instance_of(&ErrorType::IOError));
+        assert_eq!(ErrorType::from_name("ConnectionError"), Some(ErrorType::ConnectionError));
+    }
+}
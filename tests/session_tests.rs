@@ -0,0 +1,103 @@
+// Тесты Session: выполнение по шагам на общей VM, перехват вывода, обмен переменными и политика
+#[cfg(test)]
+mod tests {
+    use data_code::common::table::Table;
+    use data_code::vm::policy::ExecutionPolicy;
+    use data_code::{Session, Value};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_state_persists_between_execs() {
+        let mut session = Session::new().capture_output(true);
+        let first = session.exec("let x = 20\nfn double(n) { return n * 2 }\nprint(\"hi\")");
+        assert!(first.is_ok(), "{:?}", first.error);
+        assert_eq!(first.output, "hi\n");
+
+        // Переменная и функция из предыдущего шага доступны
+        let second = session.exec("let y = double(x) + 2\nprint(y)\ny");
+        assert!(second.is_ok(), "{:?}", second.error);
+        assert_eq!(second.output, "42\n");
+        assert_eq!(second.value, Value::Number(42.0));
+        assert_eq!(session.get_global("y"), Some(Value::Number(42.0)));
+
+        // Повторное объявление переменной в следующем шаге
+        let third = session.exec("let x = 1\nx + y");
+        assert_eq!(third.value, Value::Number(43.0));
+    }
+
+    #[test]
+    fn test_output_not_captured_by_default() {
+        let mut session = Session::new();
+        let outcome = session.exec("print(\"to stdout\")\n5");
+        assert!(outcome.is_ok());
+        assert_eq!(outcome.output, "");
+        assert_eq!(outcome.value, Value::Number(5.0));
+    }
+
+    #[test]
+    fn test_set_global_inputs_and_get_global_results() {
+        let table = Table::from_data(
+            vec![
+                vec![Value::String("a".into()), Value::Number(10.0)],
+                vec![Value::String("b".into()), Value::Number(32.0)],
+            ],
+            Some(vec!["name".to_string(), "amount".to_string()]),
+        );
+        let mut session = Session::new();
+        session.set_global("data", Value::Table(Rc::new(RefCell::new(table))));
+        session.set_global("factor", Value::Number(2.0));
+
+        let outcome = session.exec(
+            "let total = 0\nfor row in data.rows {\n    total = total + row[1] * factor\n}",
+        );
+        assert!(outcome.is_ok(), "{:?}", outcome.error);
+        assert_eq!(session.get_global("total"), Some(Value::Number(84.0)));
+
+        // Значение, заданное между шагами, заменяет прежнее
+        session.set_global("factor", Value::Number(1.0));
+        let outcome = session.exec("total = 0\nfor row in data.rows {\n    total = total + row[1] * factor\n}\ntotal");
+        assert_eq!(outcome.value, Value::Number(42.0));
+        assert_eq!(session.get_global("missing"), None);
+    }
+
+    #[test]
+    fn test_error_keeps_session_usable() {
+        let mut session = Session::new().capture_output(true);
+        let outcome = session.exec("let before = 1\nprint(\"partial\")\nlet broken = 1 / 0");
+        assert!(!outcome.is_ok());
+        let error = outcome.error.unwrap();
+        assert!(error.message.contains("Division by zero"), "{:?}", error);
+        assert_eq!(error.line, Some(3));
+        assert_eq!(outcome.output, "partial\n");
+        assert_eq!(outcome.value, Value::Null);
+
+        let outcome = session.exec("before + 1");
+        assert!(outcome.is_ok(), "{:?}", outcome.error);
+        assert_eq!(outcome.value, Value::Number(2.0));
+
+        // Синтаксическая ошибка тоже не сбрасывает состояние
+        let outcome = session.exec("let = 2");
+        assert_eq!(outcome.error.unwrap().kind, "ParseError");
+        assert_eq!(session.exec("before").value, Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_session_policy() {
+        let policy = ExecutionPolicy { allow_file_read: false, ..ExecutionPolicy::default() };
+        let mut session = Session::new().policy(policy);
+        let outcome = session.exec("read_file(\"data.txt\")");
+        let error = outcome.error.unwrap();
+        assert!(error.message.contains("reading files is disabled by the execution policy"), "{:?}", error);
+
+        // Политика действует только внутри вызовов сессии
+        assert!(data_code::vm::policy::get_execution_policy().allow_file_read);
+    }
+
+    #[test]
+    fn test_run_with_vm_is_session_wrapper() {
+        let (value, vm) = data_code::run_with_vm("let answer = 42\nanswer").unwrap();
+        assert_eq!(value, Value::Number(42.0));
+        assert_eq!(vm.get_global_by_name("answer"), Some(&Value::Number(42.0)));
+    }
+}
@@ -0,0 +1,102 @@
+// Снимки вывода show_table() в форматах grid, markdown и csv: выбор колонок, обрезка длинных
+// строк, начало и конец длинной таблицы, номера строк, null и большие числа
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Session};
+
+    /// Таблица с длинной строкой, null, кавычками, '|' и числом шире i64
+    const FIXTURE: &str = r#"let t = table([
+    [1, "short", 2.5],
+    [2, "a very long description that goes on", null],
+    [3, null, 1000000.0 * 1000000.0 * 100000000.0],
+    [4, 'say "hi", x|y', -7],
+], ["id", "note", "amount"])
+"#;
+
+    /// Вывод show_table(...) над FIXTURE
+    fn show(call: &str) -> String {
+        let mut session = Session::new().capture_output(true);
+        let outcome = session.exec(&format!("{}{}", FIXTURE, call));
+        if let Some(error) = outcome.error {
+            panic!("{}\n=> {:?}", call, error);
+        }
+        outcome.output
+    }
+
+    fn error_message(call: &str) -> String {
+        format!("{}", run(&format!("{}{}", FIXTURE, call)).unwrap_err())
+    }
+
+    #[test]
+    fn test_grid_format() {
+        let expected = "\
+┌─────┬──────────────┬───────────────────────┐
+│  id │ note         │                amount │
+├─────┼──────────────┼───────────────────────┤
+│   1 │ short        │                   2.5 │
+│   2 │ a very long… │                  null │
+│   3 │ null         │ 100000000000000000000 │
+│   4 │ say \"hi\", x… │                    -7 │
+└─────┴──────────────┴───────────────────────┘
+";
+        assert_eq!(show("show_table(t, max_col_width = 12)"), expected);
+        // Начало и конец таблицы со строкой "…", номера строк и одна колонка
+        let expected = "\
+┌─────┬────────┐
+│   # │ amount │
+├─────┼────────┤
+│   0 │    2.5 │
+│   1 │   null │
+│   … │      … │
+│   3 │     -7 │
+└─────┴────────┘
+4 rows (1 hidden)
+";
+        assert_eq!(show("show_table(t, 3, columns = \"amount\", index = true)"), expected);
+        assert_eq!(show("show_table(t, max_rows = 3, columns = [\"amount\"], index = true)"), expected);
+        assert_eq!(show("show_table(table([], [\"a\"]))"), "Empty table\n");
+    }
+
+    #[test]
+    fn test_markdown_format() {
+        let expected = "\
+|   # | note           |  id |
+| --: | -------------- | --: |
+|   0 | short          |   1 |
+|   … | …              |   … |
+|   3 | say \"hi\", x\\|y |   4 |
+";
+        assert_eq!(show("show_table(t, columns = [\"note\", \"id\"], format = \"markdown\", index = true, max_rows = 2)"), expected);
+    }
+
+    #[test]
+    fn test_csv_format() {
+        let expected = "\
+id,note,amount
+1,short,2.5
+2,a very long description that goes on,
+3,,100000000000000000000
+4,\"say \"\"hi\"\", x|y\",-7
+";
+        // CSV выводится целиком и без обрезки строк
+        assert_eq!(show("show_table(t, format = \"csv\", max_col_width = 5)"), expected);
+        assert_eq!(show("show_table(table_lazy(t), format = \"csv\")"), expected);
+        // Явный max_rows - первые строки, без строки "…"
+        assert_eq!(show("show_table(t, format = \"csv\", max_rows = 1, columns = [\"amount\", \"id\"], index = true)"), "#,amount,id\n0,2.5,1\n");
+    }
+
+    #[test]
+    fn test_show_table_option_errors() {
+        for (call, expected) in [
+            ("show_table(t, columns = [\"nope\"])", "show_table(): unknown column: nope"),
+            ("show_table(t, columns = 5)", "show_table() columns must be a column name or an array of names"),
+            ("show_table(t, format = \"html\")", "show_table() format must be \"grid\", \"markdown\" or \"csv\", got \"html\""),
+            ("show_table(t, max_col_width = 0)", "show_table() max_col_width must be at least 1"),
+            ("show_table(t, max_rows = -1)", "show_table() max_rows must be a non-negative integer"),
+            ("show_table(t, index = \"yes\")", "show_table() index must be a boolean"),
+        ] {
+            let message = error_message(call);
+            assert!(message.contains(expected), "{}\n=> {}", call, message);
+        }
+    }
+}
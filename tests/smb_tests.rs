@@ -0,0 +1,167 @@
+// Тесты SMB функций скриптов (smb_connect, smb_list, smb_read, read_file("smb://...")) на
+// подставной реализации SmbShares вместо smbclient
+#[cfg(test)]
+mod tests {
+    use data_code::common::path::{smb_url_to_lib, split_smb_path};
+    use data_code::vm::file_ops::{clear_smb_manager, set_smb_manager};
+    use data_code::vm::policy::ExecutionPolicy;
+    use data_code::websocket::smb::{SmbConnection, SmbShares};
+    use data_code::{run, Session, Value};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    /// Шары в памяти: файлы по (шара, путь) и журнал обращений
+    #[derive(Default)]
+    struct MockShares {
+        connected: Vec<String>,
+        files: HashMap<(String, String), Vec<u8>>,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SmbShares for MockShares {
+        fn connect(&mut self, connection: SmbConnection) -> Result<String, String> {
+            if connection.password != "secret" {
+                // Как smbclient: логин и пароль попадают в вывод
+                return Err(format!(
+                    "session setup failed for {}%{}: NT_STATUS_LOGON_FAILURE",
+                    connection.login, connection.password
+                ));
+            }
+            self.connected.push(connection.share_name.clone());
+            Ok(format!("connected {}", connection.share_name))
+        }
+
+        fn list_files(&self, share_name: &str, path: &str) -> Result<Vec<String>, String> {
+            self.calls.lock().unwrap().push(format!("list {} {}", share_name, path));
+            let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+            let mut names: Vec<String> = self
+                .files
+                .keys()
+                .filter(|(share, file)| share == share_name && file.starts_with(&prefix))
+                .map(|(_, file)| file[prefix.len()..].to_string())
+                .collect();
+            names.sort();
+            Ok(names)
+        }
+
+        fn read_file(&self, share_name: &str, file_path: &str) -> Result<Vec<u8>, String> {
+            self.calls.lock().unwrap().push(format!("read {} {}", share_name, file_path));
+            if !self.connected.iter().any(|share| share == share_name) {
+                return Err(format!("SMB share '{}' не подключена", share_name));
+            }
+            self.files
+                .get(&(share_name.to_string(), file_path.to_string()))
+                .cloned()
+                .ok_or_else(|| format!("Ошибка чтения файла: NT_STATUS_OBJECT_NAME_NOT_FOUND {}", file_path))
+        }
+
+        fn write_file(&self, _share_name: &str, _file_path: &str, _content: &[u8], _append: bool) -> Result<(), String> {
+            Err("read-only mock".to_string())
+        }
+    }
+
+    /// Устанавливает подставные шары для текущего потока теста и возвращает журнал обращений
+    fn install_mock() -> Arc<Mutex<Vec<String>>> {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut mock = MockShares { calls: Arc::clone(&calls), ..MockShares::default() };
+        let mut add = |share: &str, path: &str, content: &[u8]| {
+            mock.files.insert((share.to_string(), path.to_string()), content.to_vec());
+        };
+        add("data", "reports/sales.csv", b"region,amount\nnorth,10\nsouth,32\n");
+        add("data", "reports/notes.txt", "Отчет готов".as_bytes());
+        add("data", "logo.png", &[0x89, b'P', b'N', b'G', 0xff, 0x00]);
+        set_smb_manager(Arc::new(Mutex::new(mock)));
+        calls
+    }
+
+    const CONNECT: &str = r#"let share = smb_connect("10.0.0.5", "analyst", "secret", "CORP", "data")"#;
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_smb_url_parsing() {
+        assert_eq!(split_smb_path("smb://data/reports/sales.csv"), Some(("data", "reports/sales.csv")));
+        assert_eq!(split_smb_path("lib://data/reports/sales.csv"), Some(("data", "reports/sales.csv")));
+        assert_eq!(split_smb_path("smb://data"), Some(("data", "")));
+        assert_eq!(split_smb_path("/tmp/smb://data"), None);
+        assert_eq!(split_smb_path("data/sales.csv"), None);
+        assert_eq!(smb_url_to_lib(Path::new("smb://data/a.csv")), PathBuf::from("lib://data/a.csv"));
+        assert_eq!(smb_url_to_lib(Path::new("local/a.csv")), PathBuf::from("local/a.csv"));
+    }
+
+    #[test]
+    fn test_read_file_routes_smb_urls_to_manager() {
+        let calls = install_mock();
+        let result = run(&format!(
+            "{}\nlet t = read_file(\"smb://data/reports/sales.csv\")\n[str(share), len(t), t[\"amount\"][1], read_file(\"lib://data/reports/notes.txt\")]",
+            CONNECT
+        ));
+        clear_smb_manager();
+        assert_eq!(
+            result.unwrap().to_string(),
+            "[lib://data, 2, 32, Отчет готов]"
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["read data reports/sales.csv".to_string(), "read data reports/notes.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_smb_list_and_read() {
+        install_mock();
+        let result = run(&format!(
+            "{}\n[smb_list(\"data\", \"reports\"), smb_read(\"data\", \"reports/notes.txt\"), smb_read(\"data\", \"logo.png\")]",
+            CONNECT
+        ));
+        clear_smb_manager();
+        assert_eq!(result.unwrap().to_string(), "[[notes.txt, sales.csv], Отчет готов, base64:iVBOR/8A]");
+    }
+
+    #[test]
+    fn test_smb_errors_keep_kind_and_hide_credentials() {
+        install_mock();
+        let message = error_message(r#"smb_connect("10.0.0.5", "analyst", "hunter2", "CORP", "data")"#);
+        let not_found = error_message(&format!("{}\nsmb_read(\"data\", \"missing.csv\")", CONNECT));
+        let caught = run(&format!(
+            "{}\nlet status = \"read\"\ntry {{\n    smb_read(\"data\", \"missing.csv\")\n}} catch FileNotFoundError e {{\n    status = \"not found\"\n}}\nstatus",
+            CONNECT
+        ));
+        let not_connected = error_message(r#"smb_read("archive", "a.txt")"#);
+        let type_error = error_message(r#"smb_connect("10.0.0.5", "analyst", 42, "CORP", "data")"#);
+        clear_smb_manager();
+
+        assert!(message.contains("smb_connect(): session setup failed for ***%***"), "{}", message);
+        assert!(!message.contains("hunter2") && !message.contains("analyst"), "{}", message);
+        assert!(not_found.contains("smb_read(): Ошибка чтения файла"), "{}", not_found);
+        assert_eq!(caught.unwrap(), Value::String("not found".into()));
+        assert!(not_connected.contains("'archive' не подключена"), "{}", not_connected);
+        assert!(type_error.contains("smb_connect() password must be a string"), "{}", type_error);
+        assert!(!type_error.contains("42"), "{}", type_error);
+    }
+
+    #[test]
+    fn test_policy_allow_smb() {
+        install_mock();
+        let policy = ExecutionPolicy { allow_smb: false, ..ExecutionPolicy::default() };
+        let mut session = Session::new().policy(policy);
+        let connect = session.exec(CONNECT).error.unwrap();
+        let read = session.exec(r#"smb_read("data", "logo.png")"#).error.unwrap();
+        let url = session.exec(r#"read_file("smb://data/logo.png")"#).error.unwrap();
+        clear_smb_manager();
+        for error in [connect, read, url] {
+            assert!(error.message.contains("SMB access is disabled by the execution policy"), "{:?}", error);
+            assert_eq!(error.kind, "PermissionError");
+        }
+    }
+
+    #[test]
+    fn test_process_manager_used_outside_websocket() {
+        // Без set_smb_manager функции работают с общим менеджером процесса
+        let message = error_message(r#"smb_list("never_connected_share")"#);
+        assert!(message.contains("smb_list(): SMB share 'never_connected_share' не подключена"), "{}", message);
+    }
+}
@@ -0,0 +1,92 @@
+// Тесты раскрытия ...: массивы и объекты в литералах, позиционные и именованные аргументы вызова
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+    use std::path::PathBuf;
+
+    fn get_test_data_path(filename: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests");
+        path.push("test_data");
+        path.push(filename);
+        path.to_string_lossy().to_string()
+    }
+
+    fn eval_string(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_string(),
+            Err(e) => panic!("{}\n=> {}", source, e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        format!("{}", run(source).unwrap_err())
+    }
+
+    const F: &str = "fn f(alpha, rate, scale = 10) {\n    return alpha * 100 + rate * scale\n}\n";
+
+    #[test]
+    fn test_array_literal_spread() {
+        assert_eq!(eval_string("let a = [1, 2]\nlet b = [3]\n[...a, ...b, 5]"), "[1, 2, 3, 5]");
+        assert_eq!(eval_string("let a = [1, 2]\n[0, ...a, ...[], 9,]"), "[0, 1, 2, 9]");
+        // Исходный массив не меняется
+        assert_eq!(eval_string("let a = [1]\nlet b = [...a, 2]\npush(b, 3)\n[len(a), len(b)]"), "[1, 3]");
+        // Переменная родительской функции в spread захватывается вложенной функцией
+        let source = "fn outer() {\n    let xs = [1]\n    fn inner() {\n        return [...xs, 2]\n    }\n    return inner()\n}\nouter()";
+        assert_eq!(eval_string(source), "[1, 2]");
+    }
+
+    #[test]
+    fn test_object_literal_spread() {
+        let source = "let defaults = {\"x\": 0, \"y\": 0, \"tag\": \"base\"}\nlet o = {...defaults, \"x\": 1}\n[o[\"x\"], o[\"y\"], o[\"tag\"], len(o)]";
+        assert_eq!(eval_string(source), "[1, 0, base, 3]");
+        // Последний ключ побеждает, в том числе раскрытый после явного
+        let source = "let o = {\"x\": 1, ...{\"x\": 2, \"z\": 3}}\n[o[\"x\"], o[\"z\"]]";
+        assert_eq!(eval_string(source), "[2, 3]");
+    }
+
+    #[test]
+    fn test_object_spread_binds_user_function_args_by_name() {
+        // Порядок ключей не влияет на привязку
+        assert_eq!(run(&format!("{}f(...{{\"rate\": 2, \"alpha\": 1}})", F)).unwrap(), Value::Number(120.0));
+        assert_eq!(run(&format!("{}f(...{{\"alpha\": 1, \"rate\": 2}})", F)).unwrap(), Value::Number(120.0));
+        assert_eq!(run(&format!("{}f(...[1, 2])", F)).unwrap(), Value::Number(120.0));
+        assert_eq!(run(&format!("{}f(...[1], rate = 2, ...{{\"scale\": 1}})", F)).unwrap(), Value::Number(102.0));
+        assert_eq!(run("max(...[3, 9], 4)").unwrap(), Value::Number(9.0));
+    }
+
+    #[test]
+    fn test_object_spread_into_read_file_named_args() {
+        let csv_path = get_test_data_path("semicolon_cp1251.csv");
+        let source = format!(
+            "let options = {{\"encoding\": \"cp1251\", \"delimiter\": \";\"}}\nlet t = read_file(\"{}\", ...options)\n[t[\"Город\"][0], len(t[\"Город\"])]",
+            csv_path
+        );
+        assert_eq!(eval_string(&source), "[Москва, 3]");
+        let source = format!("let args = [\"{}\"]\nlet t = read_file(...args, ...{{\"delimiter\": \";\", \"encoding\": \"cp1251\"}})\nlen(t[\"Город\"])", csv_path);
+        assert_eq!(run(&source).unwrap(), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_spread_errors() {
+        for (source, expected) in [
+            (format!("{}f(...{{\"alpha\": 1, \"ratio\": 2}})", F), "Function 'f' got an unexpected keyword argument 'ratio'"),
+            (format!("{}f(...[1], ...{{\"alpha\": 2}})", F), "Function 'f' got multiple values for argument 'alpha'"),
+            (format!("{}f(alpha = 1, ...{{\"alpha\": 2}})", F), "Got multiple values for keyword argument 'alpha'"),
+            (format!("{}f(...{{\"alpha\": 1}})", F), "Function 'f' missing required argument 'rate'"),
+            (format!("{}f(...5)", F), "Cannot spread int into function arguments"),
+            ("read_file(\"a.csv\", ...{\"delim\": \";\"})".to_string(), "Function 'read_file' got an unexpected keyword argument 'delim'"),
+            ("print(...{\"sep\": \",\"})".to_string(), "Named arguments are not supported for built-in function 'print'"),
+            ("[...\"abc\"]".to_string(), "Cannot spread string into an array"),
+            ("let o = {...[1]}".to_string(), "Cannot spread array into an object"),
+        ] {
+            let message = error_message(&source);
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+        // Ошибка привязки - TypeError, ее можно перехватить
+        let caught = run(&format!("{}let r = \"none\"\ntry {{\n    f(...{{\"beta\": 1}})\n}} catch TypeError e {{\n    r = \"caught\"\n}}\nr", F));
+        assert_eq!(caught.unwrap(), Value::String("caught".into()));
+        // Spread вне вызова и литералов - ошибка
+        assert!(run("let x = ...[1]").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// Тесты экспорта в SQLite значений, не являющихся таблицами: массивы объектов и скаляры
+#[cfg(test)]
+mod tests {
+    use data_code::run_with_vm;
+    use data_code::sqlite_export::export_to_sqlite;
+    use rusqlite::Connection;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn export(name: &str, source: &str) -> (Connection, PathBuf) {
+        let (_, vm) = run_with_vm(source).expect("script failed");
+        let path = std::env::temp_dir().join(format!("datacode_value_export_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        export_to_sqlite(&vm, path.to_str().unwrap()).expect("export failed");
+        (Connection::open(&path).unwrap(), path)
+    }
+
+    fn table_names(conn: &Connection) -> Vec<String> {
+        conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        conn.prepare(&format!("SELECT name FROM pragma_table_info('{}') ORDER BY cid", table))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_array_of_objects_becomes_table() {
+        let (conn, path) = export("objects", r#"
+            global results = [
+                {"city": "Moscow", "total": 120},
+                {"city": "Kazan", "total": 45, "note": "partial"},
+                {"total": 7, "city": "Omsk"}
+            ]
+            global numbers = [1, 2, 3]
+            global mixed = [{"a": 1}, 2]
+            global empty = []
+        "#);
+
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "results"]);
+        assert_eq!(columns(&conn, "results"), vec!["city", "total", "note"]);
+        let rows: Vec<(String, i64, Option<String>)> = conn
+            .prepare("SELECT city, total, note FROM results ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("Moscow".to_string(), 120, None),
+                ("Kazan".to_string(), 45, Some("partial".to_string())),
+                ("Omsk".to_string(), 7, None),
+            ]
+        );
+
+        let (variable_type, table_name, row_count): (String, Option<String>, Option<i64>) = conn
+            .query_row(
+                "SELECT variable_type, table_name, row_count FROM _datacode_variables WHERE variable_name = 'results'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((variable_type.as_str(), table_name.as_deref(), row_count), ("Array", Some("results"), Some(3)));
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_scalars_are_exported_as_key_value() {
+        let (conn, path) = export("scalars", r#"
+            global customers = table([[1, "ann"]], ["id", "name"])
+            global threshold = 0.75
+            global run_count = 3
+            global label = "march"
+            global enabled = true
+            global nothing = null
+            let local_only = 5
+        "#);
+
+        assert_eq!(table_names(&conn), vec!["_datacode_scalars", "_datacode_variables", "customers"]);
+        let scalars: Vec<(String, String, String)> = conn
+            .prepare("SELECT name, value_type, quote(value) FROM _datacode_scalars ORDER BY name")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let expected = [
+            ("enabled", "Bool", "1"),
+            ("label", "String", "'march'"),
+            ("nothing", "Null", "NULL"),
+            ("run_count", "Integer", "3"),
+            ("threshold", "Number", "0.75"),
+        ];
+        assert_eq!(
+            scalars,
+            expected.iter().map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string())).collect::<Vec<_>>()
+        );
+        drop(conn);
+        let _ = fs::remove_file(&path);
+
+        // Без скалярных глобальных переменных таблица не создается
+        let (conn, path) = export("no_scalars", "global customers = table([[1]], [\"id\"])");
+        assert_eq!(table_names(&conn), vec!["_datacode_variables", "customers"]);
+        drop(conn);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,150 @@
+// Тесты для table_string_agg, table_collect и table_merge_columns: порядок значений в группах,
+// пустые группы и экспорт ячеек-массивов в CSV и SQLite
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+    use std::fs;
+
+    const ORDERS: &str = r#"
+        let orders = table([
+            [1001, "ann", "north"],
+            [1002, "bob", "south"],
+            [1007, "ann", "north"],
+            [1010, "cid", null],
+            [1033, "ann", "south"],
+            [1040, "bob", "south"]
+        ], ["id", "customer", "region"])
+    "#;
+
+    fn string_result(source: &str) -> String {
+        match run(&format!("{}\n{}", ORDERS, source)) {
+            Ok(Value::String(s)) => s.to_string(),
+            Ok(v) => panic!("Expected String, got {:?}", v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_string_agg_keeps_group_and_row_order() {
+        let result = string_result(r#"
+            str(table_string_agg(orders, "customer", "id", "; ")["rows"])
+        "#);
+        assert_eq!(result, "[[ann, 1001; 1007; 1033], [bob, 1002; 1040], [cid, 1010]]");
+
+        // Разделитель по умолчанию и несколько ключей
+        let result = string_result(r#"
+            str(table_string_agg(orders, ["customer", "region"], "id")["rows"])
+        "#);
+        assert_eq!(
+            result,
+            "[[ann, north, 1001, 1007], [bob, south, 1002, 1040], [cid, null, 1010], [ann, south, 1033]]"
+        );
+    }
+
+    #[test]
+    fn test_collect_produces_array_cells() {
+        let result = string_result(r#"
+            let first = table_collect(orders, "customer", "id")["rows"][0]
+            typeof(first[1]) + " " + str(first[1]) + " " + str(len(first[1]))
+        "#);
+        assert_eq!(result, "array [1001, 1007, 1033] 3");
+
+        // Повторный вызов дает тот же порядок
+        let result = string_result(r#"
+            let a = table_collect(orders, ["customer"], "id")
+            let b = table_collect(orders, ["customer"], "id")
+            str(a["rows"] == b["rows"])
+        "#);
+        assert_eq!(result, "true");
+    }
+
+    #[test]
+    fn test_null_values_leave_empty_groups() {
+        let result = string_result(r#"
+            let t = table_string_agg(orders, "customer", "region", "/")
+            let c = table_collect(orders, "customer", "region")
+            str(t["rows"]) + " " + str(c["rows"][2])
+        "#);
+        assert_eq!(result, "[[ann, north/north/south], [bob, south/south], [cid, ]] [cid, []]");
+
+        // Пустая таблица - пустой результат с ключевыми колонками и колонкой значений
+        let result = string_result(r#"
+            let empty = table_where(orders, "id", ">", 5000)
+            let t = table_collect(empty, "customer", "id")
+            str(len(t["rows"])) + " " + str(t["columns"])
+        "#);
+        assert_eq!(result, "0 [customer, id]");
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        for (source, expected) in [
+            (r#"table_collect(orders, "customer", "missing")"#, "unknown column(s): missing"),
+            (r#"table_collect(orders, ["id"], "id")"#, "column 'id' is also a key"),
+            (r#"table_string_agg(orders, "customer", "id", 1)"#, "separator must be a string"),
+            (r#"table_string_agg(orders, 1, "id")"#, "expects a key column name or an array of names"),
+        ] {
+            let message = format!("{}", run(&format!("{}\n{}", ORDERS, source)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", source, message);
+        }
+    }
+
+    #[test]
+    fn test_merge_columns() {
+        let result = string_result(r#"
+            let t = table_merge_columns(orders, ["customer", "region", "id"], "label", "-")
+            str(t["columns"]) + " " + str(t["rows"][0]) + " " + str(t["rows"][3])
+        "#);
+        assert_eq!(result, "[id, customer, region, label] [1001, ann, north, ann-north-1001] [1010, cid, null, cid-1010]");
+        let message = format!("{}", run(&format!("{}\ntable_merge_columns(orders, [\"id\"], \"customer\")", ORDERS)).unwrap_err());
+        assert!(message.contains("column 'customer' already exists"), "{}", message);
+    }
+
+    #[test]
+    fn test_array_cells_round_trip_through_csv() {
+        let dir = std::env::temp_dir().join(format!("datacode_string_agg_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("collected.csv");
+        let path = file.to_string_lossy().replace('\\', "/");
+        let result = string_result(&format!(r#"
+            write_file("{0}", table_collect(orders, "customer", "id"))
+            let back = read_file("{0}")
+            str(back["rows"][0][1])
+        "#, path));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "customer,id\nann,\"[1001,1007,1033]\"\nbob,\"[1002,1040]\"\ncid,[1010]\n"
+        );
+        assert_eq!(result, "[1001,1007,1033]");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_array_cells_exported_to_sqlite_as_json() {
+        let source = format!("{}\nglobal collected = table_collect(orders, \"customer\", \"region\")", ORDERS);
+        let (_, vm) = data_code::run_with_vm(&source).expect("script failed");
+
+        let db_path = std::env::temp_dir().join(format!("datacode_string_agg_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        data_code::sqlite_export::export_to_sqlite(&vm, db_path.to_str().unwrap()).expect("export failed");
+
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let declared: String = conn
+            .query_row("SELECT type FROM pragma_table_info('collected') WHERE name = 'region'", [], |row| row.get(0))
+            .unwrap();
+        let cells: Vec<String> = conn
+            .prepare("SELECT region FROM collected ORDER BY customer")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+
+        assert_eq!(declared, "TEXT");
+        assert_eq!(cells, vec![r#"["north","north","south"]"#, r#"["south","south"]"#, "[]"]);
+        let parsed: serde_json::Value = serde_json::from_str(&cells[0]).unwrap();
+        assert_eq!(parsed, serde_json::json!(["north", "north", "south"]));
+    }
+}
@@ -0,0 +1,153 @@
+// Тесты для table_filter_expr, table_where(table, expr) и set_option("undefined_vars", ...)
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    const ORDERS: &str = r#"
+        let orders = table([[1, 50, "new"], [2, 150, "paid"], [3, 300, "paid"]], ["id", "amount", "status"])
+    "#;
+
+    fn assert_number_result(source: &str, expected: f64) {
+        match run(source) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected),
+            Ok(Value::Integer(n)) => assert_eq!(n as f64, expected),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(value) => assert_eq!(value.to_string(), expected),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_error_contains(source: &str, expected: &str) {
+        let message = format!("{}", run(source).unwrap_err());
+        assert!(message.contains(expected), "{}", message);
+    }
+
+    #[test]
+    fn test_filter_by_row_columns() {
+        assert_number_result(&format!(r#"{}len(table_filter_expr(orders, "amount > 100"))"#, ORDERS), 2.0);
+        assert_number_result(
+            &format!(r#"{}let paid = table_filter_expr(orders, "status == 'paid' and amount < 200")
+            paid["id"][0]"#, ORDERS),
+            2.0,
+        );
+        // Глобальные переменные программы видны в выражении
+        assert_number_result(
+            &format!(r#"{}global limit = 200
+            len(table_filter_expr(orders, "amount >= limit"))"#, ORDERS),
+            1.0,
+        );
+    }
+
+    #[test]
+    fn test_unknown_column_is_an_error_by_default() {
+        assert_error_contains(
+            &format!(r#"{}table_filter_expr(orders, "discount > 0")"#, ORDERS),
+            "Undefined variable",
+        );
+        assert!(run(&format!(r#"{}table_filter_expr(orders, "discount > 0", missing="error")"#, ORDERS)).is_err());
+    }
+
+    #[test]
+    fn test_missing_null_is_scoped_to_the_call() {
+        let source = format!(r#"{}
+            let kept = len(table_filter_expr(orders, "discount == null or discount < 5", missing="null"))
+            let strict_after = false
+            try {{
+                let x = still_unknown
+            }} catch e {{
+                strict_after = true
+            }}
+            str([kept, strict_after])
+        "#, ORDERS);
+        assert_string_result(&source, "[3, true]");
+    }
+
+    #[test]
+    fn test_mode_is_restored_when_expression_throws() {
+        let source = format!(r#"{}
+            let failed = false
+            try {{
+                table_filter_expr(orders, "discount == null and amount / 0 > 1", missing="null")
+            }} catch e {{
+                failed = true
+            }}
+            let strict_after = false
+            try {{
+                let x = still_unknown
+            }} catch e {{
+                strict_after = true
+            }}
+            str([failed, strict_after])
+        "#, ORDERS);
+        assert_string_result(&source, "[true, true]");
+    }
+
+    #[test]
+    fn test_missing_null_applies_inside_called_functions() {
+        let functions = r#"
+            fn bonus_points() {
+                return loyalty_bonus
+            }
+        "#;
+        assert_number_result(
+            &format!(r#"{}{}len(table_filter_expr(orders, "bonus_points() == null and amount > 100", missing="null"))"#, ORDERS, functions),
+            2.0,
+        );
+    }
+
+    #[test]
+    fn test_missing_null_does_not_apply_to_function_names() {
+        assert_error_contains(
+            &format!(r#"{}table_filter_expr(orders, "no_such_function(amount)", missing="null")"#, ORDERS),
+            "Function 'no_such_function' not found",
+        );
+    }
+
+    #[test]
+    fn test_set_option_undefined_vars() {
+        let source = format!(r#"{}
+            let previous = set_option("undefined_vars", "null")
+            let kept = len(table_filter_expr(orders, "discount == null"))
+            let restored = set_option("undefined_vars", previous)
+            str([previous, kept, restored])
+        "#, ORDERS);
+        assert_string_result(&source, "[error, 3, null]");
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        assert_error_contains(r#"table_filter_expr([1, 2], "x > 1")"#, "expects a table and an expression string");
+        assert_error_contains(
+            &format!(r#"{}table_filter_expr(orders, "amount > 1", missing="skip")"#, ORDERS),
+            "missing must be \"null\" or \"error\"",
+        );
+        assert_error_contains(r#"set_option("colors", "on")"#, "unknown option 'colors'");
+        assert_error_contains(r#"set_option("undefined_vars", "maybe")"#, "undefined_vars must be");
+    }
+
+    #[test]
+    fn test_table_where_expression_string() {
+        assert_string_result(
+            &format!(r#"{}let paid = table_where(orders, "amount > 100 and status == 'paid'")
+            str([paid["id"][0], paid["id"][1], len(paid)])"#, ORDERS),
+            "[2, 3, 2]",
+        );
+        assert_number_result(&format!(r#"{}len(table_where(orders, "not (status == 'paid') or amount >= 300"))"#, ORDERS), 2.0);
+        // Форма (колонка, оператор, значение) работает как прежде
+        assert_number_result(&format!(r#"{}len(table_where(orders, "amount", ">", 100))"#, ORDERS), 2.0);
+    }
+
+    #[test]
+    fn test_table_where_unknown_column_names_it() {
+        let message = format!("{}", run(&format!(r#"{}table_where(orders, "amout > 100")"#, ORDERS)).unwrap_err());
+        assert!(message.contains("in table_where(\"amout > 100\")"), "{}", message);
+        assert!(message.contains("Undefined variable 'amout'"), "{}", message);
+        assert_error_contains(&format!(r#"{}table_where(orders, "amount", ">")"#, ORDERS), "table_where() expects");
+    }
+}
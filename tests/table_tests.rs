@@ -18,17 +18,33 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
 
+    // Вспомогательная функция для проверки целочисленного результата
+    fn assert_integer_result(source: &str, expected: i64) {
+        let result = run_and_get_result(source);
+        match result {
+            Ok(Value::Integer(n)) => {
+                assert_eq!(n, expected, "Expected {}, got {}", expected, n);
+            }
+            Ok(v) => panic!("Expected Integer({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
     // Вспомогательная функция для проверки строкового результата
     fn assert_string_result(source: &str, expected: &str) {
         let result = run_and_get_result(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, expected, "Expected '{}', got '{}'", expected, s);
+                assert_eq!(&*s, expected, "Expected '{}', got '{}'", expected, s);
             }
             Ok(v) => panic!("Expected String('{}'), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
@@ -252,7 +268,8 @@ mod tests {
             "#,
             csv_path
         );
-        assert_number_result(&source, 30.0);
+        // Целые числа из CSV читаются как Integer
+        assert_integer_result(&source, 30);
     }
 
     #[test]
@@ -287,6 +304,128 @@ mod tests {
         assert!(result.is_ok(), "Failed to load XLSX file");
     }
 
+    // Уникальный путь во временной директории для файлов, записываемых тестами
+    fn temp_output_path(filename: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!("datacode_{}_{}", std::process::id(), filename));
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_list_sheets_and_read_all_sheets() {
+        let xlsx_path = get_test_data_path("sample.xlsx");
+        let source = format!(
+            r#"
+            let names = list_sheets("{0}")
+            let sheets = read_file("{0}", sheet_name="*")
+            let first = sheets[names[0]]
+            len(names) == len(to_array(sheets)) and len(first) == len(read_file("{0}"))
+            "#,
+            xlsx_path
+        );
+        match run_and_get_result(&source) {
+            Ok(Value::Bool(b)) => assert!(b),
+            Ok(v) => panic!("Expected Bool, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_write_excel_round_trip() {
+        let out = temp_output_path("round_trip.xlsx");
+        let source = format!(
+            r#"
+            let t = table([[1, "Alice", 2.5, true], [2, null, -3, false]], ["id", "name", "score", "active"])
+            let sheets = write_excel("{0}", t)
+            let back = read_file("{0}")
+            let row = back.rows[1]
+            let result = [sheets[0], back.columns, row[0], row[1], row[2], row[3], back.rows[0][1]]
+            result
+            "#,
+            out
+        );
+        let result = run_and_get_result(&source);
+        let _ = std::fs::remove_file(&out);
+        match result {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::String("Sheet1".into()));
+                let columns: Vec<String> = match &arr[1] {
+                    Value::Array(cols) => cols.borrow().iter().map(|v| v.to_string()).collect(),
+                    v => panic!("Expected columns array, got {:?}", v),
+                };
+                assert_eq!(columns, vec!["id", "name", "score", "active"]);
+                assert_eq!(arr[2], Value::Number(2.0));
+                assert_eq!(arr[3], Value::Null);
+                assert_eq!(arr[4], Value::Number(-3.0));
+                assert_eq!(arr[5], Value::Bool(false));
+                assert_eq!(arr[6], Value::String("Alice".into()));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_write_excel_sanitizes_sheet_names() {
+        use data_code::common::table::Table;
+        use data_code::vm::natives::native_write_excel;
+        use std::cell::RefCell;
+        use data_code::common::value::ObjectMap;
+        use std::rc::Rc;
+
+        // Объектных литералов в языке нет - объект листов собираем напрямую
+        let sheet = |n: f64| Value::Table(Rc::new(RefCell::new(Table::from_data(
+            vec![vec![Value::Number(n)]],
+            Some(vec!["x".to_string()]),
+        ))));
+        let long_name = "a".repeat(40);
+        let mut sheets = ObjectMap::new();
+        sheets.insert("Q1/Q2: sales?".to_string(), sheet(1.0));
+        sheets.insert(long_name.clone(), sheet(2.0));
+        sheets.insert(format!("{}b", long_name), sheet(3.0));
+        sheets.insert("'report'".to_string(), sheet(4.0));
+
+        let out = temp_output_path("multi_sheet.xlsx");
+        let written = native_write_excel(&[Value::String(out.as_str().into()), Value::Object(sheets)]);
+        let names: Vec<String> = match written {
+            Value::Array(arr) => arr.borrow().iter().map(|v| v.to_string()).collect(),
+            v => panic!("Expected Array of sheet names, got {:?}", v),
+        };
+        // Листы идут в порядке ключей объекта; обрезанные до 31 символа имена не совпадают
+        assert_eq!(names, vec![
+            "Q1_Q2_ sales_".to_string(),
+            "a".repeat(31),
+            format!("{}~2", "a".repeat(29)),
+            "report".to_string(),
+        ]);
+
+        let source = format!(
+            r#"
+            let sheets = read_file("{}", sheet_name="*")
+            let result = [list_sheets("{}"), sheets["report"]["x"][0], sheets["{}~2"]["x"][0]]
+            result
+            "#,
+            out, out, "a".repeat(29)
+        );
+        let result = run_and_get_result(&source);
+        let _ = std::fs::remove_file(&out);
+        match result {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                let listed: Vec<String> = match &arr[0] {
+                    Value::Array(list) => list.borrow().iter().map(|v| v.to_string()).collect(),
+                    v => panic!("Expected Array, got {:?}", v),
+                };
+                assert_eq!(listed, names);
+                assert_eq!(arr[1], Value::Number(4.0));
+                assert_eq!(arr[2], Value::Number(3.0));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
     #[test]
     fn test_load_table_nonexistent_file() {
         // Обработка несуществующего файла
@@ -298,6 +437,393 @@ mod tests {
         assert!(result.is_err() || matches!(result, Ok(Value::Null)));
     }
 
+    #[test]
+    fn test_load_csv_semicolon_cp1251() {
+        // Разделитель ';' и кодировка cp1251: типы определяются после декодирования
+        let csv_path = get_test_data_path("semicolon_cp1251.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}", delimiter=";", encoding="cp1251")
+            let result = [t["Город"][0], t["Население"][1], t["Столица"][0], len(t["Город"])]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::String("Москва".into()));
+                assert_eq!(arr[1], Value::Number(1.3));
+                assert_eq!(arr[2], Value::Bool(true));
+                assert_eq!(arr[3], Value::Number(3.0));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_load_csv_bad_lines_error_by_default() {
+        // По умолчанию поврежденная строка - ошибка с номером строки
+        let csv_path = get_test_data_path("semicolon_broken.csv");
+        let source = format!(
+            r#"
+            let result = "none"
+            try {{
+                read_file("{}", delimiter=";")
+            }} catch IOError e {{
+                result = e
+            }}
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::String(message)) => {
+                assert!(message.contains("line 3"), "Unexpected message: {}", message);
+            }
+            Ok(v) => panic!("Expected String, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_load_csv_bad_lines_skip_and_report() {
+        // Поврежденное поле в кавычках посередине не "съедает" следующие строки
+        let csv_path = get_test_data_path("semicolon_broken.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}", delimiter=";", on_bad_lines="skip")
+            let report = last_read_report()
+            let ids = t["id"]
+            let result = [[ids[0], ids[1], ids[2]], t["name"][0], t["name"][2], report["rows"], report["skipped"], report["bad_lines"]]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                match &arr[0] {
+                    Value::Array(ids) => assert_eq!(
+                        *ids.borrow(),
+                        vec![Value::Number(1.0), Value::Number(3.0), Value::Number(4.0)]
+                    ),
+                    v => panic!("Expected Array, got {:?}", v),
+                }
+                assert_eq!(arr[1], Value::String("Smith; John".into()));
+                assert_eq!(arr[2], Value::String("Dave \"D\" Jr".into()));
+                assert_eq!(arr[3], Value::Number(3.0));
+                assert_eq!(arr[4], Value::Number(2.0));
+                match &arr[5] {
+                    Value::Array(lines) => assert_eq!(
+                        *lines.borrow(),
+                        vec![Value::Number(3.0), Value::Number(6.0)]
+                    ),
+                    v => panic!("Expected Array, got {:?}", v),
+                }
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_load_csv_bad_lines_null_fill() {
+        // null-fill сохраняет поврежденные строки, недостающие поля - null
+        let csv_path = get_test_data_path("semicolon_broken.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}", delimiter=";", on_bad_lines="null-fill")
+            let report = last_read_report()
+            let result = [len(t["id"]), t["score"][1], t["score"][4], t["name"][4], report["null_filled"], report["skipped"]]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::Number(5.0));
+                assert_eq!(arr[1], Value::Null);
+                assert_eq!(arr[2], Value::Null);
+                assert_eq!(arr[3], Value::String("Eve".into()));
+                assert_eq!(arr[4], Value::Number(2.0));
+                assert_eq!(arr[5], Value::Number(0.0));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_load_csv_skip_rows_quote_char_latin1() {
+        // Пропуск служебных строк, свой символ кавычек и кодировка latin1
+        let csv_path = get_test_data_path("latin1_skip_rows.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}", skip_rows=2, delimiter="|", quote_char="'", encoding="latin1")
+            let result = [t["name"][0], t["city"][0], t["rating"][1], len(t["name"])]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::String("José".into()));
+                assert_eq!(arr[1], Value::String("São Paulo|BR".into()));
+                assert_eq!(arr[2], Value::Number(3.0));
+                assert_eq!(arr[3], Value::Number(2.0));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_analyze_csv_semicolon_without_header() {
+        // Число над числами - данные: колонки называются Column_0, Column_1, ...
+        let csv_path = get_test_data_path("semicolon_no_header.csv");
+        let source = format!(
+            r#"
+            let report = analyze_csv("{0}")
+            let t = read_file("{0}", options=report)
+            let score = report["columns"][2]
+            [report["delimiter"], report["has_header"], report["header_row"], report["row_count"],
+             score["name"], score["type"], score["nulls"], score["confidence"], t["Column_1"][0], len(t["Column_0"])]
+            "#,
+            csv_path
+        );
+        let result = run_and_get_result(&source).unwrap_or_else(|e| panic!("Error: {:?}", e));
+        assert_eq!(result.to_string(), "[;, false, null, 4, Column_2, float, 1, 1, Alice, 4]");
+    }
+
+    #[test]
+    fn test_analyze_csv_tab_separated_with_header() {
+        let csv_path = get_test_data_path("tab_with_header.csv");
+        let source = format!(
+            r#"
+            let report = analyze_csv("{0}", print=true)
+            let t = read_file("{0}", options=report)
+            let population = report["columns"][1]
+            [report["has_header"], report["header_row"], report["encoding"], population["type"],
+             population["confidence"], population["samples"], report["columns"][2]["type"], t["city"][3]]
+            "#,
+            csv_path
+        );
+        let result = run_and_get_result(&source).unwrap_or_else(|e| panic!("Error: {:?}", e));
+        assert_eq!(result.to_string(), "[true, 0, UTF-8, float, 0.75, [12.6, 1.3, 0.4, n/a], bool, Tver]");
+        let delimiter = run_and_get_result(&format!("analyze_csv(\"{}\")[\"delimiter\"]", csv_path));
+        assert_eq!(delimiter.unwrap(), Value::String("\t".into()));
+
+        // Кодировка cp1251 определяется по байтам, заголовок - по тексту над числами
+        let csv_path = get_test_data_path("semicolon_cp1251.csv");
+        let source = format!(
+            r#"
+            let report = analyze_csv("{0}")
+            let t = read_file("{0}", options=report)
+            [report["encoding"], report["delimiter"], report["has_header"], t["Город"][0], t["Население"][1]]
+            "#,
+            csv_path
+        );
+        let result = run_and_get_result(&source).unwrap_or_else(|e| panic!("Error: {:?}", e));
+        assert_eq!(result.to_string(), "[windows-1251, ;, true, Москва, 1.3]");
+    }
+
+    #[test]
+    fn test_analyze_csv_skips_metadata_rows() {
+        // Пять служебных строк перед таблицей; в одной из них тоже есть запятая
+        let csv_path = get_test_data_path("metadata_junk.csv");
+        let source = format!(
+            r#"
+            let report = analyze_csv("{0}")
+            let t = read_file("{0}", options=report)
+            let amount = report["columns"][2]
+            [report["skip_rows"], report["header_row"], report["row_count"], report["bad_lines"],
+             amount["type"], amount["nulls"], t["region"][2], len(t["id"])]
+            "#,
+            csv_path
+        );
+        let result = run_and_get_result(&source).unwrap_or_else(|e| panic!("Error: {:?}", e));
+        assert_eq!(result.to_string(), "[5, 5, 3, 0, float, 1, east, central, 3]");
+
+        // Явный аргумент важнее значения из отчета
+        let source = format!(
+            "let t = read_file(\"{0}\", options=analyze_csv(\"{0}\"), skip_rows=6)\nt[\"columns\"]",
+            csv_path
+        );
+        let result = run_and_get_result(&source).unwrap_or_else(|e| panic!("Error: {:?}", e));
+        assert_eq!(result.to_string(), "[1, north, 10.5]");
+    }
+
+    #[test]
+    fn test_load_csv_integers_keep_precision() {
+        // 9007199254740993 не представимо в f64 и раньше превращалось в ...992
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}")
+            let ids = t["id"]
+            let result = [str(ids[0]), ids[0] + 2, ids[0] == ids[1], ids[1] - ids[0], typeof(ids[0])]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::String("9007199254740993".into()));
+                assert!(matches!(arr[1], Value::Integer(9007199254740995)), "got {:?}", arr[1]);
+                assert_eq!(arr[2], Value::Bool(false));
+                assert!(matches!(arr[3], Value::Integer(2)), "got {:?}", arr[3]);
+                assert_eq!(arr[4], Value::String("int".into()));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_integer_number_equality_is_exact() {
+        // 9007199254740993 as f64 округляется до ...992, но числа не равны
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}")
+            let id = t["id"][0]
+            let result = [id == 9007199254740992.0, id - 1 == 9007199254740992.0, id != 9007199254740992.0, 3 == 3.0, 3 == 3.5]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                let expected = [false, true, true, true, false].map(Value::Bool);
+                assert_eq!(arr.as_slice(), expected.as_slice());
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+        // i64::MAX as f64 = 2^63 уже вне диапазона i64
+        assert_ne!(Value::Integer(i64::MAX), Value::Number(i64::MAX as f64));
+        assert_eq!(Value::Integer(i64::MIN), Value::Number(i64::MIN as f64));
+        assert_eq!(Value::Integer(0), Value::Number(-0.0));
+    }
+
+    #[test]
+    fn test_integer_literals_keep_precision() {
+        // Литерал без точки - Integer, поэтому совпадает с id из CSV
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}")
+            let result = [str(9007199254740993), t["id"][0] == 9007199254740993, t["id"][0] == 9007199254740992, 2 + 0.5, 2.0, typeof(2)]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::String("9007199254740993".into()));
+                assert_eq!(arr[1], Value::Bool(true));
+                assert_eq!(arr[2], Value::Bool(false));
+                assert!(matches!(arr[3], Value::Number(n) if n == 2.5), "got {:?}", arr[3]);
+                assert!(matches!(arr[4], Value::Number(n) if n == 2.0), "got {:?}", arr[4]);
+                assert_eq!(arr[5], Value::String("int".into()));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_integer_number_mixed_arithmetic() {
+        // Integer с Integer остается Integer, смешивание с Number дает Number
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}")
+            let n = t["id"][2]
+            let amounts = t["amount"]
+            let result = [n * 3, n + 0.5, n / 2, n // 2, n % 4, n ** 2, n < 1.5, n == -7, sum([amounts[0], amounts[1], amounts[2]]), sum([n, n])]
+            result
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert!(matches!(arr[0], Value::Integer(-21)), "got {:?}", arr[0]);
+                assert!(matches!(arr[1], Value::Number(n) if n == -6.5), "got {:?}", arr[1]);
+                assert!(matches!(arr[2], Value::Number(n) if n == -3.5), "got {:?}", arr[2]);
+                assert!(matches!(arr[3], Value::Integer(-4)), "got {:?}", arr[3]);
+                assert!(matches!(arr[4], Value::Integer(-3)), "got {:?}", arr[4]);
+                assert!(matches!(arr[5], Value::Integer(49)), "got {:?}", arr[5]);
+                assert_eq!(arr[6], Value::Bool(true));
+                assert_eq!(arr[7], Value::Bool(true));
+                assert!(matches!(arr[8], Value::Number(n) if n == 6.5), "got {:?}", arr[8]);
+                assert!(matches!(arr[9], Value::Integer(-14)), "got {:?}", arr[9]);
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_integer_overflow_promotes_to_number() {
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(
+            r#"
+            let t = read_file("{}")
+            t["id"][0] * t["id"][1]
+            "#,
+            csv_path
+        );
+
+        match run_and_get_result(&source) {
+            Ok(Value::Number(n)) => assert!(n > 8.0e31, "got {}", n),
+            Ok(v) => panic!("Expected Number, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_sqlite_export_integer_column() {
+        // Integer колонка экспортируется как INTEGER без потери точности
+        let csv_path = get_test_data_path("big_ids.csv");
+        let source = format!(r#"global ids = read_file("{}")"#, csv_path);
+        let (_, vm) = data_code::run_with_vm(&source).expect("script failed");
+
+        let db_path = std::env::temp_dir().join(format!("datacode_integer_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db_path);
+        data_code::sqlite_export::export_to_sqlite(&vm, db_path.to_str().unwrap()).expect("export failed");
+
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let declared: String = conn
+            .query_row("SELECT type FROM pragma_table_info('ids') WHERE name = 'id'", [], |row| row.get(0))
+            .unwrap();
+        let first_id: i64 = conn
+            .query_row("SELECT id FROM ids WHERE id > 0 ORDER BY id LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        drop(conn);
+        let _ = std::fs::remove_file(&db_path);
+
+        assert_eq!(declared, "INTEGER");
+        assert_eq!(first_id, 9007199254740993);
+    }
+
     // ========== 4. Тесты операций с таблицами ==========
 
     #[test]
@@ -340,6 +866,74 @@ mod tests {
         assert_number_result(source, 2.0);
     }
 
+    // Таблица с n строками: id = 1..n
+    fn numbered_table(rows: usize) -> String {
+        format!(
+            "let my_table = table([{}], [\"id\", \"name\"])\n",
+            (1..=rows).map(|i| format!("[{}, \"r{}\"]", i, i)).collect::<Vec<_>>().join(", ")
+        )
+    }
+
+    #[test]
+    fn test_table_head_tail_edge_sizes() {
+        let source = numbered_table(5);
+        for (n, expected) in [(0, 0.0), (1, 1.0), (5, 5.0), (100, 5.0)] {
+            assert_number_result(&format!("{}len(table_head(my_table, {}))", source, n), expected);
+            assert_number_result(&format!("{}len(table_tail(my_table, {}))", source, n), expected);
+        }
+        assert_number_result(&format!("{}let t = table_head(my_table, 2)\nt[\"id\"][0] * 10 + t[\"id\"][1]", source), 12.0);
+        assert_number_result(&format!("{}let t = table_tail(my_table, 2)\nt[\"id\"][0] * 10 + t[\"id\"][1]", source), 45.0);
+        assert_string_result(&format!("{}table_tail(my_table, 1)[\"name\"][0]", source), "r5");
+
+        // По умолчанию 10 строк
+        let source = numbered_table(15);
+        assert_number_result(&format!("{}len(table_head(my_table))", source), 10.0);
+        assert_number_result(&format!("{}table_tail(my_table)[\"id\"][0]", source), 6.0);
+
+        // Пустая таблица
+        let source = numbered_table(0);
+        assert_number_result(&format!("{}len(table_head(my_table, 3)) + len(table_tail(my_table, 3))", source), 0.0);
+    }
+
+    #[test]
+    fn test_table_head_does_not_mutate_source() {
+        let source = format!("{}let head = table_head(my_table, 1)\nlen(my_table) + len(head)", numbered_table(3));
+        assert_number_result(&source, 4.0);
+    }
+
+    #[test]
+    fn test_table_head_tail_reject_negative_n() {
+        let source = numbered_table(3);
+        let message = format!("{}", run(&format!("{}table_head(my_table, -1)", source)).unwrap_err());
+        assert!(message.contains("non-negative integer"), "{}", message);
+        assert!(run(&format!("{}table_tail(my_table, -2)", source)).is_err());
+        assert!(run(&format!("{}table_head(my_table, \"3\")", source)).is_err());
+    }
+
+    #[test]
+    fn test_table_slice_pages() {
+        let source = numbered_table(5);
+        assert_number_result(&format!("{}let t = table_slice(my_table, 1, 2)\nt[\"id\"][0] * 10 + t[\"id\"][1]", source), 23.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, 4, 10))", source), 1.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, 5, 10))", source), 0.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, 50, 10))", source), 0.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, 0, 0))", source), 0.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, 2))", source), 3.0);
+        assert_number_result(&format!("{}len(table_slice(my_table, offset=0, limit=5))", source), 5.0);
+        assert_string_result(&format!("{}table_slice(my_table, 2, 1)[\"name\"][0]", source), "r3");
+        assert!(run(&format!("{}table_slice(my_table, -1, 2)", source)).is_err());
+        assert!(run(&format!("{}table_slice(my_table, 0, -2)", source)).is_err());
+    }
+
+    #[test]
+    fn test_show_table_row_limit() {
+        let source = numbered_table(30);
+        assert!(run(&format!("{}show_table(my_table, 3)", source)).is_ok());
+        assert!(run(&format!("{}show_table(my_table, 0)", source)).is_ok());
+        assert!(run(&format!("{}show_table(my_table, limit=100)", source)).is_ok());
+        assert!(run(&format!("{}show_table(my_table, -1)", source)).is_err());
+    }
+
     #[test]
     fn test_table_select() {
         // Выбор определенных колонок
@@ -1091,7 +1685,7 @@ mod tests {
         let result = run_and_get_result(source);
         match result {
             Ok(Value::String(s)) => {
-                assert!(s.contains("_o") || s == "id_o", "Expected column name with _o suffix, got {}", s);
+                assert!(s.contains("_o") || &*s == "id_o", "Expected column name with _o suffix, got {}", s);
             }
             Ok(v) => panic!("Expected String, got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
@@ -1262,8 +1856,8 @@ mod tests {
         // Проверяем, что данные сохраняются после применения suffixes
         let result = run_and_get_result(source);
         match result {
-            Ok(Value::Number(n)) => assert_eq!(n, 1.0, "Expected id_o[0] to be 1"),
-            Ok(v) => panic!("Expected Number(1), got {:?}", v),
+            Ok(Value::Integer(n)) => assert_eq!(n, 1, "Expected id_o[0] to be 1"),
+            Ok(v) => panic!("Expected Integer(1), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
         }
     }
@@ -1305,5 +1899,1003 @@ mod tests {
             Err(e) => panic!("Error: {:?}", e),
         }
     }
-}
 
+    #[test]
+    fn test_table_interpolate_linear_internal_gap() {
+        let source = r#"
+            let t = table([[1, 10], [2, null], [3, null], [4, 40], [5, null]], ["day", "value"])
+            let filled = table_interpolate(t, "value")
+            let col = filled["value"]
+            let result = [col[1], col[2], col[4]]
+            result
+        "#;
+
+        // Внутренний пропуск заполняется линейно, хвостовой null остается
+        match run_and_get_result(source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::Number(20.0));
+                assert_eq!(arr[1], Value::Number(30.0));
+                assert_eq!(arr[2], Value::Null);
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_table_interpolate_forward_and_backward() {
+        let source = r#"
+            let t = table([[null], [1], [null], [3], [null]], ["v"])
+            let f = table_interpolate(t, "v", method="forward")["v"]
+            let b = table_interpolate(t, "v", "backward")["v"]
+            let result = [f[0], f[2], f[4], b[0], b[2], b[4]]
+            result
+        "#;
+
+        match run_and_get_result(source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::Null);
+                assert_eq!(arr[1], Value::Number(1.0));
+                assert_eq!(arr[2], Value::Number(3.0));
+                assert_eq!(arr[3], Value::Number(1.0));
+                assert_eq!(arr[4], Value::Number(3.0));
+                assert_eq!(arr[5], Value::Null);
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_table_interpolate_errors_keep_the_table() {
+        // Ошибка не возвращает null молча: t = table_interpolate(t, "typo") не затирает t
+        for (call, expected) in [
+            (r#"table_interpolate(t, "typo")"#, "[ColumnNotFoundError]"),
+            (r#"table_interpolate(t, "v", "cubic")"#, "[ValueError]"),
+            (r#"table_interpolate([1, null], "v")"#, "[TypeError]"),
+            (r#"table_interpolate(t, "v", 5)"#, "[TypeError]"),
+        ] {
+            let source = format!("let t = table([[1], [null], [3]], [\"v\"])\n{}", call);
+            let message = format!("{}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+        let source = r#"
+            let t = table([[1], [null], [3]], ["v"])
+            try {
+                t = table_interpolate(t, "typo")
+            } catch ColumnNotFoundError e {
+            }
+            t["v"][2]
+        "#;
+        assert_eq!(run_and_get_result(source).unwrap(), Value::Integer(3));
+    }
+
+    // ========== Переименование и удаление колонок ==========
+
+    fn assert_columns(source: &str, expected: &[&str]) {
+        match run_and_get_result(source) {
+            Ok(Value::Array(arr)) => {
+                let names: Vec<String> = arr.borrow().iter().map(|v| v.to_string()).collect();
+                assert_eq!(names, expected);
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_table_rename_in_place() {
+        let source = r#"
+            let t = table([[1, "Alice", 30], [2, "Bob", 25]], ["id", "Full Name", "age"])
+            table_rename(t, [["Full Name", "name"], ["age", "years"]])
+            t.columns
+        "#;
+        assert_columns(source, &["id", "name", "years"]);
+        assert_string_result(r#"
+            let t = table([[1, "Alice"]], ["id", "n"])
+            table_rename(t, [["n", "name"]])
+            t["name"][0]
+        "#, "Alice");
+    }
+
+    #[test]
+    fn test_table_rename_errors() {
+        // Новое имя совпадает с существующей колонкой
+        let duplicate = run_and_get_result(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            table_rename(t, [["a", "b"]])
+        "#);
+        let message = format!("{:?}", duplicate.unwrap_err());
+        assert!(message.contains("Duplicate column name(s) after rename: b"), "{}", message);
+
+        // Обмен именами допустим
+        assert_columns(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            table_rename(t, [["a", "b"], ["b", "a"]])
+            t.columns
+        "#, &["b", "a"]);
+
+        let unknown = run_and_get_result(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            table_rename(t, [["x", "y"], ["a", "c"], ["z", "w"]])
+        "#);
+        let message = format!("{:?}", unknown.unwrap_err());
+        assert!(message.contains("table_rename(): unknown column(s): x, z"), "{}", message);
+
+        // Ошибка перехватывается по типу и таблица не меняется
+        assert_columns(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            try {
+                table_rename(t, [["a", "b"]])
+            } catch ValueError e {
+            }
+            try {
+                table_rename(t, {"missing": "c"})
+            } catch ColumnNotFoundError e {
+            }
+            t.columns
+        "#, &["a", "b"]);
+        for (call, expected) in [
+            (r#"table_rename(t, [["a", "b"]])"#, "[ValueError]"),
+            (r#"table_rename(t, {"x": "y"})"#, "[ColumnNotFoundError]"),
+            (r#"table_rename(t, 5)"#, "[TypeError]"),
+            (r#"table_rename([1], {"a": "c"})"#, "[TypeError]"),
+        ] {
+            let source = format!("let t = table([[1, 2]], [\"a\", \"b\"])\n{}", call);
+            let message = format!("{}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_reorder() {
+        assert_columns(r#"
+            let t = table([[1, "Alice", 30], [2, "Bob", 25]], ["id", "name", "age"])
+            let r = table_reorder(t, ["age", "id", "name"], all = true)
+            r.columns
+        "#, &["age", "id", "name"]);
+
+        // Подмножество колонок; значения строк следуют за колонками, исходная таблица не меняется
+        assert_string_result(r#"
+            let t = table([[1, "Alice", 30], [2, "Bob", 25]], ["id", "name", "age"])
+            let r = table_reorder(t, ["name", "id"])
+            str([r.columns, r["name"][1], r["id"][1], t.columns])
+        "#, "[[name, id], Bob, 2, [id, name, age]]");
+    }
+
+    #[test]
+    fn test_table_reorder_errors() {
+        for (call, expected) in [
+            (r#"table_reorder(t, ["id", "missing"])"#, "table_reorder(): unknown column: missing"),
+            (r#"table_reorder(t, ["id", "id"])"#, "column 'id' is listed twice"),
+            (r#"table_reorder(t, ["age", "id"], all = true)"#, "missing column(s) with all = true: name"),
+            (r#"table_reorder(t, "id")"#, "table_reorder() expects a table and an array of column names"),
+            (r#"table_reorder(t, ["id"], all = 1)"#, "all must be a boolean"),
+        ] {
+            let source = format!("let t = table([[1, \"Alice\", 30]], [\"id\", \"name\", \"age\"])\n{}", call);
+            let message = format!("{:?}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+
+        // Ошибки перехватываются как ValueError
+        assert_string_result(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            let kind = "none"
+            try {
+                table_reorder(t, ["c"])
+            } catch ValueError e {
+                kind = "value"
+            }
+            kind
+        "#, "value");
+    }
+
+    #[test]
+    fn test_table_add_column() {
+        assert_string_result(r#"
+            fn total(row) {
+                return row["price"] * row["qty"]
+            }
+            let t = table([["pen", 2, 3], ["ink", 5, 1]], ["item", "price", "qty"])
+            let r = table_add_column(t, "total", total)
+            str([r.columns, r["total"][0], r["total"][1], t.columns])
+        "#, "[[item, price, qty, total], 6, 5, [item, price, qty]]");
+
+        // Встроенная функция и именованные аргументы
+        assert_string_result(r#"
+            let t = table([[1], [2]], ["id"])
+            let r = table_add_column(t, fn = len, name = "fields")
+            str(r["fields"][1])
+        "#, "1");
+    }
+
+    #[test]
+    fn test_table_add_column_errors() {
+        // Ошибка в функции на второй строке пробрасывается, исходная таблица не меняется
+        assert_columns(r#"
+            fn check(row) {
+                if row["id"] > 1 {
+                    throw "bad row"
+                }
+                return 0
+            }
+            global t = table([[1], [2]], ["id"])
+            try {
+                global t = table_add_column(t, "flag", check)
+            } catch e {
+            }
+            t.columns
+        "#, &["id"]);
+
+        for (call, expected) in [
+            (r#"table_add_column(t, "id", str)"#, "table_add_column(): column 'id' already exists"),
+            (r#"table_add_column(t, 5, str)"#, "table_add_column() expects a table, a column name and a function"),
+            (r#"table_add_column(t, "x", 5)"#, "Can only call functions"),
+        ] {
+            let source = format!("let t = table([[1]], [\"id\"])\n{}", call);
+            let message = format!("{:?}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_drop_column() {
+        // Новая таблица; исходная сохраняет все колонки
+        assert_string_result(r#"
+            let t = table([[1, "a", true], [2, "b", false]], ["id", "name", "debug"])
+            let one = table_drop_column(t, "debug")
+            let two = table_drop_column(t, ["id", "debug"])
+            str([one.columns, two.columns, two.rows, t.columns])
+        "#, "[[id, name], [name], [[a], [b]], [id, name, debug]]");
+
+        for (call, expected) in [
+            (r#"table_drop_column(t, ["id", "missing"])"#, "table_drop_column(): unknown column(s): missing"),
+            (r#"table_drop_column(t, 5)"#, "table_drop_column() expects a table and a column name or an array of names"),
+            (r#"table_drop_column(t, [1])"#, "table_drop_column() expects a table and a column name or an array of names"),
+        ] {
+            let source = format!("let t = table([[1]], [\"id\"])\n{}", call);
+            let message = format!("{:?}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_drop_rows() {
+        assert_string_result(r#"
+            fn is_test(row) {
+                return row["status"] == "test"
+            }
+            let t = table([[1, "ok"], [2, "test"], [3, "ok"], [4, "test"]], ["id", "status"])
+            let r = table_drop_rows(t, is_test)
+            str([r.rows, len(t.rows)])
+        "#, "[[[1, ok], [3, ok]], 4]");
+
+        // Ошибка в предикате пробрасывается
+        let message = format!("{:?}", run_and_get_result(r#"
+            fn bad(row) {
+                throw "bad predicate"
+            }
+            table_drop_rows(table([[1]], ["id"]), bad)
+        "#).unwrap_err());
+        assert!(message.contains("bad predicate"), "{}", message);
+    }
+
+    #[test]
+    fn test_table_drop_duplicates() {
+        // По всем колонкам: остается первое вхождение, значения сравниваются структурно
+        assert_string_result(r#"
+            let t = table([
+                [1, "a", [1, 2]],
+                [1, "a", [1, 2]],
+                [1, "a", [2, 1]],
+                [2, "b", [1, 2]]
+            ], ["id", "name", "tags"])
+            str(table_drop_duplicates(t).rows)
+        "#, "[[1, a, [1, 2]], [1, a, [2, 1]], [2, b, [1, 2]]]");
+
+        // По ключевым колонкам; 1 и 1.0 равны, как в ==
+        assert_string_result(r#"
+            let t = table([["x@a", 1], ["y@a", 2], ["x@a", 3], ["z@a", 1.0]], ["email", "n"])
+            str([table_drop_duplicates(t, ["email"]).rows, len(table_drop_duplicates(t, "n").rows)])
+        "#, "[[[x@a, 1], [y@a, 2], [z@a, 1]], 3]");
+
+        let message = format!("{:?}", run_and_get_result(r#"table_drop_duplicates(table([[1]], ["id"]), ["nope"])"#).unwrap_err());
+        assert!(message.contains("table_drop_duplicates(): unknown column: nope"), "{}", message);
+    }
+
+    /// Строки таблицы из t.rows как значения Rust, чтобы различать Integer, Number и String
+    fn table_rows(source: &str) -> Vec<Vec<Value>> {
+        match run_and_get_result(source) {
+            Ok(Value::Array(rows)) => rows
+                .borrow()
+                .iter()
+                .map(|row| match row {
+                    Value::Array(cells) => cells.borrow().clone(),
+                    other => panic!("Expected row array, got {:?}", other),
+                })
+                .collect(),
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    fn s(text: &str) -> Value {
+        Value::String(text.into())
+    }
+
+    #[test]
+    fn test_table_cast_eu_on_error_modes() {
+        let csv_path = get_test_data_path("messy_numbers_eu.csv");
+        let cast = |on_error: &str| {
+            table_rows(&format!(
+                r#"
+                let t = read_file("{}", delimiter=";")
+                table_cast(t, {{"price": "number", "qty": "integer", "active": "bool"}}, on_error="{}", locale="eu")
+                t.rows
+                "#,
+                csv_path, on_error
+            ))
+        };
+
+        // Непреобразуемые ячейки становятся null
+        assert_eq!(cast("null"), vec![
+            vec![s("pen"), Value::Number(1024.5), Value::Integer(3), Value::Bool(true)],
+            vec![s("ink"), Value::Number(2500.0), Value::Integer(1), Value::Bool(false)],
+            vec![s("pad"), Value::Null, Value::Integer(2), Value::Bool(true)],
+            vec![s("cap"), Value::Number(7.25), Value::Null, Value::Bool(false)],
+        ]);
+        // ... или остаются как были
+        let kept = cast("keep");
+        assert_eq!(kept[2][1], s("n/a"));
+        assert_eq!(kept[3][2], s("x"));
+        assert_eq!(kept[0][1], Value::Number(1024.5));
+
+        // По умолчанию - DataFormatError, таблица не меняется
+        let source = format!(
+            r#"
+            let t = read_file("{}", delimiter=";")
+            let message = ""
+            try {{
+                table_cast(t, {{"qty": "integer", "price": "number"}}, locale="eu")
+            }} catch DataFormatError e {{
+                message = e
+            }}
+            [message, t.rows[0][1], t.rows[0][2]]
+            "#,
+            csv_path
+        );
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert!(arr[0].to_string().contains("cannot convert x in column 'qty' (row 3) to integer"), "{}", arr[0].to_string());
+                assert_eq!(arr[1], s("1 024,50"));
+                assert_eq!(arr[2], Value::Integer(3));
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_table_cast_us_locale() {
+        let csv_path = get_test_data_path("messy_numbers_us.csv");
+        let rows = table_rows(&format!(
+            r#"
+            let t = read_file("{}")
+            t = table_cast(t, {{"price": "number", "qty": "string"}}, on_error="null")
+            t.rows
+            "#,
+            csv_path
+        ));
+        let columns: Vec<(Value, Value)> = rows.into_iter().map(|row| (row[1].clone(), row[2].clone())).collect();
+        assert_eq!(columns, vec![
+            (Value::Number(1024.5), s("3")),
+            (Value::Number(2500.0), s("1")),
+            (Value::Null, s("2")),
+        ]);
+
+        // Разделители разрядов только между группами из трех цифр
+        let rows = table_rows(r#"
+            let t = table([["1,5"], ["1.5"], ["12,345,678"], ["-0.25"], ["3,00"]], ["v"])
+            table_cast(t, {"v": "number"}, on_error="keep", locale="us")
+            t.rows
+        "#);
+        let values: Vec<Value> = rows.into_iter().map(|row| row[0].clone()).collect();
+        assert_eq!(values, vec![s("1,5"), Value::Number(1.5), Value::Number(12345678.0), Value::Number(-0.25), s("3,00")]);
+        let rows = table_rows(r#"
+            let t = table([["1,5"], ["1.5"], ["3,00"]], ["v"])
+            table_cast(t, {"v": "integer"}, on_error="null", locale="eu")
+            t.rows
+        "#);
+        assert_eq!(rows, vec![vec![Value::Null], vec![Value::Null], vec![Value::Integer(3)]]);
+    }
+
+    #[test]
+    fn test_table_cast_argument_errors() {
+        for (call, expected) in [
+            (r#"table_cast(t, {"id": "date"})"#, "table_cast(): unknown type date for column 'id'"),
+            (r#"table_cast(t, {"missing": "number"})"#, "table_cast(): unknown column: missing"),
+            (r#"table_cast(t, {"id": "number"}, on_error="skip")"#, "on_error must be"),
+            (r#"table_cast(t, {"id": "number"}, locale="ru")"#, "locale must be"),
+            (r#"table_cast(t, ["id"])"#, "table_cast() expects a table and an object"),
+            (r#"table_autocast(5)"#, "table_autocast() expects a table"),
+        ] {
+            let source = format!("let t = table([[\"1\"]], [\"id\"])\n{}", call);
+            let message = format!("{:?}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_autocast() {
+        let rows = table_rows(r#"
+            let t = table([
+                ["1", "1,5", "yes", "a", "7", ""],
+                ["2", "2", "No", "b", "x", ""],
+                ["", "1.000,25", "1", "c", "8", ""]
+            ], ["int", "num", "flag", "text", "mixed", "blank"])
+            table_autocast(t, locale="eu").rows
+        "#);
+        assert_eq!(rows, vec![
+            vec![Value::Integer(1), Value::Number(1.5), Value::Bool(true), s("a"), s("7"), s("")],
+            vec![Value::Integer(2), Value::Number(2.0), Value::Bool(false), s("b"), s("x"), s("")],
+            vec![Value::Null, Value::Number(1000.25), Value::Bool(true), s("c"), s("8"), s("")],
+        ]);
+    }
+
+    #[test]
+    fn test_table_fillna() {
+        // Одно значение для всех колонок; исходная таблица не меняется
+        assert_eq!(table_rows(r#"
+            let t = table([[1, null, "a"], [null, 2, null]], ["x", "y", "z"])
+            let filled = table_fillna(t, 0)
+            [filled.rows[0], filled.rows[1], t.rows[1]]
+        "#), vec![
+            vec![Value::Integer(1), Value::Integer(0), s("a")],
+            vec![Value::Integer(0), Value::Integer(2), Value::Integer(0)],
+            vec![Value::Null, Value::Integer(2), Value::Null],
+        ]);
+
+        // Значения по колонкам; колонки без значения сохраняют null
+        assert_eq!(table_rows(r#"
+            let t = table([[1, null, "a"], [null, 2, null]], ["x", "y", "z"])
+            table_fillna(t, {"x": -1, "z": "n/a"}).rows
+        "#), vec![
+            vec![Value::Integer(1), Value::Null, s("a")],
+            vec![Value::Integer(-1), Value::Integer(2), s("n/a")],
+        ]);
+
+        let message = format!("{:?}", run_and_get_result(r#"table_fillna(table([[1]], ["x"]), {"y": 0})"#).unwrap_err());
+        assert!(message.contains("table_fillna(): unknown column: y"), "{}", message);
+    }
+
+    #[test]
+    fn test_table_dropna() {
+        let source = |call: &str| format!(r#"
+            let t = table([
+                [1, "a", 10],
+                [2, null, 20],
+                [null, null, null],
+                [4, null, null]
+            ], ["id", "name", "score"])
+            let r = {}
+            r.rows
+        "#, call);
+        let ids = |call: &str| -> Vec<Value> { table_rows(&source(call)).into_iter().map(|row| row[0].clone()).collect() };
+
+        assert_eq!(ids("table_dropna(t)"), vec![Value::Integer(1)]);
+        assert_eq!(ids(r#"table_dropna(t, "all")"#), vec![Value::Integer(1), Value::Integer(2), Value::Integer(4)]);
+        assert_eq!(ids(r#"table_dropna(t, subset = ["id", "score"])"#), vec![Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(ids(r#"table_dropna(t, how = "all", subset = ["name", "score"])"#), vec![Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(ids(r#"table_dropna(t, subset = "id")"#), vec![Value::Integer(1), Value::Integer(2), Value::Integer(4)]);
+
+        for (call, expected) in [
+            (r#"table_dropna(t, "some")"#, "table_dropna() how must be"),
+            (r#"table_dropna(t, subset = ["nope"])"#, "table_dropna(): unknown column: nope"),
+            (r#"table_dropna(t, subset = 5)"#, "table_dropna() subset must be"),
+        ] {
+            let message = format!("{:?}", run_and_get_result(&source(call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_fill_nulls_in_place() {
+        // Таблица меняется на месте; колонки без значения сохраняют null
+        assert_eq!(table_rows(r#"
+            let t = table([[1, null, null], [null, 2, "ok"]], ["temp", "hum", "status"])
+            let same = table_fill_nulls(t, {"temp": 0, "status": "unknown"})
+            [t.rows[0], t.rows[1], same.rows[0]]
+        "#), vec![
+            vec![Value::Integer(1), Value::Null, s("unknown")],
+            vec![Value::Integer(0), Value::Integer(2), s("ok")],
+            vec![Value::Integer(1), Value::Null, s("unknown")],
+        ]);
+
+        // Все неизвестные колонки перечисляются в одной ошибке
+        let message = format!("{:?}", run_and_get_result(r#"table_fill_nulls(table([[1]], ["x"]), {"a": 0, "x": 1, "b": 2})"#).unwrap_err());
+        assert!(message.contains("table_fill_nulls(): unknown column(s): a, b"), "{}", message);
+        let message = format!("{:?}", run_and_get_result(r#"table_fill_nulls(table([[1]], ["x"]), 0)"#).unwrap_err());
+        assert!(message.contains("table_fill_nulls() expects a table and an object"), "{}", message);
+    }
+
+    #[test]
+    fn test_table_fill_direction_partitioned() {
+        let source = |call: &str| format!(r#"
+            let t = table([
+                ["a", null, null],
+                ["a", 1, "on"],
+                ["b", null, null],
+                ["a", null, null],
+                ["b", 5, "off"],
+                ["b", null, null]
+            ], ["sensor_id", "temp", "status"])
+            {}
+            [t.rows[0], t.rows[1], t.rows[2], t.rows[3], t.rows[4], t.rows[5]]
+        "#, call);
+        let column = |call: &str, index: usize| -> Vec<Value> { table_rows(&source(call)).into_iter().map(|row| row[index].clone()).collect() };
+        let n = Value::Integer;
+
+        // Без partition_by значение переходит между датчиками; начальные null остаются
+        assert_eq!(column(r#"table_fill_direction(t, "temp")"#, 1), vec![Value::Null, n(1), n(1), n(1), n(5), n(5)]);
+        // С partition_by - только внутри своего датчика
+        assert_eq!(
+            column(r#"table_fill_direction(t, ["temp", "status"], partition_by = ["sensor_id"])"#, 1),
+            vec![Value::Null, n(1), Value::Null, n(1), n(5), n(5)]
+        );
+        assert_eq!(
+            column(r#"table_fill_direction(t, ["temp", "status"], partition_by = "sensor_id")"#, 2),
+            vec![Value::Null, s("on"), Value::Null, s("on"), s("off"), s("off")]
+        );
+        // backward берет следующее значение; конечные null группы остаются
+        assert_eq!(
+            column(r#"table_fill_direction(t, "temp", direction = "backward", partition_by = ["sensor_id"])"#, 1),
+            vec![n(1), n(1), n(5), Value::Null, n(5), Value::Null]
+        );
+        // Ключевая колонка не меняется
+        assert_eq!(
+            column(r#"table_fill_direction(t, "temp", partition_by = "sensor_id")"#, 0),
+            vec![s("a"), s("a"), s("b"), s("a"), s("b"), s("b")]
+        );
+    }
+
+    #[test]
+    fn test_table_fill_direction_all_null_column_and_errors() {
+        assert_eq!(table_rows(r#"
+            let t = table([[1, null], [null, null], [3, null]], ["x", "empty"])
+            table_fill_direction(t, ["x", "empty"])
+            table_fill_direction(t, "empty", direction = "backward")
+            [t.rows[0], t.rows[1], t.rows[2]]
+        "#), vec![
+            vec![Value::Integer(1), Value::Null],
+            vec![Value::Integer(1), Value::Null],
+            vec![Value::Integer(3), Value::Null],
+        ]);
+
+        let t = r#"let t = table([[1, 2]], ["x", "y"])"#;
+        for (call, expected) in [
+            (r#"table_fill_direction(t, ["x", "nope", "y"], partition_by = "other")"#, "table_fill_direction(): unknown column(s): nope, other"),
+            (r#"table_fill_direction(t, "x", direction = "sideways")"#, "table_fill_direction() direction must be \"forward\" or \"backward\""),
+            (r#"table_fill_direction(t, "x", partition_by = "x")"#, "table_fill_direction(): column 'x' is also in partition_by"),
+            (r#"table_fill_direction(t, 5)"#, "table_fill_direction() expects a table and a column name"),
+            (r#"table_fill_direction(t, "x", partition_by = 1)"#, "table_fill_direction() partition_by must be"),
+        ] {
+            let message = format!("{}", run_and_get_result(&format!("{}\n{}", t, call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_fill_then_aggregate_pipeline() {
+        // Цепочка: заполнение на месте возвращает ту же таблицу для следующего вызова
+        assert_eq!(table_rows(r#"
+            let t = table([
+                ["a", 10, null],
+                ["a", null, "x"],
+                ["b", null, null],
+                ["b", 4, null]
+            ], ["sensor_id", "temp", "status"])
+            let g = table_group_by(
+                table_fill_nulls(table_fill_direction(t, "temp", partition_by = "sensor_id"), {"temp": 0, "status": "unknown"}),
+                "sensor_id",
+                {"temp": "sum", "status": "count"}
+            )
+            [g.rows[0], g.rows[1], t.rows[2]]
+        "#), vec![
+            vec![s("a"), Value::Integer(20), Value::Number(2.0)],
+            vec![s("b"), Value::Integer(4), Value::Number(2.0)],
+            vec![s("b"), Value::Integer(0), s("unknown")],
+        ]);
+    }
+
+    #[test]
+    fn test_describe() {
+        let rows = table_rows(r#"
+            let t = table([
+                [1, "north", null, null],
+                [2, "south", 2.5, null],
+                [4, "north", 4.5, null],
+                [5, null, null, null]
+            ], ["id", "region", "score", "empty"])
+            let d = describe(table_cast(t, {"id": "integer"}))
+            [d.columns, d.rows[0], d.rows[1], d.rows[2], d.rows[3]]
+        "#);
+        // Первая "строка" - заголовки сводной таблицы
+        let headers: Vec<Value> = ["column", "type", "count", "null_count", "min", "max", "mean", "std", "unique", "top"]
+            .iter()
+            .map(|h| s(h))
+            .collect();
+        assert_eq!(rows[0], headers);
+
+        let id = &rows[1];
+        assert_eq!(id[..6], [s("id"), s("integer"), Value::Integer(4), Value::Integer(0), Value::Integer(1), Value::Integer(5)]);
+        assert_eq!(id[6], Value::Number(3.0));
+        // Выборочное std для 1, 2, 4, 5: sqrt(10 / 3)
+        match id[7] {
+            Value::Number(std) => assert!((std - (10.0f64 / 3.0).sqrt()).abs() < 1e-9, "{}", std),
+            ref other => panic!("Expected std number, got {:?}", other),
+        }
+        assert_eq!(id[8..], [Value::Null, Value::Null]);
+
+        assert_eq!(
+            rows[2],
+            vec![s("region"), s("string"), Value::Integer(3), Value::Integer(1), Value::Null, Value::Null, Value::Null, Value::Null, Value::Integer(2), s("north")]
+        );
+        assert_eq!(rows[3][..8], [s("score"), s("number"), Value::Integer(2), Value::Integer(2), Value::Number(2.5), Value::Number(4.5), Value::Number(3.5), Value::Number(2.0f64.sqrt())]);
+        // Колонка только из null: count 0, статистики null
+        assert_eq!(
+            rows[4],
+            vec![s("empty"), s("null"), Value::Integer(0), Value::Integer(4), Value::Null, Value::Null, Value::Null, Value::Null, Value::Null, Value::Null]
+        );
+
+        let message = format!("{:?}", run_and_get_result("describe([1, 2])").unwrap_err());
+        assert!(message.contains("describe() expects a table"), "{}", message);
+    }
+
+    #[test]
+    fn test_value_counts() {
+        let source = |call: &str| format!(r#"
+            let t = table([
+                ["north", 1], ["south", 2], [null, 3], ["north", 4],
+                ["east", 5], ["south", 6], ["north", 7], [null, 8]
+            ], ["region", "id"])
+            let r = {}
+            [r.columns, r.rows]
+        "#, call);
+        let result = |call: &str| -> (Vec<Value>, Vec<Vec<Value>>) {
+            let parts = table_rows(&source(call));
+            let rows = parts[1]
+                .iter()
+                .map(|row| match row {
+                    Value::Array(cells) => cells.borrow().clone(),
+                    other => panic!("Expected row array, got {:?}", other),
+                })
+                .collect();
+            (parts[0].clone(), rows)
+        };
+
+        // По убыванию частоты, равные - в порядке первого появления; null - отдельное значение
+        let (headers, rows) = result(r#"value_counts(t, "region")"#);
+        assert_eq!(headers, vec![s("region"), s("count")]);
+        assert_eq!(
+            rows,
+            vec![
+                vec![s("north"), Value::Integer(3)],
+                vec![s("south"), Value::Integer(2)],
+                vec![Value::Null, Value::Integer(2)],
+                vec![s("east"), Value::Integer(1)],
+            ]
+        );
+
+        let (headers, rows) = result(r#"value_counts(t, "region", normalize = true, dropna = true)"#);
+        assert_eq!(headers, vec![s("region"), s("proportion")]);
+        assert_eq!(
+            rows,
+            vec![
+                vec![s("north"), Value::Number(0.5)],
+                vec![s("south"), Value::Number(2.0 / 6.0)],
+                vec![s("east"), Value::Number(1.0 / 6.0)],
+            ]
+        );
+
+        for (call, expected) in [
+            (r#"value_counts(t, "nope")"#, "value_counts(): unknown column: nope"),
+            (r#"value_counts(t, "region", normalize = 1)"#, "value_counts() normalize must be a boolean"),
+            (r#"value_counts([1], "region")"#, "value_counts() expects a table and a column name"),
+        ] {
+            let message = format!("{:?}", run_and_get_result(&source(call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_window() {
+        let source = |call: &str| format!(r#"
+            let sales = table([
+                ["bob", 3, 30],
+                ["ann", 2, 20],
+                ["bob", 1, 10],
+                ["ann", 1, 5],
+                ["bob", 2, null],
+                ["ann", 2, 15]
+            ], ["customer", "day", "amount"])
+            let w = {}
+            w.rows
+        "#, call);
+        let rows = table_rows(&source(r#"table_window(sales, "customer", "day", {
+            "running_total": "sum:amount",
+            "prev_amount": "lag:amount:1",
+            "next_amount": "lead:amount",
+            "running_max": "max:amount",
+            "rn": "row_number",
+            "rk": "rank"
+        })"#));
+        let n = Value::Number;
+        let i = Value::Integer;
+        // Партиции в порядке первого появления, внутри - по day; равные day сохраняют исходный порядок
+        let expected = vec![
+            vec![s("bob"), n(1.0), n(10.0), n(10.0), Value::Null, Value::Null, n(10.0), i(1), i(1)],
+            vec![s("bob"), n(2.0), Value::Null, n(10.0), n(10.0), n(30.0), n(10.0), i(2), i(2)],
+            vec![s("bob"), n(3.0), n(30.0), n(40.0), Value::Null, Value::Null, n(30.0), i(3), i(3)],
+            vec![s("ann"), n(1.0), n(5.0), n(5.0), Value::Null, n(20.0), n(5.0), i(1), i(1)],
+            vec![s("ann"), n(2.0), n(20.0), n(25.0), n(5.0), n(15.0), n(20.0), i(2), i(2)],
+            vec![s("ann"), n(2.0), n(15.0), n(40.0), n(20.0), Value::Null, n(20.0), i(3), i(2)],
+        ];
+        assert_eq!(rows, expected);
+
+        match run_and_get_result(&source(r#"table_window(sales, null, ["day", "amount"], {"avg": "avg:amount"})"#).replace("w.rows", "w.columns")) {
+            Ok(Value::Array(headers)) => assert_eq!(*headers.borrow(), vec![s("customer"), s("day"), s("amount"), s("avg")]),
+            other => panic!("Expected headers array, got {:?}", other),
+        }
+        // Без партиций - одно окно; null в order_by идут первыми
+        let rows = table_rows(&source(r#"table_window(sales, null, ["day", "amount"], {"avg": "avg:amount"})"#));
+        let averages: Vec<Value> = rows.iter().map(|row| row[3].clone()).collect();
+        assert_eq!(averages, vec![n(5.0), n(7.5), n(7.5), n(10.0), n(12.5), n(16.0)]);
+
+        for (call, expected) in [
+            (r#"table_window(sales, "customer", "day", {"x": "sum:price"})"#, "table_window(): unknown column 'price' in spec \\\"sum:price\\\""),
+            (r#"table_window(sales, "customer", "day", {"x": "median:amount"})"#, "invalid spec \\\"median:amount\\\""),
+            (r#"table_window(sales, "customer", "day", {"amount": "row_number"})"#, "column 'amount' already exists"),
+            (r#"table_window(sales, "region", "day", {"x": "rank"})"#, "table_window(): unknown column: region"),
+            (r#"table_window(sales, "customer", "day", {"x": "sum:customer"})"#, "expects numbers"),
+        ] {
+            let message = format!("{:?}", run_and_get_result(&source(call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_where_regex() {
+        let source = |call: &str| format!(r#"
+            let t = table([
+                [1, "alice@example.com"],
+                [2, "bob at example"],
+                [3, null],
+                [4, 12345],
+                [5, "Carol@Example.org"]
+            ], ["id", "contact"])
+            let r = {}
+            r.rows
+        "#, call);
+        let ids = |call: &str| -> Vec<Value> { table_rows(&source(call)).into_iter().map(|row| row[0].clone()).collect() };
+        let n = Value::Number;
+
+        assert_eq!(ids(r#"table_where_regex(t, "contact", "^[a-z]+@")"#), vec![n(1.0)]);
+        assert_eq!(ids(r#"table_where_regex(t, "contact", "(?i)@example\\.(com|org)$")"#), vec![n(1.0), n(5.0)]);
+        // Числа сравниваются по строковому представлению, null не проходит
+        assert_eq!(ids(r#"table_where_regex(t, "contact", "^\\d+$")"#), vec![n(4.0)]);
+        assert_eq!(ids(r#"table_where_regex(t, "contact", "^\\d+$", skip_non_strings = true)"#), Vec::<Value>::new());
+        assert_eq!(ids(r#"table_where_regex(t, "contact", "")"#), vec![n(1.0), n(2.0), n(4.0), n(5.0)]);
+
+        for (call, expected) in [
+            (r#"table_where_regex(t, "contact", "([a-z")"#, "table_where_regex(): invalid pattern"),
+            (r#"table_where_regex(t, "email", "x")"#, "table_where_regex(): unknown column: email"),
+            (r#"table_where_regex(t, "contact", 5)"#, "expects a table, a column name and a pattern string"),
+        ] {
+            let message = format!("{:?}", run_and_get_result(&source(call)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+    }
+
+    #[test]
+    fn test_table_apply() {
+        let source = |body: &str| format!(r#"
+            let t = table([[1, "a"], [2, null]], ["x", "name"])
+            fn double(v) {{
+                if v == null {{
+                    return "-"
+                }}
+                return v + v
+            }}
+            fn locate(v, row, column) {{
+                return column + str(row)
+            }}
+            fn check(v) {{
+                if v > 1 {{
+                    throw "too big"
+                }}
+                return v
+            }}
+            {}
+        "#, body);
+        let s = |text: &str| Value::String(text.into());
+        let n = Value::Number;
+
+        assert_eq!(
+            table_rows(&source("table_apply(t, double).rows")),
+            vec![vec![n(2.0), s("aa")], vec![n(4.0), s("-")]]
+        );
+        assert_eq!(
+            table_rows(&source("table_apply(t, locate).rows")),
+            vec![vec![s("x0"), s("name0")], vec![s("x1"), s("name1")]]
+        );
+        assert_eq!(
+            table_rows(&source("let r = table_apply_column(t, \"x\", double)\n[t.rows[1], r.rows[1]]")),
+            vec![vec![n(2.0), Value::Null], vec![n(4.0), Value::Null]]
+        );
+        assert_eq!(table_rows(&source("table_apply_column(t, \"name\", str).rows"))[1], vec![n(2.0), s("null")]);
+
+        for (body, expected) in [
+            ("table_apply_column(t, \"x\", check)", "table_apply_column(): at row 1, column 'x': too big"),
+            ("table_apply_column(t, \"y\", double)", "table_apply_column(): unknown column: y"),
+            ("table_apply(t, 5)", "table_apply() expects a table and a function"),
+        ] {
+            let message = format!("{:?}", run_and_get_result(&source(body)).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", body, message);
+        }
+        // Тип ошибки сохраняется: ошибка колбэка перехватывается как обычно
+        let caught = run_and_get_result(&source("let r = \"ok\"\ntry {\n    table_apply(t, check)\n} catch e {\n    r = \"caught\"\n}\nr"));
+        assert_eq!(caught.unwrap(), s("caught"));
+    }
+
+    #[test]
+    fn test_for_over_table_uses_row_snapshot() {
+        // Строки перебираются как объекты; удаление колонки в теле цикла не влияет на итерацию
+        assert_string_result(r#"
+            let t = table([[1, "a"], [2, "b"], [3, "c"]], ["id", "name"])
+            let seen = []
+            for row in t {
+                push(seen, row["name"] + str(row["id"]))
+                table_drop(t, "na*")
+            }
+            str([seen, t.columns])
+        "#, "[[a1, b2, c3], [id]]");
+    }
+
+    #[test]
+    fn test_same_table_as_several_arguments() {
+        assert_number_result(r#"
+            let t = table([[1, "a"], [2, "b"]], ["id", "name"])
+            len(merge_tables([t, t]))
+        "#, 4.0);
+
+        // Функция table_rename_map() может обращаться к той же таблице
+        assert_columns(r#"
+            global t = table([[1, 2]], ["a", "b"])
+            fn touch(name) {
+                table_drop(t, "zz*")
+                return upper(name)
+            }
+            table_rename_map(t, touch)
+            t.columns
+        "#, &["A", "B"]);
+    }
+
+    #[test]
+    fn test_table_drop_columns_keeps_rows_aligned() {
+        let source = r#"
+            let t = table([[1, "x", 10, true], [2, "y", 20, false]], ["id", "tmp_a", "value", "tmp_b"])
+            let result = table_drop(t, ["tmp_a"])
+            let row = t.rows[1]
+            let out = [len(t.columns), row[0], row[1], row[2], len(result["dropped"]), result["warning"]]
+            out
+        "#;
+        match run_and_get_result(source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::Number(3.0));
+                assert_eq!(arr[1], Value::Number(2.0));
+                assert_eq!(arr[2], Value::Number(20.0));
+                assert_eq!(arr[3], Value::Bool(false));
+                assert_eq!(arr[4], Value::Number(1.0));
+                assert_eq!(arr[5], Value::Bool(false));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+
+        let unknown = run_and_get_result(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            table_drop(t, ["a", "missing"])
+        "#);
+        let message = format!("{:?}", unknown.unwrap_err());
+        assert!(message.contains("table_drop(): unknown column(s): missing"), "{}", message);
+
+        for (call, expected) in [
+            (r#"table_drop(t, ["a", "missing"])"#, "[ColumnNotFoundError]"),
+            (r#"table_drop(t, "tmp_[")"#, "[ValueError]"),
+            (r#"table_drop(t, [1])"#, "[TypeError]"),
+            (r#"table_drop(t, 5)"#, "[TypeError]"),
+        ] {
+            let source = format!("let t = table([[1, 2]], [\"a\", \"b\"])\n{}", call);
+            let message = format!("{}", run_and_get_result(&source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", call, message);
+        }
+        let source = r#"
+            let t = table([[1, 2]], ["a", "b"])
+            try {
+                table_drop(t, ["a", "missing"])
+            } catch ColumnNotFoundError e {
+            }
+            t.columns
+        "#;
+        assert_columns(source, &["a", "b"]);
+    }
+
+    #[test]
+    fn test_table_drop_pattern() {
+        assert_columns(r#"
+            let t = table([[1, 2, 3, 4]], ["id", "tmp_a", "value", "tmp_b"])
+            let dropped = table_drop(t, "tmp_*")["dropped"]
+            dropped
+        "#, &["tmp_a", "tmp_b"]);
+
+        // Шаблон без совпадений - ничего не удаляется, в результате выставлен warning
+        let source = r#"
+            let t = table([[1, 2]], ["a", "b"])
+            let result = table_drop(t, "tmp_*")
+            let out = [result["warning"], len(result["dropped"]), len(t.columns)]
+            out
+        "#;
+        match run_and_get_result(source) {
+            Ok(Value::Array(arr)) => {
+                let arr = arr.borrow();
+                assert_eq!(arr[0], Value::Bool(true));
+                assert_eq!(arr[1], Value::Number(0.0));
+                assert_eq!(arr[2], Value::Number(2.0));
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_table_rename_map() {
+        assert_columns(r#"
+            let t = table([[1, 2]], ["  ID ", "Name"])
+            table_rename_map(t, trim)
+            table_rename_map(t, lower)
+            t.columns
+        "#, &["id", "name"]);
+
+        assert_columns(r#"
+            fn snake(name) {
+                return "col_" + lower(name)
+            }
+            let t = table([[1, 2]], ["A", "B"])
+            table_rename_map(t, snake).columns
+        "#, &["col_a", "col_b"]);
+
+        // Функция, сводящая разные имена к одному, дает ошибку
+        let collision = run_and_get_result(r#"
+            fn same(name) {
+                return "x"
+            }
+            let t = table([[1, 2]], ["a", "b"])
+            table_rename_map(t, same)
+        "#);
+        assert!(collision.is_err());
+    }
+
+    #[test]
+    fn test_table_rename_and_drop_chained() {
+        let source = r#"
+            let t = table([[1, "Alice", "x"], [2, "Bob", "y"]], ["ID", "Name", "Tmp_Note"])
+            let cleaned = table_drop(table_rename_map(t, lower), "tmp_*")["table"]
+            table_rename(cleaned, [["name", "person"]])
+            cleaned["person"][1] + str(len(t.columns))
+        "#;
+        assert_string_result(source, "Bob2");
+    }
+}
@@ -0,0 +1,109 @@
+// Тесты для условного выражения cond ? a : b
+
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    fn assert_number_result(source: &str, expected: f64) {
+        match run(source) {
+            Ok(Value::Number(n)) => assert_eq!(n, expected, "Expected {}, got {}", expected, n),
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i),
+            Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            Ok(v) => panic!("Expected String({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_ternary_selects_branch() {
+        assert_string_result(r#"let x = 5
+            x > 3 ? "big" : "small""#, "big");
+        assert_string_result(r#"let x = 1
+            x > 3 ? "big" : "small""#, "small");
+    }
+
+    #[test]
+    fn test_ternary_nested_right_associative() {
+        let source = r#"
+            fn grade(score) {
+                return score >= 90 ? "A" : score >= 75 ? "B" : score >= 50 ? "C" : "F"
+            }
+            grade(95) + grade(80) + grade(60) + grade(10)
+        "#;
+        assert_string_result(source, "ABCF");
+    }
+
+    #[test]
+    fn test_ternary_nested_in_then_branch() {
+        assert_number_result("true ? false ? 1 : 2 : 3", 2.0);
+    }
+
+    #[test]
+    fn test_ternary_evaluates_only_selected_branch() {
+        let source = r#"
+            let d = 0
+            d != 0 ? 10 / d : -1
+        "#;
+        assert_number_result(source, -1.0);
+    }
+
+    #[test]
+    fn test_ternary_branch_function_call_not_invoked() {
+        let source = r#"
+            let calls = []
+            fn side(v) {
+                push(calls, v)
+                return v
+            }
+            let r = len(calls) == 0 ? side(1) : side(2)
+            r * 10 + len(calls)
+        "#;
+        assert_number_result(source, 11.0);
+    }
+
+    #[test]
+    fn test_ternary_precedence() {
+        // Ниже сравнений и логических операторов, выше присваивания
+        let source = r#"
+            let x = 4
+            let y = x > 1 and x < 10 ? x + 1 : x - 1
+            y
+        "#;
+        assert_number_result(source, 5.0);
+        assert_number_result("let z = 0\nz = 1 == 1 ? 7 : 8\nz", 7.0);
+    }
+
+    #[test]
+    fn test_ternary_inside_expression_string() {
+        let source = r#"
+            let n = 7
+            eval("n % 2 == 0 ? \"even\" : \"odd\"")
+        "#;
+        assert_string_result(source, "odd");
+    }
+
+    #[test]
+    fn test_ternary_in_loop_builds_values() {
+        let source = r#"
+            let result = []
+            for v in [3, -2, 0, 5] {
+                push(result, v < 0 ? 0 : v)
+            }
+            sum(result)
+        "#;
+        assert_number_result(source, 8.0);
+    }
+
+    #[test]
+    fn test_ternary_missing_colon_is_error() {
+        assert!(run("true ? 1").is_err());
+    }
+}
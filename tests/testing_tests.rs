@@ -0,0 +1,136 @@
+// Тесты для assert-функций и тестового раннера (datacode --test)
+
+#[cfg(test)]
+mod tests {
+    use data_code::testing::{run_tests, test_names};
+    use data_code::{run, Value};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn fixtures_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_data/dc_tests")
+    }
+
+    fn assert_string_result(source: &str, expected: &str) {
+        match run(source) {
+            Ok(Value::String(s)) => assert_eq!(&*s, expected),
+            Ok(v) => panic!("Expected String({}), got {:?}", expected, v),
+            Err(e) => panic!("Error: {}", e),
+        }
+    }
+
+    fn error_message(source: &str) -> String {
+        match run(source) {
+            Ok(v) => panic!("Expected error, got {:?}", v),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_assert_passes_and_fails() {
+        assert!(run("assert(1 < 2)\nassert([1], \"non-empty\")").is_ok());
+        let message = error_message(r#"assert(len([]) > 0, "list must not be empty")"#);
+        assert!(message.contains("AssertionError"), "{}", message);
+        assert!(message.contains("list must not be empty"), "{}", message);
+    }
+
+    #[test]
+    fn test_assert_eq_shows_both_values() {
+        assert!(run(r#"assert_eq([1, "a"], [1, "a"])"#).is_ok());
+        let message = error_message(r#"assert_eq("abc", "abd")"#);
+        assert!(message.contains("- left:  \"abc\""), "{}", message);
+        assert!(message.contains("+ right: \"abd\""), "{}", message);
+    }
+
+    #[test]
+    fn test_assertion_error_is_catchable_by_type() {
+        let source = r#"
+            let kind = ""
+            try {
+                assert_eq(1, 2)
+            } catch AssertionError e {
+                kind = "assertion"
+            }
+            try {
+                assert(false)
+            } catch RuntimeError e {
+                kind = kind + "+runtime"
+            }
+            kind
+        "#;
+        assert_string_result(source, "assertion+runtime");
+    }
+
+    #[test]
+    fn test_assert_throws_by_name_and_value() {
+        let source = r#"
+            fn fail(x) {
+                throw "bad value " + str(x)
+            }
+            let by_name = assert_throws("fail", [7])
+            let by_value = assert_throws(fail, [8])
+            by_name + "|" + by_value
+        "#;
+        assert_string_result(source, "bad value 7|bad value 8");
+
+        let message = error_message("fn ok() {\nreturn 1\n}\nassert_throws(ok)");
+        assert!(message.contains("expected an error"), "{}", message);
+        assert!(run(r#"assert_throws("missing_fn", [])"#).is_err());
+    }
+
+    #[test]
+    fn test_runner_discovers_and_isolates_tests() {
+        let summary = run_tests(&fixtures_dir().join("math_helpers_test.dc")).unwrap();
+        let names: Vec<&str> = summary.outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["test_clamp_bounds", "test_state_is_reset_between_tests", "test_safe_div_throws"]
+        );
+        assert!(summary.is_success(), "{:?}", summary.outcomes);
+    }
+
+    #[test]
+    fn test_runner_reports_failures() {
+        let summary = run_tests(&fixtures_dir().join("failing")).unwrap();
+        assert_eq!(summary.passed(), 1);
+        assert_eq!(summary.failed(), 2);
+        assert!(!summary.is_success());
+
+        let wrong = summary.outcomes.iter().find(|o| o.name == "test_wrong_value").unwrap();
+        let error = wrong.error.as_ref().unwrap();
+        assert!(error.contains("arrays differ") && error.contains("[1,3]"), "{}", error);
+        // Функции без префикса test_ не запускаются
+        assert!(summary.outcomes.iter().all(|o| o.name != "helper_not_a_test"));
+    }
+
+    #[test]
+    fn test_runner_directory_and_names() {
+        let summary = run_tests(&fixtures_dir()).unwrap();
+        assert_eq!(summary.outcomes.len(), 8);
+        assert_eq!(summary.failed(), 2);
+        assert!(run_tests(&fixtures_dir().join("missing")).is_err());
+
+        let (_, functions) = data_code::compile("fn test_a() {\n}\nfn test_b(x) {\n}\nfn helper() {\n}").unwrap();
+        assert_eq!(test_names(&functions), vec!["test_a".to_string()]);
+    }
+
+    #[test]
+    fn test_cli_exit_code() {
+        let binary = env!("CARGO_BIN_EXE_datacode");
+        let passing = Command::new(binary)
+            .arg("--test")
+            .arg(fixtures_dir().join("strings_test.dc"))
+            .output()
+            .unwrap();
+        assert!(passing.status.success());
+        let stdout = String::from_utf8_lossy(&passing.stdout);
+        assert!(stdout.contains("Tests: 2 passed, 0 failed, 2 total"), "{}", stdout);
+
+        let failing = Command::new(binary)
+            .arg("--test")
+            .arg(fixtures_dir().join("failing"))
+            .output()
+            .unwrap();
+        assert_eq!(failing.status.code(), Some(1));
+    }
+}
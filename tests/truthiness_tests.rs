@@ -0,0 +1,93 @@
+// Тесты истинности: одно правило Value::is_truthy для всех мест, где нужно логическое значение,
+// и словесные операторы not/and/or с приоритетом not > and > or
+#[cfg(test)]
+mod tests {
+    use data_code::{run, Value};
+
+    /// Выражения для каждого варианта Value и ожидаемая истинность
+    const CASES: &[(&str, bool)] = &[
+        ("null", false),
+        ("false", false),
+        ("true", true),
+        ("0", false),
+        ("0.5", true),
+        ("-1", true),
+        ("table_cast(table([[0]], [\"n\"]), {\"n\": \"integer\"}).rows[0][0]", false),
+        ("table_cast(table([[3]], [\"n\"]), {\"n\": \"integer\"}).rows[0][0]", true),
+        ("\"\"", false),
+        ("\"0\"", true),
+        ("\"false\"", true),
+        ("[]", false),
+        ("[0]", true),
+        ("{}", false),
+        ("{\"a\": null}", true),
+        ("table([], [\"a\"])", false),
+        ("table([[1]], [\"a\"])", true),
+        ("table([], [\"a\"])[\"a\"]", false),
+        ("table([[0]], [\"a\"])[\"a\"]", true),
+        ("path(\"\")", false),
+        // Существование файла не проверяется
+        ("path(\"no/such/file.csv\")", true),
+        ("parse_currency(\"$0\")", false),
+        ("parse_currency(\"$1.50\")", true),
+        ("len", true),
+        ("table_lazy(table([], [\"a\"]))", true),
+    ];
+
+    fn eval_bool(source: &str) -> bool {
+        match run(source) {
+            Ok(Value::Bool(b)) => b,
+            other => panic!("{}\n=> {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn test_truthiness_is_consistent_across_entry_points() {
+        for &(expr, expected) in CASES {
+            let forms = [
+                format!("bool({})", expr),
+                format!("let v = {}\nlet r = false\nif v {{\n    r = true\n}}\nr", expr),
+                format!("let v = {}\nlet r = false\nwhile v {{\n    r = true\n    v = false\n}}\nr", expr),
+                format!("let v = {}\nv ? true : false", expr),
+                format!("let v = {}\nnot not v", expr),
+                format!("let v = {}\nbool(v and true)", expr),
+                format!("let v = {}\nbool(v or false)", expr),
+                format!(
+                    "let v = {}\nfn always(row) {{\n    return v\n}}\nlen(table_drop_rows(table([[1]], [\"a\"]), always)) == 0",
+                    expr
+                ),
+                format!("let v = {}\nlet r = true\ntry {{\n    assert(v)\n}} catch e {{\n    r = false\n}}\nr", expr),
+            ];
+            for source in &forms {
+                assert_eq!(eval_bool(source), expected, "{}", source);
+            }
+            // to_bool() распознает строки вроде "false", для остальных значений - то же правило
+            if !expr.starts_with('"') {
+                assert_eq!(eval_bool(&format!("to_bool({})", expr)), expected, "to_bool({})", expr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_folding_matches_runtime() {
+        for &(expr, expected) in &[("0", false), ("\"\"", false), ("\"x\"", true), ("null", false), ("2", true)] {
+            assert_eq!(eval_bool(&format!("{} ? true : false", expr)), expected, "{}", expr);
+            assert_eq!(eval_bool(&format!("not {}", expr)), !expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_word_operator_precedence() {
+        // not > and > or
+        assert!(eval_bool("bool(true or false and false)"));
+        assert!(!eval_bool("bool((true or false) and false)"));
+        assert!(eval_bool("bool(not false and true)"));
+        assert!(!eval_bool("bool(not true or false)"));
+        assert!(eval_bool("not 1 == 2"));
+        // not и and/or внутри аргументов функций
+        assert_eq!(run("str(not false)").unwrap(), Value::String("true".into()));
+        assert_eq!(run("len([not 0, 1 and 0, 0 or 2])").unwrap(), Value::Number(3.0));
+        let kept = run("let t = table([[1], [0], [2]], ['n'])\nlen(table_filter_expr(t, \"not n or n > 1\"))").unwrap();
+        assert_eq!(kept, Value::Number(2.0));
+    }
+}
@@ -17,6 +17,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -1079,5 +1083,125 @@ mod tests {
         "#;
         assert_number_result(source, 1300.0);
     }
-}
 
+    // ========== Брошенные значения и повторный throw ==========
+
+    #[test]
+    fn test_object_literal() {
+        let source = r#"
+            let row = {"id": 7, name: "Alice", "tags": [1, 2]}
+            let empty = {}
+            row["id"] + len(row["tags"]) + len(row["name"])
+        "#;
+        assert_number_result(source, 14.0);
+    }
+
+    #[test]
+    fn test_throw_object_fields_available_in_catch() {
+        let source = r#"
+            let found = 0
+            for i in [1, 2, 3] {
+                try {
+                    if i == 2 {
+                        throw {"kind": "ValidationError", "row": i, "message": "bad value"}
+                    }
+                } catch e {
+                    found = e["row"] * 10 + len(e["message"])
+                }
+            }
+            found
+        "#;
+        assert_number_result(source, 29.0);
+    }
+
+    #[test]
+    fn test_catch_custom_kind_by_name() {
+        let source = r#"
+            let result = 0
+            try {
+                throw {"kind": "ValidationError", "message": "bad value"}
+            } catch ValueError e {
+                result = 1
+            } catch ValidationError e {
+                result = 2
+            }
+            result
+        "#;
+        assert_number_result(source, 2.0);
+    }
+
+    #[test]
+    fn test_throw_object_with_builtin_kind() {
+        let source = r#"
+            let result = 0
+            try {
+                throw {"kind": "ValueError", "message": "negative amount"}
+            } catch ValueError e {
+                result = 1
+            }
+            result
+        "#;
+        assert_number_result(source, 1.0);
+    }
+
+    #[test]
+    fn test_rethrow_preserves_line_and_kind() {
+        let source = r#"
+            try {
+                throw {"kind": "ValidationError", "row": 4, "message": "bad value"}
+            } catch e {
+                throw
+            }
+        "#;
+        match run_and_get_result(source) {
+            Err(error) => {
+                assert!(matches!(error, LangError::RuntimeError { line: 3, .. }), "{:?}", error);
+                assert_eq!(error.kind_name(), "ValidationError");
+                assert!(matches!(error.thrown_value(), Some(Value::Object(map)) if map["row"] == Value::Number(4.0)));
+            }
+            Ok(v) => panic!("Expected error, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_rethrow_reaches_outer_handler() {
+        let source = r#"
+            let result = 0
+            try {
+                try {
+                    let x = 10 / 0
+                } catch e {
+                    result = 1
+                    throw
+                }
+            } catch e {
+                result = result + 10
+            }
+            result
+        "#;
+        assert_number_result(source, 11.0);
+    }
+
+    #[test]
+    fn test_rethrow_outside_catch_is_error() {
+        assert_error("throw");
+        assert_error(r#"
+            fn check() {
+                throw
+            }
+            try {
+                check()
+            } catch e {
+            }
+        "#);
+    }
+
+    #[test]
+    fn test_uncaught_object_shows_fields() {
+        let source = r#"throw {"kind": "ValidationError", "row": 12, "message": "bad value"}"#;
+        let message = run_and_get_result(source).unwrap_err().to_string();
+        assert!(message.contains("ValidationError"), "{}", message);
+        assert!(message.contains("bad value"), "{}", message);
+        assert!(message.contains("row: 12"), "{}", message);
+    }
+}
@@ -24,6 +24,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -46,7 +50,7 @@ mod tests {
         let result = run_and_get_result(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, expected, "Expected '{}', got '{}'", expected, s);
+                assert_eq!(&*s, expected, "Expected '{}', got '{}'", expected, s);
             }
             Ok(v) => panic!("Expected String('{}'), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
@@ -434,14 +438,11 @@ mod tests {
         "#;
         let result = run_and_get_result(source);
         match result {
-            Ok(Value::Number(n)) => {
-                // Проверяем, что число хранится правильно
-                assert_eq!(n, 42.0);
+            Ok(value @ Value::Integer(42)) => {
                 // Проверяем, что to_string() работает корректно
-                let s = n.to_string();
-                assert_eq!(s, "42");
+                assert_eq!(value.to_string(), "42");
             }
-            _ => panic!("Expected Number"),
+            _ => panic!("Expected Integer(42)"),
         }
     }
 
@@ -679,9 +680,9 @@ mod tests {
             Ok(Value::Array(arr)) => {
                 let arr_ref = arr.borrow();
                 assert_eq!(arr_ref.len(), 3);
-                assert_eq!(arr_ref[0], Value::String("a".to_string()));
-                assert_eq!(arr_ref[1], Value::String("b".to_string()));
-                assert_eq!(arr_ref[2], Value::String("c".to_string()));
+                assert_eq!(arr_ref[0], Value::String("a".into()));
+                assert_eq!(arr_ref[1], Value::String("b".into()));
+                assert_eq!(arr_ref[2], Value::String("c".into()));
             }
             _ => panic!("Expected Array"),
         }
@@ -699,7 +700,7 @@ mod tests {
                 let arr_ref = arr.borrow();
                 assert_eq!(arr_ref.len(), 4);
                 assert_eq!(arr_ref[0], Value::Number(1.0));
-                assert_eq!(arr_ref[1], Value::String("hello".to_string()));
+                assert_eq!(arr_ref[1], Value::String("hello".into()));
                 assert_eq!(arr_ref[2], Value::Bool(true));
                 assert_eq!(arr_ref[3], Value::Null);
             }
@@ -773,7 +774,7 @@ mod tests {
                 let arr_ref = arr.borrow();
                 assert_eq!(arr_ref.len(), 3);
                 assert_eq!(arr_ref[0], Value::Number(42.0));
-                assert_eq!(arr_ref[1], Value::String("hello".to_string()));
+                assert_eq!(arr_ref[1], Value::String("hello".into()));
                 assert_eq!(arr_ref[2], Value::Bool(true));
             }
             _ => panic!("Expected Array"),
@@ -1001,7 +1002,7 @@ mod tests {
                 assert_eq!(arr_ref.len(), 3);
                 assert_eq!(arr_ref[0], Value::Number(100.5));
                 assert_eq!(arr_ref[1], Value::Number(11.0));
-                assert_eq!(arr_ref[2], Value::String("Hello".to_string()));
+                assert_eq!(arr_ref[2], Value::String("Hello".into()));
             }
             _ => panic!("Expected Array"),
         }
@@ -1120,6 +1121,150 @@ mod tests {
         assert_bool_result("isinstance(true, int)", false);
     }
 
+    #[test]
+    fn test_typeof_every_value_variant() {
+        let big_ids = format!("{}/tests/test_data/big_ids.csv", env!("CARGO_MANIFEST_DIR"));
+        let source = format!(r#"
+            fn f() {{
+                return 1
+            }}
+            let t = table([[1, 2]], ["a", "b"])
+            let ids = read_file("{}")["id"]
+            let types = [typeof(1), typeof(1.5), typeof(ids[0]), typeof("s"), typeof(true),
+                typeof([1]), typeof(table_drop(t, "x*")), typeof(t), typeof(null),
+                typeof(path("data")), typeof("$10"), typeof(f), typeof(len)]
+            types
+        "#, big_ids);
+        match run_and_get_result(&source) {
+            Ok(Value::Array(arr)) => {
+                let names: Vec<String> = arr.borrow().iter().map(|v| v.to_string()).collect();
+                assert_eq!(names, vec![
+                    "int", "float", "int", "string", "bool", "array", "object", "table", "null",
+                    "path", "money", "function", "function",
+                ]);
+            }
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_isinstance_accepts_array_of_types() {
+        assert_bool_result(r#"isinstance(42, ["string", "number"])"#, true);
+        assert_bool_result(r#"isinstance("x", [int, str])"#, true);
+        assert_bool_result(r#"isinstance(null, ["string", "array"])"#, false);
+        assert_bool_result("isinstance(true, [])", false);
+        assert_bool_result(r#"isinstance("$5", "currency")"#, true);
+    }
+
+    #[test]
+    fn test_to_number_lenient_parsing() {
+        assert_number_result(r#"to_number("1,234.5")"#, 1234.5);
+        assert_number_result(r#"to_number(" $1 000 ")"#, 1000.0);
+        assert_number_result(r#"to_number("12 EUR")"#, 12.0);
+        assert_number_result(r#"to_number("-3e2")"#, -300.0);
+        assert_number_result("to_number(true)", 1.0);
+        assert_number_result("to_number(7)", 7.0);
+    }
+
+    #[test]
+    fn test_to_number_strict_and_lossy() {
+        // По умолчанию ошибка, при strict=false - null
+        assert_type_error(r#"to_number("abc")"#);
+        assert_type_error("to_number(null)");
+        assert_null_result(r#"to_number("abc", strict=false)"#);
+        assert_null_result("to_number([1], false)");
+        assert_number_result(r#"to_number("42", strict=false)"#, 42.0);
+    }
+
+    #[test]
+    fn test_to_number_error_types() {
+        // Нечисловая строка - ValueError, значение другого типа - TypeError
+        for (source, expected) in [
+            (r#"to_number("abc")"#, "[ValueError]"),
+            ("to_number(null)", "[TypeError]"),
+            ("to_number([1])", "[TypeError]"),
+        ] {
+            let message = format!("{}", run_and_get_result(source).unwrap_err());
+            assert!(message.contains(expected), "{} => {}", source, message);
+        }
+        assert_string_result(r#"
+            let r = "none"
+            try {
+                to_number("abc")
+            } catch ValueError e {
+                r = "caught"
+            }
+            r
+        "#, "caught");
+    }
+
+    #[test]
+    fn test_to_string_and_to_bool() {
+        assert_string_result("to_string(42)", "42");
+        assert_string_result("to_string(true)", "true");
+        assert_string_result(r#"to_string("s")"#, "s");
+        assert_bool_result(r#"to_bool("yes")"#, true);
+        assert_bool_result(r#"to_bool("False")"#, false);
+        assert_bool_result(r#"to_bool("0")"#, false);
+        assert_bool_result(r#"to_bool("anything")"#, true);
+        assert_bool_result("to_bool(0)", false);
+        assert_bool_result("to_bool([1])", true);
+        assert_bool_result("to_bool(null)", false);
+    }
+
+    #[test]
+    fn test_to_array_conversions() {
+        assert_string_result(r#"join(to_array("abc"), "-")"#, "a-b-c");
+        assert_number_result("len(to_array(null))", 0.0);
+        assert_number_result("to_array(5)[0]", 5.0);
+        // Объект - массив пар [ключ, значение] в порядке добавления ключей
+        assert_string_result(r#"
+            let t = table([[1, 2]], ["a", "b"])
+            let entries = to_array(table_drop(t, "x*"))
+            entries[0][0] + entries[1][0] + entries[2][0]
+        "#, "warningdroppedtable");
+        // Таблица - массив объектов-строк
+        assert_number_result(r#"
+            let t = table([[1, 10], [2, 20]], ["id", "value"])
+            let rows = to_array(t)
+            rows[1]["value"] + len(rows)
+        "#, 22.0);
+    }
+
+    #[test]
+    fn test_object_keys_keep_insertion_order() {
+        // Строка таблицы - объект с ключами в порядке колонок, а не по алфавиту
+        assert_string_result(r#"
+            let t = table([[1, "x", true]], ["zeta", "alpha", "mid"])
+            str(to_array(t)[0])
+        "#, r#"{"zeta": 1, "alpha": x, "mid": true}"#);
+        assert_string_result(r#"
+            let t = table([[1, 2, 3]], ["zeta", "alpha", "mid"])
+            let pairs = to_array(to_array(t)[0])
+            pairs[0][0] + pairs[1][0] + pairs[2][0]
+        "#, "zetaalphamid");
+    }
+
+    #[test]
+    fn test_object_json_preserves_field_order() {
+        use data_code::common::value::ObjectMap;
+
+        let mut object = ObjectMap::new();
+        object.insert("zeta".to_string(), Value::Number(1.0));
+        object.insert("alpha".to_string(), Value::Null);
+        object.insert("mid".to_string(), Value::Bool(true));
+        let value = Value::Object(object);
+        assert_eq!(value.to_json().to_string(), r#"{"zeta":1,"alpha":null,"mid":true}"#);
+
+        // Порядок ключей не влияет на равенство объектов
+        let mut reversed = ObjectMap::new();
+        reversed.insert("mid".to_string(), Value::Bool(true));
+        reversed.insert("alpha".to_string(), Value::Null);
+        reversed.insert("zeta".to_string(), Value::Number(1.0));
+        assert_eq!(value, Value::Object(reversed));
+    }
+
     // ========== Тесты математических функций ==========
 
     #[test]
@@ -1215,9 +1360,9 @@ mod tests {
             Ok(Value::Array(arr)) => {
                 let arr_ref = arr.borrow();
                 assert_eq!(arr_ref.len(), 3);
-                assert_eq!(arr_ref[0], Value::String("a".to_string()));
-                assert_eq!(arr_ref[1], Value::String("b".to_string()));
-                assert_eq!(arr_ref[2], Value::String("c".to_string()));
+                assert_eq!(arr_ref[0], Value::String("a".into()));
+                assert_eq!(arr_ref[1], Value::String("b".into()));
+                assert_eq!(arr_ref[2], Value::String("c".into()));
             }
             _ => panic!("Expected array, got {:?}", result),
         }
@@ -1228,8 +1373,8 @@ mod tests {
             Ok(Value::Array(arr)) => {
                 let arr_ref = arr.borrow();
                 assert_eq!(arr_ref.len(), 2);
-                assert_eq!(arr_ref[0], Value::String("hello".to_string()));
-                assert_eq!(arr_ref[1], Value::String("world".to_string()));
+                assert_eq!(arr_ref[0], Value::String("hello".into()));
+                assert_eq!(arr_ref[1], Value::String("world".into()));
             }
             _ => panic!("Expected array, got {:?}", result),
         }
@@ -10,6 +10,10 @@ mod tests {
             Ok(Value::Number(n)) => {
                 assert_eq!(n, expected, "Expected {}, got {}", expected, n);
             }
+            // Целые литералы и целочисленная арифметика дают Integer
+            Ok(Value::Integer(i)) => {
+                assert_eq!(i as f64, expected, "Expected {}, got {}", expected, i);
+            }
             Ok(v) => panic!("Expected Number({}), got {:?}", expected, v),
             Err(e) => panic!("Error: {:?}", e),
         }
@@ -265,7 +269,7 @@ mod tests {
         let result = run(source);
         match result {
             Ok(Value::String(s)) => {
-                assert_eq!(s, "hello world", "Expected 'hello world', got '{}'", s);
+                assert_eq!(&*s, "hello world", "Expected 'hello world', got '{}'", s);
             }
             Ok(v) => panic!("Expected String('hello world'), got {:?}", v),
             Err(e) => panic!("Error: {:?}", e),
@@ -388,6 +392,32 @@ mod tests {
         assert_number_result(source, 1.0);
     }
 
+    #[test]
+    fn test_assignment_in_function_does_not_overwrite_functions() {
+        // Без let имя встроенной или глобальной функции становится локальной переменной
+        let source = r#"
+            fn total(xs) {
+                sum = 0
+                for x in xs {
+                    sum += x
+                }
+                return sum
+            }
+            fn report() {
+                total = 100
+                max += 1
+                return total + max
+            }
+            [total([1, 2, 3]), report(), sum([4, 5]), total([10]), max(7, 8)]
+        "#;
+        let expected: Vec<Value> = [6.0, 101.0, 9.0, 10.0, 8.0].iter().map(|n| Value::Number(*n)).collect();
+        match run(source) {
+            Ok(Value::Array(arr)) => assert_eq!(*arr.borrow(), expected),
+            Ok(v) => panic!("Expected Array, got {:?}", v),
+            Err(e) => panic!("Error: {:?}", e),
+        }
+    }
+
     #[test]
     fn test_global_in_while_loop() {
         let source = r#"
@@ -499,19 +529,19 @@ mod tests {
     #[test]
     fn test_assignment_creates_local() {
         let source = r#"
-            global x = 100
             fn test() {
-                x = 50
-                return x
+                y = 50
+                return y
             }
             test()
         "#;
-        // x = 50 создает локальную переменную в функции
+        // y = 50 создает локальную переменную в функции (глобальной y нет)
         assert_number_result(source, 50.0);
+        assert!(run("fn test() {\ny = 50\n}\ntest()\ny").is_err());
     }
 
     #[test]
-    fn test_assignment_does_not_modify_global() {
+    fn test_assignment_updates_existing_global() {
         let source = r#"
             global x = 100
             fn test() {
@@ -520,8 +550,59 @@ mod tests {
             test()
             x
         "#;
-        // Глобальная переменная не изменяется
-        assert_number_result(source, 100.0);
+        // Имя существует только глобально - присваивание изменяет глобальную переменную
+        assert_number_result(source, 50.0);
+    }
+
+    #[test]
+    fn test_function_increments_global_in_loop() {
+        let source = r#"
+            global counter = 0
+            global total = 0
+            fn tick(n) {
+                global counter = counter + 1
+                total += n
+            }
+            for i in range(1, 6) {
+                tick(i)
+            }
+            counter * 100 + total
+        "#;
+        assert_number_result(source, 515.0);
+    }
+
+    #[test]
+    fn test_local_shadow_keeps_global_unchanged() {
+        let source = r#"
+            global x = 100
+            fn test() {
+                let x = 1
+                x = x + 1
+                x += 10
+                return x
+            }
+            test() * 1000 + x
+        "#;
+        assert_number_result(source, 12100.0);
+    }
+
+    #[test]
+    fn test_nested_call_global_write_visible_to_caller() {
+        let source = r#"
+            global hits = 0
+            fn record() {
+                hits = hits + 1
+            }
+            fn run_twice() {
+                record()
+                let seen = hits
+                record()
+                return seen * 10 + hits
+            }
+            run_twice() * 10 + hits
+        "#;
+        // run_twice видит запись record() сразу после вызова: 1 * 10 + 2 = 12
+        assert_number_result(source, 122.0);
     }
 
     #[test]
@@ -559,5 +640,83 @@ mod tests {
         "#;
         assert_number_result(source, 3.0);
     }
-}
 
+    // ========== Тесты короткого вычисления and/or ==========
+
+    #[test]
+    fn test_and_short_circuit_skips_right() {
+        let source = r#"
+            let calls = []
+            fn f() {
+                push(calls, 1)
+                return true
+            }
+            let r = false and f()
+            len(calls)
+        "#;
+        assert_number_result(source, 0.0);
+    }
+
+    #[test]
+    fn test_or_short_circuit_skips_right() {
+        let source = r#"
+            let calls = []
+            fn g() {
+                push(calls, 1)
+                return false
+            }
+            let r = true or g()
+            len(calls)
+        "#;
+        assert_number_result(source, 0.0);
+    }
+
+    #[test]
+    fn test_and_or_evaluate_right_when_needed() {
+        let source = r#"
+            let calls = []
+            fn f() {
+                push(calls, 1)
+                return true
+            }
+            let a = true and f()
+            let b = false or f()
+            len(calls)
+        "#;
+        assert_number_result(source, 2.0);
+    }
+
+    #[test]
+    fn test_short_circuit_guards_error() {
+        let source = r#"
+            let arr = []
+            len(arr) > 0 and arr[0] > 1
+        "#;
+        assert_bool_result(source, false);
+    }
+
+    #[test]
+    fn test_and_or_return_operand_values() {
+        assert_number_result("0 or 5", 5.0);
+        assert_number_result("3 and 7", 7.0);
+        assert_number_result("0 and 7", 0.0);
+        assert_bool_result("false or false", false);
+    }
+
+    #[test]
+    fn test_short_circuit_chain_in_condition() {
+        let source = r#"
+            let calls = []
+            fn side(v) {
+                push(calls, v)
+                return v
+            }
+            if side(false) and side(true) or side(true) {
+                push(calls, 100)
+            }
+            len(calls)
+        "#;
+        // side(false), затем правая часть or, затем тело if
+        assert_number_result(source, 3.0);
+    }
+}
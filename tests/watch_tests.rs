@@ -0,0 +1,129 @@
+// Тесты cached_eval() и инвалидации кэша по времени изменения прочитанных файлов (--watch)
+#[cfg(test)]
+mod tests {
+    use data_code::watch::{self, FileStamps};
+    use data_code::{run, Session};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    /// Временный файл с заданным содержимым; путь с '/' для подстановки в строку DataCode
+    fn temp_file(name: &str, content: &str) -> (PathBuf, String) {
+        let path = std::env::temp_dir().join(format!("datacode_watch_{}_{}.txt", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let source_path = path.to_string_lossy().replace('\\', "/");
+        (path, source_path)
+    }
+
+    /// Перезапись файла с явным временем изменения: тест не зависит от точности часов ФС
+    fn rewrite(path: &PathBuf, content: &str, seconds_ago: u64) {
+        fs::write(path, content).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(seconds_ago)).unwrap();
+    }
+
+    /// Запуск скрипта новой сессией, как при каждом перезапуске --watch; вывод print()
+    fn rerun(source: &str) -> String {
+        let mut session = Session::new().capture_output(true);
+        let outcome = session.exec(source);
+        if let Some(error) = outcome.error {
+            panic!("{}\n=> {:?}", source, error);
+        }
+        outcome.output
+    }
+
+    fn loader(source_path: &str, key: &str) -> String {
+        format!(
+            "fn load() {{\n    print(\"loading\")\n    return read_lines(\"{}\")\n}}\nlet lines = cached_eval(\"{}\", load)\nprint(join(lines, \"+\"))",
+            source_path, key
+        )
+    }
+
+    #[test]
+    fn test_unchanged_file_hits_cache() {
+        let (path, source_path) = temp_file("hit", "a\nb\n");
+        rewrite(&path, "a\nb\n", 60);
+        let source = loader(&source_path, "hit");
+        assert_eq!(rerun(&source), "loading\na+b\n");
+        // Второй запуск не вызывает load(), но выполняет остальной код
+        assert_eq!(rerun(&source), "a+b\n");
+        assert_eq!(rerun(&source), "a+b\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_mtime_change_busts_cache() {
+        let (path, source_path) = temp_file("bust", "a\n");
+        rewrite(&path, "a\n", 60);
+        let source = loader(&source_path, "bust");
+        assert_eq!(rerun(&source), "loading\na\n");
+        rewrite(&path, "a\nc\n", 30);
+        assert_eq!(rerun(&source), "loading\na+c\n");
+        assert_eq!(rerun(&source), "a+c\n");
+        // Удаленный файл тоже считается изменением: ошибка чтения не кэшируется
+        fs::remove_file(&path).unwrap();
+        let mut session = Session::new().capture_output(true);
+        assert!(session.exec(&source).error.is_some());
+        rewrite(&path, "d\n", 10);
+        assert_eq!(rerun(&source), "loading\nd\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_stamps() {
+        let (path, _) = temp_file("stamps", "x");
+        rewrite(&path, "x", 60);
+        let stamps = FileStamps::capture(&[path.clone(), path.clone()]);
+        assert_eq!(stamps.paths().count(), 1);
+        assert!(stamps.unchanged());
+        rewrite(&path, "x", 20);
+        assert!(!stamps.unchanged());
+        fs::remove_file(&path).unwrap();
+        assert!(FileStamps::capture(std::slice::from_ref(&path)).unchanged());
+    }
+
+    #[test]
+    fn test_nested_cached_eval_tracks_inner_files() {
+        let (path, source_path) = temp_file("nested", "1\n");
+        rewrite(&path, "1\n", 60);
+        let source = format!(
+            "fn inner() {{\n    return read_lines(\"{}\")\n}}\nfn outer() {{\n    print(\"outer\")\n    return len(cached_eval(\"inner\", inner))\n}}\nprint(cached_eval(\"outer\", outer))",
+            source_path
+        );
+        assert_eq!(rerun(&source), "outer\n1\n");
+        assert_eq!(rerun(&source), "1\n");
+        // Изменение файла вложенного вычисления сбрасывает и внешнее
+        rewrite(&path, "1\n2\n", 30);
+        assert_eq!(rerun(&source), "outer\n2\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cached_result_is_a_copy() {
+        watch::cache_clear();
+        let source = "fn make() {\n    return [1, 2]\n}\nlet xs = cached_eval(\"copy\", make)\npush(xs, 3)\nlen(xs)";
+        assert_eq!(run(source).unwrap().to_string(), "3");
+        assert_eq!(run(source).unwrap().to_string(), "3");
+        assert_eq!(watch::cache_len(), 1);
+        assert_eq!(watch::cache_clear(), 1);
+    }
+
+    #[test]
+    fn test_cached_eval_errors() {
+        for (source, expected) in [
+            ("cached_eval(1, len)", "cached_eval() expects a string key and a function without arguments"),
+            ("cached_eval(\"k\", 5)", "cached_eval() expects a string key and a function without arguments"),
+            (
+                "fn f() {\n    return [len]\n}\ncached_eval(\"fn\", f)",
+                "cached_eval(): result of 'fn' contains a function, lazy table or column reference and cannot be cached",
+            ),
+        ] {
+            let message = format!("{}", run(source).unwrap_err());
+            assert!(message.contains(expected), "{}\n=> {}", source, message);
+        }
+        // Ошибка внутри функции не кэшируется и перехватывается try/catch
+        let source = "fn fail() {\n    return 1 / 0\n}\nlet r = \"none\"\ntry {\n    cached_eval(\"fail\", fail)\n} catch e {\n    r = \"caught\"\n}\nr";
+        assert_eq!(run(source).unwrap().to_string(), "caught");
+        assert_eq!(run(source).unwrap().to_string(), "caught");
+    }
+}
@@ -0,0 +1,435 @@
+// Тесты для WebSocket запросов просмотра состояния сессии (inspect, preview_table), SMB запросов,
+// аутентификации по токену и профилей разрешений подключений
+
+#[cfg(test)]
+mod tests {
+    use data_code::websocket::AuthConfig;
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value as Json};
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use std::time::Duration;
+    use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+    type Client = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+    // Каждый тест поднимает собственный сервер на отдельном порту
+    static NEXT_PORT: AtomicU16 = AtomicU16::new(38650);
+
+    fn start_server() -> String {
+        spawn_server(AuthConfig::default(), None)
+    }
+
+    fn start_server_with_token(token: &str) -> String {
+        spawn_server(AuthConfig::with_token(token), None)
+    }
+
+    // timeout задается политикой потока сервера, как это сделал бы встраивающий код
+    fn spawn_server(auth: AuthConfig, timeout: Option<Duration>) -> String {
+        let port = NEXT_PORT.fetch_add(1, Ordering::SeqCst);
+        let address = format!("127.0.0.1:{}", port);
+        let server_address = address.clone();
+        std::thread::spawn(move || {
+            if timeout.is_some() {
+                use data_code::vm::policy::{set_execution_policy, ExecutionPolicy};
+                set_execution_policy(ExecutionPolicy { timeout, ..ExecutionPolicy::default() });
+            }
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let limits = data_code::parser::ParserLimits::server();
+            let _ = runtime.block_on(data_code::websocket::start_server_with_auth(&server_address, false, limits, auth));
+        });
+        format!("ws://{}", address)
+    }
+
+    async fn connect(url: &str) -> Client {
+        let mut attempts = 0;
+        loop {
+            match connect_async(url).await {
+                Ok((ws, _)) => return ws,
+                Err(_) if attempts < 50 => {
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => panic!("Не удалось подключиться к серверу: {}", e),
+            }
+        }
+    }
+
+    // Следующий текстовый ответ сервера; None, если сервер закрыл соединение
+    async fn next_response(ws: &mut Client) -> Option<Json> {
+        loop {
+            match ws.next().await {
+                Some(Ok(Message::Text(text))) => return Some(serde_json::from_str(&text).unwrap()),
+                Some(Ok(Message::Close(_))) | None => return None,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => panic!("Ошибка чтения ответа сервера: {}", e),
+            }
+        }
+    }
+
+    // Отправляет запросы по одному соединению и возвращает ответы в том же порядке
+    fn send_requests(requests: Vec<Json>) -> Vec<Json> {
+        send_requests_to(&start_server(), requests)
+    }
+
+    fn send_requests_to(url: &str, requests: Vec<Json>) -> Vec<Json> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let mut ws = connect(url).await;
+            let mut responses = Vec::new();
+            for request in requests {
+                ws.send(Message::Text(request.to_string())).await.unwrap();
+                match next_response(&mut ws).await {
+                    Some(response) => responses.push(response),
+                    None => panic!("Сервер закрыл соединение в ответ на {}", request),
+                }
+            }
+            let _ = ws.close(None).await;
+            responses
+        })
+    }
+
+    #[test]
+    fn test_inspect_empty_session() {
+        let responses = send_requests(vec![json!({"type": "inspect"})]);
+        assert_eq!(responses[0]["success"], json!(true));
+        assert_eq!(responses[0]["globals"], json!([]));
+        assert_eq!(responses[0]["functions"], json!([]));
+        assert_eq!(responses[0]["tables"], json!([]));
+    }
+
+    #[test]
+    fn test_inspect_lists_globals_functions_and_tables() {
+        let code = r#"
+            let count = 3
+            let label = "items"
+            fn scale(value, factor) {
+                return value * factor
+            }
+            let people = table([[1, "Alice", 30], [2, "Bob", null]], ["id", "name", "age"])
+        "#;
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": code}),
+            json!({"type": "inspect"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(true), "{:?}", responses[0]);
+
+        let inspect = &responses[1];
+        assert_eq!(inspect["success"], json!(true));
+
+        let globals = inspect["globals"].as_array().unwrap();
+        let names: Vec<&str> = globals.iter().map(|g| g["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["count", "label", "people"]);
+        assert_eq!(globals[0]["type"], json!("int"));
+        assert_eq!(globals[0]["preview"], json!("3"));
+        assert_eq!(globals[1]["type"], json!("string"));
+        assert_eq!(globals[2]["type"], json!("table"));
+
+        assert_eq!(inspect["functions"], json!([{"name": "scale", "params": ["value", "factor"]}]));
+        assert_eq!(inspect["tables"], json!([{
+            "name": "people",
+            "rows": 2,
+            "columns": 3,
+            "column_names": ["id", "name", "age"],
+        }]));
+    }
+
+    #[test]
+    fn test_preview_table_returns_typed_rows() {
+        let code = r#"
+            let people = table([[1, "Alice", 30.5], [2, "Bob", null], [3, "Eve", true]], ["id", "name", "score"])
+        "#;
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": code}),
+            json!({"type": "preview_table", "name": "people", "limit": 2, "offset": 1}),
+            json!({"type": "preview_table", "name": "people"}),
+        ]);
+
+        let page = &responses[1];
+        assert_eq!(page["success"], json!(true), "{:?}", page);
+        assert_eq!(page["total_rows"], json!(3));
+        assert_eq!(page["columns"], json!(["id", "name", "score"]));
+        assert_eq!(page["rows"], json!([
+            {"id": 2, "name": "Bob", "score": null},
+            {"id": 3, "name": "Eve", "score": true},
+        ]));
+
+        // limit и offset по умолчанию: первые 50 строк
+        let all = &responses[2];
+        assert_eq!(all["rows"].as_array().unwrap().len(), 3);
+        assert_eq!(all["rows"][0]["score"], json!(30.5));
+    }
+
+    #[test]
+    fn test_preview_table_text_format() {
+        let code = r#"
+            let people = table([[1, "Alice", 30.5], [2, "Bob", null], [3, "Eve", true]], ["id", "name", "score"])
+        "#;
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": code}),
+            json!({"type": "preview_table", "name": "people", "limit": 2, "offset": 1, "format": "markdown"}),
+            json!({"type": "preview_table", "name": "people", "format": "csv"}),
+            json!({"type": "preview_table", "name": "people", "format": "html"}),
+        ]);
+
+        // Текст той же страницы, что и rows
+        assert_eq!(
+            responses[1]["text"],
+            json!("|  id | name | score |\n| --: | ---- | ----- |\n|   2 | Bob  | null  |\n|   3 | Eve  | true  |\n")
+        );
+        assert_eq!(responses[1]["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(responses[2]["text"], json!("id,name,score\n1,Alice,30.5\n2,Bob,\n3,Eve,true\n"));
+        assert_eq!(responses[3]["success"], json!(false));
+        assert_eq!(responses[3]["error_type"], json!("ValueError"));
+    }
+
+    #[test]
+    fn test_preview_unknown_table_is_structured_error() {
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": "let x = 1"}),
+            json!({"type": "preview_table", "name": "missing"}),
+            json!({"type": "preview_table", "name": "x"}),
+            json!({"type": "execute", "code": "print(1)"}),
+        ]);
+
+        assert_eq!(responses[1]["success"], json!(false));
+        assert_eq!(responses[1]["error_type"], json!("TableNotFound"));
+        assert!(responses[1]["error"].as_str().unwrap().contains("missing"));
+        // Переменная, не являющаяся таблицей, тоже не найдена как таблица
+        assert_eq!(responses[2]["error_type"], json!("TableNotFound"));
+        // Соединение остается рабочим после ошибки
+        assert_eq!(responses[3]["success"], json!(true));
+        assert_eq!(responses[3]["output"], json!("1\n"));
+    }
+
+    #[test]
+    fn test_execute_thrown_object_is_json_error() {
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": "throw {\"kind\": \"ValidationError\", \"row\": 3, \"message\": \"bad value\"}"}),
+            json!({"type": "execute", "code": "throw \"plain\""}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(false));
+        assert_eq!(responses[0]["error"], json!({"kind": "ValidationError", "row": 3, "message": "bad value"}));
+        assert!(responses[1]["error"].as_str().unwrap().contains("plain"));
+    }
+
+    #[test]
+    fn test_connection_survives_table_mutation_scripts() {
+        let responses = send_requests(vec![
+            json!({"type": "execute", "code": "let t = table([[1, 2]], [\"a\", \"b\"])\nfor row in t {\n    table_drop(t, [\"b\"])\n    table_rename(t, [[\"a\", \"c\"]])\n}\nprint(len(merge_tables([t, t])))"}),
+            json!({"type": "execute", "code": "print(\"still alive\")"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(true), "{}", responses[0]);
+        assert_eq!(responses[0]["output"].as_str().unwrap().trim(), "2");
+        assert_eq!(responses[1]["success"], json!(true));
+        assert_eq!(responses[1]["output"].as_str().unwrap().trim(), "still alive");
+    }
+
+    #[test]
+    fn test_smb_write_file_reports_errors() {
+        let responses = send_requests(vec![
+            json!({"type": "smb_write_file", "share_name": "missing", "file_path": "out/report.csv", "content": "a,b\n1,2\n"}),
+            json!({"type": "smb_write_file", "share_name": "missing", "file_path": "image.png", "content": "base64:not base64!"}),
+            json!({"type": "execute", "code": "print(1)"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(false));
+        assert_eq!(responses[0]["bytes_written"], json!(0));
+        assert!(responses[0]["error"].as_str().unwrap().contains("missing"), "{}", responses[0]);
+        // Некорректный base64 отклоняется до обращения к шаре
+        assert_eq!(responses[1]["success"], json!(false));
+        assert!(responses[1]["error"].as_str().unwrap().contains("base64"), "{}", responses[1]);
+        assert_eq!(responses[2]["output"], json!("1\n"));
+    }
+
+    #[test]
+    fn test_smb_list_connections_starts_empty() {
+        let responses = send_requests(vec![
+            json!({"type": "smb_list_connections"}),
+            json!({"type": "smb_read_file", "share_name": "missing", "file_path": "a.txt"}),
+        ]);
+        assert_eq!(responses[0], json!({"success": true, "connections": []}));
+        // Неподключенная шара - не обрыв соединения: ошибка без попыток переподключения
+        assert_eq!(responses[1]["success"], json!(false));
+        assert!(!responses[1]["error"].as_str().unwrap().contains("переподключение"), "{}", responses[1]);
+    }
+
+    #[test]
+    fn test_connection_errors_are_recognized() {
+        use data_code::websocket::smb::is_connection_error;
+        assert!(is_connection_error("Ошибка чтения файла: session setup failed: NT_STATUS_IO_TIMEOUT"));
+        assert!(is_connection_error("Ошибка чтения файла: NT_STATUS_CONNECTION_RESET reading"));
+        assert!(is_connection_error("Ошибка чтения файла: The specified network name is no longer available. (os error 64)"));
+        assert!(!is_connection_error("Ошибка чтения файла: NT_STATUS_OBJECT_NAME_NOT_FOUND opening remote file"));
+        assert!(!is_connection_error("session setup failed: NT_STATUS_LOGON_FAILURE"));
+        assert!(!is_connection_error("SMB share 'data' не подключена"));
+    }
+
+    #[test]
+    fn test_requests_rejected_until_authenticated() {
+        let url = start_server_with_token("s3cret");
+        let responses = send_requests_to(&url, vec![
+            json!({"type": "execute", "code": "print(1)"}),
+            json!({"type": "auth", "token": "wrong"}),
+            json!({"type": "auth", "token": "s3cret"}),
+            json!({"type": "execute", "code": "print(2)"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(false));
+        assert!(responses[0]["error"].as_str().unwrap().contains("Требуется аутентификация"), "{}", responses[0]);
+        assert!(responses[0]["error"].as_str().unwrap().contains("Осталось попыток: 2"), "{}", responses[0]);
+        assert!(responses[1]["error"].as_str().unwrap().contains("Неверный токен"), "{}", responses[1]);
+        assert_eq!(responses[0]["error_type"], json!("AuthenticationError"));
+        assert_eq!(responses[2], json!({"success": true, "error": null, "error_type": null}));
+        assert_eq!(responses[3]["success"], json!(true));
+        assert_eq!(responses[3]["output"], json!("2\n"));
+    }
+
+    #[test]
+    fn test_connection_closed_after_failed_auth_attempts() {
+        let url = start_server_with_token("s3cret");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let mut ws = connect(&url).await;
+            let mut last = None;
+            for _ in 0..data_code::websocket::MAX_AUTH_ATTEMPTS {
+                ws.send(Message::Text(json!({"type": "auth", "token": "guess"}).to_string())).await.unwrap();
+                last = next_response(&mut ws).await;
+            }
+            let last = last.expect("ответ на последнюю попытку");
+            assert_eq!(last["success"], json!(false));
+            assert!(last["error"].as_str().unwrap().contains("соединение закрыто"), "{}", last);
+            // Сервер закрывает соединение, код больше не принимается
+            let _ = ws.send(Message::Text(json!({"type": "execute", "code": "print(1)"}).to_string())).await;
+            assert!(!matches!(ws.next().await, Some(Ok(Message::Text(_)))));
+        });
+    }
+
+    #[test]
+    fn test_auth_without_configured_token() {
+        let responses = send_requests(vec![
+            json!({"type": "auth", "token": "anything"}),
+            json!({"type": "execute", "code": "print(1)"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(true));
+        assert_eq!(responses[1]["output"], json!("1\n"));
+    }
+
+    #[test]
+    fn test_runaway_execution_times_out() {
+        let url = spawn_server(AuthConfig::default(), Some(Duration::from_millis(300)));
+        let responses = send_requests_to(&url, vec![
+            json!({"type": "execute", "code": "print('start')\nlet i = 0\nwhile true {\n    i = i + 1\n}"}),
+            json!({"type": "execute", "code": "print(1)"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(false));
+        assert_eq!(responses[0]["error"], json!("execution timed out"));
+        assert_eq!(responses[0]["output"], json!("start\n"));
+        // Следующий запрос получает новый отсчет времени
+        assert_eq!(responses[1]["success"], json!(true));
+        assert_eq!(responses[1]["output"], json!("1\n"));
+    }
+
+    #[test]
+    fn test_query_token_authenticates_connection() {
+        let url = start_server_with_token("s3 cret");
+        let responses = send_requests_to(&format!("{}/?token=s3%20cret", url), vec![
+            json!({"type": "execute", "code": "print(1)"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(true), "{}", responses[0]);
+        assert_eq!(responses[0]["output"], json!("1\n"));
+    }
+
+    #[test]
+    fn test_wrong_query_token_rejects_handshake() {
+        use tokio_tungstenite::tungstenite::Error;
+
+        let url = start_server_with_token("s3cret");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            // Подключение без токена дожидается запуска сервера
+            let mut ws = connect(&url).await;
+            let _ = ws.close(None).await;
+            match connect_async(format!("{}/?token=guess", url)).await {
+                Err(Error::Http(response)) => assert_eq!(response.status().as_u16(), 401),
+                Err(e) => panic!("Ожидался ответ 401, получено {}", e),
+                Ok(_) => panic!("Подключение с неверным токеном установлено"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_connection_closed_when_auth_times_out() {
+        let mut auth = AuthConfig::with_token("s3cret");
+        auth.timeout = Duration::from_millis(200);
+        let url = spawn_server(auth, None);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let mut ws = connect(&url).await;
+            let response = next_response(&mut ws).await.expect("ответ об истечении времени");
+            assert_eq!(response["success"], json!(false));
+            assert_eq!(response["error_type"], json!("AuthenticationError"));
+            assert!(response["error"].as_str().unwrap().contains("Время ожидания аутентификации истекло"), "{}", response);
+            assert!(next_response(&mut ws).await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_auth_file_profile_is_enforced() {
+        let dir = std::env::temp_dir().join(format!("datacode_ws_auth_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let auth_file = dir.join("tokens.json");
+        std::fs::write(&auth_file, json!({
+            "tokens": {
+                "reader": {"allow_file_write": false, "allow_smb": false},
+                "admin": {}
+            }
+        }).to_string()).unwrap();
+        let target = dir.join("out.txt").to_string_lossy().replace('\\', "/");
+        let url = spawn_server(AuthConfig::from_file(&auth_file).unwrap(), None);
+
+        let write_code = format!(r#"
+            try {{
+                write_file("{0}", "hello")
+                print("written")
+            }} catch PermissionError e {{
+                print("denied: " + e)
+            }}
+        "#, target);
+        let responses = send_requests_to(&url, vec![
+            json!({"type": "auth", "token": "reader"}),
+            json!({"type": "execute", "code": write_code}),
+            json!({"type": "smb_list_connections"}),
+            json!({"type": "execute", "code": "print(len(range(3)))"}),
+        ]);
+        assert_eq!(responses[0]["success"], json!(true));
+        let output = responses[1]["output"].as_str().unwrap();
+        assert!(output.starts_with("denied: "), "{}", output);
+        assert!(output.contains("write_file(): writing files is disabled by the execution policy"), "{}", output);
+        assert!(!std::path::Path::new(&target).exists());
+        assert_eq!(responses[2]["success"], json!(false));
+        assert_eq!(responses[2]["error_type"], json!("PermissionError"));
+        assert_eq!(responses[3]["output"], json!("3\n"));
+
+        // Другой токен того же файла дает полные права
+        let responses = send_requests_to(&url, vec![
+            json!({"type": "auth", "token": "admin"}),
+            json!({"type": "execute", "code": write_code}),
+            json!({"type": "smb_list_connections"}),
+        ]);
+        assert_eq!(responses[1]["output"], json!("written\n"), "{}", responses[1]);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "hello");
+        assert_eq!(responses[2]["success"], json!(true));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_auth_file_is_reported() {
+        let dir = std::env::temp_dir().join(format!("datacode_ws_auth_invalid_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let auth_file = dir.join("tokens.json");
+        std::fs::write(&auth_file, r#"{"tokens": {"t": {"allow_fs": false}}}"#).unwrap();
+        let error = AuthConfig::from_file(&auth_file).unwrap_err();
+        assert!(error.contains("allow_fs"), "{}", error);
+        std::fs::write(&auth_file, r#"{"tokens": {"t": {"max_session_disk_mb": -1}}}"#).unwrap();
+        assert!(AuthConfig::from_file(&auth_file).unwrap_err().contains("max_session_disk_mb"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
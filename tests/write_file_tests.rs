@@ -0,0 +1,95 @@
+// Тесты для write_file и append_file
+#[cfg(test)]
+mod tests {
+    use data_code::websocket::{set_use_ve, set_user_session_path};
+    use data_code::{run, Value};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("datacode_write_file_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Путь с '/' для подстановки в строку DataCode
+    fn source_path(path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    #[test]
+    fn test_write_file_truncates_and_append_file_appends() {
+        let dir = temp_dir("write");
+        let file = dir.join("out.txt");
+        let source = format!(r#"
+            write_file("{0}", "old content that is longer")
+            write_file("{0}", "first\n")
+            append_file("{0}", "second\n")
+            append_file(path("{0}"), 42)
+        "#, source_path(&file));
+        run(&source).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first\nsecond\n42");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parent_directories_are_created() {
+        let dir = temp_dir("parents");
+        let file = dir.join("a").join("b").join("log.txt");
+        run(&format!(r#"append_file("{}", "line")"#, source_path(&file))).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "line");
+        // Записанный файл читается обратно через read_lines
+        let lines = run(&format!(r#"str(read_lines("{}"))"#, source_path(&file)));
+        assert!(matches!(lines, Ok(Value::String(ref s)) if &**s == "[line]"), "{:?}", lines);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_file_writes_table_as_csv() {
+        let dir = temp_dir("table");
+        let file = dir.join("table.csv");
+        let source = format!(r#"
+            let t = table([[1, "a, b", null], [2, 'say "hi"', 3.5]], ["id", "text", "x"])
+            write_file("{}", t)
+        "#, source_path(&file));
+        run(&source).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "id,text,x\n1,\"a, b\",\n2,\"say \"\"hi\"\"\",3.5\n"
+        );
+
+        // Таблицу нельзя дописать в конец файла
+        let other = dir.join("other.csv");
+        let source = format!(r#"
+            let kind = ""
+            try {{
+                append_file("{}", table([[1, 2]], ["a", "b"]))
+            }} catch TypeError e {{
+                kind = e
+            }}
+            kind
+        "#, source_path(&other));
+        let result = run(&source);
+        assert!(matches!(result, Ok(Value::String(ref s)) if s.contains("cannot append a table")), "{:?}", result);
+        assert!(!other.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_use_ve_writes_inside_session_only() {
+        let dir = temp_dir("session");
+        set_use_ve(true);
+        set_user_session_path(Some(dir.clone()));
+        let inside = run(r#"write_file("reports/summary.txt", "ok")"#);
+        let outside = run(r#"write_file("../escaped.txt", "no")"#);
+        set_use_ve(false);
+        set_user_session_path(None);
+
+        assert!(inside.is_ok(), "{:?}", inside);
+        assert_eq!(fs::read_to_string(dir.join("reports").join("summary.txt")).unwrap(), "ok");
+        let message = format!("{}", outside.unwrap_err());
+        assert!(message.contains("outside session directory"), "{}", message);
+        assert!(!dir.parent().unwrap().join("escaped.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}